@@ -0,0 +1,1443 @@
+//! Capture anonymisation for sharing pcaps outside the organisation.
+//!
+//! Addresses are pseudonymised with a keyed, prefix-preserving mapping: two
+//! addresses sharing an `n`-bit prefix still share an `n`-bit prefix after
+//! rewriting, so subnet structure survives while the real networks don't.
+//!
+//! Packets inside GRE, VXLAN, GENEVE and IP-in-IP tunnels are rewritten like
+//! outer ones. By default only payloads the anonymiser understands (DNS and
+//! DHCP) are kept; everything else, including frames that aren't IP, is cut.
+
+use crate::capture::link_type;
+use crate::decode::{LinkType, MAX_TUNNEL_DEPTH};
+use crate::error::{CaptureError, CaptureResult};
+use crate::tunnel;
+use etherparse::SlicedPacket;
+use pcap::{Capture, Packet, PacketHeader};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::path::Path;
+use tracing::{debug, info};
+
+/// How MAC addresses are rewritten.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MacPolicy {
+    /// Leave MAC addresses untouched.
+    Keep,
+    /// Replace the whole address with a locally administered pseudonym.
+    #[default]
+    Scramble,
+    /// Keep the vendor OUI and scramble the device part.
+    KeepOui,
+}
+
+/// How transport payloads are rewritten.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PayloadPolicy {
+    /// Leave payloads untouched.
+    Keep,
+    /// Keep payloads whose addresses and names were rewritten (DNS, DHCP)
+    /// and cut all others after `payload_bytes` bytes.
+    #[default]
+    Rewritten,
+    /// Overwrite payload bytes with zeros, keeping packet lengths.
+    Zero,
+    /// Cut packets after the first `payload_bytes` bytes of payload.
+    Truncate,
+}
+
+/// Anonymisation policy, usually loaded from a TOML file.
+///
+/// ```toml
+/// key = "shared-secret"
+/// mac = "keep_oui"
+/// payload = "truncate"
+/// payload_bytes = 0
+/// remove_dns_names = true
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnonymizationPolicy {
+    /// Secret for the address mapping; a random key is used when unset.
+    ///
+    /// Reusing a key keeps pseudonyms stable across captures.
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// Pseudonymise IPv4/IPv6 addresses.
+    #[serde(default = "default_true")]
+    pub addresses: bool,
+
+    /// MAC address handling.
+    #[serde(default)]
+    pub mac: MacPolicy,
+
+    /// Payload handling.
+    #[serde(default)]
+    pub payload: PayloadPolicy,
+
+    /// Payload bytes kept per packet when truncating.
+    #[serde(default)]
+    pub payload_bytes: usize,
+
+    /// Blank out names in DNS questions and answers, and DHCP host names.
+    #[serde(default = "default_true")]
+    pub remove_dns_names: bool,
+
+    /// Recompute IP, TCP, UDP and ICMP checksums after rewriting.
+    #[serde(default = "default_true")]
+    pub fix_checksums: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+impl Default for AnonymizationPolicy {
+    fn default() -> Self {
+        Self {
+            key: None,
+            addresses: true,
+            mac: MacPolicy::default(),
+            payload: PayloadPolicy::default(),
+            payload_bytes: 0,
+            remove_dns_names: true,
+            fix_checksums: true,
+        }
+    }
+}
+
+impl AnonymizationPolicy {
+    /// Load a policy from a TOML file.
+    pub fn load(path: &Path) -> CaptureResult<Self> {
+        let content = std::fs::read_to_string(path)?;
+        toml::from_str(&content).map_err(|e| CaptureError::InvalidPolicy(e.to_string()))
+    }
+
+    /// Set the mapping key.
+    pub fn key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    /// Set MAC address handling.
+    pub fn mac(mut self, policy: MacPolicy) -> Self {
+        self.mac = policy;
+        self
+    }
+
+    /// Overwrite payloads with zeros.
+    pub fn zero_payloads(mut self) -> Self {
+        self.payload = PayloadPolicy::Zero;
+        self
+    }
+
+    /// Leave payloads untouched.
+    pub fn keep_payloads(mut self) -> Self {
+        self.payload = PayloadPolicy::Keep;
+        self
+    }
+
+    /// Keep at most `bytes` of payload per packet.
+    pub fn truncate_payloads(mut self, bytes: usize) -> Self {
+        self.payload = PayloadPolicy::Truncate;
+        self.payload_bytes = bytes;
+        self
+    }
+
+    /// Blank out DNS names.
+    pub fn remove_dns_names(mut self, enabled: bool) -> Self {
+        self.remove_dns_names = enabled;
+        self
+    }
+}
+
+/// Where the transport layer of a packet sits.
+#[derive(Debug, Clone, Copy)]
+struct IpLayout {
+    /// Offset of the IP header.
+    ip: usize,
+    /// Offset of the transport header.
+    l4: usize,
+    /// End of the IP packet within the buffer.
+    end: usize,
+    /// Transport protocol.
+    protocol: u8,
+    /// Non-first fragment (no transport header).
+    fragment: bool,
+    /// First fragment of a fragmented datagram.
+    first_fragment: bool,
+    /// IPv6 rather than IPv4.
+    v6: bool,
+}
+
+/// Rewrites packets according to an [`AnonymizationPolicy`].
+pub struct Anonymizer {
+    policy: AnonymizationPolicy,
+    key: Vec<u8>,
+    addresses: HashMap<IpAddr, IpAddr>,
+    packets: u64,
+    unparsed: u64,
+}
+
+impl Anonymizer {
+    /// Create an anonymizer.
+    pub fn new(policy: AnonymizationPolicy) -> Self {
+        let key = match &policy.key {
+            Some(key) => key.as_bytes().to_vec(),
+            None => rand::random::<[u8; 32]>().to_vec(),
+        };
+        Self {
+            policy,
+            key,
+            addresses: HashMap::new(),
+            packets: 0,
+            unparsed: 0,
+        }
+    }
+
+    /// Number of packets rewritten.
+    pub fn packets(&self) -> u64 {
+        self.packets
+    }
+
+    /// Packets (or tunnelled inner frames) whose network layer couldn't be
+    /// parsed; everything after their link header is treated as payload.
+    pub fn unparsed_packets(&self) -> u64 {
+        self.unparsed
+    }
+
+    /// Pseudonymise an address, preserving shared prefixes.
+    ///
+    /// Unspecified, loopback, multicast and broadcast addresses are kept.
+    pub fn anonymize_ip(&mut self, ip: IpAddr) -> IpAddr {
+        if !self.policy.addresses || is_special(ip) {
+            return ip;
+        }
+        if let Some(mapped) = self.addresses.get(&ip) {
+            return *mapped;
+        }
+
+        let mapped = match ip {
+            IpAddr::V4(v4) => IpAddr::V4(Ipv4Addr::from(self.prefix_preserve(&v4.octets()))),
+            IpAddr::V6(v6) => {
+                let bytes: [u8; 16] = self.prefix_preserve(&v6.octets());
+                IpAddr::V6(Ipv6Addr::from(bytes))
+            }
+        };
+        self.addresses.insert(ip, mapped);
+        mapped
+    }
+
+    /// Flip each address bit according to a keyed hash of the bits before it.
+    fn prefix_preserve<const N: usize>(&self, input: &[u8; N]) -> [u8; N] {
+        let mut output = *input;
+        let mut prefix = [0u8; N];
+        for bit in 0..N * 8 {
+            let hash = Sha256::new()
+                .chain_update(&self.key)
+                .chain_update([N as u8, bit as u8])
+                .chain_update(prefix)
+                .finalize();
+            let mask = 0x80 >> (bit % 8);
+            if hash[0] & 1 == 1 {
+                output[bit / 8] ^= mask;
+            }
+            prefix[bit / 8] |= input[bit / 8] & mask;
+        }
+        output
+    }
+
+    /// Pseudonymise a MAC address in place.
+    fn anonymize_mac(&self, mac: &mut [u8]) {
+        // Broadcast and multicast MACs carry no device identity
+        if self.policy.mac == MacPolicy::Keep || mac.len() != 6 || mac[0] & 0x01 != 0 {
+            return;
+        }
+        let hash = Sha256::new()
+            .chain_update(&self.key)
+            .chain_update(b"mac")
+            .chain_update(&*mac)
+            .finalize();
+        match self.policy.mac {
+            MacPolicy::KeepOui => mac[3..].copy_from_slice(&hash[3..6]),
+            _ => {
+                mac.copy_from_slice(&hash[..6]);
+                // Locally administered unicast
+                mac[0] = (mac[0] & 0xFC) | 0x02;
+            }
+        }
+    }
+
+    /// Rewrite an address stored in a packet buffer.
+    fn rewrite_ip(&mut self, data: &mut [u8], offset: usize, v6: bool) {
+        let len = if v6 { 16 } else { 4 };
+        let Some(bytes) = data.get_mut(offset..offset + len) else {
+            return;
+        };
+        let ip = if v6 {
+            IpAddr::V6(Ipv6Addr::from(
+                <[u8; 16]>::try_from(&*bytes).unwrap_or_default(),
+            ))
+        } else {
+            IpAddr::V4(Ipv4Addr::from(
+                <[u8; 4]>::try_from(&*bytes).unwrap_or_default(),
+            ))
+        };
+        match self.anonymize_ip(ip) {
+            IpAddr::V4(v4) => bytes.copy_from_slice(&v4.octets()),
+            IpAddr::V6(v6) => bytes.copy_from_slice(&v6.octets()),
+        }
+    }
+
+    /// Anonymise a raw packet with the given link-layer framing.
+    pub fn anonymize(&mut self, link_type: LinkType, data: &[u8]) -> Vec<u8> {
+        let mut out = data.to_vec();
+        self.packets += 1;
+
+        let payload_start = self.rewrite_frame(&mut out, link_type, 0);
+        if let (Some(start), PayloadPolicy::Truncate | PayloadPolicy::Rewritten) =
+            (payload_start, self.policy.payload)
+        {
+            out.truncate(start.saturating_add(self.policy.payload_bytes));
+        }
+        out
+    }
+
+    /// Rewrite a frame found `depth` tunnels deep; returns where the payload
+    /// left to the payload policy starts.
+    fn rewrite_frame(
+        &mut self,
+        data: &mut [u8],
+        link_type: LinkType,
+        depth: usize,
+    ) -> Option<usize> {
+        let network = match link_type {
+            LinkType::Ethernet => self.rewrite_ethernet(data),
+            LinkType::LinuxSll => self.rewrite_sll(data),
+            LinkType::RawIp => match data.first().map(|b| b >> 4) {
+                Some(4) => Some((0, 0x0800)),
+                Some(6) => Some((0, 0x86DD)),
+                _ => None,
+            },
+        };
+
+        let payload_start = match network {
+            Some((offset, 0x0800)) => self.rewrite_ipv4(data, offset, depth),
+            Some((offset, 0x86DD)) => self.rewrite_ipv6(data, offset, depth),
+            Some((offset, 0x0806)) => {
+                self.rewrite_arp(data, offset);
+                None
+            }
+            _ => None,
+        };
+
+        match (payload_start, network) {
+            (Some(start), _) => Some(start),
+            // ARP has no payload to scrub
+            (None, Some((_, 0x0806))) => None,
+            // Not IP (LLDP, EAPOL, ...): nothing after the link header is understood
+            (None, network) => {
+                self.unparsed += 1;
+                let start = network.map_or(0, |(offset, _)| offset);
+                self.scrub(data, start, data.len());
+                Some(start)
+            }
+        }
+    }
+
+    /// Anonymise a captured packet, adjusting the captured length.
+    pub fn anonymize_packet(
+        &mut self,
+        link_type: LinkType,
+        header: &PacketHeader,
+        data: &[u8],
+    ) -> (PacketHeader, Vec<u8>) {
+        let data = self.anonymize(link_type, data);
+        let header = PacketHeader {
+            caplen: data.len() as u32,
+            ..*header
+        };
+        (header, data)
+    }
+
+    /// Anonymise a PCAP/PCAPNG file into a new pcap file.
+    ///
+    /// Returns the number of packets written.
+    pub fn anonymize_file(&mut self, input: &Path, output: &Path) -> CaptureResult<u64> {
+        let mut cap =
+            Capture::from_file(input).map_err(|e| CaptureError::InvalidFile(e.to_string()))?;
+        let linktype = cap.get_datalink();
+        let link = link_type(linktype)?;
+
+        let mut savefile = Capture::dead(linktype)
+            .and_then(|dead| dead.savefile(output))
+            .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+        let mut written = 0;
+        loop {
+            match cap.next_packet() {
+                Ok(packet) => {
+                    let (header, data) = self.anonymize_packet(link, packet.header, packet.data);
+                    savefile.write(&Packet::new(&header, &data));
+                    written += 1;
+                }
+                Err(pcap::Error::NoMorePackets) => break,
+                Err(e) => return Err(CaptureError::InvalidFile(e.to_string())),
+            }
+        }
+        savefile
+            .flush()
+            .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+
+        info!(
+            "Anonymised {} packets from {} to {}",
+            written,
+            input.display(),
+            output.display()
+        );
+        Ok(written)
+    }
+
+    /// Rewrite Ethernet (and VLAN tag) headers; returns the network offset and EtherType.
+    fn rewrite_ethernet(&self, data: &mut [u8]) -> Option<(usize, u16)> {
+        if data.len() < 14 {
+            return None;
+        }
+        self.anonymize_mac(&mut data[0..6]);
+        self.anonymize_mac(&mut data[6..12]);
+
+        let mut offset = 12;
+        loop {
+            let ether_type = read_u16(data, offset)?;
+            if matches!(ether_type, 0x8100 | 0x88A8) {
+                offset += 4;
+            } else {
+                return Some((offset + 2, ether_type));
+            }
+        }
+    }
+
+    /// Rewrite a Linux cooked header; returns the network offset and protocol.
+    fn rewrite_sll(&self, data: &mut [u8]) -> Option<(usize, u16)> {
+        if data.len() < 16 {
+            return None;
+        }
+        if read_u16(data, 4) == Some(6) {
+            self.anonymize_mac(&mut data[6..12]);
+        }
+        Some((16, read_u16(data, 14)?))
+    }
+
+    /// Rewrite ARP sender/target addresses.
+    fn rewrite_arp(&mut self, data: &mut [u8], offset: usize) {
+        // Ethernet/IPv4 ARP only
+        if read_u16(data, offset) != Some(1) || read_u16(data, offset + 2) != Some(0x0800) {
+            return;
+        }
+        if data.len() < offset + 28 {
+            return;
+        }
+        self.anonymize_mac(&mut data[offset + 8..offset + 14]);
+        self.rewrite_ip(data, offset + 14, false);
+        self.anonymize_mac(&mut data[offset + 18..offset + 24]);
+        self.rewrite_ip(data, offset + 24, false);
+    }
+
+    /// Rewrite an IPv4 packet; returns where its payload starts.
+    fn rewrite_ipv4(&mut self, data: &mut [u8], offset: usize, depth: usize) -> Option<usize> {
+        let layout = ipv4_layout(data, offset)?;
+        let original = self.fragment_original(data, layout);
+        self.rewrite_ip(data, offset + 12, false);
+        self.rewrite_ip(data, offset + 16, false);
+        let payload = self.rewrite_transport(data, layout, depth, original.as_deref());
+        if self.policy.fix_checksums {
+            fix_ipv4_header_checksum(data, offset);
+        }
+        Some(payload)
+    }
+
+    /// Rewrite an IPv6 packet; returns where its payload starts.
+    fn rewrite_ipv6(&mut self, data: &mut [u8], offset: usize, depth: usize) -> Option<usize> {
+        let layout = ipv6_layout(data, offset)?;
+        let original = self.fragment_original(data, layout);
+        self.rewrite_ip(data, offset + 8, true);
+        self.rewrite_ip(data, offset + 24, true);
+        Some(self.rewrite_transport(data, layout, depth, original.as_deref()))
+    }
+
+    /// Copy of a first fragment before rewriting, to adjust its checksum.
+    fn fragment_original(&self, data: &[u8], layout: IpLayout) -> Option<Vec<u8>> {
+        (layout.first_fragment && self.policy.fix_checksums)
+            .then(|| data[layout.ip..layout.end].to_vec())
+    }
+
+    /// Rewrite the transport layer and payload; returns where the payload starts.
+    ///
+    /// `original` is a first fragment as captured, from its IP header.
+    fn rewrite_transport(
+        &mut self,
+        data: &mut [u8],
+        layout: IpLayout,
+        depth: usize,
+        original: Option<&[u8]>,
+    ) -> usize {
+        if layout.fragment {
+            self.scrub(data, layout.l4, layout.end);
+            return layout.l4;
+        }
+
+        if depth < MAX_TUNNEL_DEPTH {
+            if let Some(payload) = self.rewrite_tunnel(data, layout, depth) {
+                if self.policy.fix_checksums {
+                    repair_transport_checksum(data, layout, original);
+                }
+                return payload;
+            }
+        }
+
+        let (payload, rewritten) = match layout.protocol {
+            6 => {
+                let header_len = data
+                    .get(layout.l4 + 12)
+                    .map_or(20, |b| usize::from(b >> 4) * 4);
+                let payload = (layout.l4 + header_len).min(layout.end);
+                let dns = is_dns_port(data, layout.l4) && payload + 2 <= layout.end;
+                if dns {
+                    // DNS over TCP has a two-byte length prefix
+                    self.rewrite_dns(&mut data[payload + 2..layout.end]);
+                }
+                (payload, dns)
+            }
+            17 => {
+                let payload = (layout.l4 + 8).min(layout.end);
+                if is_dns_port(data, layout.l4) {
+                    self.rewrite_dns(&mut data[payload..layout.end]);
+                    (payload, true)
+                } else if is_dhcp_port(data, layout.l4) {
+                    self.rewrite_dhcp(&mut data[payload..layout.end]);
+                    (payload, true)
+                } else {
+                    (payload, false)
+                }
+            }
+            1 | 58 => (self.rewrite_icmp(data, layout), false),
+            _ => (layout.l4, false),
+        };
+
+        let payload = if rewritten && self.policy.payload == PayloadPolicy::Rewritten {
+            layout.end
+        } else {
+            self.scrub(data, payload, layout.end);
+            payload
+        };
+        if self.policy.fix_checksums {
+            repair_transport_checksum(data, layout, original);
+        }
+        payload
+    }
+
+    /// Rewrite the packet inside a GRE, VXLAN, GENEVE or IP-in-IP tunnel;
+    /// returns where the inner payload starts.
+    ///
+    /// Encrypted (WireGuard) and unrecognised tunnels return `None` and are
+    /// treated as opaque payload.
+    fn rewrite_tunnel(&mut self, data: &mut [u8], layout: IpLayout, depth: usize) -> Option<usize> {
+        if !matches!(layout.protocol, 4 | 17 | 41 | 47) {
+            return None;
+        }
+        let (inner, link_type) = {
+            let sliced = SlicedPacket::from_ip(&data[layout.ip..layout.end]).ok()?;
+            let (inner, link_type) = tunnel::decapsulate(&sliced)?.inner?;
+            // The inner packet runs to the end of the outer one
+            (layout.end - inner.len(), link_type)
+        };
+        let start = self.rewrite_frame(&mut data[inner..layout.end], link_type, depth + 1);
+        Some(start.map_or(layout.end, |start| inner + start))
+    }
+
+    /// Rewrite ICMP/ICMPv6 contents; returns where the payload starts.
+    fn rewrite_icmp(&mut self, data: &mut [u8], layout: IpLayout) -> usize {
+        let body = (layout.l4 + 8).min(layout.end);
+        let icmp_type = data.get(layout.l4).copied().unwrap_or_default();
+
+        match (layout.v6, icmp_type) {
+            // Errors quote the offending packet's header
+            (false, 3 | 4 | 5 | 11 | 12) => {
+                if let Some(inner) = ipv4_layout(data, body) {
+                    self.rewrite_ip(data, body + 12, false);
+                    self.rewrite_ip(data, body + 16, false);
+                    if self.policy.fix_checksums {
+                        fix_ipv4_header_checksum(data, body);
+                    }
+                    return (inner.l4 + 8).min(layout.end);
+                }
+                body
+            }
+            (true, 1..=4) => {
+                if ipv6_layout(data, body).is_some() {
+                    self.rewrite_ip(data, body + 8, true);
+                    self.rewrite_ip(data, body + 24, true);
+                    return (body + 48).min(layout.end);
+                }
+                body
+            }
+            // Neighbor discovery: target address and link-layer options
+            (true, 135 | 136) => {
+                self.rewrite_ip(data, body, true);
+                let mut option = body + 16;
+                while option + 8 <= layout.end {
+                    let len = usize::from(data[option + 1]) * 8;
+                    if len == 0 {
+                        break;
+                    }
+                    if matches!(data[option], 1 | 2) {
+                        self.anonymize_mac(&mut data[option + 2..option + 8]);
+                    }
+                    option += len;
+                }
+                layout.end
+            }
+            _ => body,
+        }
+    }
+
+    /// Rewrite DHCP address fields and address options.
+    fn rewrite_dhcp(&mut self, message: &mut [u8]) {
+        if message.len() < 240 {
+            return;
+        }
+        for offset in [12, 16, 20, 24] {
+            self.rewrite_ip(message, offset, false);
+        }
+        if message[2] == 6 {
+            self.anonymize_mac(&mut message[28..34]);
+        }
+
+        let mut offset = 240;
+        while offset + 2 <= message.len() {
+            let code = message[offset];
+            if code == 0 {
+                offset += 1;
+                continue;
+            }
+            if code == 255 {
+                break;
+            }
+            let len = usize::from(message[offset + 1]);
+            let value = offset + 2;
+            if value + len > message.len() {
+                break;
+            }
+            match code {
+                // Router, DNS servers, requested address, server identifier
+                3 | 6 | 50 | 54 => {
+                    for ip in (value..value + len - len % 4).step_by(4) {
+                        self.rewrite_ip(message, ip, false);
+                    }
+                }
+                // Host name, domain name, FQDN
+                12 | 15 | 81 if self.policy.remove_dns_names => {
+                    message[value..value + len].fill(b'x');
+                }
+                // Client identifier (hardware type + MAC)
+                61 if len == 7 && message[value] == 1 => {
+                    self.anonymize_mac(&mut message[value + 1..value + 7]);
+                }
+                _ => {}
+            }
+            offset = value + len;
+        }
+    }
+
+    /// Rewrite names and addresses in a DNS message.
+    fn rewrite_dns(&mut self, message: &mut [u8]) {
+        let (Some(questions), Some(answers), Some(authority), Some(additional)) = (
+            read_u16(message, 4),
+            read_u16(message, 6),
+            read_u16(message, 8),
+            read_u16(message, 10),
+        ) else {
+            return;
+        };
+        let remove_names = self.policy.remove_dns_names;
+
+        let mut offset = 12;
+        for _ in 0..questions {
+            let Some(end) = walk_name(message, offset, remove_names) else {
+                return;
+            };
+            offset = end + 4;
+        }
+
+        for _ in 0..u32::from(answers) + u32::from(authority) + u32::from(additional) {
+            let Some(end) = walk_name(message, offset, remove_names) else {
+                return;
+            };
+            let (Some(rtype), Some(rdlen)) = (read_u16(message, end), read_u16(message, end + 8))
+            else {
+                return;
+            };
+            let rdata = end + 10;
+            let rdlen = usize::from(rdlen);
+            if rdata + rdlen > message.len() {
+                return;
+            }
+            match rtype {
+                1 if rdlen == 4 => self.rewrite_ip(message, rdata, false),
+                28 if rdlen == 16 => self.rewrite_ip(message, rdata, true),
+                // SVCB, HTTPS
+                64 | 65 => self.rewrite_svcb(message, rdata, rdata + rdlen),
+                _ if !remove_names => {}
+                // NS, CNAME, PTR, DNAME
+                2 | 5 | 12 | 39 => {
+                    walk_name(message, rdata, true);
+                }
+                // MX
+                15 => {
+                    walk_name(message, rdata + 2, true);
+                }
+                // SOA: primary server and responsible mailbox
+                6 => {
+                    if let Some(end) = walk_name(message, rdata, true) {
+                        walk_name(message, end, true);
+                    }
+                }
+                // SRV: target after priority, weight and port
+                33 => {
+                    walk_name(message, rdata + 6, true);
+                }
+                // TXT and anything else can carry names in any form
+                _ => message[rdata..rdata + rdlen].fill(0),
+            }
+            offset = rdata + rdlen;
+        }
+    }
+
+    /// Rewrite SVCB/HTTPS record data: the target name and the
+    /// `ipv4hint`/`ipv6hint` addresses.
+    fn rewrite_svcb(&mut self, message: &mut [u8], rdata: usize, end: usize) {
+        let Some(mut offset) = walk_name(message, rdata + 2, self.policy.remove_dns_names) else {
+            return;
+        };
+        while offset + 4 <= end {
+            let (Some(key), Some(len)) = (read_u16(message, offset), read_u16(message, offset + 2))
+            else {
+                return;
+            };
+            let value = offset + 4;
+            let len = usize::from(len);
+            if value + len > end {
+                return;
+            }
+            match key {
+                4 => {
+                    for ip in (value..value + len - len % 4).step_by(4) {
+                        self.rewrite_ip(message, ip, false);
+                    }
+                }
+                6 => {
+                    for ip in (value..value + len - len % 16).step_by(16) {
+                        self.rewrite_ip(message, ip, true);
+                    }
+                }
+                _ => {}
+            }
+            offset = value + len;
+        }
+    }
+
+    /// Apply the payload policy to `data[start..end]`.
+    fn scrub(&self, data: &mut [u8], start: usize, end: usize) {
+        let end = end.min(data.len());
+        if self.policy.payload == PayloadPolicy::Zero && start < end {
+            data[start..end].fill(0);
+        }
+    }
+}
+
+impl std::fmt::Debug for Anonymizer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Never print the key
+        f.debug_struct("Anonymizer")
+            .field("packets", &self.packets)
+            .field("addresses", &self.addresses.len())
+            .finish()
+    }
+}
+
+/// Addresses kept as-is because they identify nothing.
+fn is_special(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(v4) => {
+            v4.is_unspecified() || v4.is_loopback() || v4.is_multicast() || v4.is_broadcast()
+        }
+        IpAddr::V6(v6) => v6.is_unspecified() || v6.is_loopback() || v6.is_multicast(),
+    }
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_be_bytes([
+        *data.get(offset)?,
+        *data.get(offset + 1)?,
+    ]))
+}
+
+fn is_dns_port(data: &[u8], l4: usize) -> bool {
+    [read_u16(data, l4), read_u16(data, l4 + 2)]
+        .iter()
+        .any(|port| matches!(port, Some(53 | 5353)))
+}
+
+fn is_dhcp_port(data: &[u8], l4: usize) -> bool {
+    [read_u16(data, l4), read_u16(data, l4 + 2)]
+        .iter()
+        .any(|port| matches!(port, Some(67 | 68)))
+}
+
+/// Walk a DNS name, optionally blanking its labels; returns the offset after it.
+///
+/// Compression pointers are not followed: the names they point at are
+/// rewritten where they first appear.
+fn walk_name(message: &mut [u8], start: usize, blank: bool) -> Option<usize> {
+    let mut offset = start;
+    loop {
+        let len = *message.get(offset)?;
+        match len & 0xC0 {
+            0x00 if len == 0 => return Some(offset + 1),
+            0x00 => {
+                let end = offset + 1 + usize::from(len);
+                let label = message.get_mut(offset + 1..end)?;
+                if blank {
+                    label.fill(b'x');
+                }
+                offset = end;
+            }
+            0xC0 => return Some(offset + 2),
+            _ => return None,
+        }
+    }
+}
+
+/// Locate the transport layer of an IPv4 packet.
+fn ipv4_layout(data: &[u8], offset: usize) -> Option<IpLayout> {
+    let first = *data.get(offset)?;
+    let header_len = usize::from(first & 0x0F) * 4;
+    if first >> 4 != 4 || header_len < 20 || data.len() < offset + 20 {
+        return None;
+    }
+    let total = usize::from(read_u16(data, offset + 2)?);
+    let flags = read_u16(data, offset + 6)?;
+    let fragment_offset = flags & 0x1FFF;
+
+    Some(IpLayout {
+        ip: offset,
+        l4: (offset + header_len).min(data.len()),
+        end: (offset + total.max(header_len)).min(data.len()),
+        protocol: data[offset + 9],
+        fragment: fragment_offset != 0,
+        first_fragment: fragment_offset == 0 && flags & 0x2000 != 0,
+        v6: false,
+    })
+}
+
+/// Locate the transport layer of an IPv6 packet, skipping extension headers.
+fn ipv6_layout(data: &[u8], offset: usize) -> Option<IpLayout> {
+    if data.len() < offset + 40 || data[offset] >> 4 != 6 {
+        return None;
+    }
+    let end = (offset + 40 + usize::from(read_u16(data, offset + 4)?)).min(data.len());
+    let mut next = data[offset + 6];
+    let mut l4 = offset + 40;
+    let mut fragment = false;
+    let mut first_fragment = false;
+
+    loop {
+        match next {
+            // Hop-by-hop, routing, destination options
+            0 | 43 | 60 => {
+                let len = (usize::from(*data.get(l4 + 1)?) + 1) * 8;
+                next = *data.get(l4)?;
+                l4 += len;
+            }
+            44 => {
+                let field = read_u16(data, l4 + 2)?;
+                fragment |= field >> 3 != 0;
+                first_fragment |= field >> 3 == 0 && field & 1 != 0;
+                next = *data.get(l4)?;
+                l4 += 8;
+            }
+            // Authentication header
+            51 => {
+                let len = (usize::from(*data.get(l4 + 1)?) + 2) * 4;
+                next = *data.get(l4)?;
+                l4 += len;
+            }
+            _ => break,
+        }
+    }
+
+    Some(IpLayout {
+        ip: offset,
+        l4: l4.min(end),
+        end,
+        protocol: next,
+        fragment,
+        first_fragment,
+        v6: true,
+    })
+}
+
+/// Ones' complement sum of 16-bit words.
+fn checksum_add(mut sum: u32, bytes: &[u8]) -> u32 {
+    let mut chunks = bytes.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += u32::from(u16::from_be_bytes([chunk[0], chunk[1]]));
+    }
+    if let [last] = chunks.remainder() {
+        sum += u32::from(*last) << 8;
+    }
+    sum
+}
+
+/// Fold a sum into a 16-bit internet checksum.
+fn checksum_finish(mut sum: u32) -> u16 {
+    while sum > 0xFFFF {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Recompute the checksum of the IPv4 header at `offset`.
+pub(crate) fn fix_ipv4_header_checksum(data: &mut [u8], offset: usize) {
+    let header_len = usize::from(data[offset] & 0x0F) * 4;
+    let Some(header) = data.get_mut(offset..offset + header_len) else {
+        return;
+    };
+    header[10..12].fill(0);
+    let checksum = checksum_finish(checksum_add(0, header));
+    header[10..12].copy_from_slice(&checksum.to_be_bytes());
+}
+
+/// Offset of the TCP/UDP/ICMP checksum within the transport header.
+fn transport_checksum_field(protocol: u8) -> Option<usize> {
+    match protocol {
+        6 => Some(16),
+        17 => Some(6),
+        1 | 58 => Some(2),
+        _ => None,
+    }
+}
+
+/// Repair the transport checksum after rewriting: recomputed for whole
+/// datagrams, adjusted for first fragments.
+fn repair_transport_checksum(data: &mut [u8], layout: IpLayout, original: Option<&[u8]>) {
+    match original {
+        Some(original) => adjust_transport_checksum(data, layout, original),
+        None if !layout.first_fragment => fix_transport_checksum(data, layout),
+        None => {}
+    }
+}
+
+/// Adjust the TCP/UDP/ICMP checksum of a first fragment for the bytes that
+/// changed (RFC 1624). The checksum covers the whole datagram, so it can't be
+/// recomputed from the fragment. `original` is the fragment before
+/// rewriting, from its IP header.
+fn adjust_transport_checksum(data: &mut [u8], layout: IpLayout, original: &[u8]) {
+    let Some(field) = transport_checksum_field(layout.protocol) else {
+        return;
+    };
+    let Some(checksum) = read_u16(data, layout.l4 + field) else {
+        return;
+    };
+    // A zero UDP checksum over IPv4 means "not computed"
+    if layout.protocol == 17 && !layout.v6 && checksum == 0 {
+        return;
+    }
+
+    // Sum of everything the checksum covers in the fragment except itself
+    let l4 = layout.l4 - layout.ip;
+    let field = l4 + field;
+    let covered = |packet: &[u8]| {
+        let mut sum = 0;
+        if layout.v6 {
+            sum = checksum_add(sum, &packet[8..40]);
+        } else if layout.protocol != 1 {
+            sum = checksum_add(sum, &packet[12..20]);
+        }
+        sum = checksum_add(sum, &packet[l4..field]);
+        !checksum_finish(checksum_add(sum, &packet[field + 2..]))
+    };
+    let before = covered(original);
+    let after = covered(&data[layout.ip..layout.end]);
+
+    // HC' = ~(~HC + ~m + m')
+    let mut adjusted =
+        checksum_finish(u32::from(!checksum) + u32::from(!before) + u32::from(after));
+    if layout.protocol == 17 && adjusted == 0 {
+        adjusted = 0xFFFF;
+    }
+    data[layout.ip + field..layout.ip + field + 2].copy_from_slice(&adjusted.to_be_bytes());
+    debug!(
+        "Adjusted checksum of a first fragment for protocol {}",
+        layout.protocol
+    );
+}
+
+/// Recompute a TCP/UDP/ICMP checksum when the whole segment was captured.
+fn fix_transport_checksum(data: &mut [u8], layout: IpLayout) {
+    let Some(field) = transport_checksum_field(layout.protocol) else {
+        return;
+    };
+    let declared_end = if layout.v6 {
+        read_u16(data, layout.ip + 4).map(|len| layout.ip + 40 + usize::from(len))
+    } else {
+        read_u16(data, layout.ip + 2).map(|len| layout.ip + usize::from(len))
+    };
+    // Checksums of truncated segments can't be recomputed
+    if declared_end != Some(layout.end) || layout.l4 + field + 2 > layout.end {
+        return;
+    }
+    // A zero UDP checksum over IPv4 means "not computed"
+    if layout.protocol == 17 && !layout.v6 && read_u16(data, layout.l4 + 6) == Some(0) {
+        return;
+    }
+
+    data[layout.l4 + field..layout.l4 + field + 2].fill(0);
+    let segment = &data[layout.l4..layout.end];
+    let mut sum = 0;
+    if layout.v6 {
+        sum = checksum_add(sum, &data[layout.ip + 8..layout.ip + 40]);
+        sum = checksum_add(sum, &(segment.len() as u32).to_be_bytes());
+        sum = checksum_add(sum, &[0, 0, 0, layout.protocol]);
+    } else if layout.protocol != 1 {
+        sum = checksum_add(sum, &data[layout.ip + 12..layout.ip + 20]);
+        sum = checksum_add(sum, &[0, layout.protocol]);
+        sum = checksum_add(sum, &(segment.len() as u16).to_be_bytes());
+    }
+    sum = checksum_add(sum, segment);
+
+    let mut checksum = checksum_finish(sum);
+    if layout.protocol == 17 && checksum == 0 {
+        checksum = 0xFFFF;
+    }
+    data[layout.l4 + field..layout.l4 + field + 2].copy_from_slice(&checksum.to_be_bytes());
+    debug!("Recomputed checksum for protocol {}", layout.protocol);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ProtocolDecoder;
+    use crate::dissect::ApplicationData;
+    use chrono::Utc;
+    use etherparse::{IpNumber, PacketBuilder, SlicedPacket, TransportSlice};
+    use pcap::Linktype;
+
+    fn anonymizer() -> Anonymizer {
+        Anonymizer::new(AnonymizationPolicy::default().key("test"))
+    }
+
+    fn udp_frame(src: [u8; 4], dst: [u8; 4], dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let builder = PacketBuilder::ethernet2([0x00, 0x1B, 0x63, 1, 2, 3], [2, 0, 0, 0, 0, 2])
+            .ipv4(src, dst, 64)
+            .udp(40000, dst_port);
+        let mut frame = Vec::new();
+        builder.write(&mut frame, payload).unwrap();
+        frame
+    }
+
+    fn v4(ip: IpAddr) -> u32 {
+        match ip {
+            IpAddr::V4(v4) => u32::from(v4),
+            IpAddr::V6(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_prefix_preserving_addresses() {
+        let mut anon = anonymizer();
+        let a = anon.anonymize_ip("10.1.2.3".parse().unwrap());
+        let b = anon.anonymize_ip("10.1.2.200".parse().unwrap());
+        let c = anon.anonymize_ip("192.168.0.1".parse().unwrap());
+
+        assert_ne!(a, "10.1.2.3".parse::<IpAddr>().unwrap());
+        // Shared /24 stays shared, different /1 stays different
+        assert_eq!(v4(a) >> 8, v4(b) >> 8);
+        assert_ne!(v4(a) >> 31, v4(c) >> 31);
+        // Stable per key, different across keys
+        assert_eq!(anonymizer().anonymize_ip("10.1.2.3".parse().unwrap()), a);
+        let mut other = Anonymizer::new(AnonymizationPolicy::default().key("other"));
+        assert_ne!(other.anonymize_ip("10.1.2.3".parse().unwrap()), a);
+        // Multicast is kept
+        let mdns: IpAddr = "224.0.0.251".parse().unwrap();
+        assert_eq!(anon.anonymize_ip(mdns), mdns);
+
+        let x = anon.anonymize_ip("2001:db8::1".parse().unwrap());
+        let y = anon.anonymize_ip("2001:db8::2".parse().unwrap());
+        match (x, y) {
+            (IpAddr::V6(x), IpAddr::V6(y)) => assert_eq!(x.segments()[..7], y.segments()[..7]),
+            _ => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_anonymize_packet_fixes_checksums() {
+        let frame = udp_frame([192, 168, 1, 10], [192, 168, 1, 1], 9999, b"secret data");
+        let mut anon = Anonymizer::new(
+            AnonymizationPolicy::default()
+                .key("test")
+                .mac(MacPolicy::KeepOui)
+                .zero_payloads(),
+        );
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert_eq!(out.len(), frame.len());
+
+        // OUI kept, device part changed
+        assert_eq!(out[6..9], frame[6..9]);
+        assert_ne!(out[9..12], frame[9..12]);
+
+        let sliced = SlicedPacket::from_ethernet(&out).unwrap();
+        let Some(etherparse::NetSlice::Ipv4(ip)) = sliced.net else {
+            panic!("expected IPv4");
+        };
+        assert_ne!(ip.header().source(), [192, 168, 1, 10]);
+        let header = ip.header().to_header();
+        assert_eq!(header.header_checksum, header.calc_header_checksum());
+
+        let Some(TransportSlice::Udp(udp)) = sliced.transport else {
+            panic!("expected UDP");
+        };
+        assert!(udp.payload().iter().all(|b| *b == 0));
+        let expected = udp
+            .to_header()
+            .calc_checksum_ipv4(&header, udp.payload())
+            .unwrap();
+        assert_eq!(udp.checksum(), expected);
+    }
+
+    #[test]
+    fn test_anonymize_fragmented_udp_adjusts_checksum() {
+        // 40-byte UDP datagram split after 24 bytes
+        let payload: Vec<u8> = (0..32).collect();
+        let mut datagram = Vec::new();
+        PacketBuilder::ipv4([192, 168, 1, 10], [192, 168, 1, 1], 64)
+            .udp(40000, 9999)
+            .write(&mut datagram, &payload)
+            .unwrap();
+        let segment = &datagram[20..];
+        let fragment = |data: &[u8], offset: u16, more: bool| {
+            let mut packet = datagram[..20].to_vec();
+            packet[2..4].copy_from_slice(&(20 + data.len() as u16).to_be_bytes());
+            let flags = (offset / 8) | if more { 0x2000 } else { 0 };
+            packet[6..8].copy_from_slice(&flags.to_be_bytes());
+            packet.extend_from_slice(data);
+            fix_ipv4_header_checksum(&mut packet, 0);
+            packet
+        };
+        let first = fragment(&segment[..24], 0, true);
+        let second = fragment(&segment[24..], 24, false);
+
+        let mut anon = Anonymizer::new(AnonymizationPolicy::default().key("test").keep_payloads());
+        let first = anon.anonymize(LinkType::RawIp, &first);
+        let second = anon.anonymize(LinkType::RawIp, &second);
+        assert_ne!(first[12..20], datagram[12..20]);
+        assert_eq!(first[12..20], second[12..20]);
+
+        // The reassembled datagram checks out against the new addresses
+        let mut sum = checksum_add(0, &first[12..20]);
+        sum = checksum_add(sum, &[0, 17]);
+        sum = checksum_add(sum, &(segment.len() as u16).to_be_bytes());
+        sum = checksum_add(sum, &first[20..]);
+        sum = checksum_add(sum, &second[20..]);
+        assert_eq!(checksum_finish(sum), 0);
+    }
+
+    #[test]
+    fn test_anonymize_dns_names_and_truncate() {
+        let mut query = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 1, 0, 0, 0, 0];
+        query.extend_from_slice(b"\x08intranet\x04corp\x00\x00\x01\x00\x01");
+        // Answer: pointer to the question name, A 10.0.0.7
+        query.extend_from_slice(&[0xC0, 12, 0, 1, 0, 1, 0, 0, 0, 60, 0, 4, 10, 0, 0, 7]);
+        let frame = udp_frame([192, 168, 1, 1], [192, 168, 1, 10], 53, &query);
+
+        let mut anon = Anonymizer::new(
+            AnonymizationPolicy::default()
+                .key("test")
+                .remove_dns_names(true),
+        );
+        let packet =
+            ProtocolDecoder::new().decode(&anon.anonymize(LinkType::Ethernet, &frame), Utc::now());
+        let Some(ApplicationData::Dns(dns)) = packet.application else {
+            panic!("expected DNS");
+        };
+        assert_eq!(dns.questions[0].name, "xxxxxxxx.xxxx");
+        assert_ne!(dns.answers[0].data, "10.0.0.7");
+
+        let mut anon = Anonymizer::new(AnonymizationPolicy::default().truncate_payloads(4));
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert_eq!(out.len(), 14 + 20 + 8 + 4);
+    }
+
+    #[test]
+    fn test_anonymize_dns_record_names() {
+        let mut response = vec![0x12, 0x34, 0x81, 0x80, 0, 1, 0, 4, 0, 0, 0, 0];
+        response.extend_from_slice(b"\x04corp\x00\x00\xFF\x00\x01");
+        let record = |response: &mut Vec<u8>, rtype: u16, rdata: &[u8]| {
+            response.extend_from_slice(&[0xC0, 12]);
+            response.extend_from_slice(&rtype.to_be_bytes());
+            response.extend_from_slice(&[0, 1, 0, 0, 0, 60]);
+            response.extend_from_slice(&(rdata.len() as u16).to_be_bytes());
+            response.extend_from_slice(rdata);
+        };
+        let mut soa = b"\x03ns1\x06secret\x00\x0Ahostmaster\x06secret\x00".to_vec();
+        soa.extend_from_slice(&[0; 20]);
+        record(&mut response, 6, &soa);
+        record(
+            &mut response,
+            33,
+            b"\x00\x0A\x00\x05\x01\xBB\x03sip\x06secret\x00",
+        );
+        record(&mut response, 16, b"\x16v=spf1 include:secret");
+        // HTTPS: priority 1, target, ipv4hint 10.0.0.7
+        record(
+            &mut response,
+            65,
+            b"\x00\x01\x03svc\x06secret\x00\x00\x04\x00\x04\x0A\x00\x00\x07",
+        );
+        let frame = udp_frame([192, 168, 1, 1], [192, 168, 1, 10], 53, &response);
+
+        let mut anon = Anonymizer::new(
+            AnonymizationPolicy::default()
+                .key("test")
+                .remove_dns_names(true),
+        );
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert_eq!(out.len(), frame.len());
+        assert!(!contains(&out, b"secret"));
+        assert!(!contains(&out, b"hostmaster"));
+        assert!(!contains(&out, b"spf1"));
+        assert!(!contains(&out, &[10, 0, 0, 7]));
+
+        // Address hints are rewritten even when names are kept
+        let mut anon = Anonymizer::new(
+            AnonymizationPolicy::default()
+                .key("test")
+                .remove_dns_names(false),
+        );
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert!(contains(&out, b"secret"));
+        assert!(!contains(&out, &[10, 0, 0, 7]));
+    }
+
+    fn decode(frame: &[u8]) -> crate::decode::DecodedPacket {
+        ProtocolDecoder::new().decode(frame, Utc::now())
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+
+    #[test]
+    fn test_default_policy_cuts_unknown_payloads() {
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([0x00, 0x1B, 0x63, 1, 2, 3], [2, 0, 0, 0, 0, 2])
+            .ipv4([192, 168, 1, 10], [203, 0, 113, 5], 64)
+            .tcp(40000, 80, 1, 1024)
+            .write(
+                &mut frame,
+                b"GET /payroll HTTP/1.1\r\nHost: intranet.corp\r\n\r\n",
+            )
+            .unwrap();
+
+        let out = anonymizer().anonymize(LinkType::Ethernet, &frame);
+        assert_eq!(out.len(), 14 + 20 + 20);
+
+        // Payloads can still be kept on request
+        let mut anon = Anonymizer::new(AnonymizationPolicy::default().keep_payloads());
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert!(contains(&out, b"intranet.corp"));
+    }
+
+    #[test]
+    fn test_anonymize_dhcp() {
+        let mut message = vec![0u8; 240];
+        message[0] = 1; // BOOTREQUEST
+        message[1] = 1;
+        message[2] = 6;
+        message[12..16].copy_from_slice(&[192, 168, 1, 50]);
+        message[28..34].copy_from_slice(&[0x00, 0x1B, 0x63, 0xAA, 0xBB, 0xCC]);
+        message[236..240].copy_from_slice(&[99, 130, 83, 99]);
+        message.extend_from_slice(&[50, 4, 192, 168, 1, 50]);
+        message.extend_from_slice(&[12, 11]);
+        message.extend_from_slice(b"alice-macbk");
+        message.push(255);
+        let frame = udp_frame([192, 168, 1, 50], [255, 255, 255, 255], 67, &message);
+
+        let mut anon = anonymizer();
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        // Rewritten payloads are kept by default
+        assert_eq!(out.len(), frame.len());
+        let dhcp = &out[14 + 20 + 8..];
+        let client = anon.anonymize_ip("192.168.1.50".parse().unwrap());
+        let IpAddr::V4(client) = client else {
+            unreachable!()
+        };
+        assert_eq!(dhcp[12..16], client.octets());
+        assert_eq!(dhcp[242..246], client.octets());
+        assert_ne!(dhcp[28..34], message[28..34]);
+        assert!(!contains(&out, b"alice"));
+    }
+
+    #[test]
+    fn test_rewrite_tunnelled_packets() {
+        let mut inner_ip = Vec::new();
+        PacketBuilder::ipv4([172, 16, 0, 1], [172, 16, 0, 2], 64)
+            .udp(40000, 9999)
+            .write(&mut inner_ip, b"tunnelled secret")
+            .unwrap();
+        let mut inner_ethernet = Vec::new();
+        PacketBuilder::ethernet2([0x00, 0x1B, 0x63, 4, 5, 6], [0x00, 0x1B, 0x63, 7, 8, 9])
+            .ipv4([172, 16, 0, 1], [172, 16, 0, 2], 64)
+            .udp(40000, 9999)
+            .write(&mut inner_ethernet, b"tunnelled secret")
+            .unwrap();
+        let mut inner_v6 = Vec::new();
+        PacketBuilder::ipv6(
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            64,
+        )
+        .udp(40000, 9999)
+        .write(&mut inner_v6, b"tunnelled secret")
+        .unwrap();
+
+        let outer_ip = |protocol: IpNumber, payload: &[u8]| {
+            let mut frame = Vec::new();
+            PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+                .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+                .write(&mut frame, protocol, payload)
+                .unwrap();
+            frame
+        };
+        let mut gre = vec![0x20, 0x00, 0x08, 0x00, 0, 0, 0, 7];
+        gre.extend(&inner_ip);
+        let mut vxlan = vec![0x08, 0, 0, 0, 0, 0, 42, 0];
+        vxlan.extend(&inner_ethernet);
+        let mut geneve = vec![0x00, 0, 0x65, 0x58, 0, 0x01, 0x00, 0];
+        geneve.extend(&inner_ethernet);
+
+        let frames = [
+            outer_ip(IpNumber::GRE, &gre),
+            outer_ip(IpNumber::IPV4, &inner_ip),
+            outer_ip(IpNumber::IPV6, &inner_v6),
+            udp_frame([10, 0, 0, 1], [10, 0, 0, 2], 4789, &vxlan),
+            udp_frame([10, 0, 0, 1], [10, 0, 0, 2], 6081, &geneve),
+        ];
+        for frame in frames {
+            // The inner payload is cut by default, not just the outer one
+            let out = anonymizer().anonymize(LinkType::Ethernet, &frame);
+            assert!(!contains(&out, b"secret"));
+            assert!(out.len() < frame.len());
+
+            // Zeroed copies keep their lengths and still decode
+            let original = decode(&frame);
+            let mut anon =
+                Anonymizer::new(AnonymizationPolicy::default().key("test").zero_payloads());
+            let out = anon.anonymize(LinkType::Ethernet, &frame);
+            assert!(!contains(&out, b"secret"));
+            let packet = decode(&out);
+
+            assert_eq!(packet.tunnels.len(), 1, "{}", original.summary());
+            assert_eq!(
+                packet.tunnels[0].src,
+                anon.anonymize_ip(original.tunnels[0].src)
+            );
+            let (src, dst) = (original.src_ip.unwrap(), original.dst_ip.unwrap());
+            assert_eq!(packet.src_ip, Some(anon.anonymize_ip(src)));
+            assert_eq!(packet.dst_ip, Some(anon.anonymize_ip(dst)));
+            assert_ne!(packet.src_ip, Some(src));
+            if original.src_mac.as_deref() == Some("00:1b:63:04:05:06") {
+                assert_ne!(packet.src_mac, original.src_mac);
+            }
+        }
+
+        // The outer UDP checksum covers the rewritten inner frame
+        let frame = udp_frame([10, 0, 0, 1], [10, 0, 0, 2], 4789, &vxlan);
+        let mut anon = Anonymizer::new(AnonymizationPolicy::default().key("test").zero_payloads());
+        let out = anon.anonymize(LinkType::Ethernet, &frame);
+        assert_eq!(out.len(), frame.len());
+        assert!(!contains(&out, b"secret"));
+        let sliced = SlicedPacket::from_ethernet(&out).unwrap();
+        let (Some(etherparse::NetSlice::Ipv4(ip)), Some(TransportSlice::Udp(udp))) =
+            (sliced.net, sliced.transport)
+        else {
+            panic!("expected UDP over IPv4");
+        };
+        let expected = udp
+            .to_header()
+            .calc_checksum_ipv4(&ip.header().to_header(), udp.payload())
+            .unwrap();
+        assert_eq!(udp.checksum(), expected);
+    }
+
+    #[test]
+    fn test_refuses_unknown_link_types() {
+        assert_eq!(
+            link_type(Linktype::ETHERNET).unwrap(),
+            LinkType::Ethernet
+        );
+        assert_eq!(
+            link_type(Linktype::LINUX_SLL).unwrap(),
+            LinkType::LinuxSll
+        );
+        for linktype in [
+            Linktype::IEEE802_11_RADIOTAP,
+            Linktype::IEEE802_11,
+            Linktype::NULL,
+            Linktype::LOOP,
+            Linktype::PPP,
+        ] {
+            assert!(matches!(
+                link_type(linktype),
+                Err(CaptureError::UnsupportedLinkType(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn test_non_ip_frames() {
+        // LLDP advertising the switch name
+        let mut lldp = vec![
+            0x01, 0x80, 0xC2, 0, 0, 0x0E, 0x00, 0x1B, 0x63, 1, 2, 3, 0x88, 0xCC,
+        ];
+        lldp.extend_from_slice(&[0x0A, 0x0B]);
+        lldp.extend_from_slice(b"core-sw-hq1");
+
+        let mut anon = anonymizer();
+        let out = anon.anonymize(LinkType::Ethernet, &lldp);
+        assert_eq!(out.len(), 14);
+        // Multicast destination kept, source scrambled
+        assert_eq!(out[..6], lldp[..6]);
+        assert_ne!(out[6..12], lldp[6..12]);
+        assert_eq!(anon.unparsed_packets(), 1);
+
+        // EAPOL over a Linux cooked capture, zeroed in place
+        let mut eapol = vec![
+            0, 0, 0, 1, 0, 6, 0x00, 0x1B, 0x63, 1, 2, 3, 0, 0, 0x88, 0x8E,
+        ];
+        eapol.extend_from_slice(&[0x01, 0x00, 0x00, 0x05, 0x02, 0x01, 0x00, 0x05, 0x01]);
+        eapol.extend_from_slice(b"alice");
+        let mut anon = Anonymizer::new(AnonymizationPolicy::default().key("test").zero_payloads());
+        let out = anon.anonymize(LinkType::LinuxSll, &eapol);
+        assert_eq!(out.len(), eapol.len());
+        assert_ne!(out[6..12], eapol[6..12]);
+        assert!(out[16..].iter().all(|b| *b == 0));
+
+        // Unknown link contents are cut entirely
+        let out = anonymizer().anonymize(LinkType::RawIp, b"not an ip packet");
+        assert!(out.is_empty());
+    }
+}
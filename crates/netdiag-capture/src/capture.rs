@@ -1,13 +1,20 @@
 //! Packet capture functionality.
 
-use crate::decode::{DecodedPacket, ProtocolDecoder};
+use crate::anonymize::{AnonymizationPolicy, Anonymizer};
+use crate::decode::{DecodedPacket, LinkType, ProtocolDecoder};
 use crate::error::{CaptureError, CaptureResult};
-use crate::filter::CaptureFilter;
+use crate::filter::{CaptureFilter, CompiledFilter};
+use crate::process::ProcessResolver;
+use crate::reassembly::FragmentReassembler;
+use crate::recorder::FlightRecorder;
+use crate::ring::{RingBufferConfig, RingBufferWriter, HEADER_SNAPLEN};
 use crate::stats::CaptureStats;
-use chrono::Utc;
-use pcap::{Active, Capture, Device};
+use chrono::{DateTime, Utc};
+use pcap::{Activated, Active, Capture, Device, Linktype, Offline, PacketHeader};
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::borrow::Cow;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
@@ -19,6 +26,10 @@ pub struct CaptureConfig {
     /// Device name to capture on.
     pub device: String,
 
+    /// PCAP/PCAPNG file to read instead of a live device.
+    #[serde(default)]
+    pub file: Option<PathBuf>,
+
     /// BPF filter.
     pub filter: CaptureFilter,
 
@@ -37,14 +48,35 @@ pub struct CaptureConfig {
     /// Maximum packets to capture (0 = unlimited).
     pub max_packets: usize,
 
-    /// Maximum capture duration.
+    /// Maximum capture duration (measured in packet time when reading a file).
     pub max_duration: Option<Duration>,
+
+    /// Write captured packets to rotating files.
+    #[serde(default)]
+    pub ring_buffer: Option<RingBufferConfig>,
+
+    /// Anonymise packets before they are decoded or written anywhere.
+    #[serde(default)]
+    pub anonymize: Option<AnonymizationPolicy>,
+
+    /// Attribute live traffic to local processes (Linux).
+    #[serde(default)]
+    pub attribute_processes: bool,
+
+    /// Reassemble fragmented IP datagrams before dissection.
+    #[serde(default = "default_reassemble_fragments")]
+    pub reassemble_fragments: bool,
+}
+
+fn default_reassemble_fragments() -> bool {
+    true
 }
 
 impl Default for CaptureConfig {
     fn default() -> Self {
         Self {
             device: String::new(),
+            file: None,
             filter: CaptureFilter::all(),
             promiscuous: true,
             snaplen: 65535,
@@ -52,6 +84,10 @@ impl Default for CaptureConfig {
             timeout_ms: 1000,
             max_packets: 0,
             max_duration: None,
+            ring_buffer: None,
+            anonymize: None,
+            attribute_processes: false,
+            reassemble_fragments: true,
         }
     }
 }
@@ -65,6 +101,19 @@ impl CaptureConfig {
         }
     }
 
+    /// Create config for reading a PCAP/PCAPNG file.
+    pub fn for_file(path: impl Into<PathBuf>) -> Self {
+        Self {
+            file: Some(path.into()),
+            ..Default::default()
+        }
+    }
+
+    /// Check if this config reads from a capture file.
+    pub fn is_offline(&self) -> bool {
+        self.file.is_some()
+    }
+
     /// Set BPF filter.
     pub fn with_filter(mut self, filter: CaptureFilter) -> Self {
         self.filter = filter;
@@ -88,18 +137,78 @@ impl CaptureConfig {
         self.max_duration = Some(duration);
         self
     }
+
+    /// Write captured packets to a rotating ring buffer.
+    ///
+    /// Header-only ring buffers also lower the live snapshot length.
+    pub fn with_ring_buffer(mut self, ring_buffer: RingBufferConfig) -> Self {
+        if ring_buffer.headers_only {
+            self.snaplen = self.snaplen.min(HEADER_SNAPLEN);
+        }
+        self.ring_buffer = Some(ring_buffer);
+        self
+    }
+
+    /// Anonymise packets according to a policy.
+    pub fn with_anonymization(mut self, policy: AnonymizationPolicy) -> Self {
+        self.anonymize = Some(policy);
+        self
+    }
+
+    /// Attribute live traffic to local processes.
+    ///
+    /// Ignored when reading files, whose sockets are long gone.
+    pub fn attribute_processes(mut self, enabled: bool) -> Self {
+        self.attribute_processes = enabled;
+        self
+    }
+
+    /// Enable or disable IP fragment reassembly (enabled by default).
+    pub fn reassemble_fragments(mut self, enabled: bool) -> Self {
+        self.reassemble_fragments = enabled;
+        self
+    }
 }
 
 /// Packet capture handle.
+#[derive(Debug, Clone)]
 pub struct CaptureHandle {
     /// Stop flag.
     stop_flag: Arc<AtomicBool>,
+    /// Packets dropped by the kernel.
+    dropped: Arc<AtomicU64>,
+    /// Packets dropped by the interface.
+    if_dropped: Arc<AtomicU64>,
 }
 
 impl CaptureHandle {
     /// Create a new handle.
     fn new(stop_flag: Arc<AtomicBool>) -> Self {
-        Self { stop_flag }
+        Self {
+            stop_flag,
+            dropped: Arc::new(AtomicU64::new(0)),
+            if_dropped: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Packets dropped by the kernel so far (updated about once a second).
+    pub fn packets_dropped(&self) -> u64 {
+        self.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Packets dropped by the interface so far (updated about once a second).
+    pub fn packets_dropped_interface(&self) -> u64 {
+        self.if_dropped.load(Ordering::Relaxed)
+    }
+
+    /// Record the drop counters reported by pcap.
+    fn record_drops<T: Activated + ?Sized>(&self, cap: &mut Capture<T>) {
+        if let Ok(stats) = cap.stats() {
+            self.dropped
+                .store(u64::from(stats.dropped), Ordering::Relaxed);
+            self.if_dropped
+                .store(u64::from(stats.if_dropped), Ordering::Relaxed);
+        }
     }
 
     /// Stop the capture.
@@ -117,6 +226,7 @@ impl CaptureHandle {
 pub struct PacketCapture {
     config: CaptureConfig,
     decoder: ProtocolDecoder,
+    recorder: Option<FlightRecorder>,
 }
 
 impl PacketCapture {
@@ -125,9 +235,16 @@ impl PacketCapture {
         Self {
             config,
             decoder: ProtocolDecoder::new(),
+            recorder: None,
         }
     }
 
+    /// Feed captured packets into a flight recorder.
+    pub fn with_flight_recorder(mut self, recorder: FlightRecorder) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
     /// Create capture for default device.
     pub fn default_device() -> CaptureResult<Self> {
         let device = Device::lookup()
@@ -137,8 +254,98 @@ impl PacketCapture {
         Ok(Self::new(CaptureConfig::for_device(&device.name)))
     }
 
+    /// Create capture reading from a PCAP/PCAPNG file.
+    pub fn from_file(path: impl Into<PathBuf>) -> Self {
+        Self::new(CaptureConfig::for_file(path))
+    }
+
+    /// Open the configured capture source and apply the filter.
+    ///
+    /// Returns the compiled filter so the parts BPF can't express are checked
+    /// against decoded packets.
+    fn open_capture(&self) -> CaptureResult<(Capture<dyn Activated>, CompiledFilter)> {
+        // Validate before touching the device
+        let filter = self.config.filter.compile()?;
+
+        let mut cap: Capture<dyn Activated> = match &self.config.file {
+            Some(path) => Self::open_file(path)?.into(),
+            None => self.open_device()?.into(),
+        };
+
+        // Apply filter
+        if let Some(bpf) = filter.bpf() {
+            debug!("Applying filter: {}", bpf);
+            cap.filter(bpf, true)
+                .map_err(|e| CaptureError::InvalidFilter(e.to_string()))?;
+        }
+        if filter.needs_userspace() {
+            debug!("Evaluating filter in userspace: {}", self.config.filter);
+        }
+
+        Ok((cap, filter))
+    }
+
+    /// Create the ring-buffer writer, if configured.
+    fn ring_writer(&self, cap: &Capture<dyn Activated>) -> CaptureResult<Option<RingBufferWriter>> {
+        self.config
+            .ring_buffer
+            .clone()
+            .map(|config| {
+                info!("Writing ring buffer to {}", config.directory.display());
+                RingBufferWriter::new(config, cap.get_datalink())
+            })
+            .transpose()
+    }
+
+    /// Prepare the flight recorder, if attached, for this capture's link type.
+    fn flight_recorder(&self, cap: &Capture<dyn Activated>) -> Option<FlightRecorder> {
+        let recorder = self.recorder.clone()?;
+        recorder.set_linktype(cap.get_datalink());
+        Some(recorder)
+    }
+
+    /// Create the process resolver for live captures, if enabled.
+    fn process_resolver(&self) -> Option<ProcessResolver> {
+        if !self.config.attribute_processes || self.config.is_offline() {
+            return None;
+        }
+        if !ProcessResolver::is_supported() {
+            debug!("Process attribution is not supported on this platform");
+            return None;
+        }
+        // Interface addresses tell local listeners apart from remote ports
+        let addresses = Device::list()
+            .map(|devices| {
+                devices
+                    .into_iter()
+                    .flat_map(|device| device.addresses)
+                    .map(|address| address.addr)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+        Some(
+            ProcessResolver::new()
+                .with_local_addresses(addresses)
+                .with_background_refresh(),
+        )
+    }
+
+    /// Create the fragment reassembler, if enabled.
+    fn fragment_reassembler(&self) -> Option<FragmentReassembler> {
+        self.config
+            .reassemble_fragments
+            .then(FragmentReassembler::new)
+    }
+
+    /// Open a capture file.
+    fn open_file(path: &Path) -> CaptureResult<Capture<Offline>> {
+        debug!("Opening capture file: {}", path.display());
+
+        Capture::from_file(path).map_err(|e| CaptureError::InvalidFile(e.to_string()))
+    }
+
     /// Open the capture device.
-    fn open_capture(&self) -> CaptureResult<Capture<Active>> {
+    fn open_device(&self) -> CaptureResult<Capture<Active>> {
         let device = if self.config.device.is_empty() {
             Device::lookup()
                 .map_err(|e| CaptureError::PcapError(e.to_string()))?
@@ -153,7 +360,7 @@ impl PacketCapture {
 
         debug!("Opening capture on device: {}", device.name);
 
-        let mut cap = Capture::from_device(device)
+        Capture::from_device(device)
             .map_err(|e| {
                 if e.to_string().contains("permission") {
                     CaptureError::PermissionDenied
@@ -172,16 +379,7 @@ impl PacketCapture {
                 } else {
                     CaptureError::PcapError(e.to_string())
                 }
-            })?;
-
-        // Apply filter
-        if !self.config.filter.is_empty() {
-            debug!("Applying filter: {}", self.config.filter);
-            cap.filter(self.config.filter.as_str(), true)
-                .map_err(|e| CaptureError::InvalidFilter(e.to_string()))?;
-        }
-
-        Ok(cap)
+            })
     }
 
     /// Start capturing packets.
@@ -191,17 +389,35 @@ impl PacketCapture {
         let (tx, rx) = mpsc::channel(1000);
         let stop_flag = Arc::new(AtomicBool::new(false));
         let handle = CaptureHandle::new(stop_flag.clone());
+        let counters = handle.clone();
 
-        let mut cap = self.open_capture()?;
+        let (mut cap, filter) = self.open_capture()?;
+        let link_type = link_type(cap.get_datalink())?;
+        let mut ring = self.ring_writer(&cap)?;
+        let recorder = self.flight_recorder(&cap);
+        let mut anonymizer = self.config.anonymize.clone().map(Anonymizer::new);
+        let mut reassembler = self.fragment_reassembler();
+        let mut anonymized_reassembler = anonymizer
+            .as_ref()
+            .and_then(|_| self.fragment_reassembler());
+        let mut processes = self.process_resolver();
         let decoder = self.decoder.clone();
         let max_packets = self.config.max_packets;
         let max_duration = self.config.max_duration;
+        let offline = self.config.is_offline();
         let start_time = std::time::Instant::now();
 
         std::thread::spawn(move || {
             let mut packet_count = 0usize;
+            let mut first_timestamp = None;
+            let mut last_stats = std::time::Instant::now();
 
             loop {
+                if !offline && last_stats.elapsed() >= Duration::from_secs(1) {
+                    counters.record_drops(&mut cap);
+                    last_stats = std::time::Instant::now();
+                }
+
                 // Check stop conditions
                 if stop_flag.load(Ordering::SeqCst) {
                     debug!("Capture stopped by handle");
@@ -214,7 +430,7 @@ impl PacketCapture {
                 }
 
                 if let Some(max_dur) = max_duration {
-                    if start_time.elapsed() >= max_dur {
+                    if !offline && start_time.elapsed() >= max_dur {
                         debug!("Max duration reached");
                         break;
                     }
@@ -223,7 +439,51 @@ impl PacketCapture {
                 // Try to get next packet
                 match cap.next_packet() {
                     Ok(packet) => {
-                        let decoded = decoder.decode(packet.data, Utc::now());
+                        let decoded =
+                            decode_packet(&decoder, packet.header, packet.data, link_type);
+                        let mut decoded = reassemble(
+                            reassembler.as_mut(),
+                            &decoder,
+                            decoded,
+                            packet.data,
+                            link_type,
+                        );
+                        if !filter.matches(&decoded) {
+                            continue;
+                        }
+                        let process = processes.as_mut().and_then(|p| p.lookup_packet(&decoded));
+                        let (header, data) = anonymize(
+                            anonymizer.as_mut(),
+                            anonymized_reassembler.as_mut(),
+                            &mut decoded,
+                            &decoder,
+                            packet.header,
+                            packet.data,
+                            link_type,
+                        );
+                        decoded.process = process;
+
+                        if offline
+                            && exceeds_duration(
+                                &mut first_timestamp,
+                                decoded.timestamp,
+                                max_duration,
+                            )
+                        {
+                            debug!("Max duration reached");
+                            break;
+                        }
+
+                        if let Some(ring) = ring.as_mut() {
+                            if let Err(e) = ring.write(&header, &data) {
+                                warn!("Ring buffer error: {}", e);
+                                break;
+                            }
+                        }
+
+                        if let Some(recorder) = &recorder {
+                            recorder.record(&header, &data);
+                        }
 
                         if tx.blocking_send(decoded).is_err() {
                             // Receiver dropped
@@ -237,6 +497,10 @@ impl PacketCapture {
                         // Normal timeout, continue
                         continue;
                     }
+                    Err(pcap::Error::NoMorePackets) => {
+                        debug!("End of capture file");
+                        break;
+                    }
                     Err(e) => {
                         warn!("Capture error: {}", e);
                         break;
@@ -244,6 +508,13 @@ impl PacketCapture {
                 }
             }
 
+            if let Some(ring) = ring.as_mut() {
+                ring.close();
+            }
+            if !offline {
+                counters.record_drops(&mut cap);
+            }
+
             info!("Capture finished: {} packets", packet_count);
         });
 
@@ -255,10 +526,22 @@ impl PacketCapture {
     where
         F: FnMut(DecodedPacket) -> bool,
     {
-        let mut cap = self.open_capture()?;
+        let (mut cap, filter) = self.open_capture()?;
+        let link_type = link_type(cap.get_datalink())?;
+        let mut ring = self.ring_writer(&cap)?;
+        let recorder = self.flight_recorder(&cap);
+        let mut anonymizer = self.config.anonymize.clone().map(Anonymizer::new);
+        let mut reassembler = self.fragment_reassembler();
+        let mut anonymized_reassembler = anonymizer
+            .as_ref()
+            .and_then(|_| self.fragment_reassembler());
+        let mut processes = self.process_resolver();
+        let offline = self.config.is_offline();
         let mut stats = CaptureStats::new();
         let start_time = std::time::Instant::now();
         let mut packet_count = 0usize;
+        let mut first_timestamp = None;
+        let mut last_timestamp = None;
 
         loop {
             // Check stop conditions
@@ -267,7 +550,7 @@ impl PacketCapture {
             }
 
             if let Some(max_dur) = self.config.max_duration {
-                if start_time.elapsed() >= max_dur {
+                if !offline && start_time.elapsed() >= max_dur {
                     break;
                 }
             }
@@ -275,7 +558,48 @@ impl PacketCapture {
             // Try to get next packet
             match cap.next_packet() {
                 Ok(packet) => {
-                    let decoded = self.decoder.decode(packet.data, Utc::now());
+                    let decoded =
+                        decode_packet(&self.decoder, packet.header, packet.data, link_type);
+                    let mut decoded = reassemble(
+                        reassembler.as_mut(),
+                        &self.decoder,
+                        decoded,
+                        packet.data,
+                        link_type,
+                    );
+                    if !filter.matches(&decoded) {
+                        continue;
+                    }
+                    let process = processes.as_mut().and_then(|p| p.lookup_packet(&decoded));
+                    let (header, data) = anonymize(
+                        anonymizer.as_mut(),
+                        anonymized_reassembler.as_mut(),
+                        &mut decoded,
+                        &self.decoder,
+                        packet.header,
+                        packet.data,
+                        link_type,
+                    );
+                    decoded.process = process;
+
+                    if offline {
+                        if exceeds_duration(
+                            &mut first_timestamp,
+                            decoded.timestamp,
+                            self.config.max_duration,
+                        ) {
+                            break;
+                        }
+                        last_timestamp = Some(decoded.timestamp);
+                    }
+
+                    if let Some(ring) = ring.as_mut() {
+                        ring.write(&header, &data)?;
+                    }
+
+                    if let Some(recorder) = &recorder {
+                        recorder.record(&header, &data);
+                    }
 
                     // Update stats
                     stats.update(
@@ -286,6 +610,7 @@ impl PacketCapture {
                         decoded.src_port,
                         decoded.dst_port,
                     );
+                    stats.update_encapsulation(&decoded);
 
                     // Call user callback
                     if !callback(decoded) {
@@ -297,6 +622,10 @@ impl PacketCapture {
                 Err(pcap::Error::TimeoutExpired) => {
                     continue;
                 }
+                Err(pcap::Error::NoMorePackets) => {
+                    debug!("End of capture file");
+                    break;
+                }
                 Err(e) => {
                     warn!("Capture error: {}", e);
                     break;
@@ -304,13 +633,30 @@ impl PacketCapture {
             }
         }
 
+        if let Some(ring) = ring.as_mut() {
+            ring.close();
+        }
+
+        if let Some(mut reassembler) = reassembler {
+            reassembler.flush();
+            stats.fragmentation = reassembler.stats().clone();
+        }
+
         // Get pcap stats
         if let Ok(pcap_stats) = cap.stats() {
             stats.packets_dropped = pcap_stats.dropped as u64;
             stats.packets_dropped_interface = pcap_stats.if_dropped as u64;
         }
 
-        stats.finalize();
+        // Files are summarised over the time span of their packets
+        match (first_timestamp, last_timestamp) {
+            (Some(first), Some(last)) => {
+                stats.start_time = Some(first);
+                stats.finalize_at(last);
+            }
+            _ => stats.finalize(),
+        }
+
         Ok(stats)
     }
 
@@ -337,3 +683,403 @@ impl Clone for ProtocolDecoder {
         Self::new()
     }
 }
+
+/// Map a pcap link type to the decoder's framing.
+///
+/// Other link types (radiotap, 802.11, loopback, PPP, SLL2) are refused:
+/// decoding them as Ethernet would produce plausible-looking garbage, and
+/// the anonymiser would edit the wrong bytes and leave real addresses in
+/// place.
+pub(crate) fn link_type(linktype: Linktype) -> CaptureResult<LinkType> {
+    match linktype {
+        Linktype::ETHERNET => Ok(LinkType::Ethernet),
+        Linktype::LINUX_SLL => Ok(LinkType::LinuxSll),
+        Linktype::RAW | Linktype::IPV4 | Linktype::IPV6 => Ok(LinkType::RawIp),
+        _ => Err(CaptureError::UnsupportedLinkType(
+            linktype
+                .get_name()
+                .unwrap_or_else(|_| linktype.0.to_string()),
+        )),
+    }
+}
+
+/// Decode a packet using its pcap header for timestamp and original length.
+fn decode_packet(
+    decoder: &ProtocolDecoder,
+    header: &PacketHeader,
+    data: &[u8],
+    link_type: LinkType,
+) -> DecodedPacket {
+    let mut decoded = decoder.decode_link(data, packet_timestamp(header), link_type);
+    decoded.length = header.len as usize;
+    decoded
+}
+
+/// Feed a decoded packet to the fragment reassembler, if enabled.
+fn reassemble(
+    reassembler: Option<&mut FragmentReassembler>,
+    decoder: &ProtocolDecoder,
+    decoded: DecodedPacket,
+    data: &[u8],
+    link_type: LinkType,
+) -> DecodedPacket {
+    match reassembler {
+        Some(reassembler) => reassembler.reassemble(decoder, decoded, data, link_type),
+        None => decoded,
+    }
+}
+
+/// Anonymise a packet that passed the filter, re-decoding the rewritten bytes.
+///
+/// The rewritten fragments go through their own reassembler so completed
+/// datagrams are still presented whole.
+///
+/// Returns the header and data to pass on to writers.
+fn anonymize<'a>(
+    anonymizer: Option<&mut Anonymizer>,
+    reassembler: Option<&mut FragmentReassembler>,
+    decoded: &mut DecodedPacket,
+    decoder: &ProtocolDecoder,
+    header: &PacketHeader,
+    data: &'a [u8],
+    link_type: LinkType,
+) -> (PacketHeader, Cow<'a, [u8]>) {
+    match anonymizer {
+        Some(anonymizer) => {
+            let (header, data) = anonymizer.anonymize_packet(link_type, header, data);
+            let redecoded = decode_packet(decoder, &header, &data, link_type);
+            *decoded = reassemble(reassembler, decoder, redecoded, &data, link_type);
+            (header, Cow::Owned(data))
+        }
+        None => (*header, Cow::Borrowed(data)),
+    }
+}
+
+/// Convert a pcap header timestamp to UTC.
+#[allow(clippy::unnecessary_cast)] // `time_t` is not `i64` on every platform
+pub(crate) fn packet_timestamp(header: &PacketHeader) -> DateTime<Utc> {
+    let nanos = u32::try_from(header.ts.tv_usec).unwrap_or(0) * 1000;
+    DateTime::from_timestamp(header.ts.tv_sec as i64, nanos).unwrap_or_else(Utc::now)
+}
+
+/// Check whether a packet falls outside the maximum duration, measured from the first packet.
+fn exceeds_duration(
+    first: &mut Option<DateTime<Utc>>,
+    timestamp: DateTime<Utc>,
+    max_duration: Option<Duration>,
+) -> bool {
+    let first = *first.get_or_insert(timestamp);
+    max_duration.is_some_and(|max| (timestamp - first).to_std().unwrap_or_default() > max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::Protocol;
+    use crate::recorder::FlightRecorderConfig;
+    use etherparse::PacketBuilder;
+    use std::net::IpAddr;
+
+    /// Build an Ethernet/IPv4/UDP frame.
+    fn udp_frame(src_port: u16, dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4([192, 168, 1, 10], [192, 168, 1, 1], 64)
+            .udp(src_port, dst_port);
+        let mut frame = Vec::with_capacity(builder.size(payload.len()));
+        builder.write(&mut frame, payload).unwrap();
+        frame
+    }
+
+    /// Write a classic little-endian pcap file with one-second packet spacing.
+    fn write_pcap(name: &str, frames: &[Vec<u8>]) -> PathBuf {
+        write_pcap_linktype(name, 1, frames)
+    }
+
+    fn write_pcap_linktype(name: &str, linktype: u32, frames: &[Vec<u8>]) -> PathBuf {
+        let mut bytes = Vec::new();
+        for field in [0xa1b2_c3d4_u32, 0x0004_0002, 0, 0, 65535, linktype] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for (i, frame) in frames.iter().enumerate() {
+            let ts_sec = 1_700_000_000 + i as u32;
+            let len = frame.len() as u32;
+            for field in [ts_sec, 0, len, len] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            bytes.extend_from_slice(frame);
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "netdiag-capture-{}-{}.pcap",
+            name,
+            std::process::id()
+        ));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_capture_from_file() {
+        let path = write_pcap(
+            "read",
+            &[
+                udp_frame(50000, 53, b"query"),
+                udp_frame(53, 50000, b"answer"),
+                udp_frame(68, 67, b"discover"),
+            ],
+        );
+
+        let mut packets = Vec::new();
+        let stats = PacketCapture::from_file(&path)
+            .capture_sync(|packet| {
+                packets.push(packet);
+                true
+            })
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(packets.len(), 3);
+        assert_eq!(packets[0].protocol, Protocol::Dns);
+        assert_eq!(packets[2].protocol, Protocol::Dhcp);
+        assert_eq!(packets[0].timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(stats.packets_captured, 3);
+        assert_eq!(stats.protocols["DNS"].packets, 2);
+        assert_eq!(stats.duration, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_capture_from_file_max_duration() {
+        let frames: Vec<_> = (0..5).map(|_| udp_frame(1000, 2000, b"x")).collect();
+        let path = write_pcap("duration", &frames);
+
+        let config = CaptureConfig::for_file(&path).max_duration(Duration::from_secs(2));
+        let stats = PacketCapture::new(config).capture_sync(|_| true).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(stats.packets_captured, 3);
+    }
+
+    #[test]
+    fn test_capture_from_file_userspace_filter() {
+        let path = write_pcap(
+            "userspace-filter",
+            &[
+                udp_frame(50000, 53, b"query"),
+                udp_frame(68, 67, b"discover"),
+                udp_frame(1000, 2000, b"other"),
+            ],
+        );
+
+        let config = CaptureConfig::for_file(&path)
+            .with_filter(CaptureFilter::parse("dhcp or udp.dstport == 2000").unwrap());
+        let mut ports = Vec::new();
+        let stats = PacketCapture::new(config)
+            .capture_sync(|packet| {
+                ports.push(packet.dst_port);
+                true
+            })
+            .unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(ports, vec![Some(67), Some(2000)]);
+        assert_eq!(stats.packets_captured, 2);
+    }
+
+    #[test]
+    fn test_anonymize_file_and_capture() {
+        let input = write_pcap(
+            "anonymize-in",
+            &[
+                udp_frame(50000, 53, b"query"),
+                udp_frame(1000, 2000, b"secret"),
+            ],
+        );
+        let output = input.with_extension("anon.pcap");
+        let policy = AnonymizationPolicy::default().key("test").zero_payloads();
+
+        let written = Anonymizer::new(policy.clone())
+            .anonymize_file(&input, &output)
+            .unwrap();
+        assert_eq!(written, 2);
+
+        let mut from_file = Vec::new();
+        PacketCapture::from_file(&output)
+            .capture_sync(|packet| {
+                from_file.push(packet);
+                true
+            })
+            .unwrap();
+
+        let mut live = Vec::new();
+        PacketCapture::new(CaptureConfig::for_file(&input).with_anonymization(policy))
+            .capture_sync(|packet| {
+                live.push(packet);
+                true
+            })
+            .unwrap();
+        std::fs::remove_file(&input).ok();
+        std::fs::remove_file(&output).ok();
+
+        let original: IpAddr = "192.168.1.10".parse().unwrap();
+        assert_eq!(from_file.len(), 2);
+        assert_ne!(from_file[0].src_ip, Some(original));
+        assert_eq!(from_file[0].src_ip, live[0].src_ip);
+        assert_eq!(from_file[1].payload_preview, Some(vec![0; 6]));
+    }
+
+    /// Files written to a ring-buffer directory, oldest first.
+    fn ring_files(dir: &Path) -> Vec<PathBuf> {
+        let mut files: Vec<_> = std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        files.sort();
+        files
+    }
+
+    #[test]
+    fn test_ring_buffer_rotates_by_size() {
+        let frame = udp_frame(1000, 2000, &[0u8; 100]);
+        let path = write_pcap("ring-size", &vec![frame.clone(); 7]);
+        let dir = std::env::temp_dir().join(format!("netdiag-ring-size-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+
+        // Room for two packets per file, keeping the last two files
+        let ring = RingBufferConfig::new(&dir)
+            .max_file_size(24 + 2 * (16 + frame.len() as u64))
+            .max_files(2);
+        let config = CaptureConfig::for_file(&path).with_ring_buffer(ring);
+        PacketCapture::new(config).capture_sync(|_| true).unwrap();
+
+        let files = ring_files(&dir);
+        assert_eq!(files.len(), 2);
+        assert!(files[0].to_string_lossy().ends_with("_00002.pcap"));
+        assert_eq!(
+            PacketCapture::from_file(&files[0])
+                .capture_packets(10)
+                .unwrap()
+                .len(),
+            2
+        );
+        assert_eq!(
+            PacketCapture::from_file(&files[1])
+                .capture_packets(10)
+                .unwrap()
+                .len(),
+            1
+        );
+
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_ring_buffer_rotates_by_time_headers_only() {
+        let frame = udp_frame(1000, 2000, &[0u8; 500]);
+        let path = write_pcap("ring-time", &vec![frame.clone(); 5]);
+        let dir = std::env::temp_dir().join(format!("netdiag-ring-time-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let ring = RingBufferConfig::new(&dir)
+            .max_file_duration(Duration::from_secs(2))
+            .max_files(0)
+            .headers_only(true);
+        let config = CaptureConfig::for_file(&path).with_ring_buffer(ring);
+        assert_eq!(config.snaplen, HEADER_SNAPLEN);
+        PacketCapture::new(config).capture_sync(|_| true).unwrap();
+
+        // Packets are one second apart: [0, 1], [2, 3], [4]
+        let files = ring_files(&dir);
+        assert_eq!(files.len(), 3);
+
+        let packets = PacketCapture::from_file(&files[0])
+            .capture_packets(10)
+            .unwrap();
+        assert_eq!(packets.len(), 2);
+        // Original length is kept, payload is not
+        assert_eq!(packets[0].length, frame.len());
+        assert_eq!(
+            std::fs::metadata(&files[0]).unwrap().len(),
+            24 + 2 * (16 + HEADER_SNAPLEN as u64)
+        );
+
+        // A later run counts the files already in the directory
+        let writer =
+            RingBufferWriter::new(RingBufferConfig::new(&dir), Linktype::ETHERNET).unwrap();
+        assert_eq!(writer.files().count(), 3);
+
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_flight_recorder_keeps_window_around_trigger() {
+        let frames: Vec<_> = (0..10).map(|_| udp_frame(1000, 2000, b"x")).collect();
+        let path = write_pcap("recorder", &frames);
+        let dir = std::env::temp_dir().join(format!("netdiag-recorder-{}", std::process::id()));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let recorder = FlightRecorder::new(
+            FlightRecorderConfig::new(&dir)
+                .pre_trigger(Duration::from_secs(3))
+                .post_trigger(Duration::from_secs(2)),
+        );
+        let capture = PacketCapture::from_file(&path).with_flight_recorder(recorder.clone());
+
+        let mut dump = None;
+        capture
+            .capture_sync(|packet| {
+                let second = packet.timestamp.timestamp() - 1_700_000_000;
+                if second == 5 {
+                    dump = Some(
+                        recorder
+                            .trigger_at("gateway down", packet.timestamp)
+                            .unwrap(),
+                    );
+                } else if second == 6 {
+                    // A second alert during the dump joins the same file
+                    let again = recorder.trigger_at("dns", packet.timestamp).unwrap();
+                    assert_eq!(Some(&again), dump.as_ref());
+                }
+                true
+            })
+            .unwrap();
+        recorder.close();
+
+        // Packets 2-5 from before the trigger, 6-8 after (window extended by the second alert)
+        let dump = dump.unwrap();
+        assert!(dump
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .starts_with("gateway-down_"));
+        let packets = PacketCapture::from_file(&dump).capture_packets(20).unwrap();
+        let seconds: Vec<_> = packets
+            .iter()
+            .map(|p| p.timestamp.timestamp() - 1_700_000_000)
+            .collect();
+        assert_eq!(seconds, vec![2, 3, 4, 5, 6, 7, 8]);
+        assert_eq!(recorder.buffered_packets(), 4);
+
+        std::fs::remove_file(&path).ok();
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_capture_from_file_refuses_unknown_link_type() {
+        // DLT_NULL, as captured on macOS lo0: 4-byte address family, then IPv4
+        let mut frame = 2u32.to_le_bytes().to_vec();
+        frame.extend_from_slice(&udp_frame(1000, 53, b"query")[14..]);
+        let path = write_pcap_linktype("null", 0, &[frame]);
+
+        let result = PacketCapture::from_file(&path).capture_packets(1);
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(result, Err(CaptureError::UnsupportedLinkType(_))));
+    }
+
+    #[test]
+    fn test_capture_from_missing_file() {
+        let result = PacketCapture::from_file("/nonexistent/netdiag.pcap").capture_packets(1);
+        assert!(matches!(result, Err(CaptureError::InvalidFile(_))));
+    }
+}
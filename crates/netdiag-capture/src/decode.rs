@@ -1,7 +1,11 @@
 //! Protocol decoding.
 
+use crate::dissect::{self, ApplicationData};
+use crate::process::ProcessInfo;
+use crate::tunnel::{self, Decapsulated, TunnelInfo, TunnelKind};
 use chrono::{DateTime, Utc};
-use etherparse::SlicedPacket;
+use etherparse::{Ipv6ExtensionSlice, SlicedPacket};
+use netdiag_types::capture::{CapturedPacket, FragmentInfo};
 use serde::{Deserialize, Serialize};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
@@ -38,6 +42,18 @@ pub struct DecodedPacket {
     /// TTL / Hop limit.
     pub ttl: Option<u8>,
 
+    /// IPv6 extension headers, in order.
+    #[serde(default)]
+    pub ipv6_extensions: Vec<Ipv6Extension>,
+
+    /// Fragment header fields, if the packet is an IP fragment.
+    #[serde(default)]
+    pub fragment: Option<FragmentInfo>,
+
+    /// Number of fragments reassembled into this packet (0 if not reassembled).
+    #[serde(default)]
+    pub reassembled_fragments: usize,
+
     /// Source port.
     pub src_port: Option<u16>,
 
@@ -50,6 +66,15 @@ pub struct DecodedPacket {
     /// TCP flags (if TCP).
     pub tcp_flags: Option<TcpFlags>,
 
+    /// TCP sequence number (if TCP).
+    pub tcp_seq: Option<u32>,
+
+    /// TCP acknowledgment number (if TCP).
+    pub tcp_ack: Option<u32>,
+
+    /// TCP receive window (if TCP, unscaled).
+    pub tcp_window: Option<u16>,
+
     /// ICMP type (if ICMP).
     pub icmp_type: Option<u8>,
 
@@ -61,6 +86,24 @@ pub struct DecodedPacket {
 
     /// Payload length.
     pub payload_length: usize,
+
+    /// Application-layer details (DNS, DHCP, TLS, HTTP).
+    pub application: Option<ApplicationData>,
+
+    /// Local process that sent or received the packet (live captures).
+    #[serde(default)]
+    pub process: Option<ProcessInfo>,
+
+    /// 802.1Q VLAN IDs, outermost first.
+    #[serde(default)]
+    pub vlans: Vec<u16>,
+
+    /// Tunnels the packet was carried in, outermost first.
+    ///
+    /// When a tunnel is peeled, the address and protocol fields above
+    /// describe the inner packet.
+    #[serde(default)]
+    pub tunnels: Vec<TunnelInfo>,
 }
 
 /// Protocol type.
@@ -94,6 +137,8 @@ pub enum Protocol {
     Ssh,
     /// DHCP.
     Dhcp,
+    /// WireGuard.
+    WireGuard,
 }
 
 impl Protocol {
@@ -114,6 +159,7 @@ impl Protocol {
             Protocol::Https => "HTTPS",
             Protocol::Ssh => "SSH",
             Protocol::Dhcp => "DHCP",
+            Protocol::WireGuard => "WireGuard",
         }
     }
 }
@@ -124,6 +170,40 @@ impl std::fmt::Display for Protocol {
     }
 }
 
+/// IPv6 extension header type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Ipv6Extension {
+    /// Hop-by-Hop Options.
+    HopByHop,
+    /// Routing header.
+    Routing,
+    /// Fragment header.
+    Fragment,
+    /// Destination Options.
+    DestinationOptions,
+    /// Authentication Header.
+    Authentication,
+}
+
+impl Ipv6Extension {
+    /// Get extension header name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::HopByHop => "Hop-by-Hop",
+            Self::Routing => "Routing",
+            Self::Fragment => "Fragment",
+            Self::DestinationOptions => "Destination Options",
+            Self::Authentication => "Authentication",
+        }
+    }
+}
+
+impl std::fmt::Display for Ipv6Extension {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
 /// TCP flags.
 #[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
 pub struct TcpFlags {
@@ -160,6 +240,16 @@ impl TcpFlags {
         }
     }
 
+    /// Convert to the raw flags byte.
+    pub fn to_byte(self) -> u8 {
+        [
+            self.fin, self.syn, self.rst, self.psh, self.ack, self.urg, self.ece, self.cwr,
+        ]
+        .iter()
+        .enumerate()
+        .fold(0, |byte, (bit, set)| byte | (u8::from(*set) << bit))
+    }
+
     /// Format flags as string.
     pub fn to_string_short(&self) -> String {
         let mut s = String::new();
@@ -194,6 +284,21 @@ impl std::fmt::Display for TcpFlags {
     }
 }
 
+/// Link-layer framing of captured data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum LinkType {
+    /// Ethernet II frames.
+    #[default]
+    Ethernet,
+    /// Linux "cooked" capture (SLL), as produced by `tcpdump -i any`.
+    LinuxSll,
+    /// Raw IPv4/IPv6 packets without a link-layer header.
+    RawIp,
+}
+
+/// Maximum number of nested tunnels to peel.
+pub(crate) const MAX_TUNNEL_DEPTH: usize = 4;
+
 /// Protocol decoder.
 pub struct ProtocolDecoder {
     /// Maximum payload preview size.
@@ -215,8 +320,32 @@ impl ProtocolDecoder {
         }
     }
 
-    /// Decode a raw packet.
+    /// Decode a raw Ethernet frame.
     pub fn decode(&self, data: &[u8], timestamp: DateTime<Utc>) -> DecodedPacket {
+        self.decode_link(data, timestamp, LinkType::Ethernet)
+    }
+
+    /// Decode a raw packet with the given link-layer framing.
+    ///
+    /// VLAN tags are recorded and GRE, VXLAN, GENEVE and IP-in-IP tunnels
+    /// are peeled so the packet is classified by its inner traffic.
+    pub fn decode_link(
+        &self,
+        data: &[u8],
+        timestamp: DateTime<Utc>,
+        link_type: LinkType,
+    ) -> DecodedPacket {
+        self.decode_at_depth(data, timestamp, link_type, 0)
+    }
+
+    /// Decode a packet found `depth` tunnels deep.
+    fn decode_at_depth(
+        &self,
+        data: &[u8],
+        timestamp: DateTime<Utc>,
+        link_type: LinkType,
+        depth: usize,
+    ) -> DecodedPacket {
         let mut packet = DecodedPacket {
             timestamp,
             length: data.len(),
@@ -228,18 +357,34 @@ impl ProtocolDecoder {
             dst_ip: None,
             ip_protocol: None,
             ttl: None,
+            ipv6_extensions: Vec::new(),
+            fragment: None,
+            reassembled_fragments: 0,
             src_port: None,
             dst_port: None,
             protocol: Protocol::Unknown,
             tcp_flags: None,
+            tcp_seq: None,
+            tcp_ack: None,
+            tcp_window: None,
             icmp_type: None,
             icmp_code: None,
             payload_preview: None,
             payload_length: 0,
+            application: None,
+            process: None,
+            vlans: Vec::new(),
+            tunnels: Vec::new(),
         };
 
         // Try to parse the packet
-        match SlicedPacket::from_ethernet(data) {
+        let sliced = match link_type {
+            LinkType::Ethernet => SlicedPacket::from_ethernet(data),
+            LinkType::LinuxSll => SlicedPacket::from_linux_sll(data),
+            LinkType::RawIp => SlicedPacket::from_ip(data),
+        };
+
+        match sliced {
             Ok(sliced) => {
                 // Extract link layer
                 if let Some(link) = &sliced.link {
@@ -261,6 +406,18 @@ impl ProtocolDecoder {
                     }
                 }
 
+                // Extract VLAN tags
+                match &sliced.vlan {
+                    Some(etherparse::VlanSlice::SingleVlan(vlan)) => {
+                        packet.vlans.push(vlan.vlan_identifier().value());
+                    }
+                    Some(etherparse::VlanSlice::DoubleVlan(vlan)) => {
+                        packet.vlans.push(vlan.outer().vlan_identifier().value());
+                        packet.vlans.push(vlan.inner().vlan_identifier().value());
+                    }
+                    None => {}
+                }
+
                 // Extract network layer
                 if let Some(net) = &sliced.net {
                     match net {
@@ -271,14 +428,43 @@ impl ProtocolDecoder {
                             packet.ip_protocol = Some(header.protocol().0);
                             packet.ttl = Some(header.ttl());
                             packet.protocol = Protocol::Ipv4;
+                            if ipv4.is_payload_fragmented() {
+                                packet.fragment = Some(FragmentInfo {
+                                    offset: header.fragments_offset().byte_offset(),
+                                    more_fragments: header.more_fragments(),
+                                    dont_fragment: header.dont_fragment(),
+                                    id: u32::from(header.identification()),
+                                });
+                                self.set_payload(&mut packet, ipv4.payload().payload);
+                            }
                         }
                         etherparse::NetSlice::Ipv6(ipv6) => {
                             let header = ipv6.header();
                             packet.src_ip = Some(IpAddr::V6(Ipv6Addr::from(header.source())));
                             packet.dst_ip = Some(IpAddr::V6(Ipv6Addr::from(header.destination())));
-                            packet.ip_protocol = Some(header.next_header().0);
+                            // Upper-layer protocol after any extension headers
+                            packet.ip_protocol = Some(ipv6.payload().ip_number.0);
                             packet.ttl = Some(header.hop_limit());
                             packet.protocol = Protocol::Ipv6;
+                            for extension in ipv6.extensions().clone() {
+                                packet.ipv6_extensions.push(match extension {
+                                    Ipv6ExtensionSlice::HopByHop(_) => Ipv6Extension::HopByHop,
+                                    Ipv6ExtensionSlice::Routing(_) => Ipv6Extension::Routing,
+                                    Ipv6ExtensionSlice::Fragment(fragment) => {
+                                        packet.fragment = ipv6_fragment(fragment.slice());
+                                        Ipv6Extension::Fragment
+                                    }
+                                    Ipv6ExtensionSlice::DestinationOptions(_) => {
+                                        Ipv6Extension::DestinationOptions
+                                    }
+                                    Ipv6ExtensionSlice::Authentication(_) => {
+                                        Ipv6Extension::Authentication
+                                    }
+                                });
+                            }
+                            if packet.fragment.is_some() {
+                                self.set_payload(&mut packet, ipv6.payload().payload);
+                            }
                         }
                     }
                 }
@@ -289,6 +475,9 @@ impl ProtocolDecoder {
                         etherparse::TransportSlice::Tcp(tcp) => {
                             packet.src_port = Some(tcp.source_port());
                             packet.dst_port = Some(tcp.destination_port());
+                            packet.tcp_seq = Some(tcp.sequence_number());
+                            packet.tcp_ack = Some(tcp.acknowledgment_number());
+                            packet.tcp_window = Some(tcp.window_size());
                             packet.tcp_flags = Some(TcpFlags {
                                 fin: tcp.fin(),
                                 syn: tcp.syn(),
@@ -301,44 +490,111 @@ impl ProtocolDecoder {
                             });
                             packet.protocol = self
                                 .identify_tcp_protocol(tcp.source_port(), tcp.destination_port());
+                            packet.application = dissect::dissect_tcp(
+                                tcp.source_port(),
+                                tcp.destination_port(),
+                                tcp.payload(),
+                            );
+                            // Content-based detection for services on non-standard ports
+                            if packet.protocol == Protocol::Tcp {
+                                match packet.application {
+                                    Some(ApplicationData::Tls(_)) => {
+                                        packet.protocol = Protocol::Https
+                                    }
+                                    Some(ApplicationData::Http(_)) => {
+                                        packet.protocol = Protocol::Http
+                                    }
+                                    _ => {}
+                                }
+                            }
+                            self.set_payload(&mut packet, tcp.payload());
                         }
                         etherparse::TransportSlice::Udp(udp) => {
                             packet.src_port = Some(udp.source_port());
                             packet.dst_port = Some(udp.destination_port());
                             packet.protocol = self
                                 .identify_udp_protocol(udp.source_port(), udp.destination_port());
+                            packet.application = dissect::dissect_udp(
+                                udp.source_port(),
+                                udp.destination_port(),
+                                udp.payload(),
+                            );
+                            self.set_payload(&mut packet, udp.payload());
                         }
                         etherparse::TransportSlice::Icmpv4(icmp) => {
                             packet.icmp_type = Some(icmp.type_u8());
                             packet.icmp_code = Some(icmp.code_u8());
                             packet.protocol = Protocol::Icmp;
+                            self.set_payload(&mut packet, icmp.payload());
                         }
                         etherparse::TransportSlice::Icmpv6(icmp) => {
                             packet.icmp_type = Some(icmp.type_u8());
                             packet.icmp_code = Some(icmp.code_u8());
                             packet.protocol = Protocol::Icmpv6;
+                            self.set_payload(&mut packet, icmp.payload());
                         }
                     }
                 }
 
-                // Note: In etherparse 0.16, payload is accessed differently
-                // For now, we calculate payload length from the remaining data
-                // This is a simplification
-                packet.payload_length = 0;
+                // Peel tunnels and classify the inner traffic
+                if depth < MAX_TUNNEL_DEPTH {
+                    if let Some(decapsulated) = tunnel::decapsulate(&sliced) {
+                        self.peel(&mut packet, decapsulated, depth);
+                    }
+                }
             }
             Err(_) => {
                 // Couldn't parse, just store raw data info
-                packet.payload_length = data.len();
-                if self.max_payload_preview > 0 {
-                    let preview_len = data.len().min(self.max_payload_preview);
-                    packet.payload_preview = Some(data[..preview_len].to_vec());
-                }
+                self.set_payload(&mut packet, data);
             }
         }
 
         packet
     }
 
+    /// Replace the outer packet's fields with those of the tunnelled packet.
+    fn peel(&self, packet: &mut DecodedPacket, decapsulated: Decapsulated<'_>, depth: usize) {
+        let kind = decapsulated.tunnel.kind;
+        packet.tunnels.push(decapsulated.tunnel);
+
+        let Some((data, link_type)) = decapsulated.inner else {
+            if kind == TunnelKind::WireGuard {
+                packet.protocol = Protocol::WireGuard;
+            }
+            return;
+        };
+
+        let inner = self.decode_at_depth(data, packet.timestamp, link_type, depth + 1);
+        if inner.src_ip.is_none() {
+            // Not an IP packet (e.g. ARP inside VXLAN); keep the outer view
+            return;
+        }
+
+        let outer = std::mem::replace(packet, inner);
+        packet.length = outer.length;
+        packet.capture_length = outer.capture_length;
+        if packet.src_mac.is_none() {
+            packet.src_mac = outer.src_mac;
+            packet.dst_mac = outer.dst_mac;
+            packet.ether_type = outer.ether_type;
+        }
+        let mut vlans = outer.vlans;
+        vlans.append(&mut packet.vlans);
+        packet.vlans = vlans;
+        let mut tunnels = outer.tunnels;
+        tunnels.append(&mut packet.tunnels);
+        packet.tunnels = tunnels;
+    }
+
+    /// Record payload length and preview.
+    fn set_payload(&self, packet: &mut DecodedPacket, payload: &[u8]) {
+        packet.payload_length = payload.len();
+        if self.max_payload_preview > 0 && !payload.is_empty() {
+            let preview_len = payload.len().min(self.max_payload_preview);
+            packet.payload_preview = Some(payload[..preview_len].to_vec());
+        }
+    }
+
     /// Identify TCP protocol from port numbers.
     fn identify_tcp_protocol(&self, src_port: u16, dst_port: u16) -> Protocol {
         match (src_port, dst_port) {
@@ -366,6 +622,26 @@ impl Default for ProtocolDecoder {
     }
 }
 
+/// Read an IPv6 fragment header, returning `None` for atomic fragments.
+///
+/// Parsed by hand: etherparse 0.16 reads the offset and M flag from the
+/// wrong bits.
+pub(crate) fn ipv6_fragment(header: &[u8]) -> Option<FragmentInfo> {
+    let field = u16::from_be_bytes([*header.get(2)?, *header.get(3)?]);
+    let id = u32::from_be_bytes(header.get(4..8)?.try_into().ok()?);
+    let offset = field & 0xFFF8;
+    let more_fragments = field & 0x0001 != 0;
+    if offset == 0 && !more_fragments {
+        return None;
+    }
+    Some(FragmentInfo {
+        offset,
+        more_fragments,
+        dont_fragment: false,
+        id,
+    })
+}
+
 /// Format MAC address.
 fn format_mac(bytes: &[u8; 6]) -> String {
     format!(
@@ -404,8 +680,31 @@ impl DecodedPacket {
             .map(|f| f.to_string_short())
             .unwrap_or_default();
 
+        let mut details = String::new();
+        if let Some(fragment) = self.fragment {
+            details.push_str(&format!(
+                " frag id={} offset={}{}",
+                fragment.id,
+                fragment.offset,
+                if fragment.more_fragments { " MF" } else { "" }
+            ));
+        }
+        if self.reassembled_fragments > 0 {
+            details.push_str(&format!(
+                " reassembled from {} fragments",
+                self.reassembled_fragments
+            ));
+        }
+        if !self.vlans.is_empty() {
+            let ids: Vec<String> = self.vlans.iter().map(|id| id.to_string()).collect();
+            details.push_str(&format!(" vlan={}", ids.join(",")));
+        }
+        for tunnel in &self.tunnels {
+            details.push_str(&format!(" via {}", tunnel));
+        }
+
         format!(
-            "{} {} -> {} {} len={}{}",
+            "{} {} -> {} {} len={}{}{}{}{}",
             self.protocol,
             src,
             dst,
@@ -415,7 +714,135 @@ impl DecodedPacket {
                 format!(" payload={}", self.payload_length)
             } else {
                 String::new()
-            }
+            },
+            self.application
+                .as_ref()
+                .map(|app| format!(" {}", app))
+                .unwrap_or_default(),
+            self.process
+                .as_ref()
+                .map(|process| format!(" [{}]", process))
+                .unwrap_or_default(),
+            details
         )
     }
+
+    /// Convert to the platform-neutral packet type used by `CaptureProvider`.
+    pub fn to_captured_packet(&self, interface: &str) -> CapturedPacket {
+        use netdiag_types::capture as types;
+
+        let ethernet = match (&self.src_mac, &self.dst_mac) {
+            (Some(src), Some(dst)) => Some(types::EthernetInfo {
+                src_mac: src.clone(),
+                dst_mac: dst.clone(),
+                ether_type: self.ether_type.unwrap_or(0),
+            }),
+            _ => None,
+        };
+
+        // The IP length isn't kept, so derive it from the frame
+        let link_header = if ethernet.is_some() {
+            14 + 4 * self.vlans.len()
+        } else {
+            0
+        };
+        let ip = match (self.src_ip, self.dst_ip) {
+            (Some(src_ip), Some(dst_ip)) => Some(types::IpInfo {
+                version: if src_ip.is_ipv4() { 4 } else { 6 },
+                src_ip,
+                dst_ip,
+                protocol: self.ip_protocol.unwrap_or(0),
+                ttl: self.ttl.unwrap_or(0),
+                length: clamp_u16(self.length.saturating_sub(link_header)),
+                fragment: self.fragment,
+            }),
+            _ => None,
+        };
+
+        let transport = match (self.ip_protocol, self.src_port, self.dst_port) {
+            (Some(6), Some(src_port), Some(dst_port)) => {
+                let flags = self.tcp_flags.unwrap_or_default();
+                Some(types::TransportInfo::Tcp(types::TcpInfo {
+                    src_port,
+                    dst_port,
+                    seq: self.tcp_seq.unwrap_or(0),
+                    ack: self.tcp_ack.unwrap_or(0),
+                    flags: types::TcpFlags {
+                        fin: flags.fin,
+                        syn: flags.syn,
+                        rst: flags.rst,
+                        psh: flags.psh,
+                        ack: flags.ack,
+                        urg: flags.urg,
+                        ece: flags.ece,
+                        cwr: flags.cwr,
+                    },
+                    window: self.tcp_window.unwrap_or(0),
+                    payload_len: clamp_u16(self.payload_length),
+                }))
+            }
+            (Some(17), Some(src_port), Some(dst_port)) => {
+                Some(types::TransportInfo::Udp(types::UdpInfo {
+                    src_port,
+                    dst_port,
+                    length: clamp_u16(self.payload_length + 8),
+                }))
+            }
+            _ => self.icmp_type.map(|icmp_type| {
+                types::TransportInfo::Icmp(types::IcmpInfo {
+                    icmp_type,
+                    code: self.icmp_code.unwrap_or(0),
+                    type_name: icmp_type_name(self.protocol == Protocol::Icmpv6, icmp_type)
+                        .to_string(),
+                })
+            }),
+        };
+
+        let application = match self.protocol {
+            Protocol::Dns => Some(types::ApplicationProtocol::Dns),
+            Protocol::Http => Some(types::ApplicationProtocol::Http),
+            Protocol::Https => Some(types::ApplicationProtocol::Https),
+            Protocol::Ssh => Some(types::ApplicationProtocol::Ssh),
+            Protocol::Dhcp => Some(types::ApplicationProtocol::Dhcp),
+            Protocol::Tcp | Protocol::Udp => Some(types::ApplicationProtocol::Unknown),
+            _ => None,
+        };
+
+        CapturedPacket {
+            timestamp: self.timestamp,
+            length: self.capture_length as u32,
+            original_length: self.length as u32,
+            interface: interface.to_string(),
+            info: types::PacketInfo {
+                ethernet,
+                ip,
+                transport,
+                application,
+            },
+            data: None,
+        }
+    }
+}
+
+/// Saturate a length to 16 bits.
+fn clamp_u16(value: usize) -> u16 {
+    u16::try_from(value).unwrap_or(u16::MAX)
+}
+
+/// Name of an ICMP or ICMPv6 message type.
+fn icmp_type_name(v6: bool, icmp_type: u8) -> &'static str {
+    match (v6, icmp_type) {
+        (false, 0) | (true, 129) => "Echo Reply",
+        (false, 3) | (true, 1) => "Destination Unreachable",
+        (false, 5) | (true, 137) => "Redirect",
+        (false, 8) | (true, 128) => "Echo Request",
+        (false, 11) | (true, 3) => "Time Exceeded",
+        (false, 12) | (true, 4) => "Parameter Problem",
+        (true, 2) => "Packet Too Big",
+        (true, 133) => "Router Solicitation",
+        (true, 134) => "Router Advertisement",
+        (true, 135) => "Neighbor Solicitation",
+        (true, 136) => "Neighbor Advertisement",
+        _ => "Other",
+    }
 }
@@ -0,0 +1,253 @@
+//! DHCP (BOOTP) message dissection.
+
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+
+/// DHCP magic cookie that precedes the options field.
+const MAGIC_COOKIE: [u8; 4] = [99, 130, 83, 99];
+
+/// Offset of the magic cookie in a BOOTP message.
+const OPTIONS_OFFSET: usize = 236;
+
+/// Decoded DHCP message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DhcpInfo {
+    /// BOOTP operation (1 = request, 2 = reply).
+    pub op: u8,
+    /// Transaction ID.
+    pub xid: u32,
+    /// DHCP message type (option 53).
+    pub message_type: Option<DhcpMessageType>,
+    /// Client IP address (ciaddr).
+    pub client_ip: Ipv4Addr,
+    /// Address offered/assigned to the client (yiaddr).
+    pub your_ip: Ipv4Addr,
+    /// Next server IP address (siaddr).
+    pub server_ip: Ipv4Addr,
+    /// Relay agent IP address (giaddr).
+    pub relay_ip: Ipv4Addr,
+    /// Client hardware address.
+    pub client_mac: String,
+    /// Requested IP address (option 50).
+    pub requested_ip: Option<Ipv4Addr>,
+    /// Server identifier (option 54).
+    pub server_id: Option<Ipv4Addr>,
+    /// Subnet mask (option 1).
+    pub subnet_mask: Option<Ipv4Addr>,
+    /// Routers (option 3).
+    pub routers: Vec<Ipv4Addr>,
+    /// DNS servers (option 6).
+    pub dns_servers: Vec<Ipv4Addr>,
+    /// Domain name (option 15).
+    pub domain_name: Option<String>,
+    /// Client host name (option 12).
+    pub hostname: Option<String>,
+    /// Lease time in seconds (option 51).
+    pub lease_time: Option<u32>,
+    /// Parameter request list (option 55).
+    pub parameter_requests: Vec<u8>,
+    /// Server message (option 56), typically explaining a NAK.
+    pub message: Option<String>,
+}
+
+/// DHCP message type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DhcpMessageType {
+    /// DHCPDISCOVER.
+    Discover,
+    /// DHCPOFFER.
+    Offer,
+    /// DHCPREQUEST.
+    Request,
+    /// DHCPDECLINE.
+    Decline,
+    /// DHCPACK.
+    Ack,
+    /// DHCPNAK.
+    Nak,
+    /// DHCPRELEASE.
+    Release,
+    /// DHCPINFORM.
+    Inform,
+    /// Other message type.
+    Other(u8),
+}
+
+impl DhcpMessageType {
+    /// Create from the raw option 53 value.
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::Discover,
+            2 => Self::Offer,
+            3 => Self::Request,
+            4 => Self::Decline,
+            5 => Self::Ack,
+            6 => Self::Nak,
+            7 => Self::Release,
+            8 => Self::Inform,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Get message type name.
+    pub fn name(&self) -> String {
+        match self {
+            Self::Discover => "DHCPDISCOVER".to_string(),
+            Self::Offer => "DHCPOFFER".to_string(),
+            Self::Request => "DHCPREQUEST".to_string(),
+            Self::Decline => "DHCPDECLINE".to_string(),
+            Self::Ack => "DHCPACK".to_string(),
+            Self::Nak => "DHCPNAK".to_string(),
+            Self::Release => "DHCPRELEASE".to_string(),
+            Self::Inform => "DHCPINFORM".to_string(),
+            Self::Other(value) => format!("DHCP({})", value),
+        }
+    }
+}
+
+impl std::fmt::Display for DhcpMessageType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl DhcpInfo {
+    /// Parse a DHCP message from a UDP payload.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < OPTIONS_OFFSET + MAGIC_COOKIE.len() {
+            return None;
+        }
+
+        let ipv4_at = |offset: usize| {
+            Ipv4Addr::new(
+                data[offset],
+                data[offset + 1],
+                data[offset + 2],
+                data[offset + 3],
+            )
+        };
+        let hlen = usize::from(data[2]).min(16);
+        let client_mac = data[28..28 + hlen]
+            .iter()
+            .map(|b| format!("{:02X}", b))
+            .collect::<Vec<_>>()
+            .join(":");
+
+        let mut info = Self {
+            op: data[0],
+            xid: u32::from_be_bytes([data[4], data[5], data[6], data[7]]),
+            message_type: None,
+            client_ip: ipv4_at(12),
+            your_ip: ipv4_at(16),
+            server_ip: ipv4_at(20),
+            relay_ip: ipv4_at(24),
+            client_mac,
+            requested_ip: None,
+            server_id: None,
+            subnet_mask: None,
+            routers: Vec::new(),
+            dns_servers: Vec::new(),
+            domain_name: None,
+            hostname: None,
+            lease_time: None,
+            parameter_requests: Vec::new(),
+            message: None,
+        };
+
+        // Plain BOOTP without options is still a valid message
+        if data[OPTIONS_OFFSET..OPTIONS_OFFSET + 4] != MAGIC_COOKIE {
+            return Some(info);
+        }
+
+        let mut offset = OPTIONS_OFFSET + 4;
+        while let Some(&code) = data.get(offset) {
+            match code {
+                0 => {
+                    offset += 1;
+                    continue;
+                }
+                255 => break,
+                _ => {}
+            }
+
+            let Some(&len) = data.get(offset + 1) else {
+                break;
+            };
+            let Some(value) = data.get(offset + 2..offset + 2 + usize::from(len)) else {
+                break;
+            };
+            info.apply_option(code, value);
+            offset += 2 + usize::from(len);
+        }
+
+        Some(info)
+    }
+
+    /// Record a single DHCP option.
+    fn apply_option(&mut self, code: u8, value: &[u8]) {
+        match code {
+            1 => self.subnet_mask = first_ipv4(value),
+            3 => self.routers = ipv4_list(value),
+            6 => self.dns_servers = ipv4_list(value),
+            12 => self.hostname = Some(String::from_utf8_lossy(value).into_owned()),
+            15 => self.domain_name = Some(String::from_utf8_lossy(value).into_owned()),
+            50 => self.requested_ip = first_ipv4(value),
+            51 if value.len() == 4 => {
+                self.lease_time =
+                    Some(u32::from_be_bytes([value[0], value[1], value[2], value[3]]));
+            }
+            53 => self.message_type = value.first().map(|&t| DhcpMessageType::from_u8(t)),
+            54 => self.server_id = first_ipv4(value),
+            55 => self.parameter_requests = value.to_vec(),
+            56 => self.message = Some(String::from_utf8_lossy(value).into_owned()),
+            _ => {}
+        }
+    }
+}
+
+impl std::fmt::Display for DhcpInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.message_type {
+            Some(message_type) => write!(f, "{}", message_type)?,
+            None => write!(
+                f,
+                "BOOTP {}",
+                if self.op == 1 { "request" } else { "reply" }
+            )?,
+        }
+        write!(f, " xid=0x{:08x}", self.xid)?;
+
+        if !self.your_ip.is_unspecified() {
+            write!(f, " yiaddr={}", self.your_ip)?;
+        } else if let Some(requested) = self.requested_ip {
+            write!(f, " requested={}", requested)?;
+        }
+        if let Some(router) = self.routers.first() {
+            write!(f, " router={}", router)?;
+        }
+        if !self.dns_servers.is_empty() {
+            let servers: Vec<_> = self.dns_servers.iter().map(ToString::to_string).collect();
+            write!(f, " dns={}", servers.join(","))?;
+        }
+        if let Some(lease) = self.lease_time {
+            write!(f, " lease={}s", lease)?;
+        }
+        if let Some(ref message) = self.message {
+            write!(f, " \"{}\"", message)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read the first IPv4 address from an option value.
+fn first_ipv4(value: &[u8]) -> Option<Ipv4Addr> {
+    ipv4_list(value).into_iter().next()
+}
+
+/// Read a list of IPv4 addresses from an option value.
+fn ipv4_list(value: &[u8]) -> Vec<Ipv4Addr> {
+    value
+        .chunks_exact(4)
+        .map(|c| Ipv4Addr::new(c[0], c[1], c[2], c[3]))
+        .collect()
+}
@@ -0,0 +1,318 @@
+//! DNS message dissection.
+
+use serde::{Deserialize, Serialize};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+/// Maximum number of compression pointers followed while reading a name.
+const MAX_POINTER_JUMPS: usize = 16;
+
+/// Decoded DNS message.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsInfo {
+    /// Transaction ID.
+    pub id: u16,
+    /// Whether this message is a response.
+    pub is_response: bool,
+    /// Operation code.
+    pub opcode: u8,
+    /// Response code.
+    pub rcode: DnsResponseCode,
+    /// Authoritative answer flag.
+    pub authoritative: bool,
+    /// Truncated flag.
+    pub truncated: bool,
+    /// Recursion desired flag.
+    pub recursion_desired: bool,
+    /// Recursion available flag.
+    pub recursion_available: bool,
+    /// Question section.
+    pub questions: Vec<DnsQuestion>,
+    /// Answer section.
+    pub answers: Vec<DnsRecord>,
+    /// Number of authority records.
+    pub authority_count: u16,
+    /// Number of additional records.
+    pub additional_count: u16,
+}
+
+/// DNS question.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsQuestion {
+    /// Queried name.
+    pub name: String,
+    /// Query type.
+    pub qtype: u16,
+    /// Query class.
+    pub qclass: u16,
+}
+
+/// DNS resource record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsRecord {
+    /// Owner name.
+    pub name: String,
+    /// Record type.
+    pub rtype: u16,
+    /// Time to live (seconds).
+    pub ttl: u32,
+    /// Record data rendered as text.
+    pub data: String,
+}
+
+/// DNS response code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum DnsResponseCode {
+    /// No error.
+    NoError,
+    /// Format error.
+    FormErr,
+    /// Server failure.
+    ServFail,
+    /// Non-existent domain.
+    NxDomain,
+    /// Not implemented.
+    NotImp,
+    /// Query refused.
+    Refused,
+    /// Other response code.
+    Other(u8),
+}
+
+impl DnsResponseCode {
+    /// Create from the raw 4-bit response code.
+    pub fn from_u8(code: u8) -> Self {
+        match code {
+            0 => Self::NoError,
+            1 => Self::FormErr,
+            2 => Self::ServFail,
+            3 => Self::NxDomain,
+            4 => Self::NotImp,
+            5 => Self::Refused,
+            other => Self::Other(other),
+        }
+    }
+
+    /// Get response code name.
+    pub fn name(&self) -> String {
+        match self {
+            Self::NoError => "NOERROR".to_string(),
+            Self::FormErr => "FORMERR".to_string(),
+            Self::ServFail => "SERVFAIL".to_string(),
+            Self::NxDomain => "NXDOMAIN".to_string(),
+            Self::NotImp => "NOTIMP".to_string(),
+            Self::Refused => "REFUSED".to_string(),
+            Self::Other(code) => format!("RCODE{}", code),
+        }
+    }
+}
+
+impl std::fmt::Display for DnsResponseCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Get the mnemonic for a DNS record type.
+pub fn record_type_name(rtype: u16) -> String {
+    match rtype {
+        1 => "A".to_string(),
+        2 => "NS".to_string(),
+        5 => "CNAME".to_string(),
+        6 => "SOA".to_string(),
+        12 => "PTR".to_string(),
+        15 => "MX".to_string(),
+        16 => "TXT".to_string(),
+        28 => "AAAA".to_string(),
+        33 => "SRV".to_string(),
+        41 => "OPT".to_string(),
+        64 => "SVCB".to_string(),
+        65 => "HTTPS".to_string(),
+        255 => "ANY".to_string(),
+        other => format!("TYPE{}", other),
+    }
+}
+
+impl DnsInfo {
+    /// Parse a DNS message carried over UDP.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 12 {
+            return None;
+        }
+
+        let flags = u16::from_be_bytes([data[2], data[3]]);
+        let qdcount = u16::from_be_bytes([data[4], data[5]]);
+        let ancount = u16::from_be_bytes([data[6], data[7]]);
+
+        let mut info = Self {
+            id: u16::from_be_bytes([data[0], data[1]]),
+            is_response: flags & 0x8000 != 0,
+            opcode: ((flags >> 11) & 0x0F) as u8,
+            rcode: DnsResponseCode::from_u8((flags & 0x0F) as u8),
+            authoritative: flags & 0x0400 != 0,
+            truncated: flags & 0x0200 != 0,
+            recursion_desired: flags & 0x0100 != 0,
+            recursion_available: flags & 0x0080 != 0,
+            questions: Vec::new(),
+            answers: Vec::new(),
+            authority_count: u16::from_be_bytes([data[8], data[9]]),
+            additional_count: u16::from_be_bytes([data[10], data[11]]),
+        };
+
+        // Stop at the first malformed entry but keep what was decoded so far
+        let mut offset = 12;
+        for _ in 0..qdcount {
+            let Some((name, next)) = read_name(data, offset) else {
+                return Some(info);
+            };
+            let Some(fixed) = data.get(next..next + 4) else {
+                return Some(info);
+            };
+            info.questions.push(DnsQuestion {
+                name,
+                qtype: u16::from_be_bytes([fixed[0], fixed[1]]),
+                qclass: u16::from_be_bytes([fixed[2], fixed[3]]),
+            });
+            offset = next + 4;
+        }
+
+        for _ in 0..ancount {
+            match read_record(data, offset) {
+                Some((record, next)) => {
+                    info.answers.push(record);
+                    offset = next;
+                }
+                None => break,
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Parse a DNS message carried over TCP (with a 2-byte length prefix).
+    pub fn parse_tcp(data: &[u8]) -> Option<Self> {
+        let len = usize::from(u16::from_be_bytes([*data.first()?, *data.get(1)?]));
+        let message = data.get(2..)?;
+        // The segment may only contain the start of the message
+        Self::parse(&message[..len.min(message.len())])
+    }
+
+    /// Get the first queried name.
+    pub fn query_name(&self) -> Option<&str> {
+        self.questions.first().map(|q| q.name.as_str())
+    }
+}
+
+impl std::fmt::Display for DnsInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let question = self
+            .questions
+            .first()
+            .map(|q| format!(" {} {}", record_type_name(q.qtype), q.name))
+            .unwrap_or_default();
+
+        if !self.is_response {
+            return write!(f, "query 0x{:04x}{}", self.id, question);
+        }
+
+        write!(f, "response 0x{:04x} {}{}", self.id, self.rcode, question)?;
+        if !self.answers.is_empty() {
+            let answers: Vec<_> = self
+                .answers
+                .iter()
+                .map(|a| format!("{} {}", record_type_name(a.rtype), a.data))
+                .collect();
+            write!(f, " -> {}", answers.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a possibly-compressed domain name, returning it and the offset after it.
+fn read_name(data: &[u8], start: usize) -> Option<(String, usize)> {
+    let mut labels: Vec<String> = Vec::new();
+    let mut offset = start;
+    let mut end = None;
+    let mut jumps = 0;
+
+    loop {
+        let len = *data.get(offset)?;
+        match len & 0xC0 {
+            0x00 => {
+                if len == 0 {
+                    let name = if labels.is_empty() {
+                        ".".to_string()
+                    } else {
+                        labels.join(".")
+                    };
+                    return Some((name, end.unwrap_or(offset + 1)));
+                }
+                let label = data.get(offset + 1..offset + 1 + usize::from(len))?;
+                labels.push(String::from_utf8_lossy(label).into_owned());
+                offset += 1 + usize::from(len);
+            }
+            0xC0 => {
+                let pointer =
+                    usize::from(u16::from_be_bytes([len, *data.get(offset + 1)?]) & 0x3FFF);
+                jumps += 1;
+                if jumps > MAX_POINTER_JUMPS {
+                    return None;
+                }
+                end.get_or_insert(offset + 2);
+                offset = pointer;
+            }
+            _ => return None,
+        }
+    }
+}
+
+/// Read a resource record, returning it and the offset after it.
+fn read_record(data: &[u8], start: usize) -> Option<(DnsRecord, usize)> {
+    let (name, offset) = read_name(data, start)?;
+    let fixed = data.get(offset..offset + 10)?;
+    let rtype = u16::from_be_bytes([fixed[0], fixed[1]]);
+    let ttl = u32::from_be_bytes([fixed[4], fixed[5], fixed[6], fixed[7]]);
+    let rdlength = usize::from(u16::from_be_bytes([fixed[8], fixed[9]]));
+    let rdata_start = offset + 10;
+    let rdata = data.get(rdata_start..rdata_start + rdlength)?;
+
+    let rendered = match rtype {
+        1 if rdata.len() == 4 => Ipv4Addr::new(rdata[0], rdata[1], rdata[2], rdata[3]).to_string(),
+        28 if rdata.len() == 16 => {
+            let octets: [u8; 16] = rdata.try_into().ok()?;
+            Ipv6Addr::from(octets).to_string()
+        }
+        2 | 5 | 12 => read_name(data, rdata_start)
+            .map(|(n, _)| n)
+            .unwrap_or_default(),
+        15 if rdata.len() > 2 => {
+            let preference = u16::from_be_bytes([rdata[0], rdata[1]]);
+            let exchange = read_name(data, rdata_start + 2)
+                .map(|(n, _)| n)
+                .unwrap_or_default();
+            format!("{} {}", preference, exchange)
+        }
+        16 => {
+            let mut strings = Vec::new();
+            let mut pos = 0;
+            while let Some(&len) = rdata.get(pos) {
+                let Some(text) = rdata.get(pos + 1..pos + 1 + usize::from(len)) else {
+                    break;
+                };
+                strings.push(format!("\"{}\"", String::from_utf8_lossy(text)));
+                pos += 1 + usize::from(len);
+            }
+            strings.join(" ")
+        }
+        _ => format!("({} bytes)", rdata.len()),
+    };
+
+    Some((
+        DnsRecord {
+            name,
+            rtype,
+            ttl,
+            data: rendered,
+        },
+        rdata_start + rdlength,
+    ))
+}
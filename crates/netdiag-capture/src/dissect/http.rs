@@ -0,0 +1,128 @@
+//! HTTP/1.x message dissection.
+
+use serde::{Deserialize, Serialize};
+
+/// Request methods recognised at the start of a TCP payload.
+const METHODS: &[&str] = &[
+    "GET", "POST", "PUT", "DELETE", "HEAD", "OPTIONS", "PATCH", "CONNECT", "TRACE",
+];
+
+/// Decoded HTTP request or response head.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpInfo {
+    /// Request method (requests only).
+    pub method: Option<String>,
+    /// Request target (requests only).
+    pub uri: Option<String>,
+    /// Status code (responses only).
+    pub status_code: Option<u16>,
+    /// Reason phrase (responses only).
+    pub reason: Option<String>,
+    /// Protocol version (e.g. "HTTP/1.1").
+    pub version: String,
+    /// Host header.
+    pub host: Option<String>,
+    /// User-Agent header.
+    pub user_agent: Option<String>,
+    /// Server header.
+    pub server: Option<String>,
+    /// Content-Type header.
+    pub content_type: Option<String>,
+    /// Content-Length header.
+    pub content_length: Option<u64>,
+}
+
+impl HttpInfo {
+    /// Parse the start line and headers of an HTTP/1.x message.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if !Self::looks_like_http(data) {
+            return None;
+        }
+
+        // Only the head is needed; the body may be binary
+        let head_end = data
+            .windows(4)
+            .position(|w| w == b"\r\n\r\n")
+            .unwrap_or(data.len());
+        let head = String::from_utf8_lossy(&data[..head_end]);
+        let mut lines = head.split("\r\n");
+        let start_line = lines.next()?;
+        let mut parts = start_line.splitn(3, ' ');
+        let first = parts.next()?;
+        let second = parts.next()?;
+        let third = parts.next().unwrap_or_default();
+
+        let (method, uri, status_code, reason, version) = if first.starts_with("HTTP/") {
+            (None, None, Some(second.parse().ok()?), Some(third), first)
+        } else {
+            (Some(first), Some(second), None, None, third)
+        };
+
+        let mut info = Self {
+            method: method.map(str::to_string),
+            uri: uri.map(str::to_string),
+            status_code,
+            reason: reason.map(str::to_string),
+            version: version.to_string(),
+            host: None,
+            user_agent: None,
+            server: None,
+            content_type: None,
+            content_length: None,
+        };
+
+        for line in lines {
+            let Some((name, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim().to_string();
+            match name.trim().to_ascii_lowercase().as_str() {
+                "host" => info.host = Some(value),
+                "user-agent" => info.user_agent = Some(value),
+                "server" => info.server = Some(value),
+                "content-type" => info.content_type = Some(value),
+                "content-length" => info.content_length = value.parse().ok(),
+                _ => {}
+            }
+        }
+
+        Some(info)
+    }
+
+    /// Check whether a payload starts with an HTTP request or status line.
+    pub fn looks_like_http(data: &[u8]) -> bool {
+        if data.starts_with(b"HTTP/1.") {
+            return true;
+        }
+        METHODS.iter().any(|method| {
+            data.len() > method.len()
+                && data.starts_with(method.as_bytes())
+                && data[method.len()] == b' '
+        })
+    }
+
+    /// Check if this is a request.
+    pub fn is_request(&self) -> bool {
+        self.method.is_some()
+    }
+}
+
+impl std::fmt::Display for HttpInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let (Some(method), Some(uri)) = (&self.method, &self.uri) {
+            write!(f, "{} {}", method, uri)?;
+            if let Some(ref host) = self.host {
+                write!(f, " Host={}", host)?;
+            }
+        } else if let Some(status) = self.status_code {
+            write!(f, "{} {}", self.version, status)?;
+            if let Some(ref reason) = self.reason {
+                write!(f, " {}", reason)?;
+            }
+        }
+        if let Some(ref content_type) = self.content_type {
+            write!(f, " ({})", content_type)?;
+        }
+        Ok(())
+    }
+}
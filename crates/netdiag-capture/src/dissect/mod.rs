@@ -0,0 +1,195 @@
+//! Application-layer protocol dissection.
+
+mod dhcp;
+mod dns;
+mod http;
+mod tls;
+
+pub use dhcp::{DhcpInfo, DhcpMessageType};
+pub use dns::{record_type_name, DnsInfo, DnsQuestion, DnsRecord, DnsResponseCode};
+pub use http::HttpInfo;
+pub use tls::{version_name as tls_version_name, TlsHandshakeType, TlsInfo};
+
+use serde::{Deserialize, Serialize};
+
+/// Application-layer details decoded from a packet payload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ApplicationData {
+    /// DNS message.
+    Dns(DnsInfo),
+    /// DHCP message.
+    Dhcp(DhcpInfo),
+    /// TLS record.
+    Tls(TlsInfo),
+    /// HTTP request or response head.
+    Http(HttpInfo),
+}
+
+impl std::fmt::Display for ApplicationData {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Dns(info) => write!(f, "{}", info),
+            Self::Dhcp(info) => write!(f, "{}", info),
+            Self::Tls(info) => write!(f, "{}", info),
+            Self::Http(info) => write!(f, "{}", info),
+        }
+    }
+}
+
+/// Dissect a UDP payload.
+pub(crate) fn dissect_udp(src_port: u16, dst_port: u16, payload: &[u8]) -> Option<ApplicationData> {
+    let either = |ports: &[u16]| ports.contains(&src_port) || ports.contains(&dst_port);
+
+    if either(&[53, 5353]) {
+        DnsInfo::parse(payload).map(ApplicationData::Dns)
+    } else if either(&[67, 68]) {
+        DhcpInfo::parse(payload).map(ApplicationData::Dhcp)
+    } else {
+        None
+    }
+}
+
+/// Dissect a TCP payload.
+pub(crate) fn dissect_tcp(src_port: u16, dst_port: u16, payload: &[u8]) -> Option<ApplicationData> {
+    if payload.is_empty() {
+        return None;
+    }
+
+    if src_port == 53 || dst_port == 53 {
+        return DnsInfo::parse_tcp(payload).map(ApplicationData::Dns);
+    }
+
+    // TLS and HTTP are recognised by content so non-standard ports still work.
+    // Application data records are skipped since segments rarely align with records.
+    if let Some(info) = TlsInfo::parse(payload).filter(|info| info.content_type != 23) {
+        return Some(ApplicationData::Tls(info));
+    }
+    HttpInfo::parse(payload).map(ApplicationData::Http)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    /// DNS response for `example.com A` with one compressed answer.
+    fn dns_response() -> Vec<u8> {
+        let mut msg = vec![
+            0x12, 0x34, 0x81, 0x80, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00,
+        ];
+        msg.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        msg.extend_from_slice(&[0xC0, 0x0C, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x0E, 0x10]);
+        msg.extend_from_slice(&[0x00, 0x04, 93, 184, 216, 34]);
+        msg
+    }
+
+    /// DHCPOFFER with router, DNS and lease time options.
+    fn dhcp_offer() -> Vec<u8> {
+        let mut msg = vec![0u8; 236];
+        msg[0] = 2;
+        msg[1] = 1;
+        msg[2] = 6;
+        msg[4..8].copy_from_slice(&0xdead_beef_u32.to_be_bytes());
+        msg[16..20].copy_from_slice(&[192, 168, 1, 50]);
+        msg[28..34].copy_from_slice(&[0x02, 0, 0, 0, 0, 0x01]);
+        msg.extend_from_slice(&[99, 130, 83, 99]);
+        msg.extend_from_slice(&[53, 1, 2]);
+        msg.extend_from_slice(&[3, 4, 192, 168, 1, 1]);
+        msg.extend_from_slice(&[6, 8, 1, 1, 1, 1, 8, 8, 8, 8]);
+        msg.extend_from_slice(&[51, 4, 0, 1, 0x51, 0x80]);
+        msg.push(255);
+        msg
+    }
+
+    /// TLS ClientHello with SNI, ALPN and supported_versions.
+    fn client_hello() -> Vec<u8> {
+        let mut extensions = Vec::new();
+        let sni = b"example.com";
+        extensions.extend_from_slice(&[0x00, 0x00, 0x00, (sni.len() + 5) as u8]);
+        extensions.extend_from_slice(&[0x00, (sni.len() + 3) as u8, 0x00, 0x00, sni.len() as u8]);
+        extensions.extend_from_slice(sni);
+        extensions.extend_from_slice(&[0x00, 0x10, 0x00, 0x0e, 0x00, 0x0c]);
+        extensions.extend_from_slice(b"\x02h2\x08http/1.1");
+        extensions.extend_from_slice(&[0x00, 0x2b, 0x00, 0x05, 0x04, 0x03, 0x04, 0x03, 0x03]);
+
+        let mut body = vec![0x03, 0x03];
+        body.extend_from_slice(&[0u8; 32]);
+        body.push(0);
+        body.extend_from_slice(&[0x00, 0x04, 0x13, 0x01, 0x13, 0x02, 0x01, 0x00]);
+        body.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        body.extend_from_slice(&extensions);
+
+        let mut handshake = vec![0x01, 0x00];
+        handshake.extend_from_slice(&(body.len() as u16).to_be_bytes());
+        handshake.extend_from_slice(&body);
+
+        let mut record = vec![0x16, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    #[test]
+    fn test_dissect_dns_response() {
+        let Some(ApplicationData::Dns(info)) = dissect_udp(53, 40000, &dns_response()) else {
+            panic!("expected DNS");
+        };
+        assert!(info.is_response);
+        assert_eq!(info.id, 0x1234);
+        assert_eq!(info.rcode, DnsResponseCode::NoError);
+        assert_eq!(info.query_name(), Some("example.com"));
+        assert_eq!(info.answers.len(), 1);
+        assert_eq!(info.answers[0].data, "93.184.216.34");
+        assert_eq!(info.answers[0].ttl, 3600);
+    }
+
+    #[test]
+    fn test_dissect_dhcp_offer() {
+        let Some(ApplicationData::Dhcp(info)) = dissect_udp(67, 68, &dhcp_offer()) else {
+            panic!("expected DHCP");
+        };
+        assert_eq!(info.message_type, Some(DhcpMessageType::Offer));
+        assert_eq!(info.your_ip, Ipv4Addr::new(192, 168, 1, 50));
+        assert_eq!(info.routers, vec![Ipv4Addr::new(192, 168, 1, 1)]);
+        assert_eq!(info.dns_servers.len(), 2);
+        assert_eq!(info.lease_time, Some(86400));
+        assert_eq!(info.client_mac, "02:00:00:00:00:01");
+    }
+
+    #[test]
+    fn test_dissect_tls_client_hello() {
+        let Some(ApplicationData::Tls(info)) = dissect_tcp(51000, 8443, &client_hello()) else {
+            panic!("expected TLS");
+        };
+        assert_eq!(info.handshake, Some(TlsHandshakeType::ClientHello));
+        assert_eq!(info.server_name.as_deref(), Some("example.com"));
+        assert_eq!(info.alpn, vec!["h2", "http/1.1"]);
+        assert_eq!(info.max_offered_version(), Some(0x0304));
+        assert_eq!(info.cipher_suites_offered, 2);
+    }
+
+    #[test]
+    fn test_dissect_http() {
+        let request = b"GET /index.html HTTP/1.1\r\nHost: example.com\r\nUser-Agent: test\r\n\r\n";
+        let Some(ApplicationData::Http(info)) = dissect_tcp(51000, 80, request) else {
+            panic!("expected HTTP");
+        };
+        assert_eq!(info.method.as_deref(), Some("GET"));
+        assert_eq!(info.host.as_deref(), Some("example.com"));
+        assert_eq!(info.to_string(), "GET /index.html Host=example.com");
+
+        let response = b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n";
+        let Some(ApplicationData::Http(info)) = dissect_tcp(80, 51000, response) else {
+            panic!("expected HTTP");
+        };
+        assert_eq!(info.status_code, Some(404));
+        assert_eq!(info.content_length, Some(0));
+    }
+
+    #[test]
+    fn test_dissect_rejects_garbage() {
+        assert!(dissect_udp(53, 40000, &[0x00, 0x01]).is_none());
+        assert!(dissect_tcp(40000, 40001, b"\x00\x01\x02\x03").is_none());
+    }
+}
@@ -0,0 +1,284 @@
+//! TLS handshake dissection.
+
+use serde::{Deserialize, Serialize};
+
+/// TLS record content type for handshake messages.
+const CONTENT_HANDSHAKE: u8 = 22;
+
+/// Decoded TLS record.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TlsInfo {
+    /// Record content type (20 = change cipher spec, 21 = alert, 22 = handshake, 23 = data).
+    pub content_type: u8,
+    /// Record layer protocol version.
+    pub record_version: u16,
+    /// Handshake message type, if this is a handshake record.
+    pub handshake: Option<TlsHandshakeType>,
+    /// Server name indication from a ClientHello.
+    pub server_name: Option<String>,
+    /// ALPN protocols (offered by the client or selected by the server).
+    pub alpn: Vec<String>,
+    /// Versions offered in the supported_versions extension of a ClientHello.
+    pub supported_versions: Vec<u16>,
+    /// Negotiated version from a ServerHello.
+    pub selected_version: Option<u16>,
+    /// Number of cipher suites offered by the client.
+    pub cipher_suites_offered: usize,
+    /// Cipher suite selected by the server.
+    pub cipher_suite: Option<u16>,
+    /// Alert level and description, if this is an alert record.
+    pub alert: Option<(u8, u8)>,
+}
+
+/// TLS handshake message type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TlsHandshakeType {
+    /// ClientHello.
+    ClientHello,
+    /// ServerHello.
+    ServerHello,
+    /// Certificate.
+    Certificate,
+    /// Other handshake message.
+    Other(u8),
+}
+
+impl TlsHandshakeType {
+    /// Create from the raw handshake type.
+    pub fn from_u8(value: u8) -> Self {
+        match value {
+            1 => Self::ClientHello,
+            2 => Self::ServerHello,
+            11 => Self::Certificate,
+            other => Self::Other(other),
+        }
+    }
+}
+
+impl std::fmt::Display for TlsHandshakeType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ClientHello => write!(f, "ClientHello"),
+            Self::ServerHello => write!(f, "ServerHello"),
+            Self::Certificate => write!(f, "Certificate"),
+            Self::Other(value) => write!(f, "Handshake({})", value),
+        }
+    }
+}
+
+/// Get a readable name for a TLS protocol version.
+pub fn version_name(version: u16) -> String {
+    match version {
+        0x0300 => "SSL3.0".to_string(),
+        0x0301 => "TLS1.0".to_string(),
+        0x0302 => "TLS1.1".to_string(),
+        0x0303 => "TLS1.2".to_string(),
+        0x0304 => "TLS1.3".to_string(),
+        other => format!("0x{:04x}", other),
+    }
+}
+
+/// Simple big-endian reader over a byte slice.
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        let value = *self.data.get(self.pos)?;
+        self.pos += 1;
+        Some(value)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        Some(u16::from_be_bytes([self.u8()?, self.u8()?]))
+    }
+
+    fn bytes(&mut self, len: usize) -> Option<&'a [u8]> {
+        let slice = self.data.get(self.pos..self.pos + len)?;
+        self.pos += len;
+        Some(slice)
+    }
+
+    fn skip(&mut self, len: usize) -> Option<()> {
+        self.bytes(len).map(|_| ())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.pos >= self.data.len()
+    }
+}
+
+impl TlsInfo {
+    /// Parse the first TLS record of a TCP payload.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        let mut reader = Reader::new(data);
+        let content_type = reader.u8()?;
+        let record_version = reader.u16()?;
+        let _record_len = reader.u16()?;
+
+        // Reject anything that does not look like a TLS record header
+        if !(20..=23).contains(&content_type) || record_version >> 8 != 0x03 {
+            return None;
+        }
+
+        let mut info = Self {
+            content_type,
+            record_version,
+            handshake: None,
+            server_name: None,
+            alpn: Vec::new(),
+            supported_versions: Vec::new(),
+            selected_version: None,
+            cipher_suites_offered: 0,
+            cipher_suite: None,
+            alert: None,
+        };
+
+        match content_type {
+            CONTENT_HANDSHAKE => {
+                let handshake_type = TlsHandshakeType::from_u8(reader.u8()?);
+                info.handshake = Some(handshake_type);
+                // Body may be cut short by the snap length; parse what is there
+                let len =
+                    u32::from_be_bytes([0, reader.u8()?, reader.u8()?, reader.u8()?]) as usize;
+                let remaining = &data[reader.pos..];
+                let body = &remaining[..len.min(remaining.len())];
+                match handshake_type {
+                    TlsHandshakeType::ClientHello => info.parse_client_hello(body),
+                    TlsHandshakeType::ServerHello => info.parse_server_hello(body),
+                    _ => None,
+                };
+            }
+            21 => {
+                info.alert = Some((reader.u8()?, reader.u8()?));
+            }
+            _ => {}
+        }
+
+        Some(info)
+    }
+
+    /// Parse a ClientHello body.
+    fn parse_client_hello(&mut self, body: &[u8]) -> Option<()> {
+        let mut reader = Reader::new(body);
+        reader.skip(2 + 32)?; // legacy_version + random
+        let session_id_len = usize::from(reader.u8()?);
+        reader.skip(session_id_len)?;
+        let suites_len = usize::from(reader.u16()?);
+        self.cipher_suites_offered = suites_len / 2;
+        reader.skip(suites_len)?;
+        let compression_len = usize::from(reader.u8()?);
+        reader.skip(compression_len)?;
+        self.parse_extensions(&mut reader, true)
+    }
+
+    /// Parse a ServerHello body.
+    fn parse_server_hello(&mut self, body: &[u8]) -> Option<()> {
+        let mut reader = Reader::new(body);
+        self.selected_version = Some(reader.u16()?);
+        reader.skip(32)?; // random
+        let session_id_len = usize::from(reader.u8()?);
+        reader.skip(session_id_len)?;
+        self.cipher_suite = Some(reader.u16()?);
+        reader.skip(1)?; // compression method
+        self.parse_extensions(&mut reader, false)
+    }
+
+    /// Parse the extensions block of a hello message.
+    fn parse_extensions(&mut self, reader: &mut Reader<'_>, client: bool) -> Option<()> {
+        if reader.is_empty() {
+            return Some(());
+        }
+        let extensions_len = usize::from(reader.u16()?);
+        let mut extensions = Reader::new(reader.bytes(extensions_len)?);
+
+        while !extensions.is_empty() {
+            let ext_type = extensions.u16()?;
+            let ext_len = usize::from(extensions.u16()?);
+            let mut ext = Reader::new(extensions.bytes(ext_len)?);
+
+            match ext_type {
+                // server_name
+                0 if client => {
+                    let _list_len = ext.u16()?;
+                    while !ext.is_empty() {
+                        let name_type = ext.u8()?;
+                        let name_len = usize::from(ext.u16()?);
+                        let name = ext.bytes(name_len)?;
+                        if name_type == 0 {
+                            self.server_name = Some(String::from_utf8_lossy(name).into_owned());
+                        }
+                    }
+                }
+                // application_layer_protocol_negotiation
+                16 => {
+                    let _list_len = ext.u16()?;
+                    while !ext.is_empty() {
+                        let proto_len = usize::from(ext.u8()?);
+                        let proto = ext.bytes(proto_len)?;
+                        self.alpn.push(String::from_utf8_lossy(proto).into_owned());
+                    }
+                }
+                // supported_versions
+                43 if client => {
+                    let list_len = usize::from(ext.u8()?);
+                    let mut versions = Reader::new(ext.bytes(list_len)?);
+                    while let Some(version) = versions.u16() {
+                        // Skip GREASE values
+                        if version & 0x0F0F != 0x0A0A {
+                            self.supported_versions.push(version);
+                        }
+                    }
+                }
+                43 => self.selected_version = Some(ext.u16()?),
+                _ => {}
+            }
+        }
+
+        Some(())
+    }
+
+    /// Get the highest version offered by the client.
+    pub fn max_offered_version(&self) -> Option<u16> {
+        self.supported_versions.iter().copied().max()
+    }
+}
+
+impl std::fmt::Display for TlsInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.content_type, self.handshake) {
+            (_, Some(handshake)) => write!(f, "{}", handshake)?,
+            (20, _) => write!(f, "ChangeCipherSpec")?,
+            (21, _) => write!(f, "Alert")?,
+            _ => write!(f, "Application Data")?,
+        }
+
+        let version = match self.handshake {
+            Some(TlsHandshakeType::ClientHello) => self.max_offered_version(),
+            Some(TlsHandshakeType::ServerHello) => self.selected_version,
+            _ => None,
+        }
+        .unwrap_or(self.record_version);
+        write!(f, " {}", version_name(version))?;
+
+        if let Some(ref sni) = self.server_name {
+            write!(f, " SNI={}", sni)?;
+        }
+        if !self.alpn.is_empty() {
+            write!(f, " ALPN={}", self.alpn.join(","))?;
+        }
+        if let Some(suite) = self.cipher_suite {
+            write!(f, " cipher=0x{:04x}", suite)?;
+        }
+        if let Some((level, description)) = self.alert {
+            write!(f, " level={} description={}", level, description)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,558 @@
+//! Passive DNS performance measurement from captured traffic.
+
+use crate::decode::DecodedPacket;
+use crate::dissect::{ApplicationData, DnsInfo, DnsResponseCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// Default time after which an unanswered query counts as timed out.
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default number of distinct query names tracked.
+const DEFAULT_MAX_NAMES: usize = 1000;
+
+/// Lower bound of the first response time bucket (ms).
+const HISTOGRAM_MIN_MS: f64 = 0.01;
+
+/// Ratio between consecutive response time bucket bounds (about 2.5% error).
+const HISTOGRAM_GROWTH: f64 = 1.05;
+
+/// Key matching a query to its response: transaction ID plus client and server endpoints.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct QueryKey {
+    id: u16,
+    client: SocketAddr,
+    server: SocketAddr,
+}
+
+/// A query waiting for its response.
+#[derive(Debug, Clone)]
+struct PendingQuery {
+    /// Time the query was first sent.
+    sent: DateTime<Utc>,
+    /// Queried name.
+    name: String,
+}
+
+/// Response time distribution in logarithmic buckets.
+///
+/// Uses a bounded amount of memory however many responses are recorded;
+/// percentiles are accurate to within a few percent, minimum, maximum and
+/// average are exact.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ResponseTimeHistogram {
+    count: u64,
+    sum_ms: f64,
+    min_ms: Option<f64>,
+    max_ms: Option<f64>,
+    buckets: BTreeMap<u16, u64>,
+}
+
+impl ResponseTimeHistogram {
+    /// Record a response time (ms).
+    pub fn record(&mut self, ms: f64) {
+        self.count += 1;
+        self.sum_ms += ms;
+        self.min_ms = Some(self.min_ms.map_or(ms, |min| min.min(ms)));
+        self.max_ms = Some(self.max_ms.map_or(ms, |max| max.max(ms)));
+        *self.buckets.entry(Self::bucket(ms)).or_default() += 1;
+    }
+
+    /// Add all response times from another histogram.
+    pub fn merge(&mut self, other: &Self) {
+        self.count += other.count;
+        self.sum_ms += other.sum_ms;
+        self.min_ms = [self.min_ms, other.min_ms].into_iter().flatten().reduce(f64::min);
+        self.max_ms = [self.max_ms, other.max_ms].into_iter().flatten().reduce(f64::max);
+        for (bucket, count) in &other.buckets {
+            *self.buckets.entry(*bucket).or_default() += count;
+        }
+    }
+
+    /// Number of recorded response times.
+    pub fn len(&self) -> u64 {
+        self.count
+    }
+
+    /// Check if no response times were recorded.
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Response time percentile (ms), e.g. `percentile(95.0)`.
+    pub fn percentile(&self, percentile: f64) -> Option<f64> {
+        let (min, max) = (self.min_ms?, self.max_ms?);
+        let rank = (percentile.clamp(0.0, 100.0) / 100.0 * (self.count - 1) as f64).round() as u64;
+        let mut seen = 0;
+        for (bucket, count) in &self.buckets {
+            seen += count;
+            if seen > rank {
+                return Some(Self::midpoint(*bucket).clamp(min, max));
+            }
+        }
+        Some(max)
+    }
+
+    fn bucket(ms: f64) -> u16 {
+        if ms <= HISTOGRAM_MIN_MS {
+            return 0;
+        }
+        ((ms / HISTOGRAM_MIN_MS).ln() / HISTOGRAM_GROWTH.ln()).ceil() as u16
+    }
+
+    /// Geometric midpoint of a bucket (ms).
+    fn midpoint(bucket: u16) -> f64 {
+        HISTOGRAM_MIN_MS * HISTOGRAM_GROWTH.powf(f64::from(bucket) - 0.5)
+    }
+}
+
+/// DNS response time and failure statistics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DnsLatencyStats {
+    /// Queries sent (excluding retransmissions).
+    pub queries: u64,
+    /// Responses matched to a query.
+    pub responses: u64,
+    /// Queries that went unanswered.
+    pub timeouts: u64,
+    /// Queries re-sent with the same transaction ID before a response arrived.
+    pub retransmissions: u64,
+    /// SERVFAIL responses.
+    pub servfail: u64,
+    /// NXDOMAIN responses.
+    pub nxdomain: u64,
+    /// Other error responses (REFUSED, FORMERR, ...).
+    pub other_errors: u64,
+    /// Response time distribution.
+    pub response_times: ResponseTimeHistogram,
+}
+
+impl DnsLatencyStats {
+    /// Record a response.
+    fn record_response(&mut self, rtt: Duration, rcode: DnsResponseCode) {
+        self.responses += 1;
+        self.response_times.record(rtt.as_secs_f64() * 1000.0);
+        match rcode {
+            DnsResponseCode::NoError => {}
+            DnsResponseCode::ServFail => self.servfail += 1,
+            DnsResponseCode::NxDomain => self.nxdomain += 1,
+            _ => self.other_errors += 1,
+        }
+    }
+
+    /// Minimum response time (ms).
+    pub fn min_ms(&self) -> Option<f64> {
+        self.response_times.min_ms
+    }
+
+    /// Maximum response time (ms).
+    pub fn max_ms(&self) -> Option<f64> {
+        self.response_times.max_ms
+    }
+
+    /// Average response time (ms).
+    pub fn avg_ms(&self) -> Option<f64> {
+        if self.response_times.is_empty() {
+            return None;
+        }
+        Some(self.response_times.sum_ms / self.response_times.count as f64)
+    }
+
+    /// Response time percentile (ms), e.g. `percentile_ms(95.0)`.
+    pub fn percentile_ms(&self, percentile: f64) -> Option<f64> {
+        self.response_times.percentile(percentile)
+    }
+
+    /// Percentage of queries that timed out or failed with SERVFAIL.
+    pub fn failure_rate(&self) -> f64 {
+        if self.queries == 0 {
+            return 0.0;
+        }
+        ((self.timeouts + self.servfail) as f64 / self.queries as f64) * 100.0
+    }
+}
+
+impl std::fmt::Display for DnsLatencyStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} queries", self.queries)?;
+        if let (Some(median), Some(p95)) = (self.percentile_ms(50.0), self.percentile_ms(95.0)) {
+            write!(f, ", median {:.1} ms, p95 {:.1} ms", median, p95)?;
+        }
+        if self.timeouts > 0 {
+            write!(f, ", {} timeouts", self.timeouts)?;
+        }
+        if self.retransmissions > 0 {
+            write!(f, ", {} retransmitted", self.retransmissions)?;
+        }
+        if self.servfail > 0 {
+            write!(f, ", {} SERVFAIL", self.servfail)?;
+        }
+        if self.nxdomain > 0 {
+            write!(f, ", {} NXDOMAIN", self.nxdomain)?;
+        }
+        Ok(())
+    }
+}
+
+/// Matches captured DNS queries and responses to measure real resolver performance.
+///
+/// Per-name statistics are kept for a bounded number of names; when the limit
+/// is reached the least queried name makes room for a new one.
+#[derive(Debug, Clone)]
+pub struct DnsTracker {
+    timeout: Duration,
+    max_names: usize,
+    pending: HashMap<QueryKey, PendingQuery>,
+    resolvers: HashMap<IpAddr, DnsLatencyStats>,
+    names: HashMap<String, DnsLatencyStats>,
+    evicted_names: u64,
+    unmatched_responses: u64,
+    last_seen: Option<DateTime<Utc>>,
+}
+
+impl DnsTracker {
+    /// Create a tracker with the default 5 second query timeout.
+    pub fn new() -> Self {
+        Self::with_timeout(DEFAULT_QUERY_TIMEOUT)
+    }
+
+    /// Create a tracker with a custom query timeout.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            timeout,
+            max_names: DEFAULT_MAX_NAMES,
+            pending: HashMap::new(),
+            resolvers: HashMap::new(),
+            names: HashMap::new(),
+            evicted_names: 0,
+            unmatched_responses: 0,
+            last_seen: None,
+        }
+    }
+
+    /// Set the maximum number of distinct names with per-name statistics.
+    pub fn with_max_names(mut self, max_names: usize) -> Self {
+        self.max_names = max_names.max(1);
+        self
+    }
+
+    /// Statistics for a name, making room for it if needed.
+    fn name_stats(&mut self, name: String) -> &mut DnsLatencyStats {
+        if !self.names.contains_key(&name) && self.names.len() >= self.max_names {
+            let least = self
+                .names
+                .iter()
+                .min_by_key(|(_, stats)| stats.queries)
+                .map(|(name, _)| name.clone());
+            if let Some(least) = least {
+                self.names.remove(&least);
+                self.evicted_names += 1;
+            }
+        }
+        self.names.entry(name).or_default()
+    }
+
+    /// Update with a decoded packet. Non-DNS packets are ignored.
+    pub fn update(&mut self, packet: &DecodedPacket) {
+        let Some(ApplicationData::Dns(ref dns)) = packet.application else {
+            return;
+        };
+        let (Some(src_ip), Some(src_port), Some(dst_ip), Some(dst_port)) = (
+            packet.src_ip,
+            packet.src_port,
+            packet.dst_ip,
+            packet.dst_port,
+        ) else {
+            return;
+        };
+        let src = SocketAddr::new(src_ip, src_port);
+        let dst = SocketAddr::new(dst_ip, dst_port);
+
+        self.last_seen = Some(packet.timestamp);
+        self.expire(packet.timestamp);
+
+        if dns.is_response {
+            self.record_response(dns, dst, src, packet.timestamp);
+        } else {
+            self.record_query(dns, src, dst, packet.timestamp);
+        }
+    }
+
+    /// Record a query sent from `client` to `server`.
+    fn record_query(
+        &mut self,
+        dns: &DnsInfo,
+        client: SocketAddr,
+        server: SocketAddr,
+        timestamp: DateTime<Utc>,
+    ) {
+        let key = QueryKey {
+            id: dns.id,
+            client,
+            server,
+        };
+        let name = dns.query_name().unwrap_or(".").to_ascii_lowercase();
+
+        if self.pending.contains_key(&key) {
+            self.resolvers
+                .entry(server.ip())
+                .or_default()
+                .retransmissions += 1;
+            self.name_stats(name).retransmissions += 1;
+            return;
+        }
+
+        self.resolvers.entry(server.ip()).or_default().queries += 1;
+        self.name_stats(name.clone()).queries += 1;
+        self.pending.insert(
+            key,
+            PendingQuery {
+                sent: timestamp,
+                name,
+            },
+        );
+    }
+
+    /// Record a response sent from `server` to `client`.
+    fn record_response(
+        &mut self,
+        dns: &DnsInfo,
+        client: SocketAddr,
+        server: SocketAddr,
+        timestamp: DateTime<Utc>,
+    ) {
+        let key = QueryKey {
+            id: dns.id,
+            client,
+            server,
+        };
+        let Some(query) = self.pending.remove(&key) else {
+            self.unmatched_responses += 1;
+            return;
+        };
+
+        let rtt = (timestamp - query.sent).to_std().unwrap_or_default();
+        self.resolvers
+            .entry(server.ip())
+            .or_default()
+            .record_response(rtt, dns.rcode);
+        self.name_stats(query.name).record_response(rtt, dns.rcode);
+    }
+
+    /// Count queries older than the timeout as timed out.
+    fn expire(&mut self, now: DateTime<Utc>) {
+        let timeout = self.timeout;
+        let expired: Vec<QueryKey> = self
+            .pending
+            .iter()
+            .filter(|(_, q)| (now - q.sent).to_std().is_ok_and(|age| age >= timeout))
+            .map(|(key, _)| *key)
+            .collect();
+
+        for key in expired {
+            if let Some(query) = self.pending.remove(&key) {
+                self.resolvers.entry(key.server.ip()).or_default().timeouts += 1;
+                self.name_stats(query.name).timeouts += 1;
+            }
+        }
+    }
+
+    /// Finish tracking at the end of a capture.
+    ///
+    /// Queries still pending longer than the timeout before `end` count as timeouts;
+    /// newer ones are dropped since their responses may simply not have been captured.
+    pub fn finish(&mut self, end: DateTime<Utc>) {
+        self.expire(end);
+        self.pending.clear();
+    }
+
+    /// Finish tracking at the time of the last DNS packet seen.
+    pub fn finish_at_last_packet(&mut self) {
+        if let Some(last) = self.last_seen {
+            self.finish(last);
+        }
+    }
+
+    /// Per-resolver statistics, busiest first.
+    pub fn resolvers(&self) -> Vec<(IpAddr, &DnsLatencyStats)> {
+        let mut resolvers: Vec<_> = self.resolvers.iter().map(|(ip, s)| (*ip, s)).collect();
+        resolvers.sort_by_key(|(_, s)| std::cmp::Reverse(s.queries));
+        resolvers
+    }
+
+    /// Per-name statistics for the N most queried names.
+    pub fn top_names(&self, n: usize) -> Vec<(&str, &DnsLatencyStats)> {
+        let mut names: Vec<_> = self.names.iter().map(|(n, s)| (n.as_str(), s)).collect();
+        names.sort_by_key(|(_, s)| std::cmp::Reverse(s.queries));
+        names.truncate(n);
+        names
+    }
+
+    /// Per-name statistics for the N slowest names by median response time.
+    pub fn slowest_names(&self, n: usize) -> Vec<(&str, &DnsLatencyStats)> {
+        let mut names: Vec<_> = self
+            .names
+            .iter()
+            .filter_map(|(name, s)| s.percentile_ms(50.0).map(|m| (name.as_str(), s, m)))
+            .collect();
+        names.sort_by(|a, b| b.2.total_cmp(&a.2));
+        names
+            .into_iter()
+            .take(n)
+            .map(|(name, s, _)| (name, s))
+            .collect()
+    }
+
+    /// Statistics across all resolvers.
+    pub fn overall(&self) -> DnsLatencyStats {
+        let mut total = DnsLatencyStats::default();
+        for stats in self.resolvers.values() {
+            total.queries += stats.queries;
+            total.responses += stats.responses;
+            total.timeouts += stats.timeouts;
+            total.retransmissions += stats.retransmissions;
+            total.servfail += stats.servfail;
+            total.nxdomain += stats.nxdomain;
+            total.other_errors += stats.other_errors;
+            total.response_times.merge(&stats.response_times);
+        }
+        total
+    }
+
+    /// Names whose statistics were dropped to stay within the name limit.
+    pub fn evicted_names(&self) -> u64 {
+        self.evicted_names
+    }
+
+    /// Responses that did not match any captured query.
+    pub fn unmatched_responses(&self) -> u64 {
+        self.unmatched_responses
+    }
+
+    /// Check if any DNS queries were seen.
+    pub fn is_empty(&self) -> bool {
+        self.resolvers.is_empty()
+    }
+}
+
+impl Default for DnsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ProtocolDecoder;
+    use chrono::TimeZone;
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [192, 168, 1, 10];
+    const RESOLVER: [u8; 4] = [192, 168, 1, 1];
+
+    /// Build a DNS query or response for `name` and decode it at `ms` into the capture.
+    fn dns_packet(ms: i64, id: u16, response: bool, rcode: u8, name: &str) -> DecodedPacket {
+        let flags: u16 = if response {
+            0x8180 | u16::from(rcode)
+        } else {
+            0x0100
+        };
+        let mut msg = id.to_be_bytes().to_vec();
+        msg.extend_from_slice(&flags.to_be_bytes());
+        msg.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+        for label in name.split('.') {
+            msg.push(label.len() as u8);
+            msg.extend_from_slice(label.as_bytes());
+        }
+        msg.extend_from_slice(&[0, 0, 1, 0, 1]);
+
+        let (src, dst, sport, dport) = if response {
+            (RESOLVER, CLIENT, 53, 40000)
+        } else {
+            (CLIENT, RESOLVER, 40000, 53)
+        };
+        let builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4(src, dst, 64)
+            .udp(sport, dport);
+        let mut frame = Vec::new();
+        builder.write(&mut frame, &msg).unwrap();
+
+        let ts = Utc.timestamp_opt(1_700_000_000, 0).unwrap() + chrono::Duration::milliseconds(ms);
+        ProtocolDecoder::new().decode(&frame, ts)
+    }
+
+    #[test]
+    fn test_dns_tracker_latency_and_errors() {
+        let mut tracker = DnsTracker::new();
+        for packet in [
+            dns_packet(0, 1, false, 0, "example.com"),
+            dns_packet(20, 1, true, 0, "example.com"),
+            dns_packet(100, 2, false, 0, "missing.example"),
+            dns_packet(140, 2, true, 3, "missing.example"),
+            dns_packet(200, 3, false, 0, "broken.example"),
+            dns_packet(1200, 3, false, 0, "broken.example"),
+            dns_packet(1500, 3, true, 2, "broken.example"),
+        ] {
+            tracker.update(&packet);
+        }
+        tracker.finish_at_last_packet();
+
+        let resolvers = tracker.resolvers();
+        assert_eq!(resolvers.len(), 1);
+        let (ip, stats) = resolvers[0];
+        assert_eq!(ip.to_string(), "192.168.1.1");
+        assert_eq!(stats.queries, 3);
+        assert_eq!(stats.responses, 3);
+        assert_eq!(stats.retransmissions, 1);
+        assert_eq!(stats.nxdomain, 1);
+        assert_eq!(stats.servfail, 1);
+        assert_eq!(stats.min_ms(), Some(20.0));
+        assert_eq!(stats.max_ms(), Some(1300.0));
+        let median = stats.percentile_ms(50.0).unwrap();
+        assert!((median - 40.0).abs() < 40.0 * 0.05, "median {median}");
+        assert_eq!(stats.percentile_ms(100.0), Some(1300.0));
+        assert_eq!(stats.avg_ms(), Some(1360.0 / 3.0));
+
+        let slowest = tracker.slowest_names(1);
+        assert_eq!(slowest[0].0, "broken.example");
+    }
+
+    #[test]
+    fn test_dns_tracker_timeouts() {
+        let mut tracker = DnsTracker::with_timeout(Duration::from_secs(2));
+        tracker.update(&dns_packet(0, 7, false, 0, "slow.example"));
+        tracker.update(&dns_packet(100, 8, false, 0, "late.example"));
+        tracker.update(&dns_packet(3000, 9, false, 0, "other.example"));
+        tracker.finish_at_last_packet();
+
+        let overall = tracker.overall();
+        assert_eq!(overall.queries, 3);
+        assert_eq!(overall.timeouts, 2);
+        assert_eq!(overall.responses, 0);
+    }
+
+    #[test]
+    fn test_dns_tracker_bounds_names() {
+        let mut tracker = DnsTracker::new().with_max_names(2);
+        tracker.update(&dns_packet(0, 1, false, 0, "popular.example"));
+        tracker.update(&dns_packet(10, 1, true, 0, "popular.example"));
+        tracker.update(&dns_packet(20, 2, false, 0, "popular.example"));
+        tracker.update(&dns_packet(30, 2, true, 0, "popular.example"));
+        for id in 3..50 {
+            let name = format!("r{id}.example");
+            tracker.update(&dns_packet(i64::from(id) * 10, id, false, 0, &name));
+            tracker.update(&dns_packet(i64::from(id) * 10 + 5, id, true, 0, &name));
+        }
+
+        let top = tracker.top_names(5);
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].0, "popular.example");
+        assert_eq!(top[0].1.queries, 2);
+        assert_eq!(tracker.evicted_names(), 46);
+        assert_eq!(tracker.overall().responses, 49);
+    }
+}
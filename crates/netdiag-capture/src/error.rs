@@ -33,6 +33,22 @@ pub enum CaptureError {
     #[error("pcap error: {0}")]
     PcapError(String),
 
+    /// Capture file could not be opened or read.
+    #[error("invalid capture file: {0}")]
+    InvalidFile(String),
+
+    /// Anonymisation policy could not be parsed.
+    #[error("invalid anonymisation policy: {0}")]
+    InvalidPolicy(String),
+
+    /// Link type the decoder and anonymiser do not understand.
+    #[error("unsupported link type {0}")]
+    UnsupportedLinkType(String),
+
+    /// Flow export target could not be parsed or opened.
+    #[error("invalid flow export target: {0}")]
+    InvalidExportTarget(String),
+
     /// Decode error.
     #[error("decode error: {0}")]
     DecodeError(String),
@@ -0,0 +1,882 @@
+//! Flow record export (IPFIX, NetFlow v9, JSON lines).
+
+use crate::decode::DecodedPacket;
+use crate::error::{CaptureError, CaptureResult};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::{BufWriter, Write};
+use std::net::{IpAddr, SocketAddr, ToSocketAddrs, UdpSocket};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Default IPFIX collector port.
+const IPFIX_PORT: u16 = 4739;
+
+/// Default NetFlow collector port.
+const NETFLOW_PORT: u16 = 2055;
+
+/// Largest export message, kept below a typical path MTU.
+const MAX_MESSAGE: usize = 1400;
+
+/// Template ID for IPv4 flow records.
+const TEMPLATE_IPV4: u16 = 256;
+
+/// Template ID for IPv6 flow records.
+const TEMPLATE_IPV6: u16 = 257;
+
+/// Where and how flow records are exported.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FlowExportTarget {
+    /// IPFIX (RFC 7011) over UDP.
+    Ipfix(SocketAddr),
+    /// NetFlow v9 (RFC 3954) over UDP.
+    NetflowV9(SocketAddr),
+    /// Newline-delimited JSON appended to a file (`-` for stdout).
+    JsonLines(PathBuf),
+}
+
+impl FlowExportTarget {
+    /// Parse a target such as `ipfix://collector:4739`, `netflow9://10.0.0.1`
+    /// or a file path for JSON lines.
+    pub fn parse(target: &str) -> CaptureResult<Self> {
+        let target = target.trim();
+        if target.is_empty() {
+            return Err(CaptureError::InvalidExportTarget(
+                "empty target".to_string(),
+            ));
+        }
+
+        if let Some(address) = target.strip_prefix("ipfix://") {
+            return resolve(address, IPFIX_PORT).map(Self::Ipfix);
+        }
+        for scheme in ["netflow9://", "netflow://", "nfv9://"] {
+            if let Some(address) = target.strip_prefix(scheme) {
+                return resolve(address, NETFLOW_PORT).map(Self::NetflowV9);
+            }
+        }
+        if let Some(path) = target.strip_prefix("json://") {
+            return Ok(Self::JsonLines(PathBuf::from(path)));
+        }
+        if target.contains("://") {
+            return Err(CaptureError::InvalidExportTarget(format!(
+                "unknown scheme in '{}' (expected ipfix://, netflow9:// or a file path)",
+                target
+            )));
+        }
+        Ok(Self::JsonLines(PathBuf::from(target)))
+    }
+}
+
+impl std::fmt::Display for FlowExportTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Ipfix(addr) => write!(f, "ipfix://{}", addr),
+            Self::NetflowV9(addr) => write!(f, "netflow9://{}", addr),
+            Self::JsonLines(path) => write!(f, "{}", path.display()),
+        }
+    }
+}
+
+/// Resolve `host[:port]` to a collector address.
+fn resolve(address: &str, default_port: u16) -> CaptureResult<SocketAddr> {
+    let address = address.trim_end_matches('/');
+    let with_port = if address.parse::<SocketAddr>().is_ok()
+        || (address
+            .rsplit_once(':')
+            .is_some_and(|(host, port)| !host.contains(':') && port.parse::<u16>().is_ok()))
+    {
+        address.to_string()
+    } else if address.contains(':') && !address.starts_with('[') {
+        // Bare IPv6 address
+        format!("[{}]:{}", address, default_port)
+    } else {
+        format!("{}:{}", address, default_port)
+    };
+
+    with_port
+        .to_socket_addrs()
+        .map_err(|e| CaptureError::InvalidExportTarget(format!("{}: {}", address, e)))?
+        .next()
+        .ok_or_else(|| CaptureError::InvalidExportTarget(format!("{}: no address", address)))
+}
+
+/// Flow export settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowExportConfig {
+    /// Export destination and format.
+    pub target: FlowExportTarget,
+
+    /// Export long-lived flows after this long, even while active.
+    pub active_timeout: Duration,
+
+    /// Export flows after this long without packets.
+    pub inactive_timeout: Duration,
+
+    /// Observation domain (IPFIX) or source ID (NetFlow v9).
+    pub observation_domain: u32,
+
+    /// Resend templates every this many messages.
+    pub template_refresh: u32,
+}
+
+impl FlowExportConfig {
+    /// Create a config with 60 second active and 15 second inactive timeouts.
+    pub fn new(target: FlowExportTarget) -> Self {
+        Self {
+            target,
+            active_timeout: Duration::from_secs(60),
+            inactive_timeout: Duration::from_secs(15),
+            observation_domain: 0,
+            template_refresh: 20,
+        }
+    }
+
+    /// Set the active timeout.
+    pub fn active_timeout(mut self, timeout: Duration) -> Self {
+        self.active_timeout = timeout;
+        self
+    }
+
+    /// Set the inactive timeout.
+    pub fn inactive_timeout(mut self, timeout: Duration) -> Self {
+        self.inactive_timeout = timeout;
+        self
+    }
+
+    /// Set the observation domain / source ID.
+    pub fn observation_domain(mut self, domain: u32) -> Self {
+        self.observation_domain = domain;
+        self
+    }
+}
+
+/// Why a flow record was exported (IPFIX `flowEndReason` values).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowEndReason {
+    /// No packets within the inactive timeout.
+    IdleTimeout = 1,
+    /// Flow was active for longer than the active timeout.
+    ActiveTimeout = 2,
+    /// TCP FIN or RST seen.
+    EndOfFlow = 3,
+    /// Exporter shut down.
+    ForcedEnd = 4,
+}
+
+/// A unidirectional flow record.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlowRecord {
+    /// Source address.
+    pub src_ip: IpAddr,
+    /// Destination address.
+    pub dst_ip: IpAddr,
+    /// Source port (0 for protocols without ports).
+    pub src_port: u16,
+    /// Destination port (ICMP type and code for ICMP).
+    pub dst_port: u16,
+    /// IP protocol number.
+    pub protocol: u8,
+    /// Packets in the flow.
+    pub packets: u64,
+    /// Bytes in the flow (captured frame lengths).
+    pub bytes: u64,
+    /// Union of TCP flags seen.
+    pub tcp_flags: u8,
+    /// Outermost VLAN ID.
+    pub vlan: Option<u16>,
+    /// First packet time.
+    pub first_seen: DateTime<Utc>,
+    /// Last packet time.
+    pub last_seen: DateTime<Utc>,
+    /// Why the record was exported.
+    pub end_reason: FlowEndReason,
+}
+
+impl FlowRecord {
+    /// Aggregation key of the record.
+    fn key(&self) -> RecordKey {
+        (
+            self.src_ip,
+            self.dst_ip,
+            self.src_port,
+            self.dst_port,
+            self.protocol,
+        )
+    }
+}
+
+/// Source, destination, ports and protocol.
+type RecordKey = (IpAddr, IpAddr, u16, u16, u8);
+
+/// Destination for encoded records.
+enum Sink {
+    Udp {
+        socket: UdpSocket,
+        collector: SocketAddr,
+    },
+    Json(Box<dyn Write + Send>),
+}
+
+/// Aggregates packets into unidirectional flows and exports them.
+///
+/// Timeouts are measured in packet time, so files export the same records
+/// as the live capture they came from; call [`expire`](Self::expire) with the
+/// wall clock during quiet periods of a live capture.
+pub struct FlowExporter {
+    config: FlowExportConfig,
+    sink: Sink,
+    flows: HashMap<RecordKey, FlowRecord>,
+    boot: Option<DateTime<Utc>>,
+    clock: Option<DateTime<Utc>>,
+    last_sweep: Option<DateTime<Utc>>,
+    sequence: u32,
+    messages_sent: u64,
+    records_exported: u64,
+}
+
+impl FlowExporter {
+    /// Create an exporter, opening its socket or output file.
+    ///
+    /// JSON lines are appended, so restarting an exporter keeps the records
+    /// it wrote before.
+    pub fn new(config: FlowExportConfig) -> CaptureResult<Self> {
+        let sink = match &config.target {
+            FlowExportTarget::Ipfix(collector) | FlowExportTarget::NetflowV9(collector) => {
+                let bind: SocketAddr = if collector.is_ipv4() {
+                    "0.0.0.0:0".parse().expect("valid address")
+                } else {
+                    "[::]:0".parse().expect("valid address")
+                };
+                Sink::Udp {
+                    socket: UdpSocket::bind(bind)?,
+                    collector: *collector,
+                }
+            }
+            FlowExportTarget::JsonLines(path) if path.as_os_str() == "-" => {
+                Sink::Json(Box::new(std::io::stdout()))
+            }
+            FlowExportTarget::JsonLines(path) => {
+                let file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .map_err(|e| {
+                        CaptureError::InvalidExportTarget(format!("{}: {}", path.display(), e))
+                    })?;
+                Sink::Json(Box::new(BufWriter::new(file)))
+            }
+        };
+
+        Ok(Self {
+            config,
+            sink,
+            flows: HashMap::new(),
+            boot: None,
+            clock: None,
+            last_sweep: None,
+            sequence: 0,
+            messages_sent: 0,
+            records_exported: 0,
+        })
+    }
+
+    /// Export configuration.
+    pub fn config(&self) -> &FlowExportConfig {
+        &self.config
+    }
+
+    /// Flows currently being aggregated.
+    pub fn active_flows(&self) -> usize {
+        self.flows.len()
+    }
+
+    /// Flow records exported so far.
+    pub fn records_exported(&self) -> u64 {
+        self.records_exported
+    }
+
+    /// IPFIX/NetFlow messages sent so far (0 for JSON lines).
+    pub fn messages_sent(&self) -> u64 {
+        self.messages_sent
+    }
+
+    /// Add a packet to its flow, exporting any flows that have timed out.
+    pub fn observe(&mut self, packet: &DecodedPacket) -> CaptureResult<()> {
+        self.expire(packet.timestamp)?;
+
+        let (Some(src_ip), Some(dst_ip), Some(protocol)) =
+            (packet.src_ip, packet.dst_ip, packet.ip_protocol)
+        else {
+            return Ok(());
+        };
+        let (src_port, dst_port) = match (packet.src_port, packet.dst_port) {
+            (Some(src), Some(dst)) => (src, dst),
+            _ => (
+                0,
+                packet
+                    .icmp_type
+                    .map(|t| u16::from(t) << 8 | u16::from(packet.icmp_code.unwrap_or(0)))
+                    .unwrap_or(0),
+            ),
+        };
+        let tcp_flags = packet.tcp_flags.map(|f| f.to_byte()).unwrap_or(0);
+
+        let key = (src_ip, dst_ip, src_port, dst_port, protocol);
+        let record = self.flows.entry(key).or_insert_with(|| FlowRecord {
+            src_ip,
+            dst_ip,
+            src_port,
+            dst_port,
+            protocol,
+            packets: 0,
+            bytes: 0,
+            tcp_flags: 0,
+            vlan: packet.vlans.first().copied(),
+            first_seen: packet.timestamp,
+            last_seen: packet.timestamp,
+            end_reason: FlowEndReason::IdleTimeout,
+        });
+        record.packets += 1;
+        record.bytes += packet.length as u64;
+        record.tcp_flags |= tcp_flags;
+        record.last_seen = record.last_seen.max(packet.timestamp);
+
+        // FIN or RST ends the flow
+        if tcp_flags & 0x05 != 0 {
+            if let Some(mut record) = self.flows.remove(&key) {
+                record.end_reason = FlowEndReason::EndOfFlow;
+                self.export(vec![record])?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Export flows that have exceeded the active or inactive timeout at `now`.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> CaptureResult<()> {
+        self.boot.get_or_insert(now);
+        if self.clock.map_or(true, |clock| now > clock) {
+            self.clock = Some(now);
+        }
+
+        // Sweep at most once per second
+        if self
+            .last_sweep
+            .is_some_and(|last| (now - last).num_milliseconds() < 1000)
+        {
+            return Ok(());
+        }
+        self.last_sweep = Some(now);
+
+        let active =
+            chrono::Duration::from_std(self.config.active_timeout).unwrap_or(chrono::Duration::MAX);
+        let inactive = chrono::Duration::from_std(self.config.inactive_timeout)
+            .unwrap_or(chrono::Duration::MAX);
+
+        let mut expired = Vec::new();
+        self.flows.retain(|_, record| {
+            let reason = if now - record.last_seen >= inactive {
+                FlowEndReason::IdleTimeout
+            } else if now - record.first_seen >= active {
+                FlowEndReason::ActiveTimeout
+            } else {
+                return true;
+            };
+            let mut record = record.clone();
+            record.end_reason = reason;
+            expired.push(record);
+            false
+        });
+
+        expired.sort_by_key(|record| record.first_seen);
+        self.export(expired)
+    }
+
+    /// Export every flow still being aggregated, e.g. when the capture ends.
+    pub fn flush(&mut self) -> CaptureResult<()> {
+        let mut records: Vec<_> = self
+            .flows
+            .drain()
+            .map(|(_, mut record)| {
+                record.end_reason = FlowEndReason::ForcedEnd;
+                record
+            })
+            .collect();
+        records.sort_by_key(|record| (record.first_seen, record.key()));
+        self.export(records)?;
+
+        if let Sink::Json(writer) = &mut self.sink {
+            writer.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Encode and send records.
+    fn export(&mut self, records: Vec<FlowRecord>) -> CaptureResult<()> {
+        if records.is_empty() {
+            return Ok(());
+        }
+
+        match &self.config.target {
+            FlowExportTarget::JsonLines(_) => {
+                if let Sink::Json(writer) = &mut self.sink {
+                    for record in &records {
+                        serde_json::to_writer(&mut *writer, record)
+                            .map_err(|e| CaptureError::Io(e.into()))?;
+                        writer.write_all(b"\n")?;
+                    }
+                }
+            }
+            FlowExportTarget::Ipfix(_) | FlowExportTarget::NetflowV9(_) => {
+                let ipfix = matches!(self.config.target, FlowExportTarget::Ipfix(_));
+                let (v4, v6): (Vec<_>, Vec<_>) =
+                    records.iter().partition(|record| record.src_ip.is_ipv4());
+                let mut message = self.start_message(ipfix);
+                for (template, group) in [(TEMPLATE_IPV4, v4), (TEMPLATE_IPV6, v6)] {
+                    for record in group {
+                        let encoded = encode_record(record, ipfix, self.boot);
+                        if message.would_overflow(encoded.len()) {
+                            self.send(message)?;
+                            message = self.start_message(ipfix);
+                        }
+                        message.add_record(template, &encoded);
+                    }
+                }
+                self.send(message)?;
+            }
+        }
+
+        self.records_exported += records.len() as u64;
+        Ok(())
+    }
+
+    /// Begin a message, including templates when they are due.
+    fn start_message(&self, ipfix: bool) -> Message {
+        let mut message = Message::new(ipfix);
+        let refresh = u64::from(self.config.template_refresh.max(1));
+        if self.messages_sent % refresh == 0 {
+            message.add_templates();
+        }
+        message
+    }
+
+    /// Finish a message and send it to the collector.
+    fn send(&mut self, message: Message) -> CaptureResult<()> {
+        let clock = self.clock.unwrap_or_else(Utc::now);
+        let uptime = self
+            .boot
+            .map(|boot| (clock - boot).num_milliseconds().max(0) as u32)
+            .unwrap_or(0);
+        let data_records = message.data_records;
+        // IPFIX counts data records sent; NetFlow v9 counts messages
+        let sequence = if message.ipfix {
+            self.sequence
+        } else {
+            self.messages_sent as u32
+        };
+        let bytes = message.finish(
+            clock.timestamp() as u32,
+            uptime,
+            sequence,
+            self.config.observation_domain,
+        );
+
+        if let Sink::Udp { socket, collector } = &self.sink {
+            socket.send_to(&bytes, collector)?;
+        }
+        self.sequence = self.sequence.wrapping_add(data_records);
+        self.messages_sent += 1;
+        Ok(())
+    }
+}
+
+impl std::fmt::Debug for FlowExporter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FlowExporter")
+            .field("target", &self.config.target)
+            .field("active_flows", &self.flows.len())
+            .field("records_exported", &self.records_exported)
+            .finish()
+    }
+}
+
+/// Template fields as (information element, length).
+fn template_fields(ipfix: bool, ipv6: bool) -> Vec<(u16, u16)> {
+    let mut fields = if ipv6 {
+        vec![(27, 16), (28, 16)]
+    } else {
+        vec![(8, 4), (12, 4)]
+    };
+    // Ports, protocol, TCP flags, packets, bytes
+    fields.extend([(7, 2), (11, 2), (4, 1), (6, 1), (2, 8), (1, 8)]);
+    if ipfix {
+        // flowStartMilliseconds, flowEndMilliseconds, vlanId, flowEndReason
+        fields.extend([(152, 8), (153, 8), (58, 2), (136, 1)]);
+    } else {
+        // FIRST_SWITCHED, LAST_SWITCHED, SRC_VLAN
+        fields.extend([(22, 4), (21, 4), (58, 2)]);
+    }
+    fields
+}
+
+/// Encode a record in template field order.
+fn encode_record(record: &FlowRecord, ipfix: bool, boot: Option<DateTime<Utc>>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(64);
+    for address in [record.src_ip, record.dst_ip] {
+        match address {
+            IpAddr::V4(v4) => out.extend_from_slice(&v4.octets()),
+            IpAddr::V6(v6) => out.extend_from_slice(&v6.octets()),
+        }
+    }
+    out.extend_from_slice(&record.src_port.to_be_bytes());
+    out.extend_from_slice(&record.dst_port.to_be_bytes());
+    out.push(record.protocol);
+    out.push(record.tcp_flags);
+    out.extend_from_slice(&record.packets.to_be_bytes());
+    out.extend_from_slice(&record.bytes.to_be_bytes());
+    if ipfix {
+        for time in [record.first_seen, record.last_seen] {
+            out.extend_from_slice(&(time.timestamp_millis().max(0) as u64).to_be_bytes());
+        }
+        out.extend_from_slice(&record.vlan.unwrap_or(0).to_be_bytes());
+        out.push(record.end_reason as u8);
+    } else {
+        let boot = boot.unwrap_or(record.first_seen);
+        for time in [record.first_seen, record.last_seen] {
+            let uptime = (time - boot).num_milliseconds().max(0) as u32;
+            out.extend_from_slice(&uptime.to_be_bytes());
+        }
+        out.extend_from_slice(&record.vlan.unwrap_or(0).to_be_bytes());
+    }
+    out
+}
+
+/// An IPFIX or NetFlow v9 message being assembled.
+struct Message {
+    ipfix: bool,
+    buf: Vec<u8>,
+    /// Start and template ID of the open set.
+    set: Option<(usize, u16)>,
+    /// Template and data records (NetFlow v9 header count).
+    records: u16,
+    /// Data records only (IPFIX sequence number).
+    data_records: u32,
+}
+
+impl Message {
+    /// Header length: 16 bytes for IPFIX, 20 for NetFlow v9.
+    fn header_len(ipfix: bool) -> usize {
+        if ipfix {
+            16
+        } else {
+            20
+        }
+    }
+
+    fn new(ipfix: bool) -> Self {
+        Self {
+            ipfix,
+            buf: vec![0; Self::header_len(ipfix)],
+            set: None,
+            records: 0,
+            data_records: 0,
+        }
+    }
+
+    /// Add a template set describing both record layouts.
+    fn add_templates(&mut self) {
+        let start = self.buf.len();
+        // Template set ID: 2 for IPFIX, 0 for NetFlow v9
+        let set_id: u16 = if self.ipfix { 2 } else { 0 };
+        self.buf.extend_from_slice(&set_id.to_be_bytes());
+        self.buf.extend_from_slice(&[0, 0]);
+        for (template, ipv6) in [(TEMPLATE_IPV4, false), (TEMPLATE_IPV6, true)] {
+            let fields = template_fields(self.ipfix, ipv6);
+            self.buf.extend_from_slice(&template.to_be_bytes());
+            self.buf
+                .extend_from_slice(&(fields.len() as u16).to_be_bytes());
+            for (id, len) in fields {
+                self.buf.extend_from_slice(&id.to_be_bytes());
+                self.buf.extend_from_slice(&len.to_be_bytes());
+            }
+            self.records += 1;
+        }
+        let len = (self.buf.len() - start) as u16;
+        self.buf[start + 2..start + 4].copy_from_slice(&len.to_be_bytes());
+    }
+
+    /// Whether adding a record of `len` bytes would exceed the message size.
+    fn would_overflow(&self, len: usize) -> bool {
+        // Room for a new set header and padding
+        self.records > 0 && self.buf.len() + len + 4 + 3 > MAX_MESSAGE
+    }
+
+    /// Append a data record, opening a data set for its template if needed.
+    fn add_record(&mut self, template: u16, record: &[u8]) {
+        if self.set.map(|(_, id)| id) != Some(template) {
+            self.close_set();
+            self.set = Some((self.buf.len(), template));
+            self.buf.extend_from_slice(&template.to_be_bytes());
+            self.buf.extend_from_slice(&[0, 0]);
+        }
+        self.buf.extend_from_slice(record);
+        self.records += 1;
+        self.data_records += 1;
+    }
+
+    /// Close the open data set, padding NetFlow v9 flowsets to 32 bits.
+    fn close_set(&mut self) {
+        if let Some((start, _)) = self.set.take() {
+            if !self.ipfix {
+                while (self.buf.len() - start) % 4 != 0 {
+                    self.buf.push(0);
+                }
+            }
+            let len = (self.buf.len() - start) as u16;
+            self.buf[start + 2..start + 4].copy_from_slice(&len.to_be_bytes());
+        }
+    }
+
+    /// Write the header and return the encoded message.
+    fn finish(mut self, export_secs: u32, uptime_ms: u32, sequence: u32, domain: u32) -> Vec<u8> {
+        self.close_set();
+        let mut header = Vec::with_capacity(Self::header_len(self.ipfix));
+        if self.ipfix {
+            header.extend_from_slice(&10u16.to_be_bytes());
+            header.extend_from_slice(&(self.buf.len() as u16).to_be_bytes());
+            header.extend_from_slice(&export_secs.to_be_bytes());
+        } else {
+            header.extend_from_slice(&9u16.to_be_bytes());
+            header.extend_from_slice(&self.records.to_be_bytes());
+            header.extend_from_slice(&uptime_ms.to_be_bytes());
+            header.extend_from_slice(&export_secs.to_be_bytes());
+        }
+        header.extend_from_slice(&sequence.to_be_bytes());
+        header.extend_from_slice(&domain.to_be_bytes());
+        self.buf[..header.len()].copy_from_slice(&header);
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ProtocolDecoder;
+    use chrono::TimeZone;
+    use etherparse::PacketBuilder;
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000, 0).unwrap() + chrono::Duration::milliseconds(ms)
+    }
+
+    fn udp(ms: i64, src: [u8; 4], dst: [u8; 4], payload: &[u8]) -> DecodedPacket {
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4(src, dst, 64)
+            .udp(40000, 53)
+            .write(&mut frame, payload)
+            .unwrap();
+        ProtocolDecoder::new().decode(&frame, at(ms))
+    }
+
+    fn tcp(ms: i64, fin: bool) -> DecodedPacket {
+        let mut frame = Vec::new();
+        let builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv6(
+                [0x20, 0x01, 0xd, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+                [0x20, 0x01, 0xd, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+                64,
+            )
+            .tcp(50000, 443, 1, 1024)
+            .ack(1);
+        let builder = if fin { builder.fin() } else { builder };
+        builder.write(&mut frame, b"data").unwrap();
+        ProtocolDecoder::new().decode(&frame, at(ms))
+    }
+
+    fn collector() -> (UdpSocket, SocketAddr) {
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let addr = socket.local_addr().unwrap();
+        (socket, addr)
+    }
+
+    /// Split a message body into (set ID, set contents).
+    fn sets(body: &[u8]) -> Vec<(u16, Vec<u8>)> {
+        let mut sets = Vec::new();
+        let mut rest = body;
+        while rest.len() >= 4 {
+            let id = u16::from_be_bytes([rest[0], rest[1]]);
+            let len = usize::from(u16::from_be_bytes([rest[2], rest[3]]));
+            sets.push((id, rest[4..len].to_vec()));
+            rest = &rest[len..];
+        }
+        sets
+    }
+
+    #[test]
+    fn test_parse_export_target() {
+        assert_eq!(
+            FlowExportTarget::parse("ipfix://127.0.0.1").unwrap(),
+            FlowExportTarget::Ipfix("127.0.0.1:4739".parse().unwrap())
+        );
+        assert_eq!(
+            FlowExportTarget::parse("netflow9://[::1]:9995").unwrap(),
+            FlowExportTarget::NetflowV9("[::1]:9995".parse().unwrap())
+        );
+        assert_eq!(
+            FlowExportTarget::parse("flows.jsonl").unwrap(),
+            FlowExportTarget::JsonLines("flows.jsonl".into())
+        );
+        assert!(FlowExportTarget::parse("sflow://10.0.0.1").is_err());
+        assert!(FlowExportTarget::parse("").is_err());
+    }
+
+    #[test]
+    fn test_ipfix_export_to_udp_collector() {
+        let (socket, addr) = collector();
+        let config = FlowExportConfig::new(FlowExportTarget::Ipfix(addr)).observation_domain(7);
+        let mut exporter = FlowExporter::new(config).unwrap();
+
+        exporter
+            .observe(&udp(0, [10, 0, 0, 1], [10, 0, 0, 2], b"a"))
+            .unwrap();
+        exporter
+            .observe(&udp(10, [10, 0, 0, 1], [10, 0, 0, 2], b"bb"))
+            .unwrap();
+        exporter.observe(&tcp(20, false)).unwrap();
+        // FIN exports the TCP flow immediately
+        exporter.observe(&tcp(30, true)).unwrap();
+        assert_eq!(exporter.records_exported(), 1);
+        exporter.flush().unwrap();
+        assert_eq!(exporter.records_exported(), 2);
+
+        let mut buf = [0u8; 2048];
+        let mut data_sets = Vec::new();
+        for expected_sequence in [0u32, 1] {
+            let len = socket.recv(&mut buf).unwrap();
+            let message = &buf[..len];
+            assert_eq!(u16::from_be_bytes([message[0], message[1]]), 10);
+            assert_eq!(
+                usize::from(u16::from_be_bytes([message[2], message[3]])),
+                len
+            );
+            assert_eq!(
+                u32::from_be_bytes(message[8..12].try_into().unwrap()),
+                expected_sequence
+            );
+            assert_eq!(u32::from_be_bytes(message[12..16].try_into().unwrap()), 7);
+            data_sets.extend(sets(&message[16..]));
+        }
+
+        // Templates first, then an IPv6 record (TCP) and an IPv4 record (UDP)
+        assert_eq!(data_sets[0].0, 2);
+        assert_eq!(data_sets[1].0, TEMPLATE_IPV6);
+        let v6 = &data_sets[1].1;
+        assert_eq!(v6.len(), 16 + 16 + 2 + 2 + 1 + 1 + 8 + 8 + 8 + 8 + 2 + 1);
+        assert_eq!(u16::from_be_bytes([v6[34], v6[35]]), 443);
+        assert_eq!(v6[36], 6);
+        assert_eq!(v6[37] & 0x01, 0x01);
+        assert_eq!(u64::from_be_bytes(v6[38..46].try_into().unwrap()), 2);
+        assert_eq!(*v6.last().unwrap(), FlowEndReason::EndOfFlow as u8);
+
+        let (id, v4) = &data_sets[2];
+        assert_eq!(*id, TEMPLATE_IPV4);
+        assert_eq!(&v4[..4], &[10, 0, 0, 1]);
+        assert_eq!(u64::from_be_bytes(v4[14..22].try_into().unwrap()), 2);
+        assert_eq!(
+            u64::from_be_bytes(v4[38..46].try_into().unwrap()),
+            at(10).timestamp_millis() as u64
+        );
+        assert_eq!(*v4.last().unwrap(), FlowEndReason::ForcedEnd as u8);
+    }
+
+    #[test]
+    fn test_netflow_v9_timeouts() {
+        let (socket, addr) = collector();
+        let config = FlowExportConfig::new(FlowExportTarget::NetflowV9(addr))
+            .active_timeout(Duration::from_secs(10))
+            .inactive_timeout(Duration::from_secs(2));
+        let mut exporter = FlowExporter::new(config).unwrap();
+
+        // A steady flow and one that goes quiet
+        exporter
+            .observe(&udp(0, [10, 0, 0, 1], [10, 0, 0, 2], b"x"))
+            .unwrap();
+        exporter
+            .observe(&udp(0, [10, 0, 0, 3], [10, 0, 0, 4], b"x"))
+            .unwrap();
+        for second in 1..=11 {
+            exporter
+                .observe(&udp(second * 1000, [10, 0, 0, 1], [10, 0, 0, 2], b"x"))
+                .unwrap();
+        }
+
+        let mut buf = [0u8; 2048];
+        let mut reasons = Vec::new();
+        while reasons.len() < 2 {
+            let len = socket.recv(&mut buf).unwrap();
+            let message = &buf[..len];
+            assert_eq!(u16::from_be_bytes([message[0], message[1]]), 9);
+            for (id, body) in sets(&message[20..]) {
+                if id == TEMPLATE_IPV4 {
+                    assert_eq!(body.len(), 40);
+                    reasons.push(body[0..4].to_vec());
+                }
+            }
+        }
+        // Idle flow first, then the long-lived flow at the active timeout
+        assert_eq!(reasons, vec![vec![10, 0, 0, 3], vec![10, 0, 0, 1]]);
+        assert_eq!(exporter.active_flows(), 1);
+    }
+
+    #[test]
+    fn test_json_lines_export() {
+        let path = std::env::temp_dir().join(format!("netdiag-flows-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let config = FlowExportConfig::new(FlowExportTarget::JsonLines(path.clone()));
+        let mut exporter = FlowExporter::new(config).unwrap();
+        exporter
+            .observe(&udp(0, [10, 0, 0, 1], [10, 0, 0, 2], b"x"))
+            .unwrap();
+        exporter
+            .observe(&udp(5, [10, 0, 0, 2], [10, 0, 0, 1], b"y"))
+            .unwrap();
+        exporter.flush().unwrap();
+        assert_eq!(exporter.messages_sent(), 0);
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        let records: Vec<FlowRecord> = contents
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].src_ip, "10.0.0.1".parse::<IpAddr>().unwrap());
+        assert_eq!(records[0].dst_port, 53);
+        assert_eq!(records[1].end_reason, FlowEndReason::ForcedEnd);
+    }
+
+    #[test]
+    fn test_json_lines_export_appends() {
+        let path =
+            std::env::temp_dir().join(format!("netdiag-flows-append-{}.jsonl", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        for ms in [0, 1] {
+            let config = FlowExportConfig::new(FlowExportTarget::JsonLines(path.clone()));
+            let mut exporter = FlowExporter::new(config).unwrap();
+            exporter
+                .observe(&udp(ms, [10, 0, 0, 1], [10, 0, 0, 2], b"x"))
+                .unwrap();
+            exporter.flush().unwrap();
+        }
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+        assert_eq!(contents.lines().count(), 2);
+    }
+}
@@ -0,0 +1,1244 @@
+//! Filter expression language.
+//!
+//! A BPF-like syntax (`host`, `net`, `port`, `portrange`, protocol names,
+//! `less`/`greater`, `and`/`or`/`not`) extended with display-style fields
+//! such as `dns.qname contains "example"` or `tcp.flags.syn`.
+//!
+//! As in libpcap, `and` and `or` have equal precedence and group from the
+//! left, so `tcp or udp and port 53` means `(tcp or udp) and port 53`;
+//! `not` binds tighter than both.
+
+use crate::decode::{DecodedPacket, Protocol};
+use crate::dissect::ApplicationData;
+use crate::error::{CaptureError, CaptureResult};
+use std::net::IpAddr;
+
+/// Address/port direction qualifier.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    /// Source or destination.
+    Any,
+    /// Source only.
+    Src,
+    /// Destination only.
+    Dst,
+}
+
+impl Direction {
+    /// BPF qualifier prefix.
+    fn bpf(self) -> &'static str {
+        match self {
+            Self::Any => "",
+            Self::Src => "src ",
+            Self::Dst => "dst ",
+        }
+    }
+}
+
+/// Protocol keyword.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProtoMatch {
+    /// IPv4.
+    Ip,
+    /// IPv6.
+    Ip6,
+    /// TCP.
+    Tcp,
+    /// UDP.
+    Udp,
+    /// ICMP.
+    Icmp,
+    /// ICMPv6.
+    Icmp6,
+    /// ARP.
+    Arp,
+    /// DNS.
+    Dns,
+    /// DHCP.
+    Dhcp,
+    /// HTTP.
+    Http,
+    /// TLS.
+    Tls,
+}
+
+impl ProtoMatch {
+    /// Look up a protocol keyword.
+    fn from_keyword(word: &str) -> Option<Self> {
+        Some(match word {
+            "ip" => Self::Ip,
+            "ip6" => Self::Ip6,
+            "tcp" => Self::Tcp,
+            "udp" => Self::Udp,
+            "icmp" => Self::Icmp,
+            "icmp6" => Self::Icmp6,
+            "arp" => Self::Arp,
+            "dns" => Self::Dns,
+            "dhcp" => Self::Dhcp,
+            "http" => Self::Http,
+            "tls" | "ssl" => Self::Tls,
+            _ => return None,
+        })
+    }
+
+    /// BPF primitive, if the protocol can be matched by libpcap.
+    fn bpf(self) -> Option<&'static str> {
+        match self {
+            Self::Ip => Some("ip"),
+            Self::Ip6 => Some("ip6"),
+            Self::Tcp => Some("tcp"),
+            Self::Udp => Some("udp"),
+            Self::Icmp => Some("icmp"),
+            Self::Icmp6 => Some("icmp6"),
+            Self::Arp => Some("arp"),
+            // Detected by content, not just port
+            Self::Dns | Self::Dhcp | Self::Http | Self::Tls => None,
+        }
+    }
+
+    /// IP protocol number for transport protocols.
+    fn ip_protocol(self) -> Option<u8> {
+        match self {
+            Self::Tcp => Some(6),
+            Self::Udp => Some(17),
+            Self::Icmp => Some(1),
+            Self::Icmp6 => Some(58),
+            _ => None,
+        }
+    }
+
+    /// Check a decoded packet.
+    fn matches(self, packet: &DecodedPacket) -> bool {
+        let app = packet.application.as_ref();
+        match self {
+            Self::Ip => packet.src_ip.is_some_and(|ip| ip.is_ipv4()),
+            Self::Ip6 => packet.src_ip.is_some_and(|ip| ip.is_ipv6()),
+            Self::Tcp | Self::Udp | Self::Icmp | Self::Icmp6 => {
+                packet.ip_protocol == self.ip_protocol()
+            }
+            Self::Arp => packet.protocol == Protocol::Arp,
+            Self::Dns => {
+                packet.protocol == Protocol::Dns || matches!(app, Some(ApplicationData::Dns(_)))
+            }
+            Self::Dhcp => {
+                packet.protocol == Protocol::Dhcp || matches!(app, Some(ApplicationData::Dhcp(_)))
+            }
+            Self::Http => {
+                packet.protocol == Protocol::Http || matches!(app, Some(ApplicationData::Http(_)))
+            }
+            Self::Tls => {
+                packet.protocol == Protocol::Https || matches!(app, Some(ApplicationData::Tls(_)))
+            }
+        }
+    }
+}
+
+/// Comparison operator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CompareOp {
+    /// `==`
+    Eq,
+    /// `!=`
+    Ne,
+    /// `<`
+    Lt,
+    /// `<=`
+    Le,
+    /// `>`
+    Gt,
+    /// `>=`
+    Ge,
+    /// `contains` (case-insensitive substring).
+    Contains,
+}
+
+impl CompareOp {
+    /// Parse an operator token.
+    fn parse(token: &str) -> Option<Self> {
+        Some(match token {
+            "==" | "=" | "eq" => Self::Eq,
+            "!=" | "ne" => Self::Ne,
+            "<" | "lt" => Self::Lt,
+            "<=" | "le" => Self::Le,
+            ">" | "gt" => Self::Gt,
+            ">=" | "ge" => Self::Ge,
+            "contains" => Self::Contains,
+            _ => return None,
+        })
+    }
+
+    /// Apply an ordering.
+    fn test(self, ordering: std::cmp::Ordering) -> bool {
+        use std::cmp::Ordering::{Equal, Greater, Less};
+        match self {
+            Self::Eq => ordering == Equal,
+            Self::Ne => ordering != Equal,
+            Self::Lt => ordering == Less,
+            Self::Le => ordering != Greater,
+            Self::Gt => ordering == Greater,
+            Self::Ge => ordering != Less,
+            Self::Contains => false,
+        }
+    }
+}
+
+/// Kind of value a field holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum FieldKind {
+    Number,
+    Text,
+    Addr,
+    Bool,
+}
+
+/// A display-style packet field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    /// `frame.len`
+    FrameLen,
+    /// `eth.src`
+    EthSrc,
+    /// `eth.dst`
+    EthDst,
+    /// `eth.addr`
+    EthAddr,
+    /// `ip.src`
+    IpSrc,
+    /// `ip.dst`
+    IpDst,
+    /// `ip.addr`
+    IpAddr,
+    /// `ip.ttl`
+    IpTtl,
+    /// `ip.proto`
+    IpProto,
+    /// `tcp.srcport`
+    TcpSrcPort,
+    /// `tcp.dstport`
+    TcpDstPort,
+    /// `tcp.port`
+    TcpPort,
+    /// `tcp.flags.syn`
+    TcpSyn,
+    /// `tcp.flags.ack`
+    TcpAck,
+    /// `tcp.flags.fin`
+    TcpFin,
+    /// `tcp.flags.rst`
+    TcpRst,
+    /// `tcp.flags.psh`
+    TcpPsh,
+    /// `tcp.flags.urg`
+    TcpUrg,
+    /// `tcp.window`
+    TcpWindow,
+    /// `tcp.seq`
+    TcpSeq,
+    /// `tcp.ack`
+    TcpAckNum,
+    /// `udp.srcport`
+    UdpSrcPort,
+    /// `udp.dstport`
+    UdpDstPort,
+    /// `udp.port`
+    UdpPort,
+    /// `icmp.type`
+    IcmpType,
+    /// `icmp.code`
+    IcmpCode,
+    /// `dns.qname`
+    DnsQname,
+    /// `dns.qtype`
+    DnsQtype,
+    /// `dns.id`
+    DnsId,
+    /// `dns.rcode`
+    DnsRcode,
+    /// `dns.response`
+    DnsResponse,
+    /// `http.host`
+    HttpHost,
+    /// `http.method`
+    HttpMethod,
+    /// `http.uri`
+    HttpUri,
+    /// `http.status`
+    HttpStatus,
+    /// `http.user_agent`
+    HttpUserAgent,
+    /// `tls.sni`
+    TlsSni,
+    /// `dhcp.hostname`
+    DhcpHostname,
+}
+
+/// Field names, for parsing and error messages.
+const FIELDS: &[(&str, Field)] = &[
+    ("frame.len", Field::FrameLen),
+    ("eth.src", Field::EthSrc),
+    ("eth.dst", Field::EthDst),
+    ("eth.addr", Field::EthAddr),
+    ("ip.src", Field::IpSrc),
+    ("ip.dst", Field::IpDst),
+    ("ip.addr", Field::IpAddr),
+    ("ip.ttl", Field::IpTtl),
+    ("ip.proto", Field::IpProto),
+    ("tcp.srcport", Field::TcpSrcPort),
+    ("tcp.dstport", Field::TcpDstPort),
+    ("tcp.port", Field::TcpPort),
+    ("tcp.flags.syn", Field::TcpSyn),
+    ("tcp.flags.ack", Field::TcpAck),
+    ("tcp.flags.fin", Field::TcpFin),
+    ("tcp.flags.rst", Field::TcpRst),
+    ("tcp.flags.reset", Field::TcpRst),
+    ("tcp.flags.psh", Field::TcpPsh),
+    ("tcp.flags.push", Field::TcpPsh),
+    ("tcp.flags.urg", Field::TcpUrg),
+    ("tcp.window", Field::TcpWindow),
+    ("tcp.seq", Field::TcpSeq),
+    ("tcp.ack", Field::TcpAckNum),
+    ("udp.srcport", Field::UdpSrcPort),
+    ("udp.dstport", Field::UdpDstPort),
+    ("udp.port", Field::UdpPort),
+    ("icmp.type", Field::IcmpType),
+    ("icmp.code", Field::IcmpCode),
+    ("dns.qname", Field::DnsQname),
+    ("dns.qry.name", Field::DnsQname),
+    ("dns.qtype", Field::DnsQtype),
+    ("dns.id", Field::DnsId),
+    ("dns.rcode", Field::DnsRcode),
+    ("dns.response", Field::DnsResponse),
+    ("http.host", Field::HttpHost),
+    ("http.method", Field::HttpMethod),
+    ("http.uri", Field::HttpUri),
+    ("http.status", Field::HttpStatus),
+    ("http.user_agent", Field::HttpUserAgent),
+    ("tls.sni", Field::TlsSni),
+    ("dhcp.hostname", Field::DhcpHostname),
+];
+
+impl Field {
+    /// Look up a field by name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        FIELDS
+            .iter()
+            .find(|(field_name, _)| *field_name == name)
+            .map(|(_, field)| *field)
+    }
+
+    /// Value kind of the field.
+    fn kind(self) -> FieldKind {
+        match self {
+            Self::EthSrc
+            | Self::EthDst
+            | Self::EthAddr
+            | Self::DnsQname
+            | Self::DnsQtype
+            | Self::DnsRcode
+            | Self::HttpHost
+            | Self::HttpMethod
+            | Self::HttpUri
+            | Self::HttpUserAgent
+            | Self::TlsSni
+            | Self::DhcpHostname => FieldKind::Text,
+            Self::IpSrc | Self::IpDst | Self::IpAddr => FieldKind::Addr,
+            Self::TcpSyn
+            | Self::TcpAck
+            | Self::TcpFin
+            | Self::TcpRst
+            | Self::TcpPsh
+            | Self::TcpUrg
+            | Self::DnsResponse => FieldKind::Bool,
+            _ => FieldKind::Number,
+        }
+    }
+
+    /// Extract the field's values from a packet (empty if absent).
+    fn values(self, packet: &DecodedPacket) -> Vec<Value> {
+        let tcp = packet.ip_protocol == Some(6);
+        let udp = packet.ip_protocol == Some(17);
+        let num = |v: Option<u64>| v.map(Value::Number).into_iter().collect::<Vec<_>>();
+        let text = |v: Option<String>| v.map(Value::Text).into_iter().collect::<Vec<_>>();
+        let addr = |v: Option<IpAddr>| v.map(Value::Addr).into_iter().collect::<Vec<_>>();
+        let flag = |f: fn(&crate::decode::TcpFlags) -> bool| {
+            packet
+                .tcp_flags
+                .as_ref()
+                .map(|flags| Value::Bool(f(flags)))
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        let ports = |enabled: bool, ports: &[Option<u16>]| {
+            if !enabled {
+                return Vec::new();
+            }
+            ports
+                .iter()
+                .flatten()
+                .map(|p| Value::Number(u64::from(*p)))
+                .collect::<Vec<_>>()
+        };
+        let dns = match &packet.application {
+            Some(ApplicationData::Dns(dns)) => Some(dns),
+            _ => None,
+        };
+        let http = match &packet.application {
+            Some(ApplicationData::Http(http)) => Some(http),
+            _ => None,
+        };
+
+        match self {
+            Self::FrameLen => num(Some(packet.length as u64)),
+            Self::EthSrc => text(packet.src_mac.clone()),
+            Self::EthDst => text(packet.dst_mac.clone()),
+            Self::EthAddr => [text(packet.src_mac.clone()), text(packet.dst_mac.clone())].concat(),
+            Self::IpSrc => addr(packet.src_ip),
+            Self::IpDst => addr(packet.dst_ip),
+            Self::IpAddr => [addr(packet.src_ip), addr(packet.dst_ip)].concat(),
+            Self::IpTtl => num(packet.ttl.map(u64::from)),
+            Self::IpProto => num(packet.ip_protocol.map(u64::from)),
+            Self::TcpSrcPort => ports(tcp, &[packet.src_port]),
+            Self::TcpDstPort => ports(tcp, &[packet.dst_port]),
+            Self::TcpPort => ports(tcp, &[packet.src_port, packet.dst_port]),
+            Self::TcpSyn => flag(|f| f.syn),
+            Self::TcpAck => flag(|f| f.ack),
+            Self::TcpFin => flag(|f| f.fin),
+            Self::TcpRst => flag(|f| f.rst),
+            Self::TcpPsh => flag(|f| f.psh),
+            Self::TcpUrg => flag(|f| f.urg),
+            Self::TcpWindow => num(packet.tcp_window.map(u64::from)),
+            Self::TcpSeq => num(packet.tcp_seq.map(u64::from)),
+            Self::TcpAckNum => num(packet.tcp_ack.map(u64::from)),
+            Self::UdpSrcPort => ports(udp, &[packet.src_port]),
+            Self::UdpDstPort => ports(udp, &[packet.dst_port]),
+            Self::UdpPort => ports(udp, &[packet.src_port, packet.dst_port]),
+            Self::IcmpType => num(packet.icmp_type.map(u64::from)),
+            Self::IcmpCode => num(packet.icmp_code.map(u64::from)),
+            Self::DnsQname => dns
+                .into_iter()
+                .flat_map(|d| d.questions.iter().map(|q| Value::Text(q.name.clone())))
+                .collect(),
+            Self::DnsQtype => dns
+                .into_iter()
+                .flat_map(|d| {
+                    d.questions
+                        .iter()
+                        .map(|q| Value::Text(crate::dissect::record_type_name(q.qtype)))
+                })
+                .collect(),
+            Self::DnsId => num(dns.map(|d| u64::from(d.id))),
+            Self::DnsRcode => text(dns.map(|d| d.rcode.name())),
+            Self::DnsResponse => dns
+                .map(|d| Value::Bool(d.is_response))
+                .into_iter()
+                .collect(),
+            Self::HttpHost => text(http.and_then(|h| h.host.clone())),
+            Self::HttpMethod => text(http.and_then(|h| h.method.clone())),
+            Self::HttpUri => text(http.and_then(|h| h.uri.clone())),
+            Self::HttpStatus => num(http.and_then(|h| h.status_code).map(u64::from)),
+            Self::HttpUserAgent => text(http.and_then(|h| h.user_agent.clone())),
+            Self::TlsSni => match &packet.application {
+                Some(ApplicationData::Tls(tls)) => text(tls.server_name.clone()),
+                _ => Vec::new(),
+            },
+            Self::DhcpHostname => match &packet.application {
+                Some(ApplicationData::Dhcp(dhcp)) => text(dhcp.hostname.clone()),
+                _ => Vec::new(),
+            },
+        }
+    }
+
+    /// BPF equivalent of a comparison, if libpcap can express it.
+    fn bpf(self, op: Option<CompareOp>, value: Option<&FilterValue>) -> Option<String> {
+        let primitive = match (self, value) {
+            (Self::IpSrc | Self::IpDst | Self::IpAddr, Some(value)) => {
+                let dir = match self {
+                    Self::IpSrc => Direction::Src,
+                    Self::IpDst => Direction::Dst,
+                    _ => Direction::Any,
+                };
+                match value {
+                    FilterValue::Addr(ip) => format!("{}host {}", dir.bpf(), ip),
+                    FilterValue::Net(ip, len) => format!("{}net {}/{}", dir.bpf(), ip, len),
+                    _ => return None,
+                }
+            }
+            (
+                Self::TcpSrcPort
+                | Self::TcpDstPort
+                | Self::TcpPort
+                | Self::UdpSrcPort
+                | Self::UdpDstPort
+                | Self::UdpPort,
+                Some(FilterValue::Number(port)),
+            ) => {
+                let (proto, dir) = match self {
+                    Self::TcpSrcPort => ("tcp", Direction::Src),
+                    Self::TcpDstPort => ("tcp", Direction::Dst),
+                    Self::TcpPort => ("tcp", Direction::Any),
+                    Self::UdpSrcPort => ("udp", Direction::Src),
+                    Self::UdpDstPort => ("udp", Direction::Dst),
+                    _ => ("udp", Direction::Any),
+                };
+                format!("{} {}port {}", proto, dir.bpf(), port)
+            }
+            (
+                Self::TcpSyn
+                | Self::TcpAck
+                | Self::TcpFin
+                | Self::TcpRst
+                | Self::TcpPsh
+                | Self::TcpUrg,
+                _,
+            ) => {
+                let name = match self {
+                    Self::TcpSyn => "syn",
+                    Self::TcpAck => "ack",
+                    Self::TcpFin => "fin",
+                    Self::TcpRst => "rst",
+                    Self::TcpPsh => "push",
+                    _ => "urg",
+                };
+                let primitive = format!("tcp[tcpflags] & tcp-{} != 0", name);
+                // `tcp.flags.syn == false` / `== 0`
+                return match (op, value) {
+                    (None, None) => Some(primitive),
+                    (Some(CompareOp::Eq), Some(FilterValue::Bool(true)))
+                    | (Some(CompareOp::Ne), Some(FilterValue::Bool(false))) => Some(primitive),
+                    (Some(CompareOp::Eq), Some(FilterValue::Bool(false)))
+                    | (Some(CompareOp::Ne), Some(FilterValue::Bool(true))) => {
+                        Some(format!("tcp and not ({})", primitive))
+                    }
+                    _ => None,
+                };
+            }
+            (Self::FrameLen, Some(FilterValue::Number(len))) => {
+                return match op? {
+                    CompareOp::Le => Some(format!("less {}", len)),
+                    CompareOp::Ge => Some(format!("greater {}", len)),
+                    CompareOp::Lt if *len > 0 => Some(format!("less {}", len - 1)),
+                    CompareOp::Gt => Some(format!("greater {}", len + 1)),
+                    CompareOp::Eq => Some(format!("len == {}", len)),
+                    CompareOp::Ne => Some(format!("len != {}", len)),
+                    _ => None,
+                };
+            }
+            _ => return None,
+        };
+
+        match op? {
+            CompareOp::Eq => Some(primitive),
+            CompareOp::Ne => Some(format!("not ({})", primitive)),
+            _ => None,
+        }
+    }
+}
+
+/// A value extracted from a packet.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Number(u64),
+    Text(String),
+    Addr(IpAddr),
+    Bool(bool),
+}
+
+/// A literal in a filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterValue {
+    /// Integer.
+    Number(u64),
+    /// String (compared case-insensitively).
+    Text(String),
+    /// IP address.
+    Addr(IpAddr),
+    /// Network in CIDR notation.
+    Net(IpAddr, u8),
+    /// Boolean.
+    Bool(bool),
+}
+
+impl FilterValue {
+    /// Compare a packet value with this literal.
+    fn compare(&self, op: CompareOp, value: &Value) -> bool {
+        match (value, self) {
+            (Value::Number(a), Self::Number(b)) => op.test(a.cmp(b)),
+            (Value::Bool(a), Self::Bool(b)) => op.test(a.cmp(b)),
+            (Value::Addr(a), Self::Addr(b)) => op.test(a.cmp(b)),
+            (Value::Addr(a), Self::Net(net, len)) => {
+                let inside = in_network(*a, *net, *len);
+                match op {
+                    CompareOp::Eq => inside,
+                    CompareOp::Ne => !inside,
+                    _ => false,
+                }
+            }
+            (Value::Text(a), Self::Text(b)) => {
+                if op == CompareOp::Contains {
+                    a.to_ascii_lowercase().contains(&b.to_ascii_lowercase())
+                } else {
+                    op.test(a.to_ascii_lowercase().cmp(&b.to_ascii_lowercase()))
+                }
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Parsed filter expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterExpr {
+    /// Both must match.
+    And(Box<FilterExpr>, Box<FilterExpr>),
+    /// Either must match.
+    Or(Box<FilterExpr>, Box<FilterExpr>),
+    /// Must not match.
+    Not(Box<FilterExpr>),
+    /// `[src|dst] host ADDR`
+    Host(Direction, IpAddr),
+    /// `[src|dst] net ADDR/LEN`
+    Net(Direction, IpAddr, u8),
+    /// `[tcp|udp] [src|dst] port N` or `portrange N-M`
+    Port {
+        /// Transport restriction.
+        proto: Option<ProtoMatch>,
+        /// Direction.
+        direction: Direction,
+        /// First port.
+        low: u16,
+        /// Last port (inclusive).
+        high: u16,
+    },
+    /// Protocol keyword.
+    Proto(ProtoMatch),
+    /// `less N` (`true`) or `greater N` (`false`).
+    Length {
+        /// Whether this is `less`.
+        less: bool,
+        /// Length in bytes.
+        len: usize,
+    },
+    /// Display field, optionally compared with a value.
+    Field {
+        /// The field.
+        field: Field,
+        /// Comparison, or `None` for "present and true".
+        compare: Option<(CompareOp, FilterValue)>,
+    },
+}
+
+impl FilterExpr {
+    /// Parse a filter expression.
+    pub fn parse(input: &str) -> CaptureResult<Self> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Err(CaptureError::InvalidFilter("empty expression".to_string()));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.parse_binary()?;
+        if let Some(token) = parser.peek() {
+            return Err(CaptureError::InvalidFilter(format!(
+                "unexpected '{}'",
+                token
+            )));
+        }
+        Ok(expr)
+    }
+
+    /// Evaluate against a decoded packet.
+    pub fn matches(&self, packet: &DecodedPacket) -> bool {
+        match self {
+            Self::And(a, b) => a.matches(packet) && b.matches(packet),
+            Self::Or(a, b) => a.matches(packet) || b.matches(packet),
+            Self::Not(a) => !a.matches(packet),
+            Self::Host(dir, ip) => addresses(packet, *dir).any(|a| a == *ip),
+            Self::Net(dir, net, len) => addresses(packet, *dir).any(|a| in_network(a, *net, *len)),
+            Self::Port {
+                proto,
+                direction,
+                low,
+                high,
+            } => {
+                let transport_ok = match proto.and_then(ProtoMatch::ip_protocol) {
+                    Some(p) => packet.ip_protocol == Some(p),
+                    None => matches!(packet.ip_protocol, Some(6 | 17)),
+                };
+                let ports = match direction {
+                    Direction::Any => [packet.src_port, packet.dst_port],
+                    Direction::Src => [packet.src_port, None],
+                    Direction::Dst => [packet.dst_port, None],
+                };
+                transport_ok && ports.iter().flatten().any(|p| (*low..=*high).contains(p))
+            }
+            Self::Proto(proto) => proto.matches(packet),
+            Self::Length { less, len } => {
+                if *less {
+                    packet.length <= *len
+                } else {
+                    packet.length >= *len
+                }
+            }
+            Self::Field { field, compare } => {
+                let values = field.values(packet);
+                match compare {
+                    None => values.iter().any(|v| !matches!(v, Value::Bool(false))),
+                    // `!=` means "no value equals", so absent fields match
+                    Some((CompareOp::Ne, literal)) => {
+                        !values.iter().any(|v| literal.compare(CompareOp::Eq, v))
+                    }
+                    Some((op, literal)) => values.iter().any(|v| literal.compare(*op, v)),
+                }
+            }
+        }
+    }
+
+    /// Exact BPF equivalent, if every part of the expression can run in libpcap.
+    pub fn to_bpf(&self) -> Option<String> {
+        match self {
+            Self::And(a, b) => Some(format!("({}) and ({})", a.to_bpf()?, b.to_bpf()?)),
+            Self::Or(a, b) => Some(format!("({}) or ({})", a.to_bpf()?, b.to_bpf()?)),
+            Self::Not(a) => Some(format!("not ({})", a.to_bpf()?)),
+            Self::Host(dir, ip) => Some(format!("{}host {}", dir.bpf(), ip)),
+            Self::Net(dir, ip, len) => Some(format!("{}net {}/{}", dir.bpf(), ip, len)),
+            Self::Port {
+                proto,
+                direction,
+                low,
+                high,
+            } => {
+                let proto = proto.and_then(ProtoMatch::bpf).map(|p| format!("{} ", p));
+                let proto = proto.unwrap_or_default();
+                if low == high {
+                    Some(format!("{}{}port {}", proto, direction.bpf(), low))
+                } else {
+                    Some(format!(
+                        "{}{}portrange {}-{}",
+                        proto,
+                        direction.bpf(),
+                        low,
+                        high
+                    ))
+                }
+            }
+            Self::Proto(proto) => proto.bpf().map(str::to_string),
+            Self::Length { less, len } => Some(format!(
+                "{} {}",
+                if *less { "less" } else { "greater" },
+                len
+            )),
+            Self::Field { field, compare } => {
+                let (op, value) = match compare {
+                    Some((op, value)) => (Some(*op), Some(value)),
+                    None => (None, None),
+                };
+                field.bpf(op, value)
+            }
+        }
+    }
+
+    /// BPF that passes at least every matching packet, for pre-filtering in libpcap.
+    ///
+    /// Parts that cannot be compiled are left to [`FilterExpr::matches`].
+    pub fn prefilter_bpf(&self) -> Option<String> {
+        match self {
+            Self::And(a, b) => match (a.prefilter_bpf(), b.prefilter_bpf()) {
+                (Some(a), Some(b)) => Some(format!("({}) and ({})", a, b)),
+                (Some(one), None) | (None, Some(one)) => Some(one),
+                (None, None) => None,
+            },
+            Self::Or(a, b) => Some(format!(
+                "({}) or ({})",
+                a.prefilter_bpf()?,
+                b.prefilter_bpf()?
+            )),
+            Self::Field {
+                field:
+                    Field::DnsQname
+                    | Field::DnsQtype
+                    | Field::DnsId
+                    | Field::DnsRcode
+                    | Field::DnsResponse,
+                ..
+            } => Some("port 53 or port 5353".to_string()),
+            Self::Field {
+                field: Field::DhcpHostname,
+                ..
+            } => Some("udp port 67 or udp port 68".to_string()),
+            Self::Proto(ProtoMatch::Dns) => Some("port 53 or port 5353".to_string()),
+            Self::Proto(ProtoMatch::Dhcp) => Some("udp port 67 or udp port 68".to_string()),
+            Self::Proto(ProtoMatch::Http | ProtoMatch::Tls)
+            | Self::Field {
+                field:
+                    Field::HttpHost
+                    | Field::HttpMethod
+                    | Field::HttpUri
+                    | Field::HttpStatus
+                    | Field::HttpUserAgent
+                    | Field::TlsSni,
+                ..
+            } => Some("tcp".to_string()),
+            other => other.to_bpf(),
+        }
+    }
+}
+
+/// Addresses of a packet in the given direction.
+fn addresses(packet: &DecodedPacket, dir: Direction) -> impl Iterator<Item = IpAddr> {
+    let (src, dst) = match dir {
+        Direction::Any => (packet.src_ip, packet.dst_ip),
+        Direction::Src => (packet.src_ip, None),
+        Direction::Dst => (None, packet.dst_ip),
+    };
+    src.into_iter().chain(dst)
+}
+
+/// Check if an address is inside a network.
+fn in_network(addr: IpAddr, net: IpAddr, len: u8) -> bool {
+    match (addr, net) {
+        (IpAddr::V4(a), IpAddr::V4(n)) => {
+            let mask = u32::MAX.checked_shl(32 - u32::from(len)).unwrap_or(0);
+            u32::from(a) & mask == u32::from(n) & mask
+        }
+        (IpAddr::V6(a), IpAddr::V6(n)) => {
+            let mask = u128::MAX.checked_shl(128 - u32::from(len)).unwrap_or(0);
+            u128::from(a) & mask == u128::from(n) & mask
+        }
+        _ => false,
+    }
+}
+
+/// Split an expression into tokens.
+fn tokenize(input: &str) -> CaptureResult<Vec<String>> {
+    let mut tokens = Vec::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                tokens.push(c.to_string());
+                chars.next();
+            }
+            '"' | '\'' => {
+                chars.next();
+                let mut text = String::from('"');
+                loop {
+                    match chars.next() {
+                        Some(q) if q == c => break,
+                        Some(ch) => text.push(ch),
+                        None => {
+                            return Err(CaptureError::InvalidFilter(
+                                "unterminated string".to_string(),
+                            ))
+                        }
+                    }
+                }
+                tokens.push(text);
+            }
+            '=' | '!' | '<' | '>' | '&' | '|' => {
+                let mut op = String::from(c);
+                chars.next();
+                if let Some(&next) = chars.peek() {
+                    if next == '=' || (next == c && (c == '&' || c == '|' || c == '=')) {
+                        op.push(next);
+                        chars.next();
+                    }
+                }
+                if op == "&" || op == "|" {
+                    return Err(CaptureError::InvalidFilter(format!(
+                        "unexpected '{}' (use '{}{}')",
+                        op, op, op
+                    )));
+                }
+                tokens.push(op);
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&ch) = chars.peek() {
+                    if ch.is_whitespace() || "()=!<>&|\"'".contains(ch) {
+                        break;
+                    }
+                    word.push(ch);
+                    chars.next();
+                }
+                tokens.push(word);
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// Recursive-descent parser over tokens.
+struct Parser {
+    tokens: Vec<String>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect_value(&mut self, what: &str) -> CaptureResult<String> {
+        self.next()
+            .ok_or_else(|| CaptureError::InvalidFilter(format!("expected {}", what)))
+    }
+
+    /// `and`/`or` chain, grouped from the left like libpcap.
+    fn parse_binary(&mut self) -> CaptureResult<FilterExpr> {
+        let mut left = self.parse_not()?;
+        loop {
+            let or = match self.peek() {
+                Some("or" | "||") => true,
+                Some("and" | "&&") => false,
+                _ => return Ok(left),
+            };
+            self.next();
+            let right = Box::new(self.parse_not()?);
+            left = if or {
+                FilterExpr::Or(Box::new(left), right)
+            } else {
+                FilterExpr::And(Box::new(left), right)
+            };
+        }
+    }
+
+    fn parse_not(&mut self) -> CaptureResult<FilterExpr> {
+        if matches!(self.peek(), Some("not" | "!")) {
+            self.next();
+            return Ok(FilterExpr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> CaptureResult<FilterExpr> {
+        let token = self.expect_value("an expression")?;
+        match token.as_str() {
+            "(" => {
+                let expr = self.parse_binary()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(expr),
+                    _ => Err(CaptureError::InvalidFilter("missing ')'".to_string())),
+                }
+            }
+            "src" | "dst" => {
+                let direction = if token == "src" {
+                    Direction::Src
+                } else {
+                    Direction::Dst
+                };
+                self.parse_qualified(None, direction)
+            }
+            "host" | "net" | "port" | "portrange" => {
+                self.pos -= 1;
+                self.parse_qualified(None, Direction::Any)
+            }
+            "less" | "greater" => {
+                let value = self.expect_value("a length")?;
+                let len = value.parse().map_err(|_| {
+                    CaptureError::InvalidFilter(format!("invalid length '{}'", value))
+                })?;
+                Ok(FilterExpr::Length {
+                    less: token == "less",
+                    len,
+                })
+            }
+            word => {
+                if let Some(proto) = ProtoMatch::from_keyword(word) {
+                    // `tcp port 80`, `udp src port 53`
+                    if matches!(proto, ProtoMatch::Tcp | ProtoMatch::Udp)
+                        && matches!(self.peek(), Some("src" | "dst" | "port" | "portrange"))
+                    {
+                        let direction = match self.peek() {
+                            Some("src") => Direction::Src,
+                            Some("dst") => Direction::Dst,
+                            _ => Direction::Any,
+                        };
+                        if direction != Direction::Any {
+                            self.next();
+                        }
+                        return self.parse_qualified(Some(proto), direction);
+                    }
+                    return Ok(FilterExpr::Proto(proto));
+                }
+                if let Some(field) = Field::from_name(word) {
+                    return self.parse_field(field);
+                }
+                if word.contains('.') && word.chars().next().is_some_and(char::is_alphabetic) {
+                    return Err(CaptureError::InvalidFilter(format!(
+                        "unknown field '{}'",
+                        word
+                    )));
+                }
+                Err(CaptureError::InvalidFilter(format!(
+                    "unexpected '{}'",
+                    word
+                )))
+            }
+        }
+    }
+
+    /// Parse `host`/`net`/`port`/`portrange` after optional protocol and direction.
+    fn parse_qualified(
+        &mut self,
+        proto: Option<ProtoMatch>,
+        direction: Direction,
+    ) -> CaptureResult<FilterExpr> {
+        let keyword = self.expect_value("host, net, port or portrange")?;
+        match keyword.as_str() {
+            "host" if proto.is_none() => {
+                let value = self.expect_value("an address")?;
+                Ok(FilterExpr::Host(direction, parse_addr(&value)?))
+            }
+            "net" if proto.is_none() => {
+                let value = self.expect_value("a network")?;
+                let (ip, len) = parse_net(&value)?;
+                Ok(FilterExpr::Net(direction, ip, len))
+            }
+            "port" => {
+                let value = self.expect_value("a port")?;
+                let port = parse_port(&value)?;
+                Ok(FilterExpr::Port {
+                    proto,
+                    direction,
+                    low: port,
+                    high: port,
+                })
+            }
+            "portrange" => {
+                let value = self.expect_value("a port range")?;
+                let (low, high) = value.split_once('-').ok_or_else(|| {
+                    CaptureError::InvalidFilter(format!("invalid port range '{}'", value))
+                })?;
+                let (low, high) = (parse_port(low)?, parse_port(high)?);
+                if low > high {
+                    return Err(CaptureError::InvalidFilter(format!(
+                        "invalid port range '{}'",
+                        value
+                    )));
+                }
+                Ok(FilterExpr::Port {
+                    proto,
+                    direction,
+                    low,
+                    high,
+                })
+            }
+            // `src 10.0.0.1` is shorthand for `src host 10.0.0.1`
+            other if proto.is_none() && direction != Direction::Any => {
+                Ok(FilterExpr::Host(direction, parse_addr(other)?))
+            }
+            other => Err(CaptureError::InvalidFilter(format!(
+                "expected host, net, port or portrange, found '{}'",
+                other
+            ))),
+        }
+    }
+
+    /// Parse an optional comparison after a field name.
+    fn parse_field(&mut self, field: Field) -> CaptureResult<FilterExpr> {
+        let Some(op) = self.peek().and_then(CompareOp::parse) else {
+            return Ok(FilterExpr::Field {
+                field,
+                compare: None,
+            });
+        };
+        self.next();
+
+        let raw = self.expect_value("a value")?;
+        let text = raw.strip_prefix('"').unwrap_or(&raw);
+        let kind = field.kind();
+        let value = match kind {
+            FieldKind::Number => FilterValue::Number(parse_number(text)?),
+            FieldKind::Text => FilterValue::Text(text.to_string()),
+            FieldKind::Addr if text.contains('/') => {
+                let (ip, len) = parse_net(text)?;
+                FilterValue::Net(ip, len)
+            }
+            FieldKind::Addr => FilterValue::Addr(parse_addr(text)?),
+            FieldKind::Bool => FilterValue::Bool(match text {
+                "1" | "true" => true,
+                "0" | "false" => false,
+                _ => {
+                    return Err(CaptureError::InvalidFilter(format!(
+                        "expected true or false, found '{}'",
+                        text
+                    )))
+                }
+            }),
+        };
+
+        let valid = match kind {
+            FieldKind::Text => true,
+            FieldKind::Number => op != CompareOp::Contains,
+            FieldKind::Addr => {
+                matches!(op, CompareOp::Eq | CompareOp::Ne)
+                    || (op != CompareOp::Contains && !matches!(value, FilterValue::Net(..)))
+            }
+            FieldKind::Bool => matches!(op, CompareOp::Eq | CompareOp::Ne),
+        };
+        if !valid {
+            return Err(CaptureError::InvalidFilter(format!(
+                "operator not supported for this field: {:?}",
+                op
+            )));
+        }
+
+        Ok(FilterExpr::Field {
+            field,
+            compare: Some((op, value)),
+        })
+    }
+}
+
+fn parse_addr(value: &str) -> CaptureResult<IpAddr> {
+    value
+        .parse()
+        .map_err(|_| CaptureError::InvalidFilter(format!("invalid address '{}'", value)))
+}
+
+fn parse_net(value: &str) -> CaptureResult<(IpAddr, u8)> {
+    let invalid = || CaptureError::InvalidFilter(format!("invalid network '{}'", value));
+    let (ip, len) = match value.split_once('/') {
+        Some((ip, len)) => {
+            let ip: IpAddr = ip.parse().map_err(|_| invalid())?;
+            (ip, len.parse::<u8>().map_err(|_| invalid())?)
+        }
+        None => {
+            let ip: IpAddr = value.parse().map_err(|_| invalid())?;
+            (ip, if ip.is_ipv4() { 32 } else { 128 })
+        }
+    };
+    let max = if ip.is_ipv4() { 32 } else { 128 };
+    if len > max {
+        return Err(invalid());
+    }
+    Ok((ip, len))
+}
+
+fn parse_port(value: &str) -> CaptureResult<u16> {
+    value
+        .parse()
+        .map_err(|_| CaptureError::InvalidFilter(format!("invalid port '{}'", value)))
+}
+
+fn parse_number(value: &str) -> CaptureResult<u64> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+    parsed.map_err(|_| CaptureError::InvalidFilter(format!("invalid number '{}'", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ProtocolDecoder;
+    use chrono::Utc;
+    use etherparse::PacketBuilder;
+
+    /// Decode a DNS query for `name` from 192.168.1.10 to 192.168.1.1.
+    fn dns_query(name: &str) -> DecodedPacket {
+        let mut msg = vec![0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0];
+        for label in name.split('.') {
+            msg.push(label.len() as u8);
+            msg.extend_from_slice(label.as_bytes());
+        }
+        msg.extend_from_slice(&[0, 0, 1, 0, 1]);
+
+        let builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4([192, 168, 1, 10], [192, 168, 1, 1], 64)
+            .udp(40000, 53);
+        let mut frame = Vec::new();
+        builder.write(&mut frame, &msg).unwrap();
+        ProtocolDecoder::new().decode(&frame, Utc::now())
+    }
+
+    /// Decode a TCP SYN from 10.0.0.5:51000 to 93.184.216.34:443.
+    fn tcp_syn() -> DecodedPacket {
+        let builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4([10, 0, 0, 5], [93, 184, 216, 34], 64)
+            .tcp(51000, 443, 1000, 65535)
+            .syn();
+        let mut frame = Vec::new();
+        builder.write(&mut frame, &[]).unwrap();
+        ProtocolDecoder::new().decode(&frame, Utc::now())
+    }
+
+    #[test]
+    fn test_parse_bpf_primitives() {
+        let expr = FilterExpr::parse("src host 10.0.0.5 and tcp dst port 443").unwrap();
+        assert_eq!(
+            expr.to_bpf().as_deref(),
+            Some("(src host 10.0.0.5) and (tcp dst port 443)")
+        );
+        assert!(expr.matches(&tcp_syn()));
+        assert!(!expr.matches(&dns_query("example.com")));
+
+        let expr = FilterExpr::parse("not net 192.168.0.0/16 || portrange 400-500").unwrap();
+        assert!(expr.matches(&tcp_syn()));
+        assert!(!expr.matches(&dns_query("example.com")));
+    }
+
+    #[test]
+    fn test_and_or_group_like_libpcap() {
+        let expr = FilterExpr::parse("tcp or udp and port 53").unwrap();
+        assert_eq!(
+            expr.to_bpf().as_deref(),
+            Some("((tcp) or (udp)) and (port 53)")
+        );
+        assert!(expr.matches(&dns_query("example.com")));
+        // Not `tcp or (udp and port 53)`
+        assert!(!expr.matches(&tcp_syn()));
+
+        let expr = FilterExpr::parse("udp and port 53 or tcp").unwrap();
+        assert!(expr.matches(&tcp_syn()));
+    }
+
+    #[test]
+    fn test_parse_display_fields() {
+        let expr = FilterExpr::parse(r#"dns.qname contains "EXAMPLE" and udp"#).unwrap();
+        assert_eq!(expr.to_bpf(), None);
+        assert!(expr.prefilter_bpf().is_some());
+        assert!(expr.matches(&dns_query("www.example.com")));
+        assert!(!expr.matches(&dns_query("other.org")));
+        assert!(!expr.matches(&tcp_syn()));
+
+        let expr = FilterExpr::parse("tcp.flags.syn && !tcp.flags.ack").unwrap();
+        assert!(expr.matches(&tcp_syn()));
+        assert!(!expr.matches(&dns_query("example.com")));
+
+        let expr = FilterExpr::parse("ip.dst == 93.184.216.34 and tcp.dstport >= 443").unwrap();
+        assert!(expr.matches(&tcp_syn()));
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        for bad in [
+            "",
+            "host",
+            "host 300.1.1.1",
+            "port 70000",
+            "tcp and",
+            "(udp",
+            "dns.bogus == 1",
+            "ip.ttl contains 3",
+            "tcp.srcport == abc",
+        ] {
+            assert!(
+                matches!(FilterExpr::parse(bad), Err(CaptureError::InvalidFilter(_))),
+                "expected error for {:?}",
+                bad
+            );
+        }
+    }
+}
@@ -0,0 +1,376 @@
+//! BPF filter support.
+
+mod expr;
+
+pub use expr::{CompareOp, Direction, Field, FilterExpr, FilterValue, ProtoMatch};
+
+use crate::decode::DecodedPacket;
+use crate::error::CaptureResult;
+use pcap::{Capture, Linktype};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// Capture filter using BPF syntax.
+///
+/// The expression is parsed once when the filter is built, so matching
+/// packets in userspace does not re-parse it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "RawCaptureFilter", into = "RawCaptureFilter")]
+pub struct CaptureFilter {
+    /// Raw BPF filter string.
+    filter: String,
+    /// Parsed expression, if the filter is in the netdiag filter language.
+    expr: Option<FilterExpr>,
+}
+
+/// Serialized form of [`CaptureFilter`]: only the expression text.
+#[derive(Serialize, Deserialize)]
+struct RawCaptureFilter {
+    filter: String,
+}
+
+impl From<RawCaptureFilter> for CaptureFilter {
+    fn from(raw: RawCaptureFilter) -> Self {
+        Self::new(raw.filter)
+    }
+}
+
+impl From<CaptureFilter> for RawCaptureFilter {
+    fn from(filter: CaptureFilter) -> Self {
+        Self {
+            filter: filter.filter,
+        }
+    }
+}
+
+impl CaptureFilter {
+    /// Create a filter from a raw BPF string.
+    pub fn new(filter: impl Into<String>) -> Self {
+        let filter = filter.into();
+        let expr = FilterExpr::parse(&filter).ok();
+        Self { filter, expr }
+    }
+
+    /// Create a filter, validating the expression up front.
+    pub fn parse(filter: impl Into<String>) -> CaptureResult<Self> {
+        let filter = Self::new(filter);
+        filter.compile()?;
+        Ok(filter)
+    }
+
+    /// Create an empty filter (capture all).
+    pub fn all() -> Self {
+        Self {
+            filter: String::new(),
+            expr: None,
+        }
+    }
+
+    /// Filter by host IP address.
+    pub fn host(addr: IpAddr) -> Self {
+        Self::new(format!("host {}", addr))
+    }
+
+    /// Filter by source IP address.
+    pub fn src_host(addr: IpAddr) -> Self {
+        Self::new(format!("src host {}", addr))
+    }
+
+    /// Filter by destination IP address.
+    pub fn dst_host(addr: IpAddr) -> Self {
+        Self::new(format!("dst host {}", addr))
+    }
+
+    /// Filter by port.
+    pub fn port(port: u16) -> Self {
+        Self::new(format!("port {}", port))
+    }
+
+    /// Filter by source port.
+    pub fn src_port(port: u16) -> Self {
+        Self::new(format!("src port {}", port))
+    }
+
+    /// Filter by destination port.
+    pub fn dst_port(port: u16) -> Self {
+        Self::new(format!("dst port {}", port))
+    }
+
+    /// Filter by protocol.
+    pub fn protocol(proto: &str) -> Self {
+        Self::new(proto.to_lowercase())
+    }
+
+    /// Filter TCP traffic.
+    pub fn tcp() -> Self {
+        Self::protocol("tcp")
+    }
+
+    /// Filter UDP traffic.
+    pub fn udp() -> Self {
+        Self::protocol("udp")
+    }
+
+    /// Filter ICMP traffic.
+    pub fn icmp() -> Self {
+        Self::protocol("icmp")
+    }
+
+    /// Filter DNS traffic.
+    pub fn dns() -> Self {
+        Self::new("port 53")
+    }
+
+    /// Filter HTTP traffic.
+    pub fn http() -> Self {
+        Self::new("port 80 or port 443")
+    }
+
+    /// Combine with AND.
+    pub fn and(self, other: CaptureFilter) -> Self {
+        if self.filter.is_empty() {
+            other
+        } else if other.filter.is_empty() {
+            self
+        } else {
+            Self::new(format!("({}) and ({})", self.filter, other.filter))
+        }
+    }
+
+    /// Combine with OR.
+    pub fn or(self, other: CaptureFilter) -> Self {
+        if self.filter.is_empty() {
+            other
+        } else if other.filter.is_empty() {
+            self
+        } else {
+            Self::new(format!("({}) or ({})", self.filter, other.filter))
+        }
+    }
+
+    /// Negate the filter.
+    pub fn not(self) -> Self {
+        if self.filter.is_empty() {
+            self
+        } else {
+            Self::new(format!("not ({})", self.filter))
+        }
+    }
+
+    /// Get the BPF filter string.
+    pub fn as_str(&self) -> &str {
+        &self.filter
+    }
+
+    /// Check if filter is empty.
+    pub fn is_empty(&self) -> bool {
+        self.filter.is_empty()
+    }
+
+    /// Compile the filter into a BPF program and/or a userspace expression.
+    ///
+    /// Expressions in the netdiag filter language are translated to BPF where
+    /// possible and evaluated in Rust otherwise. Anything else is handed to
+    /// libpcap's compiler as raw BPF.
+    pub fn compile(&self) -> CaptureResult<CompiledFilter> {
+        if self.filter.trim().is_empty() {
+            return Ok(CompiledFilter::default());
+        }
+
+        if let Some(expr) = &self.expr {
+            return Ok(match expr.to_bpf() {
+                Some(bpf) => CompiledFilter {
+                    bpf: Some(bpf),
+                    expr: None,
+                },
+                None => CompiledFilter {
+                    bpf: expr.prefilter_bpf(),
+                    expr: Some(expr.clone()),
+                },
+            });
+        }
+
+        // Raw BPF (e.g. `tcp[13] & 2 != 0`, `vlan`) that our parser doesn't cover
+        let valid_bpf = Capture::dead(Linktype::ETHERNET)
+            .and_then(|cap| cap.compile(&self.filter, true))
+            .is_ok();
+        if valid_bpf {
+            Ok(CompiledFilter {
+                bpf: Some(self.filter.clone()),
+                expr: None,
+            })
+        } else {
+            // Parse again only to report why the expression was rejected
+            Err(FilterExpr::parse(&self.filter)
+                .expect_err("expressions that parse are kept when the filter is built"))
+        }
+    }
+
+    /// Check a decoded packet against the filter.
+    ///
+    /// Raw BPF that only libpcap understands matches every packet.
+    pub fn matches(&self, packet: &DecodedPacket) -> bool {
+        self.expr.as_ref().map_or(true, |expr| expr.matches(packet))
+    }
+}
+
+/// A filter split into what libpcap runs and what is evaluated in Rust.
+#[derive(Debug, Clone, Default)]
+pub struct CompiledFilter {
+    bpf: Option<String>,
+    expr: Option<FilterExpr>,
+}
+
+impl CompiledFilter {
+    /// BPF program text to install in libpcap.
+    pub fn bpf(&self) -> Option<&str> {
+        self.bpf.as_deref()
+    }
+
+    /// Check if packets still need to be checked in userspace.
+    pub fn needs_userspace(&self) -> bool {
+        self.expr.is_some()
+    }
+
+    /// Check a decoded packet against the parts BPF could not express.
+    pub fn matches(&self, packet: &DecodedPacket) -> bool {
+        self.expr.as_ref().map_or(true, |expr| expr.matches(packet))
+    }
+}
+
+/// Validate a filter expression and return the BPF libpcap should run.
+///
+/// The result is empty when nothing can be pre-filtered in the kernel.
+pub fn compile_filter(expression: &str) -> CaptureResult<String> {
+    Ok(CaptureFilter::new(expression)
+        .compile()?
+        .bpf
+        .unwrap_or_default())
+}
+
+impl From<&netdiag_types::capture::CaptureFilter> for CaptureFilter {
+    /// Combine the BPF expression, host, port and protocol with AND.
+    ///
+    /// The packet and time limits belong to the capture configuration.
+    fn from(spec: &netdiag_types::capture::CaptureFilter) -> Self {
+        let mut filter = spec.bpf.as_deref().map(Self::new).unwrap_or_default();
+        if let Some(protocol) = &spec.protocol {
+            filter = filter.and(Self::protocol(protocol));
+        }
+        if let Some(host) = spec.host {
+            filter = filter.and(Self::host(host));
+        }
+        if let Some(port) = spec.port {
+            filter = filter.and(Self::port(port));
+        }
+        filter
+    }
+}
+
+impl Default for CaptureFilter {
+    fn default() -> Self {
+        Self::all()
+    }
+}
+
+impl std::fmt::Display for CaptureFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.filter.is_empty() {
+            write!(f, "(all)")
+        } else {
+            write!(f, "{}", self.filter)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn test_filter_host() {
+        let filter = CaptureFilter::host(IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1)));
+        assert_eq!(filter.as_str(), "host 192.168.1.1");
+    }
+
+    #[test]
+    fn test_filter_port() {
+        let filter = CaptureFilter::port(80);
+        assert_eq!(filter.as_str(), "port 80");
+    }
+
+    #[test]
+    fn test_filter_and() {
+        let filter = CaptureFilter::tcp().and(CaptureFilter::port(80));
+        assert_eq!(filter.as_str(), "(tcp) and (port 80)");
+    }
+
+    #[test]
+    fn test_filter_or() {
+        let filter = CaptureFilter::port(80).or(CaptureFilter::port(443));
+        assert_eq!(filter.as_str(), "(port 80) or (port 443)");
+    }
+
+    #[test]
+    fn test_filter_from_spec() {
+        let spec = netdiag_types::capture::CaptureFilter {
+            bpf: Some("not arp".to_string()),
+            host: Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))),
+            port: Some(53),
+            protocol: Some("UDP".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            CaptureFilter::from(&spec).as_str(),
+            "(((not arp) and (udp)) and (host 10.0.0.1)) and (port 53)"
+        );
+        assert!(CaptureFilter::from(&netdiag_types::capture::CaptureFilter::default()).is_empty());
+    }
+
+    #[test]
+    fn test_compile_splits_bpf_and_userspace() {
+        let compiled = CaptureFilter::new("tcp port 443").compile().unwrap();
+        assert_eq!(compiled.bpf(), Some("tcp port 443"));
+        assert!(!compiled.needs_userspace());
+
+        let compiled = CaptureFilter::new("udp and dns.qname == example.com")
+            .compile()
+            .unwrap();
+        assert!(compiled.bpf().is_some());
+        assert!(compiled.needs_userspace());
+
+        assert!(CaptureFilter::all().compile().unwrap().bpf().is_none());
+        assert!(CaptureFilter::parse("host and or").is_err());
+        assert_eq!(
+            compile_filter("dns.qname contains x").unwrap(),
+            "port 53 or port 5353"
+        );
+    }
+
+    #[test]
+    fn test_matches_uses_parsed_expression() {
+        let decode = |port: u16| {
+            let mut frame = Vec::new();
+            etherparse::PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+                .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+                .tcp(50000, port, 1, 65535)
+                .write(&mut frame, &[])
+                .unwrap();
+            crate::decode::ProtocolDecoder::new().decode(&frame, chrono::Utc::now())
+        };
+
+        let filter = CaptureFilter::tcp().and(CaptureFilter::dst_port(443));
+        assert!(filter.matches(&decode(443)));
+        assert!(!filter.matches(&decode(80)));
+
+        // Deserializing rebuilds the parsed expression
+        let json = serde_json::to_string(&filter).unwrap();
+        assert_eq!(json, r#"{"filter":"(tcp) and (dst port 443)"}"#);
+        let filter: CaptureFilter = serde_json::from_str(&json).unwrap();
+        assert!(!filter.matches(&decode(80)));
+
+        // Raw BPF only libpcap understands matches everything in userspace
+        assert!(CaptureFilter::new("tcp[13] & 2 != 0").matches(&decode(80)));
+    }
+}
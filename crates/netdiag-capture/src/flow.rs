@@ -0,0 +1,864 @@
+//! Flow tracking and TCP analysis.
+
+use crate::decode::DecodedPacket;
+use crate::process::ProcessInfo;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::time::Duration;
+
+/// IP protocol number for TCP.
+const IPPROTO_TCP: u8 = 6;
+
+/// Retransmission rate (percent) above which a host is reported.
+const RETRANSMIT_WARN_PERCENT: f64 = 3.0;
+
+/// Handshake RTT above which a host is reported.
+const SLOW_HANDSHAKE: Duration = Duration::from_millis(300);
+
+/// Gap below which a late segment counts as reordered rather than retransmitted,
+/// used until the handshake RTT is known.
+const DEFAULT_REORDER_WINDOW: Duration = Duration::from_millis(3);
+
+/// Flows without packets for this long are closed.
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(300);
+
+/// Flows open for longer than this are closed so a new entry starts fresh.
+const DEFAULT_ACTIVE_TIMEOUT: Duration = Duration::from_secs(3600);
+
+/// Most flows kept before the least recently seen are evicted.
+const DEFAULT_MAX_FLOWS: usize = 65_536;
+
+/// Flow key: protocol plus both endpoints, ordered so both directions share a key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FlowKey {
+    /// IP protocol number.
+    pub protocol: u8,
+    /// Lower endpoint.
+    pub a: SocketAddr,
+    /// Higher endpoint.
+    pub b: SocketAddr,
+}
+
+impl FlowKey {
+    /// Build a key from a packet, also returning whether it travels from `a` to `b`.
+    pub fn from_packet(packet: &DecodedPacket) -> Option<(Self, bool)> {
+        let src = SocketAddr::new(packet.src_ip?, packet.src_port?);
+        let dst = SocketAddr::new(packet.dst_ip?, packet.dst_port?);
+        let protocol = packet.ip_protocol?;
+
+        if src <= dst {
+            Some((
+                Self {
+                    protocol,
+                    a: src,
+                    b: dst,
+                },
+                true,
+            ))
+        } else {
+            Some((
+                Self {
+                    protocol,
+                    a: dst,
+                    b: src,
+                },
+                false,
+            ))
+        }
+    }
+
+    /// Check if this is a TCP flow.
+    pub fn is_tcp(&self) -> bool {
+        self.protocol == IPPROTO_TCP
+    }
+}
+
+impl std::fmt::Display for FlowKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let proto = match self.protocol {
+            6 => "TCP".to_string(),
+            17 => "UDP".to_string(),
+            other => format!("IP({})", other),
+        };
+        write!(f, "{} {} <-> {}", proto, self.a, self.b)
+    }
+}
+
+/// Per-direction TCP sequence state.
+#[derive(Debug, Clone, Default)]
+struct DirectionState {
+    /// Next expected sequence number.
+    next_seq: Option<u32>,
+    /// Last acknowledgment number sent.
+    last_ack: Option<u32>,
+    /// Last advertised window.
+    last_window: Option<u16>,
+    /// Time of the last segment carrying data.
+    last_segment: Option<DateTime<Utc>>,
+    /// Whether the receive window is currently zero.
+    zero_window: bool,
+}
+
+/// Statistics for a single flow.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowStats {
+    /// Flow key.
+    pub key: FlowKey,
+    /// Endpoint that sent the initial SYN, if the handshake was captured.
+    pub client: Option<SocketAddr>,
+    /// First packet time.
+    pub first_seen: DateTime<Utc>,
+    /// Last packet time.
+    pub last_seen: DateTime<Utc>,
+    /// Total packets.
+    pub packets: u64,
+    /// Total bytes.
+    pub bytes: u64,
+    /// Bytes sent from `a` to `b`.
+    pub bytes_a_to_b: u64,
+    /// Bytes sent from `b` to `a`.
+    pub bytes_b_to_a: u64,
+    /// TCP segments carrying data.
+    pub data_segments: u64,
+    /// Time from SYN to the ACK completing the handshake.
+    pub handshake_rtt: Option<Duration>,
+    /// Retransmitted segments.
+    pub retransmissions: u64,
+    /// Duplicate ACKs.
+    pub duplicate_acks: u64,
+    /// Out-of-order segments.
+    pub out_of_order: u64,
+    /// Times a receiver advertised a zero window.
+    pub zero_window_events: u64,
+    /// RST segments.
+    pub resets: u64,
+    /// Local process owning the flow, if attributed.
+    #[serde(default)]
+    pub process: Option<ProcessInfo>,
+
+    #[serde(skip)]
+    state: [DirectionState; 2],
+    #[serde(skip)]
+    syn_time: Option<DateTime<Utc>>,
+    #[serde(skip)]
+    syn_ack_seen: bool,
+}
+
+impl FlowStats {
+    /// Create stats for a new flow.
+    fn new(key: FlowKey, timestamp: DateTime<Utc>) -> Self {
+        Self {
+            key,
+            client: None,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            packets: 0,
+            bytes: 0,
+            bytes_a_to_b: 0,
+            bytes_b_to_a: 0,
+            data_segments: 0,
+            handshake_rtt: None,
+            retransmissions: 0,
+            duplicate_acks: 0,
+            out_of_order: 0,
+            zero_window_events: 0,
+            resets: 0,
+            process: None,
+            state: Default::default(),
+            syn_time: None,
+            syn_ack_seen: false,
+        }
+    }
+
+    /// Get the server endpoint (the SYN receiver, or the lower port if unknown).
+    pub fn server(&self) -> SocketAddr {
+        match self.client {
+            Some(client) if client == self.key.a => self.key.b,
+            Some(_) => self.key.a,
+            None if self.key.a.port() <= self.key.b.port() => self.key.a,
+            None => self.key.b,
+        }
+    }
+
+    /// Total number of TCP problems observed.
+    pub fn problems(&self) -> u64 {
+        self.retransmissions
+            + self.duplicate_acks
+            + self.out_of_order
+            + self.zero_window_events
+            + self.resets
+    }
+
+    /// Retransmissions as a percentage of data segments.
+    pub fn retransmission_rate(&self) -> f64 {
+        if self.data_segments == 0 {
+            return 0.0;
+        }
+        (self.retransmissions as f64 / self.data_segments as f64) * 100.0
+    }
+
+    /// Flow duration.
+    pub fn duration(&self) -> Duration {
+        (self.last_seen - self.first_seen)
+            .to_std()
+            .unwrap_or_default()
+    }
+
+    /// Update with a packet travelling in the given direction.
+    fn update(&mut self, packet: &DecodedPacket, a_to_b: bool) {
+        if self.process.is_none() {
+            self.process = packet.process.clone();
+        }
+        self.packets += 1;
+        self.bytes += packet.length as u64;
+        if a_to_b {
+            self.bytes_a_to_b += packet.length as u64;
+        } else {
+            self.bytes_b_to_a += packet.length as u64;
+        }
+        if packet.timestamp > self.last_seen {
+            self.last_seen = packet.timestamp;
+        }
+
+        if self.key.is_tcp() {
+            self.update_tcp(packet, a_to_b);
+        }
+    }
+
+    /// Run TCP analysis for a segment.
+    fn update_tcp(&mut self, packet: &DecodedPacket, a_to_b: bool) {
+        let (Some(flags), Some(seq), Some(ack), Some(window)) = (
+            packet.tcp_flags,
+            packet.tcp_seq,
+            packet.tcp_ack,
+            packet.tcp_window,
+        ) else {
+            return;
+        };
+        let (from, to) = if a_to_b { (0, 1) } else { (1, 0) };
+        let sender = if a_to_b { self.key.a } else { self.key.b };
+        let ts = packet.timestamp;
+
+        if flags.rst {
+            self.resets += 1;
+            return;
+        }
+
+        // Handshake timing
+        if flags.syn && !flags.ack {
+            if self.syn_time.is_none() {
+                self.syn_time = Some(ts);
+                self.client = Some(sender);
+            }
+        } else if flags.syn && flags.ack {
+            self.syn_ack_seen = true;
+        } else if flags.ack && self.syn_ack_seen && self.client == Some(sender) {
+            if let (None, Some(syn_time)) = (self.handshake_rtt, self.syn_time) {
+                self.handshake_rtt = (ts - syn_time).to_std().ok();
+            }
+        }
+
+        // Sequence analysis
+        let seg_len = packet.payload_length as u32 + u32::from(flags.syn) + u32::from(flags.fin);
+        let reorder_window = self.handshake_rtt.unwrap_or(DEFAULT_REORDER_WINDOW);
+        let outstanding = self.state[to]
+            .next_seq
+            .is_some_and(|next| seq_lt(ack, next));
+        let state = &mut self.state[from];
+
+        if seg_len > 0 {
+            if packet.payload_length > 0 {
+                self.data_segments += 1;
+            }
+            match state.next_seq {
+                // Keep-alive probes resend the last byte and are not retransmissions
+                Some(next)
+                    if !flags.syn
+                        && !flags.fin
+                        && packet.payload_length <= 1
+                        && seq == next.wrapping_sub(1) => {}
+                Some(next) if seq_lt(seq, next) => {
+                    let recent = state
+                        .last_segment
+                        .and_then(|last| (ts - last).to_std().ok())
+                        .is_some_and(|gap| gap < reorder_window);
+                    if recent {
+                        self.out_of_order += 1;
+                    } else {
+                        self.retransmissions += 1;
+                    }
+                    let end = seq.wrapping_add(seg_len);
+                    if seq_lt(next, end) {
+                        state.next_seq = Some(end);
+                    }
+                }
+                _ => state.next_seq = Some(seq.wrapping_add(seg_len)),
+            }
+            state.last_segment = Some(ts);
+        } else if flags.ack
+            && !flags.fin
+            && state.last_ack == Some(ack)
+            && state.last_window == Some(window)
+            && outstanding
+        {
+            self.duplicate_acks += 1;
+        }
+
+        if flags.ack {
+            if window == 0 && !state.zero_window {
+                self.zero_window_events += 1;
+            }
+            state.zero_window = window == 0;
+            state.last_ack = Some(ack);
+            state.last_window = Some(window);
+        }
+    }
+}
+
+/// Kind of problem reported for a host.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum FlowIssue {
+    /// High retransmission rate.
+    Retransmissions,
+    /// Connections reset.
+    Resets,
+    /// Receiver advertised a zero window.
+    ZeroWindow,
+    /// Slow TCP handshake.
+    SlowHandshake,
+}
+
+/// A problem observed in traffic to a host.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowFinding {
+    /// Server address the problem relates to.
+    pub host: IpAddr,
+    /// Problem kind.
+    pub issue: FlowIssue,
+    /// Measured value (percentage, count or milliseconds depending on the issue).
+    pub value: f64,
+    /// Number of flows affected.
+    pub flows: usize,
+}
+
+impl std::fmt::Display for FlowFinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.issue {
+            FlowIssue::Retransmissions => {
+                write!(f, "{:.0}% retransmits to {}", self.value, self.host)?;
+            }
+            FlowIssue::Resets => write!(f, "{:.0} resets with {}", self.value, self.host)?,
+            FlowIssue::ZeroWindow => {
+                write!(f, "{:.0} zero-window events with {}", self.value, self.host)?;
+            }
+            FlowIssue::SlowHandshake => {
+                write!(f, "{:.0} ms handshake RTT to {}", self.value, self.host)?;
+            }
+        }
+        write!(
+            f,
+            " ({} flow{})",
+            self.flows,
+            if self.flows == 1 { "" } else { "s" }
+        )
+    }
+}
+
+/// Traffic attributed to one local process.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    /// The process.
+    pub process: ProcessInfo,
+    /// Number of flows.
+    pub flows: usize,
+    /// Total packets.
+    pub packets: u64,
+    /// Total bytes.
+    pub bytes: u64,
+}
+
+/// Table of flows keyed by 5-tuple.
+///
+/// Timeouts are measured in packet time. Flows idle or open longer than the
+/// timeouts are closed and kept as finished flows, which the summaries still
+/// include until [`FlowTable::drain_finished`] takes them. Once either the
+/// open or the finished flows reach `max_flows` the least recently seen are
+/// evicted to make room, so a long capture or a port scan cannot grow the
+/// table without limit.
+#[derive(Debug, Clone)]
+pub struct FlowTable {
+    flows: HashMap<FlowKey, FlowStats>,
+    idle_timeout: Duration,
+    active_timeout: Duration,
+    max_flows: usize,
+    finished: Vec<FlowStats>,
+    last_sweep: Option<DateTime<Utc>>,
+    evicted: u64,
+}
+
+impl Default for FlowTable {
+    fn default() -> Self {
+        Self {
+            flows: HashMap::new(),
+            idle_timeout: DEFAULT_IDLE_TIMEOUT,
+            active_timeout: DEFAULT_ACTIVE_TIMEOUT,
+            max_flows: DEFAULT_MAX_FLOWS,
+            finished: Vec::new(),
+            last_sweep: None,
+            evicted: 0,
+        }
+    }
+}
+
+impl FlowTable {
+    /// Create an empty flow table with the default limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close flows without packets for this long.
+    pub fn with_idle_timeout(mut self, timeout: Duration) -> Self {
+        self.idle_timeout = timeout;
+        self
+    }
+
+    /// Close flows that have been open for this long.
+    pub fn with_active_timeout(mut self, timeout: Duration) -> Self {
+        self.active_timeout = timeout;
+        self
+    }
+
+    /// Keep at most this many open flows, and as many finished ones (at least one).
+    pub fn with_max_flows(mut self, max_flows: usize) -> Self {
+        self.max_flows = max_flows.max(1);
+        self
+    }
+
+    /// Update the table with a decoded packet.
+    pub fn update(&mut self, packet: &DecodedPacket) {
+        let expired = self.expire(packet.timestamp);
+        self.finish(expired);
+
+        let Some((key, a_to_b)) = FlowKey::from_packet(packet) else {
+            return;
+        };
+        if !self.flows.contains_key(&key) && self.flows.len() >= self.max_flows {
+            self.evict_oldest();
+        }
+        self.flows
+            .entry(key)
+            .or_insert_with(|| FlowStats::new(key, packet.timestamp))
+            .update(packet, a_to_b);
+    }
+
+    /// Remove flows that have exceeded the idle or active timeout at `now`,
+    /// returning them instead of keeping them as finished flows.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> Vec<FlowStats> {
+        // Sweep at most once per second
+        if self
+            .last_sweep
+            .is_some_and(|last| (now - last).num_milliseconds() < 1000)
+        {
+            return Vec::new();
+        }
+        self.last_sweep = Some(now);
+
+        let idle = chrono::Duration::from_std(self.idle_timeout).unwrap_or(chrono::Duration::MAX);
+        let active =
+            chrono::Duration::from_std(self.active_timeout).unwrap_or(chrono::Duration::MAX);
+
+        let expired: Vec<FlowKey> = self
+            .flows
+            .values()
+            .filter(|flow| now - flow.last_seen >= idle || now - flow.first_seen >= active)
+            .map(|flow| flow.key)
+            .collect();
+        expired
+            .iter()
+            .filter_map(|key| self.flows.remove(key))
+            .collect()
+    }
+
+    /// Keep flows closed by the timeouts, dropping the earliest finished once
+    /// there are more than `max_flows`.
+    fn finish(&mut self, flows: Vec<FlowStats>) {
+        self.finished.extend(flows);
+        let excess = self.finished.len().saturating_sub(self.max_flows);
+        if excess > 0 {
+            self.finished.drain(..excess);
+            self.evicted += excess as u64;
+        }
+    }
+
+    /// Take the flows closed by the timeouts so far, e.g. to export them.
+    pub fn drain_finished(&mut self) -> Vec<FlowStats> {
+        std::mem::take(&mut self.finished)
+    }
+
+    /// Evict the least recently seen flows, a sixteenth of the table at a
+    /// time so a full table is not rescanned for every new flow.
+    fn evict_oldest(&mut self) {
+        let count = (self.max_flows / 16).clamp(1, self.flows.len());
+        let mut by_age: Vec<_> = self
+            .flows
+            .values()
+            .map(|flow| (flow.last_seen, flow.key))
+            .collect();
+        by_age.select_nth_unstable_by_key(count - 1, |(last_seen, _)| *last_seen);
+        for (_, key) in &by_age[..count] {
+            self.flows.remove(key);
+        }
+        self.evicted += count as u64;
+    }
+
+    /// Flows evicted to stay within `max_flows`.
+    pub fn evicted(&self) -> u64 {
+        self.evicted
+    }
+
+    /// Number of flows, open and finished.
+    pub fn len(&self) -> usize {
+        self.flows.len() + self.finished.len()
+    }
+
+    /// Check if the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.flows.is_empty() && self.finished.is_empty()
+    }
+
+    /// Look up an open flow.
+    pub fn get(&self, key: &FlowKey) -> Option<&FlowStats> {
+        self.flows.get(key)
+    }
+
+    /// Iterate over all flows, open and finished.
+    pub fn flows(&self) -> impl Iterator<Item = &FlowStats> {
+        self.flows.values().chain(&self.finished)
+    }
+
+    /// Top N conversations by bytes.
+    pub fn top_by_bytes(&self, n: usize) -> Vec<&FlowStats> {
+        let mut flows: Vec<_> = self.flows().collect();
+        flows.sort_by_key(|f| std::cmp::Reverse(f.bytes));
+        flows.truncate(n);
+        flows
+    }
+
+    /// Top N local processes by bytes, for flows with a known process.
+    pub fn top_processes(&self, n: usize) -> Vec<ProcessUsage> {
+        let mut usage: HashMap<&ProcessInfo, ProcessUsage> = HashMap::new();
+        for flow in self.flows() {
+            let Some(process) = &flow.process else {
+                continue;
+            };
+            let entry = usage.entry(process).or_insert_with(|| ProcessUsage {
+                process: process.clone(),
+                flows: 0,
+                packets: 0,
+                bytes: 0,
+            });
+            entry.flows += 1;
+            entry.packets += flow.packets;
+            entry.bytes += flow.bytes;
+        }
+
+        let mut usage: Vec<_> = usage.into_values().collect();
+        usage.sort_by_key(|u| std::cmp::Reverse(u.bytes));
+        usage.truncate(n);
+        usage
+    }
+
+    /// Top N conversations by number of TCP problems.
+    pub fn top_by_problems(&self, n: usize) -> Vec<&FlowStats> {
+        let mut flows: Vec<_> = self.flows().filter(|f| f.problems() > 0).collect();
+        flows.sort_by_key(|f| std::cmp::Reverse(f.problems()));
+        flows.truncate(n);
+        flows
+    }
+
+    /// Summarise problems per server host, grouped by issue with the worst first.
+    pub fn findings(&self) -> Vec<FlowFinding> {
+        #[derive(Default)]
+        struct HostTotals {
+            flows: usize,
+            data_segments: u64,
+            retransmissions: u64,
+            resets: u64,
+            zero_windows: u64,
+            handshakes: Vec<Duration>,
+        }
+
+        let mut hosts: HashMap<IpAddr, HostTotals> = HashMap::new();
+        for flow in self.flows().filter(|f| f.key.is_tcp()) {
+            let totals = hosts.entry(flow.server().ip()).or_default();
+            totals.flows += 1;
+            totals.data_segments += flow.data_segments;
+            totals.retransmissions += flow.retransmissions;
+            totals.resets += flow.resets;
+            totals.zero_windows += flow.zero_window_events;
+            totals.handshakes.extend(flow.handshake_rtt);
+        }
+
+        let mut findings = Vec::new();
+        for (host, totals) in hosts {
+            let finding = |issue, value| FlowFinding {
+                host,
+                issue,
+                value,
+                flows: totals.flows,
+            };
+
+            if totals.data_segments > 0 {
+                let rate = totals.retransmissions as f64 / totals.data_segments as f64 * 100.0;
+                if rate >= RETRANSMIT_WARN_PERCENT {
+                    findings.push(finding(FlowIssue::Retransmissions, rate));
+                }
+            }
+            if totals.resets > 0 {
+                findings.push(finding(FlowIssue::Resets, totals.resets as f64));
+            }
+            if totals.zero_windows > 0 {
+                findings.push(finding(FlowIssue::ZeroWindow, totals.zero_windows as f64));
+            }
+            if !totals.handshakes.is_empty() {
+                let avg =
+                    totals.handshakes.iter().sum::<Duration>() / totals.handshakes.len() as u32;
+                if avg >= SLOW_HANDSHAKE {
+                    findings.push(finding(
+                        FlowIssue::SlowHandshake,
+                        avg.as_secs_f64() * 1000.0,
+                    ));
+                }
+            }
+        }
+
+        findings.sort_by(|a, b| a.issue.cmp(&b.issue).then(b.value.total_cmp(&a.value)));
+        findings
+    }
+}
+
+/// Wrap-aware sequence number comparison (`a` before `b`).
+fn seq_lt(a: u32, b: u32) -> bool {
+    (a.wrapping_sub(b) as i32) < 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::ProtocolDecoder;
+    use chrono::TimeZone;
+    use etherparse::PacketBuilder;
+
+    const CLIENT: [u8; 4] = [192, 168, 1, 10];
+    const SERVER: [u8; 4] = [10, 0, 0, 5];
+
+    /// Segment description: (from client?, ms offset, seq, ack, flags, window, payload length).
+    type Segment = (bool, i64, u32, u32, &'static str, u16, usize);
+
+    fn segment(spec: Segment) -> DecodedPacket {
+        let (from_client, ms, seq, ack, flags, window, len) = spec;
+        let (src, dst, sport, dport) = if from_client {
+            (CLIENT, SERVER, 50000, 443)
+        } else {
+            (SERVER, CLIENT, 443, 50000)
+        };
+        let mut builder = PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4(src, dst, 64)
+            .tcp(sport, dport, seq, window);
+        for flag in flags.chars() {
+            builder = match flag {
+                'S' => builder.syn(),
+                'A' => builder.ack(ack),
+                'F' => builder.fin(),
+                'R' => builder.rst(),
+                _ => builder,
+            };
+        }
+        let payload = vec![0u8; len];
+        let mut frame = Vec::new();
+        builder.write(&mut frame, &payload).unwrap();
+
+        let ts = Utc.timestamp_opt(1_700_000_000, 0).unwrap() + chrono::Duration::milliseconds(ms);
+        ProtocolDecoder::new().decode(&frame, ts)
+    }
+
+    fn table(segments: &[Segment]) -> FlowTable {
+        let mut table = FlowTable::new();
+        for spec in segments {
+            table.update(&segment(*spec));
+        }
+        table
+    }
+
+    #[test]
+    fn test_handshake_and_retransmission() {
+        let table = table(&[
+            (true, 0, 1000, 0, "S", 65535, 0),
+            (false, 40, 5000, 1001, "SA", 65535, 0),
+            (true, 80, 1001, 5001, "A", 65535, 0),
+            (true, 90, 1001, 5001, "A", 65535, 100),
+            (true, 400, 1001, 5001, "A", 65535, 100),
+            (false, 450, 5001, 1101, "A", 65535, 0),
+        ]);
+
+        assert_eq!(table.len(), 1);
+        let flow = table.flows().next().unwrap();
+        assert_eq!(flow.handshake_rtt, Some(Duration::from_millis(80)));
+        assert_eq!(flow.server().port(), 443);
+        assert_eq!(flow.data_segments, 2);
+        assert_eq!(flow.retransmissions, 1);
+        assert_eq!(flow.out_of_order, 0);
+
+        let findings = table.findings();
+        assert_eq!(findings[0].issue, FlowIssue::Retransmissions);
+        assert_eq!(
+            findings[0].to_string(),
+            "50% retransmits to 10.0.0.5 (1 flow)"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_acks_and_zero_window() {
+        let table = table(&[
+            (false, 0, 5000, 1000, "A", 65535, 100),
+            (false, 1, 5100, 1000, "A", 65535, 100),
+            (true, 2, 1000, 5100, "A", 8192, 0),
+            (true, 3, 1000, 5100, "A", 8192, 0),
+            (true, 4, 1000, 5100, "A", 8192, 0),
+            (true, 5, 1000, 5200, "A", 0, 0),
+            (true, 6, 1000, 5200, "R", 0, 0),
+        ]);
+
+        let flow = table.flows().next().unwrap();
+        assert_eq!(flow.duplicate_acks, 2);
+        assert_eq!(flow.zero_window_events, 1);
+        assert_eq!(flow.resets, 1);
+        assert_eq!(table.top_by_problems(5).len(), 1);
+    }
+
+    #[test]
+    fn test_out_of_order_and_keepalive() {
+        let table = table(&[
+            (true, 0, 1000, 1, "A", 65535, 100),
+            (true, 1, 1200, 1, "A", 65535, 100),
+            (true, 2, 1100, 1, "A", 65535, 100),
+            (true, 5000, 1299, 1, "A", 65535, 1),
+        ]);
+
+        let flow = table.flows().next().unwrap();
+        assert_eq!(flow.out_of_order, 1);
+        assert_eq!(flow.retransmissions, 0);
+    }
+
+    #[test]
+    fn test_syn_and_fin_retransmissions() {
+        let syn = table(&[
+            (true, 0, 1000, 0, "S", 65535, 0),
+            (true, 1000, 1000, 0, "S", 65535, 0),
+        ]);
+        assert_eq!(syn.flows().next().unwrap().retransmissions, 1);
+
+        let fin = table(&[
+            (true, 0, 1000, 1, "A", 65535, 100),
+            (true, 10, 1100, 1, "FA", 65535, 0),
+            (true, 1000, 1100, 1, "FA", 65535, 0),
+        ]);
+        assert_eq!(fin.flows().next().unwrap().retransmissions, 1);
+    }
+
+    #[test]
+    fn test_top_processes() {
+        let browser = ProcessInfo {
+            pid: 42,
+            name: "firefox".to_string(),
+            exe: None,
+        };
+        let mut table = FlowTable::new();
+        // Attribution may only succeed after the first packet
+        table.update(&segment((true, 0, 1000, 0, "S", 65535, 0)));
+        for spec in [
+            (false, 10, 5000, 1001, "SA", 65535, 0),
+            (true, 20, 1001, 5001, "A", 65535, 500),
+        ] {
+            let mut packet = segment(spec);
+            packet.process = Some(browser.clone());
+            table.update(&packet);
+        }
+
+        let usage = table.top_processes(5);
+        assert_eq!(usage.len(), 1);
+        assert_eq!(usage[0].process, browser);
+        assert_eq!(usage[0].flows, 1);
+        assert_eq!(usage[0].packets, 3);
+    }
+
+    #[test]
+    fn test_flow_timeouts() {
+        let mut table = FlowTable::new()
+            .with_idle_timeout(Duration::from_secs(10))
+            .with_active_timeout(Duration::from_secs(60));
+        table.update(&segment((true, 0, 1000, 0, "S", 65535, 0)));
+        assert_eq!(table.len(), 1);
+
+        // Quiet for longer than the idle timeout
+        let now = segment((true, 11_000, 0, 0, "", 0, 0)).timestamp;
+        assert_eq!(table.expire(now).len(), 1);
+        assert!(table.is_empty());
+
+        // Busy, but open for longer than the active timeout
+        let mut table = FlowTable::new().with_active_timeout(Duration::from_secs(2));
+        for ms in (0..=3000).step_by(500) {
+            table.update(&segment((true, ms, 1000, 1, "A", 65535, 0)));
+        }
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.evicted(), 0);
+        let finished = table.drain_finished();
+        assert_eq!(finished.len(), 1);
+        assert_eq!(finished[0].packets, 4);
+        assert_eq!(table.len(), 1);
+        let flow = table.flows().next().unwrap();
+        assert_eq!(flow.packets, 3);
+    }
+
+    #[test]
+    fn test_summary_keeps_timed_out_flows() {
+        let mut table = FlowTable::new().with_idle_timeout(Duration::from_secs(10));
+        let mut early = segment((true, 0, 1000, 0, "A", 65535, 500));
+        early.src_port = Some(40000);
+        table.update(&early);
+        // A later flow in a capture longer than the idle timeout
+        for ms in (20_000..=30_000).step_by(1000) {
+            table.update(&segment((true, ms, 1000, 1, "A", 65535, 100)));
+        }
+
+        assert_eq!(table.len(), 2);
+        let ports: Vec<_> = table
+            .top_by_bytes(5)
+            .iter()
+            .map(|flow| flow.key.a.port().max(flow.key.b.port()))
+            .collect();
+        assert!(ports.contains(&40000));
+        assert_eq!(table.evicted(), 0);
+    }
+
+    #[test]
+    fn test_max_flows_evicts_oldest() {
+        let mut table = FlowTable::new().with_max_flows(4);
+        for port in 0..6u16 {
+            let mut packet = segment((true, i64::from(port), 1000, 0, "S", 65535, 0));
+            packet.src_port = Some(40000 + port);
+            table.update(&packet);
+        }
+
+        assert_eq!(table.len(), 4);
+        assert_eq!(table.evicted(), 2);
+        let mut ports: Vec<_> = table
+            .flows()
+            .map(|flow| flow.key.a.port().max(flow.key.b.port()))
+            .collect();
+        ports.sort_unstable();
+        assert_eq!(ports, [40002, 40003, 40004, 40005]);
+    }
+}
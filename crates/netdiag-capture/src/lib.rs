@@ -4,25 +4,67 @@
 //!
 //! Provides pcap-based packet capture with:
 //! - Live interface capture
-//! - Protocol decoding (Ethernet, IP, TCP, UDP, ICMP, DNS, HTTP)
+//! - Offline analysis of PCAP/PCAPNG files
+//! - Protocol decoding (Ethernet, IP, TCP, UDP, ICMP)
+//! - IPv6 extension header traversal and IP fragment reassembly
+//! - VLAN and tunnel (GRE, VXLAN, GENEVE, IP-in-IP, WireGuard) decapsulation
+//! - Application-layer dissection (DNS, DHCP, TLS, HTTP)
 //! - BPF filter support
+//! - Filter language with display fields, evaluated in userspace where BPF can't
 //! - Packet statistics
+//! - TCP flow tracking (handshake RTT, retransmissions, zero windows, resets)
+//! - Flow record export as IPFIX, NetFlow v9 or JSON lines
+//! - Passive DNS latency and failure measurement
+//! - Attribution of flows to local processes (Linux)
 //! - PCAP file reading/writing
+//! - Rolling ring-buffer output with size/time rotation
+//! - Flight recorder that saves packets from around a trigger
+//! - Anonymisation of live output and existing files for sharing captures
+//! - 802.11 monitor-mode analysis (radiotap): retry rates, airtime, beacons,
+//!   deauthentication bursts and probe requests
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
+mod anonymize;
 mod capture;
 mod decode;
+mod dissect;
+mod dns;
 mod error;
+mod export;
 mod filter;
+mod flow;
+mod process;
+mod reassembly;
+mod recorder;
+mod ring;
 mod stats;
+mod tunnel;
+mod wlan;
 
+pub use anonymize::{AnonymizationPolicy, Anonymizer, MacPolicy, PayloadPolicy};
 pub use capture::{CaptureConfig, CaptureHandle, PacketCapture};
-pub use decode::{DecodedPacket, Protocol, ProtocolDecoder};
+pub use decode::{DecodedPacket, Ipv6Extension, LinkType, Protocol, ProtocolDecoder};
+pub use dissect::{
+    record_type_name, tls_version_name, ApplicationData, DhcpInfo, DhcpMessageType, DnsInfo,
+    DnsQuestion, DnsRecord, DnsResponseCode, HttpInfo, TlsHandshakeType, TlsInfo,
+};
+pub use dns::{DnsLatencyStats, DnsTracker, ResponseTimeHistogram};
 pub use error::{CaptureError, CaptureResult};
-pub use filter::CaptureFilter;
-pub use stats::{CaptureStats, ProtocolStats};
+pub use export::{FlowEndReason, FlowExportConfig, FlowExportTarget, FlowExporter, FlowRecord};
+pub use filter::{
+    compile_filter, CaptureFilter, CompareOp, CompiledFilter, Direction, Field, FilterExpr,
+    FilterValue, ProtoMatch,
+};
+pub use flow::{FlowFinding, FlowIssue, FlowKey, FlowStats, FlowTable, ProcessUsage};
+pub use process::{ProcessInfo, ProcessResolver};
+pub use reassembly::FragmentReassembler;
+pub use recorder::{FlightRecorder, FlightRecorderConfig};
+pub use ring::{RingBufferConfig, RingBufferWriter, HEADER_SNAPLEN};
+pub use stats::{CaptureStats, DeviceSummary, FragmentStats, ProtocolStats, TrafficCounter};
+pub use tunnel::{TunnelInfo, TunnelKind};
+pub use wlan::{BeaconBody, Dot11Frame, FrameType, RadiotapHeader, RadiotapMcs, WlanAnalyzer};
 
 use pcap::Device;
 
@@ -0,0 +1,522 @@
+//! Attribution of traffic to local processes.
+//!
+//! On Linux, sockets from `/proc/net/{tcp,udp,tcp6,udp6}` are matched to
+//! processes through the `socket:[inode]` links in `/proc/<pid>/fd`. Other
+//! platforms resolve nothing.
+
+use crate::decode::DecodedPacket;
+use crate::error::CaptureResult;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Minimum time between socket table reloads triggered by lookup misses.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// A local process owning a socket.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    /// Process ID.
+    pub pid: u32,
+    /// Command name.
+    pub name: String,
+    /// Executable path, if readable.
+    pub exe: Option<PathBuf>,
+}
+
+impl std::fmt::Display for ProcessInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ({})", self.name, self.pid)
+    }
+}
+
+/// A socket from the kernel socket tables.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SocketEntry {
+    protocol: u8,
+    local: SocketAddr,
+    /// Unset for listening and unconnected sockets.
+    remote: Option<SocketAddr>,
+    inode: u64,
+}
+
+/// A snapshot of the socket tables.
+#[derive(Debug, Default)]
+struct SocketTable {
+    /// Sockets keyed by protocol and local port.
+    sockets: HashMap<(u8, u16), Vec<(SocketEntry, ProcessInfo)>>,
+    /// Addresses sockets are bound to, so known to be local.
+    addresses: HashSet<IpAddr>,
+}
+
+/// Reloads the socket tables on a separate thread.
+#[derive(Debug)]
+struct Refresher {
+    requests: mpsc::Sender<()>,
+    tables: mpsc::Receiver<SocketTable>,
+}
+
+/// Maps local sockets to the processes that own them.
+#[derive(Debug)]
+pub struct ProcessResolver {
+    proc_root: PathBuf,
+    table: SocketTable,
+    /// Addresses of this host's interfaces.
+    local_addresses: HashSet<IpAddr>,
+    last_refresh: Option<Instant>,
+    refresher: Option<Refresher>,
+}
+
+impl Default for ProcessResolver {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProcessResolver {
+    /// Create a resolver reading `/proc`.
+    pub fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    /// Create a resolver reading a different procfs mount.
+    pub fn with_proc_root(root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: root.into(),
+            table: SocketTable::default(),
+            local_addresses: HashSet::new(),
+            last_refresh: None,
+            refresher: None,
+        }
+    }
+
+    /// Treat these addresses as local, besides loopback and the addresses
+    /// sockets are bound to.
+    pub fn with_local_addresses(mut self, addresses: impl IntoIterator<Item = IpAddr>) -> Self {
+        self.local_addresses
+            .extend(addresses.into_iter().map(canonical));
+        self
+    }
+
+    /// Reload the socket tables on a separate thread rather than inside
+    /// [`lookup_packet`](Self::lookup_packet), so reading `/proc` never
+    /// stalls packet processing. The first load starts right away.
+    pub fn with_background_refresh(mut self) -> Self {
+        let (requests, pending) = mpsc::channel::<()>();
+        let (loaded, tables) = mpsc::channel();
+        let proc_root = self.proc_root.clone();
+        std::thread::spawn(move || loop {
+            let table = load_socket_table(&proc_root).unwrap_or_else(|e| {
+                debug!("Failed to read socket tables: {}", e);
+                SocketTable::default()
+            });
+            if loaded.send(table).is_err() || pending.recv().is_err() {
+                break;
+            }
+            // Requests queued during the load are served by the next one
+            while pending.try_recv().is_ok() {}
+        });
+        self.last_refresh = Some(Instant::now());
+        self.refresher = Some(Refresher { requests, tables });
+        self
+    }
+
+    /// Check if process attribution works on this platform.
+    pub fn is_supported() -> bool {
+        cfg!(target_os = "linux")
+    }
+
+    /// Number of sockets with a known owner.
+    pub fn len(&self) -> usize {
+        self.table.sockets.values().map(Vec::len).sum()
+    }
+
+    /// Check if no sockets are known.
+    pub fn is_empty(&self) -> bool {
+        self.table.sockets.is_empty()
+    }
+
+    /// Reload the socket tables and their owning processes.
+    ///
+    /// Sockets of processes we may not inspect (other users, without root)
+    /// are skipped.
+    pub fn refresh(&mut self) -> CaptureResult<()> {
+        self.last_refresh = Some(Instant::now());
+        self.table = load_socket_table(&self.proc_root)?;
+        debug!("Attributed {} sockets to processes", self.len());
+        Ok(())
+    }
+
+    /// Check if an address belongs to this host.
+    fn is_local(&self, ip: IpAddr) -> bool {
+        let ip = canonical(ip);
+        ip.is_loopback() || self.local_addresses.contains(&ip) || self.table.addresses.contains(&ip)
+    }
+
+    /// Find the process owning a local socket talking to `remote`.
+    ///
+    /// Connected sockets are preferred; otherwise, if `local` is an address of
+    /// this host, a listening or unconnected socket bound to it (or to any
+    /// address) matches.
+    pub fn lookup(
+        &self,
+        protocol: u8,
+        local: SocketAddr,
+        remote: SocketAddr,
+    ) -> Option<&ProcessInfo> {
+        let candidates = self.table.sockets.get(&(protocol, local.port()))?;
+
+        candidates
+            .iter()
+            .find(|(socket, _)| {
+                socket.remote.is_some_and(|peer| {
+                    peer.port() == remote.port() && same_ip(peer.ip(), remote.ip())
+                }) && same_ip(socket.local.ip(), local.ip())
+            })
+            .or_else(|| {
+                if !self.is_local(local.ip()) {
+                    return None;
+                }
+                candidates.iter().find(|(socket, _)| {
+                    socket.remote.is_none()
+                        && (socket.local.ip().is_unspecified()
+                            || same_ip(socket.local.ip(), local.ip()))
+                })
+            })
+            .map(|(_, process)| process)
+    }
+
+    /// Find the local process sending or receiving a packet.
+    ///
+    /// Misses reload the socket tables, at most once a second, so new
+    /// connections are picked up. With a background refresh the reload is
+    /// only requested here and used by later packets.
+    pub fn lookup_packet(&mut self, packet: &DecodedPacket) -> Option<ProcessInfo> {
+        if let Some(table) = self
+            .refresher
+            .as_ref()
+            .and_then(|refresher| refresher.tables.try_iter().last())
+        {
+            self.table = table;
+        }
+
+        let protocol = packet.ip_protocol?;
+        let src = SocketAddr::new(packet.src_ip?, packet.src_port?);
+        let dst = SocketAddr::new(packet.dst_ip?, packet.dst_port?);
+
+        let find = |resolver: &Self| {
+            resolver
+                .lookup(protocol, src, dst)
+                .or_else(|| resolver.lookup(protocol, dst, src))
+                .cloned()
+        };
+
+        if let Some(process) = find(self) {
+            return Some(process);
+        }
+        let stale = self
+            .last_refresh
+            .map_or(true, |at| at.elapsed() >= REFRESH_INTERVAL);
+        if !stale {
+            return None;
+        }
+        if let Some(refresher) = &self.refresher {
+            self.last_refresh = Some(Instant::now());
+            let _ = refresher.requests.send(());
+            return None;
+        }
+        if let Err(e) = self.refresh() {
+            debug!("Failed to read socket tables: {}", e);
+        }
+        find(self)
+    }
+}
+
+/// Read the socket tables and the processes owning each socket.
+fn load_socket_table(proc_root: &Path) -> CaptureResult<SocketTable> {
+    let mut by_inode: HashMap<u64, Vec<SocketEntry>> = HashMap::new();
+    for (file, protocol, v6) in [
+        ("tcp", 6, false),
+        ("udp", 17, false),
+        ("tcp6", 6, true),
+        ("udp6", 17, true),
+    ] {
+        let Ok(content) = std::fs::read_to_string(proc_root.join("net").join(file)) else {
+            continue;
+        };
+        for entry in parse_socket_table(&content, protocol, v6) {
+            by_inode.entry(entry.inode).or_default().push(entry);
+        }
+    }
+
+    let mut table = SocketTable::default();
+    if by_inode.is_empty() {
+        return Ok(table);
+    }
+
+    for entry in std::fs::read_dir(proc_root)?.flatten() {
+        let Some(pid) = entry
+            .file_name()
+            .to_str()
+            .and_then(|s| s.parse::<u32>().ok())
+        else {
+            continue;
+        };
+        let Ok(fds) = std::fs::read_dir(entry.path().join("fd")) else {
+            continue;
+        };
+
+        let mut process = None;
+        for fd in fds.flatten() {
+            let Some(inode) = std::fs::read_link(fd.path())
+                .ok()
+                .and_then(|target| socket_inode(&target))
+            else {
+                continue;
+            };
+            let Some(sockets) = by_inode.get(&inode) else {
+                continue;
+            };
+            let process = process.get_or_insert_with(|| read_process(&entry.path(), pid));
+            for socket in sockets {
+                if !socket.local.ip().is_unspecified() {
+                    table.addresses.insert(canonical(socket.local.ip()));
+                }
+                table
+                    .sockets
+                    .entry((socket.protocol, socket.local.port()))
+                    .or_default()
+                    .push((*socket, process.clone()));
+            }
+        }
+    }
+
+    Ok(table)
+}
+
+/// Compare addresses, treating IPv4-mapped IPv6 as IPv4.
+fn same_ip(a: IpAddr, b: IpAddr) -> bool {
+    canonical(a) == canonical(b)
+}
+
+fn canonical(ip: IpAddr) -> IpAddr {
+    match ip {
+        IpAddr::V6(v6) => v6.to_ipv4_mapped().map_or(ip, IpAddr::V4),
+        IpAddr::V4(_) => ip,
+    }
+}
+
+/// Extract the inode from a `socket:[12345]` fd link.
+fn socket_inode(target: &Path) -> Option<u64> {
+    target
+        .to_str()?
+        .strip_prefix("socket:[")?
+        .strip_suffix(']')?
+        .parse()
+        .ok()
+}
+
+/// Read a process's name and executable.
+fn read_process(dir: &Path, pid: u32) -> ProcessInfo {
+    let name = std::fs::read_to_string(dir.join("comm"))
+        .map(|comm| comm.trim().to_string())
+        .unwrap_or_else(|_| pid.to_string());
+    ProcessInfo {
+        pid,
+        name,
+        exe: std::fs::read_link(dir.join("exe")).ok(),
+    }
+}
+
+/// Parse a `/proc/net/{tcp,udp,tcp6,udp6}` table.
+fn parse_socket_table(content: &str, protocol: u8, v6: bool) -> Vec<SocketEntry> {
+    content
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let local = parse_socket_addr(fields.get(1)?, v6)?;
+            let remote = parse_socket_addr(fields.get(2)?, v6)?;
+            let inode = fields.get(9)?.parse().ok()?;
+            // Inode 0 marks sockets in TIME_WAIT that no process owns
+            if inode == 0 {
+                return None;
+            }
+            Some(SocketEntry {
+                protocol,
+                local,
+                remote: (!remote.ip().is_unspecified() || remote.port() != 0).then_some(remote),
+                inode,
+            })
+        })
+        .collect()
+}
+
+/// Parse a kernel `ADDR:PORT` pair; addresses are 32-bit words in host byte order.
+fn parse_socket_addr(field: &str, v6: bool) -> Option<SocketAddr> {
+    let (addr, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+
+    let mut bytes = Vec::with_capacity(16);
+    for chunk in addr.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(chunk).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+
+    let ip = match (v6, bytes.len()) {
+        (false, 4) => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        (true, 16) => IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?)),
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TCP: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 1111 1 0000000000000000 100 0 0 10 0
+   1: 0A01A8C0:C350 22D8B85D:01BB 01 00000000:00000000 00:00000000 00000000  1000        0 2222 1 0000000000000000 20 4 30 10 -1
+   2: 0A01A8C0:C351 22D8B85D:01BB 06 00000000:00000000 03:00001234 00000000     0        0 0 3 0000000000000000
+";
+
+    #[test]
+    fn test_parse_socket_table() {
+        let entries = parse_socket_table(TCP, 6, false);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].local, "127.0.0.1:631".parse().unwrap());
+        assert_eq!(entries[0].remote, None);
+        assert_eq!(entries[1].local, "192.168.1.10:50000".parse().unwrap());
+        assert_eq!(
+            entries[1].remote,
+            Some("93.184.216.34:443".parse().unwrap())
+        );
+        assert_eq!(entries[1].inode, 2222);
+
+        let v6 = "header\n   0: 00000000000000000000000001000000:0035 00000000000000000000000000000000:0000 07 00000000:00000000 00:00000000 00000000   101        0 3333 2\n";
+        let entries = parse_socket_table(v6, 17, true);
+        assert_eq!(entries[0].local, "[::1]:53".parse().unwrap());
+    }
+
+    /// Write a fake procfs with a TCP table and processes owning one socket each.
+    #[cfg(unix)]
+    fn fake_proc(name: &str, tcp: &str, owners: &[(u32, &str, u64)]) -> PathBuf {
+        let root = std::env::temp_dir().join(format!("netdiag-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("net")).unwrap();
+        std::fs::write(root.join("net/tcp"), tcp).unwrap();
+        for (pid, name, inode) in owners {
+            let dir = root.join(pid.to_string());
+            std::fs::create_dir_all(dir.join("fd")).unwrap();
+            std::fs::write(dir.join("comm"), format!("{}\n", name)).unwrap();
+            std::os::unix::fs::symlink(format!("socket:[{}]", inode), dir.join("fd/3")).unwrap();
+        }
+        root
+    }
+
+    /// A TCP packet between two endpoints.
+    #[cfg(unix)]
+    fn tcp_packet(src: &str, dst: &str) -> DecodedPacket {
+        let (src, dst): (SocketAddr, SocketAddr) = (src.parse().unwrap(), dst.parse().unwrap());
+        let (IpAddr::V4(src_ip), IpAddr::V4(dst_ip)) = (src.ip(), dst.ip()) else {
+            unreachable!()
+        };
+        let mut frame = Vec::new();
+        etherparse::PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4(src_ip.octets(), dst_ip.octets(), 64)
+            .tcp(src.port(), dst.port(), 1, 65535)
+            .syn()
+            .write(&mut frame, &[])
+            .unwrap();
+        crate::decode::ProtocolDecoder::new().decode(&frame, chrono::Utc::now())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_resolve_processes_from_proc() {
+        let root = fake_proc("proc", TCP, &[(42, "firefox", 2222), (7, "cupsd", 1111)]);
+
+        let mut resolver = ProcessResolver::with_proc_root(&root);
+        resolver.refresh().unwrap();
+        std::fs::remove_dir_all(&root).ok();
+
+        assert_eq!(resolver.len(), 2);
+        let browser = resolver
+            .lookup(
+                6,
+                "192.168.1.10:50000".parse().unwrap(),
+                "93.184.216.34:443".parse().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(browser.pid, 42);
+        assert_eq!(browser.to_string(), "firefox (42)");
+
+        // Listening socket matches any peer
+        let server = resolver
+            .lookup(
+                6,
+                "127.0.0.1:631".parse().unwrap(),
+                "127.0.0.1:40000".parse().unwrap(),
+            )
+            .unwrap();
+        assert_eq!(server.name, "cupsd");
+        assert!(resolver
+            .lookup(
+                17,
+                "127.0.0.1:631".parse().unwrap(),
+                "127.0.0.1:1".parse().unwrap()
+            )
+            .is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_listener_only_matches_inbound_packets() {
+        // sshd listening on 0.0.0.0:22 next to firefox's connection
+        let tcp = format!(
+            "{}   3: 00000000:0016 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 4444 1\n",
+            TCP
+        );
+        let root = fake_proc(
+            "listener",
+            &tcp,
+            &[(42, "firefox", 2222), (9, "sshd", 4444)],
+        );
+        let mut resolver = ProcessResolver::with_proc_root(&root);
+        resolver.refresh().unwrap();
+        std::fs::remove_dir_all(&root).ok();
+
+        let inbound = tcp_packet("203.0.113.5:40000", "192.168.1.10:22");
+        assert_eq!(resolver.lookup_packet(&inbound).unwrap().name, "sshd");
+
+        // Outgoing SSH to another host is not the local sshd
+        let outbound = tcp_packet("192.168.1.10:50001", "203.0.113.5:22");
+        assert!(resolver.lookup_packet(&outbound).is_none());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_background_refresh() {
+        let root = fake_proc("background", TCP, &[(42, "firefox", 2222)]);
+        let mut resolver = ProcessResolver::with_proc_root(&root).with_background_refresh();
+
+        let packet = tcp_packet("192.168.1.10:50000", "93.184.216.34:443");
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let process = loop {
+            if let Some(process) = resolver.lookup_packet(&packet) {
+                break Some(process);
+            }
+            if Instant::now() >= deadline {
+                break None;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        };
+        std::fs::remove_dir_all(&root).ok();
+        assert_eq!(process.unwrap().pid, 42);
+    }
+}
@@ -0,0 +1,546 @@
+//! IP fragment reassembly.
+
+use crate::anonymize::fix_ipv4_header_checksum;
+use crate::decode::{ipv6_fragment, DecodedPacket, LinkType, ProtocolDecoder};
+use crate::stats::FragmentStats;
+use chrono::{DateTime, Utc};
+use etherparse::{Ipv6ExtensionSlice, NetSlice, SlicedPacket};
+use std::collections::{BTreeMap, HashMap};
+use std::net::IpAddr;
+use std::time::Duration;
+
+/// Default time to wait for the remaining fragments of a datagram.
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Default limit on datagrams being reassembled at once.
+const DEFAULT_MAX_PENDING: usize = 1024;
+
+/// Largest datagram that can be described by fragment offsets.
+const MAX_DATAGRAM: usize = 65535;
+
+/// Identifies the fragments of one datagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+struct FragmentKey {
+    src: IpAddr,
+    dst: IpAddr,
+    protocol: u8,
+    id: u32,
+}
+
+/// A datagram whose fragments are being collected.
+#[derive(Debug)]
+struct PendingDatagram {
+    /// Packet time of the first fragment.
+    first_seen: DateTime<Utc>,
+    /// IP header to rebuild the datagram with.
+    header: Vec<u8>,
+    /// Whether `header` came from the fragment at offset 0.
+    header_from_first: bool,
+    /// Fragment data by byte offset.
+    segments: BTreeMap<usize, Vec<u8>>,
+    /// Datagram payload length, once the last fragment is seen.
+    total_len: Option<usize>,
+}
+
+impl PendingDatagram {
+    /// Add a fragment, returning false if it overlaps data already held.
+    fn insert(&mut self, offset: usize, data: &[u8]) -> bool {
+        let end = offset + data.len();
+        let overlaps = self
+            .segments
+            .range(..end)
+            .next_back()
+            .is_some_and(|(start, existing)| start + existing.len() > offset);
+        if overlaps {
+            return false;
+        }
+        self.segments.insert(offset, data.to_vec());
+        true
+    }
+
+    /// Concatenate the payload if every byte has arrived.
+    fn payload(&self) -> Option<Vec<u8>> {
+        let total = self.total_len?;
+        let mut payload = Vec::with_capacity(total);
+        for (offset, data) in &self.segments {
+            if *offset != payload.len() {
+                return None;
+            }
+            payload.extend_from_slice(data);
+        }
+        (payload.len() == total).then_some(payload)
+    }
+}
+
+/// Fields of one fragment needed for reassembly.
+struct Fragment<'a> {
+    key: FragmentKey,
+    ipv6: bool,
+    offset: usize,
+    more: bool,
+    header: Vec<u8>,
+    data: &'a [u8],
+}
+
+/// Reassembles fragmented IPv4 and IPv6 datagrams.
+///
+/// Fragments are passed through as they arrive; the packet completing a
+/// datagram is replaced by the decoded reassembled datagram, so DNS, TLS
+/// and other dissectors see the whole payload. Timeouts are measured in
+/// packet time, so files are handled the same as live captures.
+#[derive(Debug)]
+pub struct FragmentReassembler {
+    timeout: Duration,
+    max_pending: usize,
+    pending: HashMap<FragmentKey, PendingDatagram>,
+    last_expiry: Option<DateTime<Utc>>,
+    stats: FragmentStats,
+}
+
+impl FragmentReassembler {
+    /// Create a reassembler with the default 30 second timeout.
+    pub fn new() -> Self {
+        Self {
+            timeout: DEFAULT_TIMEOUT,
+            max_pending: DEFAULT_MAX_PENDING,
+            pending: HashMap::new(),
+            last_expiry: None,
+            stats: FragmentStats::default(),
+        }
+    }
+
+    /// Set how long to wait for missing fragments.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Set the maximum number of datagrams held at once.
+    pub fn with_max_pending(mut self, max_pending: usize) -> Self {
+        self.max_pending = max_pending.max(1);
+        self
+    }
+
+    /// Fragmentation statistics so far.
+    pub fn stats(&self) -> &FragmentStats {
+        &self.stats
+    }
+
+    /// Number of datagrams waiting for fragments.
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Abandon all incomplete datagrams, e.g. at the end of a capture.
+    pub fn flush(&mut self) {
+        self.stats.timed_out += self.pending.len() as u64;
+        self.pending.clear();
+    }
+
+    /// Process a decoded packet and its raw data.
+    ///
+    /// Returns the packet unchanged unless it completes a datagram, in which
+    /// case the reassembled datagram is returned in its place.
+    pub fn reassemble(
+        &mut self,
+        decoder: &ProtocolDecoder,
+        packet: DecodedPacket,
+        data: &[u8],
+        link_type: LinkType,
+    ) -> DecodedPacket {
+        self.expire(packet.timestamp);
+        self.count_pmtu_message(&packet);
+
+        if packet.fragment.is_none() {
+            return packet;
+        }
+        // Fragments carried inside tunnels are left alone
+        let Some(fragment) = parse_fragment(data, link_type) else {
+            return packet;
+        };
+
+        if fragment.ipv6 {
+            self.stats.ipv6_fragments += 1;
+        } else {
+            self.stats.ipv4_fragments += 1;
+        }
+        self.stats.largest_fragment = self
+            .stats
+            .largest_fragment
+            .max(fragment.header.len() + fragment.data.len());
+
+        let end = fragment.offset + fragment.data.len();
+        if end > MAX_DATAGRAM {
+            return packet;
+        }
+
+        if !self.pending.contains_key(&fragment.key) && self.pending.len() >= self.max_pending {
+            self.evict_oldest();
+        }
+        let pending = self
+            .pending
+            .entry(fragment.key)
+            .or_insert_with(|| PendingDatagram {
+                first_seen: packet.timestamp,
+                header: fragment.header.clone(),
+                header_from_first: false,
+                segments: BTreeMap::new(),
+                total_len: None,
+            });
+
+        if !pending.insert(fragment.offset, fragment.data) {
+            self.stats.overlapping += 1;
+            return packet;
+        }
+        if fragment.offset == 0 && !pending.header_from_first {
+            pending.header = fragment.header;
+            pending.header_from_first = true;
+        }
+        if !fragment.more {
+            pending.total_len = Some(end);
+        }
+
+        let Some(payload) = pending.payload() else {
+            return packet;
+        };
+        let fragments = pending.segments.len();
+        let Some(pending) = self.pending.remove(&fragment.key) else {
+            return packet;
+        };
+
+        let datagram = rebuild(&pending.header, &fragment.key, fragment.ipv6, &payload);
+        self.stats.reassembled += 1;
+        self.stats.largest_datagram = self.stats.largest_datagram.max(datagram.len());
+
+        let mut reassembled = decoder.decode_link(&datagram, packet.timestamp, LinkType::RawIp);
+        reassembled.length = packet.length;
+        reassembled.capture_length = packet.capture_length;
+        reassembled.src_mac = packet.src_mac;
+        reassembled.dst_mac = packet.dst_mac;
+        reassembled.ether_type = packet.ether_type;
+        reassembled.vlans = packet.vlans;
+        reassembled.ipv6_extensions = packet.ipv6_extensions;
+        reassembled.fragment = packet.fragment;
+        reassembled.reassembled_fragments = fragments;
+        reassembled
+    }
+
+    /// Drop datagrams older than the timeout.
+    fn expire(&mut self, now: DateTime<Utc>) {
+        // Sweep at most once per second of packet time
+        if self
+            .last_expiry
+            .is_some_and(|last| (now - last).num_milliseconds() < 1000)
+        {
+            return;
+        }
+        self.last_expiry = Some(now);
+
+        let timeout = chrono::Duration::from_std(self.timeout).unwrap_or(chrono::Duration::MAX);
+        let before = self.pending.len();
+        self.pending
+            .retain(|_, pending| now - pending.first_seen <= timeout);
+        self.stats.timed_out += (before - self.pending.len()) as u64;
+    }
+
+    /// Abandon the oldest datagram to make room.
+    fn evict_oldest(&mut self) {
+        let oldest = self
+            .pending
+            .iter()
+            .min_by_key(|(_, pending)| pending.first_seen)
+            .map(|(key, _)| *key);
+        if let Some(key) = oldest {
+            self.pending.remove(&key);
+            self.stats.timed_out += 1;
+        }
+    }
+
+    /// Count ICMP messages that drive path MTU discovery.
+    fn count_pmtu_message(&mut self, packet: &DecodedPacket) {
+        let is_pmtu = match (packet.protocol, packet.icmp_type, packet.icmp_code) {
+            // Destination unreachable: fragmentation needed and DF set
+            (crate::Protocol::Icmp, Some(3), Some(4)) => true,
+            // Packet too big
+            (crate::Protocol::Icmpv6, Some(2), _) => true,
+            _ => false,
+        };
+        if is_pmtu {
+            self.stats.pmtu_messages += 1;
+        }
+    }
+}
+
+impl Default for FragmentReassembler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Extract fragment fields from the outermost IP header of a frame.
+fn parse_fragment(data: &[u8], link_type: LinkType) -> Option<Fragment<'_>> {
+    let sliced = match link_type {
+        LinkType::Ethernet => SlicedPacket::from_ethernet(data),
+        LinkType::LinuxSll => SlicedPacket::from_linux_sll(data),
+        LinkType::RawIp => SlicedPacket::from_ip(data),
+    }
+    .ok()?;
+
+    match sliced.net? {
+        NetSlice::Ipv4(ipv4) => {
+            let header = ipv4.header();
+            if !ipv4.is_payload_fragmented() {
+                return None;
+            }
+            Some(Fragment {
+                key: FragmentKey {
+                    src: IpAddr::from(header.source()),
+                    dst: IpAddr::from(header.destination()),
+                    protocol: header.protocol().0,
+                    id: u32::from(header.identification()),
+                },
+                ipv6: false,
+                offset: usize::from(header.fragments_offset().byte_offset()),
+                more: header.more_fragments(),
+                header: header.slice().to_vec(),
+                data: ipv4.payload().payload,
+            })
+        }
+        NetSlice::Ipv6(ipv6) => {
+            let header = ipv6.header();
+            let (next_header, info) =
+                ipv6.extensions()
+                    .clone()
+                    .into_iter()
+                    .find_map(|ext| match ext {
+                        Ipv6ExtensionSlice::Fragment(fragment) => {
+                            Some((fragment.next_header().0, ipv6_fragment(fragment.slice())?))
+                        }
+                        _ => None,
+                    })?;
+            Some(Fragment {
+                key: FragmentKey {
+                    src: IpAddr::from(header.source()),
+                    dst: IpAddr::from(header.destination()),
+                    protocol: next_header,
+                    id: info.id,
+                },
+                ipv6: true,
+                offset: usize::from(info.offset),
+                more: info.more_fragments,
+                header: header.slice().to_vec(),
+                data: ipv6.payload().payload,
+            })
+        }
+    }
+}
+
+/// Build an unfragmented datagram from a fragment's IP header and the full payload.
+///
+/// IPv6 extension headers before the fragment header are not carried over;
+/// the decoded fragment already records them.
+fn rebuild(header: &[u8], key: &FragmentKey, ipv6: bool, payload: &[u8]) -> Vec<u8> {
+    let mut datagram = Vec::with_capacity(header.len() + payload.len());
+    datagram.extend_from_slice(header);
+    datagram.extend_from_slice(payload);
+
+    if ipv6 {
+        let length = payload.len().min(usize::from(u16::MAX)) as u16;
+        datagram[4..6].copy_from_slice(&length.to_be_bytes());
+        datagram[6] = key.protocol;
+    } else {
+        let length = datagram.len().min(usize::from(u16::MAX)) as u16;
+        datagram[2..4].copy_from_slice(&length.to_be_bytes());
+        // Keep DF, clear MF and the offset
+        datagram[6] &= 0x40;
+        datagram[7] = 0;
+        fix_ipv4_header_checksum(&mut datagram, 0);
+    }
+    datagram
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::{Ipv6Extension, Protocol};
+    use crate::dissect::ApplicationData;
+    use chrono::TimeZone;
+    use etherparse::PacketBuilder;
+
+    /// A DNS response with `answers` A records, too large for one packet.
+    fn large_dns_response(answers: u16) -> Vec<u8> {
+        let mut msg = vec![0x12, 0x34, 0x81, 0x80, 0, 1];
+        msg.extend_from_slice(&answers.to_be_bytes());
+        msg.extend_from_slice(&[0, 0, 0, 0]);
+        msg.extend_from_slice(b"\x07example\x03com\x00\x00\x01\x00\x01");
+        for i in 0..answers {
+            msg.extend_from_slice(&[0xC0, 0x0C, 0, 1, 0, 1, 0, 0, 0x0E, 0x10, 0, 4, 10, 0]);
+            msg.extend_from_slice(&i.to_be_bytes());
+        }
+        msg
+    }
+
+    /// Split a raw IPv4 datagram into fragments of at most `size` payload bytes.
+    fn fragment_ipv4(datagram: &[u8], size: usize) -> Vec<Vec<u8>> {
+        let header_len = usize::from(datagram[0] & 0x0F) * 4;
+        let payload = &datagram[header_len..];
+        payload
+            .chunks(size)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let offset = i * size;
+                let more = offset + chunk.len() < payload.len();
+                let mut fragment = datagram[..header_len].to_vec();
+                fragment.extend_from_slice(chunk);
+                let length = fragment.len() as u16;
+                fragment[2..4].copy_from_slice(&length.to_be_bytes());
+                fragment[4..6].copy_from_slice(&0xBEEFu16.to_be_bytes());
+                let flags = (offset / 8) as u16 | if more { 0x2000 } else { 0 };
+                fragment[6..8].copy_from_slice(&flags.to_be_bytes());
+                fix_ipv4_header_checksum(&mut fragment, 0);
+                fragment
+            })
+            .collect()
+    }
+
+    /// Split a raw IPv6 datagram into fragments behind a Hop-by-Hop header.
+    fn fragment_ipv6(datagram: &[u8], size: usize) -> Vec<Vec<u8>> {
+        let next_header = datagram[6];
+        let payload = &datagram[40..];
+        payload
+            .chunks(size)
+            .enumerate()
+            .map(|(i, chunk)| {
+                let offset = i * size;
+                let more = offset + chunk.len() < payload.len();
+                let mut fragment = datagram[..40].to_vec();
+                // Hop-by-Hop with PadN, then the fragment header
+                fragment.extend_from_slice(&[44, 0, 1, 4, 0, 0, 0, 0]);
+                let flags = (offset as u16) | u16::from(more);
+                fragment.extend_from_slice(&[next_header, 0]);
+                fragment.extend_from_slice(&flags.to_be_bytes());
+                fragment.extend_from_slice(&0xCAFE_F00Du32.to_be_bytes());
+                fragment.extend_from_slice(chunk);
+                let length = (fragment.len() - 40) as u16;
+                fragment[4..6].copy_from_slice(&length.to_be_bytes());
+                fragment[6] = 0;
+                fragment
+            })
+            .collect()
+    }
+
+    fn at(secs: i64) -> DateTime<Utc> {
+        Utc.timestamp_opt(1_700_000_000 + secs, 0).unwrap()
+    }
+
+    fn feed(
+        reassembler: &mut FragmentReassembler,
+        fragments: &[Vec<u8>],
+        secs: i64,
+    ) -> Vec<DecodedPacket> {
+        let decoder = ProtocolDecoder::new();
+        fragments
+            .iter()
+            .map(|fragment| {
+                let packet = decoder.decode_link(fragment, at(secs), LinkType::RawIp);
+                reassembler.reassemble(&decoder, packet, fragment, LinkType::RawIp)
+            })
+            .collect()
+    }
+
+    fn dns_answers(packet: &DecodedPacket) -> usize {
+        match &packet.application {
+            Some(ApplicationData::Dns(dns)) => dns.answers.len(),
+            _ => 0,
+        }
+    }
+
+    #[test]
+    fn test_reassemble_ipv4_out_of_order() {
+        let mut datagram = Vec::new();
+        PacketBuilder::ipv4([192, 168, 1, 1], [192, 168, 1, 10], 64)
+            .udp(53, 40000)
+            .write(&mut datagram, &large_dns_response(200))
+            .unwrap();
+        let mut fragments = fragment_ipv4(&datagram, 1480);
+        assert_eq!(fragments.len(), 3);
+        fragments.swap(0, 2);
+
+        let mut reassembler = FragmentReassembler::new();
+        let packets = feed(&mut reassembler, &fragments, 0);
+
+        assert!(packets[0].fragment.unwrap().offset > 0);
+        assert_eq!(packets[0].protocol, Protocol::Ipv4);
+        assert_eq!(packets[1].reassembled_fragments, 0);
+
+        let complete = &packets[2];
+        assert_eq!(complete.reassembled_fragments, 3);
+        assert_eq!(complete.protocol, Protocol::Dns);
+        assert_eq!(complete.length, fragments[2].len());
+        assert_eq!(dns_answers(complete), 200);
+
+        let stats = reassembler.stats();
+        assert_eq!(stats.ipv4_fragments, 3);
+        assert_eq!(stats.reassembled, 1);
+        assert_eq!(stats.largest_fragment, 1500);
+        assert_eq!(stats.largest_datagram, datagram.len());
+        assert_eq!(reassembler.pending(), 0);
+    }
+
+    #[test]
+    fn test_reassemble_ipv6_with_extension_headers() {
+        let mut datagram = Vec::new();
+        PacketBuilder::ipv6(
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            [0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            64,
+        )
+        .udp(53, 40000)
+        .write(&mut datagram, &large_dns_response(100))
+        .unwrap();
+        let fragments = fragment_ipv6(&datagram, 1232);
+
+        let mut reassembler = FragmentReassembler::new();
+        let packets = feed(&mut reassembler, &fragments, 0);
+
+        let first = &packets[0];
+        assert_eq!(
+            first.ipv6_extensions,
+            vec![Ipv6Extension::HopByHop, Ipv6Extension::Fragment]
+        );
+        assert_eq!(first.ip_protocol, Some(17));
+        assert_eq!(first.fragment.unwrap().id, 0xCAFE_F00D);
+
+        let complete = packets.last().unwrap();
+        assert_eq!(complete.reassembled_fragments, fragments.len());
+        assert_eq!(complete.protocol, Protocol::Dns);
+        assert_eq!(complete.src_port, Some(53));
+        assert_eq!(dns_answers(complete), 100);
+        assert_eq!(reassembler.stats().ipv6_fragments, fragments.len() as u64);
+    }
+
+    #[test]
+    fn test_reassembly_timeout_and_overlap() {
+        let mut datagram = Vec::new();
+        PacketBuilder::ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .udp(5000, 5001)
+            .write(&mut datagram, &[7; 3000])
+            .unwrap();
+        let fragments = fragment_ipv4(&datagram, 1480);
+
+        let mut reassembler = FragmentReassembler::new().with_timeout(Duration::from_secs(5));
+        // The first fragment twice, then the rest after the timeout
+        feed(&mut reassembler, &fragments[..1], 0);
+        feed(&mut reassembler, &fragments[..1], 1);
+        assert_eq!(reassembler.stats().overlapping, 1);
+        assert_eq!(reassembler.pending(), 1);
+
+        let packets = feed(&mut reassembler, &fragments[1..], 10);
+        assert!(packets.iter().all(|p| p.reassembled_fragments == 0));
+        assert_eq!(reassembler.stats().timed_out, 1);
+
+        reassembler.flush();
+        assert_eq!(reassembler.stats().timed_out, 2);
+        assert_eq!(reassembler.stats().reassembled, 0);
+    }
+}
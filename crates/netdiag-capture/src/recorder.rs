@@ -0,0 +1,348 @@
+//! In-memory "flight recorder" that saves packets from around a trigger.
+
+use crate::capture::packet_timestamp;
+use crate::error::{CaptureError, CaptureResult};
+use chrono::{DateTime, Utc};
+use pcap::{Capture, Linktype, Packet, PacketHeader, Savefile};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+use tracing::{debug, info};
+
+/// Flight recorder configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlightRecorderConfig {
+    /// Directory trigger captures are written to.
+    pub directory: PathBuf,
+
+    /// How much traffic before a trigger to keep.
+    pub pre_trigger: Duration,
+
+    /// How much traffic after a trigger to keep.
+    pub post_trigger: Duration,
+
+    /// Upper bound on buffered packet data (bytes).
+    pub max_buffer_bytes: usize,
+}
+
+impl FlightRecorderConfig {
+    /// Create a config with a 30 s / 10 s window and a 64 MB buffer.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            pre_trigger: Duration::from_secs(30),
+            post_trigger: Duration::from_secs(10),
+            max_buffer_bytes: 64 * 1024 * 1024,
+        }
+    }
+
+    /// Set the window before a trigger.
+    pub fn pre_trigger(mut self, duration: Duration) -> Self {
+        self.pre_trigger = duration;
+        self
+    }
+
+    /// Set the window after a trigger.
+    pub fn post_trigger(mut self, duration: Duration) -> Self {
+        self.post_trigger = duration;
+        self
+    }
+
+    /// Set the buffer size limit.
+    pub fn max_buffer_bytes(mut self, bytes: usize) -> Self {
+        self.max_buffer_bytes = bytes;
+        self
+    }
+}
+
+/// A buffered raw packet.
+#[derive(Clone)]
+struct RawPacket {
+    header: PacketHeader,
+    timestamp: DateTime<Utc>,
+    data: Vec<u8>,
+}
+
+/// A trigger capture still collecting post-trigger packets.
+struct PendingDump {
+    path: PathBuf,
+    /// None while the trigger writes the pre-trigger packets outside the lock.
+    savefile: Option<Savefile>,
+    /// Post-trigger packets that arrived before the savefile was ready.
+    backlog: Vec<RawPacket>,
+    until: DateTime<Utc>,
+    packets: usize,
+}
+
+impl PendingDump {
+    fn write(&mut self, packet: &RawPacket) {
+        if let Some(savefile) = self.savefile.as_mut() {
+            savefile.write(&Packet::new(&packet.header, &packet.data));
+        }
+    }
+}
+
+/// Shared recorder state.
+struct RecorderState {
+    linktype: Linktype,
+    buffer: VecDeque<RawPacket>,
+    buffered_bytes: usize,
+    pending: Option<PendingDump>,
+    triggers: u64,
+}
+
+/// Keeps a bounded window of recent packets and dumps it to a pcap file on demand.
+///
+/// Cloning gives another handle to the same recorder, so one clone can be fed
+/// by a [`crate::PacketCapture`] while another is triggered elsewhere.
+#[derive(Clone)]
+pub struct FlightRecorder {
+    config: Arc<FlightRecorderConfig>,
+    state: Arc<Mutex<RecorderState>>,
+}
+
+impl FlightRecorder {
+    /// Create a recorder.
+    pub fn new(config: FlightRecorderConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+            state: Arc::new(Mutex::new(RecorderState {
+                linktype: Linktype::ETHERNET,
+                buffer: VecDeque::new(),
+                buffered_bytes: 0,
+                pending: None,
+                triggers: 0,
+            })),
+        }
+    }
+
+    /// Lock the state, recovering from a poisoned lock.
+    fn lock(&self) -> MutexGuard<'_, RecorderState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Set the link type of recorded packets.
+    pub(crate) fn set_linktype(&self, linktype: Linktype) {
+        self.lock().linktype = linktype;
+    }
+
+    /// Record a packet from the capture loop.
+    pub(crate) fn record(&self, header: &PacketHeader, data: &[u8]) {
+        let timestamp = packet_timestamp(header);
+        let packet = RawPacket {
+            header: *header,
+            timestamp,
+            data: data.to_vec(),
+        };
+        let mut state = self.lock();
+
+        if let Some(dump) = state.pending.as_mut() {
+            if timestamp <= dump.until {
+                if dump.savefile.is_some() {
+                    dump.write(&packet);
+                } else {
+                    dump.backlog.push(packet.clone());
+                }
+                dump.packets += 1;
+            }
+        }
+        finish_expired(&mut state, timestamp);
+
+        state.buffered_bytes += packet.data.len();
+        state.buffer.push_back(packet);
+
+        // Drop packets that fell out of the window or over the memory limit
+        let window = chrono::Duration::from_std(self.config.pre_trigger).unwrap_or_default();
+        while let Some(oldest) = state.buffer.front() {
+            let too_old = timestamp - oldest.timestamp > window;
+            let too_big = state.buffered_bytes > self.config.max_buffer_bytes;
+            if !too_old && !too_big {
+                break;
+            }
+            state.buffered_bytes -= oldest.data.len();
+            state.buffer.pop_front();
+        }
+    }
+
+    /// Dump the buffered packets and keep recording for the post-trigger window.
+    ///
+    /// Returns the path of the capture file. Triggers during an ongoing dump
+    /// extend that dump instead of starting a new file.
+    pub fn trigger(&self, label: &str) -> CaptureResult<PathBuf> {
+        self.trigger_at(label, Utc::now())
+    }
+
+    /// Trigger at a specific time (packet time for offline captures).
+    ///
+    /// The buffered packets are taken out under the lock and written to disk
+    /// after releasing it, so the capture loop keeps recording meanwhile.
+    pub fn trigger_at(&self, label: &str, at: DateTime<Utc>) -> CaptureResult<PathBuf> {
+        let until = at + chrono::Duration::from_std(self.config.post_trigger).unwrap_or_default();
+        let (path, linktype, buffer) = {
+            let mut state = self.lock();
+            if let Some(dump) = state.pending.as_mut() {
+                dump.until = dump.until.max(until);
+                return Ok(dump.path.clone());
+            }
+
+            state.triggers += 1;
+            let path = self.config.directory.join(format!(
+                "{}_{}_{}.pcap",
+                sanitize(label),
+                at.format("%Y%m%dT%H%M%S"),
+                state.triggers
+            ));
+            state.pending = Some(PendingDump {
+                path: path.clone(),
+                savefile: None,
+                backlog: Vec::new(),
+                until,
+                packets: 0,
+            });
+            state.buffered_bytes = 0;
+            (path, state.linktype, std::mem::take(&mut state.buffer))
+        };
+
+        let window = chrono::Duration::from_std(self.config.pre_trigger).unwrap_or_default();
+        let written = write_savefile(&path, linktype, |savefile| {
+            let mut packets = 0;
+            for packet in buffer.iter().filter(|p| at - p.timestamp <= window) {
+                savefile.write(&Packet::new(&packet.header, &packet.data));
+                packets += 1;
+            }
+            packets
+        });
+
+        let mut state = self.lock();
+        let Some(mut dump) = state.pending.take() else {
+            return Err(CaptureError::PcapError("trigger dump was removed".to_string()));
+        };
+        let (savefile, packets) = match written {
+            Ok(written) => written,
+            Err(e) => {
+                // Give the packets back so a later trigger can still save them
+                let mut restored = buffer;
+                restored.append(&mut state.buffer);
+                state.buffered_bytes = restored.iter().map(|p| p.data.len()).sum();
+                state.buffer = restored;
+                return Err(e);
+            }
+        };
+        info!(
+            "Flight recorder triggered ({}): {} packets to {}",
+            label,
+            packets,
+            path.display()
+        );
+
+        dump.savefile = Some(savefile);
+        for packet in std::mem::take(&mut dump.backlog) {
+            dump.write(&packet);
+        }
+        dump.packets += packets;
+        state.pending = Some(dump);
+        finish_expired(&mut state, at);
+
+        Ok(path)
+    }
+
+    /// Close a dump whose post-trigger window ended before `now`.
+    ///
+    /// Call periodically so dumps complete even when no packets arrive.
+    pub fn expire(&self, now: DateTime<Utc>) {
+        finish_expired(&mut self.lock(), now);
+    }
+
+    /// Close any ongoing dump immediately.
+    ///
+    /// A dump whose trigger is still writing the pre-trigger packets is
+    /// closed as soon as they are written.
+    pub fn close(&self) {
+        let mut state = self.lock();
+        if let Some(dump) = state.pending.as_mut().filter(|d| d.savefile.is_none()) {
+            dump.until = DateTime::<Utc>::MIN_UTC;
+        } else if let Some(dump) = state.pending.take() {
+            complete(dump);
+        }
+    }
+
+    /// The capture file currently being written, if a dump is in progress.
+    pub fn current_dump(&self) -> Option<PathBuf> {
+        self.lock().pending.as_ref().map(|dump| dump.path.clone())
+    }
+
+    /// Number of packets currently buffered.
+    pub fn buffered_packets(&self) -> usize {
+        self.lock().buffer.len()
+    }
+
+    /// Bytes of packet data currently buffered.
+    pub fn buffered_bytes(&self) -> usize {
+        self.lock().buffered_bytes
+    }
+
+    /// Directory trigger captures are written to.
+    pub fn directory(&self) -> &Path {
+        &self.config.directory
+    }
+}
+
+/// Close the pending dump if its window has passed and its trigger is done
+/// writing the pre-trigger packets.
+fn finish_expired(state: &mut RecorderState, now: DateTime<Utc>) {
+    if state
+        .pending
+        .as_ref()
+        .is_some_and(|dump| dump.savefile.is_some() && now > dump.until)
+    {
+        if let Some(dump) = state.pending.take() {
+            complete(dump);
+        }
+    }
+}
+
+/// Create a savefile, fill it and flush it.
+fn write_savefile(
+    path: &Path,
+    linktype: Linktype,
+    fill: impl FnOnce(&mut Savefile) -> usize,
+) -> CaptureResult<(Savefile, usize)> {
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut savefile = Capture::dead(linktype)
+        .and_then(|cap| cap.savefile(path))
+        .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+    let packets = fill(&mut savefile);
+    savefile
+        .flush()
+        .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+    Ok((savefile, packets))
+}
+
+/// Flush and close a finished dump.
+fn complete(mut dump: PendingDump) {
+    if let Some(savefile) = dump.savefile.as_mut() {
+        let _ = savefile.flush();
+    }
+    debug!(
+        "Flight recorder dump complete: {} packets in {}",
+        dump.packets,
+        dump.path.display()
+    );
+}
+
+/// Make a trigger label safe for use in a file name.
+fn sanitize(label: &str) -> String {
+    let name: String = label
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    if name.is_empty() {
+        "trigger".to_string()
+    } else {
+        name
+    }
+}
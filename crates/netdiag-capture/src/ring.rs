@@ -0,0 +1,269 @@
+//! Rolling ring-buffer capture output.
+
+use crate::error::{CaptureError, CaptureResult};
+use chrono::{DateTime, Utc};
+use pcap::{Capture, Linktype, Packet, PacketHeader, Savefile};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{debug, info, warn};
+
+/// Snapshot length used when only packet headers are kept.
+///
+/// Large enough for Ethernet + IPv6 + TCP with options.
+pub const HEADER_SNAPLEN: i32 = 128;
+
+/// Size of the pcap global header.
+const PCAP_FILE_HEADER_LEN: u64 = 24;
+
+/// Size of a pcap per-packet record header.
+const PCAP_RECORD_HEADER_LEN: u64 = 16;
+
+/// Ring-buffer output configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RingBufferConfig {
+    /// Directory the capture files are written to.
+    pub directory: PathBuf,
+
+    /// File name prefix.
+    #[serde(default = "default_prefix")]
+    pub prefix: String,
+
+    /// Rotate once a file reaches this many bytes.
+    #[serde(default)]
+    pub max_file_size: Option<u64>,
+
+    /// Rotate once a file spans this much packet time.
+    #[serde(default)]
+    pub max_file_duration: Option<Duration>,
+
+    /// Number of files to keep (0 = keep all).
+    #[serde(default = "default_max_files")]
+    pub max_files: usize,
+
+    /// Keep only packet headers (see [`HEADER_SNAPLEN`]).
+    #[serde(default)]
+    pub headers_only: bool,
+}
+
+fn default_prefix() -> String {
+    "netdiag".to_string()
+}
+
+fn default_max_files() -> usize {
+    10
+}
+
+impl RingBufferConfig {
+    /// Create a ring buffer writing to a directory with default limits.
+    pub fn new(directory: impl Into<PathBuf>) -> Self {
+        Self {
+            directory: directory.into(),
+            prefix: default_prefix(),
+            max_file_size: None,
+            max_file_duration: None,
+            max_files: default_max_files(),
+            headers_only: false,
+        }
+    }
+
+    /// Set the file name prefix.
+    pub fn prefix(mut self, prefix: &str) -> Self {
+        self.prefix = prefix.to_string();
+        self
+    }
+
+    /// Rotate by file size.
+    pub fn max_file_size(mut self, bytes: u64) -> Self {
+        self.max_file_size = Some(bytes);
+        self
+    }
+
+    /// Rotate by time.
+    pub fn max_file_duration(mut self, duration: Duration) -> Self {
+        self.max_file_duration = Some(duration);
+        self
+    }
+
+    /// Set the number of files to keep.
+    pub fn max_files(mut self, count: usize) -> Self {
+        self.max_files = count;
+        self
+    }
+
+    /// Keep only packet headers.
+    pub fn headers_only(mut self, enabled: bool) -> Self {
+        self.headers_only = enabled;
+        self
+    }
+
+    /// Check if a file name belongs to this ring buffer.
+    fn owns(&self, name: &str) -> bool {
+        name.starts_with(&format!("{}_", self.prefix)) && name.ends_with(".pcap")
+    }
+}
+
+/// The file currently being written.
+struct CurrentFile {
+    savefile: Savefile,
+    size: u64,
+    started: DateTime<Utc>,
+}
+
+/// Writes packets into a set of rotating pcap files, deleting the oldest.
+pub struct RingBufferWriter {
+    config: RingBufferConfig,
+    linktype: Linktype,
+    current: Option<CurrentFile>,
+    files: VecDeque<PathBuf>,
+    sequence: u64,
+}
+
+impl RingBufferWriter {
+    /// Create a writer for packets of the given link type.
+    ///
+    /// Files left in the directory by an earlier run count towards `max_files`.
+    pub fn new(config: RingBufferConfig, linktype: Linktype) -> CaptureResult<Self> {
+        std::fs::create_dir_all(&config.directory)?;
+
+        let mut existing: Vec<PathBuf> = std::fs::read_dir(&config.directory)?
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| {
+                entry
+                    .file_name()
+                    .to_str()
+                    .is_some_and(|name| config.owns(name))
+            })
+            .map(|entry| entry.path())
+            .collect();
+        // Names start with a timestamp, so this is oldest first
+        existing.sort();
+
+        Ok(Self {
+            config,
+            linktype,
+            current: None,
+            files: existing.into(),
+            sequence: 0,
+        })
+    }
+
+    /// Write a packet, rotating files as needed.
+    pub fn write(&mut self, header: &PacketHeader, data: &[u8]) -> CaptureResult<()> {
+        let timestamp = crate::capture::packet_timestamp(header);
+        let caplen = if self.config.headers_only {
+            (header.caplen as usize).min(HEADER_SNAPLEN as usize)
+        } else {
+            header.caplen as usize
+        }
+        .min(data.len());
+        let record_size = PCAP_RECORD_HEADER_LEN + caplen as u64;
+
+        if self.needs_rotation(timestamp, record_size) {
+            self.rotate(timestamp)?;
+        }
+
+        let current = self.current.as_mut().expect("rotate always opens a file");
+        let header = PacketHeader {
+            caplen: caplen as u32,
+            ..*header
+        };
+        current
+            .savefile
+            .write(&Packet::new(&header, &data[..caplen]));
+        // Flush every packet so files stay valid if the process is killed
+        current
+            .savefile
+            .flush()
+            .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+        current.size += record_size;
+
+        Ok(())
+    }
+
+    /// Check if the next packet must go into a new file.
+    fn needs_rotation(&self, timestamp: DateTime<Utc>, record_size: u64) -> bool {
+        let Some(current) = &self.current else {
+            return true;
+        };
+        // Never rotate an empty file
+        if current.size <= PCAP_FILE_HEADER_LEN {
+            return false;
+        }
+
+        let too_big = self
+            .config
+            .max_file_size
+            .is_some_and(|max| current.size + record_size > max);
+        let too_old = self.config.max_file_duration.is_some_and(|max| {
+            (timestamp - current.started)
+                .to_std()
+                .is_ok_and(|age| age >= max)
+        });
+
+        too_big || too_old
+    }
+
+    /// Close the current file, open the next and drop the oldest beyond `max_files`.
+    fn rotate(&mut self, timestamp: DateTime<Utc>) -> CaptureResult<()> {
+        self.current = None;
+
+        let path = self.config.directory.join(format!(
+            "{}_{}_{:05}.pcap",
+            self.config.prefix,
+            timestamp.format("%Y%m%dT%H%M%S"),
+            self.sequence
+        ));
+        self.sequence += 1;
+
+        let savefile = Capture::dead(self.linktype)
+            .and_then(|cap| cap.savefile(&path))
+            .map_err(|e| CaptureError::PcapError(e.to_string()))?;
+        debug!("Opened capture file: {}", path.display());
+
+        self.current = Some(CurrentFile {
+            savefile,
+            size: PCAP_FILE_HEADER_LEN,
+            started: timestamp,
+        });
+        self.files.push_back(path);
+
+        if self.config.max_files > 0 {
+            while self.files.len() > self.config.max_files {
+                if let Some(oldest) = self.files.pop_front() {
+                    remove_file(&oldest);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Files currently in the ring, oldest first.
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        self.files.iter().map(PathBuf::as_path)
+    }
+
+    /// The file currently being written.
+    pub fn current_file(&self) -> Option<&Path> {
+        self.current
+            .as_ref()
+            .and(self.files.back().map(PathBuf::as_path))
+    }
+
+    /// Close the current file.
+    pub fn close(&mut self) {
+        if self.current.take().is_some() {
+            info!("Closed ring buffer with {} files", self.files.len());
+        }
+    }
+}
+
+/// Delete a rotated-out capture file.
+fn remove_file(path: &Path) {
+    match std::fs::remove_file(path) {
+        Ok(()) => debug!("Removed old capture file: {}", path.display()),
+        Err(e) => warn!("Failed to remove {}: {}", path.display(), e),
+    }
+}
@@ -1,7 +1,8 @@
 //! Capture statistics.
 
-use crate::decode::Protocol;
+use crate::decode::{DecodedPacket, Protocol};
 use chrono::{DateTime, Utc};
+use netdiag_types::network::{MacAddress, MacAddressKind};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::Duration;
@@ -44,6 +45,26 @@ pub struct CaptureStats {
 
     /// Top ports.
     pub top_ports: Vec<(u16, u64)>,
+
+    /// Traffic per 802.1Q VLAN ID.
+    #[serde(default)]
+    pub vlans: HashMap<u16, TrafficCounter>,
+
+    /// Traffic per tunnel, keyed by [`TunnelInfo::label`](crate::TunnelInfo::label).
+    #[serde(default)]
+    pub tunnels: HashMap<String, TrafficCounter>,
+
+    /// IP fragmentation and reassembly.
+    #[serde(default)]
+    pub fragmentation: FragmentStats,
+
+    /// Traffic per source MAC address.
+    #[serde(default)]
+    pub source_macs: HashMap<String, TrafficCounter>,
+
+    /// Busiest source devices, with their vendors.
+    #[serde(default)]
+    pub top_devices: Vec<DeviceSummary>,
 }
 
 impl CaptureStats {
@@ -96,9 +117,38 @@ impl CaptureStats {
         }
     }
 
+    /// Update VLAN and tunnel breakdowns with a decoded packet.
+    pub fn update_encapsulation(&mut self, packet: &DecodedPacket) {
+        let mut vlans = packet.vlans.clone();
+        vlans.sort_unstable();
+        vlans.dedup();
+        for id in vlans {
+            self.vlans.entry(id).or_default().add(packet.length);
+        }
+
+        for tunnel in &packet.tunnels {
+            self.tunnels
+                .entry(tunnel.label())
+                .or_default()
+                .add(packet.length);
+        }
+
+        if let Some(mac) = &packet.src_mac {
+            self.source_macs
+                .entry(mac.clone())
+                .or_default()
+                .add(packet.length);
+        }
+    }
+
     /// Finalize stats (calculate rates, sort top lists).
     pub fn finalize(&mut self) {
-        self.end_time = Some(Utc::now());
+        self.finalize_at(Utc::now());
+    }
+
+    /// Finalize stats with an explicit end time, such as the last packet of a capture file.
+    pub fn finalize_at(&mut self, end_time: DateTime<Utc>) {
+        self.end_time = Some(end_time);
 
         if let (Some(start), Some(end)) = (self.start_time, self.end_time) {
             let duration_secs = (end - start).num_milliseconds() as f64 / 1000.0;
@@ -131,6 +181,20 @@ impl CaptureStats {
         let mut ports: Vec<_> = port_counts.into_iter().collect();
         ports.sort_by(|a, b| b.1.cmp(&a.1));
         self.top_ports = ports.into_iter().take(10).collect();
+
+        let mut devices: Vec<_> = self
+            .source_macs
+            .iter()
+            .map(|(mac, traffic)| DeviceSummary::new(mac, *traffic))
+            .collect();
+        devices.sort_by(|a, b| {
+            b.traffic
+                .packets
+                .cmp(&a.traffic.packets)
+                .then_with(|| a.mac.cmp(&b.mac))
+        });
+        devices.truncate(10);
+        self.top_devices = devices;
     }
 
     /// Get drop rate as percentage.
@@ -157,6 +221,87 @@ impl CaptureStats {
     }
 }
 
+/// Packet and byte counter.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficCounter {
+    /// Number of packets.
+    pub packets: u64,
+
+    /// Total bytes.
+    pub bytes: u64,
+}
+
+impl TrafficCounter {
+    /// Count one packet.
+    pub fn add(&mut self, length: usize) {
+        self.packets += 1;
+        self.bytes += length as u64;
+    }
+}
+
+/// Traffic sent by one device, identified by MAC address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceSummary {
+    /// Source MAC address.
+    pub mac: String,
+
+    /// Vendor from the OUI database.
+    pub vendor: Option<String>,
+
+    /// Address kind (universal, randomized or multicast).
+    pub kind: Option<MacAddressKind>,
+
+    /// Traffic sent.
+    pub traffic: TrafficCounter,
+}
+
+impl DeviceSummary {
+    fn new(mac: &str, traffic: TrafficCounter) -> Self {
+        let parsed = mac.parse::<MacAddress>().ok();
+        Self {
+            mac: mac.to_string(),
+            vendor: parsed.and_then(|m| m.vendor()).map(str::to_string),
+            kind: parsed.map(|m| m.kind()),
+            traffic,
+        }
+    }
+}
+
+/// IP fragmentation statistics.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FragmentStats {
+    /// IPv4 fragments seen.
+    pub ipv4_fragments: u64,
+
+    /// IPv6 fragments seen.
+    pub ipv6_fragments: u64,
+
+    /// Datagrams reassembled from their fragments.
+    pub reassembled: u64,
+
+    /// Datagrams abandoned because fragments were missing at the timeout.
+    pub timed_out: u64,
+
+    /// Duplicate or overlapping fragments that were discarded.
+    pub overlapping: u64,
+
+    /// Largest fragment seen (IP packet bytes), a hint at the sender's path MTU.
+    pub largest_fragment: usize,
+
+    /// Largest reassembled datagram (bytes).
+    pub largest_datagram: usize,
+
+    /// ICMP "fragmentation needed" and ICMPv6 "packet too big" messages seen.
+    pub pmtu_messages: u64,
+}
+
+impl FragmentStats {
+    /// Total fragments seen.
+    pub fn fragments(&self) -> u64 {
+        self.ipv4_fragments + self.ipv6_fragments
+    }
+}
+
 /// Per-protocol statistics.
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ProtocolStats {
@@ -0,0 +1,431 @@
+//! Tunnel detection and decapsulation.
+
+use crate::decode::LinkType;
+use etherparse::{NetSlice, SlicedPacket, TransportSlice};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+
+/// VXLAN UDP port.
+const VXLAN_PORT: u16 = 4789;
+
+/// GENEVE UDP port.
+const GENEVE_PORT: u16 = 6081;
+
+/// Default WireGuard UDP port.
+const WIREGUARD_PORT: u16 = 51820;
+
+/// EtherType for bridged Ethernet inside GRE/GENEVE.
+const TRANSPARENT_ETHERNET: u16 = 0x6558;
+
+/// Tunnel encapsulation type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TunnelKind {
+    /// Generic Routing Encapsulation.
+    Gre,
+    /// VXLAN.
+    Vxlan,
+    /// GENEVE.
+    Geneve,
+    /// IPv4/IPv6 in IPv4/IPv6.
+    IpInIp,
+    /// WireGuard (encrypted; inner traffic is not visible).
+    WireGuard,
+}
+
+impl TunnelKind {
+    /// Get tunnel name.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Gre => "GRE",
+            Self::Vxlan => "VXLAN",
+            Self::Geneve => "GENEVE",
+            Self::IpInIp => "IP-in-IP",
+            Self::WireGuard => "WireGuard",
+        }
+    }
+
+    /// Name of the tunnel identifier, if the tunnel has one.
+    fn id_name(&self) -> &'static str {
+        match self {
+            Self::Gre => "key",
+            Self::Vxlan | Self::Geneve => "VNI",
+            Self::WireGuard => "index",
+            Self::IpInIp => "id",
+        }
+    }
+}
+
+impl std::fmt::Display for TunnelKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// A tunnel layer peeled off a packet.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TunnelInfo {
+    /// Encapsulation type.
+    pub kind: TunnelKind,
+    /// Outer source address.
+    pub src: IpAddr,
+    /// Outer destination address.
+    pub dst: IpAddr,
+    /// VNI, GRE key or WireGuard receiver index.
+    pub id: Option<u32>,
+}
+
+impl TunnelInfo {
+    /// Direction-independent label, for grouping packets by tunnel.
+    pub fn label(&self) -> String {
+        let (a, b) = if self.src <= self.dst {
+            (self.src, self.dst)
+        } else {
+            (self.dst, self.src)
+        };
+        // WireGuard indexes differ per direction
+        match self.id.filter(|_| self.kind != TunnelKind::WireGuard) {
+            Some(id) => format!(
+                "{} {} <-> {} ({} {})",
+                self.kind,
+                a,
+                b,
+                self.kind.id_name(),
+                id
+            ),
+            None => format!("{} {} <-> {}", self.kind, a, b),
+        }
+    }
+}
+
+impl std::fmt::Display for TunnelInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {} -> {}", self.kind, self.src, self.dst)?;
+        if let Some(id) = self.id {
+            write!(f, " ({} {})", self.kind.id_name(), id)?;
+        }
+        Ok(())
+    }
+}
+
+/// A recognised tunnel and, unless encrypted, the packet inside it.
+pub(crate) struct Decapsulated<'a> {
+    pub tunnel: TunnelInfo,
+    pub inner: Option<(&'a [u8], LinkType)>,
+}
+
+/// Recognise a tunnel in a parsed packet.
+pub(crate) fn decapsulate<'a>(sliced: &SlicedPacket<'a>) -> Option<Decapsulated<'a>> {
+    let (src, dst, payload) = match sliced.net.as_ref()? {
+        NetSlice::Ipv4(ipv4) => (
+            IpAddr::from(ipv4.header().source()),
+            IpAddr::from(ipv4.header().destination()),
+            ipv4.payload(),
+        ),
+        NetSlice::Ipv6(ipv6) => (
+            IpAddr::from(ipv6.header().source()),
+            IpAddr::from(ipv6.header().destination()),
+            ipv6.payload(),
+        ),
+    };
+    if payload.fragmented {
+        return None;
+    }
+
+    let tunnel = |kind, id| TunnelInfo { kind, src, dst, id };
+
+    match payload.ip_number.0 {
+        // IPv4 or IPv6 directly inside IP
+        4 | 41 => Some(Decapsulated {
+            tunnel: tunnel(TunnelKind::IpInIp, None),
+            inner: Some((payload.payload, LinkType::RawIp)),
+        }),
+        47 => {
+            let (id, protocol, inner) = parse_gre(payload.payload)?;
+            Some(Decapsulated {
+                tunnel: tunnel(TunnelKind::Gre, id),
+                inner: Some((inner, inner_link(protocol)?)),
+            })
+        }
+        17 => {
+            let Some(TransportSlice::Udp(udp)) = &sliced.transport else {
+                return None;
+            };
+            let ports = [udp.source_port(), udp.destination_port()];
+            let data = udp.payload();
+
+            if ports.contains(&VXLAN_PORT) {
+                let (vni, inner) = parse_vxlan(data)?;
+                Some(Decapsulated {
+                    tunnel: tunnel(TunnelKind::Vxlan, Some(vni)),
+                    inner: Some((inner, LinkType::Ethernet)),
+                })
+            } else if ports.contains(&GENEVE_PORT) {
+                let (vni, protocol, inner) = parse_geneve(data)?;
+                Some(Decapsulated {
+                    tunnel: tunnel(TunnelKind::Geneve, Some(vni)),
+                    inner: Some((inner, inner_link(protocol)?)),
+                })
+            } else {
+                let index = parse_wireguard(data, ports.contains(&WIREGUARD_PORT))?;
+                Some(Decapsulated {
+                    tunnel: tunnel(TunnelKind::WireGuard, Some(index)),
+                    inner: None,
+                })
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Map a GRE/GENEVE protocol type to the inner framing.
+fn inner_link(protocol: u16) -> Option<LinkType> {
+    match protocol {
+        0x0800 | 0x86DD => Some(LinkType::RawIp),
+        TRANSPARENT_ETHERNET => Some(LinkType::Ethernet),
+        _ => None,
+    }
+}
+
+/// Parse a GRE header: (key, protocol type, payload).
+fn parse_gre(data: &[u8]) -> Option<(Option<u32>, u16, &[u8])> {
+    let flags = u16::from_be_bytes([*data.first()?, *data.get(1)?]);
+    let protocol = u16::from_be_bytes([*data.get(2)?, *data.get(3)?]);
+    // Only version 0 carries tunnelled packets (version 1 is PPTP)
+    if flags & 0x0007 != 0 {
+        return None;
+    }
+
+    let mut offset = 4;
+    if flags & 0x8000 != 0 {
+        // Checksum + reserved
+        offset += 4;
+    }
+    let key = if flags & 0x2000 != 0 {
+        let key = u32::from_be_bytes(data.get(offset..offset + 4)?.try_into().ok()?);
+        offset += 4;
+        Some(key)
+    } else {
+        None
+    };
+    if flags & 0x1000 != 0 {
+        // Sequence number
+        offset += 4;
+    }
+
+    Some((key, protocol, data.get(offset..)?))
+}
+
+/// Parse a VXLAN header: (VNI, inner Ethernet frame).
+fn parse_vxlan(data: &[u8]) -> Option<(u32, &[u8])> {
+    let header = data.get(..8)?;
+    // The I flag marks a valid VNI
+    if header[0] & 0x08 == 0 {
+        return None;
+    }
+    let vni = u32::from_be_bytes([0, header[4], header[5], header[6]]);
+    Some((vni, &data[8..]))
+}
+
+/// Parse a GENEVE header: (VNI, protocol type, payload).
+fn parse_geneve(data: &[u8]) -> Option<(u32, u16, &[u8])> {
+    let header = data.get(..8)?;
+    if header[0] >> 6 != 0 {
+        return None;
+    }
+    let options_len = usize::from(header[0] & 0x3F) * 4;
+    let protocol = u16::from_be_bytes([header[2], header[3]]);
+    let vni = u32::from_be_bytes([0, header[4], header[5], header[6]]);
+    Some((vni, protocol, data.get(8 + options_len..)?))
+}
+
+/// Recognise a WireGuard message, returning the index identifying the peer session.
+///
+/// Handshake messages have fixed sizes and are recognised on any port;
+/// transport data only on the default port.
+fn parse_wireguard(data: &[u8], default_port: bool) -> Option<u32> {
+    let header = data.get(..8)?;
+    if header[1..4] != [0, 0, 0] {
+        return None;
+    }
+    let index = |at: usize| {
+        data.get(at..at + 4)
+            .and_then(|b| b.try_into().ok())
+            .map(u32::from_le_bytes)
+    };
+
+    match (header[0], data.len()) {
+        // Initiation: sender index
+        (1, 148) => index(4),
+        // Response: receiver index
+        (2, 92) => index(8),
+        // Cookie reply: receiver index
+        (3, 64) => index(4),
+        // Transport data: receiver index, 16-byte header + padded ciphertext with tag
+        (4, len) if default_port && len >= 32 && len % 16 == 0 => index(4),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::decode::{Protocol, ProtocolDecoder};
+    use crate::stats::CaptureStats;
+    use chrono::Utc;
+    use etherparse::{IpNumber, PacketBuilder};
+
+    /// Inner DNS query from 192.168.1.10 to 192.168.1.1.
+    fn inner_ethernet() -> Vec<u8> {
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4([192, 168, 1, 10], [192, 168, 1, 1], 64)
+            .udp(40000, 53)
+            .write(&mut frame, &[0; 12])
+            .unwrap();
+        frame
+    }
+
+    fn outer_udp(dst_port: u16, payload: &[u8]) -> Vec<u8> {
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([1, 0, 0, 0, 0, 1], [1, 0, 0, 0, 0, 2])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .udp(50000, dst_port)
+            .write(&mut frame, payload)
+            .unwrap();
+        frame
+    }
+
+    #[test]
+    fn test_decode_qinq_vlans() {
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([1, 0, 0, 0, 0, 1], [1, 0, 0, 0, 0, 2])
+            .double_vlan(100.try_into().unwrap(), 200.try_into().unwrap())
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .tcp(40000, 443, 1, 1024)
+            .write(&mut frame, &[])
+            .unwrap();
+
+        let packet = ProtocolDecoder::new().decode(&frame, Utc::now());
+        assert_eq!(packet.vlans, vec![100, 200]);
+        assert_eq!(packet.protocol, Protocol::Https);
+        assert!(packet.tunnels.is_empty());
+        assert!(packet.summary().contains("vlan=100,200"));
+    }
+
+    #[test]
+    fn test_decode_vxlan_inner_traffic() {
+        let mut payload = vec![0x08, 0, 0, 0, 0, 0, 42, 0];
+        payload.extend(inner_ethernet());
+        let frame = outer_udp(VXLAN_PORT, &payload);
+
+        let packet = ProtocolDecoder::new().decode(&frame, Utc::now());
+        assert_eq!(packet.protocol, Protocol::Dns);
+        assert_eq!(packet.src_ip, Some("192.168.1.10".parse().unwrap()));
+        assert_eq!(packet.dst_port, Some(53));
+        assert_eq!(packet.src_mac.as_deref(), Some("02:00:00:00:00:01"));
+        assert_eq!(packet.length, frame.len());
+        assert_eq!(
+            packet.tunnels,
+            vec![TunnelInfo {
+                kind: TunnelKind::Vxlan,
+                src: "10.0.0.1".parse().unwrap(),
+                dst: "10.0.0.2".parse().unwrap(),
+                id: Some(42),
+            }]
+        );
+        assert!(packet
+            .summary()
+            .contains("via VXLAN 10.0.0.1 -> 10.0.0.2 (VNI 42)"));
+    }
+
+    #[test]
+    fn test_decode_gre_and_ip_in_ip() {
+        let mut inner = Vec::new();
+        PacketBuilder::ipv4([172, 16, 0, 1], [172, 16, 0, 2], 64)
+            .udp(40000, 53)
+            .write(&mut inner, &[0; 12])
+            .unwrap();
+
+        // GRE with key, carrying IPv4
+        let mut gre = vec![0x20, 0x00, 0x08, 0x00, 0, 0, 0, 7];
+        gre.extend(&inner);
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([1, 0, 0, 0, 0, 1], [1, 0, 0, 0, 0, 2])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .write(&mut frame, IpNumber::GRE, &gre)
+            .unwrap();
+
+        let packet = ProtocolDecoder::new().decode(&frame, Utc::now());
+        assert_eq!(packet.protocol, Protocol::Dns);
+        assert_eq!(packet.dst_ip, Some("172.16.0.2".parse().unwrap()));
+        assert_eq!(packet.tunnels[0].kind, TunnelKind::Gre);
+        assert_eq!(packet.tunnels[0].id, Some(7));
+        // Inner IP packets have no link layer; the outer MACs are kept
+        assert_eq!(packet.src_mac.as_deref(), Some("01:00:00:00:00:01"));
+
+        // IPv4 in IPv4
+        let mut frame = Vec::new();
+        PacketBuilder::ethernet2([1, 0, 0, 0, 0, 1], [1, 0, 0, 0, 0, 2])
+            .ipv4([10, 0, 0, 1], [10, 0, 0, 2], 64)
+            .write(&mut frame, IpNumber::IPV4, &inner)
+            .unwrap();
+
+        let packet = ProtocolDecoder::new().decode(&frame, Utc::now());
+        assert_eq!(packet.protocol, Protocol::Dns);
+        assert_eq!(packet.tunnels[0].kind, TunnelKind::IpInIp);
+    }
+
+    #[test]
+    fn test_decode_geneve_and_wireguard() {
+        // GENEVE with one 4-byte option, carrying Ethernet
+        let mut payload = vec![0x01, 0, 0x65, 0x58, 0, 0x01, 0x00, 0, 0, 0, 0, 0];
+        payload.extend(inner_ethernet());
+        let packet = ProtocolDecoder::new().decode(&outer_udp(GENEVE_PORT, &payload), Utc::now());
+        assert_eq!(packet.protocol, Protocol::Dns);
+        assert_eq!(packet.tunnels[0].kind, TunnelKind::Geneve);
+        assert_eq!(packet.tunnels[0].id, Some(256));
+
+        // WireGuard handshake initiation on a non-default port
+        let mut initiation = vec![0u8; 148];
+        initiation[0] = 1;
+        initiation[4..8].copy_from_slice(&0xDEADu32.to_le_bytes());
+        let packet = ProtocolDecoder::new().decode(&outer_udp(443, &initiation), Utc::now());
+        assert_eq!(packet.protocol, Protocol::WireGuard);
+        assert_eq!(packet.tunnels[0].kind, TunnelKind::WireGuard);
+        assert_eq!(packet.tunnels[0].id, Some(0xDEAD));
+
+        // Random UDP data is left alone
+        let packet = ProtocolDecoder::new().decode(&outer_udp(443, &[4; 40]), Utc::now());
+        assert_eq!(packet.protocol, Protocol::Udp);
+        assert!(packet.tunnels.is_empty());
+    }
+
+    #[test]
+    fn test_encapsulation_stats() {
+        let decoder = ProtocolDecoder::new();
+        let mut stats = CaptureStats::new();
+
+        let mut vxlan = vec![0x08, 0, 0, 0, 0, 0, 42, 0];
+        vxlan.extend(inner_ethernet());
+        let forward = outer_udp(VXLAN_PORT, &vxlan);
+        let mut reverse = Vec::new();
+        PacketBuilder::ethernet2([1, 0, 0, 0, 0, 2], [1, 0, 0, 0, 0, 1])
+            .single_vlan(100.try_into().unwrap())
+            .ipv4([10, 0, 0, 2], [10, 0, 0, 1], 64)
+            .udp(50000, VXLAN_PORT)
+            .write(&mut reverse, &vxlan)
+            .unwrap();
+
+        for frame in [&forward, &reverse] {
+            stats.update_encapsulation(&decoder.decode(frame, Utc::now()));
+        }
+
+        assert_eq!(stats.vlans[&100].packets, 1);
+        assert_eq!(stats.vlans[&100].bytes, reverse.len() as u64);
+        assert_eq!(stats.tunnels.len(), 1);
+        assert_eq!(
+            stats.tunnels["VXLAN 10.0.0.1 <-> 10.0.0.2 (VNI 42)"].packets,
+            2
+        );
+    }
+}
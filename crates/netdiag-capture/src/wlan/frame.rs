@@ -0,0 +1,235 @@
+//! IEEE 802.11 MAC header decoding.
+
+use serde::{Deserialize, Serialize};
+
+/// Frame control flag: to the distribution system.
+const TO_DS: u8 = 0x01;
+/// Frame control flag: from the distribution system.
+const FROM_DS: u8 = 0x02;
+/// Frame control flag: retransmission.
+const RETRY: u8 = 0x08;
+/// Frame control flag: payload is encrypted.
+const PROTECTED: u8 = 0x40;
+/// Frame control flag: HT control field present.
+const ORDER: u8 = 0x80;
+
+/// Information element: SSID.
+const ELEMENT_SSID: u8 = 0;
+/// Information element: DS parameter set (current channel).
+const ELEMENT_DS_PARAMS: u8 = 3;
+
+/// 802.11 frame type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FrameType {
+    /// Management frame.
+    Management,
+    /// Control frame.
+    Control,
+    /// Data frame.
+    Data,
+    /// Extension frame (DMG beacons).
+    Extension,
+}
+
+/// Management subtype: probe request.
+pub(crate) const PROBE_REQUEST: u8 = 4;
+/// Management subtype: probe response.
+pub(crate) const PROBE_RESPONSE: u8 = 5;
+/// Management subtype: beacon.
+pub(crate) const BEACON: u8 = 8;
+/// Management subtype: disassociation.
+pub(crate) const DISASSOCIATION: u8 = 10;
+/// Management subtype: deauthentication.
+pub(crate) const DEAUTHENTICATION: u8 = 12;
+/// Control subtype: CTS.
+const CTS: u8 = 12;
+/// Control subtype: ACK.
+const ACK: u8 = 13;
+
+/// Decoded 802.11 MAC header.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Dot11Frame {
+    /// Frame type.
+    pub frame_type: FrameType,
+    /// Subtype within the frame type.
+    pub subtype: u8,
+    /// To-DS flag.
+    pub to_ds: bool,
+    /// From-DS flag.
+    pub from_ds: bool,
+    /// Retransmission of an earlier frame.
+    pub retry: bool,
+    /// Payload is encrypted.
+    pub protected: bool,
+    /// Duration/ID field.
+    pub duration: u16,
+    /// Receiver address.
+    pub addr1: [u8; 6],
+    /// Transmitter address (absent in CTS and ACK).
+    pub addr2: Option<[u8; 6]>,
+    /// BSSID, source or destination depending on the DS flags.
+    pub addr3: Option<[u8; 6]>,
+    /// Sequence number.
+    pub sequence: Option<u16>,
+    /// Offset of the frame body.
+    pub body_offset: usize,
+}
+
+impl Dot11Frame {
+    /// Parse the MAC header of a frame.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 10 {
+            return None;
+        }
+        let fc = data[0];
+        let flags = data[1];
+        if fc & 0x03 != 0 {
+            return None; // protocol version 0 only
+        }
+        let frame_type = match (fc >> 2) & 0x03 {
+            0 => FrameType::Management,
+            1 => FrameType::Control,
+            2 => FrameType::Data,
+            _ => FrameType::Extension,
+        };
+        let subtype = fc >> 4;
+        let mac =
+            |offset: usize| -> Option<[u8; 6]> { data.get(offset..offset + 6)?.try_into().ok() };
+
+        let mut frame = Self {
+            frame_type,
+            subtype,
+            to_ds: flags & TO_DS != 0,
+            from_ds: flags & FROM_DS != 0,
+            retry: flags & RETRY != 0,
+            protected: flags & PROTECTED != 0,
+            duration: u16::from_le_bytes([data[2], data[3]]),
+            addr1: mac(4)?,
+            addr2: None,
+            addr3: None,
+            sequence: None,
+            body_offset: 10,
+        };
+
+        match frame_type {
+            FrameType::Control => {
+                if !matches!(subtype, CTS | ACK) {
+                    frame.addr2 = mac(10);
+                    frame.body_offset = 16;
+                }
+            }
+            FrameType::Management | FrameType::Data => {
+                frame.addr2 = Some(mac(10)?);
+                frame.addr3 = Some(mac(16)?);
+                frame.sequence = Some(u16::from_le_bytes([*data.get(22)?, *data.get(23)?]) >> 4);
+                let mut offset = 24;
+                if frame_type == FrameType::Data {
+                    if frame.to_ds && frame.from_ds {
+                        offset += 6; // addr4
+                    }
+                    if subtype & 0x08 != 0 {
+                        offset += 2; // QoS control
+                    }
+                }
+                let qos_or_mgmt = frame_type == FrameType::Management || subtype & 0x08 != 0;
+                if qos_or_mgmt && flags & ORDER != 0 {
+                    offset += 4; // HT control
+                }
+                frame.body_offset = offset;
+            }
+            FrameType::Extension => {}
+        }
+        Some(frame)
+    }
+
+    /// BSS the frame belongs to, when the header says.
+    pub fn bssid(&self) -> Option<[u8; 6]> {
+        match self.frame_type {
+            FrameType::Management => self.addr3,
+            FrameType::Data => match (self.to_ds, self.from_ds) {
+                (false, false) => self.addr3,
+                (true, false) => Some(self.addr1),
+                (false, true) => self.addr2,
+                (true, true) => None, // WDS / mesh
+            },
+            _ => None,
+        }
+    }
+
+    /// Station (non-AP) address of a data frame.
+    pub fn station(&self) -> Option<[u8; 6]> {
+        match (self.frame_type, self.to_ds, self.from_ds) {
+            (FrameType::Data, true, false) => self.addr2,
+            (FrameType::Data, false, true) => Some(self.addr1),
+            _ => None,
+        }
+    }
+
+    /// Whether a transmission of this frame is acknowledged, and so may be retried.
+    pub fn is_unicast(&self) -> bool {
+        self.frame_type != FrameType::Control && self.addr1[0] & 0x01 == 0
+    }
+}
+
+/// Fixed fields and elements of a beacon or probe response.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BeaconBody {
+    /// Beacon interval in time units.
+    pub interval_tu: u16,
+    /// Network name (empty for hidden networks).
+    pub ssid: Option<String>,
+    /// Channel from the DS parameter set.
+    pub channel: Option<u8>,
+}
+
+impl BeaconBody {
+    /// Parse a beacon or probe response body.
+    pub fn parse(body: &[u8]) -> Option<Self> {
+        // Timestamp (8), beacon interval (2), capabilities (2)
+        let interval_tu = u16::from_le_bytes([*body.get(8)?, *body.get(9)?]);
+        let mut beacon = Self {
+            interval_tu,
+            ..Self::default()
+        };
+        for (id, value) in elements(body.get(12..)?) {
+            match id {
+                ELEMENT_SSID => beacon.ssid = ssid(value),
+                ELEMENT_DS_PARAMS => beacon.channel = value.first().copied(),
+                _ => {}
+            }
+        }
+        Some(beacon)
+    }
+}
+
+/// SSID asked for by a probe request; `None` for a wildcard request.
+pub(crate) fn probe_ssid(body: &[u8]) -> Option<String> {
+    elements(body)
+        .find(|(id, _)| *id == ELEMENT_SSID)
+        .and_then(|(_, value)| ssid(value))
+}
+
+/// Reason code of a deauthentication or disassociation frame.
+pub(crate) fn reason_code(body: &[u8]) -> Option<u16> {
+    Some(u16::from_le_bytes([*body.first()?, *body.get(1)?]))
+}
+
+/// Information elements as (id, value) pairs; stops at the first truncated one.
+fn elements(mut data: &[u8]) -> impl Iterator<Item = (u8, &[u8])> {
+    std::iter::from_fn(move || {
+        let (&id, rest) = data.split_first()?;
+        let (&len, rest) = rest.split_first()?;
+        let value = rest.get(..usize::from(len))?;
+        data = &rest[usize::from(len)..];
+        Some((id, value))
+    })
+}
+
+/// Printable SSID, or `None` if empty or all zero bytes (hidden).
+fn ssid(value: &[u8]) -> Option<String> {
+    if value.iter().all(|&b| b == 0) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(value).into_owned())
+}
@@ -0,0 +1,753 @@
+//! 802.11 frame analysis of monitor-mode captures.
+//!
+//! Decodes radiotap headers and 802.11 MAC headers, then measures per-BSS
+//! frame counts, retry rates, airtime, beacon regularity, deauthentication
+//! and disassociation bursts, and probe request activity.
+
+mod frame;
+mod radiotap;
+
+pub use frame::{BeaconBody, Dot11Frame, FrameType};
+pub use radiotap::{RadiotapHeader, RadiotapMcs};
+
+use crate::capture::packet_timestamp;
+use crate::error::{CaptureError, CaptureResult};
+use chrono::{DateTime, Utc};
+use netdiag_types::network::MacAddress;
+use netdiag_types::wifi::{
+    AirtimeReport, BssActivity, Bssid, Channel, DisconnectBurst, DisconnectFrame, ProbeActivity,
+    WifiBand,
+};
+use pcap::{Capture, Linktype};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use tracing::info;
+
+/// Deauthentication/disassociation frames that make a burst.
+const BURST_MIN_FRAMES: usize = 10;
+
+/// Largest gap between frames of one burst, in milliseconds.
+const BURST_MAX_GAP_MS: i64 = 1000;
+
+/// Beacon gaps this many intervals long or more count as missed beacons.
+const MISSED_BEACON_RATIO: f64 = 1.5;
+
+/// Length of the FCS appended to every frame on air.
+const FCS_LEN: usize = 4;
+
+/// Per-BSS accumulator.
+struct BssState {
+    activity: BssActivity,
+    beacon_times: Vec<DateTime<Utc>>,
+    stations: HashSet<[u8; 6]>,
+    signal_sum: i64,
+    signal_count: i64,
+    airtime_us: f64,
+}
+
+impl BssState {
+    fn new(bssid: [u8; 6]) -> Self {
+        Self {
+            activity: BssActivity::new(Bssid::new(bssid)),
+            beacon_times: Vec::new(),
+            stations: HashSet::new(),
+            signal_sum: 0,
+            signal_count: 0,
+            airtime_us: 0.0,
+        }
+    }
+}
+
+/// Control frames waiting to be attributed to a BSS once the capture is read.
+#[derive(Default)]
+struct ControlFrames {
+    frames: u64,
+    bytes: u64,
+    airtime_us: f64,
+}
+
+/// A deauthentication or disassociation frame.
+struct DisconnectEvent {
+    timestamp: DateTime<Utc>,
+    receiver: [u8; 6],
+    reason: Option<u16>,
+}
+
+/// Accumulates 802.11 frames into an [`AirtimeReport`].
+#[derive(Default)]
+pub struct WlanAnalyzer {
+    first: Option<DateTime<Utc>>,
+    last: Option<DateTime<Utc>>,
+    frames: u64,
+    fcs_errors: u64,
+    frames_without_rate: u64,
+    airtime_us: f64,
+    frequencies: HashMap<u16, u64>,
+    bss: HashMap<[u8; 6], BssState>,
+    station_bss: HashMap<[u8; 6], [u8; 6]>,
+    control: HashMap<[u8; 6], ControlFrames>,
+    disconnects: HashMap<(DisconnectFrame, [u8; 6], [u8; 6]), Vec<DisconnectEvent>>,
+    probe_requests: u64,
+    wildcard_probes: u64,
+    probe_clients: HashSet<[u8; 6]>,
+    probed_ssids: HashMap<String, u64>,
+    probe_responses: u64,
+}
+
+impl WlanAnalyzer {
+    /// Create an empty analyzer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Analyze a radiotap (`DLT_IEEE802_11_RADIO`) or bare 802.11
+    /// (`DLT_IEEE802_11`) capture file.
+    pub fn analyze_file(path: &Path) -> CaptureResult<AirtimeReport> {
+        let mut cap =
+            Capture::from_file(path).map_err(|e| CaptureError::InvalidFile(e.to_string()))?;
+        let linktype = cap.get_datalink();
+        let radiotap = match linktype {
+            Linktype::IEEE802_11_RADIOTAP => true,
+            Linktype::IEEE802_11 => false,
+            _ => {
+                return Err(CaptureError::InvalidFile(format!(
+                    "not an 802.11 capture (link type {})",
+                    linktype
+                        .get_name()
+                        .unwrap_or_else(|_| linktype.0.to_string())
+                )))
+            }
+        };
+
+        let mut analyzer = Self::new();
+        loop {
+            match cap.next_packet() {
+                Ok(packet) => {
+                    let timestamp = packet_timestamp(packet.header);
+                    let length = packet.header.len as usize;
+                    if radiotap {
+                        analyzer.process_radiotap(timestamp, packet.data, length);
+                    } else {
+                        analyzer.process_frame(timestamp, packet.data, length, None);
+                    }
+                }
+                Err(pcap::Error::NoMorePackets) => break,
+                Err(e) => return Err(CaptureError::InvalidFile(e.to_string())),
+            }
+        }
+
+        let report = analyzer.finish();
+        info!(
+            "Analyzed {} 802.11 frames from {} ({} BSS)",
+            report.frames,
+            path.display(),
+            report.bss.len()
+        );
+        Ok(report)
+    }
+
+    /// Add a frame with a radiotap header.
+    ///
+    /// `original_length` is the length on the wire, which may exceed the
+    /// captured data when the capture was truncated.
+    pub fn process_radiotap(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        data: &[u8],
+        original_length: usize,
+    ) {
+        match RadiotapHeader::parse(data) {
+            Some(radiotap) => self.process_frame(
+                timestamp,
+                &data[radiotap.length..],
+                original_length.saturating_sub(radiotap.length),
+                Some(&radiotap),
+            ),
+            None => self.process_frame(timestamp, &[], 0, None),
+        }
+    }
+
+    /// Add an 802.11 frame, with the radiotap header it was received with.
+    pub fn process_frame(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        data: &[u8],
+        original_length: usize,
+        radiotap: Option<&RadiotapHeader>,
+    ) {
+        self.first.get_or_insert(timestamp);
+        self.last = Some(timestamp);
+        self.frames += 1;
+
+        if radiotap.is_some_and(|r| r.bad_fcs) {
+            self.fcs_errors += 1;
+            return;
+        }
+        if let Some(frequency) = radiotap.and_then(|r| r.frequency) {
+            *self.frequencies.entry(frequency).or_default() += 1;
+        }
+        let Some(frame) = Dot11Frame::parse(data) else {
+            return;
+        };
+
+        let has_fcs = radiotap.is_some_and(|r| r.has_fcs);
+        let on_air = if has_fcs {
+            original_length
+        } else {
+            original_length + FCS_LEN
+        };
+        let data = if has_fcs {
+            &data[..data.len().saturating_sub(FCS_LEN)]
+        } else {
+            data
+        };
+        let airtime = radiotap.and_then(|r| airtime_us(r, on_air));
+        match airtime {
+            Some(airtime) => self.airtime_us += airtime,
+            None => self.frames_without_rate += 1,
+        }
+        let body = data.get(frame.body_offset..).unwrap_or_default();
+
+        if frame.frame_type == FrameType::Control {
+            // The BSS is only known once stations have been seen in data frames
+            let address = frame.addr2.unwrap_or(frame.addr1);
+            let control = self.control.entry(address).or_default();
+            control.frames += 1;
+            control.bytes += on_air as u64;
+            control.airtime_us += airtime.unwrap_or_default();
+            return;
+        }
+
+        if frame.frame_type == FrameType::Management {
+            self.management(timestamp, &frame, body, radiotap);
+        }
+
+        let Some(bssid) = frame.bssid().filter(is_unicast) else {
+            return;
+        };
+        if let Some(station) = frame.station().filter(is_unicast) {
+            self.station_bss.insert(station, bssid);
+        }
+
+        let state = self
+            .bss
+            .entry(bssid)
+            .or_insert_with(|| BssState::new(bssid));
+        let bss = &mut state.activity;
+        bss.frames += 1;
+        bss.bytes += on_air as u64;
+        match frame.frame_type {
+            FrameType::Management => bss.management_frames += 1,
+            FrameType::Data => bss.data_frames += 1,
+            _ => {}
+        }
+        if frame.is_unicast() {
+            bss.retry_eligible_frames += 1;
+            if frame.retry {
+                bss.retries += 1;
+            }
+        }
+        state.airtime_us += airtime.unwrap_or_default();
+        if let Some(station) = frame.station().filter(is_unicast) {
+            state.stations.insert(station);
+        }
+
+        // Signal of frames the AP sent
+        let from_ap = frame.addr2 == Some(bssid);
+        if let (true, Some(signal)) = (from_ap, radiotap.and_then(|r| r.signal_dbm)) {
+            state.signal_sum += i64::from(signal);
+            state.signal_count += 1;
+        }
+        if bss.channel.is_none() {
+            bss.channel = radiotap
+                .and_then(|r| r.frequency)
+                .map(|f| Channel::from_frequency(u32::from(f)));
+        }
+    }
+
+    /// Beacons, probes and disconnects.
+    fn management(
+        &mut self,
+        timestamp: DateTime<Utc>,
+        frame: &Dot11Frame,
+        body: &[u8],
+        radiotap: Option<&RadiotapHeader>,
+    ) {
+        match frame.subtype {
+            frame::BEACON | frame::PROBE_RESPONSE => {
+                let Some(bssid) = frame.addr3.filter(is_unicast) else {
+                    return;
+                };
+                let Some(beacon) = BeaconBody::parse(body) else {
+                    return;
+                };
+                let state = self
+                    .bss
+                    .entry(bssid)
+                    .or_insert_with(|| BssState::new(bssid));
+                let bss = &mut state.activity;
+                if beacon.ssid.is_some() {
+                    bss.ssid = beacon.ssid;
+                }
+                if bss.channel.is_none() && radiotap.and_then(|r| r.frequency).is_none() {
+                    bss.channel = beacon.channel.map(|number| {
+                        let band = if number <= 14 {
+                            WifiBand::Band2_4GHz
+                        } else {
+                            WifiBand::Band5GHz
+                        };
+                        Channel::from_number(number, band)
+                    });
+                }
+                if frame.subtype == frame::BEACON {
+                    bss.beacons += 1;
+                    bss.beacon_interval_tu = Some(beacon.interval_tu);
+                    state.beacon_times.push(timestamp);
+                } else {
+                    self.probe_responses += 1;
+                }
+            }
+            frame::PROBE_REQUEST => {
+                self.probe_requests += 1;
+                if let Some(client) = frame.addr2 {
+                    self.probe_clients.insert(client);
+                }
+                match frame::probe_ssid(body) {
+                    Some(ssid) => *self.probed_ssids.entry(ssid).or_default() += 1,
+                    None => self.wildcard_probes += 1,
+                }
+            }
+            frame::DEAUTHENTICATION | frame::DISASSOCIATION => {
+                let kind = if frame.subtype == frame::DEAUTHENTICATION {
+                    DisconnectFrame::Deauthentication
+                } else {
+                    DisconnectFrame::Disassociation
+                };
+                let (Some(transmitter), Some(bssid)) = (frame.addr2, frame.addr3) else {
+                    return;
+                };
+                self.disconnects
+                    .entry((kind, transmitter, bssid))
+                    .or_default()
+                    .push(DisconnectEvent {
+                        timestamp,
+                        receiver: frame.addr1,
+                        reason: frame::reason_code(body),
+                    });
+            }
+            _ => {}
+        }
+    }
+
+    /// Build the report.
+    pub fn finish(mut self) -> AirtimeReport {
+        let duration_secs = match (self.first, self.last) {
+            (Some(first), Some(last)) => {
+                (last - first).num_microseconds().unwrap_or(0) as f64 / 1e6
+            }
+            _ => 0.0,
+        };
+
+        // Attribute control frames to the BSS of the AP or station that sent them
+        for (address, control) in std::mem::take(&mut self.control) {
+            let bssid = if self.bss.contains_key(&address) {
+                Some(address)
+            } else {
+                self.station_bss.get(&address).copied()
+            };
+            if let Some(state) = bssid.and_then(|b| self.bss.get_mut(&b)) {
+                state.activity.frames += control.frames;
+                state.activity.control_frames += control.frames;
+                state.activity.bytes += control.bytes;
+                state.airtime_us += control.airtime_us;
+            }
+        }
+
+        let mut bss: Vec<BssActivity> = self
+            .bss
+            .into_values()
+            .map(|mut state| {
+                let bss = &mut state.activity;
+                bss.airtime_us = state.airtime_us.round() as u64;
+                bss.stations = state.stations.len();
+                if state.signal_count > 0 {
+                    bss.signal_dbm = Some((state.signal_sum / state.signal_count) as i32);
+                }
+                beacon_timing(bss, &mut state.beacon_times);
+                state.activity
+            })
+            .collect();
+        bss.sort_by(|a, b| {
+            b.airtime_us
+                .cmp(&a.airtime_us)
+                .then(b.frames.cmp(&a.frames))
+        });
+
+        let disconnect_frames = self.disconnects.values().map(|e| e.len() as u64).sum();
+        let mut disconnect_bursts: Vec<DisconnectBurst> = self
+            .disconnects
+            .into_iter()
+            .flat_map(|((kind, transmitter, bssid), events)| {
+                bursts(kind, transmitter, bssid, events)
+            })
+            .collect();
+        disconnect_bursts.sort_by_key(|b| b.start);
+
+        let mut directed_ssids: Vec<(String, u64)> = self.probed_ssids.into_iter().collect();
+        directed_ssids.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let probes = ProbeActivity {
+            requests: self.probe_requests,
+            wildcard_requests: self.wildcard_probes,
+            clients: self.probe_clients.len(),
+            randomized_clients: self
+                .probe_clients
+                .iter()
+                .filter(|c| c[0] & 0x02 != 0)
+                .count(),
+            directed_ssids,
+            responses: self.probe_responses,
+        };
+
+        let channel = self
+            .frequencies
+            .into_iter()
+            .max_by_key(|&(frequency, count)| (count, std::cmp::Reverse(frequency)))
+            .map(|(frequency, _)| Channel::from_frequency(u32::from(frequency)));
+
+        AirtimeReport {
+            duration_secs,
+            frames: self.frames,
+            fcs_errors: self.fcs_errors,
+            frames_without_rate: self.frames_without_rate,
+            channel,
+            airtime_us: self.airtime_us.round() as u64,
+            bss,
+            disconnect_bursts,
+            disconnect_frames,
+            probes,
+        }
+    }
+}
+
+/// Measured beacon interval and missed beacons.
+fn beacon_timing(bss: &mut BssActivity, times: &mut [DateTime<Utc>]) {
+    times.sort();
+    let mut gaps: Vec<f64> = times
+        .windows(2)
+        .map(|w| (w[1] - w[0]).num_microseconds().unwrap_or(0) as f64 / 1000.0)
+        .filter(|gap| *gap > 0.0)
+        .collect();
+    if gaps.is_empty() {
+        return;
+    }
+    gaps.sort_by(f64::total_cmp);
+    bss.measured_beacon_interval_ms = Some(gaps[gaps.len() / 2]);
+
+    if let Some(interval_tu) = bss.beacon_interval_tu.filter(|tu| *tu > 0) {
+        let interval_ms = f64::from(interval_tu) * 1.024;
+        bss.missed_beacons = gaps
+            .iter()
+            .map(|gap| gap / interval_ms)
+            .filter(|ratio| *ratio >= MISSED_BEACON_RATIO)
+            .map(|ratio| ratio.round() as u64 - 1)
+            .sum();
+    }
+}
+
+/// Split one transmitter's disconnect frames into bursts.
+fn bursts(
+    kind: DisconnectFrame,
+    transmitter: [u8; 6],
+    bssid: [u8; 6],
+    mut events: Vec<DisconnectEvent>,
+) -> Vec<DisconnectBurst> {
+    events.sort_by_key(|e| e.timestamp);
+    let mut groups: Vec<&[DisconnectEvent]> = Vec::new();
+    let mut start = 0;
+    for i in 1..=events.len() {
+        let split = i == events.len()
+            || (events[i].timestamp - events[i - 1].timestamp).num_milliseconds()
+                > BURST_MAX_GAP_MS;
+        if split {
+            groups.push(&events[start..i]);
+            start = i;
+        }
+    }
+
+    groups
+        .into_iter()
+        .filter(|group| group.len() >= BURST_MIN_FRAMES)
+        .map(|group| {
+            let mut reasons: HashMap<u16, usize> = HashMap::new();
+            for reason in group.iter().filter_map(|e| e.reason) {
+                *reasons.entry(reason).or_default() += 1;
+            }
+            let mut reason_codes: Vec<(u16, usize)> = reasons.into_iter().collect();
+            reason_codes.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+            DisconnectBurst {
+                kind,
+                transmitter: MacAddress::new(transmitter),
+                bssid: Bssid::new(bssid),
+                frames: group.len() as u64,
+                broadcast_frames: group.iter().filter(|e| e.receiver == [0xFF; 6]).count() as u64,
+                targets: group
+                    .iter()
+                    .map(|e| e.receiver)
+                    .collect::<HashSet<_>>()
+                    .len(),
+                reason_codes: reason_codes.into_iter().map(|(code, _)| code).collect(),
+                start: group[0].timestamp,
+                end: group[group.len() - 1].timestamp,
+            }
+        })
+        .collect()
+}
+
+/// Time on air of a frame in microseconds, from its length and PHY rate.
+fn airtime_us(radiotap: &RadiotapHeader, length: usize) -> Option<f64> {
+    let rate = radiotap.phy_rate_mbps()?;
+    if rate <= 0.0 {
+        return None;
+    }
+    let preamble = match radiotap.mcs {
+        // Legacy preamble and SIG, HT/VHT signal and training fields
+        Some(mcs) if mcs.vht => 40.0 + 4.0 * f64::from(mcs.streams),
+        Some(mcs) => 32.0 + 4.0 * f64::from(mcs.streams),
+        // DSSS/CCK
+        None if matches!(rate, 1.0 | 2.0 | 5.5 | 11.0) => {
+            if radiotap.short_preamble {
+                96.0
+            } else {
+                192.0
+            }
+        }
+        // OFDM
+        None => 20.0,
+    };
+    Some(preamble + length as f64 * 8.0 / rate)
+}
+
+/// Individual (not group) address.
+fn is_unicast(address: &[u8; 6]) -> bool {
+    address[0] & 0x01 == 0 && *address != [0; 6]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const AP: [u8; 6] = [0x00, 0x11, 0x22, 0x33, 0x44, 0x55];
+    const NEIGHBOR: [u8; 6] = [0x00, 0x11, 0x22, 0x66, 0x77, 0x88];
+    const CLIENT: [u8; 6] = [0x3c, 0x22, 0xfb, 0x01, 0x02, 0x03];
+    const PHONE: [u8; 6] = [0x02, 0xaa, 0xbb, 0xcc, 0xdd, 0xee];
+    const BROADCAST: [u8; 6] = [0xff; 6];
+
+    /// Radiotap header with flags, rate (500 kbps units), channel and signal.
+    fn radiotap(rate: u8, frequency: u16, signal: i8) -> Vec<u8> {
+        let present: u32 = (1 << 1) | (1 << 2) | (1 << 3) | (1 << 5);
+        let mut header = vec![0, 0, 15, 0];
+        header.extend_from_slice(&present.to_le_bytes());
+        header.extend_from_slice(&[0, rate]);
+        header.extend_from_slice(&frequency.to_le_bytes());
+        header.extend_from_slice(&0x00a0_u16.to_le_bytes());
+        header.push(signal as u8);
+        header
+    }
+
+    /// 802.11 header: frame control, flags, duration, addresses, sequence.
+    fn header(fc: u8, flags: u8, addr1: [u8; 6], addr2: [u8; 6], addr3: [u8; 6]) -> Vec<u8> {
+        let mut frame = vec![fc, flags, 0, 0];
+        frame.extend_from_slice(&addr1);
+        frame.extend_from_slice(&addr2);
+        frame.extend_from_slice(&addr3);
+        frame.extend_from_slice(&[0, 0]);
+        frame
+    }
+
+    fn beacon(bssid: [u8; 6], ssid: &str) -> Vec<u8> {
+        let mut frame = header(0x80, 0, BROADCAST, bssid, bssid);
+        frame.extend_from_slice(&[0; 8]); // timestamp
+        frame.extend_from_slice(&100_u16.to_le_bytes());
+        frame.extend_from_slice(&[0x11, 0x04]);
+        frame.extend_from_slice(&[0, ssid.len() as u8]);
+        frame.extend_from_slice(ssid.as_bytes());
+        frame.extend_from_slice(&[3, 1, 6]);
+        frame
+    }
+
+    fn probe_request(client: [u8; 6], ssid: &str) -> Vec<u8> {
+        let mut frame = header(0x40, 0, BROADCAST, client, BROADCAST);
+        frame.extend_from_slice(&[0, ssid.len() as u8]);
+        frame.extend_from_slice(ssid.as_bytes());
+        frame
+    }
+
+    fn deauth(bssid: [u8; 6], receiver: [u8; 6], reason: u16) -> Vec<u8> {
+        let mut frame = header(0xc0, 0, receiver, bssid, bssid);
+        frame.extend_from_slice(&reason.to_le_bytes());
+        frame
+    }
+
+    fn qos_data(client: [u8; 6], bssid: [u8; 6], retry: bool) -> Vec<u8> {
+        let flags = 0x01 | 0x40 | if retry { 0x08 } else { 0 };
+        let mut frame = header(0x88, flags, bssid, client, bssid);
+        frame.extend_from_slice(&[0, 0]); // QoS control
+        frame.extend_from_slice(&[0xab; 1000]);
+        frame
+    }
+
+    fn ack(receiver: [u8; 6]) -> Vec<u8> {
+        let mut frame = vec![0xd4, 0, 0, 0];
+        frame.extend_from_slice(&receiver);
+        frame
+    }
+
+    /// Write a radiotap pcap; timestamps are microseconds from the start.
+    fn write_pcap(name: &str, frames: &[(u64, Vec<u8>)]) -> PathBuf {
+        let mut bytes = Vec::new();
+        for field in [0xa1b2_c3d4_u32, 0x0004_0002, 0, 0, 65535, 127] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        for (micros, frame) in frames {
+            let ts_sec = 1_700_000_000 + (micros / 1_000_000) as u32;
+            let len = frame.len() as u32;
+            for field in [ts_sec, (micros % 1_000_000) as u32, len, len] {
+                bytes.extend_from_slice(&field.to_le_bytes());
+            }
+            bytes.extend_from_slice(frame);
+        }
+
+        let path =
+            std::env::temp_dir().join(format!("netdiag-wlan-{}-{}.pcap", name, std::process::id()));
+        std::fs::write(&path, bytes).unwrap();
+        path
+    }
+
+    fn with_radiotap(rate: u8, signal: i8, frame: Vec<u8>) -> Vec<u8> {
+        let mut packet = radiotap(rate, 2437, signal);
+        packet.extend_from_slice(&frame);
+        packet
+    }
+
+    #[test]
+    fn test_analyze_radiotap_capture() {
+        let mut frames = Vec::new();
+        // Beacons every 102.4 ms for a second, the fifth one lost
+        for i in 0..10u64 {
+            if i != 4 {
+                frames.push((i * 102_400, with_radiotap(12, -45, beacon(AP, "office"))));
+            }
+        }
+        for i in 0..3u64 {
+            frames.push((
+                i * 102_400 + 50_000,
+                with_radiotap(2, -80, beacon(NEIGHBOR, "neighbor")),
+            ));
+        }
+        // Client traffic at 24 Mbps, a quarter of it retried, each acknowledged
+        for i in 0..20u64 {
+            let t = 10_000 + i * 40_000;
+            frames.push((t, with_radiotap(48, -60, qos_data(CLIENT, AP, i % 4 == 0))));
+            frames.push((t + 500, with_radiotap(48, -45, ack(CLIENT))));
+        }
+        // Probe requests from a randomized address
+        frames.push((20_000, with_radiotap(2, -70, probe_request(PHONE, ""))));
+        frames.push((21_000, with_radiotap(2, -70, probe_request(PHONE, ""))));
+        frames.push((
+            22_000,
+            with_radiotap(2, -70, probe_request(PHONE, "office")),
+        ));
+        // Broadcast deauthentication flood
+        for i in 0..12u64 {
+            frames.push((
+                900_000 + i * 5_000,
+                with_radiotap(2, -50, deauth(AP, BROADCAST, 7)),
+            ));
+        }
+        frames.sort_by_key(|(t, _)| *t);
+        let path = write_pcap("analyze", &frames);
+
+        let report = WlanAnalyzer::analyze_file(&path).unwrap();
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(report.frames, frames.len() as u64);
+        assert_eq!(report.channel.as_ref().map(|c| c.number), Some(6));
+        assert!(report.busy_percent() > 0.0);
+
+        let ap = &report.bss[0];
+        assert_eq!(ap.bssid, Bssid::new(AP));
+        assert_eq!(ap.ssid.as_deref(), Some("office"));
+        assert_eq!(ap.data_frames, 20);
+        assert_eq!(ap.control_frames, 20);
+        assert_eq!(ap.retries, 5);
+        assert_eq!(ap.retry_eligible_frames, 20);
+        assert!((ap.retry_percent() - 25.0).abs() < 0.01);
+        assert_eq!(ap.stations, 1);
+        assert_eq!(ap.beacons, 9);
+        assert_eq!(ap.beacon_interval_tu, Some(100));
+        assert_eq!(ap.missed_beacons, 1);
+        assert!((ap.measured_beacon_interval_ms.unwrap() - 102.4).abs() < 0.1);
+        assert_eq!(ap.signal_dbm, Some(-47));
+        assert!(report.airtime_percent(ap) > report.airtime_percent(&report.bss[1]));
+
+        let neighbor = report.find(&Bssid::new(NEIGHBOR)).unwrap();
+        assert_eq!(neighbor.ssid.as_deref(), Some("neighbor"));
+        assert_eq!(neighbor.beacons, 3);
+
+        assert_eq!(report.disconnect_frames, 12);
+        assert_eq!(report.disconnect_bursts.len(), 1);
+        let burst = &report.disconnect_bursts[0];
+        assert_eq!(burst.kind, DisconnectFrame::Deauthentication);
+        assert_eq!(burst.broadcast_frames, 12);
+        assert_eq!(burst.reason_codes, vec![7]);
+
+        assert_eq!(report.probes.requests, 3);
+        assert_eq!(report.probes.wildcard_requests, 2);
+        assert_eq!(report.probes.clients, 1);
+        assert_eq!(report.probes.randomized_clients, 1);
+        assert_eq!(
+            report.probes.directed_ssids,
+            vec![("office".to_string(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_bad_fcs_and_sparse_deauths() {
+        let mut bad = radiotap(12, 2437, -50);
+        bad[8] = 0x40;
+        bad.extend_from_slice(&beacon(AP, "office"));
+
+        let mut analyzer = WlanAnalyzer::new();
+        let start = DateTime::from_timestamp(1_700_000_000, 0).unwrap();
+        analyzer.process_radiotap(start, &bad, bad.len());
+        // A few deauthentications a second apart are not a burst
+        for i in 0..5 {
+            let packet = with_radiotap(2, -50, deauth(AP, CLIENT, 3));
+            let timestamp = start + chrono::Duration::seconds(2 * i);
+            analyzer.process_radiotap(timestamp, &packet, packet.len());
+        }
+
+        let report = analyzer.finish();
+        assert_eq!(report.fcs_errors, 1);
+        assert_eq!(report.disconnect_frames, 5);
+        assert!(report.disconnect_bursts.is_empty());
+        assert_eq!(report.bss[0].ssid, None);
+        assert_eq!(report.bss[0].retry_eligible_frames, 5);
+    }
+
+    #[test]
+    fn test_rejects_ethernet_capture() {
+        let path =
+            std::env::temp_dir().join(format!("netdiag-wlan-eth-{}.pcap", std::process::id()));
+        let mut bytes = Vec::new();
+        for field in [0xa1b2_c3d4_u32, 0x0004_0002, 0, 0, 65535, 1] {
+            bytes.extend_from_slice(&field.to_le_bytes());
+        }
+        std::fs::write(&path, bytes).unwrap();
+
+        let result = WlanAnalyzer::analyze_file(&path);
+        std::fs::remove_file(&path).ok();
+        assert!(matches!(result, Err(CaptureError::InvalidFile(_))));
+    }
+}
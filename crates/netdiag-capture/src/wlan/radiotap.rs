@@ -0,0 +1,249 @@
+//! Radiotap header decoding.
+//!
+//! Radiotap prefixes monitor-mode frames with the receive conditions: rate,
+//! channel, signal and flags. Fields are present according to a bitmap and
+//! aligned to their natural size relative to the start of the header.
+
+use serde::{Deserialize, Serialize};
+
+/// Frame includes the 4-byte FCS at the end.
+const FLAG_FCS: u8 = 0x10;
+/// Frame failed the FCS check.
+const FLAG_BAD_FCS: u8 = 0x40;
+/// Frame was sent with a short (DSSS) preamble.
+const FLAG_SHORT_PREAMBLE: u8 = 0x02;
+
+/// Presence bit that means another presence word follows.
+const PRESENT_EXT: u32 = 1 << 31;
+
+/// (alignment, size) of the fields in the default namespace, by presence bit.
+const FIELDS: [(usize, usize); 28] = [
+    (8, 8),  // 0: TSFT
+    (1, 1),  // 1: flags
+    (1, 1),  // 2: rate
+    (2, 4),  // 3: channel
+    (1, 2),  // 4: FHSS
+    (1, 1),  // 5: antenna signal (dBm)
+    (1, 1),  // 6: antenna noise (dBm)
+    (2, 2),  // 7: lock quality
+    (2, 2),  // 8: TX attenuation
+    (2, 2),  // 9: dB TX attenuation
+    (1, 1),  // 10: TX power (dBm)
+    (1, 1),  // 11: antenna
+    (1, 1),  // 12: antenna signal (dB)
+    (1, 1),  // 13: antenna noise (dB)
+    (2, 2),  // 14: RX flags
+    (2, 2),  // 15: TX flags
+    (1, 1),  // 16: RTS retries
+    (1, 1),  // 17: data retries
+    (4, 8),  // 18: XChannel
+    (1, 3),  // 19: MCS
+    (4, 8),  // 20: A-MPDU status
+    (2, 12), // 21: VHT
+    (8, 12), // 22: timestamp
+    (2, 12), // 23: HE
+    (2, 12), // 24: HE-MU
+    (2, 6),  // 25: HE-MU other user
+    (1, 1),  // 26: zero-length PSDU
+    (2, 4),  // 27: L-SIG
+];
+
+/// HT/VHT/HE modulation reported for a frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RadiotapMcs {
+    /// MCS index (per stream for VHT/HE).
+    pub index: u8,
+    /// Spatial streams.
+    pub streams: u8,
+    /// Channel width in MHz.
+    pub width_mhz: u16,
+    /// Short (400 ns) guard interval.
+    pub short_gi: bool,
+    /// VHT rather than HT.
+    pub vht: bool,
+}
+
+/// Decoded radiotap header.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RadiotapHeader {
+    /// Header length; the 802.11 frame follows.
+    pub length: usize,
+    /// Legacy rate in Mbps.
+    pub rate_mbps: Option<f64>,
+    /// Channel frequency in MHz.
+    pub frequency: Option<u16>,
+    /// Signal in dBm.
+    pub signal_dbm: Option<i8>,
+    /// Noise in dBm.
+    pub noise_dbm: Option<i8>,
+    /// HT/VHT modulation.
+    pub mcs: Option<RadiotapMcs>,
+    /// The frame ends with its FCS.
+    pub has_fcs: bool,
+    /// The frame failed the FCS check.
+    pub bad_fcs: bool,
+    /// Short DSSS preamble.
+    pub short_preamble: bool,
+}
+
+impl RadiotapHeader {
+    /// Parse the radiotap header at the start of a captured frame.
+    pub fn parse(data: &[u8]) -> Option<Self> {
+        if data.len() < 8 || data[0] != 0 {
+            return None;
+        }
+        let length = usize::from(u16::from_le_bytes([data[2], data[3]]));
+        if length < 8 || length > data.len() {
+            return None;
+        }
+        let header = &data[..length];
+
+        // Presence words, each with bit 31 set if another follows
+        let present = u32::from_le_bytes(header[4..8].try_into().ok()?);
+        let mut offset = 8;
+        let mut word = present;
+        while word & PRESENT_EXT != 0 {
+            word = u32::from_le_bytes(header.get(offset..offset + 4)?.try_into().ok()?);
+            offset += 4;
+        }
+
+        let mut radiotap = Self {
+            length,
+            ..Self::default()
+        };
+        for (bit, &(align, size)) in FIELDS.iter().enumerate() {
+            if present & (1 << bit) == 0 {
+                continue;
+            }
+            offset = offset.next_multiple_of(align);
+            let Some(field) = header.get(offset..offset + size) else {
+                break;
+            };
+            radiotap.apply_field(bit, field);
+            offset += size;
+        }
+        // Later bits have no fixed layout; fields after them can't be located
+        Some(radiotap)
+    }
+
+    fn apply_field(&mut self, bit: usize, field: &[u8]) {
+        match bit {
+            1 => {
+                self.has_fcs = field[0] & FLAG_FCS != 0;
+                self.bad_fcs = field[0] & FLAG_BAD_FCS != 0;
+                self.short_preamble = field[0] & FLAG_SHORT_PREAMBLE != 0;
+            }
+            2 if field[0] != 0 => self.rate_mbps = Some(f64::from(field[0]) / 2.0),
+            3 => self.frequency = Some(u16::from_le_bytes([field[0], field[1]])),
+            5 => self.signal_dbm = Some(field[0] as i8),
+            6 => self.noise_dbm = Some(field[0] as i8),
+            19 => self.mcs = parse_ht(field),
+            21 => self.mcs = parse_vht(field).or(self.mcs),
+            _ => {}
+        }
+    }
+
+    /// PHY rate of the frame in Mbps, if the header says how it was sent.
+    pub fn phy_rate_mbps(&self) -> Option<f64> {
+        match self.mcs {
+            Some(mcs) => mcs_rate(mcs),
+            None => self.rate_mbps,
+        }
+    }
+}
+
+/// HT MCS field: known, flags, index.
+fn parse_ht(field: &[u8]) -> Option<RadiotapMcs> {
+    const KNOWN_BW: u8 = 0x01;
+    const KNOWN_MCS: u8 = 0x02;
+    const KNOWN_GI: u8 = 0x04;
+
+    let (known, flags, index) = (field[0], field[1], field[2]);
+    if known & KNOWN_MCS == 0 {
+        return None;
+    }
+    Some(RadiotapMcs {
+        index: index % 8,
+        streams: index / 8 + 1,
+        width_mhz: if known & KNOWN_BW != 0 && flags & 0x03 == 1 {
+            40
+        } else {
+            20
+        },
+        short_gi: known & KNOWN_GI != 0 && flags & 0x04 != 0,
+        vht: false,
+    })
+}
+
+/// VHT field: known, flags, bandwidth, MCS/NSS per user, ...
+fn parse_vht(field: &[u8]) -> Option<RadiotapMcs> {
+    const KNOWN_GI: u16 = 0x0004;
+    const KNOWN_BW: u16 = 0x0040;
+
+    let known = u16::from_le_bytes([field[0], field[1]]);
+    let flags = field[2];
+    let mcs_nss = field[4];
+    let streams = mcs_nss & 0x0F;
+    if streams == 0 {
+        return None;
+    }
+    let width_mhz = if known & KNOWN_BW == 0 {
+        20
+    } else {
+        match field[3] & 0x1F {
+            0 => 20,
+            1..=3 => 40,
+            4..=10 => 80,
+            _ => 160,
+        }
+    };
+    Some(RadiotapMcs {
+        index: mcs_nss >> 4,
+        streams,
+        width_mhz,
+        short_gi: known & KNOWN_GI != 0 && flags & 0x04 != 0,
+        vht: true,
+    })
+}
+
+/// Data rate of an HT/VHT frame in Mbps.
+fn mcs_rate(mcs: RadiotapMcs) -> Option<f64> {
+    // 20 MHz, 800 ns guard interval, one stream
+    const RATES_20: [f64; 10] = [6.5, 13.0, 19.5, 26.0, 39.0, 52.0, 58.5, 65.0, 78.0, 86.7];
+
+    let base = *RATES_20.get(usize::from(mcs.index))?;
+    // Data subcarriers relative to 20 MHz (52)
+    let width = match mcs.width_mhz {
+        20 => 1.0,
+        40 => 108.0 / 52.0,
+        80 => 234.0 / 52.0,
+        _ => 468.0 / 52.0,
+    };
+    let gi = if mcs.short_gi { 10.0 / 9.0 } else { 1.0 };
+    Some(base * width * gi * f64::from(mcs.streams))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_ht_rate() {
+        // Extended presence bitmap, then the MCS field: 40 MHz, short GI, MCS 15
+        let present: u32 = (1 << 19) | (1 << 31);
+        let mut data = vec![0, 0, 15, 0];
+        data.extend_from_slice(&present.to_le_bytes());
+        data.extend_from_slice(&0_u32.to_le_bytes());
+        data.extend_from_slice(&[0x07, 0x05, 15]);
+        data.extend_from_slice(&[0x08, 0x00]);
+
+        let header = RadiotapHeader::parse(&data).unwrap();
+        assert_eq!(header.length, 15);
+        let mcs = header.mcs.unwrap();
+        assert_eq!((mcs.index, mcs.streams, mcs.width_mhz), (7, 2, 40));
+        assert!(mcs.short_gi);
+        assert!((header.phy_rate_mbps().unwrap() - 300.0).abs() < 0.1);
+
+        assert!(RadiotapHeader::parse(&data[..10]).is_none());
+    }
+}
@@ -63,7 +63,7 @@ pub enum Commands {
     Capture(CaptureArgs),
 
     /// Launch terminal user interface
-    Tui,
+    Tui(TuiArgs),
 
     /// Configuration management
     Config(ConfigArgs),
@@ -76,6 +76,9 @@ pub enum Commands {
 
     /// Auto-fix network issues
     Fix(FixArgs),
+
+    /// MAC address vendor lookup
+    Oui(OuiArgs),
 }
 
 /// Output format options
@@ -219,6 +222,39 @@ pub struct WifiArgs {
     /// Subcommand
     #[command(subcommand)]
     pub command: Option<WifiCommands>,
+
+    /// WiFi session recording
+    #[command(flatten)]
+    pub session: WifiSessionArgs,
+}
+
+/// Recording and replay of the WiFi provider
+#[derive(Parser, Debug, Clone, Default)]
+pub struct WifiSessionArgs {
+    /// Record every WiFi call and its result to a file
+    #[arg(
+        long,
+        value_name = "FILE",
+        global = true,
+        conflicts_with = "replay_session"
+    )]
+    pub record_session: Option<PathBuf>,
+
+    /// Serve WiFi results from a recorded session instead of the adapter
+    #[arg(long, value_name = "FILE", global = true)]
+    pub replay_session: Option<PathBuf>,
+
+    /// Replay speed relative to the recording (0 replays without delays)
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0, global = true)]
+    pub replay_speed: f64,
+}
+
+/// Arguments for the TUI command
+#[derive(Parser, Debug)]
+pub struct TuiArgs {
+    /// WiFi session recording
+    #[command(flatten)]
+    pub session: WifiSessionArgs,
 }
 
 /// WiFi subcommands
@@ -231,7 +267,205 @@ pub enum WifiCommands {
     /// Analyze WiFi channels
     Channels,
     /// Check for interference
-    Interference,
+    Interference {
+        /// Monitor-mode capture of the channel (radiotap pcap) for measured airtime and retries
+        #[arg(short, long)]
+        capture: Option<PathBuf>,
+    },
+    /// Analyze a monitor-mode capture: retries, airtime, beacons, deauths and probes
+    Airtime {
+        /// Capture file (radiotap or 802.11 pcap)
+        file: PathBuf,
+        /// Number of networks to list
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+    /// Floor-plan site survey
+    Survey(SurveyArgs),
+    /// Track roaming between access points
+    Roam {
+        /// Tracking duration in seconds
+        #[arg(short, long, default_value = "60")]
+        duration: u64,
+        /// Host to ping while tracking (defaults to the gateway)
+        #[arg(short, long)]
+        target: Option<std::net::IpAddr>,
+        /// Connection sampling interval in milliseconds
+        #[arg(long, default_value = "100")]
+        interval: u64,
+        /// Skip the concurrent pings
+        #[arg(long)]
+        no_ping: bool,
+    },
+    /// Detect rogue access points and evil twins
+    Rogue {
+        /// Allowlist of known BSSIDs/OUIs per SSID (TOML)
+        #[arg(short, long)]
+        allowlist: Option<PathBuf>,
+    },
+    /// Plan channels and widths for a set of access points
+    Plan {
+        /// Plan configuration with our APs and constraints (TOML)
+        #[arg(short, long)]
+        config: Option<PathBuf>,
+        /// Take our APs from the scan: every BSSID of these SSIDs
+        #[arg(short, long)]
+        ssid: Vec<String>,
+        /// Band to plan (overrides the configuration)
+        #[arg(short, long, value_enum)]
+        band: Option<SurveyBand>,
+        /// Allowed channel widths in MHz (overrides the configuration)
+        #[arg(short, long, value_delimiter = ',')]
+        width: Vec<u32>,
+        /// Allow DFS channels
+        #[arg(long)]
+        dfs: bool,
+        /// Country code for channel availability
+        #[arg(long)]
+        country: Option<String>,
+        /// Don't scan for neighbouring networks
+        #[arg(long)]
+        no_scan: bool,
+    },
+    /// Diagnose 802.1X (WPA-Enterprise) authentication
+    Enterprise {
+        /// Seconds to listen for authentication events
+        /// (defaults to 20 with --reconnect, otherwise 0)
+        #[arg(short, long)]
+        listen: Option<u64>,
+        /// Reconnect to capture a full authentication attempt
+        #[arg(long)]
+        reconnect: bool,
+        /// Save the supplicant transcript to a file
+        #[arg(long, conflicts_with = "replay")]
+        record: Option<PathBuf>,
+        /// Diagnose a saved supplicant transcript instead of the live interface
+        #[arg(long, conflicts_with_all = ["listen", "reconnect"])]
+        replay: Option<PathBuf>,
+    },
+}
+
+/// Arguments for the wifi survey command
+#[derive(Parser, Debug)]
+pub struct SurveyArgs {
+    /// Subcommand
+    #[command(subcommand)]
+    pub command: SurveyCommands,
+}
+
+/// Site survey subcommands
+#[derive(Subcommand, Debug)]
+pub enum SurveyCommands {
+    /// Create a survey project for a floor plan
+    New {
+        /// Project file to create
+        project: PathBuf,
+        /// Floor plan width in pixels
+        #[arg(long)]
+        width: u32,
+        /// Floor plan height in pixels
+        #[arg(long)]
+        height: u32,
+        /// Floor plan image used as the heatmap background
+        #[arg(long)]
+        image: Option<PathBuf>,
+        /// Floor plan scale in pixels per meter
+        #[arg(long)]
+        scale: Option<f64>,
+        /// Project name
+        #[arg(long)]
+        name: Option<String>,
+    },
+    /// Record a survey point at floor plan coordinates
+    Record {
+        /// Project file
+        project: PathBuf,
+        /// X coordinate in floor plan pixels
+        x: f64,
+        /// Y coordinate in floor plan pixels
+        y: f64,
+        /// Location description
+        #[arg(short, long)]
+        location: Option<String>,
+    },
+    /// Show survey statistics
+    Show {
+        /// Project file
+        project: PathBuf,
+    },
+    /// Render coverage heatmaps (SVG or PNG, by output extension)
+    Heatmap {
+        /// Project file
+        project: PathBuf,
+        /// Output file (.svg or .png)
+        #[arg(short, long)]
+        output: PathBuf,
+        /// Metric to render
+        #[arg(short, long, value_enum, default_value = "rssi")]
+        metric: SurveyMetric,
+        /// Render the strongest AP in a band instead of the connected AP
+        #[arg(long, value_enum, conflicts_with = "bssid")]
+        band: Option<SurveyBand>,
+        /// Render a single BSSID instead of the connected AP
+        #[arg(long)]
+        bssid: Option<String>,
+        /// Write one heatmap per band or per BSSID, suffixed to the output name
+        #[arg(long, value_enum, conflicts_with_all = ["band", "bssid"])]
+        split: Option<SurveySplit>,
+        /// Grid cell size in floor plan pixels
+        #[arg(long, default_value = "8")]
+        cell_size: u32,
+        /// Leave areas further than this from any point empty
+        /// (meters if the floor plan has a scale, otherwise pixels)
+        #[arg(long)]
+        radius: Option<f64>,
+    },
+    /// Start a repeat of a survey on the same floor plan
+    Repeat {
+        /// Existing project file
+        project: PathBuf,
+        /// New project file
+        output: PathBuf,
+        /// Name for the repeat survey
+        #[arg(long)]
+        name: Option<String>,
+    },
+}
+
+/// Heatmap metric options
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SurveyMetric {
+    /// Signal strength
+    Rssi,
+    /// Signal-to-noise ratio
+    Snr,
+    /// APs sharing the channel
+    CoChannel,
+    /// Transmit rate
+    TxRate,
+}
+
+/// Band options for survey heatmaps and channel plans
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SurveyBand {
+    /// 2.4 GHz
+    #[value(name = "2.4")]
+    Band2_4,
+    /// 5 GHz
+    #[value(name = "5")]
+    Band5,
+    /// 6 GHz
+    #[value(name = "6")]
+    Band6,
+}
+
+/// How to split survey heatmaps into several files
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum SurveySplit {
+    /// One heatmap per band
+    Band,
+    /// One heatmap per BSSID
+    Bssid,
 }
 
 /// Arguments for report command
@@ -273,7 +507,7 @@ pub struct CaptureArgs {
     #[arg(short, long)]
     pub interface: Option<String>,
 
-    /// BPF filter expression
+    /// Filter expression (BPF, or display fields like `dns.qname contains example`)
     #[arg(short, long)]
     pub filter: Option<String>,
 
@@ -289,10 +523,54 @@ pub struct CaptureArgs {
     #[arg(short, long)]
     pub promiscuous: bool,
 
-    /// Write capture to PCAP file
+    /// Write capture to PCAP file (a directory of rotating files with --rotate-*)
     #[arg(short, long)]
     pub output: Option<PathBuf>,
 
+    /// Start a new output file after this many megabytes (ring-buffer mode)
+    #[arg(long, requires = "output")]
+    pub rotate_size: Option<u64>,
+
+    /// Start a new output file after this many seconds (ring-buffer mode)
+    #[arg(long, requires = "output")]
+    pub rotate_interval: Option<u64>,
+
+    /// Number of ring-buffer files to keep (0 = keep all)
+    #[arg(long, default_value = "10")]
+    pub ring_files: usize,
+
+    /// Keep only packet headers in the output files
+    #[arg(long, requires = "output")]
+    pub headers_only: bool,
+
+    /// Read packets from a PCAP/PCAPNG file instead of a live interface
+    #[arg(short, long, conflicts_with = "interface")]
+    pub read: Option<PathBuf>,
+
+    /// Attribute traffic to local processes (Linux, live capture only)
+    #[arg(long, conflicts_with = "read")]
+    pub processes: bool,
+
+    /// Show IP fragments as captured instead of reassembling datagrams
+    #[arg(long)]
+    pub no_reassembly: bool,
+
+    /// Anonymise packets using a TOML policy file (with --read and --output, sanitise the file)
+    #[arg(long, value_name = "POLICY")]
+    pub anonymize: Option<PathBuf>,
+
+    /// Export flow records (ipfix://host[:port], netflow9://host[:port] or a JSON lines file)
+    #[arg(long, value_name = "TARGET")]
+    pub export_flows: Option<String>,
+
+    /// Export long-lived flows after this many seconds
+    #[arg(long, default_value = "60", requires = "export_flows")]
+    pub flow_active_timeout: u64,
+
+    /// Export flows after this many idle seconds
+    #[arg(long, default_value = "15", requires = "export_flows")]
+    pub flow_idle_timeout: u64,
+
     /// List available interfaces
     #[arg(long)]
     pub list_interfaces: bool,
@@ -451,3 +729,30 @@ pub enum FixCommands {
         id: String,
     },
 }
+
+/// Arguments for oui command
+#[derive(Parser, Debug)]
+pub struct OuiArgs {
+    /// Subcommand
+    #[command(subcommand)]
+    pub command: OuiCommands,
+}
+
+/// OUI subcommands
+#[derive(Subcommand, Debug)]
+pub enum OuiCommands {
+    /// Look up the vendor of MAC addresses or BSSIDs
+    Lookup {
+        /// MAC addresses (e.g. 00:1B:63:84:45:E6)
+        #[arg(required = true)]
+        addresses: Vec<String>,
+    },
+    /// Import IEEE registry CSVs (oui.csv, mam.csv, oui36.csv)
+    Update {
+        /// CSV files downloaded from the IEEE registration authority
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+    },
+    /// Show database statistics
+    Info,
+}
@@ -5,7 +5,12 @@ use color_eyre::eyre::Result;
 use comfy_table::{presets::UTF8_FULL, Table};
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
-use netdiag_capture::{list_devices, CaptureConfig, CaptureFilter, PacketCapture, Protocol};
+use netdiag_capture::{
+    list_devices, AnonymizationPolicy, Anonymizer, CaptureConfig, CaptureFilter, CaptureStats,
+    DnsTracker, FlowExportConfig, FlowExportTarget, FlowExporter, FlowTable, FragmentStats,
+    PacketCapture, ProcessResolver, Protocol, RingBufferConfig,
+};
+use std::path::Path;
 use std::time::Duration;
 
 /// Run the capture command.
@@ -18,8 +23,24 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
     println!("{}", style("Packet Capture").bold().cyan());
     println!();
 
-    // Check for root/admin privileges
-    let has_privilege = check_privilege();
+    let anonymization = match &args.anonymize {
+        Some(path) => match AnonymizationPolicy::load(path) {
+            Ok(policy) => Some(policy),
+            Err(e) => {
+                eprintln!("{}", style(format!("Failed to load policy: {}", e)).red());
+                return Ok(());
+            }
+        },
+        None => None,
+    };
+
+    // Sanitise an existing file for sharing
+    if let (Some(policy), Some(input), Some(output)) = (&anonymization, &args.read, &args.output) {
+        return anonymize_file(policy.clone(), input, output);
+    }
+
+    // Check for root/admin privileges (not needed to read a file)
+    let has_privilege = args.read.is_some() || check_privilege();
     if !has_privilege {
         println!(
             "{}",
@@ -33,8 +54,13 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
         println!();
     }
 
-    // Build capture configuration
-    let mut config = if let Some(ref iface) = args.interface {
+    // Build capture configuration. Live captures use netdiag-capture directly
+    // rather than `CaptureProvider`: the provider only forwards `CapturedPacket`,
+    // which lacks what flow, DNS, ring-buffer and anonymisation handling need.
+    let mut config = if let Some(ref path) = args.read {
+        println!("Reading from file: {}", style(path.display()).green());
+        CaptureConfig::for_file(path)
+    } else if let Some(ref iface) = args.interface {
         CaptureConfig::for_device(iface)
     } else {
         // Use default device
@@ -55,12 +81,28 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
 
     // Apply filter
     if let Some(ref filter_str) = args.filter {
-        config = config.with_filter(CaptureFilter::new(filter_str));
+        let filter = match CaptureFilter::parse(filter_str) {
+            Ok(filter) => filter,
+            Err(e) => {
+                eprintln!("{}", style(format!("Invalid filter: {}", e)).red());
+                return Ok(());
+            }
+        };
+        config = config.with_filter(filter);
         println!("Filter: {}", style(filter_str).yellow());
     }
 
     // Configure capture
-    config = config.promiscuous(args.promiscuous);
+    config = config
+        .promiscuous(args.promiscuous)
+        .attribute_processes(args.processes)
+        .reassemble_fragments(!args.no_reassembly);
+    if args.processes && !ProcessResolver::is_supported() {
+        println!(
+            "{}",
+            style("Process attribution is only available on Linux").yellow()
+        );
+    }
 
     if args.count > 0 {
         config = config.max_packets(args.count);
@@ -70,6 +112,57 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
         config = config.max_duration(Duration::from_secs(args.duration));
     }
 
+    if let Some(policy) = anonymization {
+        println!("Anonymisation: {}", style("enabled").green());
+        config = config.with_anonymization(policy);
+    }
+
+    // Ring-buffer output
+    if let Some(ring) = ring_buffer_config(&args) {
+        println!(
+            "Ring buffer: {} (keeping {} files{})",
+            style(ring.directory.display()).green(),
+            if ring.max_files == 0 {
+                "all".to_string()
+            } else {
+                ring.max_files.to_string()
+            },
+            if ring.headers_only {
+                ", headers only"
+            } else {
+                ""
+            }
+        );
+        config = config.with_ring_buffer(ring);
+    }
+
+    // Flow export
+    let mut exporter = match &args.export_flows {
+        Some(target) => {
+            let exporter = FlowExportTarget::parse(target).and_then(|target| {
+                FlowExporter::new(
+                    FlowExportConfig::new(target)
+                        .active_timeout(Duration::from_secs(args.flow_active_timeout))
+                        .inactive_timeout(Duration::from_secs(args.flow_idle_timeout)),
+                )
+            });
+            match exporter {
+                Ok(exporter) => {
+                    println!(
+                        "Exporting flows to: {}",
+                        style(&exporter.config().target).green()
+                    );
+                    Some(exporter)
+                }
+                Err(e) => {
+                    eprintln!("{}", style(format!("Failed to export flows: {}", e)).red());
+                    return Ok(());
+                }
+            }
+        }
+        None => None,
+    };
+
     println!();
 
     // Create capture
@@ -104,8 +197,18 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
 
     // Start capture
     let mut packet_count = 0usize;
+    let mut flows = FlowTable::new();
+    let mut dns = DnsTracker::new();
     let stats = capture.capture_sync(|packet| {
         packet_count += 1;
+        flows.update(&packet);
+        dns.update(&packet);
+        if let Some(ref mut flow_exporter) = exporter {
+            if let Err(e) = flow_exporter.observe(&packet) {
+                eprintln!("{}", style(format!("Flow export stopped: {}", e)).red());
+                exporter = None;
+            }
+        }
 
         // Format source/destination
         let src = packet
@@ -155,6 +258,52 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
             packet.length
         );
 
+        // Application-layer detail (DNS names, DHCP leases, TLS SNI, HTTP requests)
+        if let Some(ref app) = packet.application {
+            println!("{:>6} {}", "", style(app).dim());
+        }
+        if let Some(ref process) = packet.process {
+            println!("{:>6} {}", "", style(format!("process: {}", process)).dim());
+        }
+        if !packet.vlans.is_empty() {
+            let ids: Vec<String> = packet.vlans.iter().map(|id| id.to_string()).collect();
+            println!(
+                "{:>6} {}",
+                "",
+                style(format!("vlan: {}", ids.join(", "))).dim()
+            );
+        }
+        for tunnel in &packet.tunnels {
+            println!("{:>6} {}", "", style(format!("tunnel: {}", tunnel)).dim());
+        }
+        if packet.reassembled_fragments > 0 {
+            println!(
+                "{:>6} {}",
+                "",
+                style(format!(
+                    "reassembled from {} fragments",
+                    packet.reassembled_fragments
+                ))
+                .dim()
+            );
+        } else if let Some(fragment) = packet.fragment {
+            println!(
+                "{:>6} {}",
+                "",
+                style(format!(
+                    "fragment id={} offset={}{}",
+                    fragment.id,
+                    fragment.offset,
+                    if fragment.more_fragments {
+                        " (more)"
+                    } else {
+                        ""
+                    }
+                ))
+                .dim()
+            );
+        }
+
         // Update progress
         if let Some(ref pb) = pb {
             if args.count > 0 {
@@ -172,6 +321,17 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
         pb.finish_and_clear();
     }
 
+    if let Some(mut exporter) = exporter {
+        match exporter.flush() {
+            Ok(()) => println!(
+                "Exported {} flow records to {}",
+                exporter.records_exported(),
+                style(&exporter.config().target).green()
+            ),
+            Err(e) => eprintln!("{}", style(format!("Flow export failed: {}", e)).red()),
+        }
+    }
+
     // Print statistics
     match stats {
         Ok(stats) => {
@@ -236,6 +396,9 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
                 println!("{}", proto_table);
             }
 
+            print_encapsulation(&stats);
+            print_fragmentation(&stats.fragmentation);
+
             // Top talkers
             if !stats.top_talkers.is_empty() {
                 println!();
@@ -246,6 +409,12 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
                     println!("  {}. {} - {} packets", i + 1, ip, count);
                 }
             }
+
+            print_devices(&stats);
+            print_flows(&flows);
+
+            dns.finish_at_last_packet();
+            print_dns(&dns);
         }
         Err(e) => {
             eprintln!("{}", style(format!("Capture error: {}", e)).red());
@@ -255,6 +424,343 @@ pub async fn run(args: CaptureArgs) -> Result<()> {
     Ok(())
 }
 
+/// Print per-VLAN and per-tunnel traffic.
+fn print_encapsulation(stats: &CaptureStats) {
+    if !stats.vlans.is_empty() {
+        println!();
+        println!("{}", style("VLANs").bold().cyan());
+        println!();
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["VLAN", "Packets", "Bytes"]);
+
+        let mut vlans: Vec<_> = stats.vlans.iter().collect();
+        vlans.sort_by(|a, b| b.1.packets.cmp(&a.1.packets).then(a.0.cmp(b.0)));
+
+        for (id, counter) in vlans {
+            table.add_row(vec![
+                id.to_string(),
+                counter.packets.to_string(),
+                format_bytes(counter.bytes),
+            ]);
+        }
+
+        println!("{}", table);
+    }
+
+    if !stats.tunnels.is_empty() {
+        println!();
+        println!("{}", style("Tunnels").bold().cyan());
+        println!();
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Tunnel", "Packets", "Bytes"]);
+
+        let mut tunnels: Vec<_> = stats.tunnels.iter().collect();
+        tunnels.sort_by(|a, b| b.1.packets.cmp(&a.1.packets).then(a.0.cmp(b.0)));
+
+        for (label, counter) in tunnels {
+            table.add_row(vec![
+                label.clone(),
+                counter.packets.to_string(),
+                format_bytes(counter.bytes),
+            ]);
+        }
+
+        println!("{}", table);
+    }
+}
+
+/// Print the busiest source MAC addresses with their vendors.
+fn print_devices(stats: &CaptureStats) {
+    if stats.top_devices.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", style("Devices").bold().cyan());
+    println!();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["MAC", "Vendor", "Type", "Packets", "Bytes"]);
+
+    for device in &stats.top_devices {
+        table.add_row(vec![
+            device.mac.clone(),
+            device.vendor.clone().unwrap_or_else(|| "-".to_string()),
+            device
+                .kind
+                .map(|k| k.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            device.traffic.packets.to_string(),
+            format_bytes(device.traffic.bytes),
+        ]);
+    }
+
+    println!("{}", table);
+}
+
+/// Print IP fragmentation and path MTU signals.
+fn print_fragmentation(fragmentation: &FragmentStats) {
+    if fragmentation.fragments() == 0 && fragmentation.pmtu_messages == 0 {
+        return;
+    }
+
+    println!();
+    println!("{}", style("Fragmentation").bold().cyan());
+    println!();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["Metric", "Value"]);
+
+    table.add_row(vec![
+        "IPv4 fragments".to_string(),
+        fragmentation.ipv4_fragments.to_string(),
+    ]);
+    table.add_row(vec![
+        "IPv6 fragments".to_string(),
+        fragmentation.ipv6_fragments.to_string(),
+    ]);
+    table.add_row(vec![
+        "Datagrams reassembled".to_string(),
+        fragmentation.reassembled.to_string(),
+    ]);
+    table.add_row(vec![
+        "Incomplete (timed out)".to_string(),
+        fragmentation.timed_out.to_string(),
+    ]);
+    table.add_row(vec![
+        "Overlapping fragments".to_string(),
+        fragmentation.overlapping.to_string(),
+    ]);
+    if fragmentation.largest_fragment > 0 {
+        table.add_row(vec![
+            "Largest fragment".to_string(),
+            format!("{} bytes", fragmentation.largest_fragment),
+        ]);
+    }
+    if fragmentation.largest_datagram > 0 {
+        table.add_row(vec![
+            "Largest datagram".to_string(),
+            format!("{} bytes", fragmentation.largest_datagram),
+        ]);
+    }
+    table.add_row(vec![
+        "PMTU messages (ICMP)".to_string(),
+        fragmentation.pmtu_messages.to_string(),
+    ]);
+
+    println!("{}", table);
+
+    if fragmentation.timed_out > 0 {
+        println!(
+            "{}",
+            style(
+                "Some fragmented datagrams never completed; fragments may be dropped on the path"
+            )
+            .yellow()
+        );
+    }
+}
+
+/// Write an anonymised copy of a capture file.
+fn anonymize_file(policy: AnonymizationPolicy, input: &Path, output: &Path) -> Result<()> {
+    println!(
+        "Anonymising {} to {}",
+        style(input.display()).green(),
+        style(output.display()).green()
+    );
+
+    let mut anonymizer = Anonymizer::new(policy);
+    match anonymizer.anonymize_file(input, output) {
+        Ok(packets) => {
+            println!("{} packets written", style(packets).bold());
+            if anonymizer.unparsed_packets() > 0 {
+                println!(
+                    "{}",
+                    style(format!(
+                        "{} packets were not IP or ARP; payloads after the link header were cut",
+                        anonymizer.unparsed_packets()
+                    ))
+                    .yellow()
+                );
+            }
+        }
+        Err(e) => eprintln!("{}", style(format!("Anonymisation failed: {}", e)).red()),
+    }
+
+    Ok(())
+}
+
+/// Build the ring-buffer configuration from the rotation arguments.
+fn ring_buffer_config(args: &CaptureArgs) -> Option<RingBufferConfig> {
+    let directory = args.output.as_ref()?;
+    if args.rotate_size.is_none() && args.rotate_interval.is_none() && !args.headers_only {
+        return None;
+    }
+
+    let mut ring = RingBufferConfig::new(directory)
+        .max_files(args.ring_files)
+        .headers_only(args.headers_only);
+    if let Some(mb) = args.rotate_size {
+        ring = ring.max_file_size(mb * 1024 * 1024);
+    }
+    if let Some(secs) = args.rotate_interval {
+        ring = ring.max_file_duration(Duration::from_secs(secs));
+    }
+    Some(ring)
+}
+
+/// Print top conversations and TCP problems from the flow table.
+fn print_flows(flows: &FlowTable) {
+    if flows.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", style("Top Conversations").bold().cyan());
+    println!();
+
+    let with_process = flows.flows().any(|flow| flow.process.is_some());
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    let mut header = vec![
+        "Flow", "Packets", "Bytes", "RTT", "Retrans", "Dup ACK", "Zero Win", "RST",
+    ];
+    if with_process {
+        header.insert(1, "Process");
+    }
+    table.set_header(header);
+    for flow in flows.top_by_bytes(10) {
+        let mut row = vec![
+            flow.key.to_string(),
+            flow.packets.to_string(),
+            format_bytes(flow.bytes),
+            flow.handshake_rtt
+                .map(|rtt| format!("{:.1} ms", rtt.as_secs_f64() * 1000.0))
+                .unwrap_or_else(|| "-".to_string()),
+            flow.retransmissions.to_string(),
+            flow.duplicate_acks.to_string(),
+            flow.zero_window_events.to_string(),
+            flow.resets.to_string(),
+        ];
+        if with_process {
+            row.insert(
+                1,
+                flow.process
+                    .as_ref()
+                    .map(|process| process.to_string())
+                    .unwrap_or_else(|| "-".to_string()),
+            );
+        }
+        table.add_row(row);
+    }
+    println!("{}", table);
+    if flows.evicted() > 0 {
+        println!(
+            "{}",
+            style(format!(
+                "{} flows were dropped to keep the table within its size limit",
+                flows.evicted()
+            ))
+            .dim()
+        );
+    }
+
+    let processes = flows.top_processes(10);
+    if !processes.is_empty() {
+        println!();
+        println!("{}", style("Top Processes").bold().cyan());
+        println!();
+
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec!["Process", "PID", "Flows", "Packets", "Bytes"]);
+        for usage in processes {
+            table.add_row(vec![
+                usage.process.name.clone(),
+                usage.process.pid.to_string(),
+                usage.flows.to_string(),
+                usage.packets.to_string(),
+                format_bytes(usage.bytes),
+            ]);
+        }
+        println!("{}", table);
+    }
+
+    let problems = flows.top_by_problems(5);
+    if !problems.is_empty() {
+        println!();
+        println!("{}", style("Most Problematic Flows").bold().cyan());
+        println!();
+        for (i, flow) in problems.iter().enumerate() {
+            println!("  {}. {} - {} problems", i + 1, flow.key, flow.problems());
+        }
+    }
+
+    let findings = flows.findings();
+    if !findings.is_empty() {
+        println!();
+        println!("{}", style("TCP Analysis").bold().cyan());
+        println!();
+        for finding in findings {
+            println!("  {} {}", style("!").yellow().bold(), finding);
+        }
+    }
+}
+
+/// Print passive DNS response times and failures per resolver and name.
+fn print_dns(dns: &DnsTracker) {
+    if dns.is_empty() {
+        return;
+    }
+
+    println!();
+    println!("{}", style("DNS Performance").bold().cyan());
+    println!();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec![
+        "Resolver", "Queries", "Median", "p95", "Max", "Timeouts", "Retrans", "SERVFAIL",
+        "NXDOMAIN",
+    ]);
+    for (ip, stats) in dns.resolvers() {
+        table.add_row(vec![
+            ip.to_string(),
+            stats.queries.to_string(),
+            format_ms(stats.percentile_ms(50.0)),
+            format_ms(stats.percentile_ms(95.0)),
+            format_ms(stats.max_ms()),
+            stats.timeouts.to_string(),
+            stats.retransmissions.to_string(),
+            stats.servfail.to_string(),
+            stats.nxdomain.to_string(),
+        ]);
+    }
+    println!("{}", table);
+
+    let slowest = dns.slowest_names(5);
+    if !slowest.is_empty() {
+        println!();
+        println!("{}", style("Slowest Names").bold().cyan());
+        println!();
+        for (i, (name, stats)) in slowest.iter().enumerate() {
+            println!("  {}. {} - {}", i + 1, name, stats);
+        }
+    }
+}
+
+/// Format an optional millisecond value.
+fn format_ms(ms: Option<f64>) -> String {
+    ms.map(|ms| format!("{:.1} ms", ms))
+        .unwrap_or_else(|| "-".to_string())
+}
+
 /// List available network interfaces.
 fn list_interfaces() -> Result<()> {
     println!("{}", style("Available Network Interfaces").bold().cyan());
@@ -9,6 +9,20 @@ use netdiag_daemon::{
 };
 use std::path::PathBuf;
 
+#[cfg(target_os = "macos")]
+use netdiag_platform_macos::create_providers;
+
+#[cfg(target_os = "linux")]
+use netdiag_platform_linux::create_providers;
+
+#[cfg(target_os = "windows")]
+use netdiag_platform_windows::create_providers;
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn create_providers() -> netdiag_platform::PlatformProviders {
+    netdiag_platform::PlatformProviders::new()
+}
+
 /// Runs the daemon command.
 pub async fn run(args: &DaemonArgs, format: &OutputFormat) -> Result<()> {
     match &args.command {
@@ -75,7 +89,7 @@ async fn start_daemon(
     }
 
     // Create and start the service
-    let mut service = DaemonService::new(config);
+    let mut service = DaemonService::new(config).with_wifi_provider(create_providers().wifi);
     service.start().await?;
 
     if foreground {
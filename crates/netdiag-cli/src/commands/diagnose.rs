@@ -4,12 +4,14 @@ use crate::app::DiagnoseArgs;
 use color_eyre::eyre::Result;
 use console::style;
 use indicatif::{ProgressBar, ProgressStyle};
+use netdiag_capture::{CaptureConfig, DnsTracker, FlowTable, PacketCapture};
 use netdiag_connectivity::{
     identify_isp, DnsResolver, PathAnalyzer, PingConfig, Pinger, Tracer, TracerouteConfig,
 };
 use netdiag_platform::PlatformProviders;
 use netdiag_speed::{SpeedTestConfig, SpeedTester};
 use netdiag_types::diagnostics::{JitterStats, PacketLossStats};
+use netdiag_wifi::{Bottleneck, ThroughputEstimate};
 use std::net::IpAddr;
 use std::time::Duration;
 
@@ -169,10 +171,19 @@ pub async fn run(args: DiagnoseArgs) -> Result<()> {
         pb.inc(1);
     }
 
+    // Expected WiFi throughput, to tell WiFi and WAN bottlenecks apart when
+    // the default route goes over WiFi
+    let run_speed = !args.quick && !args.no_speed;
+    let wifi_throughput = if args.wifi || run_speed {
+        estimate_wifi_throughput(&providers).await
+    } else {
+        None
+    };
+
     // Step 9: WiFi analysis (if requested)
     if args.wifi {
         pb.set_message("Analyzing WiFi...");
-        let wifi_result = check_wifi(&providers).await;
+        let wifi_result = check_wifi(&providers, wifi_throughput.as_ref()).await;
         if !wifi_result.passed {
             issues_found += 1;
         }
@@ -181,9 +192,9 @@ pub async fn run(args: DiagnoseArgs) -> Result<()> {
     }
 
     // Step 10: Speed test (by default in comprehensive mode, skip with --no-speed)
-    if !args.quick && !args.no_speed {
+    if run_speed {
         pb.set_message("Running speed test...");
-        let speed_result = check_speed(args.connections).await;
+        let speed_result = check_speed(args.connections, wifi_throughput.as_ref()).await;
         if !speed_result.passed {
             issues_found += 1;
         }
@@ -191,6 +202,28 @@ pub async fn run(args: DiagnoseArgs) -> Result<()> {
         pb.inc(1);
     }
 
+    // Step 11: Packet capture, TCP flow and passive DNS analysis (if requested)
+    if args.capture {
+        pb.set_message("Capturing traffic...");
+        let (traffic_result, observed_dns_result) = check_traffic().await;
+        if !traffic_result.passed {
+            issues_found += 1;
+        }
+        results.push(traffic_result);
+        if let Some(observed_dns_result) = observed_dns_result {
+            if !observed_dns_result.passed {
+                issues_found += 1;
+            }
+            // Show real resolver behaviour next to the active resolution check
+            let position = results
+                .iter()
+                .position(|r| r.name == "DNS Resolution")
+                .map_or(results.len(), |i| i + 1);
+            results.insert(position, observed_dns_result);
+        }
+        pb.inc(1);
+    }
+
     pb.finish_and_clear();
 
     // Print results
@@ -290,6 +323,10 @@ fn calculate_steps(args: &DiagnoseArgs) -> usize {
         steps += 1;
     }
 
+    if args.capture {
+        steps += 1;
+    }
+
     steps
 }
 
@@ -576,7 +613,10 @@ async fn check_connectivity() -> DiagnosticCheck {
     )
 }
 
-async fn check_wifi(providers: &PlatformProviders) -> DiagnosticCheck {
+async fn check_wifi(
+    providers: &PlatformProviders,
+    throughput: Option<&ThroughputEstimate>,
+) -> DiagnosticCheck {
     if !providers.wifi.is_available() {
         return DiagnosticCheck::pass("WiFi", "WiFi not available on this system");
     }
@@ -606,10 +646,42 @@ async fn check_wifi(providers: &PlatformProviders) -> DiagnosticCheck {
                     String::new()
                 };
 
+                let mut verbose = Vec::new();
+                if let Some(estimate) = throughput {
+                    verbose.push(format!(
+                        "Link: {}, {}, {} stream(s){}{}",
+                        estimate.link.standard,
+                        estimate.link.width,
+                        estimate.link.spatial_streams,
+                        estimate
+                            .mcs
+                            .map(|mcs| format!(", MCS {}", mcs))
+                            .unwrap_or_default(),
+                        estimate
+                            .link
+                            .guard_interval
+                            .map(|gi| format!(", {} GI", gi))
+                            .unwrap_or_default()
+                    ));
+                    verbose.push(format!(
+                        "PHY rate: {:.0} Mbps ({})",
+                        estimate.phy_rate_mbps, estimate.source
+                    ));
+                    verbose.push(format!(
+                        "Expected TCP throughput: ~{:.0} Mbps{}",
+                        estimate.expected_goodput_mbps,
+                        estimate
+                            .channel_utilization
+                            .map(|u| format!(" ({:.0}% channel utilization)", u))
+                            .unwrap_or_default()
+                    ));
+                }
+
                 DiagnosticCheck::pass(
                     "WiFi",
                     &format!("Connected to \"{}\"{}", conn.access_point.ssid, signal_info),
                 )
+                .with_verbose(verbose)
             } else {
                 DiagnosticCheck::fail(
                     "WiFi",
@@ -623,7 +695,29 @@ async fn check_wifi(providers: &PlatformProviders) -> DiagnosticCheck {
     }
 }
 
-async fn check_speed(connections: usize) -> DiagnosticCheck {
+/// Expected throughput of the WiFi link carrying the default route, from
+/// the link's current rate and signal and the measured utilization of its
+/// channel. `None` if the default route isn't over WiFi.
+async fn estimate_wifi_throughput(providers: &PlatformProviders) -> Option<ThroughputEstimate> {
+    if !providers.wifi.is_available() {
+        return None;
+    }
+    let route = providers.network.get_default_route().await.ok()??;
+    let interfaces = providers.wifi.list_wifi_interfaces().await.ok()?;
+    let iface = interfaces.iter().find(|i| i.name == route.interface)?;
+    let conn = providers
+        .wifi
+        .get_current_connection(&iface.name)
+        .await
+        .ok()??;
+
+    ThroughputEstimate::measure(providers.wifi.as_ref(), &conn).await
+}
+
+async fn check_speed(
+    connections: usize,
+    wifi_throughput: Option<&ThroughputEstimate>,
+) -> DiagnosticCheck {
     let tester = SpeedTester::new();
     let config = SpeedTestConfig {
         duration: Duration::from_secs(5),
@@ -700,6 +794,21 @@ async fn check_speed(connections: usize) -> DiagnosticCheck {
 
             // Determine pass/fail based on download speed
             let download_mbps = result.download_mbps().unwrap_or(0.0);
+
+            // Compare with what the WiFi link should carry
+            let assessment = wifi_throughput
+                .filter(|_| download_mbps > 0.0)
+                .map(|estimate| estimate.compare(download_mbps));
+            let details = match &assessment {
+                Some(a) if a.bottleneck != Bottleneck::Undetermined => {
+                    format!("{} (bottleneck: {})", details, a.bottleneck)
+                }
+                _ => details,
+            };
+            if let Some(assessment) = &assessment {
+                verbose.push(assessment.summary.clone());
+            }
+
             if download_mbps < 1.0 {
                 DiagnosticCheck::fail(
                     "Speed Test",
@@ -707,6 +816,16 @@ async fn check_speed(connections: usize) -> DiagnosticCheck {
                     Some("Very slow connection - check for network issues"),
                 )
                 .with_verbose(verbose)
+            } else if assessment
+                .as_ref()
+                .is_some_and(|a| a.bottleneck == Bottleneck::Wifi && a.measured_mbps < 25.0)
+            {
+                DiagnosticCheck::fail(
+                    "Speed Test",
+                    &details,
+                    Some("Speed is limited by the WiFi link - move closer to the AP or use a less busy channel"),
+                )
+                .with_verbose(verbose)
             } else {
                 DiagnosticCheck::pass("Speed Test", &details).with_verbose(verbose)
             }
@@ -719,6 +838,153 @@ async fn check_speed(connections: usize) -> DiagnosticCheck {
     }
 }
 
+/// Capture traffic on the default interface and analyse TCP flows and DNS lookups.
+///
+/// Uses netdiag-capture directly: flow and DNS analysis need decoded packets,
+/// which `CaptureProvider` does not deliver.
+async fn check_traffic() -> (DiagnosticCheck, Option<DiagnosticCheck>) {
+    let device = match netdiag_capture::default_device() {
+        Ok(device) => device,
+        Err(e) => {
+            return (
+                DiagnosticCheck::fail(
+                    "Traffic Analysis",
+                    &format!("No capture device: {}", e),
+                    Some("Packet capture requires libpcap/Npcap and elevated privileges"),
+                ),
+                None,
+            )
+        }
+    };
+
+    let config = CaptureConfig::for_device(&device.name)
+        .max_duration(Duration::from_secs(10))
+        .attribute_processes(true);
+    let capture = tokio::task::spawn_blocking(move || {
+        let mut flows = FlowTable::new();
+        let mut dns = DnsTracker::new();
+        PacketCapture::new(config)
+            .capture_sync(|packet| {
+                flows.update(&packet);
+                dns.update(&packet);
+                true
+            })
+            .map(|stats| (stats, flows, dns))
+    })
+    .await;
+
+    let (stats, flows, mut dns) = match capture {
+        Ok(Ok(result)) => result,
+        Ok(Err(e)) => {
+            return (
+                DiagnosticCheck::fail(
+                    "Traffic Analysis",
+                    &format!("Capture failed: {}", e),
+                    Some("Try running with sudo or 'netdiag capture' for details"),
+                ),
+                None,
+            )
+        }
+        Err(e) => {
+            return (
+                DiagnosticCheck::fail(
+                    "Traffic Analysis",
+                    &format!("Capture task failed: {}", e),
+                    None,
+                ),
+                None,
+            )
+        }
+    };
+
+    let details = format!(
+        "{} packets, {} flows on {} in {:.0}s",
+        stats.packets_captured,
+        flows.len(),
+        device.name,
+        stats.duration.as_secs_f64()
+    );
+
+    let mut verbose: Vec<String> = flows
+        .top_by_bytes(5)
+        .iter()
+        .map(|flow| match &flow.process {
+            Some(process) => format!("{} - {} bytes ({})", flow.key, flow.bytes, process),
+            None => format!("{} - {} bytes", flow.key, flow.bytes),
+        })
+        .collect();
+    verbose.extend(
+        flows
+            .top_processes(3)
+            .iter()
+            .map(|usage| format!("{} - {} bytes", usage.process, usage.bytes)),
+    );
+
+    dns.finish_at_last_packet();
+    let observed_dns = check_observed_dns(&dns);
+
+    let findings = flows.findings();
+    if findings.is_empty() {
+        return (
+            DiagnosticCheck::pass("Traffic Analysis", &details).with_verbose(verbose),
+            observed_dns,
+        );
+    }
+
+    verbose.extend(findings.iter().skip(1).map(ToString::to_string));
+    (
+        DiagnosticCheck::fail(
+            "Traffic Analysis",
+            &format!("{} - {}", details, findings[0]),
+            Some(
+                "Retransmissions and resets point to packet loss or an unhealthy host on the path",
+            ),
+        )
+        .with_verbose(verbose),
+        observed_dns,
+    )
+}
+
+/// Summarise DNS lookups observed during the capture, if there were any.
+fn check_observed_dns(dns: &DnsTracker) -> Option<DiagnosticCheck> {
+    if dns.is_empty() {
+        return None;
+    }
+
+    let overall = dns.overall();
+    let mut verbose: Vec<String> = dns
+        .resolvers()
+        .iter()
+        .map(|(ip, stats)| format!("{}: {}", ip, stats))
+        .collect();
+    verbose.extend(
+        dns.slowest_names(3)
+            .iter()
+            .map(|(name, stats)| format!("{}: {}", name, stats)),
+    );
+
+    let details = format!("{} (passive)", overall);
+    let median_ms = overall.percentile_ms(50.0).unwrap_or(0.0);
+
+    let check = if overall.failure_rate() >= 5.0 {
+        DiagnosticCheck::fail(
+            "Observed DNS",
+            &details,
+            Some("Real lookups are timing out or failing; check the configured DNS servers"),
+        )
+    } else if median_ms > 200.0 {
+        DiagnosticCheck::fail(
+            "Observed DNS",
+            &details,
+            Some("Real lookups are slow; consider a closer or faster DNS server"),
+        )
+    } else {
+        DiagnosticCheck::pass("Observed DNS", &details)
+    };
+
+    Some(check.with_verbose(verbose))
+}
+
 /// Comprehensive latency and jitter analysis.
 async fn check_latency_jitter() -> (DiagnosticCheck, DiagnosticCheck) {
     let pinger = Pinger::new();
@@ -70,7 +70,10 @@ pub async fn run() -> Result<()> {
                 );
 
                 if let Some(mac) = &iface.mac_address {
-                    println!("      MAC: {}", mac);
+                    match mac.vendor() {
+                        Some(vendor) => println!("      MAC: {} ({})", mac, vendor),
+                        None => println!("      MAC: {}", mac),
+                    }
                 }
                 for ip in &iface.ipv4_addresses {
                     println!("      IPv4: {}", ip.address);
@@ -7,6 +7,7 @@ pub mod daemon;
 pub mod diagnose;
 pub mod fix;
 pub mod info;
+pub mod oui;
 pub mod ping;
 pub mod report;
 pub mod speed;
@@ -0,0 +1,120 @@
+//! OUI vendor database command.
+
+use crate::app::{OuiArgs, OuiCommands, OutputFormat};
+use color_eyre::eyre::{eyre, Result};
+use console::style;
+use netdiag_types::network::{MacAddress, OuiDatabase, OuiRegistry};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Runs the oui command.
+pub fn run(args: &OuiArgs, format: &OutputFormat) -> Result<()> {
+    match &args.command {
+        OuiCommands::Lookup { addresses } => lookup(addresses, format),
+        OuiCommands::Update { files } => update(files),
+        OuiCommands::Info => info(),
+    }
+}
+
+fn lookup(addresses: &[String], format: &OutputFormat) -> Result<()> {
+    let db = OuiDatabase::global();
+    let mut results = Vec::new();
+    for address in addresses {
+        let mac: MacAddress = address
+            .parse()
+            .map_err(|e: String| eyre!("{}: {}", address, e))?;
+        results.push((mac, db.lookup(mac.octets())));
+    }
+
+    if matches!(format, OutputFormat::Json) {
+        let json: Vec<_> = results
+            .iter()
+            .map(|(mac, entry)| {
+                serde_json::json!({
+                    "mac": mac.to_string(),
+                    "kind": mac.kind(),
+                    "vendor": entry.as_ref().map(|e| &e.organization),
+                    "registry": entry.as_ref().map(|e| e.registry),
+                    "assignment": entry.as_ref().map(|e| e.assignment()),
+                })
+            })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&json)?);
+        return Ok(());
+    }
+
+    for (mac, entry) in &results {
+        println!("{}", style(mac).bold().cyan());
+        match entry {
+            Some(entry) => {
+                println!("  {} {}", style("Vendor:").bold(), entry.organization);
+                println!(
+                    "  {} {} ({})",
+                    style("Block:").bold(),
+                    entry.assignment(),
+                    entry.registry
+                );
+            }
+            None => println!("  {} {}", style("Vendor:").bold(), style("unknown").dim()),
+        }
+        println!("  {} {}", style("Type:").bold(), mac.kind());
+    }
+    Ok(())
+}
+
+fn update(files: &[PathBuf]) -> Result<()> {
+    let dir =
+        OuiDatabase::user_dir().ok_or_else(|| eyre!("Cannot determine the data directory"))?;
+    fs::create_dir_all(&dir)?;
+
+    for file in files {
+        let content = fs::read_to_string(file)?;
+        let db = OuiDatabase::from_csv(&content).map_err(|e| eyre!("{}: {}", file.display(), e))?;
+        if db.is_empty() {
+            return Err(eyre!("{}: no assignments found", file.display()));
+        }
+
+        let name = file
+            .file_name()
+            .ok_or_else(|| eyre!("{}: not a file", file.display()))?;
+        let target = Path::new(&dir).join(name).with_extension("csv");
+        fs::write(&target, content)?;
+        println!(
+            "{} Imported {} assignments from {}",
+            style("✓").green(),
+            db.len(),
+            file.display()
+        );
+    }
+
+    let db = OuiDatabase::load(&dir)?;
+    println!(
+        "  {} {} assignments in {}",
+        style("Database:").bold(),
+        db.len(),
+        dir.display()
+    );
+    Ok(())
+}
+
+fn info() -> Result<()> {
+    let db = OuiDatabase::global();
+    println!("{}", style("OUI Database").bold().cyan());
+    println!();
+    for registry in [OuiRegistry::MaL, OuiRegistry::MaM, OuiRegistry::MaS] {
+        println!(
+            "  {} {}",
+            style(format!("{}:", registry)).bold(),
+            db.count(registry)
+        );
+    }
+    match OuiDatabase::user_dir().filter(|d| d.is_dir()) {
+        Some(dir) => println!("  {} {}", style("User registries:").bold(), dir.display()),
+        None => println!(
+            "  {} {}",
+            style("User registries:").bold(),
+            style("none (bundled database only)").dim()
+        ),
+    }
+    Ok(())
+}
@@ -8,6 +8,7 @@ use netdiag_reports::{
     DiagnosticReport, DnsSummary, HtmlFormatter, InterfaceSummary, JsonFormatter,
     MarkdownFormatter, PdfFormatter, ReportBuilder, ReportFormatter, TextFormatter,
 };
+use netdiag_types::network::MacAddress;
 use std::fs;
 use std::time::Duration;
 
@@ -150,6 +151,11 @@ async fn generate_report() -> Result<DiagnosticReport> {
             ipv4_addresses: iface.ipv4.iter().map(|n| n.addr().to_string()).collect(),
             ipv6_addresses: iface.ipv6.iter().map(|n| n.addr().to_string()).collect(),
             mac_address: iface.mac_addr.map(|m| m.to_string()),
+            mac_vendor: iface
+                .mac_addr
+                .and_then(|m| m.to_string().parse::<MacAddress>().ok())
+                .and_then(|m| m.vendor())
+                .map(str::to_string),
             is_up: iface.is_up(),
             is_default,
         });
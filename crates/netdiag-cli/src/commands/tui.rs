@@ -1,10 +1,31 @@
 //! TUI command implementation.
 
+use crate::app::TuiArgs;
+use crate::commands::wifi::session_provider;
 use color_eyre::eyre::Result;
 
+#[cfg(target_os = "macos")]
+use netdiag_platform_macos::create_providers;
+
+#[cfg(target_os = "linux")]
+use netdiag_platform_linux::create_providers;
+
+#[cfg(target_os = "windows")]
+use netdiag_platform_windows::create_providers;
+
+#[cfg(not(any(target_os = "macos", target_os = "linux", target_os = "windows")))]
+fn create_providers() -> netdiag_platform::PlatformProviders {
+    netdiag_platform::PlatformProviders::new()
+}
+
 /// Run the TUI command.
-pub async fn run() -> Result<()> {
-    netdiag_tui::run()
-        .await
-        .map_err(|e| color_eyre::eyre::eyre!("{}", e))
+pub async fn run(args: &TuiArgs) -> Result<()> {
+    let session = &args.session;
+    let result = if session.record_session.is_some() || session.replay_session.is_some() {
+        let wifi = session_provider(create_providers().wifi, session)?;
+        netdiag_tui::run_with_wifi_provider(wifi).await
+    } else {
+        netdiag_tui::run().await
+    };
+    result.map_err(|e| color_eyre::eyre::eyre!("{}", e))
 }
@@ -1,10 +1,28 @@
 //! WiFi command implementation.
 
-use crate::app::{WifiArgs, WifiCommands};
-use color_eyre::eyre::Result;
+use crate::app::{
+    SurveyBand, SurveyCommands, SurveyMetric, SurveySplit, WifiArgs, WifiCommands, WifiSessionArgs,
+};
+use color_eyre::eyre::{eyre, Result};
+use comfy_table::{presets::UTF8_FULL, Table};
 use console::style;
-use netdiag_platform::PlatformProviders;
-use netdiag_types::wifi::{InterferenceLevel, WifiBand};
+use netdiag_capture::WlanAnalyzer;
+use netdiag_platform::{
+    PlatformProviders, RecordingWifiProvider, ReplayWifiProvider, WifiProvider, WifiRecording,
+};
+use netdiag_types::wifi::{
+    AirtimeReport, InterferenceLevel, RegulatoryDomain, SupplicantTranscript, WifiBand,
+};
+use netdiag_wifi::{
+    ApAllowlist, ApDeviceClass, ApVendor, ChannelPlanConfig, ChannelPlanner, EnterpriseDiagnosis,
+    FloorPlan, FloorPosition, HeatmapLayer, HeatmapMetric, HeatmapOptions, InterferenceAnalysis,
+    IssueSeverity, PingProbe, RoamingConfig, RoamingTracker, RogueDetector, SiteSurvey,
+    SurveyProject, ThroughputEstimate,
+};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[cfg(target_os = "macos")]
 use netdiag_platform_macos::create_providers;
@@ -20,15 +38,91 @@ fn create_providers() -> PlatformProviders {
     PlatformProviders::new()
 }
 
+/// Wrap a WiFi provider to record its session, or replace it with a replay.
+pub fn session_provider(
+    wifi: Arc<dyn WifiProvider>,
+    session: &WifiSessionArgs,
+) -> Result<Arc<dyn WifiProvider>> {
+    if let Some(path) = &session.replay_session {
+        let recording = WifiRecording::load(path)
+            .map_err(|e| eyre!("Failed to load {}: {}", path.display(), e))?;
+        eprintln!(
+            "{} {} (recorded {} on {})",
+            style("Replaying WiFi session").dim(),
+            path.display(),
+            recording.header.started.format("%Y-%m-%d %H:%M"),
+            recording.header.platform
+        );
+        return Ok(Arc::new(
+            ReplayWifiProvider::new(recording).with_speed(session.replay_speed),
+        ));
+    }
+    if let Some(path) = &session.record_session {
+        let recorder = RecordingWifiProvider::create(wifi, path)
+            .map_err(|e| eyre!("Failed to create {}: {}", path.display(), e))?;
+        return Ok(Arc::new(recorder));
+    }
+    Ok(wifi)
+}
+
 /// Run the WiFi command.
 pub async fn run(args: WifiArgs) -> Result<()> {
-    let providers = create_providers();
+    let mut providers = create_providers();
+    providers.wifi = session_provider(providers.wifi, &args.session)?;
 
     match args.command {
         Some(WifiCommands::Scan) => run_scan(&providers).await,
         Some(WifiCommands::Status) => run_status(&providers).await,
         Some(WifiCommands::Channels) => run_channels(&providers).await,
-        Some(WifiCommands::Interference) => run_interference(&providers).await,
+        Some(WifiCommands::Interference { capture }) => run_interference(&providers, capture).await,
+        Some(WifiCommands::Airtime { file, limit }) => run_airtime(&file, limit),
+        Some(WifiCommands::Survey(args)) => run_survey(&providers, args.command).await,
+        Some(WifiCommands::Roam {
+            duration,
+            target,
+            interval,
+            no_ping,
+        }) => run_roam(&providers, duration, target, interval, no_ping).await,
+        Some(WifiCommands::Rogue { allowlist }) => run_rogue(&providers, allowlist).await,
+        Some(WifiCommands::Plan {
+            config,
+            ssid,
+            band,
+            width,
+            dfs,
+            country,
+            no_scan,
+        }) => {
+            let mut plan_config = match config {
+                Some(path) => ChannelPlanConfig::load(&path)
+                    .map_err(|e| eyre!("Failed to load channel plan: {}", e))?,
+                None => ChannelPlanConfig::default(),
+            };
+            if let Some(band) = band {
+                plan_config.band = match band {
+                    SurveyBand::Band2_4 => WifiBand::Band2_4GHz,
+                    SurveyBand::Band5 => WifiBand::Band5GHz,
+                    SurveyBand::Band6 => WifiBand::Band6GHz,
+                };
+            }
+            if !width.is_empty() {
+                plan_config.widths = width;
+            }
+            plan_config.allow_dfs |= dfs;
+            if country.is_some() {
+                plan_config.country = country;
+            }
+            run_plan(&providers, plan_config, &ssid, !no_scan).await
+        }
+        Some(WifiCommands::Enterprise {
+            listen,
+            reconnect,
+            record,
+            replay,
+        }) => {
+            let listen = Duration::from_secs(listen.unwrap_or(if reconnect { 20 } else { 0 }));
+            run_enterprise(&providers, listen, reconnect, record, replay).await
+        }
         None => run_status(&providers).await,
     }
 }
@@ -79,14 +173,15 @@ async fn run_scan(providers: &PlatformProviders) -> Result<()> {
     }
 
     println!(
-        "{:<25} {:>8} {:>8} {:>12} {:>12}",
+        "{:<25} {:>8} {:>8} {:>12} {:>12}  {}",
         style("SSID").bold(),
         style("Signal").bold(),
         style("Channel").bold(),
         style("Security").bold(),
-        style("Standard").bold()
+        style("Standard").bold(),
+        style("Vendor").bold()
     );
-    println!("{}", "-".repeat(70));
+    println!("{}", "-".repeat(92));
 
     for ap in &access_points {
         let ssid_display = if ap.is_hidden {
@@ -121,9 +216,24 @@ async fn run_scan(providers: &PlatformProviders) -> Result<()> {
             .unwrap_or(&ap.wifi_standard.to_string())
             .to_string();
 
+        let vendor = ApVendor::identify(ap);
+        let vendor_display = match (&vendor.name, vendor.class) {
+            (Some(name), _) if name.chars().count() > 20 => {
+                format!("{}...", name.chars().take(17).collect::<String>())
+            }
+            (Some(name), _) => name.clone(),
+            (None, ApDeviceClass::MobileHotspot) => "(hotspot)".to_string(),
+            (None, _) => "-".to_string(),
+        };
+
         println!(
-            "{:<25} {:>8} {:>8} {:>12} {:>12}",
-            ssid_styled, signal_colored, ap.channel.number, ap.security, standard_display
+            "{:<25} {:>8} {:>8} {:>12} {:>12}  {}",
+            ssid_styled,
+            signal_colored,
+            ap.channel.number,
+            ap.security,
+            standard_display,
+            style(vendor_display).dim()
         );
     }
 
@@ -169,7 +279,15 @@ async fn run_status(providers: &PlatformProviders) -> Result<()> {
     );
 
     if let Some(ref mac) = interface.mac_address {
-        println!("  {} {}", style("MAC Address:").bold(), mac);
+        match mac.vendor() {
+            Some(vendor) => println!("  {} {} ({})", style("MAC Address:").bold(), mac, vendor),
+            None => println!(
+                "  {} {} ({})",
+                style("MAC Address:").bold(),
+                mac,
+                mac.kind()
+            ),
+        }
     }
 
     if let Some(ref country) = interface.country_code {
@@ -259,6 +377,41 @@ async fn run_status(providers: &PlatformProviders) -> Result<()> {
             if let Some(rx_rate) = conn.rx_rate {
                 println!("  {} {:.0} Mbps", style("RX Rate:").bold(), rx_rate);
             }
+            if let Some(mcs) = conn.mcs_index {
+                println!(
+                    "  {} {} ({} stream(s){})",
+                    style("MCS:").bold(),
+                    mcs,
+                    conn.spatial_streams.unwrap_or(1),
+                    conn.guard_interval
+                        .map(|gi| format!(", {} GI", gi))
+                        .unwrap_or_default()
+                );
+            }
+            if let Some(retries) = conn.tx_retry_percent() {
+                println!(
+                    "  {} {:.1}% of {} packets ({} failed)",
+                    style("TX Retries:").bold(),
+                    retries,
+                    conn.tx_packets.unwrap_or_default(),
+                    conn.tx_failed.unwrap_or_default()
+                );
+            }
+            if let Some(estimate) =
+                ThroughputEstimate::measure(providers.wifi.as_ref(), &conn).await
+            {
+                println!(
+                    "  {} ~{:.0} Mbps TCP (PHY {:.0} Mbps, {}{})",
+                    style("Expected:").bold(),
+                    estimate.expected_goodput_mbps,
+                    estimate.phy_rate_mbps,
+                    estimate.source,
+                    estimate
+                        .channel_utilization
+                        .map(|u| format!(", {:.0}% channel utilization", u))
+                        .unwrap_or_default()
+                );
+            }
 
             // Connection duration
             if let Some(duration) = conn.connected_duration {
@@ -331,6 +484,26 @@ async fn run_channels(providers: &PlatformProviders) -> Result<()> {
         return Ok(());
     }
 
+    let domain = interface
+        .country_code
+        .as_deref()
+        .map_or_else(RegulatoryDomain::world, RegulatoryDomain::for_country);
+    if domain.is_world() {
+        println!(
+            "  {} {}",
+            style("Regulatory domain:").bold(),
+            style("world (no country set)").dim()
+        );
+    } else {
+        println!(
+            "  {} {} (DFS: {})",
+            style("Regulatory domain:").bold(),
+            domain.country,
+            domain.dfs_region
+        );
+    }
+    println!();
+
     // Get current channel for highlighting
     let current_channel = providers
         .wifi
@@ -410,7 +583,12 @@ async fn run_channels(providers: &PlatformProviders) -> Result<()> {
                 InterferenceLevel::Severe => style("Severe").red().bold(),
             };
 
-            let dfs_note = if u.channel.is_dfs() { " [DFS]" } else { "" };
+            let dfs_note = match (domain.is_dfs(&u.channel), domain.is_indoor_only(&u.channel)) {
+                (true, true) => " [DFS] [indoor]",
+                (true, false) => " [DFS]",
+                (false, true) => " [indoor]",
+                (false, false) => "",
+            };
             let suffix = if is_current { " (current)" } else { "" };
             let rec = if u.recommended { " *" } else { "" };
 
@@ -448,6 +626,7 @@ async fn run_channels(providers: &PlatformProviders) -> Result<()> {
                 InterferenceLevel::Severe => style("Severe").red().bold(),
             };
 
+            let psc_note = if u.channel.is_psc() { " [PSC]" } else { "" };
             let suffix = if is_current { " (current)" } else { "" };
             let rec = if u.recommended { " *" } else { "" };
 
@@ -455,17 +634,18 @@ async fn run_channels(providers: &PlatformProviders) -> Result<()> {
 
             if is_current {
                 println!(
-                    "{}{} networks, {} utilization{}{}",
+                    "{}{} networks, {} utilization{}{}{}",
                     style(channel_str).cyan().bold(),
                     u.network_count,
                     utilization_style,
+                    psc_note,
                     suffix,
                     rec
                 );
             } else {
                 println!(
-                    "{}{} networks, {} utilization{}",
-                    channel_str, u.network_count, utilization_style, rec
+                    "{}{} networks, {} utilization{}{}",
+                    channel_str, u.network_count, utilization_style, psc_note, rec
                 );
             }
         }
@@ -501,7 +681,7 @@ async fn run_channels(providers: &PlatformProviders) -> Result<()> {
     Ok(())
 }
 
-async fn run_interference(providers: &PlatformProviders) -> Result<()> {
+async fn run_interference(providers: &PlatformProviders, capture: Option<PathBuf>) -> Result<()> {
     println!(
         "{}",
         style("WiFi Interference Analysis").bold().underlined()
@@ -684,5 +864,921 @@ async fn run_interference(providers: &PlatformProviders) -> Result<()> {
         }
     }
 
+    if let Some(path) = capture {
+        let report = WlanAnalyzer::analyze_file(&path)
+            .map_err(|e| eyre!("Failed to analyze {}: {}", path.display(), e))?;
+        let aps = providers
+            .wifi
+            .scan_access_points(&interface.name)
+            .await
+            .unwrap_or_default();
+        let mut analysis =
+            InterferenceAnalysis::analyze(&aps, Some(&connection.access_point.channel), noise);
+        analysis.apply_capture(&report, Some(&connection.access_point.bssid));
+
+        println!();
+        println!("{}", style("Measured (from capture):").bold());
+        print_airtime_summary(&report);
+        if let Some(ours) = report.find(&connection.access_point.bssid) {
+            println!(
+                "  {} {:.1}% retries, {:.1}% airtime",
+                style("Your network:").bold(),
+                ours.retry_percent(),
+                report.airtime_percent(ours)
+            );
+        }
+        for source in analysis.sources.iter().take(5) {
+            println!("  {} {}", style("  -").dim(), source.description);
+        }
+        if !analysis.recommendations.is_empty() {
+            println!();
+            println!("{}", style("Recommendations").cyan().bold());
+            for rec in &analysis.recommendations {
+                println!("  {} {}", style("*").cyan(), rec);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn run_airtime(path: &Path, limit: usize) -> Result<()> {
+    println!("{}", style("802.11 Airtime Analysis").bold().underlined());
+    println!();
+
+    let report = WlanAnalyzer::analyze_file(path)
+        .map_err(|e| eyre!("Failed to analyze {}: {}", path.display(), e))?;
+    println!("  {} {}", style("Capture:").bold(), path.display());
+    print_airtime_summary(&report);
+
+    if !report.bss.is_empty() {
+        println!();
+        let mut table = Table::new();
+        table.load_preset(UTF8_FULL);
+        table.set_header(vec![
+            "BSSID", "SSID", "Frames/s", "Retries", "Airtime", "Beacons", "Signal",
+        ]);
+        for bss in report.bss.iter().take(limit) {
+            let beacons = match (bss.beacon_interval_tu, bss.measured_beacon_interval_ms) {
+                (Some(tu), Some(ms)) => format!(
+                    "{} TU / {:.0} ms, {:.0}% lost",
+                    tu,
+                    ms,
+                    bss.beacon_loss_percent()
+                ),
+                (Some(tu), None) => format!("{} TU", tu),
+                _ => "-".to_string(),
+            };
+            table.add_row(vec![
+                bss.bssid.to_string(),
+                bss.ssid.clone().unwrap_or_else(|| "-".to_string()),
+                format!("{:.1}", report.frame_rate(bss)),
+                format!("{:.1}%", bss.retry_percent()),
+                format!("{:.1}%", report.airtime_percent(bss)),
+                beacons,
+                bss.signal_dbm
+                    .map(|s| format!("{} dBm", s))
+                    .unwrap_or_else(|| "-".to_string()),
+            ]);
+        }
+        println!("{table}");
+        if report.bss.len() > limit {
+            println!(
+                "  {} ...and {} more",
+                style("  -").dim(),
+                report.bss.len() - limit
+            );
+        }
+    }
+
+    if !report.disconnect_bursts.is_empty() {
+        println!();
+        println!("{}", style("Deauthentication/Disassociation Bursts").bold());
+        for burst in &report.disconnect_bursts {
+            println!(
+                "  {} {} {} frames from {} (BSSID {}), {} to broadcast, reason {}, {}",
+                style("[!]").red(),
+                burst.frames,
+                burst.kind,
+                burst.transmitter,
+                burst.bssid,
+                burst.broadcast_frames,
+                burst
+                    .reason_codes
+                    .iter()
+                    .map(u16::to_string)
+                    .collect::<Vec<_>>()
+                    .join("/"),
+                burst.start.format("%H:%M:%S")
+            );
+        }
+    }
+
+    let probes = &report.probes;
+    if probes.requests > 0 {
+        println!();
+        println!("{}", style("Probe Requests").bold());
+        println!(
+            "  {} {} ({} wildcard) from {} client(s), {} randomized",
+            style("Requests:").bold(),
+            probes.requests,
+            probes.wildcard_requests,
+            probes.clients,
+            probes.randomized_clients
+        );
+        if !probes.directed_ssids.is_empty() {
+            let ssids: Vec<String> = probes
+                .directed_ssids
+                .iter()
+                .take(10)
+                .map(|(ssid, count)| format!("{} ({})", ssid, count))
+                .collect();
+            println!("  {} {}", style("Probed for:").bold(), ssids.join(", "));
+        }
+    }
+
+    Ok(())
+}
+
+/// Capture-wide airtime lines shared by `airtime` and `interference`.
+fn print_airtime_summary(report: &AirtimeReport) {
+    println!(
+        "  {} {} in {:.1}s{}",
+        style("Frames:").bold(),
+        report.frames,
+        report.duration_secs,
+        if report.fcs_errors > 0 {
+            format!(" ({} with bad FCS)", report.fcs_errors)
+        } else {
+            String::new()
+        }
+    );
+    if let Some(channel) = &report.channel {
+        println!(
+            "  {} {} ({} MHz)",
+            style("Channel:").bold(),
+            channel.number,
+            channel.frequency
+        );
+    }
+    let busy = report.busy_percent();
+    let busy_style = match busy {
+        b if b >= 60.0 => style(format!("{:.0}%", b)).red(),
+        b if b >= 30.0 => style(format!("{:.0}%", b)).yellow(),
+        b => style(format!("{:.0}%", b)).green(),
+    };
+    println!("  {} {}", style("Channel busy:").bold(), busy_style);
+    if report.frames_without_rate > 0 {
+        println!(
+            "  {}",
+            style(format!(
+                "{} frame(s) without rate information are not counted in airtime",
+                report.frames_without_rate
+            ))
+            .dim()
+        );
+    }
+}
+
+async fn run_survey(providers: &PlatformProviders, command: SurveyCommands) -> Result<()> {
+    match command {
+        SurveyCommands::New {
+            project,
+            width,
+            height,
+            image,
+            scale,
+            name,
+        } => {
+            let name = name.unwrap_or_else(|| file_stem(&project));
+            let mut plan = FloorPlan::new(name.clone(), width, height);
+            plan.image = image;
+            plan.pixels_per_meter = scale;
+            SurveyProject::new(name, plan).save(&project)?;
+            println!(
+                "{} Created survey project {}",
+                style("[OK]").green(),
+                style(project.display()).cyan()
+            );
+            Ok(())
+        }
+        SurveyCommands::Record {
+            project: path,
+            x,
+            y,
+            location,
+        } => {
+            let mut project = SurveyProject::load(&path)?;
+
+            if !providers.wifi.is_available() {
+                return Err(eyre!("WiFi is not available on this system"));
+            }
+            let interfaces = providers.wifi.list_wifi_interfaces().await?;
+            let Some(interface) = interfaces.first() else {
+                return Err(eyre!("No WiFi interfaces found"));
+            };
+
+            let location =
+                location.unwrap_or_else(|| format!("Point {}", project.points.len() + 1));
+            let survey = SiteSurvey::new(providers.wifi.clone());
+            let point = project
+                .record(
+                    &survey,
+                    &interface.name,
+                    &location,
+                    FloorPosition::new(x, y),
+                )
+                .await?;
+
+            println!(
+                "{} {} at ({:.0}, {:.0}): {} dBm on {} ({}), {} nearby APs",
+                style("[OK]").green(),
+                style(&point.location).bold(),
+                x,
+                y,
+                point.rssi,
+                point.ssid,
+                point.bssid,
+                point.nearby_ap_count
+            );
+            project.save(&path)?;
+            Ok(())
+        }
+        SurveyCommands::Show { project: path } => {
+            let project = SurveyProject::load(&path)?;
+            let result = project.analyze();
+
+            println!("{}", style(&project.name).bold().underlined());
+            println!();
+            println!(
+                "  {} {} ({}x{})",
+                style("Floor plan:").bold(),
+                project.floor_plan.name,
+                project.floor_plan.width,
+                project.floor_plan.height
+            );
+            println!("  {} {}", style("Points:").bold(), result.total_points);
+            if result.total_points > 0 {
+                println!(
+                    "  {} avg {:.1} dBm, min {} dBm, max {} dBm",
+                    style("RSSI:").bold(),
+                    result.rssi_average,
+                    result.rssi_min,
+                    result.rssi_max
+                );
+                println!("  {} {}%", style("Coverage:").bold(), result.coverage_score);
+                let bands: Vec<String> = project.bands().iter().map(ToString::to_string).collect();
+                println!("  {} {}", style("Bands:").bold(), bands.join(", "));
+                println!("  {} {}", style("BSSIDs:").bold(), project.bssids().len());
+            }
+            if !result.recommendations.is_empty() {
+                println!();
+                for rec in &result.recommendations {
+                    println!("  {} {}", style("*").cyan(), rec);
+                }
+            }
+            Ok(())
+        }
+        SurveyCommands::Heatmap {
+            project: path,
+            output,
+            metric,
+            band,
+            bssid,
+            split,
+            cell_size,
+            radius,
+        } => {
+            let project = SurveyProject::load(&path)?;
+            let metric = match metric {
+                SurveyMetric::Rssi => HeatmapMetric::Rssi,
+                SurveyMetric::Snr => HeatmapMetric::Snr,
+                SurveyMetric::CoChannel => HeatmapMetric::CoChannel,
+                SurveyMetric::TxRate => HeatmapMetric::TxRate,
+            };
+            let options = HeatmapOptions {
+                cell_size,
+                radius: radius.map(|r| project.floor_plan.meters_to_pixels(r).unwrap_or(r)),
+                ..Default::default()
+            };
+
+            let layers: Vec<(HeatmapLayer, PathBuf)> = match split {
+                Some(SurveySplit::Band) => project
+                    .bands()
+                    .into_iter()
+                    .map(|b| (HeatmapLayer::Band(b), suffixed(&output, &b.to_string())))
+                    .collect(),
+                Some(SurveySplit::Bssid) => project
+                    .bssids()
+                    .into_iter()
+                    .map(|b| {
+                        let out = suffixed(&output, &b.replace(':', ""));
+                        (HeatmapLayer::Bssid(b), out)
+                    })
+                    .collect(),
+                None => {
+                    let layer = match (band, bssid) {
+                        (Some(band), _) => HeatmapLayer::Band(match band {
+                            SurveyBand::Band2_4 => WifiBand::Band2_4GHz,
+                            SurveyBand::Band5 => WifiBand::Band5GHz,
+                            SurveyBand::Band6 => WifiBand::Band6GHz,
+                        }),
+                        (None, Some(bssid)) => HeatmapLayer::Bssid(bssid),
+                        (None, None) => HeatmapLayer::Connected,
+                    };
+                    vec![(layer, output)]
+                }
+            };
+
+            for (layer, out) in layers {
+                let heatmap = project.heatmap(metric, layer, options.clone());
+                if heatmap.samples.is_empty() {
+                    println!(
+                        "{} No {} samples for {}",
+                        style("[--]").dim(),
+                        metric,
+                        heatmap.layer
+                    );
+                    continue;
+                }
+
+                let is_png = out
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("png"));
+                if is_png {
+                    std::fs::write(&out, heatmap.to_png()?)
+                } else {
+                    std::fs::write(&out, heatmap.to_svg())
+                }
+                .map_err(|e| eyre!("Failed to write {}: {}", out.display(), e))?;
+
+                println!(
+                    "{} {} heatmap for {} ({} points) -> {}",
+                    style("[OK]").green(),
+                    metric,
+                    heatmap.layer,
+                    heatmap.samples.len(),
+                    style(out.display()).cyan()
+                );
+            }
+            Ok(())
+        }
+        SurveyCommands::Repeat {
+            project: path,
+            output,
+            name,
+        } => {
+            let project = SurveyProject::load(&path)?;
+            let name = name.unwrap_or_else(|| format!("{} (repeat)", project.name));
+            project.repeat(name).save(&output)?;
+
+            println!(
+                "{} Created {} on floor plan {}",
+                style("[OK]").green(),
+                style(output.display()).cyan(),
+                project.floor_plan.name
+            );
+            let positions = project.positions();
+            if !positions.is_empty() {
+                println!();
+                println!("{}", style("Positions to re-survey:").bold());
+                for (location, pos) in positions {
+                    println!("  {:<20} ({:.0}, {:.0})", location, pos.x, pos.y);
+                }
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn run_roam(
+    providers: &PlatformProviders,
+    duration: u64,
+    target: Option<IpAddr>,
+    interval: u64,
+    no_ping: bool,
+) -> Result<()> {
+    println!("{}", style("WiFi Roaming Tracker").bold().underlined());
+    println!();
+
+    if !providers.wifi.is_available() {
+        println!("{}", style("WiFi is not available on this system").yellow());
+        return Ok(());
+    }
+
+    let interfaces = providers.wifi.list_wifi_interfaces().await?;
+    let interface = match interfaces.first() {
+        Some(i) => i,
+        None => {
+            println!("{}", style("No WiFi interfaces found").yellow());
+            return Ok(());
+        }
+    };
+
+    let config = RoamingConfig {
+        sample_interval: Duration::from_millis(interval.max(10)),
+        ..Default::default()
+    };
+    let mut tracker = RoamingTracker::new(providers.wifi.clone(), config);
+
+    let target = match (no_ping, target) {
+        (true, _) => None,
+        (false, Some(t)) => Some(t),
+        (false, None) => providers
+            .network
+            .get_default_gateway()
+            .await
+            .ok()
+            .flatten()
+            .map(|gw| gw.address),
+    };
+    match target {
+        Some(target) => {
+            println!("  {} {}", style("Probing:").bold(), target);
+            tracker = tracker.with_probe(Arc::new(PingProbe::new(target, Duration::from_secs(1))));
+        }
+        None if !no_ping => println!(
+            "  {}",
+            style("No gateway found - connectivity gaps will not be measured").dim()
+        ),
+        None => {}
+    }
+    println!(
+        "  {} {} for {}s (walk between APs now)",
+        style("Tracking:").bold(),
+        style(&interface.name).cyan(),
+        duration
+    );
+    println!();
+
+    let report = match tracker
+        .track(&interface.name, Duration::from_secs(duration))
+        .await
+    {
+        Ok(report) => report,
+        Err(e) => {
+            println!("{}", style(format!("Roaming tracking failed: {}", e)).red());
+            return Ok(());
+        }
+    };
+
+    println!(
+        "  {} {} roam(s), {} samples, {} while disconnected",
+        style("Summary:").bold(),
+        report.roams.len(),
+        report.samples,
+        report.disconnected_samples
+    );
+    if report.probes_sent > 0 {
+        println!(
+            "  {} {}/{} lost",
+            style("Probes:").bold(),
+            report.probes_lost,
+            report.probes_sent
+        );
+    }
+
+    if !report.roams.is_empty() {
+        println!();
+        println!("{}", style("Roams").bold());
+        for roam in &report.roams {
+            let gap = match roam.connectivity_gap {
+                Some(g) if g.as_millis() > 150 => style(format!("{} ms", g.as_millis())).red(),
+                Some(g) => style(format!("{} ms", g.as_millis())).green(),
+                None => style("n/a".to_string()).dim(),
+            };
+            let mut advertised = Vec::new();
+            if roam.neighbor_report_supported {
+                advertised.push("11k");
+            }
+            if roam.bss_transition_supported {
+                advertised.push("11v");
+            }
+            let ft = if roam.fast_transition { "FT" } else { "no FT" };
+            let assist = if advertised.is_empty() {
+                ft.to_string()
+            } else {
+                format!("{ft}, {} advertised", advertised.join("/"))
+            };
+            println!(
+                "  {} {} (ch {}, {} dBm) -> {} (ch {}, {} dBm)  gap {}  {}",
+                roam.timestamp.format("%H:%M:%S%.3f"),
+                roam.from_bssid,
+                roam.from_channel.number,
+                roam.rssi_before,
+                roam.to_bssid,
+                roam.to_channel.number,
+                roam.rssi_after,
+                gap,
+                style(assist).dim()
+            );
+        }
+    }
+
+    if !report.sticky_episodes.is_empty() {
+        println!();
+        println!("{}", style("Sticky client episodes").bold());
+        for episode in &report.sticky_episodes {
+            println!(
+                "  {} stayed on {} at {} dBm for {}s while {} was {} dBm",
+                episode.started.format("%H:%M:%S"),
+                episode.bssid,
+                episode.rssi,
+                episode.duration().as_secs(),
+                episode.best_bssid,
+                episode.best_rssi
+            );
+        }
+    }
+
+    if !report.recommendations.is_empty() {
+        println!();
+        println!("{}", style("Recommendations").cyan().bold());
+        for rec in &report.recommendations {
+            println!("  {} {}", style("*").cyan(), rec);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_rogue(providers: &PlatformProviders, allowlist: Option<PathBuf>) -> Result<()> {
+    println!(
+        "{}",
+        style("Rogue Access Point Detection").bold().underlined()
+    );
+    println!();
+
+    if !providers.wifi.is_available() {
+        println!("{}", style("WiFi is not available on this system").yellow());
+        return Ok(());
+    }
+
+    let detector = match allowlist {
+        Some(path) => {
+            let allowlist =
+                ApAllowlist::load(&path).map_err(|e| eyre!("Failed to load allowlist: {}", e))?;
+            println!(
+                "  {} {} ({} network(s))",
+                style("Allowlist:").bold(),
+                path.display(),
+                allowlist.networks.len()
+            );
+            RogueDetector::with_allowlist(allowlist)
+        }
+        None => RogueDetector::new(),
+    };
+
+    let interfaces = providers.wifi.list_wifi_interfaces().await?;
+    let interface = match interfaces.first() {
+        Some(i) => i,
+        None => {
+            println!("{}", style("No WiFi interfaces found").yellow());
+            return Ok(());
+        }
+    };
+
+    let aps = match providers.wifi.scan_access_points(&interface.name).await {
+        Ok(aps) => aps,
+        Err(e) => {
+            println!("{}", style(format!("Scan failed: {}", e)).red());
+            return Ok(());
+        }
+    };
+
+    let analysis = detector.detect(&aps);
+    println!(
+        "  {} {} access point(s), {} network(s)",
+        style("Scanned:").bold(),
+        aps.len(),
+        analysis.networks_checked
+    );
+    println!();
+
+    if analysis.findings.is_empty() {
+        println!(
+            "  {} No rogue access points detected",
+            style("[OK]").green()
+        );
+    } else {
+        println!("{}", style("Findings").bold());
+        for finding in &analysis.findings {
+            let severity = match finding.severity {
+                IssueSeverity::Critical => style(finding.severity.to_string()).red().bold(),
+                IssueSeverity::High => style(finding.severity.to_string()).red(),
+                IssueSeverity::Medium => style(finding.severity.to_string()).yellow(),
+                _ => style(finding.severity.to_string()).dim(),
+            };
+            println!(
+                "  [{}] {} - {} {} (ch {}, {} dBm)",
+                severity,
+                style(finding.kind).bold(),
+                finding.ssid,
+                finding.bssid,
+                finding.channel.number,
+                finding.rssi
+            );
+            println!("      {}", style(&finding.description).dim());
+        }
+    }
+
+    if !analysis.recommendations.is_empty() {
+        println!();
+        println!("{}", style("Recommendations").cyan().bold());
+        for rec in &analysis.recommendations {
+            println!("  {} {}", style("*").cyan(), rec);
+        }
+    }
+
+    Ok(())
+}
+
+async fn run_plan(
+    providers: &PlatformProviders,
+    mut config: ChannelPlanConfig,
+    ssids: &[String],
+    scan: bool,
+) -> Result<()> {
+    println!("{}", style("Channel Plan").bold().underlined());
+    println!();
+
+    let mut aps = Vec::new();
+    if scan || !ssids.is_empty() {
+        if !providers.wifi.is_available() {
+            return Err(eyre!("WiFi is not available on this system"));
+        }
+        let interfaces = providers.wifi.list_wifi_interfaces().await?;
+        let interface = interfaces
+            .first()
+            .ok_or_else(|| eyre!("No WiFi interfaces found"))?;
+        aps = providers
+            .wifi
+            .scan_access_points(&interface.name)
+            .await
+            .map_err(|e| eyre!("Scan failed: {}", e))?;
+        config.add_scanned_aps(&aps, ssids);
+    }
+
+    if config.aps.is_empty() {
+        return Err(eyre!(
+            "No access points to plan; pass --config or --ssid with a network in range"
+        ));
+    }
+
+    let band = config.band;
+    let mut planner = ChannelPlanner::new(config)?;
+    if scan {
+        planner = planner.with_neighbors(&aps);
+    }
+    let plan = planner.plan()?;
+
+    println!("  {} {}", style("Band:").bold(), band);
+    println!(
+        "  {} {}",
+        style("Access points:").bold(),
+        plan.assignments.len()
+    );
+    println!(
+        "  {} {}",
+        style("Neighbouring networks:").bold(),
+        plan.neighbors_considered
+    );
+    println!();
+
+    let mut table = Table::new();
+    table.load_preset(UTF8_FULL);
+    table.set_header(vec!["AP", "Current", "Planned", "Width", "Interference"]);
+    for assignment in &plan.assignments {
+        let current = assignment
+            .current
+            .map(|c| format!("{} ({} MHz)", c.number, c.width.mhz()))
+            .unwrap_or_else(|| "-".to_string());
+        let planned = if assignment.changed {
+            format!("{} *", assignment.channel.number)
+        } else {
+            assignment.channel.number.to_string()
+        };
+        table.add_row(vec![
+            assignment.name.clone(),
+            current,
+            planned,
+            format!("{} MHz", assignment.channel.width.mhz()),
+            format!("{:.2}", assignment.interference),
+        ]);
+    }
+    println!("{}", table);
+    println!("{}", style("* = channel or width changes").dim());
+    println!();
+
+    match (plan.current_score, plan.improvement()) {
+        (Some(current), Some(improvement)) => println!(
+            "  {} {} -> {} ({:+})",
+            style("Score:").bold(),
+            current,
+            plan.planned_score,
+            improvement
+        ),
+        _ => println!("  {} {}", style("Score:").bold(), plan.planned_score),
+    }
+    println!(
+        "  {} {} of {} AP(s)",
+        style("Changes:").bold(),
+        plan.changes().count(),
+        plan.assignments.len()
+    );
+
+    if !plan.notes.is_empty() {
+        println!();
+        println!("{}", style("Notes").cyan().bold());
+        for note in &plan.notes {
+            println!("  {} {}", style("*").cyan(), note);
+        }
+    }
+
+    Ok(())
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .map(|s| s.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "Survey".to_string())
+}
+
+/// `heatmap.svg` + `5GHz` -> `heatmap-5GHz.svg`
+fn suffixed(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = format!("{}-{}", file_stem(path), suffix);
+    if let Some(ext) = path.extension() {
+        name.push('.');
+        name.push_str(&ext.to_string_lossy());
+    }
+    path.with_file_name(name)
+}
+
+async fn run_enterprise(
+    providers: &PlatformProviders,
+    listen: Duration,
+    reconnect: bool,
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+) -> Result<()> {
+    println!("{}", style("Enterprise Authentication").bold().underlined());
+    println!();
+
+    let transcript = match replay {
+        Some(path) => {
+            let text = std::fs::read_to_string(&path)
+                .map_err(|e| eyre!("Failed to read {}: {}", path.display(), e))?;
+            println!("  {} {}", style("Transcript:").bold(), path.display());
+            SupplicantTranscript::parse(&text)
+        }
+        None => {
+            if !providers.wifi.is_available() {
+                println!("{}", style("WiFi is not available on this system").yellow());
+                return Ok(());
+            }
+            let interfaces = providers.wifi.list_wifi_interfaces().await?;
+            let interface = match interfaces.first() {
+                Some(i) => i,
+                None => {
+                    println!("{}", style("No WiFi interfaces found").yellow());
+                    return Ok(());
+                }
+            };
+            if reconnect {
+                println!(
+                    "{}",
+                    style(format!(
+                        "Reconnecting {} and listening for up to {}s...",
+                        interface.name,
+                        listen.as_secs()
+                    ))
+                    .dim()
+                );
+            } else if !listen.is_zero() {
+                println!(
+                    "{}",
+                    style(format!("Listening for up to {}s...", listen.as_secs())).dim()
+                );
+            }
+            match providers
+                .wifi
+                .supplicant_transcript(&interface.name, listen, reconnect)
+                .await?
+            {
+                Some(transcript) => transcript,
+                None => {
+                    println!(
+                        "{}",
+                        style(format!(
+                            "No supplicant control interface for {}",
+                            interface.name
+                        ))
+                        .yellow()
+                    );
+                    return Ok(());
+                }
+            }
+        }
+    };
+
+    if let Some(path) = record {
+        std::fs::write(&path, transcript.to_string())
+            .map_err(|e| eyre!("Failed to write {}: {}", path.display(), e))?;
+        println!("  {} {}", style("Recorded:").bold(), path.display());
+    }
+
+    let diagnosis = EnterpriseDiagnosis::from_transcript(&transcript);
+    let unknown = || "unknown".to_string();
+    println!(
+        "  {} {}",
+        style("Network:").bold(),
+        diagnosis.ssid.clone().unwrap_or_else(unknown)
+    );
+    if let Some(bssid) = &diagnosis.bssid {
+        println!("  {} {}", style("BSSID:").bold(), bssid);
+    }
+    if let Some(key_mgmt) = &diagnosis.key_mgmt {
+        println!("  {} {}", style("Key management:").bold(), key_mgmt);
+    }
+    if !diagnosis.is_enterprise {
+        println!();
+        println!(
+            "{}",
+            style("The network doesn't use 802.1X authentication").yellow()
+        );
+        return Ok(());
+    }
+    if let Some(state) = &diagnosis.wpa_state {
+        println!("  {} {}", style("State:").bold(), state);
+    }
+    println!(
+        "  {} {}",
+        style("EAP method:").bold(),
+        diagnosis.eap_method.map_or_else(unknown, |m| m.to_string())
+    );
+    if let Some(phase2) = diagnosis.phase2_method {
+        println!("  {} {}", style("Phase 2:").bold(), phase2);
+    }
+    if let Some(identity) = &diagnosis.identity {
+        println!("  {} {}", style("Identity:").bold(), identity);
+    }
+    if let Some(anonymous) = &diagnosis.anonymous_identity {
+        println!("  {} {}", style("Anonymous identity:").bold(), anonymous);
+    }
+    if let Some(server) = &diagnosis.server_match {
+        println!("  {} {}", style("Expected server:").bold(), server);
+    }
+    for cert in &diagnosis.server_certificates {
+        println!(
+            "  {} [{}] {}",
+            style("Server certificate:").bold(),
+            cert.depth,
+            cert.subject
+        );
+        if !cert.alt_names.is_empty() {
+            println!("      {}", style(cert.alt_names.join(", ")).dim());
+        }
+    }
+    if diagnosis.eap_attempts > 0 {
+        println!(
+            "  {} {}",
+            style("EAP attempts:").bold(),
+            diagnosis.eap_attempts
+        );
+    }
+
+    let result = if diagnosis.authenticated {
+        style("Authenticated".to_string()).green()
+    } else if let Some(stage) = diagnosis.failed_stage {
+        style(format!("Failed during {}", stage)).red()
+    } else if diagnosis.eap_attempts == 0 {
+        style("No authentication attempt observed (use --reconnect)".to_string()).dim()
+    } else {
+        style("Not authenticated".to_string()).yellow()
+    };
+    println!("  {} {}", style("Result:").bold(), result);
+    println!();
+
+    if diagnosis.issues.is_empty() {
+        println!(
+            "  {} No authentication problems found",
+            style("[OK]").green()
+        );
+        return Ok(());
+    }
+
+    println!("{}", style("Issues").bold());
+    for issue in &diagnosis.issues {
+        let severity = match issue.severity {
+            IssueSeverity::Critical => style(issue.severity.to_string()).red().bold(),
+            IssueSeverity::High => style(issue.severity.to_string()).red(),
+            IssueSeverity::Medium => style(issue.severity.to_string()).yellow(),
+            _ => style(issue.severity.to_string()).dim(),
+        };
+        println!("  [{}] {}", severity, style(issue.kind).bold());
+        println!("      {}", style(&issue.description).dim());
+        println!("      {} {}", style("*").cyan(), issue.remediation);
+    }
+
     Ok(())
 }
@@ -64,11 +64,12 @@ async fn run_command(command: Commands, format: &app::OutputFormat, verbose: u8)
         Commands::Wifi(args) => commands::wifi::run(args).await,
         Commands::Report(args) => commands::report::run(args).await,
         Commands::Capture(args) => commands::capture::run(args).await,
-        Commands::Tui => commands::tui::run().await,
+        Commands::Tui(args) => commands::tui::run(&args).await,
         Commands::Config(args) => commands::config::run(args).await,
         Commands::Completions(args) => commands::completions::run(args),
         Commands::Daemon(args) => commands::daemon::run(&args, format).await,
         Commands::Fix(args) => commands::fix::run(&args, format).await,
+        Commands::Oui(args) => commands::oui::run(&args, format),
     }
 }
 
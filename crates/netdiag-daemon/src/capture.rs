@@ -0,0 +1,322 @@
+//! Continuous and alert-triggered packet capture.
+
+use crate::config::{AlertCaptureConfig, FlowExportConfig, PacketCaptureConfig};
+use crate::error::Result;
+use crate::monitor::Alert;
+use chrono::Utc;
+use netdiag_capture::{
+    AnonymizationPolicy, CaptureConfig, CaptureFilter, CaptureHandle, FlightRecorder,
+    FlightRecorderConfig, FlowExportTarget, FlowExporter, PacketCapture, RingBufferConfig,
+};
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How often the flow meter expires idle flows on the wall clock.
+const FLOW_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Long-running capture that writes packets to rotating pcap files.
+pub struct CaptureRecorder {
+    config: PacketCaptureConfig,
+    handle: Option<CaptureHandle>,
+    packets: Arc<AtomicU64>,
+}
+
+impl CaptureRecorder {
+    /// Creates a new capture recorder.
+    pub fn new(config: PacketCaptureConfig) -> Self {
+        Self {
+            config,
+            handle: None,
+            packets: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Starts capturing in the background.
+    pub fn start(&mut self) -> Result<()> {
+        if self.handle.is_some() {
+            return Ok(());
+        }
+
+        let capture = PacketCapture::new(self.capture_config()?);
+        let (mut rx, handle) = capture.start()?;
+        tracing::info!(
+            "Continuous capture writing to {}",
+            self.config.directory.display()
+        );
+
+        // Packets are already on disk; just keep the channel drained
+        let packets = self.packets.clone();
+        tokio::spawn(async move {
+            while rx.recv().await.is_some() {
+                packets.fetch_add(1, Ordering::Relaxed);
+            }
+            tracing::info!(
+                "Continuous capture ended after {} packets",
+                packets.load(Ordering::Relaxed)
+            );
+        });
+
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stops capturing.
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.stop();
+        }
+    }
+
+    /// Checks if the capture is running.
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_stopped())
+    }
+
+    /// Number of packets captured so far.
+    pub fn packets_captured(&self) -> u64 {
+        self.packets.load(Ordering::Relaxed)
+    }
+
+    /// Builds the capture configuration.
+    fn capture_config(&self) -> Result<CaptureConfig> {
+        let mut ring = RingBufferConfig::new(&self.config.directory)
+            .max_files(self.config.max_files)
+            .headers_only(self.config.headers_only);
+        if let Some(mb) = self.config.max_file_size_mb {
+            ring = ring.max_file_size(mb * 1024 * 1024);
+        }
+        if let Some(interval) = self.config.rotate_interval {
+            ring = ring.max_file_duration(interval);
+        }
+
+        Ok(live_config(
+            self.config.interface.as_deref(),
+            self.config.filter.as_deref(),
+            self.config.anonymize_policy.as_deref(),
+        )?
+        .with_ring_buffer(ring))
+    }
+}
+
+impl Drop for CaptureRecorder {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Keeps recent packets in memory and saves them when an alert fires.
+pub struct AlertRecorder {
+    config: AlertCaptureConfig,
+    recorder: FlightRecorder,
+    handle: Option<CaptureHandle>,
+}
+
+impl AlertRecorder {
+    /// Creates a new alert recorder.
+    pub fn new(config: AlertCaptureConfig) -> Self {
+        let recorder = FlightRecorder::new(
+            FlightRecorderConfig::new(&config.directory)
+                .pre_trigger(config.pre_trigger)
+                .post_trigger(config.post_trigger)
+                .max_buffer_bytes(config.max_buffer_mb * 1024 * 1024),
+        );
+        Self {
+            config,
+            recorder,
+            handle: None,
+        }
+    }
+
+    /// Starts buffering packets in the background.
+    pub fn start(&mut self) -> Result<()> {
+        if self.handle.is_some() {
+            return Ok(());
+        }
+
+        let config = live_config(
+            self.config.interface.as_deref(),
+            self.config.filter.as_deref(),
+            self.config.anonymize_policy.as_deref(),
+        )?;
+        let capture = PacketCapture::new(config).with_flight_recorder(self.recorder.clone());
+        let (mut rx, handle) = capture.start()?;
+        tracing::info!(
+            "Flight recorder buffering {:?} of traffic for alerts",
+            self.config.pre_trigger
+        );
+
+        // Drain decoded packets and close dumps even when traffic stops;
+        // closing flushes pcap files, so it runs off the async workers and
+        // is not awaited, or the capture thread would block on a full channel
+        let recorder = self.recorder.clone();
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(Duration::from_secs(1));
+            let mut expiring: Option<tokio::task::JoinHandle<()>> = None;
+            loop {
+                tokio::select! {
+                    packet = rx.recv() => {
+                        if packet.is_none() {
+                            break;
+                        }
+                    }
+                    _ = ticker.tick() => {
+                        // Skip the tick while the previous flush is still running
+                        if expiring.as_ref().is_some_and(|task| !task.is_finished()) {
+                            continue;
+                        }
+                        let recorder = recorder.clone();
+                        expiring = Some(tokio::task::spawn_blocking(move || {
+                            recorder.expire(Utc::now());
+                        }));
+                    }
+                }
+            }
+            if let Some(task) = expiring {
+                let _ = task.await;
+            }
+            let _ = tokio::task::spawn_blocking(move || recorder.close()).await;
+        });
+
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Saves the packets around an alert and references the file from it.
+    /// The buffered packets are written on the blocking thread pool.
+    ///
+    /// Alerts below the configured severity are left untouched.
+    pub async fn on_alert(&self, alert: &mut Alert) {
+        if self.handle.is_none() || alert.severity < self.config.min_severity {
+            return;
+        }
+
+        let label = alert.target.clone().unwrap_or_else(|| "alert".to_string());
+        let recorder = self.recorder.clone();
+        match tokio::task::spawn_blocking(move || recorder.trigger(&label)).await {
+            Ok(Ok(path)) => alert.capture_file = Some(path),
+            Ok(Err(e)) => tracing::warn!("Failed to save alert capture: {}", e),
+            Err(e) => tracing::warn!("Alert capture task failed: {}", e),
+        }
+    }
+
+    /// Stops buffering and closes any capture in progress.
+    pub fn stop(&self) {
+        if let Some(handle) = &self.handle {
+            handle.stop();
+        }
+        self.recorder.close();
+    }
+}
+
+/// Aggregates live traffic into flow records and exports them.
+pub struct FlowMeter {
+    config: FlowExportConfig,
+    handle: Option<CaptureHandle>,
+}
+
+impl FlowMeter {
+    /// Creates a new flow meter.
+    pub const fn new(config: FlowExportConfig) -> Self {
+        Self {
+            config,
+            handle: None,
+        }
+    }
+
+    /// Starts capturing and exporting in the background.
+    pub fn start(&mut self) -> Result<()> {
+        if self.handle.is_some() {
+            return Ok(());
+        }
+
+        let target = FlowExportTarget::parse(&self.config.target)?;
+        let mut exporter = FlowExporter::new(
+            netdiag_capture::FlowExportConfig::new(target)
+                .active_timeout(self.config.active_timeout)
+                .inactive_timeout(self.config.inactive_timeout)
+                .observation_domain(self.config.observation_domain),
+        )?;
+        let config = live_config(
+            self.config.interface.as_deref(),
+            self.config.filter.as_deref(),
+            None,
+        )?;
+        let (mut rx, handle) = PacketCapture::new(config).start()?;
+        tracing::info!("Exporting flow records to {}", exporter.config().target);
+
+        // Sockets and files block, so the exporter runs on its own thread;
+        // flows are also expired on the wall clock so quiet links still export
+        let runtime = tokio::runtime::Handle::current();
+        std::thread::Builder::new()
+            .name("flow-export".to_string())
+            .spawn(move || {
+                let report = |result: netdiag_capture::CaptureResult<()>| {
+                    if let Err(e) = result {
+                        tracing::warn!("Flow export failed: {}", e);
+                    }
+                };
+                let mut next_sweep = Instant::now() + FLOW_SWEEP_INTERVAL;
+                loop {
+                    let wait = next_sweep.saturating_duration_since(Instant::now());
+                    match runtime.block_on(tokio::time::timeout(wait, rx.recv())) {
+                        Ok(Some(packet)) => report(exporter.observe(&packet)),
+                        Ok(None) => break,
+                        Err(_) => {}
+                    }
+                    if Instant::now() >= next_sweep {
+                        report(exporter.expire(Utc::now()));
+                        next_sweep = Instant::now() + FLOW_SWEEP_INTERVAL;
+                    }
+                }
+                report(exporter.flush());
+                tracing::info!(
+                    "Flow export ended after {} records",
+                    exporter.records_exported()
+                );
+            })?;
+
+        self.handle = Some(handle);
+        Ok(())
+    }
+
+    /// Stops capturing; remaining flows are exported as the capture ends.
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.stop();
+        }
+    }
+
+    /// Checks if the flow meter is running.
+    pub fn is_running(&self) -> bool {
+        self.handle.as_ref().is_some_and(|h| !h.is_stopped())
+    }
+}
+
+impl Drop for FlowMeter {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Builds a live capture configuration for an interface (default if unset).
+fn live_config(
+    interface: Option<&str>,
+    filter: Option<&str>,
+    anonymize_policy: Option<&Path>,
+) -> Result<CaptureConfig> {
+    let device = match interface {
+        Some(interface) => interface.to_string(),
+        None => netdiag_capture::default_device()?.name,
+    };
+
+    let mut config = CaptureConfig::for_device(&device);
+    if let Some(filter) = filter {
+        config = config.with_filter(CaptureFilter::parse(filter)?);
+    }
+    if let Some(path) = anonymize_policy {
+        config = config.with_anonymization(AnonymizationPolicy::load(path)?);
+    }
+    Ok(config)
+}
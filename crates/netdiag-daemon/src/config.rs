@@ -1,6 +1,7 @@
 //! Daemon configuration.
 
 use crate::error::{DaemonError, Result};
+use crate::monitor::AlertSeverity;
 use serde::{Deserialize, Serialize};
 use std::net::IpAddr;
 use std::path::{Path, PathBuf};
@@ -32,6 +33,22 @@ pub struct DaemonConfig {
     /// Storage settings.
     #[serde(default)]
     pub storage: StorageConfig,
+
+    /// Continuous packet capture settings.
+    #[serde(default)]
+    pub capture: PacketCaptureConfig,
+
+    /// Alert-triggered packet capture settings.
+    #[serde(default)]
+    pub alert_capture: AlertCaptureConfig,
+
+    /// Flow record export settings.
+    #[serde(default)]
+    pub flow_export: FlowExportConfig,
+
+    /// Rogue access point detection settings.
+    #[serde(default)]
+    pub rogue_detection: RogueDetectionConfig,
 }
 
 impl Default for DaemonConfig {
@@ -58,6 +75,10 @@ impl Default for DaemonConfig {
             ],
             alerts: AlertConfig::default(),
             storage: StorageConfig::default(),
+            capture: PacketCaptureConfig::default(),
+            alert_capture: AlertCaptureConfig::default(),
+            flow_export: FlowExportConfig::default(),
+            rogue_detection: RogueDetectionConfig::default(),
         }
     }
 }
@@ -89,6 +110,11 @@ impl DaemonConfig {
         // Validate thresholds
         self.alerts.validate()?;
 
+        self.capture.validate()?;
+        self.alert_capture.validate()?;
+        self.flow_export.validate()?;
+        self.rogue_detection.validate()?;
+
         Ok(())
     }
 
@@ -426,3 +452,310 @@ impl Default for StorageConfig {
         }
     }
 }
+
+/// Continuous packet capture configuration.
+///
+/// Packets are written to a ring buffer of pcap files so capture can run
+/// for long periods without filling the disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PacketCaptureConfig {
+    /// Enable continuous capture.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Interface to capture on (default interface if unset).
+    pub interface: Option<String>,
+
+    /// BPF filter expression.
+    pub filter: Option<String>,
+
+    /// Directory for the capture files.
+    #[serde(default = "default_capture_dir")]
+    pub directory: PathBuf,
+
+    /// Start a new file after this many megabytes.
+    #[serde(default = "default_capture_file_size")]
+    pub max_file_size_mb: Option<u64>,
+
+    /// Start a new file after this long.
+    #[serde(with = "humantime_serde", default)]
+    pub rotate_interval: Option<Duration>,
+
+    /// Number of files to keep (0 = keep all).
+    #[serde(default = "default_capture_files")]
+    pub max_files: usize,
+
+    /// Keep only packet headers.
+    #[serde(default)]
+    pub headers_only: bool,
+
+    /// Anonymisation policy (TOML) applied before packets are written.
+    pub anonymize_policy: Option<PathBuf>,
+}
+
+fn default_capture_dir() -> PathBuf {
+    if cfg!(target_os = "macos") {
+        PathBuf::from("/Library/Application Support/netdiag/captures")
+    } else if cfg!(target_os = "linux") {
+        PathBuf::from("/var/lib/netdiag/captures")
+    } else if cfg!(target_os = "windows") {
+        PathBuf::from(r"C:\ProgramData\netdiag\captures")
+    } else {
+        PathBuf::from("captures")
+    }
+}
+
+#[allow(clippy::unnecessary_wraps)] // serde default for an optional field
+const fn default_capture_file_size() -> Option<u64> {
+    Some(100)
+}
+
+const fn default_capture_files() -> usize {
+    10
+}
+
+impl Default for PacketCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: None,
+            filter: None,
+            directory: default_capture_dir(),
+            max_file_size_mb: default_capture_file_size(),
+            rotate_interval: None,
+            max_files: default_capture_files(),
+            headers_only: false,
+            anonymize_policy: None,
+        }
+    }
+}
+
+impl PacketCaptureConfig {
+    /// Validates the capture configuration.
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.max_file_size_mb.is_none() && self.rotate_interval.is_none() {
+            return Err(DaemonError::config(
+                "Continuous capture needs max_file_size_mb or rotate_interval",
+            ));
+        }
+        if self.max_file_size_mb == Some(0) {
+            return Err(DaemonError::config(
+                "max_file_size_mb must be greater than 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Alert-triggered ("flight recorder") capture configuration.
+///
+/// Recent packets are kept in memory; when an alert fires, the window around
+/// it is written to a pcap file referenced from the alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertCaptureConfig {
+    /// Enable alert-triggered capture.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Interface to capture on (default interface if unset).
+    pub interface: Option<String>,
+
+    /// BPF filter expression.
+    pub filter: Option<String>,
+
+    /// Directory for the alert captures.
+    #[serde(default = "default_alert_capture_dir")]
+    pub directory: PathBuf,
+
+    /// Traffic to keep from before the alert.
+    #[serde(with = "humantime_serde", default = "default_pre_trigger")]
+    pub pre_trigger: Duration,
+
+    /// Traffic to keep from after the alert.
+    #[serde(with = "humantime_serde", default = "default_post_trigger")]
+    pub post_trigger: Duration,
+
+    /// Memory limit for buffered packets (MB).
+    #[serde(default = "default_alert_buffer_size")]
+    pub max_buffer_mb: usize,
+
+    /// Minimum alert severity that triggers a capture.
+    #[serde(default = "default_alert_capture_severity")]
+    pub min_severity: AlertSeverity,
+
+    /// Anonymisation policy (TOML) applied before packets are buffered.
+    pub anonymize_policy: Option<PathBuf>,
+}
+
+fn default_alert_capture_dir() -> PathBuf {
+    default_capture_dir().join("alerts")
+}
+
+const fn default_pre_trigger() -> Duration {
+    Duration::from_secs(30)
+}
+
+const fn default_post_trigger() -> Duration {
+    Duration::from_secs(10)
+}
+
+const fn default_alert_buffer_size() -> usize {
+    64
+}
+
+const fn default_alert_capture_severity() -> AlertSeverity {
+    AlertSeverity::Warning
+}
+
+impl Default for AlertCaptureConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: None,
+            filter: None,
+            directory: default_alert_capture_dir(),
+            pre_trigger: default_pre_trigger(),
+            post_trigger: default_post_trigger(),
+            max_buffer_mb: default_alert_buffer_size(),
+            min_severity: default_alert_capture_severity(),
+            anonymize_policy: None,
+        }
+    }
+}
+
+impl AlertCaptureConfig {
+    /// Validates the alert capture configuration.
+    pub fn validate(&self) -> Result<()> {
+        if self.enabled && self.max_buffer_mb == 0 {
+            return Err(DaemonError::config("max_buffer_mb must be greater than 0"));
+        }
+        Ok(())
+    }
+}
+
+/// Flow record export configuration.
+///
+/// Captured packets are aggregated into flows and sent to a flow collector
+/// (IPFIX or `NetFlow` v9), or appended to a JSON lines file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowExportConfig {
+    /// Enable flow export.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Interface to capture on (default interface if unset).
+    pub interface: Option<String>,
+
+    /// BPF filter expression.
+    pub filter: Option<String>,
+
+    /// Export target (`ipfix://host[:port]`, `netflow9://host[:port]` or a file path).
+    #[serde(default)]
+    pub target: String,
+
+    /// Export long-lived flows after this long.
+    #[serde(with = "humantime_serde", default = "default_flow_active_timeout")]
+    pub active_timeout: Duration,
+
+    /// Export flows after this long without packets.
+    #[serde(with = "humantime_serde", default = "default_flow_inactive_timeout")]
+    pub inactive_timeout: Duration,
+
+    /// IPFIX observation domain / `NetFlow` v9 source ID.
+    #[serde(default)]
+    pub observation_domain: u32,
+}
+
+const fn default_flow_active_timeout() -> Duration {
+    Duration::from_secs(60)
+}
+
+const fn default_flow_inactive_timeout() -> Duration {
+    Duration::from_secs(15)
+}
+
+impl Default for FlowExportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: None,
+            filter: None,
+            target: String::new(),
+            active_timeout: default_flow_active_timeout(),
+            inactive_timeout: default_flow_inactive_timeout(),
+            observation_domain: 0,
+        }
+    }
+}
+
+impl FlowExportConfig {
+    /// Validates the flow export configuration.
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        netdiag_capture::FlowExportTarget::parse(&self.target)
+            .map_err(|e| DaemonError::config(e.to_string()))?;
+        if self.inactive_timeout.is_zero() || self.active_timeout.is_zero() {
+            return Err(DaemonError::config(
+                "Flow export timeouts must be greater than 0",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Rogue access point detection configuration.
+///
+/// Nearby networks are scanned periodically and every new rogue AP or
+/// evil-twin finding raises an alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RogueDetectionConfig {
+    /// Enable rogue AP detection.
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// WiFi interface to scan on (first WiFi interface if unset).
+    pub interface: Option<String>,
+
+    /// Scan interval.
+    #[serde(with = "humantime_serde", default = "default_rogue_interval")]
+    pub interval: Duration,
+
+    /// Allowlist of known BSSIDs/OUIs per SSID (TOML).
+    pub allowlist: Option<PathBuf>,
+}
+
+const fn default_rogue_interval() -> Duration {
+    Duration::from_secs(300)
+}
+
+impl Default for RogueDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interface: None,
+            interval: default_rogue_interval(),
+            allowlist: None,
+        }
+    }
+}
+
+impl RogueDetectionConfig {
+    /// Validates the rogue detection configuration.
+    pub fn validate(&self) -> Result<()> {
+        if !self.enabled {
+            return Ok(());
+        }
+        if self.interval < Duration::from_secs(10) {
+            return Err(DaemonError::config(
+                "Rogue detection interval must be at least 10s",
+            ));
+        }
+        if let Some(path) = &self.allowlist {
+            netdiag_wifi::ApAllowlist::load(path)
+                .map_err(|e| DaemonError::config(e.to_string()))?;
+        }
+        Ok(())
+    }
+}
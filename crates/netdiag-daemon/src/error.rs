@@ -76,6 +76,10 @@ pub enum DaemonError {
     /// Netdiag error.
     #[error("Netdiag error: {0}")]
     Netdiag(#[from] netdiag_types::Error),
+
+    /// Packet capture error.
+    #[error("Capture error: {0}")]
+    Capture(#[from] netdiag_capture::CaptureError),
 }
 
 impl DaemonError {
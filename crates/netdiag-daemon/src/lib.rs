@@ -3,27 +3,37 @@
 //! This crate provides a daemon/service that runs in the background and performs:
 //! - Scheduled network diagnostics
 //! - Continuous monitoring with alerting
+//! - Continuous ring-buffer packet capture
+//! - Alert-triggered packet capture (flight recorder)
+//! - Rogue access point detection
 //! - IPC communication with CLI/GUI
 //!
 //! # Features
 //!
 //! - **Scheduled Diagnostics**: Run network tests on a schedule (cron-like)
 //! - **Continuous Monitoring**: Monitor network health continuously
+//! - **Continuous Capture**: Record packets to rotating pcap files
+//! - **Flight Recorder**: Save the packets from around each alert
+//! - **Rogue AP Detection**: Alert on evil twins and unknown access points
 //! - **IPC Communication**: Socket-based communication with CLI/GUI
 //! - **Cross-Platform**: Works on macOS, Linux, and Windows
 
 #![warn(missing_docs)]
 
+pub mod capture;
 pub mod config;
 pub mod error;
 pub mod ipc;
 pub mod monitor;
 pub mod scheduler;
 pub mod service;
+pub mod wifi;
 
+pub use capture::{AlertRecorder, CaptureRecorder};
 pub use config::DaemonConfig;
 pub use error::{DaemonError, Result};
 pub use ipc::{IpcClient, IpcServer};
 pub use monitor::NetworkMonitor;
 pub use scheduler::DiagnosticScheduler;
 pub use service::{DaemonService, ServiceState};
+pub use wifi::RogueMonitor;
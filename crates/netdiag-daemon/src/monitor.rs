@@ -6,6 +6,7 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::net::IpAddr;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, RwLock};
@@ -103,10 +104,13 @@ pub struct Alert {
     pub target: Option<String>,
     /// Timestamp.
     pub timestamp: DateTime<Utc>,
+    /// Packet capture taken around the alert, if the flight recorder is enabled.
+    #[serde(default)]
+    pub capture_file: Option<PathBuf>,
 }
 
 /// Alert severity level.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum AlertSeverity {
     /// Informational.
@@ -379,6 +383,7 @@ impl NetworkMonitor {
                     ),
                     target: Some(result.target.clone()),
                     timestamp: Utc::now(),
+                    capture_file: None,
                 };
                 let _ = tx.send(alert).await;
             }
@@ -394,6 +399,7 @@ impl NetworkMonitor {
                         ),
                         target: Some(result.target.clone()),
                         timestamp: Utc::now(),
+                        capture_file: None,
                     };
                     let _ = tx.send(alert).await;
                 }
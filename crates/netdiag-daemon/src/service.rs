@@ -1,11 +1,14 @@
 //! Daemon service management.
 
+use crate::capture::{AlertRecorder, CaptureRecorder, FlowMeter};
 use crate::config::DaemonConfig;
 use crate::error::{DaemonError, Result};
 use crate::ipc::{IpcConnection, IpcRequest, IpcResponse, IpcServer};
 use crate::monitor::{Alert, NetworkMonitor};
 use crate::scheduler::{DiagnosticExecutor, DiagnosticRequest, DiagnosticScheduler};
+use crate::wifi::RogueMonitor;
 use chrono::{DateTime, Utc};
+use netdiag_platform::WifiProvider;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::Arc;
@@ -53,6 +56,11 @@ pub struct DaemonService {
     ipc_server: Option<IpcServer>,
     scheduler: Option<DiagnosticScheduler>,
     monitor: Option<NetworkMonitor>,
+    capture: Option<CaptureRecorder>,
+    alert_recorder: Option<Arc<AlertRecorder>>,
+    flow_meter: Option<FlowMeter>,
+    wifi_provider: Option<Arc<dyn WifiProvider>>,
+    rogue_monitor: Option<RogueMonitor>,
 }
 
 impl DaemonService {
@@ -67,9 +75,21 @@ impl DaemonService {
             ipc_server: None,
             scheduler: None,
             monitor: None,
+            capture: None,
+            alert_recorder: None,
+            flow_meter: None,
+            wifi_provider: None,
+            rogue_monitor: None,
         }
     }
 
+    /// Sets the WiFi provider used for rogue AP detection.
+    #[must_use]
+    pub fn with_wifi_provider(mut self, provider: Arc<dyn WifiProvider>) -> Self {
+        self.wifi_provider = Some(provider);
+        self
+    }
+
     /// Gets the current service state.
     pub async fn state(&self) -> ServiceState {
         *self.state.read().await
@@ -118,6 +138,20 @@ impl DaemonService {
             executor.run().await;
         });
 
+        // Start rogue AP detection (needs a WiFi provider, so failure is not fatal)
+        if self.config.rogue_detection.enabled {
+            if let Some(provider) = &self.wifi_provider {
+                let mut rogue_monitor =
+                    RogueMonitor::new(self.config.rogue_detection.clone(), provider.clone());
+                match rogue_monitor.start(alert_tx.clone()) {
+                    Ok(()) => self.rogue_monitor = Some(rogue_monitor),
+                    Err(e) => tracing::error!("Failed to start rogue AP detection: {}", e),
+                }
+            } else {
+                tracing::warn!("Rogue AP detection enabled but no WiFi provider is set");
+            }
+        }
+
         // Start monitor
         let monitor = NetworkMonitor::new(
             self.config.monitoring.clone(),
@@ -132,15 +166,55 @@ impl DaemonService {
             tracing::debug!("Initial monitor status: {:?}", monitor_data.status);
         }
 
+        // Start flight recorder so alerts come with packets attached
+        if self.config.alert_capture.enabled {
+            let mut recorder = AlertRecorder::new(self.config.alert_capture.clone());
+            match recorder.start() {
+                Ok(()) => self.alert_recorder = Some(Arc::new(recorder)),
+                Err(e) => tracing::error!("Failed to start flight recorder: {}", e),
+            }
+        }
+
         // Start alert handler
         let alerts_generated = self.alerts_generated.clone();
+        let alert_recorder = self.alert_recorder.clone();
         tokio::spawn(async move {
-            while let Some(alert) = alert_rx.recv().await {
-                tracing::warn!("[ALERT] {:?}: {}", alert.severity, alert.message);
+            while let Some(mut alert) = alert_rx.recv().await {
+                if let Some(recorder) = &alert_recorder {
+                    recorder.on_alert(&mut alert).await;
+                }
+                if let Some(path) = &alert.capture_file {
+                    tracing::warn!(
+                        "[ALERT] {:?}: {} (packets: {})",
+                        alert.severity,
+                        alert.message,
+                        path.display()
+                    );
+                } else {
+                    tracing::warn!("[ALERT] {:?}: {}", alert.severity, alert.message);
+                }
                 *alerts_generated.write().await += 1;
             }
         });
 
+        // Start continuous capture (needs capture privileges, so failure is not fatal)
+        if self.config.capture.enabled {
+            let mut capture = CaptureRecorder::new(self.config.capture.clone());
+            match capture.start() {
+                Ok(()) => self.capture = Some(capture),
+                Err(e) => tracing::error!("Failed to start continuous capture: {}", e),
+            }
+        }
+
+        // Start flow export
+        if self.config.flow_export.enabled {
+            let mut meter = FlowMeter::new(self.config.flow_export.clone());
+            match meter.start() {
+                Ok(()) => self.flow_meter = Some(meter),
+                Err(e) => tracing::error!("Failed to start flow export: {}", e),
+            }
+        }
+
         // Start IPC server
         let mut ipc_server = IpcServer::new(self.config.ipc.socket_path.clone());
         ipc_server.start().await?;
@@ -319,6 +393,20 @@ impl DaemonService {
             scheduler.shutdown().await?;
         }
 
+        // Stop continuous capture
+        if let Some(capture) = &mut self.capture {
+            capture.stop();
+        }
+        if let Some(recorder) = &self.alert_recorder {
+            recorder.stop();
+        }
+        if let Some(meter) = &mut self.flow_meter {
+            meter.stop();
+        }
+        if let Some(rogue_monitor) = &mut self.rogue_monitor {
+            rogue_monitor.stop();
+        }
+
         // Stop IPC server
         if let Some(server) = &mut self.ipc_server {
             server.shutdown().await?;
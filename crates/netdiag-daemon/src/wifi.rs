@@ -0,0 +1,125 @@
+//! Periodic WiFi scans for rogue access points.
+
+use crate::config::RogueDetectionConfig;
+use crate::error::{DaemonError, Result};
+use crate::monitor::{Alert, AlertSeverity};
+use chrono::Utc;
+use netdiag_platform::WifiProvider;
+use netdiag_wifi::{ApAllowlist, IssueSeverity, RogueDetector, RogueFinding, RogueKind};
+use std::collections::HashSet;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// Scans for rogue access points and raises an alert for each new finding.
+pub struct RogueMonitor {
+    config: RogueDetectionConfig,
+    provider: Arc<dyn WifiProvider>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl RogueMonitor {
+    /// Creates a new rogue AP monitor.
+    pub fn new(config: RogueDetectionConfig, provider: Arc<dyn WifiProvider>) -> Self {
+        Self {
+            config,
+            provider,
+            handle: None,
+        }
+    }
+
+    /// Starts scanning in the background.
+    pub fn start(&mut self, alert_tx: mpsc::Sender<Alert>) -> Result<()> {
+        if self.handle.is_some() {
+            return Ok(());
+        }
+        if !self.provider.is_available() {
+            return Err(DaemonError::service("WiFi is not available on this system"));
+        }
+
+        let detector = match &self.config.allowlist {
+            Some(path) => RogueDetector::with_allowlist(
+                ApAllowlist::load(path).map_err(|e| DaemonError::config(e.to_string()))?,
+            ),
+            None => RogueDetector::new(),
+        };
+        let provider = self.provider.clone();
+        let configured = self.config.interface.clone();
+        let interval = self.config.interval;
+
+        self.handle = Some(tokio::spawn(async move {
+            let mut reported: HashSet<(RogueKind, String)> = HashSet::new();
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+
+                let interface = match &configured {
+                    Some(name) => name.clone(),
+                    None => match provider.list_wifi_interfaces().await {
+                        Ok(interfaces) => match interfaces.into_iter().next() {
+                            Some(i) => i.name,
+                            None => continue,
+                        },
+                        Err(e) => {
+                            tracing::debug!("Rogue detection: no WiFi interface: {}", e);
+                            continue;
+                        }
+                    },
+                };
+
+                let aps = match provider.scan_access_points(&interface).await {
+                    Ok(aps) => aps,
+                    Err(e) => {
+                        tracing::debug!("Rogue detection scan failed: {}", e);
+                        continue;
+                    }
+                };
+
+                let analysis = detector.detect(&aps);
+                tracing::debug!(
+                    "Rogue detection: {} finding(s) across {} network(s)",
+                    analysis.findings.len(),
+                    analysis.networks_checked
+                );
+                for finding in analysis.findings {
+                    if !reported.insert((finding.kind, finding.bssid.clone())) {
+                        continue;
+                    }
+                    if alert_tx.send(to_alert(&finding)).await.is_err() {
+                        return;
+                    }
+                }
+            }
+        }));
+        tracing::info!(
+            "Rogue AP detection running every {:?}",
+            self.config.interval
+        );
+        Ok(())
+    }
+
+    /// Stops scanning.
+    pub fn stop(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            handle.abort();
+        }
+    }
+}
+
+fn to_alert(finding: &RogueFinding) -> Alert {
+    let severity = match finding.severity {
+        IssueSeverity::Critical => AlertSeverity::Critical,
+        IssueSeverity::High | IssueSeverity::Medium => AlertSeverity::Warning,
+        IssueSeverity::Low | IssueSeverity::Info => AlertSeverity::Info,
+    };
+    Alert {
+        severity,
+        message: format!(
+            "{} on \"{}\" ({}, channel {}): {}",
+            finding.kind, finding.ssid, finding.bssid, finding.channel.number, finding.description
+        ),
+        target: Some(finding.bssid.clone()),
+        timestamp: Utc::now(),
+        capture_file: None,
+    }
+}
@@ -122,6 +122,8 @@ impl IosWifiProvider {
                 max_rate: None,
                 beacon_interval: None,
                 capabilities: AccessPointCapabilities::default(),
+                elements: None,
+                last_seen: None,
             };
 
             Some(WifiConnection {
@@ -131,9 +133,14 @@ impl IosWifiProvider {
                 tx_rate: None,
                 rx_rate: None,
                 spatial_streams: None,
+                mcs_index: None,
+                guard_interval: None,
                 channel_width: None,
                 connected_duration: None,
                 last_roam: None,
+                tx_packets: None,
+                tx_retries: None,
+                tx_failed: None,
             })
         }
     }
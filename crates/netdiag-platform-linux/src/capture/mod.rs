@@ -0,0 +1,358 @@
+//! Linux packet capture provider implementation.
+
+use async_trait::async_trait;
+use netdiag_capture::{CaptureConfig, CaptureError, DecodedPacket, PacketCapture};
+use netdiag_platform::{CaptureInterface, CaptureProvider};
+use netdiag_types::capture::{CaptureFilter, CaptureHandle, CaptureStats, CapturedPacket};
+use netdiag_types::error::{Error, Result};
+use netdiag_types::system::PrivilegeLevel;
+use std::collections::{HashMap, VecDeque};
+use std::fs;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::debug;
+
+/// `CAP_NET_RAW` bit in the capability sets.
+const CAP_NET_RAW: u32 = 13;
+
+/// Finished sessions whose final statistics are kept for `stop_capture`.
+const FINISHED_SESSIONS: usize = 16;
+
+/// Linux capture provider using libpcap via `netdiag-capture`.
+pub struct LinuxCaptureProvider {
+    sessions: Arc<Mutex<Sessions>>,
+    next_id: AtomicU64,
+}
+
+/// Running captures, plus the final statistics of recently finished ones.
+#[derive(Default)]
+struct Sessions {
+    active: HashMap<CaptureHandle, Session>,
+    finished: VecDeque<(CaptureHandle, CaptureStats)>,
+}
+
+impl Sessions {
+    /// Drop a session whose capture ended on its own (packet limit,
+    /// duration or a closed receiver), keeping its final statistics.
+    fn finish(&mut self, id: CaptureHandle) {
+        let Some(session) = self.active.remove(&id) else {
+            return;
+        };
+        debug!("Capture on {} finished", session.interface);
+        if self.finished.len() == FINISHED_SESSIONS {
+            self.finished.pop_front();
+        }
+        self.finished.push_back((id, session.stats()));
+    }
+
+    /// Take the final statistics of a finished session.
+    fn take_finished(&mut self, id: CaptureHandle) -> Option<CaptureStats> {
+        let index = self.finished.iter().position(|(handle, _)| *handle == id)?;
+        self.finished.remove(index).map(|(_, stats)| stats)
+    }
+
+    /// Statistics of a running or finished session.
+    fn stats(&self, id: CaptureHandle) -> Option<CaptureStats> {
+        self.active.get(&id).map(Session::stats).or_else(|| {
+            self.finished
+                .iter()
+                .find(|(handle, _)| *handle == id)
+                .map(|(_, stats)| stats.clone())
+        })
+    }
+}
+
+/// An active capture session.
+struct Session {
+    interface: String,
+    handle: netdiag_capture::CaptureHandle,
+    stats: Arc<Mutex<CaptureStats>>,
+    started: Instant,
+    forwarder: JoinHandle<()>,
+}
+
+impl Session {
+    /// Snapshot of the session statistics.
+    fn stats(&self) -> CaptureStats {
+        snapshot(&self.stats, &self.handle, self.started)
+    }
+}
+
+/// Combine the forwarded packet counts with the kernel drop counters.
+fn snapshot(
+    stats: &Mutex<CaptureStats>,
+    handle: &netdiag_capture::CaptureHandle,
+    started: Instant,
+) -> CaptureStats {
+    let mut stats = stats.lock().map(|s| s.clone()).unwrap_or_default();
+    stats.packets_dropped = handle.packets_dropped();
+    stats.packets_if_dropped = handle.packets_dropped_interface();
+    stats.duration = started.elapsed();
+    stats
+}
+
+impl LinuxCaptureProvider {
+    /// Creates a new Linux capture provider.
+    pub fn new() -> Self {
+        Self {
+            sessions: Arc::new(Mutex::new(Sessions::default())),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Check if the process holds `CAP_NET_RAW` (or is root).
+    fn can_capture() -> bool {
+        if unsafe { libc::geteuid() } == 0 {
+            return true;
+        }
+        fs::read_to_string("/proc/self/status")
+            .ok()
+            .and_then(|status| {
+                status
+                    .lines()
+                    .find_map(|line| line.strip_prefix("CapEff:"))
+                    .and_then(|hex| u64::from_str_radix(hex.trim(), 16).ok())
+            })
+            .is_some_and(|caps| (caps >> CAP_NET_RAW) & 1 == 1)
+    }
+
+    /// Count a packet in the session statistics.
+    fn count(stats: &Mutex<CaptureStats>, packet: &DecodedPacket) {
+        let Ok(mut stats) = stats.lock() else {
+            return;
+        };
+        stats.packets_received += 1;
+        stats.bytes_captured += packet.length as u64;
+
+        let protocols = &mut stats.protocol_stats;
+        match packet.src_ip {
+            Some(ip) if ip.is_ipv4() => protocols.ipv4 += 1,
+            Some(_) => protocols.ipv6 += 1,
+            None => {}
+        }
+        match packet.ip_protocol {
+            Some(6) => protocols.tcp += 1,
+            Some(17) => protocols.udp += 1,
+            Some(1 | 58) => protocols.icmp += 1,
+            _ => protocols.other += 1,
+        }
+    }
+
+    /// Convert a capture error, reporting missing privileges clearly.
+    fn convert_error(interface: &str, error: CaptureError) -> Error {
+        match error {
+            CaptureError::PermissionDenied => {
+                Error::permission_denied("Packet capture", "root or CAP_NET_RAW")
+            }
+            CaptureError::InvalidFilter(message) => Error::Parse {
+                what: "capture filter".to_string(),
+                message,
+            },
+            other => Error::PacketCapture {
+                interface: Some(interface.to_string()),
+                message: other.to_string(),
+            },
+        }
+    }
+}
+
+impl Default for LinuxCaptureProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl CaptureProvider for LinuxCaptureProvider {
+    fn is_available(&self) -> bool {
+        netdiag_capture::list_devices().is_ok()
+    }
+
+    async fn list_capture_interfaces(&self) -> Result<Vec<CaptureInterface>> {
+        debug!("Listing capture interfaces on Linux");
+
+        let devices = netdiag_capture::list_devices().map_err(|e| Error::PacketCapture {
+            interface: None,
+            message: e.to_string(),
+        })?;
+
+        Ok(devices
+            .into_iter()
+            .map(|device| CaptureInterface {
+                addresses: device
+                    .addresses
+                    .iter()
+                    .filter_map(|a| a.parse().ok())
+                    .collect(),
+                can_promiscuous: !device.is_loopback,
+                is_loopback: device.is_loopback,
+                is_up: device.is_up && device.is_running,
+                description: device.description,
+                name: device.name,
+            })
+            .collect())
+    }
+
+    async fn start_capture(
+        &self,
+        interface: &str,
+        filter: Option<CaptureFilter>,
+        packet_tx: mpsc::Sender<CapturedPacket>,
+    ) -> Result<CaptureHandle> {
+        debug!("Starting capture on {}", interface);
+
+        let mut config = CaptureConfig::for_device(interface);
+        if let Some(filter) = &filter {
+            config = config.with_filter(filter.into());
+            if let Some(limit) = filter.limit {
+                config = config.max_packets(limit as usize);
+            }
+            if let Some(duration) = filter.duration {
+                config = config.max_duration(duration);
+            }
+        }
+
+        let (mut rx, handle) = PacketCapture::new(config)
+            .start()
+            .map_err(|e| Self::convert_error(interface, e))?;
+
+        let id = CaptureHandle(self.next_id.fetch_add(1, Ordering::Relaxed));
+        let mut sessions = self.sessions.lock().map_err(|_| Error::PacketCapture {
+            interface: Some(interface.to_string()),
+            message: "capture session table poisoned".to_string(),
+        })?;
+
+        // Forward decoded packets until the capture ends or the receiver goes
+        // away, then drop the session. The table stays locked until the
+        // session is inserted, so a capture that ends at once is still removed.
+        let stats = Arc::new(Mutex::new(CaptureStats::default()));
+        let forwarder = {
+            let stats = stats.clone();
+            let handle = handle.clone();
+            let interface = interface.to_string();
+            let sessions = self.sessions.clone();
+            tokio::spawn(async move {
+                while let Some(packet) = rx.recv().await {
+                    Self::count(&stats, &packet);
+                    if packet_tx
+                        .send(packet.to_captured_packet(&interface))
+                        .await
+                        .is_err()
+                    {
+                        handle.stop();
+                        break;
+                    }
+                }
+                if let Ok(mut sessions) = sessions.lock() {
+                    sessions.finish(id);
+                }
+            })
+        };
+
+        sessions.active.insert(
+            id,
+            Session {
+                interface: interface.to_string(),
+                handle,
+                stats,
+                started: Instant::now(),
+                forwarder,
+            },
+        );
+        Ok(id)
+    }
+
+    async fn stop_capture(&self, handle: CaptureHandle) -> Result<CaptureStats> {
+        let session = {
+            let Ok(mut sessions) = self.sessions.lock() else {
+                return Err(Error::PacketCapture {
+                    interface: None,
+                    message: "capture session table poisoned".to_string(),
+                });
+            };
+            match sessions.active.remove(&handle) {
+                Some(session) => session,
+                None => {
+                    return sessions
+                        .take_finished(handle)
+                        .ok_or_else(|| Error::PacketCapture {
+                            interface: None,
+                            message: format!("no active capture with handle {}", handle.0),
+                        })
+                }
+            }
+        };
+        debug!("Stopping capture on {}", session.interface);
+
+        let Session {
+            handle: capture,
+            stats,
+            started,
+            forwarder,
+            ..
+        } = session;
+        capture.stop();
+
+        // Let the forwarder drain what the capture thread already read; the
+        // thread notices the stop flag at its next read timeout
+        if tokio::time::timeout(Duration::from_secs(2), forwarder)
+            .await
+            .is_err()
+        {
+            debug!("Capture did not finish in time; returning partial stats");
+        }
+        Ok(snapshot(&stats, &capture, started))
+    }
+
+    async fn get_capture_stats(&self, handle: CaptureHandle) -> Result<CaptureStats> {
+        self.sessions
+            .lock()
+            .ok()
+            .and_then(|sessions| sessions.stats(handle))
+            .ok_or_else(|| Error::PacketCapture {
+                interface: None,
+                message: format!("no active capture with handle {}", handle.0),
+            })
+    }
+
+    fn required_privilege_level(&self) -> PrivilegeLevel {
+        // A binary granted cap_net_raw (setcap) can capture as a normal user
+        if Self::can_capture() {
+            PrivilegeLevel::User
+        } else {
+            PrivilegeLevel::Elevated
+        }
+    }
+
+    fn compile_filter(&self, expression: &str) -> Result<String> {
+        netdiag_capture::compile_filter(expression).map_err(|e| Error::Parse {
+            what: "capture filter".to_string(),
+            message: e.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compile_filter() {
+        let provider = LinuxCaptureProvider::new();
+        assert_eq!(
+            provider.compile_filter("tcp port 443").unwrap(),
+            "tcp port 443"
+        );
+        assert!(provider.compile_filter("host and or").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_unknown_handle() {
+        let provider = LinuxCaptureProvider::new();
+        assert!(provider.get_capture_stats(CaptureHandle(42)).await.is_err());
+        assert!(provider.stop_capture(CaptureHandle(42)).await.is_err());
+    }
+}
@@ -1,15 +1,30 @@
 //! Linux WiFi provider implementation.
 
+mod netlink;
+mod nl80211;
+mod wpa_ctrl;
+
 use async_trait::async_trait;
-use netdiag_platform::WifiProvider;
-use netdiag_types::error::{Error, Result};
-use netdiag_types::wifi::{
-    AccessPoint, Channel, ChannelWidth, ConnectionInfo, FrequencyBand, SecurityType, WifiInterface,
+use netdiag_platform::{WifiInterface, WifiProvider};
+use netdiag_types::{
+    error::{Error, Result},
+    network::MacAddress,
+    wifi::{
+        AccessPoint, AccessPointCapabilities, Bssid, Channel, ChannelUtilization, ChannelWidth,
+        InterferenceLevel, KeyManagement, SecurityType, Ssid, SupplicantTranscript, WifiAuthState,
+        WifiAuthentication, WifiBand, WifiConnection, WifiConnectionState, WifiStandard,
+    },
 };
-use std::process::Command;
+use netdiag_wifi::apply_elements;
+use nl80211::{Bss, Interface, Nl80211, Station, Survey, IFTYPE_STATION};
+use std::fs;
+use std::time::Duration;
 use tracing::debug;
 
-/// Linux WiFi provider using iw and NetworkManager.
+/// How long to wait for a triggered scan to complete.
+const SCAN_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Linux WiFi provider using nl80211.
 pub struct LinuxWifiProvider;
 
 impl LinuxWifiProvider {
@@ -18,461 +33,578 @@ impl LinuxWifiProvider {
         Self
     }
 
-    /// Parse iw scan output.
-    fn parse_iw_scan(&self, output: &str) -> Vec<AccessPoint> {
-        let mut aps = Vec::new();
-        let mut current_ap: Option<AccessPointBuilder> = None;
-
-        for line in output.lines() {
-            let line = line.trim();
-
-            // New BSS (access point)
-            if line.starts_with("BSS ") {
-                // Save previous AP if exists
-                if let Some(builder) = current_ap.take() {
-                    if let Some(ap) = builder.build() {
-                        aps.push(ap);
-                    }
-                }
-
-                // Start new AP
-                let bssid = line
-                    .strip_prefix("BSS ")
-                    .and_then(|s| s.split('(').next())
-                    .map(|s| s.trim().to_uppercase());
-
-                current_ap = Some(AccessPointBuilder::new(bssid));
-            } else if let Some(ref mut builder) = current_ap {
-                // Parse AP properties
-                if let Some(ssid) = line.strip_prefix("SSID: ") {
-                    builder.ssid = Some(ssid.to_string());
-                } else if let Some(freq) = line.strip_prefix("freq: ") {
-                    if let Ok(f) = freq.parse::<u32>() {
-                        builder.frequency = Some(f);
-                        builder.band = Some(if f < 3000 {
-                            FrequencyBand::Band2_4GHz
-                        } else if f < 6000 {
-                            FrequencyBand::Band5GHz
-                        } else {
-                            FrequencyBand::Band6GHz
-                        });
-                    }
-                } else if let Some(signal) = line.strip_prefix("signal: ") {
-                    // Signal format: "-XX.XX dBm"
-                    if let Some(dbm_str) = signal.split_whitespace().next() {
-                        if let Ok(dbm) = dbm_str.parse::<f32>() {
-                            builder.signal_strength = Some(dbm as i32);
-                        }
-                    }
-                } else if line.contains("WPA:") || line.contains("RSN:") {
-                    builder.has_wpa = true;
-                } else if line.contains("WEP") {
-                    builder.has_wep = true;
-                } else if line.contains("Privacy") {
-                    builder.has_privacy = true;
-                }
-            }
-        }
+    /// Run an nl80211 query on the blocking thread pool.
+    async fn query<T, F>(f: F) -> Result<T>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Nl80211) -> Result<T> + Send + 'static,
+    {
+        tokio::task::spawn_blocking(move || f(&mut Nl80211::connect()?))
+            .await
+            .map_err(|e| Error::Wifi {
+                interface: None,
+                message: format!("nl80211 query failed: {}", e),
+            })?
+    }
 
-        // Don't forget the last AP
-        if let Some(builder) = current_ap {
-            if let Some(ap) = builder.build() {
-                aps.push(ap);
-            }
-        }
+    /// Check the administrative state of an interface.
+    fn is_powered_on(name: &str) -> bool {
+        // IFF_UP is bit 0 of the interface flags
+        fs::read_to_string(format!("/sys/class/net/{}/flags", name))
+            .ok()
+            .and_then(|flags| u32::from_str_radix(flags.trim().trim_start_matches("0x"), 16).ok())
+            .is_some_and(|flags| flags & 1 == 1)
+    }
 
-        aps
+    /// First managed-mode interface, used when no interface is given.
+    fn station_interface(nl: &mut Nl80211) -> Result<Interface> {
+        nl.interfaces()?
+            .into_iter()
+            .find(|interface| interface.iftype == IFTYPE_STATION)
+            .ok_or_else(|| Error::Wifi {
+                interface: None,
+                message: "no WiFi interface found".to_string(),
+            })
     }
 
-    /// Parse nmcli connection info.
-    fn parse_nmcli_connection(&self, output: &str) -> Option<ConnectionInfo> {
-        let mut ssid = None;
-        let mut bssid = None;
-        let mut signal = None;
-        let mut frequency = None;
-        let mut tx_rate = None;
-
-        for line in output.lines() {
-            let parts: Vec<&str> = line.splitn(2, ':').collect();
-            if parts.len() != 2 {
-                continue;
-            }
+    /// Build an access point from a scan result.
+    fn access_point(bss: &Bss, surveys: &[Survey]) -> AccessPoint {
+        let rssi = bss.signal_dbm.unwrap_or(-100);
+        let noise = surveys
+            .iter()
+            .find(|survey| survey.frequency == bss.frequency)
+            .and_then(|survey| survey.noise)
+            .map(i32::from);
+
+        let capability = bss.capability;
+        let mut ap = AccessPoint {
+            ssid: Ssid::hidden(),
+            bssid: Bssid::new(bss.bssid),
+            rssi,
+            // Linear mapping of -100..-50 dBm onto 0..100%
+            signal_quality: (2 * (rssi + 100)).clamp(0, 100) as u8,
+            channel: Channel::from_frequency(bss.frequency),
+            security: SecurityType::open(),
+            wifi_standard: WifiStandard::Unknown,
+            is_hidden: true,
+            is_connected: bss.associated,
+            noise,
+            snr: noise.map(|noise| rssi - noise),
+            country_code: None,
+            supported_rates: Vec::new(),
+            max_rate: None,
+            beacon_interval: bss.beacon_interval,
+            capabilities: AccessPointCapabilities {
+                infrastructure: capability & 0x0001 != 0,
+                adhoc: capability & 0x0002 != 0,
+                short_slot_time: capability & 0x0400 != 0,
+                short_preamble: capability & 0x0020 != 0,
+                ess: capability & 0x0001 != 0,
+                ibss: capability & 0x0002 != 0,
+                privacy: capability & 0x0010 != 0,
+                spectrum_management: capability & 0x0100 != 0,
+                radio_measurement: capability & 0x1000 != 0,
+                ..AccessPointCapabilities::default()
+            },
+            elements: None,
+            last_seen: bss.seen_ms_ago.map(|ms| {
+                chrono::Utc::now() - chrono::Duration::milliseconds(i64::from(ms))
+            }),
+        };
+        apply_elements(&mut ap, &bss.ies);
+
+        // Hidden networks advertise an empty or zeroed SSID
+        if let Some(ssid) = ap
+            .elements
+            .as_ref()
+            .and_then(|e| e.ssid.as_deref())
+            .filter(|ssid| ssid.chars().any(|c| c != '\0'))
+        {
+            ap.ssid = Ssid::new(ssid);
+            ap.is_hidden = false;
+        }
 
-            let key = parts[0].trim();
-            let value = parts[1].trim();
+        // Legacy PHYs have no capability element to go by
+        if ap.wifi_standard == WifiStandard::Unknown {
+            ap.wifi_standard = if ap.channel.band != WifiBand::Band2_4GHz {
+                WifiStandard::Dot11a
+            } else if ap.supported_rates.iter().any(|rate| *rate > 11.0) {
+                WifiStandard::Dot11g
+            } else {
+                WifiStandard::Dot11b
+            };
+        }
+        ap
+    }
 
-            match key {
-                "GENERAL.CONNECTION" | "WIFI.SSID" => {
-                    if !value.is_empty() && value != "--" {
-                        ssid = Some(value.to_string());
-                    }
-                }
-                "WIFI.BSSID" | "AP.BSSID" => {
-                    if !value.is_empty() && value != "--" {
-                        bssid = Some(value.to_uppercase());
-                    }
-                }
-                "WIFI.SIGNAL" | "AP.SIGNAL" => {
-                    if let Ok(s) = value.parse::<i32>() {
-                        // nmcli returns percentage, convert to approximate dBm
-                        signal = Some(-100 + s);
-                    }
-                }
-                "WIFI.FREQ" | "AP.FREQ" => {
-                    // Format: "2437 MHz"
-                    if let Some(freq_str) = value.split_whitespace().next() {
-                        frequency = freq_str.parse().ok();
-                    }
-                }
-                "WIFI.RATE" | "AP.RATE" => {
-                    // Format: "54 Mbit/s"
-                    if let Some(rate_str) = value.split_whitespace().next() {
-                        tx_rate = rate_str.parse().ok();
-                    }
-                }
-                _ => {}
+    /// Authentication state implied by the security of the associated network.
+    fn auth_state(security: &SecurityType) -> WifiAuthState {
+        match security.key_management {
+            KeyManagement::None if security.authentication == WifiAuthentication::Open => {
+                WifiAuthState::Open
             }
+            KeyManagement::None => WifiAuthState::None,
+            KeyManagement::Psk | KeyManagement::FtPsk => WifiAuthState::Psk,
+            KeyManagement::Sae | KeyManagement::FtSae => WifiAuthState::Sae,
+            KeyManagement::Owe => WifiAuthState::Owe,
+            _ => WifiAuthState::Eap,
         }
-
-        ssid.map(|ssid| ConnectionInfo {
-            ssid,
-            bssid,
-            signal_strength: signal,
-            noise_level: None,
-            channel: frequency.map(|f| freq_to_channel(f)),
-            tx_rate,
-            security: SecurityType::unknown(),
-            connected_at: None,
-        })
     }
-}
 
-impl Default for LinuxWifiProvider {
-    fn default() -> Self {
-        Self::new()
+    /// Channel width from a width in MHz.
+    fn channel_width(mhz: u32) -> ChannelWidth {
+        match mhz {
+            320 => ChannelWidth::Mhz320,
+            160 => ChannelWidth::Mhz160,
+            80 => ChannelWidth::Mhz80,
+            40 => ChannelWidth::Mhz40,
+            _ => ChannelWidth::Mhz20,
+        }
     }
-}
 
-/// Helper to convert frequency to channel number.
-fn freq_to_channel(freq: u32) -> Channel {
-    let number = match freq {
-        2412 => 1,
-        2417 => 2,
-        2422 => 3,
-        2427 => 4,
-        2432 => 5,
-        2437 => 6,
-        2442 => 7,
-        2447 => 8,
-        2452 => 9,
-        2457 => 10,
-        2462 => 11,
-        2467 => 12,
-        2472 => 13,
-        2484 => 14,
-        5180 => 36,
-        5200 => 40,
-        5220 => 44,
-        5240 => 48,
-        5260 => 52,
-        5280 => 56,
-        5300 => 60,
-        5320 => 64,
-        5500 => 100,
-        5520 => 104,
-        5540 => 108,
-        5560 => 112,
-        5580 => 116,
-        5600 => 120,
-        5620 => 124,
-        5640 => 128,
-        5660 => 132,
-        5680 => 136,
-        5700 => 140,
-        5720 => 144,
-        5745 => 149,
-        5765 => 153,
-        5785 => 157,
-        5805 => 161,
-        5825 => 165,
-        _ => 0,
-    };
-
-    let band = if freq < 3000 {
-        FrequencyBand::Band2_4GHz
-    } else if freq < 6000 {
-        FrequencyBand::Band5GHz
-    } else {
-        FrequencyBand::Band6GHz
-    };
-
-    Channel {
-        number: number as u8,
-        frequency: freq,
-        band,
-        width: ChannelWidth::Width20MHz,
-        is_dfs: (52..=144).contains(&number),
-    }
-}
+    /// Utilization of a channel.
+    ///
+    /// Uses the busy time from the channel survey when the driver reports
+    /// it, and falls back to an estimate from the visible networks.
+    fn utilization(
+        channel: Channel,
+        aps: &[AccessPoint],
+        survey: Option<&Survey>,
+    ) -> ChannelUtilization {
+        let networks_on_channel: Vec<_> = aps
+            .iter()
+            .filter(|ap| ap.channel.frequency == channel.frequency)
+            .collect();
+
+        let network_count = networks_on_channel.len() as u32;
+        let avg_rssi = if networks_on_channel.is_empty() {
+            -100
+        } else {
+            networks_on_channel.iter().map(|ap| ap.rssi).sum::<i32>()
+                / networks_on_channel.len() as i32
+        };
 
-/// Builder for constructing AccessPoint from parsed data.
-struct AccessPointBuilder {
-    bssid: Option<String>,
-    ssid: Option<String>,
-    signal_strength: Option<i32>,
-    frequency: Option<u32>,
-    band: Option<FrequencyBand>,
-    has_wpa: bool,
-    has_wep: bool,
-    has_privacy: bool,
-}
+        let (utilization_percent, interference_level) = match survey.and_then(Survey::busy_percent)
+        {
+            Some(busy) => (
+                busy,
+                match busy {
+                    b if b < 30.0 => InterferenceLevel::Low,
+                    b if b < 50.0 => InterferenceLevel::Medium,
+                    b if b < 75.0 => InterferenceLevel::High,
+                    _ => InterferenceLevel::Severe,
+                },
+            ),
+            None => (
+                match network_count {
+                    0 => 0.0,
+                    1..=2 => 10.0 + (avg_rssi.abs() as f32 * 0.2),
+                    3..=5 => 30.0 + (avg_rssi.abs() as f32 * 0.3),
+                    _ => 60.0 + (avg_rssi.abs() as f32 * 0.2),
+                },
+                match network_count {
+                    0..=1 => InterferenceLevel::Low,
+                    2..=3 => InterferenceLevel::Medium,
+                    4..=6 => InterferenceLevel::High,
+                    _ => InterferenceLevel::Severe,
+                },
+            ),
+        };
 
-impl AccessPointBuilder {
-    fn new(bssid: Option<String>) -> Self {
-        Self {
-            bssid,
-            ssid: None,
-            signal_strength: None,
-            frequency: None,
-            band: None,
-            has_wpa: false,
-            has_wep: false,
-            has_privacy: false,
+        let recommended = network_count <= 2
+            && matches!(
+                interference_level,
+                InterferenceLevel::Low | InterferenceLevel::Medium
+            );
+
+        ChannelUtilization {
+            channel,
+            network_count,
+            utilization_percent: utilization_percent.min(100.0),
+            avg_rssi,
+            recommended,
+            interference_level,
         }
     }
 
-    fn build(self) -> Option<AccessPoint> {
-        let ssid = self.ssid.unwrap_or_default();
-        let bssid = self.bssid?;
-
-        let security = if self.has_wpa {
-            SecurityType::wpa2_personal()
-        } else if self.has_wep {
-            SecurityType::wep()
-        } else if self.has_privacy {
-            SecurityType::unknown()
-        } else {
-            SecurityType::open()
-        };
+    /// Connection details from station info, matched against the cached scan.
+    fn connection(
+        interface: &Interface,
+        station: &Station,
+        aps: Vec<AccessPoint>,
+        surveys: &[Survey],
+    ) -> WifiConnection {
+        let mut access_point = aps
+            .into_iter()
+            .find(|ap| ap.bssid.octets() == station.mac)
+            .unwrap_or_else(|| {
+                // The BSS may have aged out of the scan cache
+                let bss = Bss {
+                    bssid: station.mac,
+                    frequency: interface.frequency.unwrap_or_default(),
+                    associated: true,
+                    ..Bss::default()
+                };
+                let mut ap = Self::access_point(&bss, surveys);
+                ap.ssid = Ssid::new(String::from_utf8_lossy(
+                    interface.ssid.as_deref().unwrap_or_default(),
+                ));
+                ap.is_hidden = false;
+                ap
+            });
+
+        // Station info is more current than the last beacon
+        if let Some(rssi) = station.signal_dbm() {
+            access_point.rssi = rssi;
+            access_point.signal_quality = (2 * (rssi + 100)).clamp(0, 100) as u8;
+            access_point.snr = access_point.noise.map(|noise| rssi - noise);
+        }
+        access_point.is_connected = true;
+
+        let tx = station.tx_bitrate;
+        let width = interface
+            .width_mhz
+            .or(tx.map(|rate| rate.width_mhz))
+            .map(Self::channel_width);
+
+        WifiConnection {
+            state: WifiConnectionState::Connected,
+            auth_state: Self::auth_state(&access_point.security),
+            tx_rate: tx.and_then(|rate| rate.mbps()),
+            rx_rate: station.rx_bitrate.and_then(|rate| rate.mbps()),
+            spatial_streams: tx.and_then(|rate| rate.nss),
+            mcs_index: tx.and_then(|rate| rate.mcs),
+            guard_interval: tx.and_then(|rate| rate.guard_interval()),
+            channel_width: width,
+            connected_duration: station
+                .connected_time
+                .map(|secs| Duration::from_secs(u64::from(secs))),
+            last_roam: None,
+            tx_packets: station.tx_packets,
+            tx_retries: station.tx_retries,
+            tx_failed: station.tx_failed,
+            access_point,
+        }
+    }
 
-        let channel = self.frequency.map(freq_to_channel);
+    /// Scan results and survey for an interface, without triggering a scan.
+    fn cached_scan(nl: &mut Nl80211, ifindex: u32) -> Result<(Vec<AccessPoint>, Vec<Survey>)> {
+        // Not every driver implements surveys
+        let surveys = nl.survey(ifindex).unwrap_or_default();
+        let aps = nl
+            .scan_results(ifindex)?
+            .iter()
+            .map(|bss| Self::access_point(bss, &surveys))
+            .collect();
+        Ok((aps, surveys))
+    }
+}
 
-        Some(AccessPoint {
-            ssid,
-            bssid,
-            signal_strength: self.signal_strength.unwrap_or(-100),
-            noise_level: None,
-            channel,
-            security,
-            is_hidden: false,
-            vendor: None,
-            supported_rates: Vec::new(),
-            country_code: None,
-        })
+impl Default for LinuxWifiProvider {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
 #[async_trait]
 impl WifiProvider for LinuxWifiProvider {
+    fn is_available(&self) -> bool {
+        Nl80211::connect()
+            .and_then(|mut nl| nl.interfaces())
+            .is_ok_and(|interfaces| !interfaces.is_empty())
+    }
+
     async fn list_wifi_interfaces(&self) -> Result<Vec<WifiInterface>> {
         debug!("Listing WiFi interfaces on Linux");
 
-        let output = Command::new("iw")
-            .args(["dev"])
-            .output()
-            .map_err(|e| Error::platform("iw dev", &e.to_string()))?;
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut interfaces = Vec::new();
-        let mut current_iface: Option<String> = None;
-
-        for line in stdout.lines() {
-            let line = line.trim();
-
-            if line.starts_with("Interface ") {
-                current_iface = line.strip_prefix("Interface ").map(|s| s.to_string());
-            } else if let Some(ref iface) = current_iface {
-                if line.starts_with("addr ") {
-                    let mac = line.strip_prefix("addr ").map(|s| s.to_uppercase());
-
-                    interfaces.push(WifiInterface {
-                        name: iface.clone(),
-                        mac_address: mac,
-                        is_up: true,                 // Would need additional check
-                        supports_monitor_mode: true, // Most Linux WiFi interfaces do
-                        supported_bands: vec![FrequencyBand::Band2_4GHz, FrequencyBand::Band5GHz],
-                    });
-                }
-            }
-        }
+        let (interfaces, country) = Self::query(|nl| {
+            let interfaces = nl.interfaces()?;
+            let country = nl.country().unwrap_or_default();
+            Ok((interfaces, country))
+        })
+        .await?;
 
         debug!("Found {} WiFi interfaces", interfaces.len());
-        Ok(interfaces)
+        Ok(interfaces
+            .into_iter()
+            .map(|interface| WifiInterface {
+                powered_on: Self::is_powered_on(&interface.name),
+                connected: interface.ssid.is_some(),
+                mac_address: interface.mac.map(MacAddress::new),
+                country_code: country.clone(),
+                name: interface.name,
+            })
+            .collect())
     }
 
     async fn scan_access_points(&self, interface: &str) -> Result<Vec<AccessPoint>> {
         debug!("Scanning for access points on {} (Linux)", interface);
 
-        // Try iw scan first (requires root or CAP_NET_ADMIN)
-        let output = Command::new("iw")
-            .args(["dev", interface, "scan"])
-            .output()
-            .map_err(|e| Error::platform("iw scan", &e.to_string()))?;
-
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Ok(self.parse_iw_scan(&stdout));
-        }
-
-        // Fallback: try nmcli
-        let output = Command::new("nmcli")
-            .args([
-                "-t",
-                "-f",
-                "BSSID,SSID,FREQ,SIGNAL,SECURITY",
-                "device",
-                "wifi",
-                "list",
-            ])
-            .output()
-            .map_err(|e| Error::platform("nmcli wifi list", &e.to_string()))?;
-
-        if !output.status.success() {
-            return Err(Error::platform(
-                "WiFi scan",
-                "Failed to scan - try running with sudo or ensure NetworkManager is running",
-            ));
-        }
-
-        let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut aps = Vec::new();
-
-        for line in stdout.lines() {
-            let parts: Vec<&str> = line.split(':').collect();
-            if parts.len() >= 5 {
-                let bssid = parts[0].to_uppercase();
-                let ssid = parts[1].to_string();
-                let freq: u32 = parts[2].parse().unwrap_or(0);
-                let signal: i32 = parts[3].parse().map(|s: i32| -100 + s).unwrap_or(-100);
-                let security_str = parts[4];
-
-                let security = if security_str.contains("WPA3") {
-                    SecurityType::wpa3_personal()
-                } else if security_str.contains("WPA2") {
-                    SecurityType::wpa2_personal()
-                } else if security_str.contains("WPA") {
-                    SecurityType::wpa_personal()
-                } else if security_str.contains("WEP") {
-                    SecurityType::wep()
-                } else {
-                    SecurityType::open()
-                };
-
-                aps.push(AccessPoint {
-                    ssid,
-                    bssid,
-                    signal_strength: signal,
-                    noise_level: None,
-                    channel: Some(freq_to_channel(freq)),
-                    security,
-                    is_hidden: false,
-                    vendor: None,
-                    supported_rates: Vec::new(),
-                    country_code: None,
-                });
+        let name = interface.to_string();
+        let (aps, _) = Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            // Triggering needs CAP_NET_ADMIN; the cached results are still useful
+            match nl.trigger_scan(interface.index, SCAN_TIMEOUT) {
+                Err(e) if e.is_permission_denied() => {
+                    debug!("Cannot trigger a scan, using cached results: {}", e);
+                }
+                other => other?,
             }
-        }
+            Self::cached_scan(nl, interface.index)
+        })
+        .await?;
 
+        debug!("Found {} access points", aps.len());
         Ok(aps)
     }
 
-    async fn get_current_connection(&self, interface: &str) -> Result<Option<ConnectionInfo>> {
-        debug!("Getting current WiFi connection on {} (Linux)", interface);
+    async fn cached_scan_results(&self, interface: &str) -> Result<Option<Vec<AccessPoint>>> {
+        let name = interface.to_string();
+        let (aps, _) = Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            Self::cached_scan(nl, interface.index)
+        })
+        .await?;
+        Ok(Some(aps))
+    }
 
-        // Try nmcli first
-        let output = Command::new("nmcli")
-            .args(["-t", "device", "show", interface])
-            .output()
-            .map_err(|e| Error::platform("nmcli device show", &e.to_string()))?;
+    async fn get_current_connection(&self, interface: &str) -> Result<Option<WifiConnection>> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            let Some(station) = nl.station(interface.index)? else {
+                return Ok(None);
+            };
+            let (aps, surveys) = Self::cached_scan(nl, interface.index)?;
+            Ok(Some(Self::connection(&interface, &station, aps, &surveys)))
+        })
+        .await
+    }
 
-        if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout);
-            return Ok(self.parse_nmcli_connection(&stdout));
-        }
+    async fn get_signal_strength(&self, interface: &str) -> Result<Option<i32>> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            Ok(nl
+                .station(interface.index)?
+                .and_then(|station| station.signal_dbm()))
+        })
+        .await
+    }
 
-        // Fallback: try iw
-        let output = Command::new("iw")
-            .args(["dev", interface, "link"])
-            .output()
-            .map_err(|e| Error::platform("iw link", &e.to_string()))?;
+    async fn get_noise_level(&self, interface: &str) -> Result<Option<i32>> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            Ok(nl
+                .survey(interface.index)?
+                .iter()
+                .find(|survey| survey.in_use)
+                .and_then(|survey| survey.noise)
+                .map(i32::from))
+        })
+        .await
+    }
 
-        let stdout = String::from_utf8_lossy(&output.stdout);
+    async fn get_channel_utilization(&self, channel: Channel) -> Result<ChannelUtilization> {
+        Self::query(move |nl| {
+            let interface = Self::station_interface(nl)?;
+            let (aps, surveys) = Self::cached_scan(nl, interface.index)?;
+            let survey = surveys
+                .iter()
+                .find(|survey| survey.frequency == channel.frequency);
+            Ok(Self::utilization(channel, &aps, survey))
+        })
+        .await
+    }
 
-        if stdout.contains("Not connected") {
-            return Ok(None);
-        }
+    async fn analyze_channels(&self, interface: &str) -> Result<Vec<ChannelUtilization>> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            let (aps, surveys) = Self::cached_scan(nl, interface.index)?;
+
+            // The survey covers every channel the radio visited during the
+            // last scan; without one, look at the common channels
+            let channels: Vec<Channel> = if surveys.is_empty() {
+                [1u8, 6, 11]
+                    .map(|ch| Channel::from_number(ch, WifiBand::Band2_4GHz))
+                    .into_iter()
+                    .chain(
+                        [36u8, 40, 44, 48, 149, 153, 157, 161, 165]
+                            .map(|ch| Channel::from_number(ch, WifiBand::Band5GHz)),
+                    )
+                    .collect()
+            } else {
+                surveys
+                    .iter()
+                    .map(|survey| Channel::from_frequency(survey.frequency))
+                    .collect()
+            };
+
+            Ok(channels
+                .into_iter()
+                .map(|channel| {
+                    let survey = surveys
+                        .iter()
+                        .find(|survey| survey.frequency == channel.frequency);
+                    Self::utilization(channel, &aps, survey)
+                })
+                .collect())
+        })
+        .await
+    }
 
-        let mut ssid = None;
-        let mut bssid = None;
-        let mut freq = None;
-        let mut signal = None;
-        let mut tx_rate = None;
-
-        for line in stdout.lines() {
-            let line = line.trim();
-
-            if line.starts_with("Connected to ") {
-                bssid = line
-                    .strip_prefix("Connected to ")
-                    .map(|s| s.split_whitespace().next().unwrap_or("").to_uppercase());
-            } else if line.starts_with("SSID: ") {
-                ssid = line.strip_prefix("SSID: ").map(|s| s.to_string());
-            } else if line.starts_with("freq: ") {
-                freq = line
-                    .strip_prefix("freq: ")
-                    .and_then(|s| s.parse::<u32>().ok());
-            } else if line.starts_with("signal: ") {
-                signal = line
-                    .strip_prefix("signal: ")
-                    .and_then(|s| s.split_whitespace().next())
-                    .and_then(|s| s.parse::<i32>().ok());
-            } else if line.starts_with("tx bitrate: ") {
-                tx_rate = line
-                    .strip_prefix("tx bitrate: ")
-                    .and_then(|s| s.split_whitespace().next())
-                    .and_then(|s| s.parse::<f32>().ok())
-                    .map(|r| r as u32);
-            }
-        }
+    fn supports_enterprise(&self) -> bool {
+        true // wpa_supplicant handles 802.1X
+    }
 
-        Ok(ssid.map(|ssid| ConnectionInfo {
-            ssid,
-            bssid,
-            signal_strength: signal,
-            noise_level: None,
-            channel: freq.map(freq_to_channel),
-            tx_rate,
-            security: SecurityType::unknown(),
-            connected_at: None,
-        }))
+    async fn supplicant_transcript(
+        &self,
+        interface: &str,
+        listen: Duration,
+        reassociate: bool,
+    ) -> Result<Option<SupplicantTranscript>> {
+        let name = interface.to_string();
+        tokio::task::spawn_blocking(move || wpa_ctrl::record(&name, listen, reassociate))
+            .await
+            .map_err(|e| Error::Wifi {
+                interface: Some(interface.to_string()),
+                message: format!("wpa_supplicant query failed: {}", e),
+            })?
     }
 
-    async fn get_signal_strength(&self, interface: &str) -> Result<Option<i32>> {
-        if let Some(conn) = self.get_current_connection(interface).await? {
-            return Ok(conn.signal_strength);
-        }
-        Ok(None)
+    async fn trigger_scan(&self, interface: &str) -> Result<()> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            nl.trigger_scan(interface.index, SCAN_TIMEOUT)
+        })
+        .await
+    }
+
+    async fn get_supported_standards(&self, interface: &str) -> Result<Vec<WifiStandard>> {
+        let name = interface.to_string();
+        Self::query(move |nl| {
+            let interface = nl.interface(&name)?;
+            Ok(nl.wiphy(interface.wiphy)?.standards())
+        })
+        .await
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use super::nl80211::{Rate, RateKind};
     use super::*;
 
+    fn ap(frequency: u32, rssi: i32) -> AccessPoint {
+        let bss = Bss {
+            frequency,
+            signal_dbm: Some(rssi),
+            ..Bss::default()
+        };
+        LinuxWifiProvider::access_point(&bss, &[])
+    }
+
+    #[test]
+    fn test_access_point_from_bss() {
+        let bss = Bss {
+            bssid: [0x02, 0, 0, 0, 0, 1],
+            frequency: 5180,
+            capability: 0x0411,
+            signal_dbm: Some(-60),
+            ies: vec![
+                0, 3, b'l', b'a', b'b', 191, 12, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+            ],
+            ..Bss::default()
+        };
+        let surveys = [Survey {
+            frequency: 5180,
+            noise: Some(-95),
+            ..Survey::default()
+        }];
+        let ap = LinuxWifiProvider::access_point(&bss, &surveys);
+        assert_eq!(ap.ssid.as_str(), "lab");
+        assert_eq!(ap.channel.number, 36);
+        assert_eq!(ap.signal_quality, 80);
+        assert_eq!(ap.snr, Some(35));
+        assert_eq!(ap.wifi_standard, WifiStandard::Dot11ac);
+        assert!(ap.capabilities.privacy && ap.capabilities.ess);
+        assert_eq!(ap.security.authentication, WifiAuthentication::Wep);
+    }
+
+    #[test]
+    fn test_utilization_prefers_survey() {
+        let aps = [ap(2437, -60), ap(2437, -70), ap(2462, -50)];
+        let channel = Channel::from_frequency(2437);
+
+        let estimated = LinuxWifiProvider::utilization(channel, &aps, None);
+        assert_eq!(estimated.network_count, 2);
+        assert_eq!(estimated.avg_rssi, -65);
+        assert_eq!(estimated.interference_level, InterferenceLevel::Medium);
+
+        let survey = Survey {
+            frequency: 2437,
+            active_ms: Some(200),
+            busy_ms: Some(160),
+            ..Survey::default()
+        };
+        let measured = LinuxWifiProvider::utilization(channel, &aps, Some(&survey));
+        assert_eq!(measured.utilization_percent, 80.0);
+        assert_eq!(measured.interference_level, InterferenceLevel::Severe);
+        assert!(!measured.recommended);
+    }
+
+    #[test]
+    fn test_connection_from_station() {
+        let interface = Interface {
+            name: "wlan0".to_string(),
+            frequency: Some(5180),
+            width_mhz: Some(80),
+            ssid: Some(b"lab".to_vec()),
+            ..Interface::default()
+        };
+        let station = Station {
+            mac: [0x02, 0, 0, 0, 0, 1],
+            signal_avg: Some(-55),
+            tx_bitrate: Some(Rate {
+                bitrate: Some(8667),
+                kind: RateKind::Vht,
+                mcs: Some(9),
+                nss: Some(2),
+                width_mhz: 80,
+                short_gi: true,
+                he_gi: None,
+            }),
+            tx_packets: Some(2000),
+            tx_retries: Some(150),
+            tx_failed: Some(3),
+            connected_time: Some(90),
+            ..Station::default()
+        };
+
+        // Not in the scan cache, so built from the interface
+        let connection = LinuxWifiProvider::connection(&interface, &station, Vec::new(), &[]);
+        assert_eq!(connection.access_point.ssid.as_str(), "lab");
+        assert_eq!(connection.access_point.rssi, -55);
+        assert!(connection.access_point.is_connected);
+        assert_eq!(connection.tx_rate, Some(866.7));
+        assert_eq!(connection.mcs_index, Some(9));
+        assert_eq!(connection.channel_width, Some(ChannelWidth::Mhz80));
+        assert_eq!(connection.connected_duration, Some(Duration::from_secs(90)));
+        assert_eq!(connection.tx_failed, Some(3));
+        assert_eq!(connection.tx_retry_percent(), Some(7.5));
+    }
+
     #[tokio::test]
     async fn test_list_wifi_interfaces() {
         let provider = LinuxWifiProvider::new();
-        // This test will only pass on Linux systems with WiFi
-        let _ = provider.list_wifi_interfaces().await;
+        // Fails without nl80211 (e.g. in containers); must not panic either way
+        if let Ok(interfaces) = provider.list_wifi_interfaces().await {
+            for interface in interfaces {
+                assert!(!interface.name.is_empty());
+            }
+        }
     }
 }
@@ -0,0 +1,590 @@
+//! Minimal generic netlink client.
+//!
+//! Only what nl80211 needs: resolving a family, dump and single-reply
+//! requests, multicast subscriptions and attribute parsing.
+
+use netdiag_types::error::{Error, Result};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::time::Duration;
+
+const NETLINK_GENERIC: libc::c_int = 16;
+const SOL_NETLINK: libc::c_int = 270;
+const NETLINK_ADD_MEMBERSHIP: libc::c_int = 1;
+const NETLINK_EXT_ACK: libc::c_int = 11;
+
+const NLM_F_REQUEST: u16 = 0x01;
+const NLM_F_ACK: u16 = 0x04;
+const NLM_F_DUMP: u16 = 0x300;
+
+const NLMSG_ERROR: u16 = 2;
+const NLMSG_DONE: u16 = 3;
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+const CTRL_ATTR_MCAST_GROUPS: u16 = 7;
+const CTRL_ATTR_MCAST_GRP_NAME: u16 = 1;
+const CTRL_ATTR_MCAST_GRP_ID: u16 = 2;
+
+const NLA_F_NESTED: u16 = 0x8000;
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+/// Netlink header plus generic netlink header.
+const HEADER_LEN: usize = 16 + 4;
+
+/// Round up to the 4-byte netlink alignment.
+const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// A generic netlink request being built.
+#[derive(Debug, Clone)]
+pub struct Request {
+    buf: Vec<u8>,
+    nests: Vec<usize>,
+}
+
+impl Request {
+    /// Start a request for `cmd` in `family`.
+    pub fn new(family: u16, cmd: u8, dump: bool) -> Self {
+        let mut buf = vec![0u8; HEADER_LEN];
+        let flags = NLM_F_REQUEST | NLM_F_ACK | if dump { NLM_F_DUMP } else { 0 };
+        buf[4..6].copy_from_slice(&family.to_ne_bytes());
+        buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+        buf[16] = cmd;
+        buf[17] = 1;
+        Self {
+            buf,
+            nests: Vec::new(),
+        }
+    }
+
+    /// Append a raw attribute.
+    pub fn attr(mut self, kind: u16, payload: &[u8]) -> Self {
+        self.push_attr(kind, payload);
+        self
+    }
+
+    /// Append a `u32` attribute.
+    pub fn attr_u32(self, kind: u16, value: u32) -> Self {
+        self.attr(kind, &value.to_ne_bytes())
+    }
+
+    /// Append a NUL-terminated string attribute.
+    pub fn attr_str(self, kind: u16, value: &str) -> Self {
+        let mut payload = value.as_bytes().to_vec();
+        payload.push(0);
+        self.attr(kind, &payload)
+    }
+
+    /// Append a flag attribute.
+    pub fn flag(self, kind: u16) -> Self {
+        self.attr(kind, &[])
+    }
+
+    /// Open a nested attribute; close it with [`end_nested`](Self::end_nested).
+    pub fn nested(mut self, kind: u16) -> Self {
+        self.nests.push(self.buf.len());
+        self.buf.extend_from_slice(&[0, 0]);
+        self.buf
+            .extend_from_slice(&(kind | NLA_F_NESTED).to_ne_bytes());
+        self
+    }
+
+    /// Close the innermost nested attribute.
+    pub fn end_nested(mut self) -> Self {
+        if let Some(start) = self.nests.pop() {
+            let len = (self.buf.len() - start) as u16;
+            self.buf[start..start + 2].copy_from_slice(&len.to_ne_bytes());
+        }
+        self
+    }
+
+    fn push_attr(&mut self, kind: u16, payload: &[u8]) {
+        let len = 4 + payload.len();
+        self.buf.extend_from_slice(&(len as u16).to_ne_bytes());
+        self.buf.extend_from_slice(&kind.to_ne_bytes());
+        self.buf.extend_from_slice(payload);
+        self.buf.resize(align(self.buf.len()), 0);
+    }
+
+    /// Encode with the given sequence number.
+    pub fn encode(&self, seq: u32) -> Vec<u8> {
+        let mut buf = self.buf.clone();
+        let len = buf.len() as u32;
+        buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        buf
+    }
+}
+
+/// A generic netlink message received from the kernel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// Generic netlink command.
+    pub cmd: u8,
+    /// Attribute payload.
+    pub attrs: Vec<u8>,
+}
+
+impl Message {
+    /// Iterate over the top-level attributes.
+    pub fn attrs(&self) -> Attrs<'_> {
+        Attrs::new(&self.attrs)
+    }
+}
+
+/// What a received datagram contained.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Received {
+    /// Messages, and whether the request is complete.
+    Messages(Vec<Message>, bool),
+    /// Kernel error (negative errno).
+    Error(i32),
+}
+
+/// Split a datagram into generic netlink messages.
+pub fn parse_datagram(mut data: &[u8], seq: Option<u32>) -> Received {
+    let mut messages = Vec::new();
+    let mut done = false;
+    while data.len() >= 16 {
+        let len = u32::from_ne_bytes([data[0], data[1], data[2], data[3]]) as usize;
+        if len < 16 || len > data.len() {
+            break;
+        }
+        let kind = u16::from_ne_bytes([data[4], data[5]]);
+        let msg_seq = u32::from_ne_bytes([data[8], data[9], data[10], data[11]]);
+        let body = &data[16..len];
+        data = &data[align(len).min(data.len())..];
+
+        // Skip replies to other requests (multicast events use sequence 0)
+        if seq.is_some_and(|seq| seq != msg_seq) {
+            continue;
+        }
+        match kind {
+            NLMSG_DONE => done = true,
+            NLMSG_ERROR => {
+                let errno = body
+                    .get(0..4)
+                    .map(|b| i32::from_ne_bytes([b[0], b[1], b[2], b[3]]))
+                    .unwrap_or(0);
+                if errno != 0 {
+                    return Received::Error(errno);
+                }
+                // Zero is the ACK that ends a non-dump request
+                done = true;
+            }
+            _ if body.len() >= 4 => {
+                messages.push(Message {
+                    cmd: body[0],
+                    attrs: body[4..].to_vec(),
+                });
+            }
+            _ => {}
+        }
+    }
+    Received::Messages(messages, done)
+}
+
+/// Iterator over netlink attributes as (type, payload).
+#[derive(Debug, Clone)]
+pub struct Attrs<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Attrs<'a> {
+    /// Iterate over an attribute payload.
+    pub const fn new(data: &'a [u8]) -> Self {
+        Self { data }
+    }
+
+    /// Find the first attribute of a type.
+    pub fn get(&self, kind: u16) -> Option<Attr<'a>> {
+        self.clone().find(|attr| attr.kind == kind)
+    }
+
+    /// Whether an attribute (typically a flag) is present.
+    pub fn has(&self, kind: u16) -> bool {
+        self.get(kind).is_some()
+    }
+}
+
+impl<'a> Iterator for Attrs<'a> {
+    type Item = Attr<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.data.len() < 4 {
+            return None;
+        }
+        let len = u16::from_ne_bytes([self.data[0], self.data[1]]) as usize;
+        let kind = u16::from_ne_bytes([self.data[2], self.data[3]]) & NLA_TYPE_MASK;
+        if len < 4 || len > self.data.len() {
+            self.data = &[];
+            return None;
+        }
+        let payload = &self.data[4..len];
+        self.data = &self.data[align(len).min(self.data.len())..];
+        Some(Attr { kind, payload })
+    }
+}
+
+/// A single netlink attribute.
+#[derive(Debug, Clone, Copy)]
+pub struct Attr<'a> {
+    /// Attribute type (without flags).
+    pub kind: u16,
+    /// Attribute payload.
+    pub payload: &'a [u8],
+}
+
+impl<'a> Attr<'a> {
+    /// Payload as `u8`.
+    pub fn u8(&self) -> Option<u8> {
+        self.payload.first().copied()
+    }
+
+    /// Payload as `u16`.
+    pub fn u16(&self) -> Option<u16> {
+        Some(u16::from_ne_bytes(self.payload.get(0..2)?.try_into().ok()?))
+    }
+
+    /// Payload as `u32`.
+    pub fn u32(&self) -> Option<u32> {
+        Some(u32::from_ne_bytes(self.payload.get(0..4)?.try_into().ok()?))
+    }
+
+    /// Payload as `u64`.
+    pub fn u64(&self) -> Option<u64> {
+        Some(u64::from_ne_bytes(self.payload.get(0..8)?.try_into().ok()?))
+    }
+
+    /// Payload as a signed byte (dBm values).
+    pub fn i8(&self) -> Option<i8> {
+        self.u8().map(|v| v as i8)
+    }
+
+    /// Payload as a NUL-terminated string.
+    pub fn string(&self) -> String {
+        let end = self
+            .payload
+            .iter()
+            .position(|&b| b == 0)
+            .unwrap_or(self.payload.len());
+        String::from_utf8_lossy(&self.payload[..end]).into_owned()
+    }
+
+    /// Nested attributes.
+    pub fn nested(&self) -> Attrs<'a> {
+        Attrs::new(self.payload)
+    }
+}
+
+/// A generic netlink socket.
+pub struct Socket {
+    fd: OwnedFd,
+    seq: u32,
+}
+
+impl Socket {
+    /// Open a generic netlink socket.
+    pub fn open() -> Result<Self> {
+        // SAFETY: plain socket(2) call; the descriptor is owned below
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_RAW | libc::SOCK_CLOEXEC,
+                NETLINK_GENERIC,
+            )
+        };
+        if fd < 0 {
+            return Err(netlink_error("socket", io::Error::last_os_error()));
+        }
+        // SAFETY: fd is a freshly created, valid descriptor
+        let fd = unsafe { OwnedFd::from_raw_fd(fd) };
+
+        // SAFETY: sockaddr_nl is plain data; zeroed means "kernel assigns the port"
+        let mut addr: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        // SAFETY: addr is a valid sockaddr_nl for the duration of the call
+        let rc = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                std::ptr::addr_of!(addr).cast(),
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(netlink_error("bind", io::Error::last_os_error()));
+        }
+
+        let socket = Self { fd, seq: 1 };
+        // Extended ACKs are optional; older kernels reject the option
+        let _ = socket.set_option(SOL_NETLINK, NETLINK_EXT_ACK, 1);
+        socket.set_timeout(Duration::from_secs(5))?;
+        Ok(socket)
+    }
+
+    fn set_option(&self, level: libc::c_int, name: libc::c_int, value: libc::c_int) -> Result<()> {
+        // SAFETY: value outlives the call and the length matches its type
+        let rc = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                level,
+                name,
+                std::ptr::addr_of!(value).cast(),
+                std::mem::size_of::<libc::c_int>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(netlink_error("setsockopt", io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Set the receive timeout.
+    pub fn set_timeout(&self, timeout: Duration) -> Result<()> {
+        let tv = libc::timeval {
+            tv_sec: timeout.as_secs() as libc::time_t,
+            tv_usec: libc::suseconds_t::from(timeout.subsec_micros() as i32),
+        };
+        // SAFETY: tv outlives the call and the length matches its type
+        let rc = unsafe {
+            libc::setsockopt(
+                self.fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                std::ptr::addr_of!(tv).cast(),
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if rc < 0 {
+            return Err(netlink_error("setsockopt", io::Error::last_os_error()));
+        }
+        Ok(())
+    }
+
+    /// Subscribe to a multicast group.
+    pub fn join_group(&self, group: u32) -> Result<()> {
+        self.set_option(SOL_NETLINK, NETLINK_ADD_MEMBERSHIP, group as libc::c_int)
+    }
+
+    /// Send a request and collect every reply message.
+    pub fn request(&mut self, request: &Request) -> Result<Vec<Message>> {
+        let seq = self.send(request)?;
+        let mut messages = Vec::new();
+        loop {
+            match parse_datagram(&self.recv()?, Some(seq)) {
+                Received::Messages(batch, done) => {
+                    messages.extend(batch);
+                    if done {
+                        return Ok(messages);
+                    }
+                }
+                Received::Error(errno) => {
+                    return Err(netlink_error(
+                        "request",
+                        io::Error::from_raw_os_error(-errno),
+                    ))
+                }
+            }
+        }
+    }
+
+    /// Send a request without waiting for replies; returns its sequence number.
+    pub fn send(&mut self, request: &Request) -> Result<u32> {
+        self.seq = self.seq.wrapping_add(1);
+        let buf = request.encode(self.seq);
+        // SAFETY: buf is valid for reads of buf.len() bytes
+        let rc = unsafe { libc::send(self.fd.as_raw_fd(), buf.as_ptr().cast(), buf.len(), 0) };
+        if rc < 0 {
+            return Err(netlink_error("send", io::Error::last_os_error()));
+        }
+        Ok(self.seq)
+    }
+
+    /// Receive one datagram.
+    pub fn recv(&self) -> Result<Vec<u8>> {
+        let mut buf = vec![0u8; 64 * 1024];
+        // SAFETY: buf is valid for writes of buf.len() bytes
+        let rc = unsafe { libc::recv(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+        if rc < 0 {
+            let err = io::Error::last_os_error();
+            if err.kind() == io::ErrorKind::WouldBlock {
+                return Err(Error::timeout("netlink receive", 0));
+            }
+            return Err(netlink_error("recv", err));
+        }
+        buf.truncate(rc as usize);
+        Ok(buf)
+    }
+
+    /// Resolve a generic netlink family to its ID and multicast groups.
+    pub fn resolve_family(&mut self, name: &str) -> Result<Family> {
+        let request = Request::new(GENL_ID_CTRL, CTRL_CMD_GETFAMILY, false)
+            .attr_str(CTRL_ATTR_FAMILY_NAME, name);
+        let messages = self.request(&request)?;
+        messages
+            .iter()
+            .find_map(|message| Family::parse(message.attrs()))
+            .ok_or_else(|| Error::unsupported(format!("netlink family {}", name), "this kernel"))
+    }
+}
+
+/// A resolved generic netlink family.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Family {
+    /// Family ID used as the message type.
+    pub id: u16,
+    /// Multicast groups as (name, ID).
+    pub groups: Vec<(String, u32)>,
+}
+
+impl Family {
+    /// Parse a `CTRL_CMD_NEWFAMILY` reply.
+    fn parse(attrs: Attrs<'_>) -> Option<Self> {
+        let id = attrs.get(CTRL_ATTR_FAMILY_ID)?.u16()?;
+        let groups = attrs
+            .get(CTRL_ATTR_MCAST_GROUPS)
+            .map(|groups| {
+                groups
+                    .nested()
+                    .filter_map(|group| {
+                        let group = group.nested();
+                        Some((
+                            group.get(CTRL_ATTR_MCAST_GRP_NAME)?.string(),
+                            group.get(CTRL_ATTR_MCAST_GRP_ID)?.u32()?,
+                        ))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        Some(Self { id, groups })
+    }
+
+    /// ID of a multicast group.
+    pub fn group(&self, name: &str) -> Option<u32> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, id)| *id)
+    }
+}
+
+/// Convert a socket error, flagging missing privileges.
+fn netlink_error(operation: &str, error: io::Error) -> Error {
+    match error.raw_os_error() {
+        Some(libc::EPERM) | Some(libc::EACCES) => {
+            Error::permission_denied(format!("nl80211 {}", operation), "CAP_NET_ADMIN")
+        }
+        Some(libc::ENODEV) => Error::Wifi {
+            interface: None,
+            message: "no such wireless device".to_string(),
+        },
+        _ => Error::Wifi {
+            interface: None,
+            message: format!("netlink {} failed: {}", operation, error),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NLM_F_MULTI: u16 = 0x02;
+
+    /// Wrap a payload in a netlink header.
+    pub(crate) fn raw(kind: u16, flags: u16, seq: u32, body: &[u8]) -> Vec<u8> {
+        let len = 16 + body.len();
+        let mut buf = Vec::with_capacity(align(len));
+        buf.extend_from_slice(&(len as u32).to_ne_bytes());
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(&flags.to_ne_bytes());
+        buf.extend_from_slice(&seq.to_ne_bytes());
+        buf.extend_from_slice(&0u32.to_ne_bytes());
+        buf.extend_from_slice(body);
+        buf.resize(align(len), 0);
+        buf
+    }
+
+    /// Wrap generic netlink attributes in a message for `cmd`.
+    pub(crate) fn frame(kind: u16, flags: u16, seq: u32, cmd: u8, attrs: &[u8]) -> Vec<u8> {
+        let mut body = vec![cmd, 1, 0, 0];
+        body.extend_from_slice(attrs);
+        raw(kind, flags, seq, &body)
+    }
+
+    #[test]
+    fn test_request_encoding() {
+        let request = Request::new(0x1c, 32, true)
+            .attr_u32(3, 7)
+            .nested(45)
+            .attr(1, &[])
+            .end_nested();
+        let buf = request.encode(9);
+        assert_eq!(
+            u32::from_ne_bytes(buf[0..4].try_into().unwrap()) as usize,
+            buf.len()
+        );
+        assert_eq!(u16::from_ne_bytes([buf[4], buf[5]]), 0x1c);
+        assert_eq!(
+            u16::from_ne_bytes([buf[6], buf[7]]),
+            NLM_F_REQUEST | NLM_F_ACK | NLM_F_DUMP
+        );
+        assert_eq!(u32::from_ne_bytes(buf[8..12].try_into().unwrap()), 9);
+        assert_eq!(buf[16], 32);
+
+        let attrs: Vec<_> = Attrs::new(&buf[HEADER_LEN..]).collect();
+        assert_eq!(attrs.len(), 2);
+        assert_eq!(attrs[0].u32(), Some(7));
+        assert_eq!(attrs[1].kind, 45);
+        assert_eq!(attrs[1].nested().next().map(|a| a.kind), Some(1));
+    }
+
+    #[test]
+    fn test_parse_dump_and_error() {
+        let attrs = Request::new(0, 0, false)
+            .attr_str(2, "nl80211")
+            .attr_u32(1, 5);
+        let body = &attrs.encode(0)[HEADER_LEN..];
+
+        let mut datagram = frame(0x1c, NLM_F_MULTI, 4, 5, body);
+        datagram.extend(frame(0x1c, NLM_F_MULTI, 3, 5, body));
+        datagram.extend(frame(NLMSG_DONE, NLM_F_MULTI, 4, 0, &[]));
+        let Received::Messages(messages, done) = parse_datagram(&datagram, Some(4)) else {
+            panic!("expected messages");
+        };
+        assert!(done);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].attrs().get(2).unwrap().string(), "nl80211");
+
+        let error = raw(NLMSG_ERROR, 0, 4, &(-libc::EPERM).to_ne_bytes());
+        assert_eq!(
+            parse_datagram(&error, Some(4)),
+            Received::Error(-libc::EPERM)
+        );
+    }
+
+    #[test]
+    fn test_parse_family() {
+        let request = Request::new(0, 0, false)
+            .attr(CTRL_ATTR_FAMILY_ID, &0x1c_u16.to_ne_bytes())
+            .nested(CTRL_ATTR_MCAST_GROUPS)
+            .nested(1)
+            .attr_str(CTRL_ATTR_MCAST_GRP_NAME, "config")
+            .attr_u32(CTRL_ATTR_MCAST_GRP_ID, 4)
+            .end_nested()
+            .nested(2)
+            .attr_str(CTRL_ATTR_MCAST_GRP_NAME, "scan")
+            .attr_u32(CTRL_ATTR_MCAST_GRP_ID, 5)
+            .end_nested()
+            .end_nested();
+        let buf = request.encode(0);
+        let family = Family::parse(Attrs::new(&buf[HEADER_LEN..])).unwrap();
+        assert_eq!(family.id, 0x1c);
+        assert_eq!(family.group("scan"), Some(5));
+        assert_eq!(family.group("mlme"), None);
+    }
+}
@@ -0,0 +1,782 @@
+//! nl80211 queries: interfaces, scans, station info, surveys and PHY capabilities.
+
+use super::netlink::{parse_datagram, Attrs, Family, Message, Received, Request, Socket};
+use netdiag_types::error::{Error, Result};
+use netdiag_types::wifi::{GuardInterval, WifiStandard};
+use std::time::{Duration, Instant};
+
+// Commands
+const CMD_GET_WIPHY: u8 = 1;
+const CMD_GET_INTERFACE: u8 = 5;
+const CMD_GET_STATION: u8 = 17;
+const CMD_GET_REG: u8 = 31;
+const CMD_GET_SCAN: u8 = 32;
+const CMD_TRIGGER_SCAN: u8 = 33;
+const CMD_NEW_SCAN_RESULTS: u8 = 34;
+const CMD_SCAN_ABORTED: u8 = 35;
+const CMD_GET_SURVEY: u8 = 50;
+
+// Top-level attributes
+const ATTR_WIPHY: u16 = 1;
+const ATTR_IFINDEX: u16 = 3;
+const ATTR_IFNAME: u16 = 4;
+const ATTR_IFTYPE: u16 = 5;
+const ATTR_MAC: u16 = 6;
+const ATTR_STA_INFO: u16 = 21;
+const ATTR_WIPHY_BANDS: u16 = 22;
+const ATTR_REG_ALPHA2: u16 = 33;
+const ATTR_WIPHY_FREQ: u16 = 38;
+const ATTR_SCAN_SSIDS: u16 = 45;
+const ATTR_BSS: u16 = 47;
+const ATTR_SSID: u16 = 52;
+const ATTR_SURVEY_INFO: u16 = 84;
+const ATTR_CHANNEL_WIDTH: u16 = 159;
+const ATTR_CENTER_FREQ1: u16 = 160;
+const ATTR_SPLIT_WIPHY_DUMP: u16 = 174;
+
+// BSS attributes
+const BSS_BSSID: u16 = 1;
+const BSS_FREQUENCY: u16 = 2;
+const BSS_BEACON_INTERVAL: u16 = 4;
+const BSS_CAPABILITY: u16 = 5;
+const BSS_INFORMATION_ELEMENTS: u16 = 6;
+const BSS_SIGNAL_MBM: u16 = 7;
+const BSS_STATUS: u16 = 9;
+const BSS_SEEN_MS_AGO: u16 = 10;
+const BSS_STATUS_ASSOCIATED: u32 = 1;
+
+// Station info attributes
+const STA_INFO_INACTIVE_TIME: u16 = 1;
+const STA_INFO_SIGNAL: u16 = 7;
+const STA_INFO_TX_BITRATE: u16 = 8;
+const STA_INFO_RX_PACKETS: u16 = 9;
+const STA_INFO_TX_PACKETS: u16 = 10;
+const STA_INFO_TX_RETRIES: u16 = 11;
+const STA_INFO_TX_FAILED: u16 = 12;
+const STA_INFO_SIGNAL_AVG: u16 = 13;
+const STA_INFO_RX_BITRATE: u16 = 14;
+const STA_INFO_CONNECTED_TIME: u16 = 16;
+
+// Rate info attributes
+const RATE_BITRATE: u16 = 1;
+const RATE_MCS: u16 = 2;
+const RATE_40_MHZ: u16 = 3;
+const RATE_SHORT_GI: u16 = 4;
+const RATE_BITRATE32: u16 = 5;
+const RATE_VHT_MCS: u16 = 6;
+const RATE_VHT_NSS: u16 = 7;
+const RATE_80_MHZ: u16 = 8;
+const RATE_80P80_MHZ: u16 = 9;
+const RATE_160_MHZ: u16 = 10;
+const RATE_HE_MCS: u16 = 13;
+const RATE_HE_NSS: u16 = 14;
+const RATE_HE_GI: u16 = 15;
+const RATE_320_MHZ: u16 = 18;
+const RATE_EHT_MCS: u16 = 19;
+const RATE_EHT_NSS: u16 = 20;
+const RATE_EHT_GI: u16 = 21;
+
+// Survey attributes
+const SURVEY_FREQUENCY: u16 = 1;
+const SURVEY_NOISE: u16 = 2;
+const SURVEY_IN_USE: u16 = 3;
+const SURVEY_TIME: u16 = 4;
+const SURVEY_TIME_BUSY: u16 = 5;
+const SURVEY_TIME_RX: u16 = 7;
+const SURVEY_TIME_TX: u16 = 8;
+
+// Band attributes
+const BAND_HT_CAPA: u16 = 4;
+const BAND_VHT_CAPA: u16 = 8;
+const BAND_IFTYPE_DATA: u16 = 9;
+const IFTYPE_HE_CAP_PHY: u16 = 3;
+const IFTYPE_EHT_CAP_PHY: u16 = 9;
+
+/// Band IDs.
+const BAND_2GHZ: u16 = 0;
+const BAND_5GHZ: u16 = 1;
+const BAND_6GHZ: u16 = 3;
+
+/// Managed (client) interface type.
+pub const IFTYPE_STATION: u32 = 2;
+
+/// A wireless network interface.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interface {
+    /// Kernel interface index.
+    pub index: u32,
+    /// Interface name.
+    pub name: String,
+    /// Hardware address.
+    pub mac: Option<[u8; 6]>,
+    /// PHY the interface belongs to.
+    pub wiphy: u32,
+    /// nl80211 interface type.
+    pub iftype: u32,
+    /// Operating frequency in MHz.
+    pub frequency: Option<u32>,
+    /// Channel width in MHz.
+    pub width_mhz: Option<u32>,
+    /// Center frequency of the whole channel.
+    pub center_frequency: Option<u32>,
+    /// SSID while associated.
+    pub ssid: Option<Vec<u8>>,
+}
+
+impl Interface {
+    fn parse(attrs: Attrs<'_>) -> Option<Self> {
+        Some(Self {
+            index: attrs.get(ATTR_IFINDEX)?.u32()?,
+            name: attrs.get(ATTR_IFNAME)?.string(),
+            mac: attrs.get(ATTR_MAC).and_then(|a| a.payload.try_into().ok()),
+            wiphy: attrs.get(ATTR_WIPHY).and_then(|a| a.u32()).unwrap_or(0),
+            iftype: attrs.get(ATTR_IFTYPE).and_then(|a| a.u32()).unwrap_or(0),
+            frequency: attrs.get(ATTR_WIPHY_FREQ).and_then(|a| a.u32()),
+            width_mhz: attrs
+                .get(ATTR_CHANNEL_WIDTH)
+                .and_then(|a| a.u32())
+                .and_then(channel_width_mhz),
+            center_frequency: attrs.get(ATTR_CENTER_FREQ1).and_then(|a| a.u32()),
+            ssid: attrs.get(ATTR_SSID).map(|a| a.payload.to_vec()),
+        })
+    }
+}
+
+/// Width in MHz of an `nl80211_chan_width` value.
+fn channel_width_mhz(width: u32) -> Option<u32> {
+    match width {
+        0 | 1 => Some(20),
+        2 => Some(40),
+        3 => Some(80),
+        4 | 5 => Some(160),
+        13 => Some(320),
+        _ => None,
+    }
+}
+
+/// A BSS from the scan results.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Bss {
+    /// BSSID.
+    pub bssid: [u8; 6],
+    /// Frequency in MHz.
+    pub frequency: u32,
+    /// Beacon interval in time units.
+    pub beacon_interval: Option<u16>,
+    /// Capability information field.
+    pub capability: u16,
+    /// Signal in dBm.
+    pub signal_dbm: Option<i32>,
+    /// Raw information elements.
+    pub ies: Vec<u8>,
+    /// Whether we are associated with this BSS.
+    pub associated: bool,
+    /// Age of the result in milliseconds.
+    pub seen_ms_ago: Option<u32>,
+}
+
+impl Bss {
+    fn parse(attrs: Attrs<'_>) -> Option<Self> {
+        let bss = attrs.get(ATTR_BSS)?.nested();
+        Some(Self {
+            bssid: bss.get(BSS_BSSID)?.payload.try_into().ok()?,
+            frequency: bss.get(BSS_FREQUENCY)?.u32()?,
+            beacon_interval: bss.get(BSS_BEACON_INTERVAL).and_then(|a| a.u16()),
+            capability: bss.get(BSS_CAPABILITY).and_then(|a| a.u16()).unwrap_or(0),
+            // mBm are hundredths of a dBm
+            signal_dbm: bss
+                .get(BSS_SIGNAL_MBM)
+                .and_then(|a| a.u32())
+                .map(|mbm| mbm as i32 / 100),
+            ies: bss
+                .get(BSS_INFORMATION_ELEMENTS)
+                .map(|a| a.payload.to_vec())
+                .unwrap_or_default(),
+            associated: bss
+                .get(BSS_STATUS)
+                .and_then(|a| a.u32())
+                .is_some_and(|status| status == BSS_STATUS_ASSOCIATED),
+            seen_ms_ago: bss.get(BSS_SEEN_MS_AGO).and_then(|a| a.u32()),
+        })
+    }
+}
+
+/// Modulation family of a bitrate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RateKind {
+    /// 802.11a/b/g.
+    #[default]
+    Legacy,
+    /// 802.11n.
+    Ht,
+    /// 802.11ac.
+    Vht,
+    /// 802.11ax.
+    He,
+    /// 802.11be.
+    Eht,
+}
+
+/// A TX or RX bitrate.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rate {
+    /// Bitrate in units of 100 kbit/s.
+    pub bitrate: Option<u32>,
+    /// Modulation family.
+    pub kind: RateKind,
+    /// MCS index.
+    pub mcs: Option<u8>,
+    /// Spatial streams.
+    pub nss: Option<u8>,
+    /// Channel width in MHz.
+    pub width_mhz: u32,
+    /// Short guard interval.
+    pub short_gi: bool,
+    /// HE/EHT guard interval (0 = 0.8 µs, 1 = 1.6 µs, 2 = 3.2 µs).
+    pub he_gi: Option<u8>,
+}
+
+impl Rate {
+    fn parse(attrs: Attrs<'_>) -> Self {
+        let u8_of = |kind| attrs.get(kind).and_then(|a| a.u8());
+        let (kind, mcs, nss) = if let Some(mcs) = u8_of(RATE_EHT_MCS) {
+            (RateKind::Eht, Some(mcs), u8_of(RATE_EHT_NSS))
+        } else if let Some(mcs) = u8_of(RATE_HE_MCS) {
+            (RateKind::He, Some(mcs), u8_of(RATE_HE_NSS))
+        } else if let Some(mcs) = u8_of(RATE_VHT_MCS) {
+            (RateKind::Vht, Some(mcs), u8_of(RATE_VHT_NSS))
+        } else if let Some(mcs) = u8_of(RATE_MCS) {
+            // HT MCS indices encode the stream count
+            (RateKind::Ht, Some(mcs % 8), Some(mcs / 8 + 1))
+        } else {
+            (RateKind::Legacy, None, Some(1))
+        };
+
+        let width_mhz = if attrs.has(RATE_320_MHZ) {
+            320
+        } else if attrs.has(RATE_160_MHZ) || attrs.has(RATE_80P80_MHZ) {
+            160
+        } else if attrs.has(RATE_80_MHZ) {
+            80
+        } else if attrs.has(RATE_40_MHZ) {
+            40
+        } else {
+            20
+        };
+
+        Self {
+            bitrate: attrs
+                .get(RATE_BITRATE32)
+                .and_then(|a| a.u32())
+                .or_else(|| attrs.get(RATE_BITRATE).and_then(|a| a.u16()).map(u32::from)),
+            kind,
+            mcs,
+            nss,
+            width_mhz,
+            short_gi: attrs.has(RATE_SHORT_GI),
+            he_gi: u8_of(RATE_EHT_GI).or_else(|| u8_of(RATE_HE_GI)),
+        }
+    }
+
+    /// Bitrate in Mbit/s.
+    pub fn mbps(&self) -> Option<f32> {
+        self.bitrate.map(|rate| rate as f32 / 10.0)
+    }
+
+    /// Guard interval, if the rate uses OFDM.
+    pub fn guard_interval(&self) -> Option<GuardInterval> {
+        match self.kind {
+            RateKind::Legacy => None,
+            RateKind::Ht | RateKind::Vht if self.short_gi => Some(GuardInterval::Ns400),
+            RateKind::Ht | RateKind::Vht => Some(GuardInterval::Ns800),
+            RateKind::He | RateKind::Eht => match self.he_gi.unwrap_or(0) {
+                1 => Some(GuardInterval::Ns1600),
+                2 => Some(GuardInterval::Ns3200),
+                _ => Some(GuardInterval::Ns800),
+            },
+        }
+    }
+}
+
+/// Link statistics for the associated access point.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Station {
+    /// Access point address.
+    pub mac: [u8; 6],
+    /// Last signal in dBm.
+    pub signal: Option<i8>,
+    /// Average signal in dBm.
+    pub signal_avg: Option<i8>,
+    /// Transmit bitrate.
+    pub tx_bitrate: Option<Rate>,
+    /// Receive bitrate.
+    pub rx_bitrate: Option<Rate>,
+    /// Packets sent.
+    pub tx_packets: Option<u32>,
+    /// Packets received.
+    pub rx_packets: Option<u32>,
+    /// Transmit retries.
+    pub tx_retries: Option<u32>,
+    /// Failed transmissions.
+    pub tx_failed: Option<u32>,
+    /// Seconds since association.
+    pub connected_time: Option<u32>,
+    /// Milliseconds since the last activity.
+    pub inactive_time: Option<u32>,
+}
+
+impl Station {
+    fn parse(attrs: Attrs<'_>) -> Option<Self> {
+        let info = attrs.get(ATTR_STA_INFO)?.nested();
+        let u32_of = |kind| info.get(kind).and_then(|a| a.u32());
+        Some(Self {
+            mac: attrs.get(ATTR_MAC)?.payload.try_into().ok()?,
+            signal: info.get(STA_INFO_SIGNAL).and_then(|a| a.i8()),
+            signal_avg: info.get(STA_INFO_SIGNAL_AVG).and_then(|a| a.i8()),
+            tx_bitrate: info
+                .get(STA_INFO_TX_BITRATE)
+                .map(|a| Rate::parse(a.nested())),
+            rx_bitrate: info
+                .get(STA_INFO_RX_BITRATE)
+                .map(|a| Rate::parse(a.nested())),
+            tx_packets: u32_of(STA_INFO_TX_PACKETS),
+            rx_packets: u32_of(STA_INFO_RX_PACKETS),
+            tx_retries: u32_of(STA_INFO_TX_RETRIES),
+            tx_failed: u32_of(STA_INFO_TX_FAILED),
+            connected_time: u32_of(STA_INFO_CONNECTED_TIME),
+            inactive_time: u32_of(STA_INFO_INACTIVE_TIME),
+        })
+    }
+
+    /// Signal in dBm, preferring the average.
+    pub fn signal_dbm(&self) -> Option<i32> {
+        self.signal_avg.or(self.signal).map(i32::from)
+    }
+}
+
+/// Survey data for one channel.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Survey {
+    /// Channel frequency in MHz.
+    pub frequency: u32,
+    /// Noise floor in dBm.
+    pub noise: Option<i8>,
+    /// Whether this is the channel currently in use.
+    pub in_use: bool,
+    /// Time the radio spent on the channel, in ms.
+    pub active_ms: Option<u64>,
+    /// Time the channel was sensed busy, in ms.
+    pub busy_ms: Option<u64>,
+    /// Time spent receiving, in ms.
+    pub rx_ms: Option<u64>,
+    /// Time spent transmitting, in ms.
+    pub tx_ms: Option<u64>,
+}
+
+impl Survey {
+    fn parse(attrs: Attrs<'_>) -> Option<Self> {
+        let info = attrs.get(ATTR_SURVEY_INFO)?.nested();
+        let u64_of = |kind| info.get(kind).and_then(|a| a.u64());
+        Some(Self {
+            frequency: info.get(SURVEY_FREQUENCY)?.u32()?,
+            noise: info.get(SURVEY_NOISE).and_then(|a| a.i8()),
+            in_use: info.has(SURVEY_IN_USE),
+            active_ms: u64_of(SURVEY_TIME),
+            busy_ms: u64_of(SURVEY_TIME_BUSY),
+            rx_ms: u64_of(SURVEY_TIME_RX),
+            tx_ms: u64_of(SURVEY_TIME_TX),
+        })
+    }
+
+    /// Share of the measured time the channel was busy (0-100).
+    pub fn busy_percent(&self) -> Option<f32> {
+        match (self.active_ms, self.busy_ms) {
+            (Some(active), Some(busy)) if active > 0 => {
+                Some((busy as f32 / active as f32 * 100.0).min(100.0))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// PHY capabilities accumulated from a (split) wiphy dump.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Wiphy {
+    /// 2.4 GHz band supported.
+    pub band_2ghz: bool,
+    /// 5 GHz band supported.
+    pub band_5ghz: bool,
+    /// 6 GHz band supported.
+    pub band_6ghz: bool,
+    /// HT (802.11n) supported.
+    pub ht: bool,
+    /// VHT (802.11ac) supported.
+    pub vht: bool,
+    /// HE (802.11ax) supported.
+    pub he: bool,
+    /// EHT (802.11be) supported.
+    pub eht: bool,
+}
+
+impl Wiphy {
+    /// Merge the band information from one dump message.
+    fn merge(&mut self, attrs: Attrs<'_>) {
+        let Some(bands) = attrs.get(ATTR_WIPHY_BANDS) else {
+            return;
+        };
+        for band in bands.nested() {
+            match band.kind {
+                BAND_2GHZ => self.band_2ghz = true,
+                BAND_5GHZ => self.band_5ghz = true,
+                BAND_6GHZ => self.band_6ghz = true,
+                _ => continue,
+            }
+            let band = band.nested();
+            self.ht |= band.has(BAND_HT_CAPA);
+            self.vht |= band.has(BAND_VHT_CAPA);
+            if let Some(iftypes) = band.get(BAND_IFTYPE_DATA) {
+                for data in iftypes.nested() {
+                    let data = data.nested();
+                    self.he |= data.has(IFTYPE_HE_CAP_PHY);
+                    self.eht |= data.has(IFTYPE_EHT_CAP_PHY);
+                }
+            }
+        }
+    }
+
+    /// Supported 802.11 standards.
+    pub fn standards(&self) -> Vec<WifiStandard> {
+        let mut standards = Vec::new();
+        if self.band_5ghz {
+            standards.push(WifiStandard::Dot11a);
+        }
+        if self.band_2ghz {
+            standards.extend([WifiStandard::Dot11b, WifiStandard::Dot11g]);
+        }
+        for (supported, standard) in [
+            (self.ht, WifiStandard::Dot11n),
+            (self.vht, WifiStandard::Dot11ac),
+            (self.he, WifiStandard::Dot11ax),
+            (self.eht, WifiStandard::Dot11be),
+        ] {
+            if supported {
+                standards.push(standard);
+            }
+        }
+        standards
+    }
+}
+
+/// An nl80211 connection.
+pub struct Nl80211 {
+    socket: Socket,
+    family: Family,
+}
+
+impl Nl80211 {
+    /// Connect to nl80211.
+    pub fn connect() -> Result<Self> {
+        let mut socket = Socket::open()?;
+        let family = socket.resolve_family("nl80211")?;
+        Ok(Self { socket, family })
+    }
+
+    fn request(&self, cmd: u8, dump: bool) -> Request {
+        Request::new(self.family.id, cmd, dump)
+    }
+
+    fn dump(&mut self, request: &Request) -> Result<Vec<Message>> {
+        self.socket.request(request)
+    }
+
+    /// List wireless interfaces.
+    pub fn interfaces(&mut self) -> Result<Vec<Interface>> {
+        let request = self.request(CMD_GET_INTERFACE, true);
+        Ok(self
+            .dump(&request)?
+            .iter()
+            .filter_map(|message| Interface::parse(message.attrs()))
+            .collect())
+    }
+
+    /// Find a wireless interface by name.
+    pub fn interface(&mut self, name: &str) -> Result<Interface> {
+        self.interfaces()?
+            .into_iter()
+            .find(|interface| interface.name == name)
+            .ok_or_else(|| Error::Wifi {
+                interface: Some(name.to_string()),
+                message: format!("{} is not a wireless interface", name),
+            })
+    }
+
+    /// Cached scan results.
+    pub fn scan_results(&mut self, ifindex: u32) -> Result<Vec<Bss>> {
+        let request = self
+            .request(CMD_GET_SCAN, true)
+            .attr_u32(ATTR_IFINDEX, ifindex);
+        Ok(self
+            .dump(&request)?
+            .iter()
+            .filter_map(|message| Bss::parse(message.attrs()))
+            .collect())
+    }
+
+    /// Trigger an active scan and wait for it to finish.
+    pub fn trigger_scan(&mut self, ifindex: u32, timeout: Duration) -> Result<()> {
+        let group = self.family.group("scan").ok_or_else(|| Error::Wifi {
+            interface: None,
+            message: "nl80211 scan multicast group not found".to_string(),
+        })?;
+        self.socket.join_group(group)?;
+
+        // A wildcard SSID makes the scan active
+        let request = self
+            .request(CMD_TRIGGER_SCAN, false)
+            .attr_u32(ATTR_IFINDEX, ifindex)
+            .nested(ATTR_SCAN_SSIDS)
+            .attr(1, &[])
+            .end_nested();
+        let seq = self.socket.send(&request)?;
+
+        let deadline = Instant::now() + timeout;
+        while Instant::now() < deadline {
+            let data = match self.socket.recv() {
+                Ok(data) => data,
+                Err(e) if e.is_timeout() => continue,
+                Err(e) => return Err(e),
+            };
+            // EBUSY means a scan is already running; wait for its results
+            if let Received::Error(errno) = parse_datagram(&data, Some(seq)) {
+                if errno != -libc::EBUSY {
+                    return Err(scan_error(errno));
+                }
+            }
+            if let Received::Messages(events, _) = parse_datagram(&data, Some(0)) {
+                for event in events {
+                    let ours = event
+                        .attrs()
+                        .get(ATTR_IFINDEX)
+                        .and_then(|a| a.u32())
+                        .is_some_and(|index| index == ifindex);
+                    match event.cmd {
+                        CMD_NEW_SCAN_RESULTS if ours => return Ok(()),
+                        CMD_SCAN_ABORTED if ours => {
+                            return Err(Error::Wifi {
+                                interface: None,
+                                message: "scan aborted".to_string(),
+                            })
+                        }
+                        _ => {}
+                    }
+                }
+            }
+        }
+        Err(Error::timeout("Wi-Fi scan", timeout.as_millis() as u64))
+    }
+
+    /// Link statistics for the associated access point, if any.
+    pub fn station(&mut self, ifindex: u32) -> Result<Option<Station>> {
+        let request = self
+            .request(CMD_GET_STATION, true)
+            .attr_u32(ATTR_IFINDEX, ifindex);
+        Ok(self
+            .dump(&request)?
+            .iter()
+            .find_map(|message| Station::parse(message.attrs())))
+    }
+
+    /// Channel survey (noise and busy time per channel).
+    pub fn survey(&mut self, ifindex: u32) -> Result<Vec<Survey>> {
+        let request = self
+            .request(CMD_GET_SURVEY, true)
+            .attr_u32(ATTR_IFINDEX, ifindex);
+        Ok(self
+            .dump(&request)?
+            .iter()
+            .filter_map(|message| Survey::parse(message.attrs()))
+            .collect())
+    }
+
+    /// Capabilities of a PHY.
+    pub fn wiphy(&mut self, wiphy: u32) -> Result<Wiphy> {
+        let request = self
+            .request(CMD_GET_WIPHY, true)
+            .attr_u32(ATTR_WIPHY, wiphy)
+            .flag(ATTR_SPLIT_WIPHY_DUMP);
+        let mut capabilities = Wiphy::default();
+        for message in self.dump(&request)? {
+            let attrs = message.attrs();
+            if attrs
+                .get(ATTR_WIPHY)
+                .and_then(|a| a.u32())
+                .is_some_and(|index| index == wiphy)
+            {
+                capabilities.merge(attrs);
+            }
+        }
+        Ok(capabilities)
+    }
+
+    /// Regulatory country code.
+    pub fn country(&mut self) -> Result<Option<String>> {
+        let request = self.request(CMD_GET_REG, false);
+        Ok(self.dump(&request)?.iter().find_map(|message| {
+            message
+                .attrs()
+                .get(ATTR_REG_ALPHA2)
+                .map(|a| a.string())
+                .filter(|code| code != "00")
+        }))
+    }
+}
+
+/// Convert a scan trigger error.
+fn scan_error(errno: i32) -> Error {
+    match -errno {
+        libc::EPERM | libc::EACCES => {
+            Error::permission_denied("Wi-Fi scan", "root or CAP_NET_ADMIN")
+        }
+        libc::ENETDOWN => Error::Wifi {
+            interface: None,
+            message: "interface is down".to_string(),
+        },
+        other => Error::Wifi {
+            interface: None,
+            message: format!("scan failed: {}", std::io::Error::from_raw_os_error(other)),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(request: Request) -> Vec<u8> {
+        request.encode(0)[20..].to_vec()
+    }
+
+    #[test]
+    fn test_parse_bss() {
+        let buf = attrs(
+            Request::new(0, 0, false)
+                .attr_u32(ATTR_IFINDEX, 3)
+                .nested(ATTR_BSS)
+                .attr(BSS_BSSID, &[0x02, 0x11, 0x22, 0x33, 0x44, 0x55])
+                .attr_u32(BSS_FREQUENCY, 5180)
+                .attr(BSS_BEACON_INTERVAL, &100u16.to_ne_bytes())
+                .attr(BSS_CAPABILITY, &0x0411u16.to_ne_bytes())
+                .attr_u32(BSS_SIGNAL_MBM, (-4700i32) as u32)
+                .attr(BSS_INFORMATION_ELEMENTS, &[0, 4, b'h', b'o', b'm', b'e'])
+                .attr_u32(BSS_STATUS, BSS_STATUS_ASSOCIATED)
+                .end_nested(),
+        );
+        let bss = Bss::parse(Attrs::new(&buf)).unwrap();
+        assert_eq!(bss.bssid, [0x02, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        assert_eq!(bss.frequency, 5180);
+        assert_eq!(bss.signal_dbm, Some(-47));
+        assert_eq!(bss.beacon_interval, Some(100));
+        assert_eq!(bss.ies.len(), 6);
+        assert!(bss.associated);
+    }
+
+    #[test]
+    fn test_parse_station() {
+        let buf = attrs(
+            Request::new(0, 0, false)
+                .attr(ATTR_MAC, &[0x02, 0, 0, 0, 0, 1])
+                .nested(ATTR_STA_INFO)
+                .attr(STA_INFO_SIGNAL, &[(-52i8) as u8])
+                .attr(STA_INFO_SIGNAL_AVG, &[(-50i8) as u8])
+                .attr_u32(STA_INFO_TX_RETRIES, 12)
+                .attr_u32(STA_INFO_CONNECTED_TIME, 3600)
+                .nested(STA_INFO_TX_BITRATE)
+                .attr_u32(RATE_BITRATE32, 8667)
+                .attr(RATE_VHT_MCS, &[9])
+                .attr(RATE_VHT_NSS, &[2])
+                .flag(RATE_80_MHZ)
+                .flag(RATE_SHORT_GI)
+                .end_nested()
+                .nested(STA_INFO_RX_BITRATE)
+                .attr(RATE_BITRATE, &1300u16.to_ne_bytes())
+                .attr(RATE_MCS, &[15])
+                .flag(RATE_40_MHZ)
+                .end_nested()
+                .end_nested(),
+        );
+        let station = Station::parse(Attrs::new(&buf)).unwrap();
+        assert_eq!(station.signal_dbm(), Some(-50));
+        assert_eq!(station.tx_retries, Some(12));
+        assert_eq!(station.connected_time, Some(3600));
+
+        let tx = station.tx_bitrate.unwrap();
+        assert_eq!(tx.kind, RateKind::Vht);
+        assert_eq!((tx.mcs, tx.nss, tx.width_mhz), (Some(9), Some(2), 80));
+        assert!(tx.short_gi);
+        assert_eq!(tx.guard_interval(), Some(GuardInterval::Ns400));
+        assert_eq!(tx.mbps(), Some(866.7));
+
+        let rx = station.rx_bitrate.unwrap();
+        assert_eq!(rx.kind, RateKind::Ht);
+        assert_eq!((rx.mcs, rx.nss, rx.width_mhz), (Some(7), Some(2), 40));
+        assert_eq!(rx.mbps(), Some(130.0));
+    }
+
+    #[test]
+    fn test_parse_survey() {
+        let buf = attrs(
+            Request::new(0, 0, false)
+                .nested(ATTR_SURVEY_INFO)
+                .attr_u32(SURVEY_FREQUENCY, 2437)
+                .attr(SURVEY_NOISE, &[(-92i8) as u8])
+                .flag(SURVEY_IN_USE)
+                .attr(SURVEY_TIME, &1000u64.to_ne_bytes())
+                .attr(SURVEY_TIME_BUSY, &350u64.to_ne_bytes())
+                .end_nested(),
+        );
+        let survey = Survey::parse(Attrs::new(&buf)).unwrap();
+        assert_eq!(survey.frequency, 2437);
+        assert_eq!(survey.noise, Some(-92));
+        assert!(survey.in_use);
+        assert_eq!(survey.busy_percent(), Some(35.0));
+    }
+
+    #[test]
+    fn test_wiphy_standards() {
+        let mut wiphy = Wiphy::default();
+        let band_2ghz = attrs(
+            Request::new(0, 0, false)
+                .nested(ATTR_WIPHY_BANDS)
+                .nested(BAND_2GHZ)
+                .attr(BAND_HT_CAPA, &0x1ffu16.to_ne_bytes())
+                .end_nested()
+                .end_nested(),
+        );
+        let band_5ghz = attrs(
+            Request::new(0, 0, false)
+                .nested(ATTR_WIPHY_BANDS)
+                .nested(BAND_5GHZ)
+                .attr_u32(BAND_VHT_CAPA, 0)
+                .nested(BAND_IFTYPE_DATA)
+                .nested(1)
+                .attr(IFTYPE_HE_CAP_PHY, &[0; 11])
+                .end_nested()
+                .end_nested()
+                .end_nested()
+                .end_nested(),
+        );
+        wiphy.merge(Attrs::new(&band_2ghz));
+        wiphy.merge(Attrs::new(&band_5ghz));
+        assert_eq!(
+            wiphy.standards(),
+            vec![
+                WifiStandard::Dot11a,
+                WifiStandard::Dot11b,
+                WifiStandard::Dot11g,
+                WifiStandard::Dot11n,
+                WifiStandard::Dot11ac,
+                WifiStandard::Dot11ax,
+            ]
+        );
+    }
+}
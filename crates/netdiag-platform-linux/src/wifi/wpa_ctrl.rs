@@ -0,0 +1,186 @@
+//! wpa_supplicant control interface client.
+//!
+//! Talks to the per-interface control socket wpa_supplicant creates under
+//! `/run/wpa_supplicant`, recording every exchange in a
+//! [`SupplicantTranscript`].
+
+use netdiag_types::error::{Error, Result};
+use netdiag_types::wifi::SupplicantTranscript;
+use std::io::ErrorKind;
+use std::os::unix::net::UnixDatagram;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::{Duration, Instant};
+
+/// Directories wpa_supplicant creates control sockets in.
+const CTRL_DIRS: &[&str] = &["/run/wpa_supplicant", "/var/run/wpa_supplicant"];
+
+/// How long to wait for a command reply.
+const REPLY_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Network block fields describing the 802.1X setup. Secrets are never read.
+const NETWORK_FIELDS: &[&str] = &[
+    "eap",
+    "identity",
+    "anonymous_identity",
+    "phase2",
+    "ca_cert",
+    "domain_suffix_match",
+    "domain_match",
+    "altsubject_match",
+    "subject_match",
+];
+
+/// Events that end a connection attempt.
+const FINAL_EVENTS: &[&str] = &["CTRL-EVENT-CONNECTED", "CTRL-EVENT-SSID-TEMP-DISABLED"];
+
+/// Distinguishes the client sockets of concurrent sessions.
+static SESSION: AtomicU32 = AtomicU32::new(0);
+
+/// A control interface session.
+pub struct WpaCtrl {
+    socket: UnixDatagram,
+    local: PathBuf,
+    interface: String,
+    transcript: SupplicantTranscript,
+}
+
+impl WpaCtrl {
+    /// Connect to the control socket of an interface, if wpa_supplicant
+    /// manages it.
+    pub fn open(interface: &str) -> Result<Option<Self>> {
+        let Some(path) = CTRL_DIRS
+            .iter()
+            .map(|dir| Path::new(dir).join(interface))
+            .find(|path| path.exists())
+        else {
+            return Ok(None);
+        };
+
+        let local = std::env::temp_dir().join(format!(
+            "netdiag_wpa_ctrl_{}-{}",
+            std::process::id(),
+            SESSION.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = std::fs::remove_file(&local);
+        let socket = UnixDatagram::bind(&local)?;
+        let ctrl = Self {
+            socket,
+            local,
+            interface: interface.to_string(),
+            transcript: SupplicantTranscript::new(),
+        };
+        ctrl.socket.connect(&path).map_err(|e| {
+            if e.kind() == ErrorKind::PermissionDenied {
+                Error::permission_denied(
+                    format!("wpa_supplicant control socket {}", path.display()),
+                    "root or the netdev group",
+                )
+            } else {
+                ctrl.error(format!("{}: {}", path.display(), e))
+            }
+        })?;
+        Ok(Some(ctrl))
+    }
+
+    /// Send a command and return its reply. Events received meanwhile are
+    /// recorded.
+    pub fn request(&mut self, command: &str) -> Result<String> {
+        self.socket.send(command.as_bytes())?;
+        let deadline = Instant::now() + REPLY_TIMEOUT;
+        loop {
+            let Some(message) = self.receive(deadline)? else {
+                return Err(Error::timeout(
+                    format!("wpa_supplicant {}", command),
+                    REPLY_TIMEOUT.as_millis() as u64,
+                ));
+            };
+            if message.starts_with('<') {
+                self.transcript.push_event(&message);
+            } else {
+                self.transcript.push_command(command, message.as_str());
+                return Ok(message);
+            }
+        }
+    }
+
+    /// Record events for up to `duration`, stopping early once a connection
+    /// attempt has finished.
+    pub fn listen(&mut self, duration: Duration) -> Result<()> {
+        let deadline = Instant::now() + duration;
+        while let Some(message) = self.receive(deadline)? {
+            self.transcript.push_event(&message);
+            if FINAL_EVENTS.iter().any(|event| message.contains(event)) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish the session, returning what was recorded.
+    pub fn into_transcript(mut self) -> SupplicantTranscript {
+        std::mem::take(&mut self.transcript)
+    }
+
+    /// Next datagram, or `None` once the deadline has passed.
+    fn receive(&self, deadline: Instant) -> Result<Option<String>> {
+        let mut buf = [0u8; 4096];
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            self.socket.set_read_timeout(Some(remaining))?;
+            match self.socket.recv(&mut buf) {
+                Ok(len) => return Ok(Some(String::from_utf8_lossy(&buf[..len]).into_owned())),
+                Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+                Err(e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => return Err(self.error(e.to_string())),
+            }
+        }
+    }
+
+    fn error(&self, message: String) -> Error {
+        Error::Wifi {
+            interface: Some(self.interface.clone()),
+            message: format!("wpa_supplicant: {}", message),
+        }
+    }
+}
+
+impl Drop for WpaCtrl {
+    fn drop(&mut self) {
+        let _ = self.socket.send(b"DETACH");
+        let _ = std::fs::remove_file(&self.local);
+    }
+}
+
+/// Record the supplicant state and network settings of an interface, then
+/// listen for authentication events.
+pub fn record(
+    interface: &str,
+    listen: Duration,
+    reassociate: bool,
+) -> Result<Option<SupplicantTranscript>> {
+    let Some(mut ctrl) = WpaCtrl::open(interface)? else {
+        return Ok(None);
+    };
+
+    let status = ctrl.request("STATUS-VERBOSE")?;
+    if let Some(id) = status.lines().find_map(|line| line.strip_prefix("id=")) {
+        for field in NETWORK_FIELDS {
+            ctrl.request(&format!("GET_NETWORK {} {}", id, field))?;
+        }
+    }
+
+    if !listen.is_zero() {
+        ctrl.request("ATTACH")?;
+        if reassociate {
+            ctrl.request("REASSOCIATE")?;
+        }
+        ctrl.listen(listen)?;
+        ctrl.request("STATUS-VERBOSE")?;
+    }
+
+    Ok(Some(ctrl.into_transcript()))
+}
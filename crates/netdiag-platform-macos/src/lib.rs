@@ -89,7 +89,10 @@ impl CaptureProvider for StubCaptureProvider {
         netdiag_types::system::PrivilegeLevel::Elevated
     }
 
-    fn compile_filter(&self, _expression: &str) -> netdiag_types::error::Result<String> {
-        Ok(String::new())
+    fn compile_filter(&self, expression: &str) -> netdiag_types::error::Result<String> {
+        netdiag_capture::compile_filter(expression).map_err(|e| netdiag_types::Error::Parse {
+            what: "capture filter".to_string(),
+            message: e.to_string(),
+        })
     }
 }
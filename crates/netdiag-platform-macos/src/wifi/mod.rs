@@ -604,6 +604,8 @@ impl WifiProvider for MacosWifiProvider {
                         max_rate: None,
                         beacon_interval: None,
                         capabilities: AccessPointCapabilities::default(),
+                        elements: None,
+                        last_seen: None,
                     });
                 }
             }
@@ -835,6 +837,8 @@ impl MacosWifiProvider {
             max_rate: info.max_rate,
             beacon_interval: None,
             capabilities: AccessPointCapabilities::default(),
+            elements: None,
+            last_seen: None,
         };
 
         let state = match info.state.as_deref() {
@@ -859,9 +863,14 @@ impl MacosWifiProvider {
             tx_rate: info.tx_rate,
             rx_rate: info.tx_rate, // airport doesn't separate TX/RX
             spatial_streams: info.nss,
+            mcs_index: info.mcs,
+            guard_interval: None,
             channel_width: Some(width),
             connected_duration: None,
             last_roam: None,
+            tx_packets: None,
+            tx_retries: None,
+            tx_failed: None,
         }))
     }
 
@@ -924,6 +933,8 @@ impl MacosWifiProvider {
             max_rate: info.tx_rate,
             beacon_interval: None,
             capabilities: AccessPointCapabilities::default(),
+            elements: None,
+            last_seen: None,
         };
 
         let auth_state = match info.security.as_deref() {
@@ -941,9 +952,14 @@ impl MacosWifiProvider {
             tx_rate: info.tx_rate,
             rx_rate: info.tx_rate,
             spatial_streams: None,
+            mcs_index: None,
+            guard_interval: None,
             channel_width: Some(width),
             connected_duration: None,
             last_roam: None,
+            tx_packets: None,
+            tx_retries: None,
+            tx_failed: None,
         }))
     }
 
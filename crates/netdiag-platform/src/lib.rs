@@ -13,7 +13,9 @@ pub mod traits;
 
 mod detection;
 mod factory;
+mod recording;
 
 pub use detection::*;
 pub use factory::*;
+pub use recording::*;
 pub use traits::*;
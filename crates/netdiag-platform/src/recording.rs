@@ -0,0 +1,1066 @@
+//! Recording and replay of [`WifiProvider`] sessions.
+//!
+//! [`RecordingWifiProvider`] wraps a real provider and writes every call and
+//! its result to a file. [`ReplayWifiProvider`] serves such a file back, so
+//! analysis can be run against a session captured on another machine.
+//!
+//! Recordings are JSON Lines: a [`WifiRecordingHeader`] followed by one
+//! [`RecordedCall`] per line, in the order the calls completed. Errors are
+//! stored as a [`RecordedError`] so replay returns the same [`Error`] variant.
+
+use crate::traits::{WifiInterface, WifiProvider};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use netdiag_types::{
+    error::{Error, Result},
+    wifi::{
+        AccessPoint, Channel, ChannelUtilization, SupplicantTranscript, WifiConnection,
+        WifiStandard,
+    },
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::{Duration, Instant};
+use tracing::warn;
+
+/// Result of a recorded call.
+pub type Recorded<T> = std::result::Result<T, RecordedError>;
+
+/// An [`Error`] in a recording, with the fields needed to rebuild it.
+///
+/// I/O and serialization errors keep their message only.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum RecordedError {
+    /// [`Error::Io`]
+    Io {
+        /// Error message
+        message: String,
+    },
+    /// [`Error::NetworkInterface`]
+    NetworkInterface {
+        /// The interface name, if known
+        interface: Option<String>,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::DnsResolution`]
+    DnsResolution {
+        /// The host that failed to resolve
+        host: String,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Ping`]
+    Ping {
+        /// The target address
+        target: IpAddr,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Traceroute`]
+    Traceroute {
+        /// The target address
+        target: IpAddr,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::SpeedTest`]
+    SpeedTest {
+        /// The server, if known
+        server: Option<String>,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Wifi`]
+    Wifi {
+        /// The interface, if known
+        interface: Option<String>,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::PacketCapture`]
+    PacketCapture {
+        /// The interface, if known
+        interface: Option<String>,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::PermissionDenied`]
+    PermissionDenied {
+        /// The operation that was denied
+        operation: String,
+        /// The privilege level required
+        required_privilege: String,
+    },
+    /// [`Error::UnsupportedOnPlatform`]
+    UnsupportedOnPlatform {
+        /// The feature name
+        feature: String,
+        /// The platform
+        platform: String,
+        /// Alternative suggestion, if any
+        alternative: Option<String>,
+    },
+    /// [`Error::Configuration`]
+    Configuration {
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Storage`]
+    Storage {
+        /// Error message
+        message: String,
+    },
+    /// [`Error::ExternalApi`]
+    ExternalApi {
+        /// The service name
+        service: String,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::ReportGeneration`]
+    ReportGeneration {
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Timeout`]
+    Timeout {
+        /// The operation that timed out
+        operation: String,
+        /// Duration in milliseconds
+        duration_ms: u64,
+    },
+    /// [`Error::Parse`]
+    Parse {
+        /// What was being parsed
+        what: String,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::InvalidArgument`]
+    InvalidArgument {
+        /// The argument name
+        argument: String,
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Autofix`]
+    Autofix {
+        /// The fix that failed
+        fix: String,
+        /// Error message
+        message: String,
+        /// Whether rollback is possible
+        rollback_available: bool,
+    },
+    /// [`Error::Serialization`]
+    Serialization {
+        /// Error message
+        message: String,
+    },
+    /// [`Error::Other`]
+    Other {
+        /// Context for the error
+        context: String,
+        /// Error message
+        message: String,
+    },
+}
+
+impl From<&Error> for RecordedError {
+    fn from(error: &Error) -> Self {
+        match error {
+            Error::Io(e) => Self::Io {
+                message: e.to_string(),
+            },
+            Error::NetworkInterface { interface, message } => Self::NetworkInterface {
+                interface: interface.clone(),
+                message: message.clone(),
+            },
+            Error::DnsResolution { host, message } => Self::DnsResolution {
+                host: host.clone(),
+                message: message.clone(),
+            },
+            Error::Ping { target, message } => Self::Ping {
+                target: *target,
+                message: message.clone(),
+            },
+            Error::Traceroute { target, message } => Self::Traceroute {
+                target: *target,
+                message: message.clone(),
+            },
+            Error::SpeedTest { server, message } => Self::SpeedTest {
+                server: server.clone(),
+                message: message.clone(),
+            },
+            Error::Wifi { interface, message } => Self::Wifi {
+                interface: interface.clone(),
+                message: message.clone(),
+            },
+            Error::PacketCapture { interface, message } => Self::PacketCapture {
+                interface: interface.clone(),
+                message: message.clone(),
+            },
+            Error::PermissionDenied {
+                operation,
+                required_privilege,
+            } => Self::PermissionDenied {
+                operation: operation.clone(),
+                required_privilege: required_privilege.clone(),
+            },
+            Error::UnsupportedOnPlatform {
+                feature,
+                platform,
+                alternative,
+            } => Self::UnsupportedOnPlatform {
+                feature: feature.clone(),
+                platform: platform.clone(),
+                alternative: alternative.clone(),
+            },
+            Error::Configuration { message } => Self::Configuration {
+                message: message.clone(),
+            },
+            Error::Storage { message } => Self::Storage {
+                message: message.clone(),
+            },
+            Error::ExternalApi { service, message } => Self::ExternalApi {
+                service: service.clone(),
+                message: message.clone(),
+            },
+            Error::ReportGeneration { message } => Self::ReportGeneration {
+                message: message.clone(),
+            },
+            Error::Timeout {
+                operation,
+                duration_ms,
+            } => Self::Timeout {
+                operation: operation.clone(),
+                duration_ms: *duration_ms,
+            },
+            Error::Parse { what, message } => Self::Parse {
+                what: what.clone(),
+                message: message.clone(),
+            },
+            Error::InvalidArgument { argument, message } => Self::InvalidArgument {
+                argument: argument.clone(),
+                message: message.clone(),
+            },
+            Error::Autofix {
+                fix,
+                message,
+                rollback_available,
+            } => Self::Autofix {
+                fix: fix.clone(),
+                message: message.clone(),
+                rollback_available: *rollback_available,
+            },
+            Error::Serialization(e) => Self::Serialization {
+                message: e.to_string(),
+            },
+            Error::Other { context, message } => Self::Other {
+                context: context.clone(),
+                message: message.clone(),
+            },
+        }
+    }
+}
+
+impl From<RecordedError> for Error {
+    fn from(error: RecordedError) -> Self {
+        match error {
+            RecordedError::Io { message } => Self::Io(io::Error::other(message)),
+            RecordedError::NetworkInterface { interface, message } => {
+                Self::NetworkInterface { interface, message }
+            }
+            RecordedError::DnsResolution { host, message } => Self::DnsResolution { host, message },
+            RecordedError::Ping { target, message } => Self::Ping { target, message },
+            RecordedError::Traceroute { target, message } => Self::Traceroute { target, message },
+            RecordedError::SpeedTest { server, message } => Self::SpeedTest { server, message },
+            RecordedError::Wifi { interface, message } => Self::Wifi { interface, message },
+            RecordedError::PacketCapture { interface, message } => {
+                Self::PacketCapture { interface, message }
+            }
+            RecordedError::PermissionDenied {
+                operation,
+                required_privilege,
+            } => Self::PermissionDenied {
+                operation,
+                required_privilege,
+            },
+            RecordedError::UnsupportedOnPlatform {
+                feature,
+                platform,
+                alternative,
+            } => Self::UnsupportedOnPlatform {
+                feature,
+                platform,
+                alternative,
+            },
+            RecordedError::Configuration { message } => Self::Configuration { message },
+            RecordedError::Storage { message } => Self::Storage { message },
+            RecordedError::ExternalApi { service, message } => {
+                Self::ExternalApi { service, message }
+            }
+            RecordedError::ReportGeneration { message } => Self::ReportGeneration { message },
+            RecordedError::Timeout {
+                operation,
+                duration_ms,
+            } => Self::Timeout {
+                operation,
+                duration_ms,
+            },
+            RecordedError::Parse { what, message } => Self::Parse { what, message },
+            RecordedError::InvalidArgument { argument, message } => {
+                Self::InvalidArgument { argument, message }
+            }
+            RecordedError::Autofix {
+                fix,
+                message,
+                rollback_available,
+            } => Self::Autofix {
+                fix,
+                message,
+                rollback_available,
+            },
+            RecordedError::Serialization { message } => {
+                Self::Serialization(serde::de::Error::custom(message))
+            }
+            RecordedError::Other { context, message } => Self::Other { context, message },
+        }
+    }
+}
+
+/// First line of a recording.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WifiRecordingHeader {
+    /// netdiag version that made the recording
+    pub version: String,
+    /// Operating system the recording was made on
+    pub platform: String,
+    /// When recording started
+    pub started: DateTime<Utc>,
+    /// Result of [`WifiProvider::is_available`]
+    pub available: bool,
+    /// Whether the provider supports 802.1X
+    pub supports_enterprise: bool,
+}
+
+/// A provider call with its arguments and result.
+#[allow(clippy::large_enum_variant)] // one per recorded line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "call", rename_all = "snake_case")]
+pub enum WifiCall {
+    /// [`WifiProvider::list_wifi_interfaces`]
+    ListWifiInterfaces {
+        /// Result
+        result: Recorded<Vec<WifiInterface>>,
+    },
+    /// [`WifiProvider::scan_access_points`]
+    ScanAccessPoints {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Vec<AccessPoint>>,
+    },
+    /// [`WifiProvider::cached_scan_results`]
+    CachedScanResults {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Option<Vec<AccessPoint>>>,
+    },
+    /// [`WifiProvider::get_current_connection`]
+    GetCurrentConnection {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Option<WifiConnection>>,
+    },
+    /// [`WifiProvider::get_signal_strength`]
+    GetSignalStrength {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Option<i32>>,
+    },
+    /// [`WifiProvider::get_noise_level`]
+    GetNoiseLevel {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Option<i32>>,
+    },
+    /// [`WifiProvider::get_channel_utilization`]
+    GetChannelUtilization {
+        /// Channel
+        channel: Channel,
+        /// Result
+        result: Recorded<ChannelUtilization>,
+    },
+    /// [`WifiProvider::analyze_channels`]
+    AnalyzeChannels {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Vec<ChannelUtilization>>,
+    },
+    /// [`WifiProvider::supplicant_transcript`]
+    SupplicantTranscript {
+        /// Interface
+        interface: String,
+        /// Listen time in milliseconds
+        listen_ms: u64,
+        /// Whether reassociation was requested
+        reassociate: bool,
+        /// Result
+        result: Recorded<Option<SupplicantTranscript>>,
+    },
+    /// [`WifiProvider::trigger_scan`]
+    TriggerScan {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<()>,
+    },
+    /// [`WifiProvider::get_supported_standards`]
+    GetSupportedStandards {
+        /// Interface
+        interface: String,
+        /// Result
+        result: Recorded<Vec<WifiStandard>>,
+    },
+}
+
+impl WifiCall {
+    /// Method name and the argument replies are matched on.
+    fn key(&self) -> (&'static str, String) {
+        match self {
+            Self::ListWifiInterfaces { .. } => ("list_wifi_interfaces", String::new()),
+            Self::ScanAccessPoints { interface, .. } => ("scan_access_points", interface.clone()),
+            Self::CachedScanResults { interface, .. } => ("cached_scan_results", interface.clone()),
+            Self::GetCurrentConnection { interface, .. } => {
+                ("get_current_connection", interface.clone())
+            }
+            Self::GetSignalStrength { interface, .. } => ("get_signal_strength", interface.clone()),
+            Self::GetNoiseLevel { interface, .. } => ("get_noise_level", interface.clone()),
+            Self::GetChannelUtilization { channel, .. } => {
+                ("get_channel_utilization", channel_key(*channel))
+            }
+            Self::AnalyzeChannels { interface, .. } => ("analyze_channels", interface.clone()),
+            Self::SupplicantTranscript { interface, .. } => {
+                ("supplicant_transcript", interface.clone())
+            }
+            Self::TriggerScan { interface, .. } => ("trigger_scan", interface.clone()),
+            Self::GetSupportedStandards { interface, .. } => {
+                ("get_supported_standards", interface.clone())
+            }
+        }
+    }
+}
+
+fn channel_key(channel: Channel) -> String {
+    format!("{} {}", channel.number, channel.band)
+}
+
+/// One line of a recording after the header.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedCall {
+    /// Milliseconds from the start of recording to the call
+    pub at_ms: u64,
+    /// How long the call took in milliseconds
+    pub duration_ms: u64,
+    /// Call and result
+    #[serde(flatten)]
+    pub call: WifiCall,
+}
+
+/// A recorded [`WifiProvider`] session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WifiRecording {
+    /// Recording metadata
+    pub header: WifiRecordingHeader,
+    /// Calls in the order they completed
+    pub calls: Vec<RecordedCall>,
+}
+
+impl WifiRecording {
+    /// Loads a recording from a file.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be read or isn't a recording.
+    pub fn load(path: &Path) -> Result<Self> {
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Parses a recording from its JSON Lines text.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error naming the first line that isn't valid.
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty());
+        let (_, first) = lines.next().ok_or_else(|| Error::Parse {
+            what: "WiFi recording".to_string(),
+            message: "recording is empty".to_string(),
+        })?;
+        let header = serde_json::from_str(first).map_err(|e| Error::Parse {
+            what: "WiFi recording".to_string(),
+            message: format!("invalid header: {e}"),
+        })?;
+        let calls = lines
+            .map(|(index, line)| {
+                serde_json::from_str(line).map_err(|e| Error::Parse {
+                    what: "WiFi recording".to_string(),
+                    message: format!("line {}: {e}", index + 1),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { header, calls })
+    }
+}
+
+/// [`WifiProvider`] that records every call made through it to a file.
+pub struct RecordingWifiProvider {
+    inner: Arc<dyn WifiProvider>,
+    started: Instant,
+    writer: Mutex<BufWriter<File>>,
+}
+
+impl RecordingWifiProvider {
+    /// Wraps `inner`, writing the recording to `path`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the file can't be created.
+    pub fn create(inner: Arc<dyn WifiProvider>, path: &Path) -> Result<Self> {
+        let header = WifiRecordingHeader {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            platform: std::env::consts::OS.to_string(),
+            started: Utc::now(),
+            available: inner.is_available(),
+            supports_enterprise: inner.supports_enterprise(),
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_line(&mut writer, &header)?;
+        Ok(Self {
+            inner,
+            started: Instant::now(),
+            writer: Mutex::new(writer),
+        })
+    }
+
+    /// Appends a call; the call itself succeeds even if recording fails.
+    fn record(&self, called: Instant, call: WifiCall) {
+        let entry = RecordedCall {
+            at_ms: millis(called.duration_since(self.started)),
+            duration_ms: millis(called.elapsed()),
+            call,
+        };
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Err(e) = write_line(&mut *writer, &entry) {
+            warn!("Failed to record WiFi call: {}", e);
+        }
+    }
+}
+
+/// Writes one JSON line and flushes, so an interrupted session is still usable.
+fn write_line(writer: &mut impl Write, value: &impl Serialize) -> io::Result<()> {
+    serde_json::to_writer(&mut *writer, value)?;
+    writer.write_all(b"\n")?;
+    writer.flush()
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+fn recorded<T: Clone>(result: &Result<T>) -> Recorded<T> {
+    match result {
+        Ok(value) => Ok(value.clone()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[async_trait]
+impl WifiProvider for RecordingWifiProvider {
+    fn is_available(&self) -> bool {
+        self.inner.is_available()
+    }
+
+    async fn list_wifi_interfaces(&self) -> Result<Vec<WifiInterface>> {
+        let called = Instant::now();
+        let result = self.inner.list_wifi_interfaces().await;
+        self.record(
+            called,
+            WifiCall::ListWifiInterfaces {
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn scan_access_points(&self, interface: &str) -> Result<Vec<AccessPoint>> {
+        let called = Instant::now();
+        let result = self.inner.scan_access_points(interface).await;
+        self.record(
+            called,
+            WifiCall::ScanAccessPoints {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn cached_scan_results(&self, interface: &str) -> Result<Option<Vec<AccessPoint>>> {
+        let called = Instant::now();
+        let result = self.inner.cached_scan_results(interface).await;
+        self.record(
+            called,
+            WifiCall::CachedScanResults {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn get_current_connection(&self, interface: &str) -> Result<Option<WifiConnection>> {
+        let called = Instant::now();
+        let result = self.inner.get_current_connection(interface).await;
+        self.record(
+            called,
+            WifiCall::GetCurrentConnection {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn get_signal_strength(&self, interface: &str) -> Result<Option<i32>> {
+        let called = Instant::now();
+        let result = self.inner.get_signal_strength(interface).await;
+        self.record(
+            called,
+            WifiCall::GetSignalStrength {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn get_noise_level(&self, interface: &str) -> Result<Option<i32>> {
+        let called = Instant::now();
+        let result = self.inner.get_noise_level(interface).await;
+        self.record(
+            called,
+            WifiCall::GetNoiseLevel {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn get_channel_utilization(&self, channel: Channel) -> Result<ChannelUtilization> {
+        let called = Instant::now();
+        let result = self.inner.get_channel_utilization(channel).await;
+        self.record(
+            called,
+            WifiCall::GetChannelUtilization {
+                channel,
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn analyze_channels(&self, interface: &str) -> Result<Vec<ChannelUtilization>> {
+        let called = Instant::now();
+        let result = self.inner.analyze_channels(interface).await;
+        self.record(
+            called,
+            WifiCall::AnalyzeChannels {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    fn supports_enterprise(&self) -> bool {
+        self.inner.supports_enterprise()
+    }
+
+    async fn supplicant_transcript(
+        &self,
+        interface: &str,
+        listen: Duration,
+        reassociate: bool,
+    ) -> Result<Option<SupplicantTranscript>> {
+        let called = Instant::now();
+        let result = self
+            .inner
+            .supplicant_transcript(interface, listen, reassociate)
+            .await;
+        self.record(
+            called,
+            WifiCall::SupplicantTranscript {
+                interface: interface.to_string(),
+                listen_ms: millis(listen),
+                reassociate,
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn trigger_scan(&self, interface: &str) -> Result<()> {
+        let called = Instant::now();
+        let result = self.inner.trigger_scan(interface).await;
+        self.record(
+            called,
+            WifiCall::TriggerScan {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+
+    async fn get_supported_standards(&self, interface: &str) -> Result<Vec<WifiStandard>> {
+        let called = Instant::now();
+        let result = self.inner.get_supported_standards(interface).await;
+        self.record(
+            called,
+            WifiCall::GetSupportedStandards {
+                interface: interface.to_string(),
+                result: recorded(&result),
+            },
+        );
+        result
+    }
+}
+
+/// [`WifiProvider`] that serves the results of a recording.
+///
+/// Each call gets the recorded results for the same method and arguments in
+/// order; once they run out the last one is repeated, so pollers keep seeing
+/// the final state. Calls that were never recorded fail.
+pub struct ReplayWifiProvider {
+    header: WifiRecordingHeader,
+    calls: Mutex<HashMap<(&'static str, String), Replies>>,
+    speed: f64,
+    /// Recording time of the first call
+    first_at_ms: u64,
+    /// When the first call was replayed
+    origin: Mutex<Option<tokio::time::Instant>>,
+}
+
+/// Recorded calls for one method and argument.
+#[derive(Default)]
+struct Replies {
+    pending: VecDeque<RecordedCall>,
+    /// Whether the last call has been served, so further calls are repeats
+    last_served: bool,
+}
+
+impl ReplayWifiProvider {
+    /// Serves `recording` without delays.
+    #[must_use]
+    pub fn new(recording: WifiRecording) -> Self {
+        let first_at_ms = recording.calls.iter().map(|c| c.at_ms).min().unwrap_or(0);
+        let mut calls: HashMap<_, Replies> = HashMap::new();
+        for call in recording.calls {
+            calls
+                .entry(call.call.key())
+                .or_default()
+                .pending
+                .push_back(call);
+        }
+        Self {
+            header: recording.header,
+            calls: Mutex::new(calls),
+            speed: 0.0,
+            first_at_ms,
+            origin: Mutex::new(None),
+        }
+    }
+
+    /// Replays in recorded time, divided by `speed`: a result isn't returned
+    /// before the recorded call completed, counting from the first call.
+    /// Repeats of the last result take as long as the recorded call did.
+    ///
+    /// A speed of zero replays without delays.
+    #[must_use]
+    pub fn with_speed(mut self, speed: f64) -> Self {
+        self.speed = speed.max(0.0);
+        self
+    }
+
+    /// Metadata of the recording being replayed.
+    #[must_use]
+    pub fn header(&self) -> &WifiRecordingHeader {
+        &self.header
+    }
+
+    /// Next recorded call for a method and argument, and whether it is a
+    /// repeat of the last one.
+    fn next(&self, method: &'static str, argument: String) -> Option<(RecordedCall, bool)> {
+        let mut calls = self.calls.lock().unwrap_or_else(PoisonError::into_inner);
+        let replies = calls.get_mut(&(method, argument))?;
+        if replies.pending.len() > 1 {
+            return replies.pending.pop_front().map(|call| (call, false));
+        }
+        let call = replies.pending.front()?.clone();
+        let repeat = std::mem::replace(&mut replies.last_served, true);
+        Some((call, repeat))
+    }
+
+    /// Waits until the recorded call would have completed.
+    async fn wait(&self, call: &RecordedCall, repeat: bool) {
+        if self.speed <= 0.0 {
+            return;
+        }
+        let now = tokio::time::Instant::now();
+        let origin = *self
+            .origin
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get_or_insert(now);
+        let scaled = |ms: u64| {
+            #[allow(clippy::cast_precision_loss)] // milliseconds of a session
+            Duration::from_secs_f64(ms as f64 / 1000.0 / self.speed)
+        };
+        let deadline = if repeat {
+            now + scaled(call.duration_ms)
+        } else {
+            origin + scaled(call.at_ms.saturating_sub(self.first_at_ms) + call.duration_ms)
+        };
+        tokio::time::sleep_until(deadline).await;
+    }
+
+    async fn replay<T>(
+        &self,
+        method: &'static str,
+        argument: &str,
+        extract: impl FnOnce(WifiCall) -> Option<Recorded<T>>,
+    ) -> Result<T> {
+        let not_recorded = || Error::Wifi {
+            interface: None,
+            message: format!("{method}({argument}) is not in the recording"),
+        };
+        let (call, repeat) = self
+            .next(method, argument.to_string())
+            .ok_or_else(not_recorded)?;
+        self.wait(&call, repeat).await;
+        extract(call.call)
+            .ok_or_else(not_recorded)?
+            .map_err(Error::from)
+    }
+}
+
+#[async_trait]
+impl WifiProvider for ReplayWifiProvider {
+    fn is_available(&self) -> bool {
+        self.header.available
+    }
+
+    async fn list_wifi_interfaces(&self) -> Result<Vec<WifiInterface>> {
+        self.replay("list_wifi_interfaces", "", |call| match call {
+            WifiCall::ListWifiInterfaces { result } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn scan_access_points(&self, interface: &str) -> Result<Vec<AccessPoint>> {
+        self.replay("scan_access_points", interface, |call| match call {
+            WifiCall::ScanAccessPoints { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_current_connection(&self, interface: &str) -> Result<Option<WifiConnection>> {
+        self.replay("get_current_connection", interface, |call| match call {
+            WifiCall::GetCurrentConnection { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn cached_scan_results(&self, interface: &str) -> Result<Option<Vec<AccessPoint>>> {
+        self.replay("cached_scan_results", interface, |call| match call {
+            WifiCall::CachedScanResults { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_signal_strength(&self, interface: &str) -> Result<Option<i32>> {
+        self.replay("get_signal_strength", interface, |call| match call {
+            WifiCall::GetSignalStrength { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_noise_level(&self, interface: &str) -> Result<Option<i32>> {
+        self.replay("get_noise_level", interface, |call| match call {
+            WifiCall::GetNoiseLevel { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_channel_utilization(&self, channel: Channel) -> Result<ChannelUtilization> {
+        self.replay(
+            "get_channel_utilization",
+            &channel_key(channel),
+            |call| match call {
+                WifiCall::GetChannelUtilization { result, .. } => Some(result),
+                _ => None,
+            },
+        )
+        .await
+    }
+
+    async fn analyze_channels(&self, interface: &str) -> Result<Vec<ChannelUtilization>> {
+        self.replay("analyze_channels", interface, |call| match call {
+            WifiCall::AnalyzeChannels { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    fn supports_enterprise(&self) -> bool {
+        self.header.supports_enterprise
+    }
+
+    async fn supplicant_transcript(
+        &self,
+        interface: &str,
+        _listen: Duration,
+        _reassociate: bool,
+    ) -> Result<Option<SupplicantTranscript>> {
+        self.replay("supplicant_transcript", interface, |call| match call {
+            WifiCall::SupplicantTranscript { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn trigger_scan(&self, interface: &str) -> Result<()> {
+        self.replay("trigger_scan", interface, |call| match call {
+            WifiCall::TriggerScan { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+
+    async fn get_supported_standards(&self, interface: &str) -> Result<Vec<WifiStandard>> {
+        self.replay("get_supported_standards", interface, |call| match call {
+            WifiCall::GetSupportedStandards { result, .. } => Some(result),
+            _ => None,
+        })
+        .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PlatformProviders;
+
+    #[tokio::test]
+    async fn test_record_and_replay_round_trip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.jsonl");
+
+        let recorder = RecordingWifiProvider::create(PlatformProviders::new().wifi, &path).unwrap();
+        assert!(recorder.list_wifi_interfaces().await.unwrap().is_empty());
+        assert!(recorder.scan_access_points("wlan0").await.is_err());
+        assert!(recorder
+            .get_current_connection("wlan0")
+            .await
+            .unwrap()
+            .is_none());
+        drop(recorder);
+
+        let recording = WifiRecording::parse(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(recording.calls.len(), 3);
+        let replay = ReplayWifiProvider::new(recording);
+        assert!(!replay.is_available());
+        assert!(replay.list_wifi_interfaces().await.unwrap().is_empty());
+        assert!(replay
+            .get_current_connection("wlan0")
+            .await
+            .unwrap()
+            .is_none());
+
+        // The error comes back as the variant that was recorded
+        let error = replay.scan_access_points("wlan0").await.unwrap_err();
+        assert!(error.is_unsupported_platform());
+        assert!(matches!(
+            error,
+            Error::UnsupportedOnPlatform { ref feature, ref platform, .. }
+                if feature == "WiFi scanning" && platform == "stub"
+        ));
+
+        assert!(matches!(
+            replay.scan_access_points("wlan1").await,
+            Err(Error::Wifi { .. })
+        ));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_replay_honours_recorded_gaps() {
+        let recording = WifiRecording::parse(
+            r#"{"version":"0.1.0","platform":"linux","started":"2026-01-01T00:00:00Z","available":true,"supports_enterprise":false}
+{"at_ms":1000,"duration_ms":10,"call":"get_signal_strength","interface":"wlan0","result":{"Ok":-50}}
+{"at_ms":6000,"duration_ms":10,"call":"get_signal_strength","interface":"wlan0","result":{"Ok":-60}}
+{"at_ms":6100,"duration_ms":20,"call":"get_noise_level","interface":"wlan0","result":{"Err":{"kind":"timeout","operation":"survey","duration_ms":20}}}"#,
+        )
+        .unwrap();
+        let replay = ReplayWifiProvider::new(recording).with_speed(2.0);
+        let start = tokio::time::Instant::now();
+        let elapsed = || start.elapsed().as_millis();
+
+        assert_eq!(
+            replay.get_signal_strength("wlan0").await.unwrap(),
+            Some(-50)
+        );
+        assert_eq!(elapsed(), 5);
+        // The five seconds between the calls are replayed at double speed
+        assert_eq!(
+            replay.get_signal_strength("wlan0").await.unwrap(),
+            Some(-60)
+        );
+        assert_eq!(elapsed(), 2505);
+        assert!(replay
+            .get_noise_level("wlan0")
+            .await
+            .unwrap_err()
+            .is_timeout());
+        assert_eq!(elapsed(), 2560);
+        // Repeats of the last result take as long as the call did
+        assert_eq!(
+            replay.get_signal_strength("wlan0").await.unwrap(),
+            Some(-60)
+        );
+        assert_eq!(elapsed(), 2565);
+    }
+}
@@ -3,8 +3,10 @@
 use async_trait::async_trait;
 use netdiag_types::{
     error::Result,
-    wifi::{AccessPoint, Channel, ChannelUtilization, WifiConnection},
+    wifi::{AccessPoint, Channel, ChannelUtilization, SupplicantTranscript, WifiConnection},
 };
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
 
 /// Provider for WiFi operations.
 #[async_trait]
@@ -18,6 +20,14 @@ pub trait WifiProvider: Send + Sync {
     /// Scans for available access points.
     async fn scan_access_points(&self, interface: &str) -> Result<Vec<AccessPoint>>;
 
+    /// Returns the access points found by the last scan without starting a
+    /// new one, so the radio stays on the connected channel.
+    ///
+    /// Returns `None` if the platform only reports results by scanning.
+    async fn cached_scan_results(&self, _interface: &str) -> Result<Option<Vec<AccessPoint>>> {
+        Ok(None)
+    }
+
     /// Gets the current WiFi connection info.
     async fn get_current_connection(&self, interface: &str) -> Result<Option<WifiConnection>>;
 
@@ -36,6 +46,20 @@ pub trait WifiProvider: Send + Sync {
     /// Checks if enterprise (802.1X) authentication is supported.
     fn supports_enterprise(&self) -> bool;
 
+    /// Records the 802.1X supplicant state of an interface, then listens for
+    /// authentication events for `listen`, after asking the supplicant to
+    /// reassociate if `reassociate` is set.
+    ///
+    /// Returns `None` if the platform has no supplicant control interface.
+    async fn supplicant_transcript(
+        &self,
+        _interface: &str,
+        _listen: Duration,
+        _reassociate: bool,
+    ) -> Result<Option<SupplicantTranscript>> {
+        Ok(None)
+    }
+
     /// Triggers an active WiFi scan (may require privileges).
     async fn trigger_scan(&self, interface: &str) -> Result<()>;
 
@@ -47,7 +71,7 @@ pub trait WifiProvider: Send + Sync {
 }
 
 /// WiFi interface information.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct WifiInterface {
     /// Interface name (e.g., "en0", "wlan0")
     pub name: String,
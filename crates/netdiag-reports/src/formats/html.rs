@@ -238,8 +238,13 @@ impl ReportFormatter for HtmlFormatter {
                 if let Some(ref mac) = iface.mac_address {
                     writeln!(
                         output,
-                        "            <p><strong>MAC:</strong> <code>{}</code></p>",
-                        mac
+                        "            <p><strong>MAC:</strong> <code>{}</code>{}</p>",
+                        mac,
+                        iface
+                            .mac_vendor
+                            .as_ref()
+                            .map(|v| format!(" ({})", html_escape(v)))
+                            .unwrap_or_default()
                     )
                     .unwrap();
                 }
@@ -103,7 +103,11 @@ impl ReportFormatter for MarkdownFormatter {
                 writeln!(output, "- **Type:** {}", iface.interface_type).unwrap();
 
                 if let Some(ref mac) = iface.mac_address {
-                    writeln!(output, "- **MAC:** `{}`", mac).unwrap();
+                    match iface.mac_vendor {
+                        Some(ref vendor) => writeln!(output, "- **MAC:** `{}` ({})", mac, vendor),
+                        None => writeln!(output, "- **MAC:** `{}`", mac),
+                    }
+                    .unwrap();
                 }
 
                 if !iface.ipv4_addresses.is_empty() {
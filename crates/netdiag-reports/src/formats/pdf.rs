@@ -158,7 +158,10 @@ impl PdfFormatter {
                     details.push(format!("IPv6: {}", iface.ipv6_addresses.first().unwrap()));
                 }
                 if let Some(ref mac) = iface.mac_address {
-                    details.push(format!("MAC: {}", mac));
+                    match iface.mac_vendor {
+                        Some(ref vendor) => details.push(format!("MAC: {} ({})", mac, vendor)),
+                        None => details.push(format!("MAC: {}", mac)),
+                    }
                 }
                 doc.push(body_text(&format!("  {}", details.join("  |  "))));
                 doc.push(Break::new(0.5));
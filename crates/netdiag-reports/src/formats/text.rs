@@ -116,7 +116,11 @@ impl ReportFormatter for TextFormatter {
                 .unwrap();
 
                 if let Some(ref mac) = iface.mac_address {
-                    writeln!(output, "  MAC: {}", mac).unwrap();
+                    match iface.mac_vendor {
+                        Some(ref vendor) => writeln!(output, "  MAC: {} ({})", mac, vendor),
+                        None => writeln!(output, "  MAC: {}", mac),
+                    }
+                    .unwrap();
                 }
 
                 for ip in &iface.ipv4_addresses {
@@ -81,6 +81,9 @@ pub struct InterfaceSummary {
     pub ipv6_addresses: Vec<String>,
     /// MAC address
     pub mac_address: Option<String>,
+    /// MAC address vendor
+    #[serde(default)]
+    pub mac_vendor: Option<String>,
     /// Is up
     pub is_up: bool,
     /// Is default
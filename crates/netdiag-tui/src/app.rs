@@ -6,10 +6,12 @@ use crate::ui;
 use chrono::{DateTime, Utc};
 use crossterm::event::{KeyCode, KeyEvent};
 use netdiag_connectivity::{DnsResolver, DnsResult, PingConfig, Pinger, Tracer, TracerouteConfig};
+use netdiag_platform::WifiProvider;
 use netdiag_types::diagnostics::{PingStats, TracerouteHop, TracerouteResult};
 use ratatui::prelude::*;
 use std::io;
 use std::net::IpAddr;
+use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::mpsc;
 
@@ -234,6 +236,8 @@ pub struct App {
     pub internet_status: Option<bool>,
     /// DNS status.
     pub dns_status: Option<bool>,
+    /// WiFi provider to use instead of the platform one.
+    wifi_provider: Option<Arc<dyn WifiProvider>>,
 }
 
 impl App {
@@ -267,9 +271,16 @@ impl App {
             gateway_status: None,
             internet_status: None,
             dns_status: None,
+            wifi_provider: None,
         }
     }
 
+    /// Read WiFi state from `provider`, e.g. a recorded session, instead of the adapter.
+    pub fn with_wifi_provider(mut self, provider: Arc<dyn WifiProvider>) -> Self {
+        self.wifi_provider = Some(provider);
+        self
+    }
+
     /// Run the application.
     pub async fn run(
         mut self,
@@ -735,54 +746,31 @@ impl App {
         let tx = self.task_tx.clone();
         self.wifi_running = true;
 
+        if let Some(provider) = self.wifi_provider.clone() {
+            tokio::spawn(async move {
+                let _ = tx.send(match load_wifi_info(provider.as_ref()).await {
+                    Ok(wifi_info) => TaskMessage::WifiUpdate(wifi_info),
+                    Err(e) => TaskMessage::WifiError(e),
+                });
+            });
+            return;
+        }
+
         tokio::spawn(async move {
             // Use platform-specific WiFi provider
             #[cfg(target_os = "macos")]
             {
-                use netdiag_platform::WifiProvider;
                 use netdiag_platform_macos::MacosWifiProvider;
 
                 let provider = MacosWifiProvider::new();
-
-                match provider.list_wifi_interfaces().await {
-                    Ok(interfaces) if !interfaces.is_empty() => {
-                        let iface = &interfaces[0];
-                        let mut wifi_info = WifiInfo {
-                            interface: iface.name.clone(),
-                            powered_on: iface.powered_on,
-                            ..Default::default()
-                        };
-
-                        // Get connection info
-                        if let Ok(Some(conn)) = provider.get_current_connection(&iface.name).await {
-                            wifi_info.ssid = Some(conn.access_point.ssid.as_str().to_string());
-                            wifi_info.bssid = Some(conn.access_point.bssid.to_string());
-                            wifi_info.rssi = Some(conn.access_point.rssi);
-                            wifi_info.noise = conn.access_point.noise;
-                            wifi_info.channel = Some(conn.access_point.channel.number);
-                            wifi_info.band = Some(format!("{:?}", conn.access_point.channel.band));
-                            wifi_info.security = Some(format!("{:?}", conn.access_point.security));
-                            wifi_info.tx_rate = conn.tx_rate;
-                            wifi_info.standard =
-                                Some(format!("{:?}", conn.access_point.wifi_standard));
-                        }
-
-                        let _ = tx.send(TaskMessage::WifiUpdate(wifi_info));
-                    }
-                    Ok(_) => {
-                        let _ = tx.send(TaskMessage::WifiError(
-                            "No WiFi interfaces found".to_string(),
-                        ));
-                    }
-                    Err(e) => {
-                        let _ = tx.send(TaskMessage::WifiError(e.to_string()));
-                    }
-                }
+                let _ = tx.send(match load_wifi_info(&provider).await {
+                    Ok(wifi_info) => TaskMessage::WifiUpdate(wifi_info),
+                    Err(e) => TaskMessage::WifiError(e),
+                });
             }
 
             #[cfg(target_os = "linux")]
             {
-                use netdiag_platform::WifiProvider;
                 use netdiag_platform_linux::LinuxWifiProvider;
 
                 let provider = LinuxWifiProvider::new();
@@ -877,3 +865,34 @@ impl Default for App {
         Self::new()
     }
 }
+
+/// Read the first WiFi interface and its connection from a provider.
+async fn load_wifi_info(provider: &dyn WifiProvider) -> Result<WifiInfo, String> {
+    let interfaces = provider
+        .list_wifi_interfaces()
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(iface) = interfaces.first() else {
+        return Err("No WiFi interfaces found".to_string());
+    };
+    let mut wifi_info = WifiInfo {
+        interface: iface.name.clone(),
+        powered_on: iface.powered_on,
+        ..Default::default()
+    };
+
+    // Get connection info
+    if let Ok(Some(conn)) = provider.get_current_connection(&iface.name).await {
+        wifi_info.ssid = Some(conn.access_point.ssid.as_str().to_string());
+        wifi_info.bssid = Some(conn.access_point.bssid.to_string());
+        wifi_info.rssi = Some(conn.access_point.rssi);
+        wifi_info.noise = conn.access_point.noise;
+        wifi_info.channel = Some(conn.access_point.channel.number);
+        wifi_info.band = Some(format!("{:?}", conn.access_point.channel.band));
+        wifi_info.security = Some(format!("{:?}", conn.access_point.security));
+        wifi_info.tx_rate = conn.tx_rate;
+        wifi_info.standard = Some(format!("{:?}", conn.access_point.wifi_standard));
+    }
+
+    Ok(wifi_info)
+}
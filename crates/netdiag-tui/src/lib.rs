@@ -26,8 +26,10 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
+use netdiag_platform::WifiProvider;
 use ratatui::prelude::*;
 use std::io::{self, stdout};
+use std::sync::Arc;
 
 /// Initialize the terminal for TUI mode.
 pub fn init_terminal() -> TuiResult<Terminal<CrosstermBackend<io::Stdout>>> {
@@ -53,11 +55,19 @@ pub fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<io::Stdout>>) -
 
 /// Run the TUI application.
 pub async fn run() -> TuiResult<()> {
+    run_app(App::new()).await
+}
+
+/// Run the TUI application with WiFi state read from `provider`.
+pub async fn run_with_wifi_provider(provider: Arc<dyn WifiProvider>) -> TuiResult<()> {
+    run_app(App::new().with_wifi_provider(provider)).await
+}
+
+async fn run_app(app: App) -> TuiResult<()> {
     // Initialize terminal
     let mut terminal = init_terminal()?;
 
-    // Create and run app
-    let app = App::new();
+    // Run app
     let result = app.run(&mut terminal).await;
 
     // Restore terminal
@@ -65,16 +65,16 @@ pub struct IpInfo {
 }
 
 /// IP fragment information.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub struct FragmentInfo {
-    /// Fragment offset
+    /// Fragment offset in bytes
     pub offset: u16,
     /// More fragments flag
     pub more_fragments: bool,
-    /// Don't fragment flag
+    /// Don't fragment flag (always false for IPv6)
     pub dont_fragment: bool,
-    /// Fragment ID
-    pub id: u16,
+    /// Fragment ID (16 bits for IPv4, 32 bits for IPv6)
+    pub id: u32,
 }
 
 /// Transport layer information.
@@ -3,9 +3,11 @@
 mod address;
 mod dns;
 mod interface;
+mod oui;
 mod route;
 
 pub use address::*;
 pub use dns::*;
 pub use interface::*;
+pub use oui::*;
 pub use route::*;
@@ -0,0 +1,480 @@
+//! IEEE MAC address block registry (OUI vendor lookup).
+//!
+//! The bundled registry is a curated subset of the IEEE MA-L registry
+//! covering common network equipment and client device vendors; it has no
+//! MA-M or MA-S blocks until it is regenerated with `cargo xtask update-oui`.
+//! The full MA-L, MA-M and MA-S registries
+//! (`oui.csv`, `mam.csv`, `oui36.csv` and `iab.csv` from
+//! <https://standards-oui.ieee.org>) can be loaded on top with
+//! [`OuiDatabase::load`]; copies in [`OuiDatabase::user_dir`] are loaded
+//! automatically on the first lookup.
+
+use super::MacAddress;
+use crate::error::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Bundled registry, in the IEEE CSV format.
+const BUNDLED: &str = include_str!("../../data/oui.csv");
+
+static GLOBAL: OnceLock<OuiDatabase> = OnceLock::new();
+
+/// IEEE registry an assignment comes from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum OuiRegistry {
+    /// MAC Address Block Large (24-bit OUI).
+    MaL,
+    /// MAC Address Block Medium (28-bit prefix).
+    MaM,
+    /// MAC Address Block Small (36-bit prefix), including legacy IABs.
+    MaS,
+}
+
+impl OuiRegistry {
+    /// Registries from the most to the least specific.
+    const BY_SPECIFICITY: [Self; 3] = [Self::MaS, Self::MaM, Self::MaL];
+
+    /// Prefix length in bits.
+    #[must_use]
+    pub const fn prefix_bits(self) -> u32 {
+        match self {
+            Self::MaL => 24,
+            Self::MaM => 28,
+            Self::MaS => 36,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "MA-L" => Some(Self::MaL),
+            "MA-M" => Some(Self::MaM),
+            "MA-S" | "IAB" => Some(Self::MaS),
+            _ => None,
+        }
+    }
+
+    const fn index(self) -> usize {
+        match self {
+            Self::MaL => 0,
+            Self::MaM => 1,
+            Self::MaS => 2,
+        }
+    }
+
+    /// The assigned prefix of a MAC address, as an integer.
+    fn prefix_of(self, octets: [u8; 6]) -> u64 {
+        let value = octets
+            .iter()
+            .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+        value >> (48 - self.prefix_bits())
+    }
+}
+
+impl fmt::Display for OuiRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MaL => write!(f, "MA-L"),
+            Self::MaM => write!(f, "MA-M"),
+            Self::MaS => write!(f, "MA-S"),
+        }
+    }
+}
+
+/// Kind of MAC address, from its I/G and U/L bits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MacAddressKind {
+    /// Globally unique, vendor-assigned address.
+    Universal,
+    /// Locally administered unicast address, as used by MAC randomization
+    /// on phones and laptops, virtual interfaces and hotspots.
+    Randomized,
+    /// Multicast or broadcast address.
+    Multicast,
+}
+
+impl MacAddressKind {
+    /// Classify a MAC address.
+    #[must_use]
+    pub const fn of(octets: [u8; 6]) -> Self {
+        if octets[0] & 0x01 != 0 {
+            Self::Multicast
+        } else if octets[0] & 0x02 != 0 {
+            Self::Randomized
+        } else {
+            Self::Universal
+        }
+    }
+}
+
+impl fmt::Display for MacAddressKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Universal => write!(f, "universal"),
+            Self::Randomized => write!(f, "randomized"),
+            Self::Multicast => write!(f, "multicast"),
+        }
+    }
+}
+
+/// An assigned MAC address block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OuiEntry {
+    /// Registry the block was assigned from.
+    pub registry: OuiRegistry,
+    /// Assigned prefix, right-aligned.
+    pub prefix: u64,
+    /// Organization name.
+    pub organization: String,
+}
+
+impl OuiEntry {
+    /// The assigned prefix as hex digits (e.g. `001B63` or `70B3D5123`).
+    #[must_use]
+    pub fn assignment(&self) -> String {
+        let digits = (self.registry.prefix_bits() / 4) as usize;
+        format!("{:0digits$X}", self.prefix)
+    }
+}
+
+/// MAC address block registry with longest-prefix lookup.
+#[derive(Debug, Clone, Default)]
+pub struct OuiDatabase {
+    entries: [HashMap<u64, String>; 3],
+}
+
+impl OuiDatabase {
+    /// Creates an empty database.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The registry bundled with netdiag.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the bundled registry is malformed.
+    #[must_use]
+    pub fn bundled() -> Self {
+        Self::from_csv(BUNDLED).expect("bundled OUI registry is valid")
+    }
+
+    /// Parses an IEEE registry CSV (`Registry,Assignment,Organization Name,...`).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a row has an unknown registry or a malformed assignment.
+    pub fn from_csv(content: &str) -> Result<Self> {
+        let mut db = Self::new();
+        db.merge_csv(content)?;
+        Ok(db)
+    }
+
+    /// Loads an IEEE registry CSV file, or every `.csv` file in a directory,
+    /// on top of the bundled registry.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a file cannot be read or parsed.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let mut db = Self::bundled();
+        if path.is_dir() {
+            let mut files: Vec<_> = std::fs::read_dir(path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.extension().is_some_and(|ext| ext == "csv"))
+                .collect();
+            files.sort();
+            for file in files {
+                db.merge_file(&file)?;
+            }
+        } else {
+            db.merge_file(path)?;
+        }
+        Ok(db)
+    }
+
+    fn merge_file(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)?;
+        self.merge_csv(&content).map_err(|e| Error::Parse {
+            what: path.display().to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// Adds the rows of an IEEE registry CSV, replacing existing assignments.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a row has an unknown registry or a malformed assignment.
+    pub fn merge_csv(&mut self, content: &str) -> Result<()> {
+        for (number, line) in content.lines().enumerate() {
+            let fields = split_csv_line(line);
+            let (Some(registry), Some(assignment), Some(organization)) =
+                (fields.first(), fields.get(1), fields.get(2))
+            else {
+                continue;
+            };
+            if number == 0 && registry.eq_ignore_ascii_case("Registry") {
+                continue;
+            }
+            // CID assignments are not usable as unicast address prefixes.
+            if registry == "CID" {
+                continue;
+            }
+
+            let parse_error = |message: String| Error::Parse {
+                what: "OUI registry".to_string(),
+                message: format!("line {}: {}", number + 1, message),
+            };
+            let registry = OuiRegistry::from_name(registry)
+                .ok_or_else(|| parse_error(format!("unknown registry {registry}")))?;
+            let digits = (registry.prefix_bits() / 4) as usize;
+            if assignment.len() != digits {
+                return Err(parse_error(format!(
+                    "{registry} assignment {assignment} is not {digits} hex digits"
+                )));
+            }
+            let prefix = u64::from_str_radix(assignment, 16)
+                .map_err(|_| parse_error(format!("invalid assignment {assignment}")))?;
+            self.insert(OuiEntry {
+                registry,
+                prefix,
+                organization: organization.trim().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Adds an assignment, replacing any existing one for the same block.
+    pub fn insert(&mut self, entry: OuiEntry) {
+        self.entries[entry.registry.index()].insert(entry.prefix, entry.organization);
+    }
+
+    /// Number of assignments.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.entries.iter().map(HashMap::len).sum()
+    }
+
+    /// Whether the database has no assignments.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Number of assignments from one registry.
+    #[must_use]
+    pub fn count(&self, registry: OuiRegistry) -> usize {
+        self.entries[registry.index()].len()
+    }
+
+    /// All assignments, ordered by registry and prefix.
+    #[must_use]
+    pub fn entries(&self) -> Vec<OuiEntry> {
+        let mut entries: Vec<_> = [OuiRegistry::MaL, OuiRegistry::MaM, OuiRegistry::MaS]
+            .into_iter()
+            .flat_map(|registry| {
+                self.entries[registry.index()]
+                    .iter()
+                    .map(move |(&prefix, organization)| OuiEntry {
+                        registry,
+                        prefix,
+                        organization: organization.clone(),
+                    })
+            })
+            .collect();
+        entries.sort_by_key(|entry| (entry.registry.index(), entry.prefix));
+        entries
+    }
+
+    /// Writes the database as an IEEE registry CSV that [`OuiDatabase::from_csv`] reads back.
+    #[must_use]
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from("Registry,Assignment,Organization Name,Organization Address\n");
+        for entry in self.entries() {
+            let _ = writeln!(
+                csv,
+                "{},{},\"{}\",",
+                entry.registry,
+                entry.assignment(),
+                entry.organization.replace('"', "\"\"")
+            );
+        }
+        csv
+    }
+
+    /// Finds the most specific block containing a MAC address.
+    #[must_use]
+    pub fn lookup(&self, octets: [u8; 6]) -> Option<OuiEntry> {
+        OuiRegistry::BY_SPECIFICITY.iter().find_map(|&registry| {
+            let prefix = registry.prefix_of(octets);
+            self.entries[registry.index()]
+                .get(&prefix)
+                .map(|organization| OuiEntry {
+                    registry,
+                    prefix,
+                    organization: organization.clone(),
+                })
+        })
+    }
+
+    /// Organization name for a MAC address.
+    #[must_use]
+    pub fn vendor(&self, octets: [u8; 6]) -> Option<&str> {
+        OuiRegistry::BY_SPECIFICITY.iter().find_map(|&registry| {
+            self.entries[registry.index()]
+                .get(&registry.prefix_of(octets))
+                .map(String::as_str)
+        })
+    }
+
+    /// Directory holding user-supplied registry CSVs
+    /// (`$NETDIAG_DATA_DIR/oui`, or `oui` in the platform data directory).
+    #[must_use]
+    pub fn user_dir() -> Option<PathBuf> {
+        let data_dir = match std::env::var_os("NETDIAG_DATA_DIR") {
+            Some(dir) => PathBuf::from(dir),
+            None => dirs::data_dir()?.join("netdiag"),
+        };
+        Some(data_dir.join("oui"))
+    }
+
+    /// The bundled registry merged with the registries in
+    /// [`OuiDatabase::user_dir`], if there are any and they parse.
+    fn with_user_registries() -> Self {
+        let Some(dir) = Self::user_dir().filter(|d| d.is_dir()) else {
+            return Self::bundled();
+        };
+        match Self::load(&dir) {
+            Ok(db) => {
+                tracing::debug!("Loaded {} OUI assignments from {}", db.len(), dir.display());
+                db
+            }
+            Err(e) => {
+                tracing::warn!("Ignoring OUI database in {}: {}", dir.display(), e);
+                Self::bundled()
+            }
+        }
+    }
+
+    /// The process-wide database: the one passed to [`OuiDatabase::install`],
+    /// or the bundled registry with the user's registries, loaded on first use.
+    pub fn global() -> &'static Self {
+        GLOBAL.get_or_init(Self::with_user_registries)
+    }
+
+    /// Installs the process-wide database.
+    ///
+    /// Returns false if a database was already installed or the default one
+    /// has already been loaded.
+    pub fn install(db: Self) -> bool {
+        GLOBAL.set(db).is_ok()
+    }
+}
+
+/// Splits a CSV line, honouring double-quoted fields.
+fn split_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.trim_end_matches('\r').chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            _ => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+impl MacAddress {
+    /// Vendor of this address from the global OUI database.
+    #[must_use]
+    pub fn vendor(&self) -> Option<&'static str> {
+        OuiDatabase::global().vendor(self.octets())
+    }
+
+    /// Kind of address (universal, randomized or multicast).
+    #[must_use]
+    pub const fn kind(&self) -> MacAddressKind {
+        MacAddressKind::of(self.octets())
+    }
+
+    /// Whether this is a locally administered unicast address, such as a
+    /// randomized private address.
+    #[must_use]
+    pub const fn is_randomized(&self) -> bool {
+        matches!(self.kind(), MacAddressKind::Randomized)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const REGISTRY: &str = "\
+Registry,Assignment,Organization Name,Organization Address
+MA-L,70B3D5,IEEE Registration Authority,445 Hoes Lane Piscataway NJ US 08554
+MA-S,70B3D5123,\"Example \"\"Sensors\"\", Inc.\",Somewhere
+MA-M,3C39E72,Example Radio Ltd,Elsewhere
+";
+
+    #[test]
+    fn test_longest_prefix_lookup() {
+        let db = OuiDatabase::from_csv(REGISTRY).unwrap();
+        assert_eq!(db.len(), 3);
+        assert_eq!(db.count(OuiRegistry::MaS), 1);
+
+        let small = [0x70, 0xB3, 0xD5, 0x12, 0x34, 0x56];
+        let entry = db.lookup(small).unwrap();
+        assert_eq!(entry.registry, OuiRegistry::MaS);
+        assert_eq!(entry.assignment(), "70B3D5123");
+        assert_eq!(entry.organization, "Example \"Sensors\", Inc.");
+
+        let other = [0x70, 0xB3, 0xD5, 0x99, 0x00, 0x01];
+        assert_eq!(db.vendor(other), Some("IEEE Registration Authority"));
+
+        let medium = [0x3C, 0x39, 0xE7, 0x2F, 0xFF, 0xFF];
+        assert_eq!(db.vendor(medium), Some("Example Radio Ltd"));
+        assert_eq!(db.vendor([0x3C, 0x39, 0xE7, 0x30, 0, 0]), None);
+
+        let written = db.to_csv();
+        assert!(written.starts_with("Registry,Assignment,"));
+        assert_eq!(OuiDatabase::from_csv(&written).unwrap().entries(), db.entries());
+
+        assert!(OuiDatabase::from_csv("MA-L,12345,Short,\n").is_err());
+        assert!(OuiDatabase::from_csv("XX-L,123456,Unknown,\n").is_err());
+    }
+
+    #[test]
+    fn test_bundled_and_kind() {
+        let db = OuiDatabase::bundled();
+        assert!(db.len() > 100);
+        assert_eq!(
+            db.vendor([0xB8, 0x27, 0xEB, 1, 2, 3]),
+            Some("Raspberry Pi Foundation")
+        );
+
+        let universal: MacAddress = "00:0C:29:12:34:56".parse().unwrap();
+        assert_eq!(universal.kind(), MacAddressKind::Universal);
+        assert_eq!(universal.vendor(), Some("VMware, Inc."));
+
+        let private: MacAddress = "DA:A1:19:00:00:01".parse().unwrap();
+        assert!(private.is_randomized());
+        assert_eq!(private.vendor(), None);
+
+        assert_eq!(MacAddress::broadcast().kind(), MacAddressKind::Multicast);
+    }
+}
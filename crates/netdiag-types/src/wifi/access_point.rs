@@ -1,6 +1,6 @@
 //! WiFi access point types.
 
-use super::{Channel, SecurityType, WifiStandard};
+use super::{Channel, InformationElements, SecurityType, WifiStandard};
 use serde::{Deserialize, Serialize};
 
 /// Represents a WiFi access point.
@@ -38,6 +38,12 @@ pub struct AccessPoint {
     pub beacon_interval: Option<u16>,
     /// Capabilities
     pub capabilities: AccessPointCapabilities,
+    /// Decoded information elements (if the platform exposes them)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub elements: Option<InformationElements>,
+    /// When the access point was last heard (if the platform reports it)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_seen: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 impl AccessPoint {
@@ -59,6 +65,33 @@ impl AccessPoint {
     pub fn calculate_snr(&self) -> Option<i32> {
         self.snr.or_else(|| self.noise.map(|n| self.rssi - n))
     }
+
+    /// A WPA2 802.11ac access point on channel 36 at -60 dBm with every
+    /// optional field unset, for tests to adjust with struct update syntax.
+    #[cfg(any(test, feature = "test-util"))]
+    #[must_use]
+    pub fn test_default() -> Self {
+        Self {
+            ssid: Ssid::new("test"),
+            bssid: Bssid::new([0x02, 0, 0, 0, 0, 1]),
+            rssi: -60,
+            signal_quality: 60,
+            channel: Channel::from_number(36, super::WifiBand::Band5GHz),
+            security: SecurityType::wpa2_personal(),
+            wifi_standard: WifiStandard::Dot11ac,
+            is_hidden: false,
+            is_connected: false,
+            noise: None,
+            snr: None,
+            country_code: None,
+            supported_rates: Vec::new(),
+            max_rate: None,
+            beacon_interval: None,
+            capabilities: AccessPointCapabilities::default(),
+            elements: None,
+            last_seen: None,
+        }
+    }
 }
 
 /// SSID (Service Set Identifier).
@@ -129,6 +162,19 @@ impl Bssid {
     pub const fn octets(&self) -> [u8; 6] {
         self.0
     }
+
+    /// Returns the vendor from the global OUI database.
+    #[must_use]
+    pub fn vendor(&self) -> Option<&'static str> {
+        crate::network::OuiDatabase::global().vendor(self.0)
+    }
+
+    /// Returns true if the BSSID is locally administered (randomized
+    /// hotspot or virtual BSSID).
+    #[must_use]
+    pub const fn is_locally_administered(&self) -> bool {
+        self.0[0] & 0x02 != 0
+    }
 }
 
 impl std::fmt::Display for Bssid {
@@ -189,12 +235,40 @@ pub struct WifiConnection {
     pub rx_rate: Option<f32>,
     /// Number of spatial streams (MIMO)
     pub spatial_streams: Option<u8>,
+    /// MCS index of the transmit rate
+    #[serde(default)]
+    pub mcs_index: Option<u8>,
+    /// Guard interval of the transmit rate
+    #[serde(default)]
+    pub guard_interval: Option<super::GuardInterval>,
     /// Channel width in use
     pub channel_width: Option<super::ChannelWidth>,
     /// Connection duration
     pub connected_duration: Option<std::time::Duration>,
     /// Last roamed time (if applicable)
     pub last_roam: Option<chrono::DateTime<chrono::Utc>>,
+    /// Packets sent since association
+    #[serde(default)]
+    pub tx_packets: Option<u32>,
+    /// Transmit retries since association
+    #[serde(default)]
+    pub tx_retries: Option<u32>,
+    /// Packets dropped after all retries since association
+    #[serde(default)]
+    pub tx_failed: Option<u32>,
+}
+
+impl WifiConnection {
+    /// Transmit retries as a percentage of packets sent.
+    #[must_use]
+    pub fn tx_retry_percent(&self) -> Option<f64> {
+        match (self.tx_retries, self.tx_packets) {
+            (Some(retries), Some(packets)) if packets > 0 => {
+                Some(f64::from(retries) * 100.0 / f64::from(packets))
+            }
+            _ => None,
+        }
+    }
 }
 
 /// WiFi connection state.
@@ -0,0 +1,210 @@
+//! Airtime and frame statistics measured from monitor-mode captures.
+
+use super::access_point::Bssid;
+use super::channel::Channel;
+use crate::network::MacAddress;
+use serde::{Deserialize, Serialize};
+use strum::Display;
+
+/// Management frame that tears down an association.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+pub enum DisconnectFrame {
+    /// Deauthentication
+    Deauthentication,
+    /// Disassociation
+    Disassociation,
+}
+
+/// Frames of one BSS seen in a capture.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BssActivity {
+    /// BSSID
+    pub bssid: Bssid,
+    /// Network name from beacons or probe responses
+    pub ssid: Option<String>,
+    /// Channel the BSS was heard on
+    pub channel: Option<Channel>,
+    /// All frames attributed to the BSS
+    pub frames: u64,
+    /// Management frames
+    pub management_frames: u64,
+    /// Control frames
+    pub control_frames: u64,
+    /// Data frames
+    pub data_frames: u64,
+    /// Frames that can be retransmitted (unicast management and data)
+    pub retry_eligible_frames: u64,
+    /// Frames with the retry flag set
+    pub retries: u64,
+    /// Bytes on air
+    pub bytes: u64,
+    /// Estimated airtime in microseconds
+    pub airtime_us: u64,
+    /// Beacons received
+    pub beacons: u64,
+    /// Beacon interval the AP advertises, in time units (1.024 ms)
+    pub beacon_interval_tu: Option<u16>,
+    /// Median measured gap between beacons in milliseconds
+    pub measured_beacon_interval_ms: Option<f64>,
+    /// Beacons expected from the advertised interval but not received
+    pub missed_beacons: u64,
+    /// Stations seen sending or receiving data
+    pub stations: usize,
+    /// Average signal in dBm
+    pub signal_dbm: Option<i32>,
+}
+
+impl BssActivity {
+    /// Creates an empty record for a BSSID.
+    #[must_use]
+    pub fn new(bssid: Bssid) -> Self {
+        Self {
+            bssid,
+            ssid: None,
+            channel: None,
+            frames: 0,
+            management_frames: 0,
+            control_frames: 0,
+            data_frames: 0,
+            retry_eligible_frames: 0,
+            retries: 0,
+            bytes: 0,
+            airtime_us: 0,
+            beacons: 0,
+            beacon_interval_tu: None,
+            measured_beacon_interval_ms: None,
+            missed_beacons: 0,
+            stations: 0,
+            signal_dbm: None,
+        }
+    }
+
+    /// Share of retransmittable frames sent more than once, in percent.
+    #[must_use]
+    pub fn retry_percent(&self) -> f64 {
+        percent(self.retries, self.retry_eligible_frames)
+    }
+
+    /// Share of expected beacons that were not received, in percent.
+    #[must_use]
+    pub fn beacon_loss_percent(&self) -> f64 {
+        percent(self.missed_beacons, self.beacons + self.missed_beacons)
+    }
+}
+
+/// A burst of deauthentication or disassociation frames.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DisconnectBurst {
+    /// Frame type
+    pub kind: DisconnectFrame,
+    /// Transmitter address
+    pub transmitter: MacAddress,
+    /// BSS the frames claim to come from
+    pub bssid: Bssid,
+    /// Frames in the burst
+    pub frames: u64,
+    /// Frames sent to the broadcast address
+    pub broadcast_frames: u64,
+    /// Distinct receivers
+    pub targets: usize,
+    /// Reason codes, most frequent first
+    pub reason_codes: Vec<u16>,
+    /// First frame
+    pub start: chrono::DateTime<chrono::Utc>,
+    /// Last frame
+    pub end: chrono::DateTime<chrono::Utc>,
+}
+
+/// Probe requests seen in a capture.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProbeActivity {
+    /// Probe requests
+    pub requests: u64,
+    /// Requests for any network (empty SSID)
+    pub wildcard_requests: u64,
+    /// Distinct transmitting addresses
+    pub clients: usize,
+    /// Transmitting addresses that are randomized (locally administered)
+    pub randomized_clients: usize,
+    /// Networks asked for by name, with request counts, most requested first
+    pub directed_ssids: Vec<(String, u64)>,
+    /// Probe responses
+    pub responses: u64,
+}
+
+/// Frame statistics of a monitor-mode capture.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AirtimeReport {
+    /// Time between the first and last frame in seconds
+    pub duration_secs: f64,
+    /// Frames decoded
+    pub frames: u64,
+    /// Frames with a bad FCS (not decoded further)
+    pub fcs_errors: u64,
+    /// Frames without rate information (no airtime estimate)
+    pub frames_without_rate: u64,
+    /// Channel most frames were captured on
+    pub channel: Option<Channel>,
+    /// Estimated airtime of all frames in microseconds
+    pub airtime_us: u64,
+    /// Per-BSS statistics, busiest first
+    pub bss: Vec<BssActivity>,
+    /// Deauthentication and disassociation bursts
+    pub disconnect_bursts: Vec<DisconnectBurst>,
+    /// Deauthentication and disassociation frames
+    pub disconnect_frames: u64,
+    /// Probe request activity
+    pub probes: ProbeActivity,
+}
+
+impl AirtimeReport {
+    /// Share of the capture time the medium was busy, in percent.
+    #[must_use]
+    pub fn busy_percent(&self) -> f64 {
+        if self.duration_secs <= 0.0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)] // microseconds of a capture
+        let airtime = self.airtime_us as f64;
+        (airtime / (self.duration_secs * 10_000.0)).min(100.0)
+    }
+
+    /// Share of the capture time a BSS used, in percent.
+    #[must_use]
+    pub fn airtime_percent(&self, bss: &BssActivity) -> f64 {
+        if self.duration_secs <= 0.0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let airtime = bss.airtime_us as f64;
+        (airtime / (self.duration_secs * 10_000.0)).min(100.0)
+    }
+
+    /// Frames per second of a BSS.
+    #[must_use]
+    pub fn frame_rate(&self, bss: &BssActivity) -> f64 {
+        if self.duration_secs <= 0.0 {
+            return 0.0;
+        }
+        #[allow(clippy::cast_precision_loss)]
+        let frames = bss.frames as f64;
+        frames / self.duration_secs
+    }
+
+    /// Statistics of one BSS.
+    #[must_use]
+    pub fn find(&self, bssid: &Bssid) -> Option<&BssActivity> {
+        self.bss.iter().find(|b| &b.bssid == bssid)
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // frame counts
+fn percent(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
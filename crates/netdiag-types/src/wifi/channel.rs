@@ -31,7 +31,7 @@ impl Channel {
                     5000 + (number as u32 * 5)
                 } else if number >= 100 && number <= 144 {
                     5000 + (number as u32 * 5)
-                } else if number >= 149 && number <= 165 {
+                } else if (149..=177).contains(&number) {
                     5000 + (number as u32 * 5)
                 } else {
                     5000
@@ -76,16 +76,97 @@ impl Channel {
         }
     }
 
+    /// Creates a bonded channel of the given width around a primary channel.
+    ///
+    /// Returns `None` if no channel of that width contains the primary. For
+    /// overlapping 320 MHz channels the lower one is used.
+    #[must_use]
+    pub fn bonded(primary: u8, band: WifiBand, width: ChannelWidth) -> Option<Self> {
+        let block = bonding_blocks(band, width)
+            .into_iter()
+            .find(|block| block.contains(&primary))?;
+        Some(Self::from_block(primary, band, width, &block))
+    }
+
+    /// Creates a bonded channel from its 20 MHz channel numbers.
+    pub(super) fn from_block(
+        primary: u8,
+        band: WifiBand,
+        width: ChannelWidth,
+        block: &[u8],
+    ) -> Self {
+        let mut channel = Self::from_number(primary, band);
+        channel.width = width;
+        if block.len() > 1 {
+            let first = Self::from_number(block[0], band).frequency;
+            let last = Self::from_number(block[block.len() - 1], band).frequency;
+            channel.center_frequency = Some((first + last) / 2);
+            // The secondary 20 MHz channel pairs with the primary
+            let index = block.iter().position(|&n| n == primary).unwrap_or(0);
+            channel.secondary_position = Some(if index % 2 == 0 {
+                SecondaryChannelPosition::Above
+            } else {
+                SecondaryChannelPosition::Below
+            });
+        }
+        channel
+    }
+
+    /// Returns the 20 MHz channel numbers covered by this (possibly bonded) channel.
+    #[must_use]
+    pub fn subchannels(&self) -> Vec<u8> {
+        let width = self.width.mhz();
+        if width <= 20 {
+            return vec![self.number];
+        }
+
+        if self.band == WifiBand::Band2_4GHz {
+            // 40 MHz in 2.4 GHz pairs the primary with a channel four above or below
+            return match self.secondary_position {
+                Some(SecondaryChannelPosition::Above) => {
+                    vec![self.number, self.number.saturating_add(4)]
+                }
+                Some(SecondaryChannelPosition::Below) => {
+                    vec![self.number.saturating_sub(4), self.number]
+                }
+                _ => vec![self.number],
+            };
+        }
+
+        let Some(center) = self
+            .center_frequency
+            .map(|freq| Self::from_frequency(freq).number)
+        else {
+            return vec![self.number];
+        };
+        // 20 MHz channels are numbered in steps of 4 around the center
+        let half_span = u8::try_from(width / 10 - 2).unwrap_or(0);
+        (center.saturating_sub(half_span)..=center.saturating_add(half_span))
+            .step_by(4)
+            .collect()
+    }
+
     /// Returns true if this is a DFS (Dynamic Frequency Selection) channel.
+    ///
+    /// Channels 52-64 and 100-144 require DFS wherever they are allowed; use
+    /// [`RegulatoryDomain::is_dfs`] for bonded channels and country rules.
     #[must_use]
     pub fn is_dfs(&self) -> bool {
         if self.band != WifiBand::Band5GHz {
             return false;
         }
-        // DFS channels: 52-64, 100-144 in the US
         (self.number >= 52 && self.number <= 64) || (self.number >= 100 && self.number <= 144)
     }
 
+    /// Returns true if this is a 6 GHz preferred scanning channel (PSC).
+    ///
+    /// Clients scanning 6 GHz only probe every fourth 20 MHz channel (5, 21,
+    /// 37, ...), so APs without a 2.4/5 GHz radio should use one of them.
+    #[must_use]
+    pub fn is_psc(&self) -> bool {
+        self.band == WifiBand::Band6GHz && self.number % 16 == 5 && self.number <= 229
+    }
+
     /// Returns the list of overlapping channels in 2.4GHz band.
     #[must_use]
     pub fn overlapping_channels(&self) -> Vec<u8> {
@@ -108,6 +189,55 @@ impl std::fmt::Display for Channel {
     }
 }
 
+/// Groups of 20 MHz channel numbers that can be bonded into one channel of
+/// the given width, in ascending order.
+///
+/// 6 GHz 320 MHz channels come in two overlapping sets (320-1 and 320-2);
+/// both are returned.
+#[must_use]
+pub fn bonding_blocks(band: WifiBand, width: ChannelWidth) -> Vec<Vec<u8>> {
+    // Number of 20 MHz channels in a block
+    let size: u8 = match width {
+        ChannelWidth::Mhz20 => 1,
+        ChannelWidth::Mhz40 => 2,
+        ChannelWidth::Mhz80 => 4,
+        ChannelWidth::Mhz160 => 8,
+        ChannelWidth::Mhz320 => 16,
+    };
+    let block = |start: u8| (0..size).map(|k| start + 4 * k).collect::<Vec<u8>>();
+    match band {
+        WifiBand::Band2_4GHz => match width {
+            ChannelWidth::Mhz20 => (1..=14).map(|n| vec![n]).collect(),
+            ChannelWidth::Mhz40 => (1..=9).map(|n| vec![n, n + 4]).collect(),
+            _ => Vec::new(),
+        },
+        WifiBand::Band5GHz => {
+            let starts: &[u8] = match width {
+                ChannelWidth::Mhz20 => &[
+                    36, 40, 44, 48, 52, 56, 60, 64, 100, 104, 108, 112, 116, 120, 124, 128, 132,
+                    136, 140, 144, 149, 153, 157, 161, 165, 169, 173, 177,
+                ],
+                ChannelWidth::Mhz40 => &[
+                    36, 44, 52, 60, 100, 108, 116, 124, 132, 140, 149, 157, 165, 173,
+                ],
+                ChannelWidth::Mhz80 => &[36, 52, 100, 116, 132, 149, 165],
+                ChannelWidth::Mhz160 => &[36, 100, 149],
+                ChannelWidth::Mhz320 => &[],
+            };
+            starts.iter().map(|&start| block(start)).collect()
+        }
+        WifiBand::Band6GHz => {
+            // 20 MHz channels 1, 5, ..., 233; wider channels are aligned groups
+            let starts: Vec<u8> = match width {
+                ChannelWidth::Mhz20 => (0..59).map(|k| 1 + 4 * k).collect(),
+                ChannelWidth::Mhz320 => vec![1, 33, 65, 97, 129, 161],
+                _ => (0..58 / size).map(|k| 1 + 4 * size * k).collect(),
+            };
+            starts.into_iter().map(block).collect()
+        }
+    }
+}
+
 /// WiFi frequency band.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString)]
 #[serde(rename_all = "lowercase")]
@@ -162,6 +292,19 @@ impl ChannelWidth {
             Self::Mhz320 => 320,
         }
     }
+
+    /// Returns the channel width for a width in MHz.
+    #[must_use]
+    pub const fn from_mhz(mhz: u32) -> Option<Self> {
+        match mhz {
+            20 => Some(Self::Mhz20),
+            40 => Some(Self::Mhz40),
+            80 => Some(Self::Mhz80),
+            160 => Some(Self::Mhz160),
+            320 => Some(Self::Mhz320),
+            _ => None,
+        }
+    }
 }
 
 /// Secondary channel position for bonded channels.
@@ -237,6 +380,37 @@ impl WifiStandard {
     }
 }
 
+/// OFDM guard interval between symbols.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString)]
+#[serde(rename_all = "lowercase")]
+pub enum GuardInterval {
+    /// 0.4 µs short guard interval (802.11n/ac)
+    #[strum(serialize = "0.4us")]
+    Ns400,
+    /// 0.8 µs guard interval
+    #[strum(serialize = "0.8us")]
+    Ns800,
+    /// 1.6 µs guard interval (802.11ax/be)
+    #[strum(serialize = "1.6us")]
+    Ns1600,
+    /// 3.2 µs guard interval (802.11ax/be)
+    #[strum(serialize = "3.2us")]
+    Ns3200,
+}
+
+impl GuardInterval {
+    /// Returns the guard interval in nanoseconds.
+    #[must_use]
+    pub const fn nanos(&self) -> u32 {
+        match self {
+            Self::Ns400 => 400,
+            Self::Ns800 => 800,
+            Self::Ns1600 => 1600,
+            Self::Ns3200 => 3200,
+        }
+    }
+}
+
 /// Channel utilization information.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelUtilization {
@@ -0,0 +1,509 @@
+//! 802.11 information element types.
+//!
+//! These hold the decoded contents of the information elements an access
+//! point advertises in its beacons and probe responses.
+
+use super::{ChannelWidth, SecondaryChannelPosition, WifiEncryption, WifiStandard};
+use serde::{Deserialize, Serialize};
+
+/// Decoded information elements of a BSS.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InformationElements {
+    /// SSID as advertised (may be empty or zeroed for hidden networks)
+    pub ssid: Option<String>,
+    /// Supported and extended rates in Mbps
+    pub supported_rates: Vec<f32>,
+    /// Basic (mandatory) rates in Mbps
+    pub basic_rates: Vec<f32>,
+    /// Country and regulatory information
+    pub country: Option<CountryInfo>,
+    /// BSS Load (station count and channel utilization)
+    pub bss_load: Option<BssLoad>,
+    /// RSN (WPA2/WPA3) element
+    pub rsn: Option<RsnInfo>,
+    /// Legacy WPA vendor element
+    pub wpa: Option<RsnInfo>,
+    /// HT (802.11n) capabilities
+    pub ht_capabilities: Option<HtCapabilities>,
+    /// HT operation
+    pub ht_operation: Option<HtOperation>,
+    /// VHT (802.11ac) capabilities
+    pub vht_capabilities: Option<VhtCapabilities>,
+    /// VHT operation
+    pub vht_operation: Option<VhtOperation>,
+    /// HE (802.11ax) capabilities
+    pub he_capabilities: Option<HeCapabilities>,
+    /// HE operation
+    pub he_operation: Option<HeOperation>,
+    /// EHT (802.11be) capabilities
+    pub eht_capabilities: Option<EhtCapabilities>,
+    /// Mobility domain (802.11r)
+    pub mobility_domain: Option<MobilityDomain>,
+    /// Radio measurement capabilities (802.11k)
+    pub rm_capabilities: Option<RmCapabilities>,
+    /// Extended capabilities
+    pub extended_capabilities: Option<ExtendedCapabilities>,
+    /// WMM parameters advertised
+    pub wmm: bool,
+    /// WPS enabled
+    pub wps: bool,
+    /// OUIs of other vendor-specific elements
+    pub vendor_ouis: Vec<[u8; 3]>,
+}
+
+impl InformationElements {
+    /// Returns the newest 802.11 standard the BSS advertises, if any beyond legacy.
+    #[must_use]
+    pub fn wifi_standard(&self) -> Option<WifiStandard> {
+        if self.eht_capabilities.is_some() {
+            Some(WifiStandard::Dot11be)
+        } else if self.he_capabilities.is_some() {
+            Some(WifiStandard::Dot11ax)
+        } else if self.vht_capabilities.is_some() {
+            Some(WifiStandard::Dot11ac)
+        } else if self.ht_capabilities.is_some() {
+            Some(WifiStandard::Dot11n)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the operating channel width and the center channel number.
+    ///
+    /// The 6 GHz operation info takes precedence, then VHT and HT operation.
+    #[must_use]
+    pub fn operating_width(&self) -> Option<(ChannelWidth, u8)> {
+        if let Some(six_ghz) = self
+            .he_operation
+            .as_ref()
+            .and_then(|op| op.six_ghz.as_ref())
+        {
+            return Some(six_ghz.width());
+        }
+        if let Some((width, center)) = self.vht_operation.as_ref().and_then(VhtOperation::width) {
+            return Some((width, center));
+        }
+        let ht = self.ht_operation.as_ref()?;
+        match (ht.sta_channel_width, ht.secondary_channel) {
+            (true, SecondaryChannelPosition::Above) => {
+                Some((ChannelWidth::Mhz40, ht.primary_channel.saturating_add(2)))
+            }
+            (true, SecondaryChannelPosition::Below) => {
+                Some((ChannelWidth::Mhz40, ht.primary_channel.saturating_sub(2)))
+            }
+            _ => Some((ChannelWidth::Mhz20, ht.primary_channel)),
+        }
+    }
+
+    /// Returns the maximum number of spatial streams advertised.
+    #[must_use]
+    pub fn max_spatial_streams(&self) -> Option<u8> {
+        [
+            self.he_capabilities.as_ref().map(|c| c.max_spatial_streams),
+            self.vht_capabilities
+                .as_ref()
+                .map(|c| c.max_spatial_streams),
+            self.ht_capabilities.as_ref().map(|c| c.max_spatial_streams),
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+    }
+
+    /// Returns true if 802.11k neighbor reports are supported.
+    #[must_use]
+    pub fn supports_dot11k(&self) -> bool {
+        self.rm_capabilities
+            .as_ref()
+            .is_some_and(|rm| rm.neighbor_report)
+    }
+
+    /// Returns true if 802.11r fast BSS transition is supported.
+    #[must_use]
+    pub fn supports_dot11r(&self) -> bool {
+        self.mobility_domain.is_some()
+            && self.rsn.as_ref().map_or(true, |rsn| {
+                rsn.akm_suites.iter().any(AkmSuite::is_fast_transition)
+            })
+    }
+
+    /// Returns true if 802.11v BSS transition management is supported.
+    #[must_use]
+    pub fn supports_dot11v(&self) -> bool {
+        self.extended_capabilities
+            .as_ref()
+            .is_some_and(|ext| ext.bss_transition)
+    }
+}
+
+/// Country element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountryInfo {
+    /// ISO 3166-1 alpha-2 country code
+    pub code: String,
+    /// Environment: 'I' indoor, 'O' outdoor, ' ' any
+    pub environment: char,
+    /// Channel ranges and their maximum transmit power
+    pub channels: Vec<CountryChannels>,
+}
+
+/// A subband triplet from the country element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CountryChannels {
+    /// First channel number
+    pub first_channel: u8,
+    /// Number of channels
+    pub count: u8,
+    /// Maximum transmit power in dBm
+    pub max_power_dbm: i8,
+}
+
+/// BSS Load element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BssLoad {
+    /// Number of associated stations
+    pub station_count: u16,
+    /// Channel utilization as seen by the AP (0-255)
+    pub channel_utilization: u8,
+    /// Available admission capacity in units of 32 µs/s
+    pub available_admission_capacity: u16,
+}
+
+impl BssLoad {
+    /// Returns the channel utilization as a percentage.
+    #[must_use]
+    pub fn utilization_percent(&self) -> f32 {
+        f32::from(self.channel_utilization) * 100.0 / 255.0
+    }
+}
+
+/// RSN or WPA element.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RsnInfo {
+    /// Element version
+    pub version: u16,
+    /// Group (broadcast) cipher
+    pub group_cipher: Option<CipherSuite>,
+    /// Pairwise (unicast) ciphers
+    pub pairwise_ciphers: Vec<CipherSuite>,
+    /// Authentication and key management suites
+    pub akm_suites: Vec<AkmSuite>,
+    /// Management frame protection capable
+    pub mfp_capable: bool,
+    /// Management frame protection required
+    pub mfp_required: bool,
+    /// Group management cipher (for PMF)
+    pub group_management_cipher: Option<CipherSuite>,
+}
+
+impl RsnInfo {
+    /// Returns true if any AKM suite matches.
+    #[must_use]
+    pub fn has_akm(&self, akm: AkmSuite) -> bool {
+        self.akm_suites.contains(&akm)
+    }
+
+    /// Returns true if TKIP is offered as a pairwise or group cipher.
+    #[must_use]
+    pub fn uses_tkip(&self) -> bool {
+        self.group_cipher == Some(CipherSuite::Tkip)
+            || self.pairwise_ciphers.contains(&CipherSuite::Tkip)
+    }
+
+    /// Returns true if the BSS offers both SAE and PSK (WPA3 transition mode).
+    #[must_use]
+    pub fn is_wpa3_transition(&self) -> bool {
+        self.akm_suites.iter().any(AkmSuite::is_sae) && self.akm_suites.iter().any(AkmSuite::is_psk)
+    }
+}
+
+/// Cipher suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CipherSuite {
+    /// Use the group cipher
+    UseGroup,
+    /// WEP-40
+    Wep40,
+    /// TKIP
+    Tkip,
+    /// CCMP-128 (AES)
+    Ccmp,
+    /// WEP-104
+    Wep104,
+    /// BIP-CMAC-128
+    BipCmac128,
+    /// GCMP-128
+    Gcmp,
+    /// GCMP-256
+    Gcmp256,
+    /// CCMP-256
+    Ccmp256,
+    /// BIP-GMAC-128
+    BipGmac128,
+    /// BIP-GMAC-256
+    BipGmac256,
+    /// BIP-CMAC-256
+    BipCmac256,
+    /// Unknown suite selector
+    Other(u32),
+}
+
+impl CipherSuite {
+    /// Returns the matching encryption type.
+    #[must_use]
+    pub fn encryption(&self) -> WifiEncryption {
+        match self {
+            Self::Wep40 | Self::Wep104 => WifiEncryption::Wep,
+            Self::Tkip => WifiEncryption::Tkip,
+            Self::Ccmp | Self::Ccmp256 => WifiEncryption::Ccmp,
+            Self::Gcmp => WifiEncryption::Gcmp,
+            Self::Gcmp256 => WifiEncryption::Gcmp256,
+            Self::BipCmac128 | Self::BipGmac128 | Self::BipGmac256 | Self::BipCmac256 => {
+                WifiEncryption::Bip
+            }
+            Self::UseGroup | Self::Other(_) => WifiEncryption::None,
+        }
+    }
+}
+
+/// Authentication and key management suite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AkmSuite {
+    /// 802.1X (EAP)
+    Ieee8021x,
+    /// Pre-shared key
+    Psk,
+    /// FT over 802.1X
+    FtIeee8021x,
+    /// FT with PSK
+    FtPsk,
+    /// 802.1X with SHA-256
+    Ieee8021xSha256,
+    /// PSK with SHA-256
+    PskSha256,
+    /// SAE (WPA3-Personal)
+    Sae,
+    /// FT with SAE
+    FtSae,
+    /// Suite B 802.1X
+    SuiteB,
+    /// Suite B 192-bit 802.1X
+    SuiteB192,
+    /// FT over 802.1X with SHA-384
+    FtIeee8021xSha384,
+    /// OWE (Enhanced Open)
+    Owe,
+    /// SAE with group-dependent hash
+    SaeExt,
+    /// FT with SAE and group-dependent hash
+    FtSaeExt,
+    /// Unknown suite selector
+    Other(u32),
+}
+
+impl AkmSuite {
+    /// Returns true for SAE suites.
+    #[must_use]
+    pub fn is_sae(&self) -> bool {
+        matches!(
+            self,
+            Self::Sae | Self::FtSae | Self::SaeExt | Self::FtSaeExt
+        )
+    }
+
+    /// Returns true for PSK suites.
+    #[must_use]
+    pub fn is_psk(&self) -> bool {
+        matches!(self, Self::Psk | Self::FtPsk | Self::PskSha256)
+    }
+
+    /// Returns true for 802.1X suites.
+    #[must_use]
+    pub fn is_enterprise(&self) -> bool {
+        matches!(
+            self,
+            Self::Ieee8021x
+                | Self::FtIeee8021x
+                | Self::Ieee8021xSha256
+                | Self::SuiteB
+                | Self::SuiteB192
+                | Self::FtIeee8021xSha384
+        )
+    }
+
+    /// Returns true for fast BSS transition suites.
+    #[must_use]
+    pub fn is_fast_transition(&self) -> bool {
+        matches!(
+            self,
+            Self::FtIeee8021x
+                | Self::FtPsk
+                | Self::FtSae
+                | Self::FtIeee8021xSha384
+                | Self::FtSaeExt
+        )
+    }
+}
+
+/// HT capabilities element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HtCapabilities {
+    /// 40 MHz operation supported
+    pub supports_40mhz: bool,
+    /// Short guard interval at 20 MHz
+    pub short_gi_20: bool,
+    /// Short guard interval at 40 MHz
+    pub short_gi_40: bool,
+    /// Spatial streams from the supported MCS set
+    pub max_spatial_streams: u8,
+}
+
+/// HT operation element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HtOperation {
+    /// Primary channel number
+    pub primary_channel: u8,
+    /// Position of the secondary channel
+    pub secondary_channel: SecondaryChannelPosition,
+    /// Any channel width allowed (40 MHz in use)
+    pub sta_channel_width: bool,
+}
+
+/// VHT capabilities element.
+#[allow(clippy::struct_excessive_bools)] // mirrors the VHT capabilities info bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VhtCapabilities {
+    /// 160 MHz operation supported
+    pub supports_160mhz: bool,
+    /// 80+80 MHz operation supported
+    pub supports_80p80mhz: bool,
+    /// Short guard interval at 80 MHz
+    pub short_gi_80: bool,
+    /// SU beamformer capable
+    pub su_beamformer: bool,
+    /// MU beamformer capable
+    pub mu_beamformer: bool,
+    /// Spatial streams from the supported MCS map
+    pub max_spatial_streams: u8,
+}
+
+/// VHT operation element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VhtOperation {
+    /// Channel width field (0 = 20/40, 1 = 80/160/80+80)
+    pub channel_width: u8,
+    /// Center frequency segment 0 (channel number)
+    pub center_segment0: u8,
+    /// Center frequency segment 1 (channel number)
+    pub center_segment1: u8,
+}
+
+impl VhtOperation {
+    /// Returns the channel width and center channel, if 80 MHz or wider.
+    #[must_use]
+    pub fn width(&self) -> Option<(ChannelWidth, u8)> {
+        match self.channel_width {
+            1 if self.center_segment1 != 0
+                && self.center_segment1.abs_diff(self.center_segment0) == 8 =>
+            {
+                // Segment 1 is the center of the whole 160 MHz channel
+                Some((ChannelWidth::Mhz160, self.center_segment1))
+            }
+            // 3 is the deprecated 80+80 encoding
+            1 | 3 => Some((ChannelWidth::Mhz80, self.center_segment0)),
+            // Deprecated 160 MHz encoding
+            2 => Some((ChannelWidth::Mhz160, self.center_segment0)),
+            _ => None,
+        }
+    }
+}
+
+/// HE capabilities element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeCapabilities {
+    /// 160 MHz operation supported in 5/6 GHz
+    pub supports_160mhz: bool,
+    /// Spatial streams from the ≤80 MHz MCS map
+    pub max_spatial_streams: u8,
+}
+
+/// HE operation element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HeOperation {
+    /// BSS color
+    pub bss_color: u8,
+    /// BSS color disabled
+    pub bss_color_disabled: bool,
+    /// 6 GHz operation information
+    pub six_ghz: Option<SixGhzOperation>,
+}
+
+/// 6 GHz operation information from the HE operation element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SixGhzOperation {
+    /// Primary channel number
+    pub primary_channel: u8,
+    /// Channel width field (0 = 20, 1 = 40, 2 = 80, 3 = 160/80+80)
+    pub channel_width: u8,
+    /// Center frequency segment 0
+    pub center_segment0: u8,
+    /// Center frequency segment 1
+    pub center_segment1: u8,
+}
+
+impl SixGhzOperation {
+    /// Returns the channel width and center channel.
+    #[must_use]
+    pub fn width(&self) -> (ChannelWidth, u8) {
+        match self.channel_width {
+            0 => (ChannelWidth::Mhz20, self.primary_channel),
+            1 => (ChannelWidth::Mhz40, self.center_segment0),
+            2 => (ChannelWidth::Mhz80, self.center_segment0),
+            _ if self.center_segment1 != 0 => (ChannelWidth::Mhz160, self.center_segment1),
+            _ => (ChannelWidth::Mhz160, self.center_segment0),
+        }
+    }
+}
+
+/// EHT capabilities element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EhtCapabilities {
+    /// 320 MHz operation supported in 6 GHz
+    pub supports_320mhz: bool,
+}
+
+/// Mobility domain element (802.11r).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MobilityDomain {
+    /// Mobility domain identifier
+    pub mdid: u16,
+    /// Fast transition over the distribution system
+    pub ft_over_ds: bool,
+}
+
+/// RM enabled capabilities element (802.11k).
+#[allow(clippy::struct_excessive_bools)] // mirrors the RM enabled capability bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RmCapabilities {
+    /// Neighbor reports
+    pub neighbor_report: bool,
+    /// Passive beacon measurement
+    pub beacon_passive: bool,
+    /// Active beacon measurement
+    pub beacon_active: bool,
+    /// Beacon table measurement
+    pub beacon_table: bool,
+}
+
+/// Extended capabilities element.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtendedCapabilities {
+    /// BSS transition management (802.11v)
+    pub bss_transition: bool,
+    /// Multiple BSSID
+    pub multiple_bssid: bool,
+    /// Interworking (Hotspot 2.0)
+    pub interworking: bool,
+}
@@ -30,6 +30,42 @@ pub enum EapMethod {
     Md5,
 }
 
+impl EapMethod {
+    /// Returns the method for an IANA EAP type number.
+    #[must_use]
+    pub const fn from_type(eap_type: u8) -> Option<Self> {
+        match eap_type {
+            4 => Some(Self::Md5),
+            13 => Some(Self::Tls),
+            17 => Some(Self::Leap),
+            18 => Some(Self::Sim),
+            21 => Some(Self::Ttls),
+            23 => Some(Self::Aka),
+            25 => Some(Self::Peap),
+            43 => Some(Self::Fast),
+            50 => Some(Self::AkaPrime),
+            52 => Some(Self::Pwd),
+            _ => None,
+        }
+    }
+
+    /// Returns the method for a `wpa_supplicant` `eap` name (e.g. `PEAP`, `AKA'`).
+    #[must_use]
+    pub fn from_supplicant_name(name: &str) -> Option<Self> {
+        let name = name.trim().trim_start_matches("EAP-");
+        if name == "AKA'" {
+            return Some(Self::AkaPrime);
+        }
+        name.to_ascii_uppercase().parse().ok()
+    }
+
+    /// Whether the method authenticates the server with a TLS certificate.
+    #[must_use]
+    pub const fn uses_server_certificate(&self) -> bool {
+        matches!(self, Self::Tls | Self::Ttls | Self::Peap | Self::Fast)
+    }
+}
+
 /// Phase 2 (inner) authentication method.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Display, EnumString)]
 #[serde(rename_all = "UPPERCASE")]
@@ -154,3 +190,122 @@ pub struct EapSession {
     /// VLAN assigned
     pub vlan_id: Option<u16>,
 }
+
+/// One message on a `wpa_supplicant` control interface.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SupplicantMessage {
+    /// A command and its reply
+    Command {
+        /// Command sent, e.g. `STATUS-VERBOSE`
+        request: String,
+        /// Reply text
+        reply: String,
+    },
+    /// An unsolicited event, without its `<level>` prefix
+    Event(String),
+}
+
+/// A recorded `wpa_supplicant` control interface session.
+///
+/// The text form follows `wpa_cli`: `> COMMAND` lines are followed by the
+/// reply, `<N>` lines are events and `#` lines are comments, so sessions can
+/// be recorded on one machine and replayed in tests or elsewhere.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SupplicantTranscript {
+    /// Messages in the order they were exchanged
+    pub messages: Vec<SupplicantMessage>,
+}
+
+impl SupplicantTranscript {
+    /// Creates an empty transcript.
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a command and its reply.
+    pub fn push_command(&mut self, request: impl Into<String>, reply: impl Into<String>) {
+        self.messages.push(SupplicantMessage::Command {
+            request: request.into(),
+            reply: reply.into().trim_end().to_string(),
+        });
+    }
+
+    /// Records an event, stripping the `<level>` prefix if present.
+    pub fn push_event(&mut self, event: &str) {
+        self.messages.push(SupplicantMessage::Event(
+            strip_level(event.trim()).to_string(),
+        ));
+    }
+
+    /// Parses the `wpa_cli` style text form.
+    #[must_use]
+    pub fn parse(text: &str) -> Self {
+        let mut transcript = Self::new();
+        let mut command: Option<(String, Vec<&str>)> = None;
+        for line in text.lines() {
+            let event = line.starts_with('<') && strip_level(line).len() < line.len();
+            if line.starts_with("> ") || event || line.starts_with('#') {
+                if let Some((request, reply)) = command.take() {
+                    transcript.push_command(request, reply.join("\n"));
+                }
+            }
+            if let Some(request) = line.strip_prefix("> ") {
+                command = Some((request.trim().to_string(), Vec::new()));
+            } else if event {
+                transcript.push_event(line);
+            } else if let Some((_, reply)) = command.as_mut() {
+                reply.push(line);
+            }
+        }
+        if let Some((request, reply)) = command {
+            transcript.push_command(request, reply.join("\n"));
+        }
+        transcript
+    }
+
+    /// Reply to the last occurrence of a command (case-insensitive).
+    #[must_use]
+    pub fn reply(&self, request: &str) -> Option<&str> {
+        self.messages.iter().rev().find_map(|m| match m {
+            SupplicantMessage::Command { request: r, reply } if r.eq_ignore_ascii_case(request) => {
+                Some(reply.as_str())
+            }
+            _ => None,
+        })
+    }
+
+    /// Events in the order they were received.
+    pub fn events(&self) -> impl Iterator<Item = &str> {
+        self.messages.iter().filter_map(|m| match m {
+            SupplicantMessage::Event(event) => Some(event.as_str()),
+            SupplicantMessage::Command { .. } => None,
+        })
+    }
+}
+
+impl std::fmt::Display for SupplicantTranscript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for message in &self.messages {
+            match message {
+                SupplicantMessage::Command { request, reply } => {
+                    writeln!(f, "> {request}")?;
+                    if !reply.is_empty() {
+                        writeln!(f, "{reply}")?;
+                    }
+                }
+                SupplicantMessage::Event(event) => writeln!(f, "<3>{event}")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Strips the `<N>` priority prefix of a control interface event.
+fn strip_level(line: &str) -> &str {
+    line.strip_prefix('<')
+        .and_then(|rest| rest.split_once('>'))
+        .filter(|(level, _)| !level.is_empty() && level.bytes().all(|b| b.is_ascii_digit()))
+        .map_or(line, |(_, event)| event)
+}
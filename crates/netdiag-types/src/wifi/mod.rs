@@ -1,11 +1,17 @@
 //! WiFi-related types.
 
 mod access_point;
+mod airtime;
 mod channel;
+mod elements;
 mod enterprise;
+mod regulatory;
 mod security;
 
 pub use access_point::*;
+pub use airtime::*;
 pub use channel::*;
+pub use elements::*;
 pub use enterprise::*;
+pub use regulatory::*;
 pub use security::*;
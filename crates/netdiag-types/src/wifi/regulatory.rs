@@ -0,0 +1,474 @@
+//! Regulatory domains ([`RegulatoryDomain`]) and the channel rules they impose.
+
+use super::channel::{bonding_blocks, Channel, ChannelWidth, WifiBand};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use strum::{Display, EnumString};
+
+/// Channel availability check duration on most DFS channels.
+const CAC_TIME: Duration = Duration::from_secs(60);
+
+/// ETSI channel availability check on the weather radar channels (120-128).
+const WEATHER_RADAR_CAC_TIME: Duration = Duration::from_secs(600);
+
+/// DFS rule set of a regulatory domain.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Display, EnumString, Default,
+)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "UPPERCASE")]
+pub enum DfsRegion {
+    /// No DFS region
+    #[default]
+    Unset,
+    /// United States FCC rules
+    Fcc,
+    /// European ETSI rules
+    Etsi,
+    /// Japanese rules
+    Jp,
+}
+
+/// Rules for a range of 20 MHz channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FrequencyRule {
+    /// Frequency band
+    pub band: WifiBand,
+    /// First 20 MHz channel number
+    pub first_channel: u8,
+    /// Last 20 MHz channel number
+    pub last_channel: u8,
+    /// Maximum EIRP in dBm
+    pub max_eirp_dbm: u8,
+    /// Widest channel allowed
+    pub max_width: ChannelWidth,
+    /// Radar detection (DFS) required
+    pub dfs: bool,
+    /// Indoor use only (6 GHz low power indoor, some 5 GHz sub-bands)
+    pub indoor_only: bool,
+}
+
+impl FrequencyRule {
+    const fn new(
+        band: WifiBand,
+        channels: (u8, u8),
+        max_eirp_dbm: u8,
+        max_width: ChannelWidth,
+    ) -> Self {
+        Self {
+            band,
+            first_channel: channels.0,
+            last_channel: channels.1,
+            max_eirp_dbm,
+            max_width,
+            dfs: false,
+            indoor_only: false,
+        }
+    }
+
+    const fn dfs(mut self) -> Self {
+        self.dfs = true;
+        self
+    }
+
+    const fn indoor(mut self) -> Self {
+        self.indoor_only = true;
+        self
+    }
+
+    /// Returns true if the rule covers a 20 MHz channel.
+    #[must_use]
+    pub fn contains(&self, band: WifiBand, number: u8) -> bool {
+        self.band == band && (self.first_channel..=self.last_channel).contains(&number)
+    }
+}
+
+use ChannelWidth::{Mhz160, Mhz320, Mhz40, Mhz80};
+use WifiBand::{Band2_4GHz, Band5GHz, Band6GHz};
+
+/// Intersection of every table below: the strictest limits of any country.
+const WORLD: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 11), 20, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz80).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz80)
+        .dfs()
+        .indoor(),
+];
+
+/// United States (FCC), 6 GHz low power indoor.
+const FCC: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 11), 36, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 36, Mhz160),
+    FrequencyRule::new(Band5GHz, (52, 64), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (100, 144), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (149, 165), 36, Mhz160),
+    FrequencyRule::new(Band5GHz, (169, 177), 36, Mhz160).indoor(),
+    FrequencyRule::new(Band6GHz, (1, 233), 30, Mhz320).indoor(),
+];
+
+/// Canada (ISED): FCC rules without U-NII-4 (channels 169-177).
+const CANADA: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 11), 36, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz160).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (100, 144), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (149, 165), 36, Mhz160),
+    FrequencyRule::new(Band6GHz, (1, 233), 30, Mhz320).indoor(),
+];
+
+/// EU/EEA, United Kingdom and Switzerland (ETSI), lower 6 GHz band only.
+const ETSI: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 13), 20, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz160).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz160)
+        .dfs()
+        .indoor(),
+    FrequencyRule::new(Band5GHz, (100, 140), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band6GHz, (1, 93), 23, Mhz320).indoor(),
+];
+
+/// Japan.
+const JAPAN: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 13), 20, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz160).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz160)
+        .dfs()
+        .indoor(),
+    FrequencyRule::new(Band5GHz, (100, 144), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band6GHz, (1, 93), 23, Mhz320).indoor(),
+];
+
+/// China (6 GHz not opened).
+const CHINA: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 13), 20, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz80).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz80)
+        .dfs()
+        .indoor(),
+    FrequencyRule::new(Band5GHz, (149, 165), 33, Mhz80),
+];
+
+/// South Korea.
+const KOREA: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 13), 23, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz160),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (100, 144), 30, Mhz160).dfs(),
+    FrequencyRule::new(Band5GHz, (149, 165), 30, Mhz80),
+    FrequencyRule::new(Band6GHz, (1, 233), 24, Mhz320).indoor(),
+];
+
+/// Australia and New Zealand (no weather radar channels 120-128).
+const OCEANIA: &[FrequencyRule] = &[
+    FrequencyRule::new(Band2_4GHz, (1, 13), 36, Mhz40),
+    FrequencyRule::new(Band5GHz, (36, 48), 23, Mhz160).indoor(),
+    FrequencyRule::new(Band5GHz, (52, 64), 23, Mhz160)
+        .dfs()
+        .indoor(),
+    FrequencyRule::new(Band5GHz, (100, 116), 30, Mhz80).dfs(),
+    FrequencyRule::new(Band5GHz, (132, 144), 30, Mhz80).dfs(),
+    FrequencyRule::new(Band5GHz, (149, 165), 36, Mhz80),
+    FrequencyRule::new(Band6GHz, (1, 93), 24, Mhz320).indoor(),
+];
+
+/// Countries following ETSI rules.
+const ETSI_COUNTRIES: &[&str] = &[
+    "AT", "BE", "BG", "CH", "CY", "CZ", "DE", "DK", "EE", "ES", "FI", "FR", "GB", "GR", "HR", "HU",
+    "IE", "IS", "IT", "LI", "LT", "LU", "LV", "MT", "NL", "NO", "PL", "PT", "RO", "SE", "SI", "SK",
+];
+
+/// Channel rules for a country.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RegulatoryDomain {
+    /// ISO 3166 alpha-2 country code (`00` for the world domain)
+    pub country: String,
+    /// DFS rule set
+    pub dfs_region: DfsRegion,
+    /// Allowed channel ranges
+    pub rules: Vec<FrequencyRule>,
+}
+
+impl RegulatoryDomain {
+    /// Returns the world domain: channels usable in every country.
+    #[must_use]
+    pub fn world() -> Self {
+        Self {
+            country: "00".to_string(),
+            dfs_region: DfsRegion::Unset,
+            rules: WORLD.to_vec(),
+        }
+    }
+
+    /// Returns the domain for an ISO 3166 alpha-2 country code.
+    ///
+    /// Countries without a known rule set get the world domain.
+    #[must_use]
+    pub fn for_country(code: &str) -> Self {
+        let country = code.trim().to_ascii_uppercase();
+        let (dfs_region, rules) = match country.as_str() {
+            "US" | "PR" => (DfsRegion::Fcc, FCC),
+            "CA" => (DfsRegion::Fcc, CANADA),
+            "JP" => (DfsRegion::Jp, JAPAN),
+            "CN" => (DfsRegion::Fcc, CHINA),
+            "KR" => (DfsRegion::Etsi, KOREA),
+            "AU" | "NZ" => (DfsRegion::Etsi, OCEANIA),
+            c if ETSI_COUNTRIES.contains(&c) => (DfsRegion::Etsi, ETSI),
+            _ => return Self::world(),
+        };
+        Self {
+            country,
+            dfs_region,
+            rules: rules.to_vec(),
+        }
+    }
+
+    /// Picks the domain from observed country codes (interface setting,
+    /// 802.11d country elements), using the most common known country.
+    #[must_use]
+    pub fn detect<'a>(codes: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut counts: Vec<(Self, usize)> = Vec::new();
+        for code in codes {
+            let domain = Self::for_country(code);
+            if domain.is_world() {
+                continue;
+            }
+            match counts.iter_mut().find(|(d, _)| d.country == domain.country) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((domain, 1)),
+            }
+        }
+        counts
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .map_or_else(Self::world, |(domain, _)| domain)
+    }
+
+    /// Returns true for the world domain.
+    #[must_use]
+    pub fn is_world(&self) -> bool {
+        self.country == "00"
+    }
+
+    /// Returns the rule covering a 20 MHz channel.
+    #[must_use]
+    pub fn rule(&self, band: WifiBand, number: u8) -> Option<&FrequencyRule> {
+        self.rules.iter().find(|r| r.contains(band, number))
+    }
+
+    fn rules_for(&self, channel: &Channel) -> Option<Vec<&FrequencyRule>> {
+        channel
+            .subchannels()
+            .into_iter()
+            .map(|n| self.rule(channel.band, n))
+            .collect()
+    }
+
+    /// Returns true if the channel, at its width, may be used.
+    #[must_use]
+    pub fn allows(&self, channel: &Channel) -> bool {
+        self.rules_for(channel).is_some_and(|rules| {
+            rules
+                .iter()
+                .all(|r| channel.width.mhz() <= r.max_width.mhz())
+        })
+    }
+
+    /// Returns true if any part of the channel requires radar detection.
+    #[must_use]
+    pub fn is_dfs(&self, channel: &Channel) -> bool {
+        self.rules_for(channel)
+            .is_some_and(|rules| rules.iter().any(|r| r.dfs))
+    }
+
+    /// Returns true if the channel may only be used indoors.
+    #[must_use]
+    pub fn is_indoor_only(&self, channel: &Channel) -> bool {
+        self.rules_for(channel)
+            .is_some_and(|rules| rules.iter().any(|r| r.indoor_only))
+    }
+
+    /// Returns the maximum EIRP in dBm for the channel.
+    #[must_use]
+    pub fn max_eirp_dbm(&self, channel: &Channel) -> Option<u8> {
+        self.rules_for(channel)?
+            .iter()
+            .map(|r| r.max_eirp_dbm)
+            .min()
+    }
+
+    /// Returns how long an AP must listen for radar before using the channel.
+    #[must_use]
+    pub fn cac_time(&self, channel: &Channel) -> Option<Duration> {
+        if !self.is_dfs(channel) {
+            return None;
+        }
+        let weather_radar = channel
+            .subchannels()
+            .iter()
+            .any(|n| (120..=128).contains(n));
+        if self.dfs_region == DfsRegion::Etsi && weather_radar {
+            Some(WEATHER_RADAR_CAC_TIME)
+        } else {
+            Some(CAC_TIME)
+        }
+    }
+
+    /// Returns every allowed channel of a band and width.
+    #[must_use]
+    pub fn channels(&self, band: WifiBand, width: ChannelWidth) -> Vec<Channel> {
+        bonding_blocks(band, width)
+            .into_iter()
+            .map(|block| Channel::from_block(block[0], band, width, &block))
+            .filter(|channel| self.allows(channel))
+            .collect()
+    }
+
+    /// Returns the widest channel width allowed in a band.
+    #[must_use]
+    pub fn max_width(&self, band: WifiBand) -> Option<ChannelWidth> {
+        self.rules
+            .iter()
+            .filter(|r| r.band == band)
+            .map(|r| r.max_width)
+            .max_by_key(ChannelWidth::mhz)
+    }
+}
+
+impl Default for RegulatoryDomain {
+    fn default() -> Self {
+        Self::world()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_country_rules() {
+        let us = RegulatoryDomain::for_country("us");
+        let de = RegulatoryDomain::for_country("DE");
+        let world = RegulatoryDomain::for_country("ZZ");
+        assert_eq!(us.country, "US");
+        assert!(world.is_world());
+
+        let ch13 = Channel::from_number(13, WifiBand::Band2_4GHz);
+        assert!(!us.allows(&ch13));
+        assert!(de.allows(&ch13));
+
+        let ch149 = Channel::from_number(149, WifiBand::Band5GHz);
+        assert!(us.allows(&ch149));
+        assert!(!de.allows(&ch149));
+        assert!(!world.allows(&ch149));
+
+        let ch36 = Channel::from_number(36, WifiBand::Band5GHz);
+        assert!(de.is_indoor_only(&ch36));
+        assert!(!us.is_indoor_only(&ch36));
+        assert_eq!(us.max_eirp_dbm(&ch36), Some(36));
+
+        // A bonded channel reaching into DFS spectrum is DFS
+        let wide = Channel::bonded(36, WifiBand::Band5GHz, ChannelWidth::Mhz160).unwrap();
+        assert!(!wide.is_dfs());
+        assert!(us.is_dfs(&wide));
+        assert_eq!(us.cac_time(&wide), Some(CAC_TIME));
+
+        let weather = Channel::bonded(116, WifiBand::Band5GHz, ChannelWidth::Mhz80).unwrap();
+        assert_eq!(de.cac_time(&weather), Some(WEATHER_RADAR_CAC_TIME));
+        assert!(!RegulatoryDomain::for_country("AU").allows(&weather));
+
+        let six = Channel::from_number(101, WifiBand::Band6GHz);
+        assert!(us.allows(&six));
+        assert!(!de.allows(&six));
+        assert!(!world.allows(&six));
+    }
+
+    #[test]
+    fn test_bonding_and_psc() {
+        let us = RegulatoryDomain::for_country("US");
+        let de = RegulatoryDomain::for_country("DE");
+
+        let wide: Vec<u8> = us
+            .channels(WifiBand::Band6GHz, ChannelWidth::Mhz320)
+            .iter()
+            .map(|c| c.number)
+            .collect();
+        assert_eq!(wide, vec![1, 33, 65, 97, 129, 161]);
+        let eu: Vec<u8> = de
+            .channels(WifiBand::Band6GHz, ChannelWidth::Mhz320)
+            .iter()
+            .map(|c| c.number)
+            .collect();
+        assert_eq!(eu, vec![1, 33]);
+
+        let ch = Channel::bonded(37, WifiBand::Band6GHz, ChannelWidth::Mhz160).unwrap();
+        assert_eq!(ch.subchannels(), vec![33, 37, 41, 45, 49, 53, 57, 61]);
+        assert!(ch.is_psc());
+        assert_eq!(
+            us.channels(WifiBand::Band6GHz, ChannelWidth::Mhz20)
+                .iter()
+                .filter(|c| c.is_psc())
+                .count(),
+            15
+        );
+
+        assert_eq!(
+            us.channels(WifiBand::Band5GHz, ChannelWidth::Mhz160).len(),
+            3
+        );
+        assert_eq!(
+            de.channels(WifiBand::Band5GHz, ChannelWidth::Mhz160).len(),
+            2
+        );
+        assert_eq!(de.max_width(WifiBand::Band6GHz), Some(ChannelWidth::Mhz320));
+        assert_eq!(
+            RegulatoryDomain::for_country("CN").max_width(WifiBand::Band6GHz),
+            None
+        );
+
+        let ca = RegulatoryDomain::for_country("CA");
+        let ch173 = Channel::from_number(173, WifiBand::Band5GHz);
+        assert!(us.allows(&ch173));
+        assert!(!ca.allows(&ch173));
+        assert!(ca.allows(&Channel::from_number(165, WifiBand::Band5GHz)));
+
+        let detected = RegulatoryDomain::detect(["00", "DE", "US", "DE"]);
+        assert_eq!(detected.country, "DE");
+        assert!(RegulatoryDomain::detect(["00"]).is_world());
+    }
+
+    #[test]
+    fn test_world_is_legal_everywhere() {
+        let world = RegulatoryDomain::world();
+        let domains: Vec<RegulatoryDomain> = ["US", "CA", "DE", "JP", "CN", "KR", "AU"]
+            .into_iter()
+            .map(RegulatoryDomain::for_country)
+            .collect();
+
+        for band in [WifiBand::Band2_4GHz, WifiBand::Band5GHz, WifiBand::Band6GHz] {
+            for width in [
+                ChannelWidth::Mhz20,
+                ChannelWidth::Mhz40,
+                ChannelWidth::Mhz80,
+                ChannelWidth::Mhz160,
+                ChannelWidth::Mhz320,
+            ] {
+                for channel in world.channels(band, width) {
+                    for domain in &domains {
+                        assert!(
+                            domain.allows(&channel),
+                            "{} {width:?} not legal in {}",
+                            channel.number,
+                            domain.country
+                        );
+                        assert!(world.max_eirp_dbm(&channel) <= domain.max_eirp_dbm(&channel));
+                        assert!(world.is_dfs(&channel) || !domain.is_dfs(&channel));
+                        assert!(world.is_indoor_only(&channel) || !domain.is_indoor_only(&channel));
+                    }
+                }
+            }
+        }
+        let ch100 = Channel::from_number(100, WifiBand::Band5GHz);
+        assert!(!world.allows(&ch100));
+    }
+}
@@ -5,11 +5,13 @@ use crate::{
     error::{WifiError, WifiResult},
     interference::InterferenceAnalysis,
     quality::QualityMetrics,
+    rogue::{ApAllowlist, RogueAnalysis, RogueDetector},
     security::SecurityAnalysis,
+    throughput::ThroughputEstimate,
 };
 use chrono::{DateTime, Utc};
 use netdiag_platform::WifiProvider;
-use netdiag_types::wifi::{AccessPoint, WifiConnection};
+use netdiag_types::wifi::{AccessPoint, RegulatoryDomain, WifiConnection};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 use tracing::{debug, info};
@@ -29,6 +31,10 @@ pub struct WifiAnalysis {
     /// Signal quality metrics.
     pub quality: Option<QualityMetrics>,
 
+    /// Expected throughput of the current link.
+    #[serde(default)]
+    pub throughput: Option<ThroughputEstimate>,
+
     /// Channel analysis.
     pub channel_analysis: ChannelAnalysis,
 
@@ -38,6 +44,10 @@ pub struct WifiAnalysis {
     /// Security analysis (if connected).
     pub security: Option<SecurityAnalysis>,
 
+    /// Rogue AP and evil-twin findings among nearby networks.
+    #[serde(default)]
+    pub rogue_aps: RogueAnalysis,
+
     /// Nearby access points.
     pub nearby_networks: Vec<AccessPoint>,
 
@@ -154,12 +164,22 @@ impl WifiEnvironment {
 /// WiFi analyzer for comprehensive analysis.
 pub struct WifiAnalyzer {
     provider: Arc<dyn WifiProvider>,
+    rogue_detector: RogueDetector,
 }
 
 impl WifiAnalyzer {
     /// Create a new analyzer.
     pub fn new(provider: Arc<dyn WifiProvider>) -> Self {
-        Self { provider }
+        Self {
+            provider,
+            rogue_detector: RogueDetector::new(),
+        }
+    }
+
+    /// Check nearby networks against an allowlist of known access points.
+    pub fn with_allowlist(mut self, allowlist: ApAllowlist) -> Self {
+        self.rogue_detector = RogueDetector::with_allowlist(allowlist);
+        self
     }
 
     /// Run full WiFi analysis.
@@ -205,9 +225,36 @@ impl WifiAnalyzer {
             .await
             .unwrap_or_default();
 
+        // Prefer the adapter's regulatory country over what APs advertise
+        let domain = self
+            .provider
+            .list_wifi_interfaces()
+            .await
+            .ok()
+            .and_then(|list| list.into_iter().find(|i| i.name == interface))
+            .and_then(|i| i.country_code)
+            .map(|code| RegulatoryDomain::for_country(&code))
+            .filter(|domain| !domain.is_world())
+            .unwrap_or_else(|| {
+                RegulatoryDomain::detect(
+                    nearby_networks
+                        .iter()
+                        .filter_map(|ap| ap.country_code.as_deref()),
+                )
+            });
+
         // Perform channel analysis
-        let channel_analysis =
-            ChannelAnalysis::analyze(&nearby_networks, current_channel.clone(), &utilization_data);
+        let channel_analysis = ChannelAnalysis::analyze_in(
+            &nearby_networks,
+            current_channel.clone(),
+            &utilization_data,
+            &domain,
+        );
+
+        // Expected throughput given the link and how busy the channel is
+        let throughput = connection.as_ref().and_then(|conn| {
+            ThroughputEstimate::from_connection(conn, channel_analysis.current_busy_percent())
+        });
 
         // Analyze interference
         debug!("Analyzing interference...");
@@ -226,13 +273,22 @@ impl WifiAnalyzer {
             .as_ref()
             .map(SecurityAnalysis::analyze_connection);
 
+        // Rogue AP detection
+        debug!("Checking for rogue access points...");
+        let rogue_aps = self.rogue_detector.detect(&nearby_networks);
+
         // Calculate overall health score
         let health_score =
             Self::calculate_health_score(&quality, &channel_analysis, &interference, &security);
 
         // Generate summary recommendations
-        let recommendations =
-            Self::generate_recommendations(&quality, &channel_analysis, &interference, &security);
+        let recommendations = Self::generate_recommendations(
+            &quality,
+            &channel_analysis,
+            &interference,
+            &security,
+            &rogue_aps,
+        );
 
         info!("WiFi analysis complete. Health score: {}", health_score);
 
@@ -241,9 +297,11 @@ impl WifiAnalyzer {
             interface: interface.to_string(),
             connection,
             quality,
+            throughput,
             channel_analysis,
             interference,
             security,
+            rogue_aps,
             nearby_networks,
             health_score,
             recommendations,
@@ -324,6 +382,7 @@ impl WifiAnalyzer {
         channel: &ChannelAnalysis,
         interference: &InterferenceAnalysis,
         security: &Option<SecurityAnalysis>,
+        rogue_aps: &RogueAnalysis,
     ) -> Vec<String> {
         let mut recommendations = Vec::new();
 
@@ -349,9 +408,49 @@ impl WifiAnalyzer {
             }
         }
 
+        // Rogue AP recommendations (critical findings only)
+        if rogue_aps.has_critical() {
+            recommendations.extend(rogue_aps.recommendations.first().cloned());
+        }
+
         // Limit to top 5 recommendations
         recommendations.truncate(5);
 
         recommendations
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_platform::{ReplayWifiProvider, WifiRecording};
+
+    fn office() -> Arc<dyn WifiProvider> {
+        let recording =
+            WifiRecording::parse(include_str!("../testdata/recordings/office.jsonl")).unwrap();
+        Arc::new(ReplayWifiProvider::new(recording))
+    }
+
+    #[tokio::test]
+    async fn test_analyze_recorded_session() {
+        let analysis = WifiAnalyzer::new(office()).analyze("wlan0").await.unwrap();
+
+        let connection = analysis.connection.unwrap();
+        assert_eq!(connection.access_point.ssid.as_str(), "Office");
+        assert_eq!(connection.access_point.rssi, -71);
+        assert_eq!(analysis.nearby_networks.len(), 5);
+        // The recorded channel survey failed; analysis carries on without it
+        assert_eq!(analysis.channel_analysis.country.as_deref(), Some("US"));
+        assert_eq!(analysis.interference.co_channel_count, 3);
+        assert!(analysis.rogue_aps.findings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_unrecorded_interface_fails() {
+        let analyzer = WifiAnalyzer::new(office());
+        assert!(matches!(
+            analyzer.analyze("wlan1").await,
+            Err(WifiError::Platform(_))
+        ));
+    }
+}
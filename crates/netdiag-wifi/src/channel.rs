@@ -1,6 +1,9 @@
 //! Channel analysis and recommendations.
 
-use netdiag_types::wifi::{AccessPoint, Channel, ChannelUtilization, InterferenceLevel, WifiBand};
+use netdiag_types::wifi::{
+    AccessPoint, Channel, ChannelUtilization, ChannelWidth, InterferenceLevel, RegulatoryDomain,
+    WifiBand,
+};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -21,6 +24,10 @@ pub struct ChannelAnalysis {
 
     /// Overall channel health score (0-100).
     pub health_score: u8,
+
+    /// Country whose regulatory domain was applied (world rules if `None`).
+    #[serde(default)]
+    pub country: Option<String>,
 }
 
 /// Detailed information about a channel.
@@ -44,10 +51,36 @@ pub struct ChannelInfo {
     /// Is this a DFS channel?
     pub is_dfs: bool,
 
+    /// Highest channel utilization advertised in BSS Load elements (0-100).
+    pub advertised_utilization: Option<f32>,
+
+    /// Stations associated to APs on this channel, from BSS Load elements.
+    pub station_count: u32,
+
+    /// Whether the regulatory domain allows this channel.
+    #[serde(default = "default_allowed")]
+    pub allowed: bool,
+
+    /// Whether the channel may only be used indoors.
+    #[serde(default)]
+    pub indoor_only: bool,
+
+    /// Maximum EIRP in dBm allowed on this channel.
+    #[serde(default)]
+    pub max_eirp_dbm: Option<u8>,
+
+    /// Is this a 6 GHz preferred scanning channel?
+    #[serde(default)]
+    pub is_psc: bool,
+
     /// Score for this channel (0-100, higher is better).
     pub score: u8,
 }
 
+fn default_allowed() -> bool {
+    true
+}
+
 /// Channel recommendation.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChannelRecommendation {
@@ -68,76 +101,111 @@ pub struct ChannelRecommendation {
 }
 
 impl ChannelAnalysis {
-    /// Analyze channels from scan results.
+    /// Analyze channels from scan results, under the regulatory domain
+    /// advertised by the scanned access points.
     pub fn analyze(
         access_points: &[AccessPoint],
         current_channel: Option<Channel>,
         utilization_data: &[ChannelUtilization],
     ) -> Self {
-        let mut channel_map: HashMap<u8, Vec<&AccessPoint>> = HashMap::new();
+        let domain = RegulatoryDomain::detect(
+            access_points
+                .iter()
+                .filter_map(|ap| ap.country_code.as_deref()),
+        );
+        Self::analyze_in(access_points, current_channel, utilization_data, &domain)
+    }
+
+    /// Analyze channels from scan results under a regulatory domain.
+    ///
+    /// Channels the domain doesn't allow are listed only when networks use
+    /// them, and are never recommended.
+    pub fn analyze_in(
+        access_points: &[AccessPoint],
+        current_channel: Option<Channel>,
+        utilization_data: &[ChannelUtilization],
+        domain: &RegulatoryDomain,
+    ) -> Self {
+        let mut channel_map: HashMap<(WifiBand, u8), Vec<&AccessPoint>> = HashMap::new();
 
-        // Group APs by channel
+        // Group APs by every 20 MHz channel they occupy
         for ap in access_points {
-            channel_map.entry(ap.channel.number).or_default().push(ap);
+            for number in ap.channel.subchannels() {
+                channel_map
+                    .entry((ap.channel.band, number))
+                    .or_default()
+                    .push(ap);
+            }
+        }
+
+        // 6 GHz is only analyzed when it is in use nearby or by us
+        let mut bands = vec![WifiBand::Band2_4GHz, WifiBand::Band5GHz];
+        let six_ghz_seen = access_points
+            .iter()
+            .map(|ap| ap.channel.band)
+            .chain(current_channel.map(|c| c.band))
+            .any(|band| band == WifiBand::Band6GHz);
+        if six_ghz_seen {
+            bands.push(WifiBand::Band6GHz);
         }
 
         // Build channel info
         let mut channels: Vec<ChannelInfo> = Vec::new();
 
-        // Process 2.4 GHz channels (1, 6, 11 recommended)
-        for ch in 1..=14 {
-            let channel = Channel::from_number(ch, WifiBand::Band2_4GHz);
-
-            let aps = channel_map.get(&ch).map(|v| v.as_slice()).unwrap_or(&[]);
-            let utilization = utilization_data.iter().find(|u| u.channel.number == ch);
-
-            let score = Self::calculate_channel_score(&channel, aps, utilization);
-
-            channels.push(ChannelInfo {
-                channel: channel.clone(),
-                utilization: utilization.cloned(),
-                ap_count: aps.len(),
-                max_signal: aps.iter().map(|ap| ap.rssi).max(),
-                avg_signal: if aps.is_empty() {
-                    None
-                } else {
-                    Some(aps.iter().map(|ap| ap.rssi as f64).sum::<f64>() / aps.len() as f64)
-                },
-                is_dfs: false,
-                score,
-            });
-        }
+        for band in bands {
+            let mut numbers: Vec<u8> = domain
+                .channels(band, ChannelWidth::Mhz20)
+                .iter()
+                .map(|c| c.number)
+                .chain(
+                    channel_map
+                        .keys()
+                        .filter(|(b, _)| *b == band)
+                        .map(|(_, n)| *n),
+                )
+                .collect();
+            numbers.sort_unstable();
+            numbers.dedup();
+
+            for ch in numbers {
+                let channel = Channel::from_number(ch, band);
+
+                let aps = channel_map
+                    .get(&(band, ch))
+                    .map(|v| v.as_slice())
+                    .unwrap_or(&[]);
+                let utilization = utilization_data
+                    .iter()
+                    .find(|u| u.channel.band == band && u.channel.number == ch);
 
-        // Process 5 GHz channels
-        let ghz5_channels = [
-            36, 40, 44, 48, // UNII-1
-            52, 56, 60, 64, // UNII-2A (DFS)
-            100, 104, 108, 112, 116, 120, 124, 128, 132, 136, 140, 144, // UNII-2C (DFS)
-            149, 153, 157, 161, 165, // UNII-3
-        ];
-
-        for ch in ghz5_channels {
-            let channel = Channel::from_number(ch, WifiBand::Band5GHz);
-
-            let aps = channel_map.get(&ch).map(|v| v.as_slice()).unwrap_or(&[]);
-            let utilization = utilization_data.iter().find(|u| u.channel.number == ch);
-
-            let is_dfs = (52..=144).contains(&ch);
-            let score = Self::calculate_channel_score(&channel, aps, utilization);
-
-            channels.push(ChannelInfo {
-                channel: channel.clone(),
-                utilization: utilization.cloned(),
-                ap_count: aps.len(),
-                max_signal: aps.iter().map(|ap| ap.rssi).max(),
-                avg_signal: if aps.is_empty() {
-                    None
+                let allowed = domain.allows(&channel);
+                let is_dfs = domain.is_dfs(&channel);
+                let score = if allowed {
+                    Self::calculate_channel_score(&channel, aps, utilization, is_dfs)
                 } else {
-                    Some(aps.iter().map(|ap| ap.rssi as f64).sum::<f64>() / aps.len() as f64)
-                },
-                is_dfs,
-                score,
-            });
+                    0
+                };
+
+                channels.push(ChannelInfo {
+                    channel,
+                    utilization: utilization.cloned(),
+                    ap_count: aps.len(),
+                    max_signal: aps.iter().map(|ap| ap.rssi).max(),
+                    avg_signal: if aps.is_empty() {
+                        None
+                    } else {
+                        Some(aps.iter().map(|ap| ap.rssi as f64).sum::<f64>() / aps.len() as f64)
+                    },
+                    is_dfs,
+                    advertised_utilization: Self::advertised_utilization(aps),
+                    station_count: Self::station_count(aps),
+                    allowed,
+                    indoor_only: domain.is_indoor_only(&channel),
+                    max_eirp_dbm: domain.max_eirp_dbm(&channel),
+                    is_psc: channel.is_psc(),
+                    score,
+                });
+            }
         }
 
         // Calculate current channel utilization
@@ -160,14 +228,32 @@ impl ChannelAnalysis {
             channels,
             recommendations,
             health_score,
+            country: (!domain.is_world()).then(|| domain.country.clone()),
         }
     }
 
+    /// Highest channel utilization advertised by APs in their BSS Load element.
+    fn advertised_utilization(aps: &[&AccessPoint]) -> Option<f32> {
+        aps.iter()
+            .filter_map(|ap| ap.elements.as_ref()?.bss_load)
+            .map(|load| load.utilization_percent())
+            .reduce(f32::max)
+    }
+
+    /// Total stations associated to APs, from their BSS Load element.
+    fn station_count(aps: &[&AccessPoint]) -> u32 {
+        aps.iter()
+            .filter_map(|ap| ap.elements.as_ref()?.bss_load)
+            .map(|load| u32::from(load.station_count))
+            .sum()
+    }
+
     /// Calculate score for a channel (higher is better).
     fn calculate_channel_score(
         channel: &Channel,
         aps: &[&AccessPoint],
         utilization: Option<&ChannelUtilization>,
+        is_dfs: bool,
     ) -> u8 {
         let mut score = 100u8;
 
@@ -197,6 +283,10 @@ impl ChannelAnalysis {
                 InterferenceLevel::Medium => score = score.saturating_sub(10),
                 InterferenceLevel::Low => {}
             }
+        } else if let Some(advertised) = Self::advertised_utilization(aps) {
+            // APs measure their channel's airtime; use that when we have no survey
+            let util_penalty = (advertised * 0.5) as u8;
+            score = score.saturating_sub(util_penalty);
         }
 
         // Bonus for non-overlapping 2.4 GHz channels
@@ -205,7 +295,7 @@ impl ChannelAnalysis {
         }
 
         // Small penalty for DFS channels (potential radar interference)
-        if (52..=144).contains(&channel.number) {
+        if is_dfs {
             score = score.saturating_sub(5);
         }
 
@@ -223,27 +313,30 @@ impl ChannelAnalysis {
             .and_then(|ch| {
                 channels
                     .iter()
-                    .find(|c| c.channel.number == ch.number)
+                    .find(|c| c.channel.band == ch.band && c.channel.number == ch.number)
                     .map(|c| c.score)
             })
             .unwrap_or(0);
 
-        // Find best channels in each band
-        let best_2_4 = channels
-            .iter()
+        // Find best legal channels in each band
+        let legal = || channels.iter().filter(|c| c.allowed);
+        let best_2_4 = legal()
             .filter(|c| c.channel.band == WifiBand::Band2_4GHz)
             .max_by_key(|c| c.score);
 
-        let best_5 = channels
-            .iter()
+        let best_5 = legal()
             .filter(|c| c.channel.band == WifiBand::Band5GHz && !c.is_dfs)
             .max_by_key(|c| c.score);
 
-        let best_5_dfs = channels
-            .iter()
+        let best_5_dfs = legal()
             .filter(|c| c.channel.band == WifiBand::Band5GHz && c.is_dfs)
             .max_by_key(|c| c.score);
 
+        // 6 GHz clients only discover APs on preferred scanning channels
+        let best_6 = legal()
+            .filter(|c| c.channel.band == WifiBand::Band6GHz && c.is_psc)
+            .max_by_key(|c| c.score);
+
         // Recommend 5 GHz if significantly better
         if let Some(best) = best_5 {
             if best.score > current_score + 15 {
@@ -253,7 +346,10 @@ impl ChannelAnalysis {
                         "Channel {} has {} APs (score: {})",
                         best.channel.number, best.ap_count, best.score
                     ),
-                    expected_improvement: "Better speed and less interference on 5 GHz".to_string(),
+                    expected_improvement: format!(
+                        "Better speed and less interference on 5 GHz{}",
+                        Self::indoor_note(best)
+                    ),
                     priority: 1,
                     score_improvement: (best.score as i8 - current_score as i8),
                 });
@@ -281,6 +377,28 @@ impl ChannelAnalysis {
             }
         }
 
+        // Recommend a better 6 GHz channel when already on 6 GHz
+        if let (Some(best), Some(current_ch)) = (best_6, current) {
+            if current_ch.band == WifiBand::Band6GHz
+                && best.channel.number != current_ch.number
+                && best.score > current_score + 10
+            {
+                recommendations.push(ChannelRecommendation {
+                    channel: best.channel.clone(),
+                    reason: format!(
+                        "Preferred scanning channel {} is less congested ({} APs)",
+                        best.channel.number, best.ap_count
+                    ),
+                    expected_improvement: format!(
+                        "Reduced interference on 6 GHz{}",
+                        Self::indoor_note(best)
+                    ),
+                    priority: 2,
+                    score_improvement: (best.score as i8 - current_score as i8),
+                });
+            }
+        }
+
         // Mention DFS channels if they're significantly better
         if let Some(best) = best_5_dfs {
             if best.score > current_score + 25 {
@@ -305,6 +423,15 @@ impl ChannelAnalysis {
         recommendations
     }
 
+    /// Note for channels restricted to indoor use.
+    fn indoor_note(info: &ChannelInfo) -> &'static str {
+        if info.indoor_only {
+            " (indoor use only)"
+        } else {
+            ""
+        }
+    }
+
     /// Calculate overall channel health score.
     fn calculate_health_score(
         current_utilization: &Option<ChannelUtilization>,
@@ -329,11 +456,24 @@ impl ChannelAnalysis {
         }
     }
 
-    /// Get the best channel for a given band.
+    /// How busy the current channel is (0-100): measured utilization, or
+    /// the BSS Load advertised by APs on it.
+    pub fn current_busy_percent(&self) -> Option<f32> {
+        if let Some(utilization) = &self.current_utilization {
+            return Some(utilization.utilization_percent);
+        }
+        let current = self.current_channel.as_ref()?;
+        self.channels
+            .iter()
+            .find(|c| c.channel.band == current.band && c.channel.number == current.number)
+            .and_then(|c| c.advertised_utilization)
+    }
+
+    /// Get the best legal channel for a given band.
     pub fn best_channel(&self, band: WifiBand) -> Option<&ChannelInfo> {
         self.channels
             .iter()
-            .filter(|c| c.channel.band == band)
+            .filter(|c| c.channel.band == band && c.allowed)
             .max_by_key(|c| c.score)
     }
 
@@ -347,3 +487,100 @@ impl ChannelAnalysis {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_elements;
+    use netdiag_types::wifi::Ssid;
+
+    fn access_point(frequency: u32, elements: &[u8]) -> AccessPoint {
+        let mut ap = AccessPoint {
+            ssid: Ssid::new("office"),
+            rssi: -65,
+            signal_quality: 70,
+            channel: Channel::from_frequency(frequency),
+            ..AccessPoint::test_default()
+        };
+        apply_elements(&mut ap, elements);
+        ap
+    }
+
+    #[test]
+    fn test_bonded_channels_and_bss_load() {
+        // 80 MHz around channel 42, 20 stations, 60% busy
+        let wide = access_point(5180, &[192, 3, 1, 42, 0, 11, 5, 20, 0, 153, 0, 0]);
+        let analysis = ChannelAnalysis::analyze(&[wide], None, &[]);
+
+        for number in [36, 40, 44, 48] {
+            let info = analysis
+                .channels
+                .iter()
+                .find(|c| c.channel.number == number)
+                .unwrap();
+            assert_eq!(info.ap_count, 1, "channel {number}");
+            assert_eq!(info.station_count, 20);
+            assert_eq!(info.advertised_utilization, Some(60.0));
+        }
+        let clear = analysis
+            .channels
+            .iter()
+            .find(|c| c.channel.number == 52)
+            .unwrap();
+        assert_eq!(clear.ap_count, 0);
+        assert_eq!(clear.advertised_utilization, None);
+
+        let busy = analysis
+            .channels
+            .iter()
+            .find(|c| c.channel.number == 44)
+            .unwrap();
+        assert!(busy.score < clear.score);
+    }
+
+    #[test]
+    fn test_regulatory_domain() {
+        // Busy 1/6/11 plus a network on channel 13
+        let aps: Vec<_> = [2412, 2412, 2437, 2437, 2462, 2462, 2472]
+            .into_iter()
+            .map(|f| access_point(f, &[]))
+            .collect();
+
+        let us = ChannelAnalysis::analyze_in(&aps, None, &[], &RegulatoryDomain::for_country("US"));
+        let ch13 = us.channels.iter().find(|c| c.channel.number == 13).unwrap();
+        assert!(!ch13.allowed);
+        assert_eq!(ch13.score, 0);
+        assert!(us.channels.iter().all(|c| c.channel.number != 12));
+        assert!(
+            us.best_channel(WifiBand::Band2_4GHz)
+                .unwrap()
+                .channel
+                .number
+                <= 11
+        );
+        assert!(us
+            .recommendations
+            .iter()
+            .all(|r| r.channel.number <= 11 || r.channel.band != WifiBand::Band2_4GHz));
+        assert!(us
+            .channels
+            .iter()
+            .any(|c| c.channel.number == 149 && c.allowed));
+        assert!(us
+            .channels
+            .iter()
+            .all(|c| c.channel.band != WifiBand::Band6GHz));
+
+        let de = ChannelAnalysis::analyze_in(&aps, None, &[], &RegulatoryDomain::for_country("DE"));
+        assert!(de
+            .channels
+            .iter()
+            .any(|c| c.channel.number == 13 && c.allowed));
+        let ch36 = de.channels.iter().find(|c| c.channel.number == 36).unwrap();
+        assert!(ch36.indoor_only);
+        assert!(de.channels.iter().all(|c| c.channel.number != 149));
+        assert!(de
+            .best_channel(WifiBand::Band5GHz)
+            .is_some_and(|c| c.channel.number <= 140));
+    }
+}
@@ -0,0 +1,688 @@
+//! 802.11 information element parsing.
+//!
+//! Decodes the raw information elements of a beacon or probe response, as
+//! reported by nl80211 or found in captured management frames, and applies
+//! them to an [`AccessPoint`].
+
+use netdiag_types::wifi::{
+    AccessPoint, AkmSuite, BssLoad, Channel, CipherSuite, CountryChannels, CountryInfo,
+    EhtCapabilities, ExtendedCapabilities, HeCapabilities, HeOperation, HtCapabilities,
+    HtOperation, InformationElements, KeyManagement, MobilityDomain, RmCapabilities, RsnInfo,
+    SecondaryChannelPosition, SecurityType, SixGhzOperation, VhtCapabilities, VhtOperation,
+    WifiAuthentication, WifiEncryption,
+};
+
+/// Element IDs.
+mod id {
+    pub const SSID: u8 = 0;
+    pub const SUPPORTED_RATES: u8 = 1;
+    pub const COUNTRY: u8 = 7;
+    pub const BSS_LOAD: u8 = 11;
+    pub const HT_CAPABILITIES: u8 = 45;
+    pub const RSN: u8 = 48;
+    pub const EXTENDED_RATES: u8 = 50;
+    pub const MOBILITY_DOMAIN: u8 = 54;
+    pub const HT_OPERATION: u8 = 61;
+    pub const RM_ENABLED_CAPABILITIES: u8 = 70;
+    pub const EXTENDED_CAPABILITIES: u8 = 127;
+    pub const VHT_CAPABILITIES: u8 = 191;
+    pub const VHT_OPERATION: u8 = 192;
+    pub const VENDOR_SPECIFIC: u8 = 221;
+    pub const EXTENSION: u8 = 255;
+
+    // Extension element IDs
+    pub const HE_CAPABILITIES: u8 = 35;
+    pub const HE_OPERATION: u8 = 36;
+    pub const EHT_CAPABILITIES: u8 = 108;
+}
+
+/// IEEE 802.11 suite OUI.
+const OUI_IEEE: [u8; 3] = [0x00, 0x0f, 0xac];
+/// Microsoft OUI (WPA, WMM, WPS).
+const OUI_MICROSOFT: [u8; 3] = [0x00, 0x50, 0xf2];
+
+/// Parse a buffer of information elements.
+///
+/// Truncated or malformed elements are skipped; parsing stops at the first
+/// element whose length runs past the end of the buffer.
+pub fn parse_elements(mut data: &[u8]) -> InformationElements {
+    let mut elements = InformationElements::default();
+    while let [element_id, len, rest @ ..] = data {
+        let len = *len as usize;
+        let Some(body) = rest.get(..len) else {
+            break;
+        };
+        parse_element(&mut elements, *element_id, body);
+        data = &rest[len..];
+    }
+    elements
+}
+
+/// Decode one element into `elements`.
+fn parse_element(elements: &mut InformationElements, element_id: u8, body: &[u8]) {
+    match element_id {
+        id::SSID => elements.ssid = Some(String::from_utf8_lossy(body).into_owned()),
+        id::SUPPORTED_RATES | id::EXTENDED_RATES => {
+            for &rate in body {
+                // Values from 122 up are BSS membership selectors, not rates
+                if rate & 0x7f >= 0x7a {
+                    continue;
+                }
+                let mbps = f32::from(rate & 0x7f) / 2.0;
+                elements.supported_rates.push(mbps);
+                if rate & 0x80 != 0 {
+                    elements.basic_rates.push(mbps);
+                }
+            }
+        }
+        id::COUNTRY => elements.country = parse_country(body),
+        id::BSS_LOAD if body.len() >= 5 => {
+            elements.bss_load = Some(BssLoad {
+                station_count: u16::from_le_bytes([body[0], body[1]]),
+                channel_utilization: body[2],
+                available_admission_capacity: u16::from_le_bytes([body[3], body[4]]),
+            });
+        }
+        id::HT_CAPABILITIES => elements.ht_capabilities = parse_ht_capabilities(body),
+        id::HT_OPERATION if body.len() >= 2 => {
+            elements.ht_operation = Some(HtOperation {
+                primary_channel: body[0],
+                secondary_channel: match body[1] & 0x03 {
+                    1 => SecondaryChannelPosition::Above,
+                    3 => SecondaryChannelPosition::Below,
+                    _ => SecondaryChannelPosition::None,
+                },
+                sta_channel_width: body[1] & 0x04 != 0,
+            });
+        }
+        id::RSN => elements.rsn = parse_rsn(body, OUI_IEEE),
+        id::MOBILITY_DOMAIN if body.len() >= 3 => {
+            elements.mobility_domain = Some(MobilityDomain {
+                mdid: u16::from_le_bytes([body[0], body[1]]),
+                ft_over_ds: body[2] & 0x01 != 0,
+            });
+        }
+        id::RM_ENABLED_CAPABILITIES if !body.is_empty() => {
+            elements.rm_capabilities = Some(RmCapabilities {
+                neighbor_report: body[0] & 0x02 != 0,
+                beacon_passive: body[0] & 0x10 != 0,
+                beacon_active: body[0] & 0x20 != 0,
+                beacon_table: body[0] & 0x40 != 0,
+            });
+        }
+        id::EXTENDED_CAPABILITIES => {
+            let bit = |n: usize| {
+                body.get(n / 8)
+                    .is_some_and(|byte| byte & (1 << (n % 8)) != 0)
+            };
+            elements.extended_capabilities = Some(ExtendedCapabilities {
+                bss_transition: bit(19),
+                multiple_bssid: bit(22),
+                interworking: bit(31),
+            });
+        }
+        id::VHT_CAPABILITIES => elements.vht_capabilities = parse_vht_capabilities(body),
+        id::VHT_OPERATION if body.len() >= 3 => {
+            elements.vht_operation = Some(VhtOperation {
+                channel_width: body[0],
+                center_segment0: body[1],
+                center_segment1: body[2],
+            });
+        }
+        id::VENDOR_SPECIFIC if body.len() >= 4 => {
+            let oui = [body[0], body[1], body[2]];
+            match (oui, body[3]) {
+                (OUI_MICROSOFT, 1) => elements.wpa = parse_rsn(&body[4..], OUI_MICROSOFT),
+                (OUI_MICROSOFT, 2) => elements.wmm = true,
+                (OUI_MICROSOFT, 4) => elements.wps = true,
+                _ => {
+                    if !elements.vendor_ouis.contains(&oui) {
+                        elements.vendor_ouis.push(oui);
+                    }
+                }
+            }
+        }
+        id::EXTENSION => match body.split_first() {
+            Some((&id::HE_CAPABILITIES, rest)) => {
+                elements.he_capabilities = parse_he_capabilities(rest);
+            }
+            Some((&id::HE_OPERATION, rest)) => elements.he_operation = parse_he_operation(rest),
+            Some((&id::EHT_CAPABILITIES, rest)) if rest.len() >= 3 => {
+                // Two bytes of MAC capabilities, then the PHY capabilities
+                elements.eht_capabilities = Some(EhtCapabilities {
+                    supports_320mhz: rest[2] & 0x02 != 0,
+                });
+            }
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+/// Parse the country element.
+fn parse_country(body: &[u8]) -> Option<CountryInfo> {
+    let code = body.get(..2)?;
+    let channels = body
+        .get(3..)
+        .unwrap_or_default()
+        .chunks(3)
+        .filter(|triplet| triplet.len() == 3)
+        // First channel numbers of 201 and above are operating extensions
+        .filter(|triplet| triplet[0] < 201)
+        .map(|triplet| CountryChannels {
+            first_channel: triplet[0],
+            count: triplet[1],
+            max_power_dbm: triplet[2] as i8,
+        })
+        .collect();
+    Some(CountryInfo {
+        code: String::from_utf8_lossy(code).into_owned(),
+        environment: body.get(2).map_or(' ', |&b| char::from(b)),
+        channels,
+    })
+}
+
+/// Parse an RSN element, or the body of a WPA vendor element.
+fn parse_rsn(body: &[u8], oui: [u8; 3]) -> Option<RsnInfo> {
+    let version = u16::from_le_bytes([*body.first()?, *body.get(1)?]);
+    let mut rest = &body[2..];
+
+    let suite = |rest: &mut &[u8]| -> Option<u32> {
+        let bytes = rest.get(..4)?;
+        *rest = &rest[4..];
+        Some(u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    };
+    let group_cipher = suite(&mut rest).map(|s| cipher_suite(s, oui));
+
+    let suite_list = |rest: &mut &[u8]| -> Vec<u32> {
+        let Some(count) = rest.get(..2).map(|c| u16::from_le_bytes([c[0], c[1]])) else {
+            return Vec::new();
+        };
+        *rest = &rest[2..];
+        (0..count).map_while(|_| suite(rest)).collect()
+    };
+    let pairwise_ciphers = suite_list(&mut rest)
+        .into_iter()
+        .map(|s| cipher_suite(s, oui))
+        .collect();
+    let akm_suites = suite_list(&mut rest)
+        .into_iter()
+        .map(|s| akm_suite(s, oui))
+        .collect();
+
+    let capabilities = rest
+        .get(..2)
+        .map_or(0, |c| u16::from_le_bytes([c[0], c[1]]));
+    // PMKID list, then the group management cipher
+    let group_management_cipher = rest.get(2..).and_then(|after_caps| {
+        let count = after_caps
+            .get(..2)
+            .map(|c| usize::from(u16::from_le_bytes([c[0], c[1]])))?;
+        let mut tail = after_caps.get(2 + count * 16..)?;
+        suite(&mut tail).map(|s| cipher_suite(s, oui))
+    });
+
+    Some(RsnInfo {
+        version,
+        group_cipher,
+        pairwise_ciphers,
+        akm_suites,
+        mfp_capable: capabilities & 0x0080 != 0,
+        mfp_required: capabilities & 0x0040 != 0,
+        group_management_cipher,
+    })
+}
+
+/// Split a suite selector into OUI and type if the OUI matches.
+fn suite_type(selector: u32, oui: [u8; 3]) -> Option<u8> {
+    let [a, b, c, kind] = selector.to_be_bytes();
+    ([a, b, c] == oui).then_some(kind)
+}
+
+/// Decode a cipher suite selector.
+fn cipher_suite(selector: u32, oui: [u8; 3]) -> CipherSuite {
+    match suite_type(selector, oui) {
+        Some(0) => CipherSuite::UseGroup,
+        Some(1) => CipherSuite::Wep40,
+        Some(2) => CipherSuite::Tkip,
+        Some(4) => CipherSuite::Ccmp,
+        Some(5) => CipherSuite::Wep104,
+        Some(6) => CipherSuite::BipCmac128,
+        Some(8) => CipherSuite::Gcmp,
+        Some(9) => CipherSuite::Gcmp256,
+        Some(10) => CipherSuite::Ccmp256,
+        Some(11) => CipherSuite::BipGmac128,
+        Some(12) => CipherSuite::BipGmac256,
+        Some(13) => CipherSuite::BipCmac256,
+        _ => CipherSuite::Other(selector),
+    }
+}
+
+/// Decode an AKM suite selector.
+fn akm_suite(selector: u32, oui: [u8; 3]) -> AkmSuite {
+    match suite_type(selector, oui) {
+        Some(1) => AkmSuite::Ieee8021x,
+        Some(2) => AkmSuite::Psk,
+        Some(3) => AkmSuite::FtIeee8021x,
+        Some(4) => AkmSuite::FtPsk,
+        Some(5) => AkmSuite::Ieee8021xSha256,
+        Some(6) => AkmSuite::PskSha256,
+        Some(8) => AkmSuite::Sae,
+        Some(9) => AkmSuite::FtSae,
+        Some(11) => AkmSuite::SuiteB,
+        Some(12) => AkmSuite::SuiteB192,
+        Some(13) => AkmSuite::FtIeee8021xSha384,
+        Some(18) => AkmSuite::Owe,
+        Some(24) => AkmSuite::SaeExt,
+        Some(25) => AkmSuite::FtSaeExt,
+        _ => AkmSuite::Other(selector),
+    }
+}
+
+/// Count spatial streams in a VHT/HE MCS map (2 bits per stream, 3 = unsupported).
+fn streams_in_mcs_map(map: u16) -> u8 {
+    (0..8u8)
+        .filter(|stream| (map >> (stream * 2)) & 0x03 != 0x03)
+        .map(|stream| stream + 1)
+        .max()
+        .unwrap_or(0)
+}
+
+/// Parse the HT capabilities element.
+fn parse_ht_capabilities(body: &[u8]) -> Option<HtCapabilities> {
+    let info = u16::from_le_bytes([*body.first()?, *body.get(1)?]);
+    // Supported MCS set starts after the A-MPDU parameters; one byte per stream
+    let max_spatial_streams = body.get(3..7).map_or(1, |mcs| {
+        mcs.iter()
+            .rposition(|&b| b != 0)
+            .map_or(1, |last| last as u8 + 1)
+    });
+    Some(HtCapabilities {
+        supports_40mhz: info & 0x0002 != 0,
+        short_gi_20: info & 0x0020 != 0,
+        short_gi_40: info & 0x0040 != 0,
+        max_spatial_streams,
+    })
+}
+
+/// Parse the VHT capabilities element.
+fn parse_vht_capabilities(body: &[u8]) -> Option<VhtCapabilities> {
+    let info = u32::from_le_bytes(body.get(..4)?.try_into().ok()?);
+    let rx_map = body
+        .get(4..6)
+        .map_or(0xfffe, |m| u16::from_le_bytes([m[0], m[1]]));
+    let width_set = (info >> 2) & 0x03;
+    Some(VhtCapabilities {
+        supports_160mhz: width_set >= 1,
+        supports_80p80mhz: width_set == 2,
+        short_gi_80: info & (1 << 5) != 0,
+        su_beamformer: info & (1 << 11) != 0,
+        mu_beamformer: info & (1 << 19) != 0,
+        max_spatial_streams: streams_in_mcs_map(rx_map).max(1),
+    })
+}
+
+/// Parse the HE capabilities element (after the extension ID).
+fn parse_he_capabilities(body: &[u8]) -> Option<HeCapabilities> {
+    // Six bytes of MAC capabilities, eleven of PHY capabilities, then MCS maps
+    let phy = body.get(6..17)?;
+    let rx_map = body
+        .get(17..19)
+        .map_or(0xfffe, |m| u16::from_le_bytes([m[0], m[1]]));
+    Some(HeCapabilities {
+        supports_160mhz: phy[0] & 0x08 != 0,
+        max_spatial_streams: streams_in_mcs_map(rx_map).max(1),
+    })
+}
+
+/// Parse the HE operation element (after the extension ID).
+fn parse_he_operation(body: &[u8]) -> Option<HeOperation> {
+    let params = u32::from_le_bytes([*body.first()?, *body.get(1)?, *body.get(2)?, 0]);
+    let color = *body.get(3)?;
+
+    // Optional fields follow the basic HE-MCS set
+    let mut offset = 6;
+    if params & (1 << 14) != 0 {
+        offset += 3; // VHT operation information
+    }
+    if params & (1 << 15) != 0 {
+        offset += 1; // Max co-hosted BSSID indicator
+    }
+    let six_ghz = if params & (1 << 17) != 0 {
+        body.get(offset..offset + 4).map(|info| SixGhzOperation {
+            primary_channel: info[0],
+            channel_width: info[1] & 0x03,
+            center_segment0: info[2],
+            center_segment1: info[3],
+        })
+    } else {
+        None
+    };
+
+    Some(HeOperation {
+        bss_color: color & 0x3f,
+        bss_color_disabled: color & 0x80 != 0,
+        six_ghz,
+    })
+}
+
+/// Security configuration advertised by a BSS.
+///
+/// `privacy` is the privacy bit of the capability information field, which
+/// distinguishes WEP from open networks when no RSN or WPA element is present.
+pub fn elements_security(elements: &InformationElements, privacy: bool) -> SecurityType {
+    let Some(rsn) = &elements.rsn else {
+        return if let Some(wpa) = &elements.wpa {
+            SecurityType {
+                authentication: WifiAuthentication::Wpa,
+                encryption: best_cipher(&wpa.pairwise_ciphers).unwrap_or(WifiEncryption::Tkip),
+                key_management: if wpa.akm_suites.iter().any(AkmSuite::is_enterprise) {
+                    KeyManagement::Eap
+                } else {
+                    KeyManagement::Psk
+                },
+                pmf_required: false,
+                transition_mode: false,
+            }
+        } else if privacy {
+            SecurityType {
+                authentication: WifiAuthentication::Wep,
+                encryption: WifiEncryption::Wep,
+                ..SecurityType::open()
+            }
+        } else {
+            SecurityType::open()
+        };
+    };
+
+    let (authentication, key_management) = if rsn.has_akm(AkmSuite::SuiteB192) {
+        (WifiAuthentication::Wpa3, KeyManagement::EapSuiteB192)
+    } else if rsn.has_akm(AkmSuite::SuiteB) {
+        (WifiAuthentication::Wpa3, KeyManagement::EapSuiteB)
+    } else if rsn.has_akm(AkmSuite::Sae) || rsn.has_akm(AkmSuite::SaeExt) {
+        (WifiAuthentication::Wpa3, KeyManagement::Sae)
+    } else if rsn.has_akm(AkmSuite::FtSae) || rsn.has_akm(AkmSuite::FtSaeExt) {
+        (WifiAuthentication::Wpa3, KeyManagement::FtSae)
+    } else if rsn.has_akm(AkmSuite::Ieee8021xSha256) && rsn.mfp_required {
+        // WPA3-Enterprise (non-192-bit mode)
+        (WifiAuthentication::Wpa3, KeyManagement::Eap)
+    } else if rsn.has_akm(AkmSuite::Ieee8021x) || rsn.has_akm(AkmSuite::Ieee8021xSha256) {
+        (WifiAuthentication::Wpa2, KeyManagement::Eap)
+    } else if rsn.has_akm(AkmSuite::FtIeee8021x) || rsn.has_akm(AkmSuite::FtIeee8021xSha384) {
+        (WifiAuthentication::Wpa2, KeyManagement::FtEap)
+    } else if rsn.has_akm(AkmSuite::Psk) || rsn.has_akm(AkmSuite::PskSha256) {
+        (WifiAuthentication::Wpa2, KeyManagement::Psk)
+    } else if rsn.has_akm(AkmSuite::FtPsk) {
+        (WifiAuthentication::Wpa2, KeyManagement::FtPsk)
+    } else if rsn.has_akm(AkmSuite::Owe) {
+        (WifiAuthentication::Owe, KeyManagement::Owe)
+    } else {
+        (WifiAuthentication::Wpa2, KeyManagement::None)
+    };
+
+    SecurityType {
+        authentication,
+        encryption: best_cipher(&rsn.pairwise_ciphers).unwrap_or(WifiEncryption::Ccmp),
+        key_management,
+        pmf_required: rsn.mfp_required,
+        // SAE alongside PSK, or WPA alongside WPA2
+        transition_mode: rsn.is_wpa3_transition() || elements.wpa.is_some(),
+    }
+}
+
+/// Strongest pairwise cipher offered.
+fn best_cipher(ciphers: &[CipherSuite]) -> Option<WifiEncryption> {
+    [
+        WifiEncryption::Gcmp256,
+        WifiEncryption::Gcmp,
+        WifiEncryption::Ccmp,
+        WifiEncryption::Tkip,
+        WifiEncryption::Wep,
+    ]
+    .into_iter()
+    .find(|encryption| ciphers.iter().any(|c| c.encryption() == *encryption))
+}
+
+/// Parse raw information elements and apply them to an access point.
+///
+/// Security, channel width, standard, rates, country and roaming
+/// capabilities are taken from the elements; the decoded elements are kept
+/// in [`AccessPoint::elements`].
+pub fn apply_elements(ap: &mut AccessPoint, data: &[u8]) {
+    let elements = parse_elements(data);
+
+    ap.security = elements_security(&elements, ap.capabilities.privacy);
+
+    if let Some((width, center)) = elements.operating_width() {
+        ap.channel.width = width;
+        ap.channel.center_frequency = Some(Channel::from_number(center, ap.channel.band).frequency);
+    }
+    if let Some(ht) = &elements.ht_operation {
+        ap.channel.secondary_position = Some(ht.secondary_channel);
+    }
+    if let Some(standard) = elements.wifi_standard() {
+        ap.wifi_standard = standard;
+    }
+    if !elements.supported_rates.is_empty() {
+        ap.supported_rates = elements.supported_rates.clone();
+        ap.max_rate = elements.supported_rates.iter().copied().reduce(f32::max);
+    }
+    if let Some(country) = &elements.country {
+        ap.country_code = Some(country.code.clone());
+    }
+
+    let capabilities = &mut ap.capabilities;
+    capabilities.wmm = elements.wmm;
+    capabilities.wps = elements.wps;
+    capabilities.dot11k = elements.supports_dot11k();
+    capabilities.dot11r = elements.supports_dot11r();
+    capabilities.dot11v = elements.supports_dot11v();
+
+    ap.elements = Some(elements);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_types::wifi::{AccessPointCapabilities, ChannelWidth, WifiBand, WifiStandard};
+
+    fn ie(element_id: u8, body: &[u8]) -> Vec<u8> {
+        let mut out = vec![element_id, body.len() as u8];
+        out.extend_from_slice(body);
+        out
+    }
+
+    fn rsn(pairwise: &[u8], akms: &[u8], capabilities: u8) -> Vec<u8> {
+        let mut body = vec![1, 0, 0x00, 0x0f, 0xac, 4, pairwise.len() as u8, 0];
+        for cipher in pairwise {
+            body.extend_from_slice(&[0x00, 0x0f, 0xac, *cipher]);
+        }
+        body.extend_from_slice(&[akms.len() as u8, 0]);
+        for akm in akms {
+            body.extend_from_slice(&[0x00, 0x0f, 0xac, *akm]);
+        }
+        body.extend_from_slice(&[capabilities, 0]);
+        ie(id::RSN, &body)
+    }
+
+    fn access_point(frequency: u32) -> AccessPoint {
+        AccessPoint {
+            rssi: -55,
+            signal_quality: 90,
+            channel: Channel::from_frequency(frequency),
+            security: SecurityType::open(),
+            wifi_standard: WifiStandard::Unknown,
+            beacon_interval: Some(100),
+            capabilities: AccessPointCapabilities {
+                privacy: true,
+                ..AccessPointCapabilities::default()
+            },
+            ..AccessPoint::test_default()
+        }
+    }
+
+    #[test]
+    fn test_parse_basic_elements() {
+        let mut data = ie(id::SSID, b"office");
+        data.extend(ie(id::SUPPORTED_RATES, &[0x8c, 0x12, 0x98, 0x24, 0xff]));
+        data.extend(ie(id::COUNTRY, b"DE \x24\x04\x17\x64\x0c\x1e"));
+        data.extend(ie(id::BSS_LOAD, &[12, 0, 128, 0x10, 0x27]));
+        // Truncated trailing element is ignored
+        data.extend([id::SSID, 10, b'x']);
+
+        let elements = parse_elements(&data);
+        assert_eq!(elements.ssid.as_deref(), Some("office"));
+        assert_eq!(elements.supported_rates, vec![6.0, 9.0, 12.0, 18.0]);
+        assert_eq!(elements.basic_rates, vec![6.0, 12.0]);
+
+        let country = elements.country.unwrap();
+        assert_eq!(country.code, "DE");
+        assert_eq!(country.channels.len(), 2);
+        assert_eq!(country.channels[0].first_channel, 36);
+        assert_eq!(country.channels[1].max_power_dbm, 30);
+
+        let load = elements.bss_load.unwrap();
+        assert_eq!(load.station_count, 12);
+        assert!((load.utilization_percent() - 50.2).abs() < 0.1);
+        assert_eq!(load.available_admission_capacity, 10000);
+    }
+
+    #[test]
+    fn test_parse_phy_elements() {
+        // HT: 40 MHz + SGI, two streams
+        let mut data = ie(id::HT_CAPABILITIES, &[0x62, 0, 0x17, 0xff, 0xff, 0, 0]);
+        data.extend(ie(id::HT_OPERATION, &[36, 0x05, 0, 0, 0, 0]));
+        // VHT: 160 MHz, MU beamformer, three streams
+        data.extend(ie(
+            id::VHT_CAPABILITIES,
+            &[0x24, 0, 0x08, 0, 0xea, 0xff, 0, 0, 0xea, 0xff, 0, 0],
+        ));
+        data.extend(ie(id::VHT_OPERATION, &[1, 42, 50, 0, 0]));
+        // HE with four streams
+        let mut he = vec![id::HE_CAPABILITIES];
+        he.extend([0; 6]);
+        he.extend([0x08, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        he.extend([0xaa, 0xff]);
+        data.extend(ie(id::EXTENSION, &he));
+
+        let elements = parse_elements(&data);
+        let ht = elements.ht_capabilities.unwrap();
+        assert!(ht.supports_40mhz && ht.short_gi_20 && ht.short_gi_40);
+        assert_eq!(ht.max_spatial_streams, 2);
+        let vht = elements.vht_capabilities.unwrap();
+        assert!(vht.supports_160mhz && !vht.supports_80p80mhz && vht.mu_beamformer);
+        assert_eq!(vht.max_spatial_streams, 3);
+        assert!(elements.he_capabilities.unwrap().supports_160mhz);
+
+        assert_eq!(elements.wifi_standard(), Some(WifiStandard::Dot11ax));
+        assert_eq!(elements.max_spatial_streams(), Some(4));
+        assert_eq!(elements.operating_width(), Some((ChannelWidth::Mhz160, 50)));
+    }
+
+    #[test]
+    fn test_parse_six_ghz_operation() {
+        // 6 GHz operation info present, 160 MHz around channel 15
+        let body = [
+            id::HE_OPERATION,
+            0x00,
+            0x00,
+            0x02,
+            0x05,
+            0xfc,
+            0xff,
+            5,
+            0x03,
+            7,
+            15,
+            0,
+        ];
+        let elements = parse_elements(&ie(id::EXTENSION, &body));
+        let op = elements.he_operation.unwrap();
+        assert_eq!(op.bss_color, 5);
+        assert_eq!(elements.operating_width(), Some((ChannelWidth::Mhz160, 15)));
+    }
+
+    #[test]
+    fn test_roaming_capabilities() {
+        let mut data = rsn(&[4], &[2, 4], 0x80);
+        data.extend(ie(id::MOBILITY_DOMAIN, &[0x34, 0x12, 0x01]));
+        data.extend(ie(id::RM_ENABLED_CAPABILITIES, &[0x72, 0, 0, 0, 0]));
+        data.extend(ie(id::EXTENDED_CAPABILITIES, &[0, 0, 0x08, 0x80]));
+
+        let elements = parse_elements(&data);
+        assert_eq!(elements.mobility_domain.unwrap().mdid, 0x1234);
+        assert!(elements.supports_dot11k());
+        assert!(elements.supports_dot11r());
+        assert!(elements.supports_dot11v());
+        assert!(elements.extended_capabilities.unwrap().interworking);
+    }
+
+    #[test]
+    fn test_security() {
+        let open = parse_elements(&[]);
+        assert_eq!(elements_security(&open, false), SecurityType::open());
+        assert_eq!(
+            elements_security(&open, true).authentication,
+            WifiAuthentication::Wep
+        );
+
+        let wpa2 = parse_elements(&rsn(&[4], &[2], 0));
+        assert_eq!(
+            elements_security(&wpa2, true),
+            SecurityType::wpa2_personal()
+        );
+
+        let transition = elements_security(&parse_elements(&rsn(&[4], &[2, 8], 0x80)), true);
+        assert_eq!(transition.authentication, WifiAuthentication::Wpa3);
+        assert_eq!(transition.key_management, KeyManagement::Sae);
+        assert!(transition.transition_mode && !transition.pmf_required);
+
+        let sae = elements_security(&parse_elements(&rsn(&[8], &[8], 0xc0)), true);
+        assert_eq!(sae.encryption, WifiEncryption::Gcmp);
+        assert!(sae.pmf_required && !sae.transition_mode);
+
+        let enterprise = elements_security(&parse_elements(&rsn(&[4], &[1], 0)), true);
+        assert!(enterprise.is_enterprise());
+
+        // WPA/WPA2 mixed mode with TKIP
+        let mut mixed = rsn(&[2, 4], &[2], 0);
+        mixed.extend(ie(
+            id::VENDOR_SPECIFIC,
+            &[
+                0x00, 0x50, 0xf2, 1, 1, 0, 0x00, 0x50, 0xf2, 2, 1, 0, 0x00, 0x50, 0xf2, 2, 1, 0,
+                0x00, 0x50, 0xf2, 2,
+            ],
+        ));
+        let elements = parse_elements(&mixed);
+        assert!(elements.rsn.as_ref().unwrap().uses_tkip());
+        assert_eq!(
+            elements.wpa.as_ref().unwrap().akm_suites,
+            vec![AkmSuite::Psk]
+        );
+        assert!(elements_security(&elements, true).transition_mode);
+    }
+
+    #[test]
+    fn test_apply_elements() {
+        let mut data = ie(id::SSID, b"test");
+        data.extend(rsn(&[4], &[8], 0xc0));
+        data.extend(ie(id::HT_CAPABILITIES, &[0x62, 0, 0x17, 0xff, 0, 0, 0]));
+        data.extend(ie(id::HT_OPERATION, &[36, 0x05, 0, 0, 0, 0]));
+        data.extend(ie(id::VHT_CAPABILITIES, &[0; 12]));
+        data.extend(ie(id::VHT_OPERATION, &[1, 42, 0, 0, 0]));
+        data.extend(ie(id::VENDOR_SPECIFIC, &[0x00, 0x50, 0xf2, 2, 0, 1]));
+        data.extend(ie(id::COUNTRY, b"US "));
+
+        let mut ap = access_point(5180);
+        apply_elements(&mut ap, &data);
+        assert_eq!(ap.security.authentication, WifiAuthentication::Wpa3);
+        assert!(ap.security.pmf_required);
+        assert_eq!(ap.channel.band, WifiBand::Band5GHz);
+        assert_eq!(ap.channel.width, ChannelWidth::Mhz80);
+        assert_eq!(ap.channel.center_frequency, Some(5210));
+        assert_eq!(ap.wifi_standard, WifiStandard::Dot11ac);
+        assert_eq!(ap.country_code.as_deref(), Some("US"));
+        assert!(ap.capabilities.wmm && !ap.capabilities.dot11r);
+        assert!(ap.elements.is_some());
+    }
+}
@@ -0,0 +1,837 @@
+//! 802.1X / WPA-Enterprise authentication diagnostics.
+//!
+//! Works from a [`SupplicantTranscript`]: wpa_supplicant's status, the
+//! network block settings and the events of one or more connection attempts.
+//! Reports the EAP method and identity in use, the stage a failed attempt
+//! stopped at, and likely causes such as RADIUS timeouts or server
+//! certificate problems.
+
+use crate::security::IssueSeverity;
+use netdiag_types::wifi::{EapMethod, Phase2Method, SupplicantMessage, SupplicantTranscript};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// IEEE 802.11 reason code: 4-way handshake timeout.
+const REASON_4WAY_TIMEOUT: u16 = 15;
+
+/// IEEE 802.11 reason code: IEEE 802.1X authentication failed.
+const REASON_8021X_FAILED: u16 = 23;
+
+/// Stage of a connection attempt.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthStage {
+    /// 802.11 authentication and association with the AP.
+    Association,
+    /// EAP exchange with the RADIUS server.
+    Eap,
+    /// 4-way handshake deriving the session keys.
+    KeyHandshake,
+    /// Connected.
+    Completed,
+}
+
+impl std::fmt::Display for AuthStage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            AuthStage::Association => "association",
+            AuthStage::Eap => "EAP authentication",
+            AuthStage::KeyHandshake => "4-way handshake",
+            AuthStage::Completed => "completed",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Kind of enterprise authentication problem.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnterpriseIssueKind {
+    /// The AP rejected authentication or association.
+    AssociationRejected,
+    /// The supplicant asked for an identity or password it doesn't have.
+    MissingCredentials,
+    /// The RADIUS server rejected the credentials.
+    CredentialsRejected,
+    /// The network and the profile have no EAP method in common.
+    MethodRejected,
+    /// No answer from the RADIUS server.
+    RadiusTimeout,
+    /// Server certificate issued by a CA that isn't trusted.
+    UntrustedCa,
+    /// Server certificate name doesn't match the configured server.
+    HostnameMismatch,
+    /// Server certificate has expired.
+    CertificateExpired,
+    /// Server certificate is not valid yet.
+    CertificateNotYetValid,
+    /// Server certificate has been revoked.
+    CertificateRevoked,
+    /// Server certificate is otherwise unacceptable.
+    CertificateInvalid,
+    /// EAP succeeded but the 4-way handshake failed.
+    HandshakeFailed,
+    /// The profile doesn't validate the server certificate.
+    ServerNotValidated,
+}
+
+impl std::fmt::Display for EnterpriseIssueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            EnterpriseIssueKind::AssociationRejected => "Association rejected",
+            EnterpriseIssueKind::MissingCredentials => "Missing credentials",
+            EnterpriseIssueKind::CredentialsRejected => "Credentials rejected",
+            EnterpriseIssueKind::MethodRejected => "EAP method rejected",
+            EnterpriseIssueKind::RadiusTimeout => "RADIUS timeout",
+            EnterpriseIssueKind::UntrustedCa => "Untrusted CA",
+            EnterpriseIssueKind::HostnameMismatch => "Hostname mismatch",
+            EnterpriseIssueKind::CertificateExpired => "Certificate expired",
+            EnterpriseIssueKind::CertificateNotYetValid => "Certificate not yet valid",
+            EnterpriseIssueKind::CertificateRevoked => "Certificate revoked",
+            EnterpriseIssueKind::CertificateInvalid => "Invalid certificate",
+            EnterpriseIssueKind::HandshakeFailed => "4-way handshake failed",
+            EnterpriseIssueKind::ServerNotValidated => "Server not validated",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// An enterprise authentication problem.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EnterpriseIssue {
+    /// Issue kind.
+    pub kind: EnterpriseIssueKind,
+
+    /// Severity.
+    pub severity: IssueSeverity,
+
+    /// What was observed.
+    pub description: String,
+
+    /// How to fix it.
+    pub remediation: String,
+}
+
+/// A certificate the RADIUS server presented.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerCertificate {
+    /// Position in the chain (0 = server certificate).
+    pub depth: u8,
+
+    /// Subject distinguished name.
+    pub subject: String,
+
+    /// Subject alternative names.
+    pub alt_names: Vec<String>,
+
+    /// SHA-256 hash as reported by wpa_supplicant.
+    pub hash: Option<String>,
+}
+
+/// Result of diagnosing enterprise authentication.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EnterpriseDiagnosis {
+    /// Network name.
+    pub ssid: Option<String>,
+
+    /// AP the supplicant is (or was last) associated with.
+    pub bssid: Option<String>,
+
+    /// Key management in use, e.g. `WPA2/IEEE 802.1X/EAP`.
+    pub key_mgmt: Option<String>,
+
+    /// Whether the network uses 802.1X.
+    pub is_enterprise: bool,
+
+    /// wpa_supplicant state, e.g. `COMPLETED`.
+    pub wpa_state: Option<String>,
+
+    /// EAP method negotiated or configured.
+    pub eap_method: Option<EapMethod>,
+
+    /// Inner method for PEAP/TTLS.
+    pub phase2_method: Option<Phase2Method>,
+
+    /// Identity (inner identity for tunneled methods).
+    pub identity: Option<String>,
+
+    /// Outer identity sent in the clear.
+    pub anonymous_identity: Option<String>,
+
+    /// Server name the profile checks the certificate against.
+    pub server_match: Option<String>,
+
+    /// Whether the last attempt authenticated.
+    pub authenticated: bool,
+
+    /// Stage the last attempt failed at.
+    pub failed_stage: Option<AuthStage>,
+
+    /// EAP exchanges started.
+    pub eap_attempts: usize,
+
+    /// Certificate chain presented by the server.
+    pub server_certificates: Vec<ServerCertificate>,
+
+    /// Problems found.
+    pub issues: Vec<EnterpriseIssue>,
+}
+
+/// Tracks one connection attempt through the events.
+#[derive(Default)]
+struct Attempt {
+    stage: Option<AuthStage>,
+    failed: bool,
+    cert_error: bool,
+    method_selected: bool,
+}
+
+impl EnterpriseDiagnosis {
+    /// Diagnose a recorded supplicant session.
+    pub fn from_transcript(transcript: &SupplicantTranscript) -> Self {
+        let status = status_fields(transcript);
+        let network = network_fields(transcript);
+
+        let mut diagnosis = Self {
+            ssid: status.get("ssid").cloned(),
+            bssid: status.get("bssid").cloned(),
+            key_mgmt: status.get("key_mgmt").cloned(),
+            wpa_state: status.get("wpa_state").cloned(),
+            eap_method: status
+                .get("selectedMethod")
+                .and_then(|m| m.split_whitespace().next()?.parse().ok())
+                .and_then(EapMethod::from_type)
+                .or_else(|| {
+                    network
+                        .get("eap")
+                        .and_then(|v| v.as_deref())
+                        .and_then(|eap| {
+                            eap.split_whitespace()
+                                .find_map(EapMethod::from_supplicant_name)
+                        })
+                }),
+            phase2_method: network
+                .get("phase2")
+                .and_then(|v| v.as_deref())
+                .and_then(parse_phase2),
+            identity: network.get("identity").cloned().flatten(),
+            anonymous_identity: network.get("anonymous_identity").cloned().flatten(),
+            server_match: [
+                "domain_suffix_match",
+                "domain_match",
+                "altsubject_match",
+                "subject_match",
+            ]
+            .iter()
+            .find_map(|key| network.get(*key).cloned().flatten()),
+            ..Self::default()
+        };
+        diagnosis.is_enterprise = diagnosis
+            .key_mgmt
+            .as_deref()
+            .is_some_and(|k| k.contains("802.1X") || k.contains("EAP"))
+            || network.get("eap").is_some_and(Option::is_some);
+
+        let mut attempt = Attempt::default();
+        for event in transcript.events() {
+            diagnosis.apply_event(event, &mut attempt);
+        }
+
+        // Without a failure in the events, fall back to the current state
+        if attempt.stage.is_none() {
+            attempt.stage = diagnosis
+                .wpa_state
+                .as_deref()
+                .and_then(|state| match state {
+                    "AUTHENTICATING" | "ASSOCIATING" => Some(AuthStage::Association),
+                    "ASSOCIATED" => Some(AuthStage::Eap),
+                    "4WAY_HANDSHAKE" | "GROUP_HANDSHAKE" => Some(AuthStage::KeyHandshake),
+                    "COMPLETED" => Some(AuthStage::Completed),
+                    _ => None,
+                });
+        }
+        diagnosis.authenticated = attempt.stage == Some(AuthStage::Completed) && !attempt.failed;
+        if attempt.failed {
+            diagnosis.failed_stage = attempt.stage;
+        }
+
+        diagnosis.check_profile(&network);
+        diagnosis
+    }
+
+    /// Issues that stop authentication (as opposed to configuration advice).
+    pub fn failures(&self) -> impl Iterator<Item = &EnterpriseIssue> {
+        self.issues
+            .iter()
+            .filter(|i| i.kind != EnterpriseIssueKind::ServerNotValidated)
+    }
+
+    fn apply_event(&mut self, event: &str, attempt: &mut Attempt) {
+        let (name, args) = event.split_once(' ').unwrap_or((event, ""));
+        match name {
+            "Trying" | "SME:" if event.contains("Trying to a") => {
+                *attempt = Attempt {
+                    stage: Some(AuthStage::Association),
+                    ..Attempt::default()
+                };
+            }
+            "Associated" => attempt.stage = Some(AuthStage::Association),
+            "CTRL-EVENT-ASSOC-REJECT" | "CTRL-EVENT-AUTH-REJECT" => {
+                attempt.stage = Some(AuthStage::Association);
+                attempt.failed = true;
+                let status = field(args, "status_code").unwrap_or("?");
+                self.add_issue(
+                    EnterpriseIssueKind::AssociationRejected,
+                    IssueSeverity::High,
+                    format!(
+                        "The access point rejected the connection (status code {})",
+                        status
+                    ),
+                    "Check that the AP accepts this client (MAC filtering, client limits, \
+                     supported security modes)",
+                );
+            }
+            "CTRL-EVENT-EAP-STARTED" => {
+                *attempt = Attempt {
+                    stage: Some(AuthStage::Eap),
+                    ..Attempt::default()
+                };
+                self.eap_attempts += 1;
+            }
+            "CTRL-EVENT-EAP-METHOD" => {
+                attempt.method_selected = true;
+                if let Some(method) = eap_method_number(args).and_then(EapMethod::from_type) {
+                    self.eap_method = Some(method);
+                }
+            }
+            "CTRL-EVENT-EAP-PEER-CERT" => {
+                let depth = field(args, "depth")
+                    .and_then(|d| d.parse().ok())
+                    .unwrap_or(0);
+                let certificate = ServerCertificate {
+                    depth,
+                    subject: quoted_field(args, "subject")
+                        .unwrap_or_default()
+                        .to_string(),
+                    alt_names: Vec::new(),
+                    hash: field(args, "hash").map(str::to_string),
+                };
+                // Each attempt resends the chain
+                self.server_certificates.retain(|c| c.depth != depth);
+                self.server_certificates.push(certificate);
+                self.server_certificates.sort_by_key(|c| c.depth);
+            }
+            "CTRL-EVENT-EAP-PEER-ALT" => {
+                let depth: u8 = field(args, "depth")
+                    .and_then(|d| d.parse().ok())
+                    .unwrap_or(0);
+                let name = args.split_whitespace().nth(1).unwrap_or_default();
+                if let Some(cert) = self
+                    .server_certificates
+                    .iter_mut()
+                    .find(|c| c.depth == depth)
+                {
+                    if !cert.alt_names.iter().any(|n| n == name) {
+                        cert.alt_names.push(name.to_string());
+                    }
+                }
+            }
+            "CTRL-EVENT-EAP-TLS-CERT-ERROR" => {
+                attempt.failed = true;
+                attempt.cert_error = true;
+                self.certificate_error(args);
+            }
+            "CTRL-EVENT-EAP-STATUS" => self.eap_status(args, attempt),
+            "CTRL-EVENT-EAP-SUCCESS" => attempt.stage = Some(AuthStage::KeyHandshake),
+            "CTRL-EVENT-EAP-FAILURE" => {
+                attempt.failed = true;
+                if !attempt.cert_error {
+                    self.credentials_rejected(attempt);
+                }
+            }
+            "CTRL-EVENT-EAP-TIMEOUT-FAILURE" | "CTRL-EVENT-EAP-TIMEOUT-FAILURE2" => {
+                attempt.failed = true;
+                self.radius_timeout();
+            }
+            "WPA:" if args.starts_with("4-Way Handshake failed") => {
+                attempt.stage = Some(AuthStage::KeyHandshake);
+                attempt.failed = true;
+                self.handshake_failed();
+            }
+            "CTRL-EVENT-DISCONNECTED" => {
+                let reason = field(args, "reason").and_then(|r| r.parse().ok());
+                self.disconnected(reason, attempt);
+            }
+            "CTRL-EVENT-CONNECTED" => {
+                *attempt = Attempt {
+                    stage: Some(AuthStage::Completed),
+                    ..Attempt::default()
+                };
+            }
+            _ if name.starts_with("CTRL-REQ-IDENTITY")
+                || name.starts_with("CTRL-REQ-PASSWORD")
+                || name.starts_with("CTRL-REQ-NEW_PASSWORD") =>
+            {
+                let what = if name.starts_with("CTRL-REQ-IDENTITY") {
+                    "an identity"
+                } else {
+                    "a password"
+                };
+                self.add_issue(
+                    EnterpriseIssueKind::MissingCredentials,
+                    IssueSeverity::High,
+                    format!("wpa_supplicant asked for {} that isn't configured", what),
+                    "Add the username and password to the network profile",
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn eap_status(&mut self, args: &str, attempt: &mut Attempt) {
+        let status = quoted_field(args, "status").unwrap_or_default();
+        let parameter = quoted_field(args, "parameter").unwrap_or_default();
+        match status {
+            "refuse proposed method" => {
+                attempt.failed = true;
+                self.add_issue(
+                    EnterpriseIssueKind::MethodRejected,
+                    IssueSeverity::High,
+                    format!(
+                        "The server proposed EAP-{}, which this profile doesn't allow",
+                        parameter
+                    ),
+                    "Configure the EAP method the network uses (ask the network administrator)",
+                );
+            }
+            "local TLS alert" if parameter == "unknown CA" && !attempt.cert_error => {
+                attempt.failed = true;
+                attempt.cert_error = true;
+                self.add_issue(
+                    EnterpriseIssueKind::UntrustedCa,
+                    IssueSeverity::High,
+                    format!(
+                        "The RADIUS server certificate{} is not signed by a trusted CA",
+                        self.server_subject()
+                    ),
+                    "Install the CA certificate that issued the RADIUS server certificate and \
+                     reference it as ca_cert",
+                );
+            }
+            "remote TLS alert" => {
+                attempt.failed = true;
+                attempt.cert_error = true;
+                let client_cert = self.eap_method == Some(EapMethod::Tls);
+                self.add_issue(
+                    if client_cert {
+                        EnterpriseIssueKind::CredentialsRejected
+                    } else {
+                        EnterpriseIssueKind::CertificateInvalid
+                    },
+                    IssueSeverity::High,
+                    format!(
+                        "The RADIUS server aborted the TLS handshake ({})",
+                        parameter
+                    ),
+                    if client_cert {
+                        "Check that the client certificate is valid, not expired and issued \
+                         by a CA the RADIUS server trusts"
+                    } else {
+                        "Check the TLS versions and cipher suites the RADIUS server accepts"
+                    },
+                );
+            }
+            _ => {}
+        }
+    }
+
+    fn certificate_error(&mut self, args: &str) {
+        let reason: u8 = field(args, "reason")
+            .and_then(|r| r.parse().ok())
+            .unwrap_or(0);
+        let subject = quoted_field(args, "subject").unwrap_or_default();
+        let err = quoted_field(args, "err").unwrap_or_default();
+
+        // tls_fail_reason values from wpa_supplicant's tls.h
+        let kind = match reason {
+            1 => EnterpriseIssueKind::UntrustedCa,
+            2 => EnterpriseIssueKind::CertificateRevoked,
+            3 => EnterpriseIssueKind::CertificateNotYetValid,
+            4 => EnterpriseIssueKind::CertificateExpired,
+            5 | 6 | 9 | 10 | 12 => EnterpriseIssueKind::HostnameMismatch,
+            _ if err.contains("expired") => EnterpriseIssueKind::CertificateExpired,
+            _ => EnterpriseIssueKind::CertificateInvalid,
+        };
+        let remediation = match kind {
+            EnterpriseIssueKind::UntrustedCa => {
+                "Install the CA certificate that issued the RADIUS server certificate and \
+                 reference it as ca_cert"
+                    .to_string()
+            }
+            EnterpriseIssueKind::HostnameMismatch => format!(
+                "Check the server name the profile expects ({}) against the certificate{}",
+                self.server_match
+                    .as_deref()
+                    .unwrap_or("domain_suffix_match"),
+                self.server_names()
+            ),
+            EnterpriseIssueKind::CertificateExpired => {
+                "Have the network administrator renew the RADIUS server certificate, and \
+                 check this device's clock"
+                    .to_string()
+            }
+            EnterpriseIssueKind::CertificateNotYetValid => {
+                "Check this device's date and time".to_string()
+            }
+            _ => "Have the network administrator check the RADIUS server certificate".to_string(),
+        };
+        self.add_issue(
+            kind,
+            IssueSeverity::High,
+            format!("Server certificate '{}' was rejected: {}", subject, err),
+            remediation,
+        );
+    }
+
+    fn credentials_rejected(&mut self, attempt: &Attempt) {
+        if !attempt.method_selected {
+            // Rejected before any method ran: the outer identity or policy
+            self.add_issue(
+                EnterpriseIssueKind::CredentialsRejected,
+                IssueSeverity::High,
+                format!(
+                    "The RADIUS server rejected {} before authentication started",
+                    self.identity_text()
+                ),
+                "Check the (anonymous) identity and realm, and that the account may use this \
+                 network",
+            );
+            return;
+        }
+        let (description, remediation) = match self.eap_method {
+            Some(EapMethod::Tls) => (
+                "The RADIUS server rejected the client certificate".to_string(),
+                "Check that the client certificate is valid and enrolled for this network",
+            ),
+            _ => (
+                format!(
+                    "The RADIUS server rejected the credentials for {}",
+                    self.identity_text()
+                ),
+                "Check the username and password, and that the account is allowed on this \
+                 network",
+            ),
+        };
+        self.add_issue(
+            EnterpriseIssueKind::CredentialsRejected,
+            IssueSeverity::High,
+            description,
+            remediation,
+        );
+    }
+
+    fn radius_timeout(&mut self) {
+        self.add_issue(
+            EnterpriseIssueKind::RadiusTimeout,
+            IssueSeverity::High,
+            "EAP authentication stopped without an answer from the RADIUS server".to_string(),
+            "Check that the RADIUS server is running and reachable from the AP, and that the \
+             AP's shared secret matches",
+        );
+    }
+
+    fn handshake_failed(&mut self) {
+        self.add_issue(
+            EnterpriseIssueKind::HandshakeFailed,
+            IssueSeverity::High,
+            "EAP succeeded but the 4-way handshake failed".to_string(),
+            "The AP probably didn't get the session key from the RADIUS server; check the \
+             RADIUS attributes the AP expects, or clear cached PMKs by reconnecting",
+        );
+    }
+
+    fn disconnected(&mut self, reason: Option<u16>, attempt: &mut Attempt) {
+        if attempt.failed {
+            return;
+        }
+        match attempt.stage {
+            Some(AuthStage::Eap) => {
+                attempt.failed = true;
+                if reason == Some(REASON_8021X_FAILED) && attempt.method_selected {
+                    self.credentials_rejected(attempt);
+                } else if !attempt.method_selected {
+                    // The AP gave up while waiting for the RADIUS server
+                    self.radius_timeout();
+                }
+            }
+            Some(AuthStage::KeyHandshake) => {
+                attempt.failed = true;
+                self.handshake_failed();
+            }
+            Some(AuthStage::Association) if reason == Some(REASON_4WAY_TIMEOUT) => {
+                attempt.stage = Some(AuthStage::KeyHandshake);
+                attempt.failed = true;
+                self.handshake_failed();
+            }
+            _ => {}
+        }
+    }
+
+    /// Configuration advice for the network block.
+    fn check_profile(&mut self, network: &HashMap<String, Option<String>>) {
+        if !self.is_enterprise || !self.eap_method.is_some_and(|m| m.uses_server_certificate()) {
+            return;
+        }
+        let Some(ca_cert) = network.get("ca_cert") else {
+            return; // not recorded
+        };
+        if ca_cert.is_none() {
+            self.add_issue(
+                EnterpriseIssueKind::ServerNotValidated,
+                IssueSeverity::Medium,
+                "The profile has no CA certificate, so any server is accepted".to_string(),
+                "Set ca_cert and domain_suffix_match so credentials can't be captured by a \
+                 rogue AP",
+            );
+        } else if self.server_match.is_none() {
+            self.add_issue(
+                EnterpriseIssueKind::ServerNotValidated,
+                IssueSeverity::Low,
+                "The profile trusts the CA but doesn't check the server name".to_string(),
+                "Set domain_suffix_match to the RADIUS server's name",
+            );
+        }
+    }
+
+    fn add_issue(
+        &mut self,
+        kind: EnterpriseIssueKind,
+        severity: IssueSeverity,
+        description: String,
+        remediation: impl Into<String>,
+    ) {
+        if self.issues.iter().any(|i| i.kind == kind) {
+            return;
+        }
+        self.issues.push(EnterpriseIssue {
+            kind,
+            severity,
+            description,
+            remediation: remediation.into(),
+        });
+    }
+
+    fn identity_text(&self) -> String {
+        self.identity
+            .as_deref()
+            .map_or_else(|| "this identity".to_string(), |i| format!("'{}'", i))
+    }
+
+    fn server_subject(&self) -> String {
+        self.server_certificates
+            .first()
+            .map(|c| format!(" '{}'", c.subject))
+            .unwrap_or_default()
+    }
+
+    fn server_names(&self) -> String {
+        match self.server_certificates.first() {
+            Some(cert) if !cert.alt_names.is_empty() => format!(" ({})", cert.alt_names.join(", ")),
+            Some(cert) => format!(" ({})", cert.subject),
+            None => String::new(),
+        }
+    }
+}
+
+/// `key=value` lines of a status reply.
+fn key_values(reply: &str) -> HashMap<String, String> {
+    reply
+        .lines()
+        .filter_map(|line| line.split_once('='))
+        .map(|(k, v)| (k.trim().to_string(), v.trim().to_string()))
+        .collect()
+}
+
+/// `key=value` lines of the status replies. Later replies override earlier
+/// ones, so fields missing after a failed attempt (e.g. the BSSID) are kept.
+fn status_fields(transcript: &SupplicantTranscript) -> HashMap<String, String> {
+    let mut fields = HashMap::new();
+    for message in &transcript.messages {
+        if let SupplicantMessage::Command { request, reply } = message {
+            if request.eq_ignore_ascii_case("STATUS-VERBOSE")
+                || request.eq_ignore_ascii_case("STATUS")
+            {
+                fields.extend(key_values(reply));
+            }
+        }
+    }
+    fields
+}
+
+/// Network block fields read with `GET_NETWORK <id> <field>`; `None` for
+/// fields that aren't set.
+fn network_fields(transcript: &SupplicantTranscript) -> HashMap<String, Option<String>> {
+    transcript
+        .messages
+        .iter()
+        .filter_map(|message| match message {
+            SupplicantMessage::Command { request, reply }
+                if request.to_ascii_uppercase().starts_with("GET_NETWORK ") =>
+            {
+                let field = request.split_whitespace().nth(2)?.to_string();
+                let value = reply.trim();
+                let value = (!value.is_empty() && value != "FAIL")
+                    .then(|| value.trim_matches('"').to_string());
+                Some((field, value))
+            }
+            _ => None,
+        })
+        .collect()
+}
+
+/// Value of an unquoted `key=value` event argument.
+fn field<'a>(args: &'a str, key: &str) -> Option<&'a str> {
+    args.split_whitespace()
+        .find_map(|token| token.strip_prefix(key)?.strip_prefix('='))
+}
+
+/// Value of a quoted `key='value'` event argument.
+fn quoted_field<'a>(args: &'a str, key: &str) -> Option<&'a str> {
+    let start = args.find(&format!("{}='", key))? + key.len() + 2;
+    let rest = &args[start..];
+    Some(&rest[..rest.find('\'')?])
+}
+
+/// EAP type from `EAP vendor 0 method 25 (PEAP) selected`.
+fn eap_method_number(args: &str) -> Option<u8> {
+    let mut tokens = args.split_whitespace();
+    tokens.find(|t| *t == "method")?;
+    tokens.next()?.parse().ok()
+}
+
+/// Inner method from a phase2 setting such as `auth=MSCHAPV2`.
+fn parse_phase2(phase2: &str) -> Option<Phase2Method> {
+    phase2
+        .split_whitespace()
+        .filter_map(|setting| setting.split_once('='))
+        .find_map(|(_, method)| method.to_ascii_uppercase().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diagnose(transcript: &str) -> EnterpriseDiagnosis {
+        EnterpriseDiagnosis::from_transcript(&SupplicantTranscript::parse(transcript))
+    }
+
+    fn kinds(diagnosis: &EnterpriseDiagnosis) -> Vec<EnterpriseIssueKind> {
+        diagnosis.issues.iter().map(|i| i.kind).collect()
+    }
+
+    #[test]
+    fn test_peap_success() {
+        let diagnosis = diagnose(include_str!("../testdata/wpa_supplicant/peap_success.txt"));
+
+        assert!(diagnosis.is_enterprise);
+        assert!(diagnosis.authenticated);
+        assert_eq!(diagnosis.failed_stage, None);
+        assert_eq!(diagnosis.ssid.as_deref(), Some("corp"));
+        assert_eq!(diagnosis.eap_method, Some(EapMethod::Peap));
+        assert_eq!(diagnosis.phase2_method, Some(Phase2Method::MsChapV2));
+        assert_eq!(diagnosis.identity.as_deref(), Some("alice@example.com"));
+        assert_eq!(
+            diagnosis.server_match.as_deref(),
+            Some("radius.example.com")
+        );
+        assert_eq!(diagnosis.eap_attempts, 1);
+        assert_eq!(diagnosis.server_certificates.len(), 2);
+        assert_eq!(
+            diagnosis.server_certificates[0].subject,
+            "/CN=radius.example.com"
+        );
+        assert_eq!(
+            diagnosis.server_certificates[0].alt_names,
+            vec!["DNS:radius.example.com".to_string()]
+        );
+        assert!(diagnosis.issues.is_empty());
+    }
+
+    #[test]
+    fn test_hostname_mismatch() {
+        let diagnosis = diagnose(include_str!(
+            "../testdata/wpa_supplicant/hostname_mismatch.txt"
+        ));
+
+        assert!(!diagnosis.authenticated);
+        assert_eq!(diagnosis.failed_stage, Some(AuthStage::Eap));
+        assert_eq!(diagnosis.bssid.as_deref(), Some("00:0b:86:12:34:56"));
+        assert_eq!(diagnosis.wpa_state.as_deref(), Some("DISCONNECTED"));
+        assert_eq!(
+            kinds(&diagnosis),
+            vec![EnterpriseIssueKind::HostnameMismatch]
+        );
+        assert!(diagnosis.issues[0]
+            .remediation
+            .contains("DNS:nps01.corp.local"));
+    }
+
+    #[test]
+    fn test_radius_timeout() {
+        let diagnosis = diagnose(include_str!(
+            "../testdata/wpa_supplicant/radius_timeout.txt"
+        ));
+
+        assert!(!diagnosis.authenticated);
+        assert_eq!(diagnosis.failed_stage, Some(AuthStage::Eap));
+        assert_eq!(diagnosis.eap_attempts, 2);
+        assert_eq!(diagnosis.eap_method, Some(EapMethod::Ttls));
+        assert_eq!(diagnosis.phase2_method, Some(Phase2Method::Pap));
+        assert_eq!(
+            kinds(&diagnosis),
+            vec![
+                EnterpriseIssueKind::RadiusTimeout,
+                EnterpriseIssueKind::ServerNotValidated
+            ]
+        );
+        assert_eq!(diagnosis.failures().count(), 1);
+    }
+
+    #[test]
+    fn test_wrong_password() {
+        let diagnosis = diagnose(include_str!(
+            "../testdata/wpa_supplicant/wrong_password.txt"
+        ));
+
+        assert_eq!(diagnosis.failed_stage, Some(AuthStage::Eap));
+        assert_eq!(
+            kinds(&diagnosis),
+            vec![
+                EnterpriseIssueKind::CredentialsRejected,
+                EnterpriseIssueKind::ServerNotValidated
+            ]
+        );
+        assert!(diagnosis.issues[0]
+            .description
+            .contains("alice@example.com"));
+        assert_eq!(diagnosis.issues[1].severity, IssueSeverity::Low);
+    }
+
+    #[test]
+    fn test_handshake_failure() {
+        let diagnosis = diagnose(
+            "> STATUS\nssid=corp\nkey_mgmt=WPA2/IEEE 802.1X/EAP\nwpa_state=4WAY_HANDSHAKE\n\
+             <3>CTRL-EVENT-EAP-STARTED EAP authentication started\n\
+             <3>CTRL-EVENT-EAP-METHOD EAP vendor 0 method 13 (TLS) selected\n\
+             <3>CTRL-EVENT-EAP-SUCCESS EAP authentication completed successfully\n\
+             <3>CTRL-EVENT-DISCONNECTED bssid=00:0b:86:12:34:56 reason=15\n",
+        );
+
+        assert_eq!(diagnosis.eap_method, Some(EapMethod::Tls));
+        assert_eq!(diagnosis.failed_stage, Some(AuthStage::KeyHandshake));
+        assert_eq!(
+            kinds(&diagnosis),
+            vec![EnterpriseIssueKind::HandshakeFailed]
+        );
+    }
+}
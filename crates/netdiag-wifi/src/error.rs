@@ -33,6 +33,26 @@ pub enum WifiError {
     #[error("insufficient privileges: {0}")]
     InsufficientPrivileges(String),
 
+    /// Survey project could not be read or is invalid.
+    #[error("invalid survey project: {0}")]
+    InvalidProject(String),
+
+    /// Rogue AP allowlist could not be parsed.
+    #[error("invalid allowlist: {0}")]
+    InvalidAllowlist(String),
+
+    /// Channel plan configuration could not be parsed or is invalid.
+    #[error("invalid channel plan: {0}")]
+    InvalidChannelPlan(String),
+
+    /// Heatmap rendering failed.
+    #[error("render failed: {0}")]
+    Render(String),
+
+    /// I/O error.
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+
     /// Timeout.
     #[error("operation timed out")]
     Timeout,
@@ -0,0 +1,317 @@
+//! Floor plans and survey project files.
+
+use crate::error::{WifiError, WifiResult};
+use crate::heatmap::{Heatmap, HeatmapLayer, HeatmapMetric, HeatmapOptions};
+use crate::survey::{FloorPosition, SiteSurvey, SurveyPoint, SurveyResult};
+use chrono::{DateTime, Utc};
+use netdiag_types::wifi::WifiBand;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::debug;
+
+/// Current survey project file format version.
+pub const PROJECT_VERSION: u32 = 1;
+
+/// A floor plan that survey points are placed on.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FloorPlan {
+    /// Floor plan name (e.g. "Level 2").
+    pub name: String,
+
+    /// Path to the floor plan image, used as the heatmap background.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image: Option<PathBuf>,
+
+    /// Width in pixels.
+    pub width: u32,
+
+    /// Height in pixels.
+    pub height: u32,
+
+    /// Scale in pixels per meter, if known.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixels_per_meter: Option<f64>,
+}
+
+impl FloorPlan {
+    /// Create a floor plan without a background image.
+    pub fn new(name: impl Into<String>, width: u32, height: u32) -> Self {
+        Self {
+            name: name.into(),
+            image: None,
+            width,
+            height,
+            pixels_per_meter: None,
+        }
+    }
+
+    /// Convert a distance in meters to floor plan pixels, if the scale is known.
+    pub fn meters_to_pixels(&self, meters: f64) -> Option<f64> {
+        self.pixels_per_meter.map(|scale| meters * scale)
+    }
+
+    /// Whether a position lies on the floor plan.
+    pub fn contains(&self, position: &FloorPosition) -> bool {
+        (0.0..=f64::from(self.width)).contains(&position.x)
+            && (0.0..=f64::from(self.height)).contains(&position.y)
+    }
+}
+
+/// A site survey tied to a floor plan, saved as a project file so it can be
+/// repeated and compared after changes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SurveyProject {
+    /// Project file format version.
+    pub version: u32,
+
+    /// Project name.
+    pub name: String,
+
+    /// Creation time.
+    pub created: DateTime<Utc>,
+
+    /// Last modification time.
+    pub modified: DateTime<Utc>,
+
+    /// Surveyed floor plan.
+    pub floor_plan: FloorPlan,
+
+    /// Free-form notes (e.g. what changed since the previous survey).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+
+    /// Recorded survey points.
+    #[serde(default)]
+    pub points: Vec<SurveyPoint>,
+}
+
+impl SurveyProject {
+    /// Create an empty project for a floor plan.
+    pub fn new(name: impl Into<String>, floor_plan: FloorPlan) -> Self {
+        let now = Utc::now();
+        Self {
+            version: PROJECT_VERSION,
+            name: name.into(),
+            created: now,
+            modified: now,
+            floor_plan,
+            notes: None,
+            points: Vec::new(),
+        }
+    }
+
+    /// Start a repeat of this survey: same floor plan, no points.
+    ///
+    /// Use [`SurveyProject::positions`] on the original to walk the same route.
+    pub fn repeat(&self, name: impl Into<String>) -> Self {
+        Self::new(name, self.floor_plan.clone())
+    }
+
+    /// Add a recorded point.
+    pub fn add_point(&mut self, point: SurveyPoint) -> WifiResult<()> {
+        if let Some(position) = &point.position {
+            if !self.floor_plan.contains(position) {
+                return Err(WifiError::InvalidProject(format!(
+                    "point ({}, {}) is outside floor plan {}x{}",
+                    position.x, position.y, self.floor_plan.width, self.floor_plan.height
+                )));
+            }
+        }
+        self.points.push(point);
+        self.modified = Utc::now();
+        Ok(())
+    }
+
+    /// Record a point at a floor plan position and add it to the project.
+    pub async fn record(
+        &mut self,
+        survey: &SiteSurvey,
+        interface: &str,
+        location: &str,
+        position: FloorPosition,
+    ) -> WifiResult<&SurveyPoint> {
+        let point = survey
+            .record_point_at(interface, location, Some(position))
+            .await?;
+        self.add_point(point)?;
+        Ok(self.points.last().expect("point was just added"))
+    }
+
+    /// Locations and positions of the recorded points, in survey order.
+    pub fn positions(&self) -> Vec<(String, FloorPosition)> {
+        self.points
+            .iter()
+            .filter_map(|p| Some((p.location.clone(), p.position?)))
+            .collect()
+    }
+
+    /// Bands observed anywhere in the survey.
+    pub fn bands(&self) -> Vec<WifiBand> {
+        let mut bands = Vec::new();
+        for band in self.points.iter().flat_map(|p| {
+            std::iter::once(p.channel.band).chain(p.observations.iter().map(|o| o.channel.band))
+        }) {
+            if !bands.contains(&band) {
+                bands.push(band);
+            }
+        }
+        bands
+    }
+
+    /// BSSIDs observed anywhere in the survey, lowercased and sorted.
+    pub fn bssids(&self) -> Vec<String> {
+        let mut bssids: Vec<String> = self
+            .points
+            .iter()
+            .flat_map(|p| std::iter::once(&p.bssid).chain(p.observations.iter().map(|o| &o.bssid)))
+            .map(|b| b.to_ascii_lowercase())
+            .collect();
+        bssids.sort();
+        bssids.dedup();
+        bssids
+    }
+
+    /// Analyze the recorded points.
+    pub fn analyze(&self) -> SurveyResult {
+        SiteSurvey::analyze_survey(&self.points)
+    }
+
+    /// Interpolate a heatmap of the recorded points.
+    pub fn heatmap(
+        &self,
+        metric: HeatmapMetric,
+        layer: HeatmapLayer,
+        options: HeatmapOptions,
+    ) -> Heatmap {
+        Heatmap::interpolate(&self.floor_plan, &self.points, metric, layer, options)
+    }
+
+    /// Save the project as JSON.
+    ///
+    /// A relative floor plan image path is taken to be relative to the
+    /// working directory and stored relative to the project file's directory,
+    /// so that [`SurveyProject::load`] resolves it back to the same file.
+    pub fn save(&self, path: impl AsRef<Path>) -> WifiResult<()> {
+        let path = path.as_ref();
+        let json = match (&self.floor_plan.image, path.parent()) {
+            (Some(image), Some(dir)) if image.is_relative() => {
+                let mut project = self.clone();
+                project.floor_plan.image = Some(relative_to(image, dir)?);
+                serde_json::to_string_pretty(&project)
+            }
+            _ => serde_json::to_string_pretty(self),
+        }
+        .map_err(|e| WifiError::InvalidProject(e.to_string()))?;
+        std::fs::write(path, json)?;
+        debug!("Saved survey project to {}", path.display());
+        Ok(())
+    }
+
+    /// Load a project saved with [`SurveyProject::save`].
+    ///
+    /// A relative floor plan image path is resolved against the project
+    /// file's directory.
+    pub fn load(path: impl AsRef<Path>) -> WifiResult<Self> {
+        let path = path.as_ref();
+        let json = std::fs::read_to_string(path)?;
+        let mut project: Self = serde_json::from_str(&json)
+            .map_err(|e| WifiError::InvalidProject(format!("{}: {}", path.display(), e)))?;
+
+        if project.version > PROJECT_VERSION {
+            return Err(WifiError::InvalidProject(format!(
+                "{}: project version {} is newer than supported version {}",
+                path.display(),
+                project.version,
+                PROJECT_VERSION
+            )));
+        }
+
+        if let (Some(image), Some(dir)) = (&project.floor_plan.image, path.parent()) {
+            if image.is_relative() {
+                project.floor_plan.image = Some(dir.join(image));
+            }
+        }
+
+        Ok(project)
+    }
+}
+
+/// Rebase a working-directory-relative path onto `dir`, falling back to an
+/// absolute path when it lies outside `dir`.
+fn relative_to(path: &Path, dir: &Path) -> WifiResult<PathBuf> {
+    match path.strip_prefix(dir) {
+        Ok(relative) => Ok(relative.to_path_buf()),
+        Err(_) => Ok(std::env::current_dir()?.join(path)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityMetrics;
+    use netdiag_types::wifi::Channel;
+
+    fn point(x: f64, y: f64) -> SurveyPoint {
+        SurveyPoint {
+            timestamp: Utc::now(),
+            location: "Kitchen".to_string(),
+            position: Some(FloorPosition::new(x, y)),
+            ssid: "home".to_string(),
+            bssid: "AA:BB:CC:00:00:01".to_string(),
+            channel: Channel::from_number(6, WifiBand::Band2_4GHz),
+            rssi: -60,
+            noise: None,
+            quality: QualityMetrics::new(-60, None),
+            tx_rate: 130.0,
+            nearby_ap_count: 0,
+            co_channel_aps: 0,
+            observations: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_project_round_trip() {
+        // Relative to the working directory, like paths given on the command line
+        let dir = PathBuf::from(format!("target/netdiag-survey-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("office.json");
+
+        let mut plan = FloorPlan::new("Ground floor", 800, 600);
+        plan.image = Some(dir.join("ground.png"));
+        let mut project = SurveyProject::new("Office", plan);
+        project.add_point(point(100.0, 200.0)).unwrap();
+        assert!(project.add_point(point(900.0, 10.0)).is_err());
+        project.save(&path).unwrap();
+        let saved = std::fs::read_to_string(&path).unwrap();
+        assert!(saved.contains(r#""image": "ground.png""#));
+
+        let loaded = SurveyProject::load(&path).unwrap();
+        assert_eq!(loaded.name, "Office");
+        assert_eq!(loaded.points.len(), 1);
+        assert_eq!(loaded.floor_plan.image, Some(dir.join("ground.png")));
+        assert_eq!(loaded.positions()[0].1, FloorPosition::new(100.0, 200.0));
+        assert_eq!(loaded.bssids(), vec!["aa:bb:cc:00:00:01".to_string()]);
+        assert_eq!(loaded.bands(), vec![WifiBand::Band2_4GHz]);
+
+        // Saving a loaded project keeps the image pointing at the same file
+        loaded.save(&path).unwrap();
+        let reloaded = SurveyProject::load(&path).unwrap();
+        assert_eq!(reloaded.floor_plan.image, Some(dir.join("ground.png")));
+
+        // An image given relative to the working directory is found again
+        let mut elsewhere = reloaded.clone();
+        elsewhere.floor_plan.image = Some("ground.png".into());
+        elsewhere.save(&path).unwrap();
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(
+            SurveyProject::load(&path).unwrap().floor_plan.image,
+            Some(cwd.join("ground.png"))
+        );
+
+        let repeat = loaded.repeat("Office (after)");
+        assert!(repeat.points.is_empty());
+        assert_eq!(repeat.floor_plan, loaded.floor_plan);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
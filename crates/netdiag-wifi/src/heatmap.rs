@@ -0,0 +1,590 @@
+//! Coverage heatmaps interpolated from floor-plan survey points.
+
+use crate::error::{WifiError, WifiResult};
+use crate::floorplan::FloorPlan;
+use crate::survey::{FloorPosition, SurveyPoint};
+use netdiag_types::wifi::WifiBand;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// Metric rendered by a heatmap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HeatmapMetric {
+    /// Signal strength in dBm.
+    Rssi,
+    /// Signal-to-noise ratio in dB.
+    Snr,
+    /// Number of other APs on the same channel.
+    CoChannel,
+    /// Transmit rate in Mbps.
+    TxRate,
+}
+
+impl HeatmapMetric {
+    /// All metrics.
+    pub const ALL: [HeatmapMetric; 4] = [Self::Rssi, Self::Snr, Self::CoChannel, Self::TxRate];
+
+    /// Unit suffix for display.
+    pub fn unit(&self) -> &'static str {
+        match self {
+            Self::Rssi => "dBm",
+            Self::Snr => "dB",
+            Self::CoChannel => "APs",
+            Self::TxRate => "Mbps",
+        }
+    }
+
+    /// Whether larger values indicate better coverage.
+    pub fn higher_is_better(&self) -> bool {
+        !matches!(self, Self::CoChannel)
+    }
+
+    /// Value range mapped onto the colour scale.
+    ///
+    /// RSSI, SNR and co-channel use fixed ranges so heatmaps from repeated
+    /// surveys are comparable; TX rate scales to the fastest sample.
+    fn scale(&self, max_sample: Option<f64>) -> (f64, f64) {
+        match self {
+            Self::Rssi => (-90.0, -30.0),
+            Self::Snr => (0.0, 40.0),
+            Self::CoChannel => (0.0, 6.0),
+            Self::TxRate => (0.0, max_sample.unwrap_or(0.0).max(1.0)),
+        }
+    }
+}
+
+impl fmt::Display for HeatmapMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Rssi => write!(f, "RSSI"),
+            Self::Snr => write!(f, "SNR"),
+            Self::CoChannel => write!(f, "Co-channel APs"),
+            Self::TxRate => write!(f, "TX rate"),
+        }
+    }
+}
+
+impl FromStr for HeatmapMetric {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('-', "_").as_str() {
+            "rssi" | "signal" => Ok(Self::Rssi),
+            "snr" => Ok(Self::Snr),
+            "co_channel" | "cochannel" => Ok(Self::CoChannel),
+            "tx_rate" | "txrate" | "rate" => Ok(Self::TxRate),
+            other => Err(format!("unknown heatmap metric: {}", other)),
+        }
+    }
+}
+
+/// Which access point a heatmap describes at each survey point.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+pub enum HeatmapLayer {
+    /// The AP the surveying client was connected to.
+    #[default]
+    Connected,
+    /// The strongest AP observed in a band.
+    Band(WifiBand),
+    /// A single BSSID.
+    Bssid(String),
+}
+
+impl fmt::Display for HeatmapLayer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Connected => write!(f, "connected AP"),
+            Self::Band(band) => write!(f, "{}", band),
+            Self::Bssid(bssid) => write!(f, "{}", bssid),
+        }
+    }
+}
+
+/// Heatmap interpolation and rendering options.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapOptions {
+    /// Grid cell size in floor plan pixels.
+    pub cell_size: u32,
+
+    /// Inverse-distance weighting power.
+    pub power: f64,
+
+    /// Cells further than this from every sample are left empty
+    /// (floor plan pixels). `None` fills the whole plan.
+    pub radius: Option<f64>,
+
+    /// Overlay opacity (0.0-1.0).
+    pub opacity: f32,
+}
+
+impl Default for HeatmapOptions {
+    fn default() -> Self {
+        Self {
+            cell_size: 8,
+            power: 2.0,
+            radius: None,
+            opacity: 0.6,
+        }
+    }
+}
+
+/// A measured value at a floor plan position.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeatmapSample {
+    /// Survey point location description.
+    pub location: String,
+
+    /// Position on the floor plan.
+    pub position: FloorPosition,
+
+    /// Measured value.
+    pub value: f64,
+}
+
+/// Interpolated heatmap over a floor plan.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Heatmap {
+    /// Floor plan the heatmap covers.
+    pub floor_plan: FloorPlan,
+
+    /// Rendered metric.
+    pub metric: HeatmapMetric,
+
+    /// Rendered layer.
+    pub layer: HeatmapLayer,
+
+    /// Options used for interpolation and rendering.
+    pub options: HeatmapOptions,
+
+    /// Number of grid columns.
+    pub columns: u32,
+
+    /// Number of grid rows.
+    pub rows: u32,
+
+    /// Interpolated cell values, row-major. `None` where no sample is in range.
+    pub cells: Vec<Option<f64>>,
+
+    /// Samples the grid was interpolated from.
+    pub samples: Vec<HeatmapSample>,
+}
+
+impl Heatmap {
+    /// Interpolate a heatmap from survey points using inverse-distance weighting.
+    ///
+    /// Points without a floor plan position, or without a value for the
+    /// metric on the requested layer, are skipped.
+    pub fn interpolate(
+        floor_plan: &FloorPlan,
+        points: &[SurveyPoint],
+        metric: HeatmapMetric,
+        layer: HeatmapLayer,
+        options: HeatmapOptions,
+    ) -> Self {
+        let samples: Vec<HeatmapSample> = points
+            .iter()
+            .filter_map(|p| {
+                Some(HeatmapSample {
+                    location: p.location.clone(),
+                    position: p.position?,
+                    value: sample_value(p, metric, &layer)?,
+                })
+            })
+            .collect();
+
+        let cell = options.cell_size.max(1);
+        let columns = floor_plan.width.div_ceil(cell);
+        let rows = floor_plan.height.div_ceil(cell);
+
+        let mut cells = Vec::with_capacity((columns * rows) as usize);
+        for row in 0..rows {
+            for col in 0..columns {
+                let center = FloorPosition::new(
+                    (f64::from(col) + 0.5) * f64::from(cell),
+                    (f64::from(row) + 0.5) * f64::from(cell),
+                );
+                cells.push(idw(&samples, &center, options.power, options.radius));
+            }
+        }
+
+        Self {
+            floor_plan: floor_plan.clone(),
+            metric,
+            layer,
+            options,
+            columns,
+            rows,
+            cells,
+            samples,
+        }
+    }
+
+    /// Interpolated value at a floor plan position.
+    pub fn value_at(&self, position: &FloorPosition) -> Option<f64> {
+        if position.x < 0.0 || position.y < 0.0 {
+            return None;
+        }
+        let cell = f64::from(self.options.cell_size.max(1));
+        let col = (position.x / cell) as u32;
+        let row = (position.y / cell) as u32;
+        if col >= self.columns || row >= self.rows {
+            return None;
+        }
+        self.cells[(row * self.columns + col) as usize]
+    }
+
+    /// Smallest interpolated value.
+    pub fn min(&self) -> Option<f64> {
+        self.cells.iter().flatten().copied().reduce(f64::min)
+    }
+
+    /// Largest interpolated value.
+    pub fn max(&self) -> Option<f64> {
+        self.cells.iter().flatten().copied().reduce(f64::max)
+    }
+
+    /// Render the heatmap as an SVG overlay.
+    ///
+    /// The floor plan image, if any, is referenced as the background so the
+    /// file can be opened directly; survey points are drawn as markers.
+    pub fn to_svg(&self) -> String {
+        let plan = &self.floor_plan;
+        let cell = self.options.cell_size.max(1);
+        let mut svg = String::new();
+
+        let _ = writeln!(
+            svg,
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}">"#,
+            w = plan.width,
+            h = plan.height
+        );
+        let _ = writeln!(
+            svg,
+            "<title>{} {} ({})</title>",
+            escape_xml(&plan.name),
+            self.metric,
+            escape_xml(&self.layer.to_string())
+        );
+        if let Some(image) = &plan.image {
+            let _ = writeln!(
+                svg,
+                r#"<image href="{}" x="0" y="0" width="{}" height="{}" preserveAspectRatio="none"/>"#,
+                escape_xml(&image.to_string_lossy()),
+                plan.width,
+                plan.height
+            );
+        }
+
+        let _ = writeln!(
+            svg,
+            r#"<g opacity="{:.2}" shape-rendering="crispEdges">"#,
+            self.options.opacity.clamp(0.0, 1.0)
+        );
+        for (i, value) in self.cells.iter().enumerate() {
+            let Some(value) = value else { continue };
+            let i = i as u32;
+            let (x, y) = ((i % self.columns) * cell, (i / self.columns) * cell);
+            let [r, g, b] = self.color(*value);
+            let _ = writeln!(
+                svg,
+                r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#{:02x}{:02x}{:02x}"/>"##,
+                x,
+                y,
+                cell.min(plan.width - x),
+                cell.min(plan.height - y),
+                r,
+                g,
+                b
+            );
+        }
+        svg.push_str("</g>\n<g>\n");
+
+        for sample in &self.samples {
+            let _ = writeln!(
+                svg,
+                r#"<circle cx="{:.1}" cy="{:.1}" r="4" fill="white" stroke="black" stroke-width="1.5"><title>{}: {:.1} {}</title></circle>"#,
+                sample.position.x,
+                sample.position.y,
+                escape_xml(&sample.location),
+                sample.value,
+                self.metric.unit()
+            );
+        }
+        svg.push_str("</g>\n");
+
+        let (lo, hi) = self.scale();
+        let (worst, best) = if self.metric.higher_is_better() {
+            (lo, hi)
+        } else {
+            (hi, lo)
+        };
+        let [wr, wg, wb] = gradient(0.0);
+        let [mr, mg, mb] = gradient(0.5);
+        let [br, bg, bb] = gradient(1.0);
+        let _ = writeln!(
+            svg,
+            r##"<defs><linearGradient id="legend"><stop offset="0" stop-color="#{wr:02x}{wg:02x}{wb:02x}"/><stop offset="0.5" stop-color="#{mr:02x}{mg:02x}{mb:02x}"/><stop offset="1" stop-color="#{br:02x}{bg:02x}{bb:02x}"/></linearGradient></defs>"##
+        );
+        let _ = writeln!(
+            svg,
+            r#"<g font-family="sans-serif" font-size="11"><rect x="8" y="8" width="160" height="40" fill="white" fill-opacity="0.8"/><rect x="14" y="14" width="148" height="10" fill="url(#legend)"/><text x="14" y="40">{:.0}</text><text x="162" y="40" text-anchor="end">{:.0} {}</text></g>"#,
+            worst,
+            best,
+            self.metric.unit()
+        );
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Render the heatmap as a transparent RGBA PNG the size of the floor plan.
+    pub fn to_png(&self) -> WifiResult<Vec<u8>> {
+        let plan = &self.floor_plan;
+        let cell = self.options.cell_size.max(1);
+        let alpha = (self.options.opacity.clamp(0.0, 1.0) * 255.0).round() as u8;
+
+        let mut pixels = vec![0u8; plan.width as usize * plan.height as usize * 4];
+        for y in 0..plan.height {
+            for x in 0..plan.width {
+                let Some(value) = self.cells[((y / cell) * self.columns + x / cell) as usize]
+                else {
+                    continue;
+                };
+                let [r, g, b] = self.color(value);
+                let offset = (y as usize * plan.width as usize + x as usize) * 4;
+                pixels[offset..offset + 4].copy_from_slice(&[r, g, b, alpha]);
+            }
+        }
+
+        let mut out = Vec::new();
+        let mut encoder = png::Encoder::new(&mut out, plan.width, plan.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&pixels))
+            .map_err(|e| WifiError::Render(format!("PNG encoding failed: {}", e)))?;
+        Ok(out)
+    }
+
+    fn scale(&self) -> (f64, f64) {
+        self.metric
+            .scale(self.samples.iter().map(|s| s.value).reduce(f64::max))
+    }
+
+    fn color(&self, value: f64) -> [u8; 3] {
+        let (lo, hi) = self.scale();
+        let t = ((value - lo) / (hi - lo)).clamp(0.0, 1.0);
+        gradient(if self.metric.higher_is_better() {
+            t
+        } else {
+            1.0 - t
+        })
+    }
+}
+
+/// Value of a metric at a survey point for a layer.
+fn sample_value(point: &SurveyPoint, metric: HeatmapMetric, layer: &HeatmapLayer) -> Option<f64> {
+    let observation = match layer {
+        HeatmapLayer::Connected => {
+            return match metric {
+                HeatmapMetric::Rssi => Some(f64::from(point.rssi)),
+                HeatmapMetric::Snr => point.snr(),
+                HeatmapMetric::CoChannel => Some(point.co_channel_aps as f64),
+                HeatmapMetric::TxRate => (point.tx_rate > 0.0).then_some(point.tx_rate),
+            };
+        }
+        HeatmapLayer::Band(band) => point.strongest_in_band(*band)?,
+        HeatmapLayer::Bssid(bssid) => point.observation(bssid)?,
+    };
+    let connected = observation.bssid.eq_ignore_ascii_case(&point.bssid);
+
+    match metric {
+        HeatmapMetric::Rssi => Some(f64::from(observation.rssi)),
+        HeatmapMetric::Snr => match point.noise {
+            Some(noise) => Some(f64::from(observation.rssi - noise)),
+            None if connected => point.snr(),
+            None => None,
+        },
+        HeatmapMetric::CoChannel => Some(point.co_channel_count(&observation) as f64),
+        HeatmapMetric::TxRate => (connected && point.tx_rate > 0.0).then_some(point.tx_rate),
+    }
+}
+
+/// Inverse-distance weighted value at `at`.
+fn idw(
+    samples: &[HeatmapSample],
+    at: &FloorPosition,
+    power: f64,
+    radius: Option<f64>,
+) -> Option<f64> {
+    let mut weighted = 0.0;
+    let mut total = 0.0;
+    for sample in samples {
+        let distance = sample.position.distance(at);
+        if radius.is_some_and(|r| distance > r) {
+            continue;
+        }
+        if distance < 1e-9 {
+            return Some(sample.value);
+        }
+        let weight = 1.0 / distance.powf(power);
+        weighted += weight * sample.value;
+        total += weight;
+    }
+    (total > 0.0).then(|| weighted / total)
+}
+
+/// Red-yellow-green colour for `t` in 0.0 (worst) to 1.0 (best).
+fn gradient(t: f64) -> [u8; 3] {
+    let t = t.clamp(0.0, 1.0);
+    let (r, g) = if t < 0.5 {
+        (1.0, t * 2.0)
+    } else {
+        ((1.0 - t) * 2.0, 1.0)
+    };
+    [(r * 220.0) as u8, (g * 200.0) as u8, 40]
+}
+
+fn escape_xml(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality::QualityMetrics;
+    use chrono::Utc;
+    use netdiag_types::wifi::Channel;
+
+    fn point(location: &str, x: f64, y: f64, rssi: i32) -> SurveyPoint {
+        let channel = Channel::from_number(36, WifiBand::Band5GHz);
+        SurveyPoint {
+            timestamp: Utc::now(),
+            location: location.to_string(),
+            position: Some(FloorPosition::new(x, y)),
+            ssid: "office".to_string(),
+            bssid: "aa:bb:cc:dd:ee:01".to_string(),
+            channel,
+            rssi,
+            noise: Some(-95),
+            quality: QualityMetrics::new(rssi, Some(-95)),
+            tx_rate: 400.0,
+            nearby_ap_count: 2,
+            co_channel_aps: 1,
+            observations: vec![
+                crate::survey::ApObservation {
+                    bssid: "aa:bb:cc:dd:ee:01".to_string(),
+                    ssid: "office".to_string(),
+                    channel,
+                    rssi,
+                },
+                crate::survey::ApObservation {
+                    bssid: "aa:bb:cc:dd:ee:02".to_string(),
+                    ssid: "office".to_string(),
+                    channel: Channel::from_number(6, WifiBand::Band2_4GHz),
+                    rssi: rssi - 10,
+                },
+            ],
+        }
+    }
+
+    fn plan() -> FloorPlan {
+        FloorPlan::new("Level 1", 100, 50)
+    }
+
+    #[test]
+    fn test_idw_exact_and_midpoint() {
+        let points = [point("a", 0.0, 25.0, -40), point("b", 100.0, 25.0, -80)];
+        let heatmap = Heatmap::interpolate(
+            &plan(),
+            &points,
+            HeatmapMetric::Rssi,
+            HeatmapLayer::Connected,
+            HeatmapOptions {
+                cell_size: 1,
+                ..Default::default()
+            },
+        );
+        assert_eq!((heatmap.columns, heatmap.rows), (100, 50));
+        let mid = heatmap.value_at(&FloorPosition::new(50.0, 25.0)).unwrap();
+        assert!((mid - -60.0).abs() < 1.0);
+        let near_a = heatmap.value_at(&FloorPosition::new(1.0, 25.0)).unwrap();
+        assert!(near_a > -45.0);
+        assert!(heatmap.min().unwrap() >= -80.0 && heatmap.max().unwrap() <= -40.0);
+    }
+
+    #[test]
+    fn test_layers_and_radius() {
+        let points = [point("a", 10.0, 10.0, -50)];
+        let options = HeatmapOptions {
+            cell_size: 10,
+            radius: Some(20.0),
+            ..Default::default()
+        };
+
+        let band = Heatmap::interpolate(
+            &plan(),
+            &points,
+            HeatmapMetric::Rssi,
+            HeatmapLayer::Band(WifiBand::Band2_4GHz),
+            options.clone(),
+        );
+        let value = band.value_at(&FloorPosition::new(12.0, 12.0)).unwrap();
+        assert!((value - -60.0).abs() < 1e-9);
+        assert_eq!(band.value_at(&FloorPosition::new(95.0, 45.0)), None);
+
+        let bssid = Heatmap::interpolate(
+            &plan(),
+            &points,
+            HeatmapMetric::TxRate,
+            HeatmapLayer::Bssid("AA:BB:CC:DD:EE:02".to_string()),
+            options.clone(),
+        );
+        assert!(bssid.samples.is_empty());
+
+        let snr = Heatmap::interpolate(
+            &plan(),
+            &points,
+            HeatmapMetric::Snr,
+            HeatmapLayer::Bssid("aa:bb:cc:dd:ee:02".to_string()),
+            options,
+        );
+        assert_eq!(snr.samples[0].value, 35.0);
+    }
+
+    #[test]
+    fn test_render_svg_and_png() {
+        let mut plan = plan();
+        plan.image = Some("floor <1>.png".into());
+        let points = [point("Desk & door", 20.0, 20.0, -55)];
+        let heatmap = Heatmap::interpolate(
+            &plan,
+            &points,
+            HeatmapMetric::Rssi,
+            HeatmapLayer::Connected,
+            HeatmapOptions::default(),
+        );
+
+        let svg = heatmap.to_svg();
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r#"href="floor &lt;1&gt;.png""#));
+        assert!(svg.contains("Desk &amp; door: -55.0 dBm"));
+        assert!(svg.trim_end().ends_with("</svg>"));
+
+        let png = heatmap.to_png().unwrap();
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    }
+
+    #[test]
+    fn test_metric_from_str() {
+        assert_eq!("co-channel".parse(), Ok(HeatmapMetric::CoChannel));
+        assert_eq!("TX_RATE".parse(), Ok(HeatmapMetric::TxRate));
+        assert!("bogus".parse::<HeatmapMetric>().is_err());
+    }
+}
@@ -1,8 +1,21 @@
 //! Interference detection and analysis.
 
-use netdiag_types::wifi::{AccessPoint, Channel, WifiBand};
+use crate::vendor::{ApDeviceClass, ApVendor};
+use netdiag_types::wifi::{AccessPoint, AirtimeReport, Bssid, Channel, WifiBand};
 use serde::{Deserialize, Serialize};
 
+/// Airtime share from which a measured neighbour counts as interference (%).
+const MIN_MEASURED_AIRTIME: f64 = 2.0;
+
+/// Retry rate on our network that points at interference (%).
+const HIGH_RETRY_PERCENT: f64 = 10.0;
+
+/// Beacon loss on our network that points at interference (%).
+const HIGH_BEACON_LOSS_PERCENT: f64 = 10.0;
+
+/// Measured channel busy time that leaves little room for our traffic (%).
+const HIGH_BUSY_PERCENT: f64 = 60.0;
+
 /// Interference analysis results.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InterferenceAnalysis {
@@ -23,6 +36,10 @@ pub struct InterferenceAnalysis {
 
     /// Recommendations.
     pub recommendations: Vec<String>,
+
+    /// Frame statistics from a monitor-mode capture, if one was applied.
+    #[serde(default)]
+    pub measured: Option<AirtimeReport>,
 }
 
 /// Detected interference source.
@@ -45,6 +62,10 @@ pub struct InterferenceSource {
 
     /// SSID (if from WiFi AP).
     pub ssid: Option<String>,
+
+    /// AP vendor and device class (if from WiFi AP).
+    #[serde(default)]
+    pub vendor: Option<ApVendor>,
 }
 
 /// Type of interference.
@@ -133,17 +154,24 @@ impl InterferenceAnalysis {
             if ap.channel.number == current_ch {
                 // Co-channel interference
                 co_channel_count += 1;
+                let vendor = ApVendor::identify(ap);
                 sources.push(InterferenceSource {
                     source_type: InterferenceType::CoChannel,
                     severity: Self::calculate_severity(ap.rssi),
-                    description: format!("WiFi network '{}' on same channel", ap.ssid),
+                    description: format!(
+                        "WiFi network '{}' on same channel{}",
+                        ap.ssid,
+                        Self::device_suffix(&vendor)
+                    ),
                     affected_channels: vec![current_ch],
                     signal_strength: Some(ap.rssi),
                     ssid: Some(ap.ssid.to_string()),
+                    vendor: Some(vendor),
                 });
             } else if Self::is_adjacent_channel(current_ch, ap.channel.number, &current_band) {
                 // Adjacent channel interference (only relevant for 2.4 GHz)
                 adjacent_channel_count += 1;
+                let vendor = ApVendor::identify(ap);
                 sources.push(InterferenceSource {
                     source_type: InterferenceType::AdjacentChannel,
                     severity: Self::calculate_adjacent_severity(
@@ -152,12 +180,15 @@ impl InterferenceAnalysis {
                         ap.channel.number,
                     ),
                     description: format!(
-                        "WiFi network '{}' on adjacent channel {}",
-                        ap.ssid, ap.channel.number
+                        "WiFi network '{}' on adjacent channel {}{}",
+                        ap.ssid,
+                        ap.channel.number,
+                        Self::device_suffix(&vendor)
                     ),
                     affected_channels: vec![current_ch, ap.channel.number],
                     signal_strength: Some(ap.rssi),
                     ssid: Some(ap.ssid.to_string()),
+                    vendor: Some(vendor),
                 });
             }
         }
@@ -179,6 +210,7 @@ impl InterferenceAnalysis {
                 affected_channels: vec![current_ch],
                 signal_strength: noise_level,
                 ssid: None,
+                vendor: None,
             });
         }
 
@@ -203,6 +235,105 @@ impl InterferenceAnalysis {
             adjacent_channel_count,
             non_wifi_detected,
             recommendations,
+            measured: None,
+        }
+    }
+
+    /// Add evidence measured from a monitor-mode capture of the channel.
+    ///
+    /// Neighbouring networks are rated by the airtime they actually used
+    /// rather than by signal strength, and retries, beacon loss and
+    /// deauthentication bursts on the channel are reported.
+    pub fn apply_capture(&mut self, report: &AirtimeReport, connected: Option<&Bssid>) {
+        let channel = report.channel.as_ref().map(|c| c.number);
+
+        for bss in &report.bss {
+            if Some(&bss.bssid) == connected {
+                continue;
+            }
+            let airtime = report.airtime_percent(bss);
+            if airtime < MIN_MEASURED_AIRTIME {
+                continue;
+            }
+            let severity = (airtime * 2.5).min(100.0) as u8;
+            let existing = self.sources.iter_mut().find(|s| {
+                s.source_type == InterferenceType::CoChannel
+                    && s.ssid.is_some()
+                    && s.ssid == bss.ssid
+            });
+            match existing {
+                Some(source) => {
+                    source.severity = source.severity.max(severity);
+                    source.description =
+                        format!("{} ({:.0}% airtime measured)", source.description, airtime);
+                }
+                None => {
+                    self.co_channel_count += 1;
+                    self.sources.push(InterferenceSource {
+                        source_type: InterferenceType::CoChannel,
+                        severity,
+                        description: format!(
+                            "WiFi network '{}' ({}) used {:.0}% of the airtime",
+                            bss.ssid.as_deref().unwrap_or("<hidden>"),
+                            bss.bssid,
+                            airtime
+                        ),
+                        affected_channels: channel.into_iter().collect(),
+                        signal_strength: bss.signal_dbm,
+                        ssid: bss.ssid.clone(),
+                        vendor: None,
+                    });
+                }
+            }
+        }
+        self.sources.sort_by(|a, b| b.severity.cmp(&a.severity));
+        self.interference_score = Self::calculate_overall_score(&self.sources);
+
+        let mut recommendations = Vec::new();
+        let busy = report.busy_percent();
+        if busy >= HIGH_BUSY_PERCENT {
+            recommendations.push(format!(
+                "The channel was busy {:.0}% of the captured time. Move to a quieter channel.",
+                busy
+            ));
+        }
+        if let Some(ours) = connected.and_then(|b| report.find(b)) {
+            let retries = ours.retry_percent();
+            if retries >= HIGH_RETRY_PERCENT {
+                recommendations.push(format!(
+                    "{:.0}% of frames on your network were retransmissions, which points at interference or a weak signal.",
+                    retries
+                ));
+            }
+            let loss = ours.beacon_loss_percent();
+            if loss >= HIGH_BEACON_LOSS_PERCENT {
+                recommendations.push(format!(
+                    "{:.0}% of your access point's beacons were not received.",
+                    loss
+                ));
+            }
+        }
+        for burst in &report.disconnect_bursts {
+            recommendations.push(format!(
+                "Burst of {} {} frames from {} (BSSID {}): a deauthentication attack or an access point dropping clients.",
+                burst.frames, burst.kind, burst.transmitter, burst.bssid
+            ));
+        }
+
+        if !self.sources.is_empty() || !recommendations.is_empty() {
+            self.recommendations
+                .retain(|r| r != "No significant interference detected.");
+        }
+        self.recommendations.extend(recommendations);
+        self.measured = Some(report.clone());
+    }
+
+    /// Device class note for a source description.
+    fn device_suffix(vendor: &ApVendor) -> String {
+        match (&vendor.name, vendor.class) {
+            (_, ApDeviceClass::MobileHotspot) => " (mobile hotspot)".to_string(),
+            (Some(name), _) => format!(" ({})", name),
+            (None, _) => String::new(),
         }
     }
 
@@ -311,6 +442,21 @@ impl InterferenceAnalysis {
             );
         }
 
+        let hotspots = sources
+            .iter()
+            .filter(|s| {
+                s.vendor
+                    .as_ref()
+                    .is_some_and(|v| v.class == ApDeviceClass::MobileHotspot)
+            })
+            .count();
+        if hotspots > 0 {
+            recommendations.push(format!(
+                "{} overlapping network(s) look like mobile hotspots. They come and go, so confirm the congestion persists before changing channels.",
+                hotspots
+            ));
+        }
+
         // Add specific recommendations for detected interference types
         let seen_types: std::collections::HashSet<_> =
             sources.iter().map(|s| s.source_type).collect();
@@ -342,3 +488,58 @@ impl InterferenceAnalysis {
         self.sources.first()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_types::network::MacAddress;
+    use netdiag_types::wifi::{BssActivity, DisconnectBurst, DisconnectFrame};
+
+    #[test]
+    fn test_apply_capture() {
+        let ours = Bssid::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]);
+        let neighbor = Bssid::new([0x00, 0x11, 0x22, 0x66, 0x77, 0x88]);
+        let channel = Channel::from_number(6, WifiBand::Band2_4GHz);
+        let mut analysis = InterferenceAnalysis::analyze(&[], Some(&channel), None);
+        assert_eq!(analysis.interference_score, 0);
+
+        let mut our_bss = BssActivity::new(ours.clone());
+        our_bss.retry_eligible_frames = 100;
+        our_bss.retries = 30;
+        our_bss.airtime_us = 200_000;
+        let mut neighbor_bss = BssActivity::new(neighbor.clone());
+        neighbor_bss.ssid = Some("neighbor".to_string());
+        neighbor_bss.airtime_us = 400_000;
+        let start = chrono::Utc::now();
+        let report = AirtimeReport {
+            duration_secs: 1.0,
+            channel: Some(channel),
+            airtime_us: 600_000,
+            bss: vec![neighbor_bss, our_bss],
+            disconnect_bursts: vec![DisconnectBurst {
+                kind: DisconnectFrame::Deauthentication,
+                transmitter: MacAddress::new(ours.octets()),
+                bssid: ours.clone(),
+                frames: 40,
+                broadcast_frames: 40,
+                targets: 1,
+                reason_codes: vec![7],
+                start,
+                end: start,
+            }],
+            ..AirtimeReport::default()
+        };
+
+        analysis.apply_capture(&report, Some(&ours));
+        assert_eq!(analysis.co_channel_count, 1);
+        assert_eq!(analysis.sources[0].severity, 100);
+        assert!(analysis.sources[0]
+            .description
+            .contains("40% of the airtime"));
+        assert!(analysis.interference_score > 50);
+        assert!(analysis.recommendations[0].contains("busy 60%"));
+        assert!(analysis.recommendations[1].contains("30% of frames"));
+        assert!(analysis.recommendations[2].contains("deauthentication frames"));
+        assert!(analysis.measured.is_some());
+    }
+}
@@ -7,27 +7,62 @@
 //! - Channel optimization recommendations
 //! - Interference detection
 //! - Security analysis
-//! - Site survey capabilities
-//! - Enterprise network support
+//! - 802.11 information element parsing
+//! - Site survey capabilities with floor-plan heatmaps
+//! - Roaming analysis (roam gaps, sticky clients)
+//! - Rogue AP and evil-twin detection
+//! - AP vendor identification (enterprise AP, home router, phone hotspot)
+//! - Expected throughput from link parameters (WiFi vs WAN bottleneck)
+//! - Enterprise network support (802.1X authentication diagnostics)
 
 #![warn(missing_docs)]
 #![warn(clippy::all)]
 
 mod analysis;
 mod channel;
+mod elements;
+mod enterprise;
 mod error;
+mod floorplan;
+mod heatmap;
 mod interference;
+mod planner;
 mod quality;
+mod roaming;
+mod rogue;
 mod security;
 mod survey;
+mod throughput;
+mod vendor;
 
 pub use analysis::{WifiAnalysis, WifiAnalyzer, WifiEnvironment};
 pub use channel::{ChannelAnalysis, ChannelRecommendation};
+pub use elements::{apply_elements, elements_security, parse_elements};
+pub use enterprise::{
+    AuthStage, EnterpriseDiagnosis, EnterpriseIssue, EnterpriseIssueKind, ServerCertificate,
+};
 pub use error::{WifiError, WifiResult};
+pub use floorplan::{FloorPlan, SurveyProject, PROJECT_VERSION};
+pub use heatmap::{Heatmap, HeatmapLayer, HeatmapMetric, HeatmapOptions, HeatmapSample};
 pub use interference::{InterferenceAnalysis, InterferenceSource};
+pub use planner::{
+    ApAssignment, ApLink, ChannelPlan, ChannelPlanConfig, ChannelPlanner, PlannedAp,
+};
 pub use quality::{QualityMetrics, SignalQuality};
-pub use security::{SecurityAnalysis, SecurityIssue, SecurityRating};
-pub use survey::{SiteSurvey, SurveyPoint, SurveyResult};
+pub use roaming::{
+    PingProbe, ProbeResult, ReachabilityProbe, RoamEvent, RoamingConfig, RoamingRecorder,
+    RoamingReport, RoamingTracker, StickyEpisode,
+};
+pub use rogue::{
+    AllowedChannel, AllowedNetwork, ApAllowlist, RogueAnalysis, RogueDetector, RogueFinding,
+    RogueKind,
+};
+pub use security::{IssueSeverity, SecurityAnalysis, SecurityIssue, SecurityRating};
+pub use survey::{ApObservation, FloorPosition, SiteSurvey, SurveyPoint, SurveyResult};
+pub use throughput::{
+    Bottleneck, BottleneckAssessment, LinkParameters, PhyRateSource, ThroughputEstimate,
+};
+pub use vendor::{ApDeviceClass, ApVendor};
 
 use netdiag_platform::WifiProvider;
 use std::sync::Arc;
@@ -63,6 +98,11 @@ impl WifiDiagnostics {
         SiteSurvey::new(self.provider.clone())
     }
 
+    /// Create a roaming tracker.
+    pub fn roaming_tracker(&self, config: RoamingConfig) -> RoamingTracker {
+        RoamingTracker::new(self.provider.clone(), config)
+    }
+
     /// Run a quick WiFi health check.
     pub async fn quick_check(&self, interface: &str) -> WifiResult<WifiAnalysis> {
         self.analyzer().analyze(interface).await
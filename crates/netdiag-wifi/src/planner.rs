@@ -0,0 +1,833 @@
+//! Multi-AP channel planning.
+//!
+//! [`ChannelAnalysis`](crate::ChannelAnalysis) picks the best channel for a
+//! single connection. The planner assigns a channel and width to every AP of
+//! a deployment at once. It treats the problem as weighted graph colouring:
+//! our APs are vertices, edges are weighted by how loud two APs hear each
+//! other, and neighbouring networks add a fixed cost to the spectrum they
+//! occupy. A greedy colouring of the most constrained APs first is refined by
+//! local search until no single AP can improve.
+
+use crate::error::{WifiError, WifiResult};
+use netdiag_types::wifi::{AccessPoint, Channel, ChannelWidth, RegulatoryDomain, WifiBand};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+/// Signal at or below which two radios don't interfere.
+const WEIGHT_FLOOR_DBM: i32 = -95;
+
+/// Signal at or above which two radios interfere fully.
+const WEIGHT_CEILING_DBM: i32 = -55;
+
+/// Overlap between 5/6 GHz channels that are next to each other.
+const ADJACENT_LEAKAGE: f64 = 0.05;
+
+/// Preference for wider channels, per doubling of the width.
+const WIDTH_BONUS: f64 = 0.15;
+
+/// Cost of using a DFS channel (radar events force channel changes).
+const DFS_PENALTY: f64 = 0.05;
+
+/// Cost of moving an AP away from its current channel.
+const CHANGE_PENALTY: f64 = 0.02;
+
+/// Local search passes over all APs.
+const MAX_PASSES: usize = 20;
+
+/// An AP heard by another of our APs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApLink {
+    /// Name of the other AP.
+    pub name: String,
+
+    /// Signal of the other AP in dBm.
+    pub rssi: i32,
+}
+
+/// One of our access points (a single radio).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlannedAp {
+    /// AP name.
+    pub name: String,
+
+    /// BSSID (`aa:bb:cc:dd:ee:ff`), used to exclude the AP from scan results.
+    #[serde(default)]
+    pub bssid: Option<String>,
+
+    /// Current primary channel.
+    #[serde(default)]
+    pub channel: Option<u8>,
+
+    /// Current channel width in MHz.
+    #[serde(default)]
+    pub width: Option<u32>,
+
+    /// Widths this AP may use in MHz (the plan's widths if empty).
+    #[serde(default)]
+    pub widths: Vec<u32>,
+
+    /// Other APs this one hears.
+    #[serde(default)]
+    pub hears: Vec<ApLink>,
+}
+
+/// Channel plan input, loaded from TOML:
+///
+/// ```toml
+/// band = "5ghz"
+/// widths = [20, 40, 80]
+/// allow_dfs = true
+/// country = "DE"
+///
+/// [[ap]]
+/// name = "lobby"
+/// bssid = "00:11:22:33:44:55"
+/// channel = 36
+/// width = 80
+/// hears = [{ name = "kitchen", rssi = -62 }]
+///
+/// [[ap]]
+/// name = "kitchen"
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPlanConfig {
+    /// Band to plan.
+    #[serde(default = "default_band")]
+    pub band: WifiBand,
+
+    /// Allowed channel widths in MHz (20 MHz on 2.4 GHz, up to 80 MHz on
+    /// 5 GHz and up to 160 MHz on 6 GHz if empty).
+    #[serde(default)]
+    pub widths: Vec<u32>,
+
+    /// Whether DFS channels may be used.
+    #[serde(default)]
+    pub allow_dfs: bool,
+
+    /// ISO 3166 country code for channel availability (detected from the
+    /// scan if unset, otherwise channels legal everywhere).
+    #[serde(default)]
+    pub country: Option<String>,
+
+    /// Channels never to use.
+    #[serde(default)]
+    pub exclude: Vec<u8>,
+
+    /// Assumed signal in dBm between APs without a `hears` entry.
+    #[serde(default = "default_coupling")]
+    pub default_coupling: i32,
+
+    /// Our access points.
+    #[serde(default, rename = "ap")]
+    pub aps: Vec<PlannedAp>,
+}
+
+fn default_band() -> WifiBand {
+    WifiBand::Band5GHz
+}
+
+fn default_coupling() -> i32 {
+    -75
+}
+
+impl Default for ChannelPlanConfig {
+    fn default() -> Self {
+        Self {
+            band: default_band(),
+            widths: Vec::new(),
+            allow_dfs: false,
+            country: None,
+            exclude: Vec::new(),
+            default_coupling: default_coupling(),
+            aps: Vec::new(),
+        }
+    }
+}
+
+impl ChannelPlanConfig {
+    /// Parse a plan configuration from TOML.
+    pub fn from_toml(content: &str) -> WifiResult<Self> {
+        toml::from_str(content).map_err(|e| WifiError::InvalidChannelPlan(e.to_string()))
+    }
+
+    /// Load a plan configuration file.
+    pub fn load(path: impl AsRef<Path>) -> WifiResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+            .map_err(|e| WifiError::InvalidChannelPlan(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Add the APs of our SSIDs found in a scan, one per BSSID in the band.
+    pub fn add_scanned_aps(&mut self, scan: &[AccessPoint], ssids: &[String]) {
+        for ap in scan {
+            if ap.channel.band != self.band || !ssids.iter().any(|s| s == ap.ssid.as_str()) {
+                continue;
+            }
+            let bssid = ap.bssid.to_string();
+            if self.aps.iter().any(|p| {
+                p.bssid
+                    .as_ref()
+                    .is_some_and(|b| b.eq_ignore_ascii_case(&bssid))
+            }) {
+                continue;
+            }
+            self.aps.push(PlannedAp {
+                name: format!("{} {}", ap.ssid, bssid),
+                bssid: Some(bssid),
+                channel: Some(ap.channel.number),
+                width: Some(ap.channel.width.mhz()),
+                ..Default::default()
+            });
+        }
+    }
+
+    fn widths_for(&self, ap: &PlannedAp) -> Vec<u32> {
+        if !ap.widths.is_empty() {
+            return ap.widths.clone();
+        }
+        if !self.widths.is_empty() {
+            return self.widths.clone();
+        }
+        match self.band {
+            WifiBand::Band2_4GHz => vec![20],
+            WifiBand::Band5GHz => vec![20, 40, 80],
+            WifiBand::Band6GHz => vec![20, 40, 80, 160],
+        }
+    }
+
+    fn validate(&self) -> WifiResult<()> {
+        if self.aps.is_empty() {
+            return Err(WifiError::InvalidChannelPlan(
+                "no access points".to_string(),
+            ));
+        }
+        for (i, ap) in self.aps.iter().enumerate() {
+            if self.aps[..i].iter().any(|other| other.name == ap.name) {
+                return Err(WifiError::InvalidChannelPlan(format!(
+                    "duplicate AP name '{}'",
+                    ap.name
+                )));
+            }
+            for width in self.widths_for(ap).iter().chain(ap.width.iter()) {
+                if ChannelWidth::from_mhz(*width).is_none() {
+                    return Err(WifiError::InvalidChannelPlan(format!(
+                        "AP '{}': unsupported width {} MHz",
+                        ap.name, width
+                    )));
+                }
+            }
+            if let Some(link) = ap
+                .hears
+                .iter()
+                .find(|link| !self.aps.iter().any(|other| other.name == link.name))
+            {
+                return Err(WifiError::InvalidChannelPlan(format!(
+                    "AP '{}' hears unknown AP '{}'",
+                    ap.name, link.name
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Planned channel for one AP.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApAssignment {
+    /// AP name.
+    pub name: String,
+
+    /// BSSID, if known.
+    pub bssid: Option<String>,
+
+    /// Current channel, if known.
+    pub current: Option<Channel>,
+
+    /// Planned channel and width.
+    pub channel: Channel,
+
+    /// Whether the AP has to change channel or width.
+    pub changed: bool,
+
+    /// Interference from our other APs and neighbouring networks with the
+    /// plan (0 = none, 1 = one strong co-channel neighbour).
+    pub interference: f64,
+}
+
+/// Channel plan for a set of APs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChannelPlan {
+    /// Planned band.
+    pub band: WifiBand,
+
+    /// Country whose regulatory domain was applied (world rules if `None`).
+    pub country: Option<String>,
+
+    /// Per-AP assignments, in input order.
+    pub assignments: Vec<ApAssignment>,
+
+    /// Neighbouring networks taken into account.
+    pub neighbors_considered: usize,
+
+    /// Score of the current channels (0-100), if every AP's channel is known.
+    pub current_score: Option<u8>,
+
+    /// Score of the plan (0-100).
+    pub planned_score: u8,
+
+    /// Notes about constraints that shaped the plan.
+    pub notes: Vec<String>,
+}
+
+impl ChannelPlan {
+    /// Expected score improvement over the current channels.
+    pub fn improvement(&self) -> Option<i16> {
+        self.current_score
+            .map(|current| i16::from(self.planned_score) - i16::from(current))
+    }
+
+    /// Assignments that require a change.
+    pub fn changes(&self) -> impl Iterator<Item = &ApAssignment> {
+        self.assignments.iter().filter(|a| a.changed)
+    }
+}
+
+/// A channel and width an AP could use.
+#[derive(Debug, Clone, PartialEq)]
+struct Candidate {
+    channel: Channel,
+    frequencies: Vec<u32>,
+    dfs: bool,
+}
+
+impl Candidate {
+    fn new(channel: Channel, domain: &RegulatoryDomain) -> Self {
+        Self {
+            frequencies: frequencies(&channel),
+            dfs: domain.is_dfs(&channel),
+            channel,
+        }
+    }
+}
+
+/// Plans channels and widths for a set of APs.
+pub struct ChannelPlanner {
+    config: ChannelPlanConfig,
+    domain: RegulatoryDomain,
+    neighbors: Vec<(Vec<u32>, f64)>,
+}
+
+impl ChannelPlanner {
+    /// Create a planner, validating the configuration.
+    pub fn new(config: ChannelPlanConfig) -> WifiResult<Self> {
+        config.validate()?;
+        let domain = config
+            .country
+            .as_deref()
+            .map_or_else(RegulatoryDomain::world, RegulatoryDomain::for_country);
+        Ok(Self {
+            config,
+            domain,
+            neighbors: Vec::new(),
+        })
+    }
+
+    /// Take neighbouring networks from a scan into account. Our own APs are
+    /// recognised by BSSID and skipped. Without a configured country, the
+    /// regulatory domain is taken from the networks' country elements.
+    pub fn with_neighbors(mut self, scan: &[AccessPoint]) -> Self {
+        let band = self.config.band;
+        if self.config.country.is_none() {
+            self.domain =
+                RegulatoryDomain::detect(scan.iter().filter_map(|ap| ap.country_code.as_deref()));
+        }
+        for ap in scan {
+            if ap.channel.band != band {
+                continue;
+            }
+            let bssid = ap.bssid.to_string();
+            let ours = self.config.aps.iter().any(|p| {
+                p.bssid
+                    .as_ref()
+                    .is_some_and(|b| b.eq_ignore_ascii_case(&bssid))
+            });
+            if ours {
+                continue;
+            }
+            self.neighbors
+                .push((frequencies(&ap.channel), signal_weight(ap.rssi)));
+        }
+        self
+    }
+
+    /// Compute the plan.
+    pub fn plan(&self) -> WifiResult<ChannelPlan> {
+        let config = &self.config;
+        let band = config.band;
+        let n = config.aps.len();
+        let mut notes = Vec::new();
+
+        let candidates: Vec<Vec<Candidate>> = config
+            .aps
+            .iter()
+            .map(|ap| self.candidates_for(ap))
+            .collect();
+        if let Some(i) = candidates.iter().position(Vec::is_empty) {
+            let hint = if self.domain.is_world() {
+                " (no country configured or detected; set one to use channels not legal everywhere)"
+            } else {
+                ""
+            };
+            return Err(WifiError::AnalysisFailed(format!(
+                "no usable {} channels for AP '{}'{}",
+                band, config.aps[i].name, hint
+            )));
+        }
+
+        let coupling = self.coupling_matrix();
+        let current: Vec<Option<Candidate>> =
+            config.aps.iter().map(|ap| self.current_of(ap)).collect();
+        for (ap, current) in config.aps.iter().zip(&current) {
+            match (ap.channel, current) {
+                (Some(channel), None) => notes.push(format!(
+                    "AP '{}': channel {} at {} MHz is not a valid {} channel",
+                    ap.name,
+                    channel,
+                    ap.width.unwrap_or(20),
+                    band
+                )),
+                (_, Some(current)) if !self.domain.allows(&current.channel) => {
+                    notes.push(format!(
+                        "AP '{}': current channel {} at {} MHz is not allowed in {}",
+                        ap.name,
+                        current.channel.number,
+                        current.channel.width.mhz(),
+                        self.domain.country
+                    ));
+                }
+                _ => {}
+            }
+        }
+
+        // Greedy colouring, most constrained AP first
+        let mut order: Vec<usize> = (0..n).collect();
+        order.sort_by(|&a, &b| {
+            let total = |i: usize| coupling[i].iter().sum::<f64>();
+            total(b).total_cmp(&total(a)).then(a.cmp(&b))
+        });
+
+        let mut assigned: Vec<Option<usize>> = vec![None; n];
+        for &i in &order {
+            assigned[i] = Some(self.best_candidate(i, &candidates, &assigned, &coupling, &current));
+        }
+        let mut plan: Vec<usize> = assigned.iter().map(|c| c.unwrap_or(0)).collect();
+
+        // Local search: move single APs while that lowers the total cost
+        for _ in 0..MAX_PASSES {
+            let mut improved = false;
+            for i in 0..n {
+                let others: Vec<Option<usize>> = plan.iter().copied().map(Some).collect();
+                let best = self.best_candidate(i, &candidates, &others, &coupling, &current);
+                let cost = |c: usize| {
+                    self.candidate_cost(i, &candidates[i][c], &candidates, &others, &coupling)
+                        + self.preference(&candidates[i][c], current[i].as_ref())
+                };
+                if best != plan[i] && cost(best) + 1e-9 < cost(plan[i]) {
+                    plan[i] = best;
+                    improved = true;
+                }
+            }
+            if !improved {
+                break;
+            }
+        }
+
+        let chosen: Vec<&Candidate> = plan
+            .iter()
+            .enumerate()
+            .map(|(i, &c)| &candidates[i][c])
+            .collect();
+        let interference = self.interference(&chosen, &coupling);
+
+        let current_score = if current.iter().all(Option::is_some) {
+            let current: Vec<&Candidate> = current.iter().flatten().collect();
+            Some(score(&self.interference(&current, &coupling)))
+        } else {
+            None
+        };
+
+        if self.domain.is_world() {
+            let legal = self.domain.channels(band, ChannelWidth::Mhz20);
+            if let (Some(first), Some(last)) = (legal.first(), legal.last()) {
+                notes.push(format!(
+                    "No country configured or detected; only {} channels {}-{}, legal in every \
+                     country, were used",
+                    band, first.number, last.number
+                ));
+            }
+        }
+        let dfs_channels = self
+            .domain
+            .channels(band, ChannelWidth::Mhz20)
+            .iter()
+            .filter(|c| self.domain.is_dfs(c))
+            .count();
+        if !config.allow_dfs && dfs_channels > 0 {
+            notes.push(format!(
+                "DFS channels were excluded; allowing them adds {} channel(s)",
+                dfs_channels
+            ));
+        }
+        for assignment in chosen.iter().filter(|c| c.dfs) {
+            if let Some(cac) = self.domain.cac_time(&assignment.channel) {
+                notes.push(format!(
+                    "Channel {} needs a {} s radar check before use",
+                    assignment.channel.number,
+                    cac.as_secs()
+                ));
+            }
+        }
+        if chosen
+            .iter()
+            .any(|c| self.domain.is_indoor_only(&c.channel))
+        {
+            notes.push(format!(
+                "Some planned channels are for indoor use only in {}",
+                self.domain.country
+            ));
+        }
+
+        let assignments = config
+            .aps
+            .iter()
+            .enumerate()
+            .map(|(i, ap)| {
+                let channel = chosen[i].channel;
+                ApAssignment {
+                    name: ap.name.clone(),
+                    bssid: ap.bssid.clone(),
+                    current: current[i].as_ref().map(|c| c.channel),
+                    changed: current[i].as_ref() != Some(chosen[i]),
+                    channel,
+                    interference: interference[i],
+                }
+            })
+            .collect();
+
+        Ok(ChannelPlan {
+            band,
+            assignments,
+            country: (!self.domain.is_world()).then(|| self.domain.country.clone()),
+            neighbors_considered: self.neighbors.len(),
+            current_score,
+            planned_score: score(&interference),
+            notes,
+        })
+    }
+
+    /// Channels and widths an AP may use.
+    fn candidates_for(&self, ap: &PlannedAp) -> Vec<Candidate> {
+        let config = &self.config;
+        config
+            .widths_for(ap)
+            .into_iter()
+            .filter_map(ChannelWidth::from_mhz)
+            .flat_map(|width| self.domain.channels(config.band, width))
+            .filter(|channel| {
+                !channel
+                    .subchannels()
+                    .iter()
+                    .any(|n| config.exclude.contains(n))
+            })
+            .map(|channel| Candidate::new(channel, &self.domain))
+            .filter(|candidate| config.allow_dfs || !candidate.dfs)
+            .collect()
+    }
+
+    /// The AP's current channel as a candidate.
+    fn current_of(&self, ap: &PlannedAp) -> Option<Candidate> {
+        let width = ChannelWidth::from_mhz(ap.width.unwrap_or(20))?;
+        Channel::bonded(ap.channel?, self.config.band, width)
+            .map(|channel| Candidate::new(channel, &self.domain))
+    }
+
+    /// Symmetric AP-to-AP interference weights.
+    fn coupling_matrix(&self) -> Vec<Vec<f64>> {
+        let aps = &self.config.aps;
+        let default = signal_weight(self.config.default_coupling);
+        let mut heard: Vec<Vec<Option<f64>>> = vec![vec![None; aps.len()]; aps.len()];
+        for (i, ap) in aps.iter().enumerate() {
+            for link in &ap.hears {
+                if let Some(j) = aps.iter().position(|other| other.name == link.name) {
+                    let weight = signal_weight(link.rssi);
+                    heard[i][j] = Some(heard[i][j].map_or(weight, |w: f64| w.max(weight)));
+                }
+            }
+        }
+
+        (0..aps.len())
+            .map(|i| {
+                (0..aps.len())
+                    .map(|j| {
+                        if i == j {
+                            0.0
+                        } else {
+                            match (heard[i][j], heard[j][i]) {
+                                (Some(a), Some(b)) => a.max(b),
+                                (Some(w), None) | (None, Some(w)) => w,
+                                (None, None) => default,
+                            }
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Interference cost of AP `i` on a candidate, given the other APs' choices.
+    fn candidate_cost(
+        &self,
+        i: usize,
+        candidate: &Candidate,
+        candidates: &[Vec<Candidate>],
+        assigned: &[Option<usize>],
+        coupling: &[Vec<f64>],
+    ) -> f64 {
+        let band = self.config.band;
+        let ours: f64 = assigned
+            .iter()
+            .enumerate()
+            .filter(|&(j, _)| j != i)
+            .filter_map(|(j, c)| c.map(|c| (j, &candidates[j][c])))
+            .map(|(j, other)| {
+                coupling[i][j] * overlap(band, &candidate.frequencies, &other.frequencies)
+            })
+            .sum();
+        ours + self.neighbor_cost(&candidate.frequencies)
+    }
+
+    fn neighbor_cost(&self, frequencies: &[u32]) -> f64 {
+        let band = self.config.band;
+        self.neighbors
+            .iter()
+            .map(|(other, weight)| weight * overlap(band, frequencies, other))
+            .sum()
+    }
+
+    /// Non-interference terms: wider channels, DFS and changes.
+    fn preference(&self, candidate: &Candidate, current: Option<&Candidate>) -> f64 {
+        let mut cost = -WIDTH_BONUS * f64::from(candidate.channel.width.mhz() / 20).log2();
+        if candidate.dfs {
+            cost += DFS_PENALTY;
+        }
+        if current != Some(candidate) {
+            cost += CHANGE_PENALTY;
+        }
+        cost
+    }
+
+    fn best_candidate(
+        &self,
+        i: usize,
+        candidates: &[Vec<Candidate>],
+        assigned: &[Option<usize>],
+        coupling: &[Vec<f64>],
+        current: &[Option<Candidate>],
+    ) -> usize {
+        let cost = |c: &Candidate| {
+            self.candidate_cost(i, c, candidates, assigned, coupling)
+                + self.preference(c, current[i].as_ref())
+        };
+        candidates[i]
+            .iter()
+            .enumerate()
+            .min_by(|(a_idx, a), (b_idx, b)| cost(a).total_cmp(&cost(b)).then(a_idx.cmp(b_idx)))
+            .map_or(0, |(idx, _)| idx)
+    }
+
+    /// Per-AP interference for a complete assignment.
+    fn interference(&self, chosen: &[&Candidate], coupling: &[Vec<f64>]) -> Vec<f64> {
+        let band = self.config.band;
+        (0..chosen.len())
+            .map(|i| {
+                let ours: f64 = (0..chosen.len())
+                    .filter(|&j| j != i)
+                    .map(|j| {
+                        coupling[i][j]
+                            * overlap(band, &chosen[i].frequencies, &chosen[j].frequencies)
+                    })
+                    .sum();
+                ours + self.neighbor_cost(&chosen[i].frequencies)
+            })
+            .collect()
+    }
+}
+
+/// Interference weight of a signal (0 at -95 dBm, 1 at -55 dBm and above).
+fn signal_weight(rssi: i32) -> f64 {
+    let span = f64::from(WEIGHT_CEILING_DBM - WEIGHT_FLOOR_DBM);
+    (f64::from(rssi - WEIGHT_FLOOR_DBM) / span).clamp(0.0, 1.0)
+}
+
+/// Spectral overlap of two channels given their 20 MHz center frequencies
+/// (0 = none, 1 = same spectrum), averaged over both directions so that a
+/// narrow channel inside a wide one counts less than two equal channels.
+fn overlap(band: WifiBand, a: &[u32], b: &[u32]) -> f64 {
+    let directed = |a: &[u32], b: &[u32]| {
+        a.iter()
+            .map(|&x| {
+                b.iter()
+                    .map(|&y| subchannel_overlap(band, x.abs_diff(y)))
+                    .fold(0.0, f64::max)
+            })
+            .sum::<f64>()
+            / a.len() as f64
+    };
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    (directed(a, b) + directed(b, a)) / 2.0
+}
+
+fn subchannel_overlap(band: WifiBand, separation: u32) -> f64 {
+    match (band, separation) {
+        (_, 0) => 1.0,
+        // 2.4 GHz channels are 5 MHz apart but 22 MHz wide
+        (WifiBand::Band2_4GHz, d) => (1.0 - f64::from(d) / 25.0).max(0.0),
+        (_, 20) => ADJACENT_LEAKAGE,
+        _ => 0.0,
+    }
+}
+
+/// Plan score from per-AP interference (100 = no interference).
+fn score(interference: &[f64]) -> u8 {
+    if interference.is_empty() {
+        return 100;
+    }
+    let average = interference.iter().sum::<f64>() / interference.len() as f64;
+    (100.0 / (1.0 + average)).round() as u8
+}
+
+/// Center frequencies of the 20 MHz channels a channel covers.
+fn frequencies(channel: &Channel) -> Vec<u32> {
+    channel
+        .subchannels()
+        .into_iter()
+        .map(|n| Channel::from_number(n, channel.band).frequency)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_types::wifi::{Bssid, Ssid};
+
+    fn neighbor(channel: Channel, rssi: i32) -> AccessPoint {
+        AccessPoint {
+            ssid: Ssid::new("neighbor"),
+            bssid: Bssid::new([0x00, 0x11, 0x22, 0x33, 0x44, 0x55]),
+            rssi,
+            channel,
+            ..AccessPoint::test_default()
+        }
+    }
+
+    #[test]
+    fn test_plan_2_4ghz_colouring() {
+        let config = ChannelPlanConfig::from_toml(
+            r#"
+            band = "2.4ghz"
+
+            [[ap]]
+            name = "a"
+            channel = 6
+            hears = [{ name = "b", rssi = -55 }, { name = "c", rssi = -60 }]
+
+            [[ap]]
+            name = "b"
+            channel = 6
+            hears = [{ name = "c", rssi = -55 }]
+
+            [[ap]]
+            name = "c"
+            channel = 6
+            "#,
+        )
+        .unwrap();
+
+        let plan = ChannelPlanner::new(config).unwrap().plan().unwrap();
+        let mut channels: Vec<u8> = plan.assignments.iter().map(|a| a.channel.number).collect();
+        channels.sort_unstable();
+        assert_eq!(channels, vec![1, 6, 11]);
+        assert!(plan.planned_score > plan.current_score.unwrap());
+        assert!(plan.improvement().unwrap() > 0);
+        // One AP can stay on channel 6
+        assert_eq!(plan.changes().count(), 2);
+        assert!(plan.notes.iter().any(|n| n.contains("channels 1-11")));
+    }
+
+    #[test]
+    fn test_plan_5ghz_constraints() {
+        let mut config = ChannelPlanConfig {
+            band: WifiBand::Band5GHz,
+            widths: vec![80],
+            country: Some("jp".to_string()),
+            ..Default::default()
+        };
+        for name in ["a", "b"] {
+            config.aps.push(PlannedAp {
+                name: name.to_string(),
+                ..Default::default()
+            });
+        }
+
+        // Without DFS, Japan leaves a single 80 MHz block (36-48), which a
+        // loud neighbour occupies
+        let mut busy = Channel::from_number(36, WifiBand::Band5GHz);
+        busy.width = ChannelWidth::Mhz80;
+        busy.center_frequency = Some(5210);
+        let scan = [neighbor(busy, -50)];
+
+        let planner = ChannelPlanner::new(config.clone()).unwrap();
+        assert!(planner.plan().is_ok());
+
+        config.allow_dfs = true;
+        let plan = ChannelPlanner::new(config.clone())
+            .unwrap()
+            .with_neighbors(&scan)
+            .plan()
+            .unwrap();
+        assert_eq!(plan.neighbors_considered, 1);
+        let mut primaries: Vec<u8> = plan.assignments.iter().map(|a| a.channel.number).collect();
+        primaries.sort_unstable();
+        primaries.dedup();
+        assert_eq!(primaries.len(), 2);
+        for assignment in &plan.assignments {
+            let number = assignment.channel.number;
+            assert!((52..=132).contains(&number), "channel {number}");
+            assert_eq!(assignment.channel.width, ChannelWidth::Mhz80);
+        }
+
+        // Without a country only 36-64 is legal everywhere, DFS or not
+        config.country = None;
+        config.widths = vec![20];
+        let plan = ChannelPlanner::new(config).unwrap().plan().unwrap();
+        assert!(plan.assignments.iter().all(|a| a.channel.number <= 64));
+        assert!(plan.notes.iter().any(|n| n.contains("channels 36-64")));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let config = ChannelPlanConfig::from_toml(
+            r#"
+            [[ap]]
+            name = "a"
+            hears = [{ name = "missing", rssi = -60 }]
+            "#,
+        )
+        .unwrap();
+        assert!(ChannelPlanner::new(config).is_err());
+        assert!(ChannelPlanner::new(ChannelPlanConfig::default()).is_err());
+    }
+}
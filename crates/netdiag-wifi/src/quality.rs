@@ -1,5 +1,6 @@
 //! Signal quality assessment.
 
+use crate::throughput::ThroughputEstimate;
 use netdiag_types::wifi::WifiConnection;
 use serde::{Deserialize, Serialize};
 
@@ -99,6 +100,10 @@ pub struct QualityMetrics {
     /// Number of spatial streams.
     pub spatial_streams: Option<u8>,
 
+    /// Expected TCP goodput in Mbps on an idle channel.
+    #[serde(default)]
+    pub expected_throughput: Option<f64>,
+
     /// Overall score (0-100).
     pub overall_score: u8,
 
@@ -130,6 +135,7 @@ impl QualityMetrics {
             mcs_index: None,
             channel_width: None,
             spatial_streams: None,
+            expected_throughput: None,
             overall_score,
             recommendations,
         }
@@ -142,6 +148,10 @@ impl QualityMetrics {
         let mut metrics = Self::new(rssi, noise);
         metrics.tx_rate = connection.tx_rate.map(|r| r as f64);
         metrics.channel_width = Some(connection.access_point.channel.width.mhz() as u16);
+        metrics.mcs_index = connection.mcs_index;
+        metrics.spatial_streams = connection.spatial_streams;
+        metrics.expected_throughput = ThroughputEstimate::from_connection(connection, None)
+            .map(|estimate| estimate.expected_goodput_mbps);
         metrics
     }
 
@@ -221,7 +231,8 @@ impl QualityMetrics {
 
     /// Check if connection is suitable for video streaming.
     pub fn suitable_for_video(&self) -> bool {
-        self.quality >= SignalQuality::Fair && self.tx_rate.unwrap_or(10.0) >= 5.0
+        self.quality >= SignalQuality::Fair
+            && self.expected_throughput.or(self.tx_rate).unwrap_or(10.0) >= 5.0
     }
 
     /// Check if connection is suitable for gaming.
@@ -232,7 +243,7 @@ impl QualityMetrics {
     /// Check if connection is suitable for video conferencing.
     pub fn suitable_for_conferencing(&self) -> bool {
         self.quality >= SignalQuality::Fair
-            && self.tx_rate.unwrap_or(2.0) >= 1.5
+            && self.expected_throughput.or(self.tx_rate).unwrap_or(2.0) >= 1.5
             && self.snr.unwrap_or(15.0) >= 10.0
     }
 }
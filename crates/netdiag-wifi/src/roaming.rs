@@ -0,0 +1,1074 @@
+//! Roaming analysis: BSSID transitions, roam gaps and sticky clients.
+
+use crate::error::{WifiError, WifiResult};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use netdiag_connectivity::{PingConfig, Pinger};
+use netdiag_platform::WifiProvider;
+use netdiag_types::wifi::{AccessPoint, Channel, KeyManagement, WifiConnection};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::sync::mpsc;
+use tokio::time::Instant;
+use tracing::{debug, info, warn};
+
+/// Probes around a roam that are attributed to it.
+const ROAM_WINDOW: Duration = Duration::from_secs(2);
+
+/// Connectivity gap above which voice calls are audibly affected.
+const VOICE_GAP: Duration = Duration::from_millis(150);
+
+/// A reachability check run concurrently with connection sampling.
+#[async_trait]
+pub trait ReachabilityProbe: Send + Sync + 'static {
+    /// Send one probe and report whether a reply arrived.
+    async fn probe(&self) -> bool;
+
+    /// Probe every `interval` until `deadline`.
+    ///
+    /// The default sends each probe with [`probe`](Self::probe) on a fixed
+    /// schedule, without waiting for earlier replies.
+    async fn run(self: Arc<Self>, interval: Duration, deadline: Instant) -> Vec<ProbeResult> {
+        probe_on_schedule(self, interval, deadline).await
+    }
+}
+
+/// ICMP echo probe using the system ping.
+///
+/// A tracking session runs a single `ping -i` process and matches its
+/// replies to the echo requests by sequence number.
+pub struct PingProbe {
+    target: IpAddr,
+    timeout: Duration,
+}
+
+impl PingProbe {
+    /// Create a ping probe; replies later than `timeout` count as lost.
+    pub fn new(target: IpAddr, timeout: Duration) -> Self {
+        Self { target, timeout }
+    }
+
+    /// A ping process sending an echo request every `interval`.
+    fn command(&self, interval: Duration) -> tokio::process::Command {
+        let mut command = tokio::process::Command::new("ping");
+        command
+            .args(["-n", "-i", &format!("{:.3}", interval.as_secs_f64())])
+            .arg(self.target.to_string())
+            .stdout(std::process::Stdio::piped())
+            .stderr(std::process::Stdio::null())
+            .kill_on_drop(true);
+        command
+    }
+}
+
+#[async_trait]
+impl ReachabilityProbe for PingProbe {
+    async fn probe(&self) -> bool {
+        let target = self.target;
+        // The system ping only accepts whole-second timeouts
+        let config = PingConfig {
+            count: 1,
+            timeout: self.timeout.max(Duration::from_secs(1)),
+            ..Default::default()
+        };
+
+        // The pinger waits on the ping process synchronously; keep it off the
+        // runtime workers so connection sampling is not delayed.
+        let handle = tokio::runtime::Handle::current();
+        tokio::task::spawn_blocking(move || handle.block_on(Pinger::new().ping(target, &config)))
+            .await
+            .ok()
+            .and_then(Result::ok)
+            .is_some_and(|stats| stats.received > 0)
+    }
+
+    async fn run(self: Arc<Self>, interval: Duration, deadline: Instant) -> Vec<ProbeResult> {
+        // Windows ping can't send more than one request per second or report
+        // sequence numbers; one probe process at a time at most
+        if cfg!(windows) {
+            let interval = interval.max(self.timeout).max(Duration::from_secs(1));
+            return probe_on_schedule(self, interval, deadline).await;
+        }
+
+        let mut child = match self.command(interval).spawn() {
+            Ok(child) => child,
+            Err(e) => {
+                warn!("Failed to start ping to {}: {}", self.target, e);
+                return Vec::new();
+            }
+        };
+        let started = Instant::now();
+        let started_at = Utc::now();
+        let Some(stdout) = child.stdout.take() else {
+            return Vec::new();
+        };
+
+        // Keep reading for one timeout past the deadline so the last
+        // requests can still be answered
+        let mut lines = BufReader::new(stdout).lines();
+        let mut replies = Vec::new();
+        let stop = deadline + self.timeout;
+        let ended = loop {
+            tokio::select! {
+                line = lines.next_line() => match line {
+                    Ok(Some(line)) => {
+                        if let Some(sequence) = reply_sequence(&line) {
+                            replies.push((sequence, started.elapsed()));
+                        }
+                    }
+                    _ => {
+                        warn!("ping to {} exited early", self.target);
+                        break started.elapsed();
+                    }
+                },
+                () = tokio::time::sleep_until(stop) => break stop - started,
+            }
+        };
+        let _ = child.kill().await;
+
+        let probing = ended.min(deadline.saturating_duration_since(started));
+        let sent = probes_sent(probing, interval);
+        probe_results(started_at, interval, self.timeout, sent, &replies)
+    }
+}
+
+/// Sequence number of the first echo request (iputils starts at 1, BSD at 0).
+const FIRST_SEQUENCE: u32 = if cfg!(target_os = "linux") { 1 } else { 0 };
+
+/// Sequence number of an echo reply line of `ping` output.
+fn reply_sequence(line: &str) -> Option<u32> {
+    if !line.contains("bytes from") {
+        return None;
+    }
+    let value = line.split("icmp_seq=").nth(1)?;
+    let digits: String = value.chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().ok()
+}
+
+/// Echo requests sent in `elapsed`, the first at the start.
+fn probes_sent(elapsed: Duration, interval: Duration) -> usize {
+    let interval = interval.as_nanos().max(1);
+    usize::try_from(elapsed.as_nanos() / interval + 1).unwrap_or(usize::MAX)
+}
+
+/// Probe results for `sent` requests, given the replies' sequence numbers
+/// and arrival times. Sequence numbers wrap at 16 bits; a reply matches the
+/// request it could have answered within `timeout`.
+fn probe_results(
+    started: DateTime<Utc>,
+    interval: Duration,
+    timeout: Duration,
+    sent: usize,
+    replies: &[(u32, Duration)],
+) -> Vec<ProbeResult> {
+    (0..sent)
+        .map(|index| {
+            let offset = interval * u32::try_from(index).unwrap_or(u32::MAX);
+            let sequence = (FIRST_SEQUENCE + u32::try_from(index % 65536).unwrap_or(0)) % 65536;
+            let success = replies.iter().any(|&(seq, arrived)| {
+                seq == sequence && arrived >= offset && arrived - offset <= timeout
+            });
+            ProbeResult {
+                sent: started + chrono::Duration::from_std(offset).unwrap_or_default(),
+                success,
+            }
+        })
+        .collect()
+}
+
+/// Wall-clock time advanced by the runtime clock, so timestamps follow the
+/// sampling schedule.
+#[derive(Debug, Clone, Copy)]
+struct Clock {
+    started: Instant,
+    started_at: DateTime<Utc>,
+}
+
+impl Clock {
+    fn start() -> Self {
+        Self {
+            started: Instant::now(),
+            started_at: Utc::now(),
+        }
+    }
+
+    fn now(&self) -> DateTime<Utc> {
+        self.started_at + chrono::Duration::from_std(self.started.elapsed()).unwrap_or_default()
+    }
+}
+
+/// Result of a single reachability probe.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    /// When the probe was sent.
+    pub sent: DateTime<Utc>,
+
+    /// Whether a reply arrived.
+    pub success: bool,
+}
+
+/// Roaming tracker configuration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoamingConfig {
+    /// How often the current connection is sampled.
+    pub sample_interval: Duration,
+
+    /// How often a reachability probe is sent.
+    pub probe_interval: Duration,
+
+    /// How often cached scan results are checked for better APs.
+    /// `None` disables sticky client detection.
+    pub scan_interval: Option<Duration>,
+
+    /// A same-SSID AP this much stronger (dB) counts as a better choice.
+    pub sticky_margin_db: i32,
+
+    /// Only flag sticky clients when the current RSSI is at or below this.
+    pub sticky_rssi_dbm: i32,
+}
+
+impl Default for RoamingConfig {
+    fn default() -> Self {
+        Self {
+            sample_interval: Duration::from_millis(100),
+            probe_interval: Duration::from_millis(100),
+            scan_interval: Some(Duration::from_secs(10)),
+            sticky_margin_db: 10,
+            sticky_rssi_dbm: -70,
+        }
+    }
+}
+
+/// A transition from one BSSID to another.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoamEvent {
+    /// When the new BSSID was first seen.
+    pub timestamp: DateTime<Utc>,
+
+    /// Network name.
+    pub ssid: String,
+
+    /// BSSID before the roam.
+    pub from_bssid: String,
+
+    /// BSSID after the roam.
+    pub to_bssid: String,
+
+    /// Channel before the roam.
+    pub from_channel: Channel,
+
+    /// Channel after the roam.
+    pub to_channel: Channel,
+
+    /// Last RSSI on the old AP.
+    pub rssi_before: i32,
+
+    /// First RSSI on the new AP.
+    pub rssi_after: i32,
+
+    /// Time between the last sample on the old AP and the first on the new
+    /// one (an upper bound on the association gap).
+    pub association_gap: Duration,
+
+    /// Connectivity outage measured by the concurrent probes, if any ran.
+    pub connectivity_gap: Option<Duration>,
+
+    /// Probes lost around the roam.
+    pub probes_lost: usize,
+
+    /// The client reassociated using 802.11r fast transition (an FT AKM).
+    pub fast_transition: bool,
+
+    /// Both APs advertise 802.11k neighbor reports; whether the client
+    /// requested one is not observable.
+    pub neighbor_report_supported: bool,
+
+    /// Both APs advertise 802.11v BSS transition management; whether the
+    /// roam was steered by a transition request is not observable.
+    pub bss_transition_supported: bool,
+}
+
+impl RoamEvent {
+    /// Whether the roam moved to a different band.
+    pub fn is_band_change(&self) -> bool {
+        self.from_channel.band != self.to_channel.band
+    }
+
+    /// Signal gained by roaming (dB).
+    pub fn rssi_gain(&self) -> i32 {
+        self.rssi_after - self.rssi_before
+    }
+}
+
+/// A period where the client stayed on a weak AP while a clearly stronger AP
+/// for the same SSID was available.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StickyEpisode {
+    /// When the better AP was first seen.
+    pub started: DateTime<Utc>,
+
+    /// When the client roamed or the condition cleared.
+    pub ended: DateTime<Utc>,
+
+    /// Network name.
+    pub ssid: String,
+
+    /// BSSID the client stuck to.
+    pub bssid: String,
+
+    /// Weakest RSSI on the connected AP during the episode.
+    pub rssi: i32,
+
+    /// Strongest alternative AP.
+    pub best_bssid: String,
+
+    /// RSSI of the strongest alternative.
+    pub best_rssi: i32,
+
+    /// Whether the episode ended with a roam.
+    pub resolved_by_roam: bool,
+}
+
+impl StickyEpisode {
+    /// Duration of the episode.
+    pub fn duration(&self) -> Duration {
+        (self.ended - self.started).to_std().unwrap_or_default()
+    }
+}
+
+/// Results of a roaming tracking session.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RoamingReport {
+    /// Tracking start.
+    pub started: DateTime<Utc>,
+
+    /// Tracking end.
+    pub ended: DateTime<Utc>,
+
+    /// Connection samples taken while associated.
+    pub samples: usize,
+
+    /// Samples taken while not associated.
+    pub disconnected_samples: usize,
+
+    /// Detected roams.
+    pub roams: Vec<RoamEvent>,
+
+    /// Sticky client episodes.
+    pub sticky_episodes: Vec<StickyEpisode>,
+
+    /// Reachability probes sent.
+    pub probes_sent: usize,
+
+    /// Reachability probes lost.
+    pub probes_lost: usize,
+
+    /// Recommendations.
+    pub recommendations: Vec<String>,
+}
+
+impl RoamingReport {
+    /// Longest connectivity gap across all roams.
+    pub fn max_connectivity_gap(&self) -> Option<Duration> {
+        self.roams.iter().filter_map(|r| r.connectivity_gap).max()
+    }
+
+    /// Average connectivity gap across roams with probe data.
+    pub fn average_connectivity_gap(&self) -> Option<Duration> {
+        let gaps: Vec<Duration> = self
+            .roams
+            .iter()
+            .filter_map(|r| r.connectivity_gap)
+            .collect();
+        if gaps.is_empty() {
+            return None;
+        }
+        Some(gaps.iter().sum::<Duration>() / gaps.len() as u32)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Sample {
+    at: DateTime<Utc>,
+    ssid: String,
+    bssid: String,
+    channel: Channel,
+    rssi: i32,
+    key_management: KeyManagement,
+}
+
+/// Accumulates connection samples and scan results into a [`RoamingReport`].
+///
+/// [`RoamingTracker`] feeds this from a live provider; it can also be fed
+/// from recorded data.
+pub struct RoamingRecorder {
+    config: RoamingConfig,
+    started: Option<DateTime<Utc>>,
+    samples: usize,
+    disconnected: usize,
+    current: Option<Sample>,
+    known: HashMap<String, AccessPoint>,
+    roams: Vec<RoamEvent>,
+    sticky: Vec<StickyEpisode>,
+    open_sticky: Option<StickyEpisode>,
+}
+
+impl RoamingRecorder {
+    /// Create an empty recorder.
+    pub fn new(config: RoamingConfig) -> Self {
+        Self {
+            config,
+            started: None,
+            samples: 0,
+            disconnected: 0,
+            current: None,
+            known: HashMap::new(),
+            roams: Vec::new(),
+            sticky: Vec::new(),
+            open_sticky: None,
+        }
+    }
+
+    /// Record a connection sample (`None` while not associated).
+    pub fn record_connection(&mut self, at: DateTime<Utc>, connection: Option<&WifiConnection>) {
+        self.started.get_or_insert(at);
+
+        let Some(connection) = connection else {
+            self.disconnected += 1;
+            return;
+        };
+        self.samples += 1;
+
+        let ap = &connection.access_point;
+        let sample = Sample {
+            at,
+            ssid: ap.ssid.to_string(),
+            bssid: ap.bssid.to_string(),
+            channel: ap.channel,
+            rssi: ap.rssi,
+            key_management: ap.security.key_management,
+        };
+        self.remember(ap);
+
+        if let Some(previous) = self.current.take() {
+            if previous.bssid != sample.bssid {
+                debug!(
+                    "Roam {} -> {} ({} -> {} dBm)",
+                    previous.bssid, sample.bssid, previous.rssi, sample.rssi
+                );
+                self.roams.push(self.roam_event(&previous, &sample));
+                if let Some(mut episode) = self.open_sticky.take() {
+                    episode.ended = at;
+                    episode.resolved_by_roam = true;
+                    self.sticky.push(episode);
+                }
+            }
+        }
+        self.current = Some(sample);
+    }
+
+    /// Record scan results, checking for a sticky client.
+    pub fn record_scan(&mut self, at: DateTime<Utc>, access_points: &[AccessPoint]) {
+        for ap in access_points {
+            self.remember(ap);
+        }
+
+        let Some(current) = &self.current else {
+            return;
+        };
+        let best = access_points
+            .iter()
+            .filter(|ap| ap.ssid.as_str() == current.ssid && ap.bssid.to_string() != current.bssid)
+            .max_by_key(|ap| ap.rssi);
+
+        let sticky = best.filter(|best| {
+            current.rssi <= self.config.sticky_rssi_dbm
+                && best.rssi - current.rssi >= self.config.sticky_margin_db
+        });
+
+        match (sticky, self.open_sticky.as_mut()) {
+            (Some(best), Some(episode)) => {
+                episode.ended = at;
+                episode.rssi = episode.rssi.min(current.rssi);
+                if best.rssi > episode.best_rssi {
+                    episode.best_rssi = best.rssi;
+                    episode.best_bssid = best.bssid.to_string();
+                }
+            }
+            (Some(best), None) => {
+                self.open_sticky = Some(StickyEpisode {
+                    started: at,
+                    ended: at,
+                    ssid: current.ssid.clone(),
+                    bssid: current.bssid.clone(),
+                    rssi: current.rssi,
+                    best_bssid: best.bssid.to_string(),
+                    best_rssi: best.rssi,
+                    resolved_by_roam: false,
+                });
+            }
+            (None, Some(_)) => {
+                let mut episode = self.open_sticky.take().expect("episode is open");
+                episode.ended = at;
+                self.sticky.push(episode);
+            }
+            (None, None) => {}
+        }
+    }
+
+    /// Finish recording and attribute probe results to roams.
+    pub fn finish(mut self, at: DateTime<Utc>, probes: &[ProbeResult]) -> RoamingReport {
+        if let Some(mut episode) = self.open_sticky.take() {
+            episode.ended = at;
+            self.sticky.push(episode);
+        }
+
+        let mut probes = probes.to_vec();
+        probes.sort_by_key(|p| p.sent);
+        if !probes.is_empty() {
+            for roam in &mut self.roams {
+                let (gap, lost) = connectivity_gap(roam, &probes, self.config.probe_interval);
+                roam.connectivity_gap = Some(gap);
+                roam.probes_lost = lost;
+            }
+        }
+
+        let recommendations = recommendations(&self.roams, &self.sticky);
+
+        RoamingReport {
+            started: self.started.unwrap_or(at),
+            ended: at,
+            samples: self.samples,
+            disconnected_samples: self.disconnected,
+            roams: self.roams,
+            sticky_episodes: self.sticky,
+            probes_sent: probes.len(),
+            probes_lost: probes.iter().filter(|p| !p.success).count(),
+            recommendations,
+        }
+    }
+
+    fn remember(&mut self, ap: &AccessPoint) {
+        let bssid = ap.bssid.to_string();
+        // Prefer scan entries that carry decoded elements over bare link info.
+        if ap.elements.is_some() || !self.known.contains_key(&bssid) {
+            self.known.insert(bssid, ap.clone());
+        }
+    }
+
+    fn roam_event(&self, from: &Sample, to: &Sample) -> RoamEvent {
+        // Advertised 802.11r says nothing about the client; the AKM it
+        // negotiated on the new AP does.
+        let fast_transition = matches!(
+            to.key_management,
+            KeyManagement::FtPsk | KeyManagement::FtSae | KeyManagement::FtEap
+        );
+        let (neighbor_report_supported, bss_transition_supported) =
+            match (self.known.get(&from.bssid), self.known.get(&to.bssid)) {
+                (Some(a), Some(b)) => (
+                    a.capabilities.dot11k && b.capabilities.dot11k,
+                    a.capabilities.dot11v && b.capabilities.dot11v,
+                ),
+                _ => (false, false),
+            };
+
+        RoamEvent {
+            timestamp: to.at,
+            ssid: to.ssid.clone(),
+            from_bssid: from.bssid.clone(),
+            to_bssid: to.bssid.clone(),
+            from_channel: from.channel,
+            to_channel: to.channel,
+            rssi_before: from.rssi,
+            rssi_after: to.rssi,
+            association_gap: (to.at - from.at).to_std().unwrap_or_default(),
+            connectivity_gap: None,
+            probes_lost: 0,
+            fast_transition,
+            neighbor_report_supported,
+            bss_transition_supported,
+        }
+    }
+}
+
+/// Outage around a roam: the span of lost probes plus one probe interval.
+fn connectivity_gap(
+    roam: &RoamEvent,
+    probes: &[ProbeResult],
+    probe_interval: Duration,
+) -> (Duration, usize) {
+    let window = chrono::Duration::from_std(ROAM_WINDOW).unwrap_or_default();
+    let association = chrono::Duration::from_std(roam.association_gap).unwrap_or_default();
+    let start = roam.timestamp - association - window;
+    let end = roam.timestamp + window;
+
+    let lost: Vec<&ProbeResult> = probes
+        .iter()
+        .filter(|p| !p.success && p.sent >= start && p.sent <= end)
+        .collect();
+    match (lost.first(), lost.last()) {
+        (Some(first), Some(last)) => {
+            let span = (last.sent - first.sent).to_std().unwrap_or_default();
+            (span + probe_interval, lost.len())
+        }
+        _ => (Duration::ZERO, 0),
+    }
+}
+
+fn recommendations(roams: &[RoamEvent], sticky: &[StickyEpisode]) -> Vec<String> {
+    let mut recommendations = Vec::new();
+
+    let slow: Vec<&RoamEvent> = roams
+        .iter()
+        .filter(|r| r.connectivity_gap.is_some_and(|g| g > VOICE_GAP))
+        .collect();
+    if !slow.is_empty() {
+        let worst = slow
+            .iter()
+            .filter_map(|r| r.connectivity_gap)
+            .max()
+            .unwrap_or_default();
+        let mut rec = format!(
+            "{} roam(s) interrupted traffic for more than {} ms (worst {} ms), long enough to drop voice.",
+            slow.len(),
+            VOICE_GAP.as_millis(),
+            worst.as_millis()
+        );
+        if slow.iter().any(|r| !r.fast_transition) {
+            rec.push_str(" Enable 802.11r fast transition on the SSID.");
+        }
+        recommendations.push(rec);
+    }
+
+    if !roams.is_empty()
+        && roams
+            .iter()
+            .all(|r| !r.neighbor_report_supported && !r.bss_transition_supported)
+    {
+        recommendations.push(
+            "APs do not advertise 802.11k/v. Enabling neighbor reports and BSS transition management helps clients pick the next AP before signal degrades."
+                .to_string(),
+        );
+    }
+
+    let ping_pong = roams
+        .windows(2)
+        .filter(|w| {
+            w[0].from_bssid == w[1].to_bssid
+                && w[0].to_bssid == w[1].from_bssid
+                && (w[1].timestamp - w[0].timestamp) < chrono::Duration::seconds(10)
+        })
+        .count();
+    if ping_pong > 0 {
+        recommendations.push(format!(
+            "Detected {} ping-pong roam(s) between the same APs within 10 seconds. Reduce AP transmit power or raise the roaming RSSI threshold to limit overlap.",
+            ping_pong
+        ));
+    }
+
+    if !sticky.is_empty() {
+        let longest = sticky
+            .iter()
+            .map(StickyEpisode::duration)
+            .max()
+            .unwrap_or_default();
+        recommendations.push(format!(
+            "Client stayed on a weak AP while a stronger one was available {} time(s) (longest {} s). Raise the client's roaming aggressiveness or set a minimum RSSI on the APs.",
+            sticky.len(),
+            longest.as_secs()
+        ));
+    }
+
+    recommendations
+}
+
+/// Samples the current connection at high frequency and records roams.
+pub struct RoamingTracker {
+    provider: Arc<dyn WifiProvider>,
+    config: RoamingConfig,
+    probe: Option<Arc<dyn ReachabilityProbe>>,
+}
+
+impl RoamingTracker {
+    /// Create a roaming tracker.
+    pub fn new(provider: Arc<dyn WifiProvider>, config: RoamingConfig) -> Self {
+        Self {
+            provider,
+            config,
+            probe: None,
+        }
+    }
+
+    /// Measure connectivity gaps with a concurrent reachability probe.
+    pub fn with_probe(mut self, probe: Arc<dyn ReachabilityProbe>) -> Self {
+        self.probe = Some(probe);
+        self
+    }
+
+    /// Track roaming on an interface for `duration`.
+    ///
+    /// Only cached scan results are read for sticky client detection, on a
+    /// separate task so a slow read never delays sampling; triggering scans
+    /// would itself disrupt the traffic being measured. Providers without
+    /// cached results get no sticky client detection.
+    pub async fn track(&self, interface: &str, duration: Duration) -> WifiResult<RoamingReport> {
+        info!(
+            "Tracking roaming on {} for {}s",
+            interface,
+            duration.as_secs()
+        );
+
+        if self
+            .provider
+            .get_current_connection(interface)
+            .await
+            .map_err(WifiError::Platform)?
+            .is_none()
+        {
+            return Err(WifiError::NotConnected);
+        }
+
+        let clock = Clock::start();
+        let deadline = Instant::now() + duration;
+        let probes = self
+            .probe
+            .clone()
+            .map(|probe| spawn_probes(probe, self.config.probe_interval, deadline));
+        let mut scans = self.config.scan_interval.map(|interval| {
+            spawn_scan_reader(
+                self.provider.clone(),
+                interface.to_string(),
+                interval,
+                deadline,
+                clock,
+            )
+        });
+
+        let mut recorder = RoamingRecorder::new(self.config.clone());
+        let mut ticker = tokio::time::interval(self.config.sample_interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        while Instant::now() < deadline {
+            ticker.tick().await;
+
+            match self.provider.get_current_connection(interface).await {
+                Ok(connection) => recorder.record_connection(clock.now(), connection.as_ref()),
+                Err(e) => debug!("Connection sample failed: {}", e),
+            }
+
+            if let Some(scans) = scans.as_mut() {
+                while let Ok((at, aps)) = scans.try_recv() {
+                    recorder.record_scan(at, &aps);
+                }
+            }
+        }
+
+        let probes = match probes {
+            Some(task) => task.await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        Ok(recorder.finish(clock.now(), &probes))
+    }
+}
+
+/// Reads cached scan results every `interval` until `deadline`, sending
+/// them with the time they were read.
+fn spawn_scan_reader(
+    provider: Arc<dyn WifiProvider>,
+    interface: String,
+    interval: Duration,
+    deadline: Instant,
+    clock: Clock,
+) -> mpsc::UnboundedReceiver<(DateTime<Utc>, Vec<AccessPoint>)> {
+    let (tx, rx) = mpsc::unbounded_channel();
+    tokio::spawn(async move {
+        let mut ticker = tokio::time::interval(interval);
+        ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+        while Instant::now() < deadline {
+            ticker.tick().await;
+            match provider.cached_scan_results(&interface).await {
+                Ok(Some(aps)) => {
+                    if tx.send((clock.now(), aps)).is_err() {
+                        break;
+                    }
+                }
+                Ok(None) => {
+                    debug!("No cached scan results; sticky client detection is off");
+                    break;
+                }
+                Err(e) => debug!("Scan results unavailable: {}", e),
+            }
+        }
+    });
+    rx
+}
+
+fn spawn_probes(
+    probe: Arc<dyn ReachabilityProbe>,
+    interval: Duration,
+    deadline: Instant,
+) -> tokio::task::JoinHandle<Vec<ProbeResult>> {
+    tokio::spawn(probe.run(interval, deadline))
+}
+
+/// Sends single probes on a fixed schedule.
+async fn probe_on_schedule<P: ReachabilityProbe + ?Sized>(
+    probe: Arc<P>,
+    interval: Duration,
+    deadline: Instant,
+) -> Vec<ProbeResult> {
+    // Probes are sent without waiting for earlier replies, so a lost probe
+    // does not stall the ones after it.
+    let clock = Clock::start();
+    let mut in_flight = tokio::task::JoinSet::new();
+    let mut ticker = tokio::time::interval(interval);
+    while Instant::now() < deadline {
+        ticker.tick().await;
+        let probe = probe.clone();
+        let sent = clock.now();
+        in_flight.spawn(async move {
+            ProbeResult {
+                sent,
+                success: probe.probe().await,
+            }
+        });
+    }
+
+    let mut results = Vec::new();
+    while let Some(result) = in_flight.join_next().await {
+        if let Ok(result) = result {
+            results.push(result);
+        }
+    }
+    results.sort_by_key(|p| p.sent);
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_types::wifi::{
+        AccessPointCapabilities, Bssid, Ssid, WifiAuthState, WifiBand, WifiConnectionState,
+    };
+
+    fn ap(last: u8, channel: u8, rssi: i32, roaming: bool) -> AccessPoint {
+        AccessPoint {
+            ssid: Ssid::new("voice"),
+            bssid: Bssid::new([0x02, 0, 0, 0, 0, last]),
+            rssi,
+            signal_quality: 50,
+            channel: Channel::from_number(channel, WifiBand::Band5GHz),
+            capabilities: AccessPointCapabilities {
+                dot11k: roaming,
+                dot11r: roaming,
+                dot11v: roaming,
+                ..Default::default()
+            },
+            ..AccessPoint::test_default()
+        }
+    }
+
+    fn connection(ap: AccessPoint) -> WifiConnection {
+        WifiConnection {
+            access_point: ap,
+            state: WifiConnectionState::Connected,
+            auth_state: WifiAuthState::Psk,
+            tx_rate: None,
+            rx_rate: None,
+            spatial_streams: None,
+            mcs_index: None,
+            guard_interval: None,
+            channel_width: None,
+            connected_duration: None,
+            last_roam: None,
+            tx_packets: None,
+            tx_retries: None,
+            tx_failed: None,
+        }
+    }
+
+    fn at(ms: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp_millis(1_700_000_000_000 + ms).unwrap()
+    }
+
+    #[test]
+    fn test_roam_with_probe_gap() {
+        let mut recorder = RoamingRecorder::new(RoamingConfig::default());
+        recorder.record_connection(at(0), Some(&connection(ap(1, 36, -72, true))));
+        recorder.record_connection(at(100), Some(&connection(ap(1, 36, -75, true))));
+        recorder.record_connection(at(200), None);
+        let mut target = ap(2, 149, -55, true);
+        target.security.key_management = KeyManagement::FtPsk;
+        recorder.record_connection(at(300), Some(&connection(target)));
+
+        // Probes every 100 ms; two lost around the roam.
+        let probes: Vec<ProbeResult> = (0..6)
+            .map(|i| ProbeResult {
+                sent: at(i * 100),
+                success: !(i == 2 || i == 3),
+            })
+            .collect();
+        let report = recorder.finish(at(600), &probes);
+
+        assert_eq!(report.samples, 3);
+        assert_eq!(report.disconnected_samples, 1);
+        assert_eq!(report.roams.len(), 1);
+        let roam = &report.roams[0];
+        assert_eq!(roam.rssi_before, -75);
+        assert_eq!(roam.rssi_gain(), 20);
+        assert_eq!(roam.association_gap, Duration::from_millis(200));
+        assert_eq!(roam.connectivity_gap, Some(Duration::from_millis(200)));
+        assert_eq!(roam.probes_lost, 2);
+        assert!(roam.fast_transition);
+        assert!(roam.neighbor_report_supported && roam.bss_transition_supported);
+        assert!(!roam.is_band_change());
+        assert_eq!(report.probes_lost, 2);
+        assert!(report
+            .recommendations
+            .iter()
+            .any(|r| r.contains("interrupted")));
+    }
+
+    #[test]
+    fn test_advertised_ft_is_not_used_ft() {
+        let mut recorder = RoamingRecorder::new(RoamingConfig::default());
+        recorder.record_connection(at(0), Some(&connection(ap(1, 36, -72, true))));
+        recorder.record_connection(at(100), Some(&connection(ap(2, 149, -55, true))));
+
+        let report = recorder.finish(at(200), &[]);
+        let roam = &report.roams[0];
+        assert!(!roam.fast_transition);
+        assert!(roam.neighbor_report_supported && roam.bss_transition_supported);
+    }
+
+    #[test]
+    fn test_sticky_client_episode() {
+        let mut recorder = RoamingRecorder::new(RoamingConfig::default());
+        let weak = ap(1, 36, -78, false);
+        recorder.record_connection(at(0), Some(&connection(weak.clone())));
+        recorder.record_scan(at(0), &[weak.clone(), ap(2, 44, -55, false)]);
+        recorder.record_scan(at(10_000), &[weak.clone(), ap(2, 44, -52, false)]);
+        recorder.record_connection(at(12_000), Some(&connection(ap(2, 44, -53, false))));
+
+        let report = recorder.finish(at(15_000), &[]);
+        assert_eq!(report.sticky_episodes.len(), 1);
+        let episode = &report.sticky_episodes[0];
+        assert_eq!(episode.best_rssi, -52);
+        assert!(episode.resolved_by_roam);
+        assert_eq!(episode.duration(), Duration::from_secs(12));
+        assert_eq!(report.roams[0].connectivity_gap, None);
+        assert!(!report.roams[0].fast_transition);
+        assert!(report
+            .recommendations
+            .iter()
+            .any(|r| r.contains("802.11k/v")));
+    }
+
+    #[test]
+    fn test_ping_reply_matching() {
+        assert_eq!(
+            reply_sequence("64 bytes from 192.0.2.1: icmp_seq=12 ttl=57 time=3.21 ms"),
+            Some(12)
+        );
+        assert_eq!(reply_sequence("Request timeout for icmp_seq 13"), None);
+        assert_eq!(
+            reply_sequence("PING 192.0.2.1 (192.0.2.1) 56(84) bytes of data."),
+            None
+        );
+
+        let interval = Duration::from_millis(100);
+        let timeout = Duration::from_millis(250);
+        assert_eq!(probes_sent(Duration::from_millis(450), interval), 5);
+
+        // Requests 1 and 2 unanswered, 3 answered too late
+        let ms = Duration::from_millis;
+        let replies = [
+            (FIRST_SEQUENCE, ms(10)),
+            (FIRST_SEQUENCE + 3, ms(600)),
+            (FIRST_SEQUENCE + 4, ms(420)),
+        ];
+        let results = probe_results(at(0), interval, timeout, 5, &replies);
+        let success: Vec<bool> = results.iter().map(|p| p.success).collect();
+        assert_eq!(success, vec![true, false, false, false, true]);
+        assert_eq!(results[4].sent, at(400));
+    }
+
+    /// Loses every probe sent in a window after it is created.
+    struct OutageProbe {
+        created: Instant,
+        outage: std::ops::Range<Duration>,
+    }
+
+    #[async_trait]
+    impl ReachabilityProbe for OutageProbe {
+        async fn probe(&self) -> bool {
+            !self.outage.contains(&self.created.elapsed())
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tracker_measures_roam_gap() {
+        use netdiag_platform::{
+            RecordedCall, ReplayWifiProvider, WifiCall, WifiRecording, WifiRecordingHeader,
+        };
+
+        // AP 1 until 200 ms, disassociated at 300 ms, then AP 2
+        let sample = |at_ms: u64, connected: Option<u8>| RecordedCall {
+            at_ms,
+            duration_ms: 0,
+            call: WifiCall::GetCurrentConnection {
+                interface: "wlan0".to_string(),
+                result: Ok(connected.map(|last| connection(ap(last, 36 + last * 4, -60, true)))),
+            },
+        };
+        let calls = [0, 0, 100, 200, 300, 400, 500]
+            .into_iter()
+            .map(|at_ms| {
+                let connected = match at_ms {
+                    300 => None,
+                    0..=200 => Some(1),
+                    _ => Some(2),
+                };
+                sample(at_ms, connected)
+            })
+            .collect();
+        let recording = WifiRecording {
+            header: WifiRecordingHeader {
+                version: "test".to_string(),
+                platform: "linux".to_string(),
+                started: Utc::now(),
+                available: true,
+                supports_enterprise: false,
+            },
+            calls,
+        };
+        let provider = Arc::new(ReplayWifiProvider::new(recording).with_speed(1.0));
+        let probe = Arc::new(OutageProbe {
+            created: Instant::now(),
+            outage: Duration::from_millis(250)..Duration::from_millis(400),
+        });
+        let config = RoamingConfig {
+            sample_interval: Duration::from_millis(20),
+            probe_interval: Duration::from_millis(25),
+            scan_interval: None,
+            ..Default::default()
+        };
+
+        let report = RoamingTracker::new(provider, config)
+            .with_probe(probe)
+            .track("wlan0", Duration::from_millis(700))
+            .await
+            .unwrap();
+
+        assert_eq!(report.roams.len(), 1);
+        let roam = &report.roams[0];
+        assert_eq!(roam.to_bssid, "02:00:00:00:00:02");
+        assert_eq!(roam.association_gap, Duration::from_millis(200));
+        assert_eq!(roam.connectivity_gap, Some(Duration::from_millis(150)));
+        assert_eq!(roam.probes_lost, 6);
+    }
+}
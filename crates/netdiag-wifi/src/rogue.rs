@@ -0,0 +1,736 @@
+//! Rogue access point and evil-twin detection.
+//!
+//! [`SecurityAnalysis`](crate::SecurityAnalysis) looks at one network at a
+//! time; this compares access points against each other and against an
+//! allowlist of known BSSIDs and vendor OUIs per SSID.
+
+use crate::error::{WifiError, WifiResult};
+use crate::security::{IssueSeverity, SecurityAnalysis};
+use netdiag_types::wifi::{AccessPoint, Channel, SecurityType, WifiAuthentication, WifiBand};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+
+/// Sightings further apart than this come from different scans.
+const SCAN_WINDOW: Duration = Duration::from_secs(10);
+
+/// Known access points for one SSID.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AllowedNetwork {
+    /// Network name.
+    pub ssid: String,
+
+    /// Known BSSIDs (`aa:bb:cc:dd:ee:ff`).
+    #[serde(default)]
+    pub bssids: Vec<String>,
+
+    /// Known vendor OUIs (`aa:bb:cc`).
+    #[serde(default)]
+    pub ouis: Vec<String>,
+
+    /// Expected authentication.
+    #[serde(default)]
+    pub security: Option<WifiAuthentication>,
+
+    /// Whether the network uses 802.1X.
+    #[serde(default)]
+    pub enterprise: Option<bool>,
+
+    /// Channels the network is deployed on (any if empty).
+    #[serde(default)]
+    pub channels: Vec<AllowedChannel>,
+}
+
+/// A channel in an allowlist.
+///
+/// Channel numbers overlap between bands (6 GHz uses 1, 5, 9, ...), so each
+/// entry names its band.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllowedChannel {
+    /// Frequency band.
+    pub band: WifiBand,
+    /// Channel number within the band.
+    pub number: u8,
+}
+
+impl AllowedChannel {
+    /// Whether a channel has this entry's center frequency.
+    pub fn matches(&self, channel: &Channel) -> bool {
+        Channel::from_number(self.number, self.band).frequency == channel.frequency
+    }
+}
+
+impl AllowedNetwork {
+    fn allows_bssid(&self, bssid: &[u8; 6]) -> bool {
+        let text = format_mac(bssid);
+        self.bssids.iter().any(|b| b.eq_ignore_ascii_case(&text))
+    }
+
+    fn allows_vendor(&self, bssid: &[u8; 6]) -> bool {
+        // Virtual BSSIDs are usually the radio MAC with the locally
+        // administered bit set, so compare OUIs with that bit cleared.
+        let oui = format!("{:02x}:{:02x}:{:02x}", bssid[0] & !0x02, bssid[1], bssid[2]);
+        self.ouis.iter().any(|o| o.eq_ignore_ascii_case(&oui))
+    }
+
+    fn allows_channel(&self, channel: &Channel) -> bool {
+        self.channels.is_empty() || self.channels.iter().any(|c| c.matches(channel))
+    }
+
+    fn expects_open(&self) -> bool {
+        self.security == Some(WifiAuthentication::Open)
+    }
+}
+
+/// Allowlist of known networks, loaded from TOML:
+///
+/// ```toml
+/// [[network]]
+/// ssid = "corp"
+/// ouis = ["00:11:22"]
+/// security = "wpa2"
+/// enterprise = true
+/// channels = [
+///     { band = "2.4ghz", number = 1 },
+///     { band = "5ghz", number = 36 },
+///     { band = "6ghz", number = 5 },
+/// ]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ApAllowlist {
+    /// Known networks.
+    #[serde(default, rename = "network")]
+    pub networks: Vec<AllowedNetwork>,
+}
+
+impl ApAllowlist {
+    /// Parse an allowlist from TOML.
+    pub fn from_toml(content: &str) -> WifiResult<Self> {
+        toml::from_str(content).map_err(|e| WifiError::InvalidAllowlist(e.to_string()))
+    }
+
+    /// Load an allowlist file.
+    pub fn load(path: impl AsRef<Path>) -> WifiResult<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)?;
+        Self::from_toml(&content)
+            .map_err(|e| WifiError::InvalidAllowlist(format!("{}: {}", path.display(), e)))
+    }
+
+    /// Entry for an SSID.
+    pub fn network(&self, ssid: &str) -> Option<&AllowedNetwork> {
+        self.networks.iter().find(|n| n.ssid == ssid)
+    }
+}
+
+/// Kind of rogue AP finding.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RogueKind {
+    /// Open AP advertising the SSID of a protected network.
+    OpenClone,
+    /// Same SSID with different security settings.
+    SecurityMismatch,
+    /// BSSID outside the allowlist for a known SSID.
+    UnknownBssid,
+    /// BSSID vendor (OUI) outside the allowlist for a known SSID.
+    UnexpectedVendor,
+    /// BSSID seen on several channels, or a known BSSID on an unexpected channel.
+    SpoofedBssid,
+    /// Network without management frame protection, so clients can be
+    /// deauthenticated onto a twin.
+    DeauthProne,
+}
+
+impl std::fmt::Display for RogueKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            RogueKind::OpenClone => "Open clone",
+            RogueKind::SecurityMismatch => "Security mismatch",
+            RogueKind::UnknownBssid => "Unknown BSSID",
+            RogueKind::UnexpectedVendor => "Unexpected vendor",
+            RogueKind::SpoofedBssid => "Spoofed BSSID",
+            RogueKind::DeauthProne => "Deauth-prone",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// A suspicious access point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RogueFinding {
+    /// Finding kind.
+    pub kind: RogueKind,
+
+    /// Severity.
+    pub severity: IssueSeverity,
+
+    /// SSID.
+    pub ssid: String,
+
+    /// BSSID.
+    pub bssid: String,
+
+    /// Channel the AP was seen on.
+    pub channel: Channel,
+
+    /// Signal strength.
+    pub rssi: i32,
+
+    /// Description.
+    pub description: String,
+}
+
+/// Rogue AP detection results.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RogueAnalysis {
+    /// Suspicious access points.
+    pub findings: Vec<RogueFinding>,
+
+    /// Number of SSIDs compared.
+    pub networks_checked: usize,
+
+    /// Whether an allowlist was used.
+    pub allowlist_used: bool,
+
+    /// Recommendations.
+    pub recommendations: Vec<String>,
+}
+
+impl RogueAnalysis {
+    /// Whether any finding is critical.
+    pub fn has_critical(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|f| f.severity == IssueSeverity::Critical)
+    }
+
+    /// Findings of one kind.
+    pub fn findings_of(&self, kind: RogueKind) -> impl Iterator<Item = &RogueFinding> {
+        self.findings.iter().filter(move |f| f.kind == kind)
+    }
+}
+
+/// Compares scan results against each other and an optional allowlist.
+#[derive(Debug, Clone, Default)]
+pub struct RogueDetector {
+    allowlist: Option<ApAllowlist>,
+}
+
+impl RogueDetector {
+    /// Create a detector using only scan-internal comparisons.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a detector with an allowlist.
+    pub fn with_allowlist(allowlist: ApAllowlist) -> Self {
+        Self {
+            allowlist: Some(allowlist),
+        }
+    }
+
+    /// Analyze scan results.
+    pub fn detect(&self, access_points: &[AccessPoint]) -> RogueAnalysis {
+        let mut findings = Vec::new();
+
+        let mut by_ssid: HashMap<&str, Vec<&AccessPoint>> = HashMap::new();
+        for ap in access_points {
+            if !ap.ssid.is_hidden() {
+                by_ssid.entry(ap.ssid.as_str()).or_default().push(ap);
+            }
+        }
+
+        Self::check_spoofed_channels(access_points, &mut findings);
+
+        let mut ssids: Vec<&str> = by_ssid.keys().copied().collect();
+        ssids.sort_unstable();
+        for ssid in &ssids {
+            let aps = &by_ssid[ssid];
+            let allowed = self.allowlist.as_ref().and_then(|a| a.network(ssid));
+            let before = findings.len();
+
+            self.check_security(ssid, aps, allowed, &mut findings);
+            if let Some(allowed) = allowed {
+                Self::check_allowlist(aps, allowed, &mut findings);
+            }
+
+            // Deauth exposure matters for known networks and ones already
+            // being imitated.
+            let targeted = allowed.is_some() || findings.len() > before;
+            if targeted {
+                Self::check_deauth(aps, &mut findings);
+            }
+        }
+
+        findings.sort_by(|a, b| a.severity.cmp(&b.severity).then(a.ssid.cmp(&b.ssid)));
+        let recommendations = Self::recommendations(&findings, self.allowlist.is_some());
+
+        RogueAnalysis {
+            findings,
+            networks_checked: ssids.len(),
+            allowlist_used: self.allowlist.is_some(),
+            recommendations,
+        }
+    }
+
+    /// The same BSSID on two channels in one scan cannot be one radio.
+    ///
+    /// Sightings more than [`SCAN_WINDOW`] apart are a channel change (DFS,
+    /// auto-channel) showing through a stale cache entry instead. Access
+    /// points without a timestamp count as seen in the same scan.
+    fn check_spoofed_channels(access_points: &[AccessPoint], findings: &mut Vec<RogueFinding>) {
+        let mut sightings: HashMap<[u8; 6], Vec<&AccessPoint>> = HashMap::new();
+        for ap in access_points {
+            sightings.entry(ap.bssid.octets()).or_default().push(ap);
+        }
+
+        for ap in access_points {
+            let mut numbers: Vec<u8> = sightings[&ap.bssid.octets()]
+                .iter()
+                .filter(|other| {
+                    other.channel.frequency != ap.channel.frequency && same_scan(ap, other)
+                })
+                .map(|other| other.channel.number)
+                .collect();
+            if numbers.is_empty() {
+                continue;
+            }
+            numbers.push(ap.channel.number);
+            numbers.sort_unstable();
+            numbers.dedup();
+            findings.push(finding(
+                RogueKind::SpoofedBssid,
+                IssueSeverity::Critical,
+                ap,
+                format!(
+                    "BSSID {} is advertised on channels {:?} at the same time; one of them is impersonating the other.",
+                    ap.bssid, numbers
+                ),
+            ));
+        }
+    }
+
+    fn check_security(
+        &self,
+        ssid: &str,
+        aps: &[&AccessPoint],
+        allowed: Option<&AllowedNetwork>,
+        findings: &mut Vec<RogueFinding>,
+    ) {
+        let expected = match allowed.filter(|a| a.security.is_some() || a.enterprise.is_some()) {
+            Some(a) => Expected::Allowlist {
+                authentication: a.security,
+                enterprise: a.enterprise,
+            },
+            None => match majority(aps) {
+                Some(security) => Expected::Majority(security),
+                None => return,
+            },
+        };
+
+        let protected = match &expected {
+            Expected::Allowlist { .. } => !allowed.is_some_and(AllowedNetwork::expects_open),
+            Expected::Majority(_) => aps.iter().any(|ap| !ap.security.is_open()),
+        };
+
+        for ap in aps {
+            if ap.security.is_open() && protected {
+                findings.push(finding(
+                    RogueKind::OpenClone,
+                    IssueSeverity::Critical,
+                    ap,
+                    format!(
+                        "Open access point is advertising protected network \"{}\". Clients that auto-join may connect to an evil twin.",
+                        ssid
+                    ),
+                ));
+            } else if !expected.matches(&ap.security) {
+                findings.push(finding(
+                    RogueKind::SecurityMismatch,
+                    IssueSeverity::High,
+                    ap,
+                    format!(
+                        "Advertises {} while {} expects {}.",
+                        ap.security.to_display_string(),
+                        ssid,
+                        expected.describe()
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_allowlist(
+        aps: &[&AccessPoint],
+        allowed: &AllowedNetwork,
+        findings: &mut Vec<RogueFinding>,
+    ) {
+        for ap in aps {
+            let bssid = ap.bssid.octets();
+            let known = allowed.allows_bssid(&bssid);
+
+            if !known && !allowed.allows_vendor(&bssid) {
+                if !allowed.ouis.is_empty() {
+                    findings.push(finding(
+                        RogueKind::UnexpectedVendor,
+                        IssueSeverity::High,
+                        ap,
+                        format!(
+                            "Vendor prefix {:02X}:{:02X}:{:02X} is not one of the allowed vendors for {}.",
+                            bssid[0], bssid[1], bssid[2], allowed.ssid
+                        ),
+                    ));
+                } else if !allowed.bssids.is_empty() {
+                    findings.push(finding(
+                        RogueKind::UnknownBssid,
+                        IssueSeverity::High,
+                        ap,
+                        format!("BSSID is not in the allowlist for {}.", allowed.ssid),
+                    ));
+                }
+            }
+
+            if !allowed.allows_channel(&ap.channel) {
+                findings.push(finding(
+                    RogueKind::SpoofedBssid,
+                    if known {
+                        IssueSeverity::Critical
+                    } else {
+                        IssueSeverity::High
+                    },
+                    ap,
+                    format!(
+                        "{} is not deployed on channel {} ({}).",
+                        allowed.ssid, ap.channel.number, ap.channel.band
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_deauth(aps: &[&AccessPoint], findings: &mut Vec<RogueFinding>) {
+        for ap in aps {
+            // PMF status is only known when the RSN element was decoded.
+            let rsn_known = ap.elements.as_ref().is_some_and(|e| e.rsn.is_some());
+            if ap.security.is_open() || !rsn_known || SecurityAnalysis::pmf_enabled(ap) {
+                continue;
+            }
+            findings.push(finding(
+                RogueKind::DeauthProne,
+                IssueSeverity::Medium,
+                ap,
+                "Management frames are unprotected, so an attacker can deauthenticate clients and lure them to a twin."
+                    .to_string(),
+            ));
+        }
+    }
+
+    fn recommendations(findings: &[RogueFinding], allowlist: bool) -> Vec<String> {
+        let mut recommendations = Vec::new();
+        let has = |kind| findings.iter().any(|f| f.kind == kind);
+
+        if has(RogueKind::OpenClone) || has(RogueKind::SpoofedBssid) {
+            recommendations.push(
+                "Possible evil twin nearby. Locate the AP by signal strength and enable wireless intrusion prevention on the controller."
+                    .to_string(),
+            );
+        }
+        if has(RogueKind::SecurityMismatch) {
+            recommendations.push(
+                "Access points share an SSID with different security. Align their configuration, or treat the outlier as rogue."
+                    .to_string(),
+            );
+        }
+        if has(RogueKind::UnknownBssid) || has(RogueKind::UnexpectedVendor) {
+            recommendations.push(
+                "Unrecognised access points are broadcasting a known SSID. Verify them and update the allowlist if they are legitimate."
+                    .to_string(),
+            );
+        }
+        if has(RogueKind::DeauthProne) {
+            recommendations.push(
+                "Enable Protected Management Frames (802.11w) to block deauthentication attacks."
+                    .to_string(),
+            );
+        }
+        if !allowlist && findings.is_empty() {
+            recommendations.push(
+                "Provide an allowlist of known BSSIDs or vendor OUIs to detect unknown access points."
+                    .to_string(),
+            );
+        }
+
+        recommendations
+    }
+}
+
+/// Expected security for an SSID.
+enum Expected {
+    Allowlist {
+        authentication: Option<WifiAuthentication>,
+        enterprise: Option<bool>,
+    },
+    Majority(SecurityType),
+}
+
+impl Expected {
+    fn matches(&self, security: &SecurityType) -> bool {
+        match self {
+            Expected::Allowlist {
+                authentication,
+                enterprise,
+            } => {
+                authentication.map_or(true, |auth| {
+                    auth_compatible(auth, security.authentication, security.transition_mode)
+                }) && enterprise.map_or(true, |e| e == security.is_enterprise())
+            }
+            Expected::Majority(expected) => compatible(expected, security),
+        }
+    }
+
+    fn describe(&self) -> String {
+        match self {
+            Expected::Allowlist {
+                authentication,
+                enterprise,
+            } => {
+                let auth = authentication.map_or_else(|| "any".to_string(), |a| a.to_string());
+                match enterprise {
+                    Some(true) => format!("{} enterprise", auth),
+                    Some(false) => format!("{} personal", auth),
+                    None => auth,
+                }
+            }
+            Expected::Majority(security) => security.to_display_string(),
+        }
+    }
+}
+
+/// Most common security among an SSID's APs, if they disagree at all.
+fn majority(aps: &[&AccessPoint]) -> Option<SecurityType> {
+    if aps.len() < 2 {
+        return None;
+    }
+    let mut counts: Vec<(&SecurityType, usize)> = Vec::new();
+    for ap in aps {
+        match counts.iter_mut().find(|(s, _)| compatible(s, &ap.security)) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((&ap.security, 1)),
+        }
+    }
+    if counts.len() < 2 {
+        return None;
+    }
+    counts
+        .into_iter()
+        .max_by_key(|(_, count)| *count)
+        .map(|(s, _)| s.clone())
+}
+
+fn compatible(a: &SecurityType, b: &SecurityType) -> bool {
+    a.is_open() == b.is_open()
+        && a.is_enterprise() == b.is_enterprise()
+        && (a.authentication == b.authentication
+            || auth_compatible(a.authentication, b.authentication, b.transition_mode)
+            || auth_compatible(b.authentication, a.authentication, a.transition_mode))
+}
+
+/// WPA2/WPA3 transition mode APs accept both.
+fn auth_compatible(
+    expected: WifiAuthentication,
+    actual: WifiAuthentication,
+    transition: bool,
+) -> bool {
+    use WifiAuthentication::{Wpa2, Wpa3};
+    expected == actual || (transition && matches!((expected, actual), (Wpa2, Wpa3) | (Wpa3, Wpa2)))
+}
+
+/// Whether two sightings fall within one scan.
+fn same_scan(a: &AccessPoint, b: &AccessPoint) -> bool {
+    match (a.last_seen, b.last_seen) {
+        (Some(a), Some(b)) => (a - b).abs().to_std().is_ok_and(|gap| gap <= SCAN_WINDOW),
+        _ => true,
+    }
+}
+
+fn finding(
+    kind: RogueKind,
+    severity: IssueSeverity,
+    ap: &AccessPoint,
+    description: String,
+) -> RogueFinding {
+    RogueFinding {
+        kind,
+        severity,
+        ssid: ap.ssid.to_string(),
+        bssid: ap.bssid.to_string(),
+        channel: ap.channel,
+        rssi: ap.rssi,
+        description,
+    }
+}
+
+fn format_mac(bytes: &[u8; 6]) -> String {
+    format!(
+        "{:02x}:{:02x}:{:02x}:{:02x}:{:02x}:{:02x}",
+        bytes[0], bytes[1], bytes[2], bytes[3], bytes[4], bytes[5]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_elements;
+    use netdiag_types::wifi::{Bssid, Ssid, WifiBand};
+
+    fn ap(ssid: &str, bssid: [u8; 6], channel: u8, security: SecurityType) -> AccessPoint {
+        AccessPoint {
+            ssid: Ssid::new(ssid),
+            bssid: Bssid::new(bssid),
+            channel: Channel::from_number(
+                channel,
+                if channel > 14 {
+                    WifiBand::Band5GHz
+                } else {
+                    WifiBand::Band2_4GHz
+                },
+            ),
+            security,
+            ..AccessPoint::test_default()
+        }
+    }
+
+    const CORP_A: [u8; 6] = [0x00, 0x11, 0x22, 0, 0, 1];
+    const CORP_B: [u8; 6] = [0x00, 0x11, 0x22, 0, 0, 2];
+    const ROGUE: [u8; 6] = [0xde, 0xad, 0xbe, 0, 0, 9];
+
+    #[test]
+    fn test_scan_only_detection() {
+        let aps = [
+            ap("corp", CORP_A, 36, SecurityType::wpa2_enterprise()),
+            ap("corp", CORP_B, 44, SecurityType::wpa2_enterprise()),
+            ap("corp", ROGUE, 6, SecurityType::wpa2_personal()),
+            ap("corp", ROGUE, 11, SecurityType::open()),
+            ap("cafe", [2, 0, 0, 0, 0, 1], 1, SecurityType::open()),
+        ];
+        let analysis = RogueDetector::new().detect(&aps);
+
+        assert_eq!(analysis.networks_checked, 2);
+        assert!(analysis.has_critical());
+        assert_eq!(analysis.findings_of(RogueKind::SpoofedBssid).count(), 2);
+        assert_eq!(analysis.findings_of(RogueKind::OpenClone).count(), 1);
+        let mismatch: Vec<_> = analysis.findings_of(RogueKind::SecurityMismatch).collect();
+        assert_eq!(mismatch.len(), 1);
+        assert_eq!(mismatch[0].channel.number, 6);
+        assert!(analysis.findings.iter().all(|f| f.ssid == "corp"));
+        assert_eq!(analysis.findings[0].severity, IssueSeverity::Critical);
+    }
+
+    #[test]
+    fn test_allowlist_detection() {
+        let allowlist = ApAllowlist::from_toml(
+            r#"
+            [[network]]
+            ssid = "corp"
+            ouis = ["00:11:22"]
+            security = "wpa3"
+            channels = [{ band = "5ghz", number = 36 }, { band = "5ghz", number = 44 }]
+            "#,
+        )
+        .unwrap();
+
+        let mut wpa2 = ap("corp", CORP_A, 36, SecurityType::wpa2_personal());
+        // RSN with PSK AKM and no MFP bits.
+        apply_elements(
+            &mut wpa2,
+            &[
+                48, 20, 1, 0, 0, 0x0f, 0xac, 4, 1, 0, 0, 0x0f, 0xac, 4, 1, 0, 0, 0x0f, 0xac, 2, 0,
+                0,
+            ],
+        );
+        let virtual_bssid = [0x02, 0x11, 0x22, 0, 0, 3];
+        let aps = [
+            ap("corp", CORP_B, 44, SecurityType::wpa3_personal()),
+            ap("corp", virtual_bssid, 36, SecurityType::wpa3_personal()),
+            ap("corp", ROGUE, 44, SecurityType::wpa3_personal()),
+            ap("corp", CORP_B, 44, SecurityType::wpa3_personal()),
+            wpa2,
+            ap(
+                "corp",
+                [0x00, 0x11, 0x22, 0, 0, 4],
+                149,
+                SecurityType::wpa3_personal(),
+            ),
+        ];
+        let analysis = RogueDetector::with_allowlist(allowlist).detect(&aps);
+
+        assert!(analysis.allowlist_used);
+        let vendors: Vec<_> = analysis.findings_of(RogueKind::UnexpectedVendor).collect();
+        assert_eq!(vendors.len(), 1);
+        assert_eq!(vendors[0].bssid, "DE:AD:BE:00:00:09");
+        assert_eq!(analysis.findings_of(RogueKind::SecurityMismatch).count(), 1);
+        assert_eq!(analysis.findings_of(RogueKind::SpoofedBssid).count(), 1);
+        assert_eq!(analysis.findings_of(RogueKind::DeauthProne).count(), 1);
+        assert!(analysis
+            .recommendations
+            .iter()
+            .any(|r| r.contains("802.11w")));
+    }
+
+    #[test]
+    fn test_allowlist_channels_include_band() {
+        let allowlist = ApAllowlist::from_toml(
+            r#"
+            [[network]]
+            ssid = "corp"
+            channels = [
+                { band = "2.4ghz", number = 1 },
+                { band = "2.4ghz", number = 6 },
+                { band = "2.4ghz", number = 11 },
+            ]
+            "#,
+        )
+        .unwrap();
+
+        let twin = AccessPoint {
+            channel: Channel::from_number(5, WifiBand::Band6GHz),
+            ..ap("corp", ROGUE, 5, SecurityType::wpa3_personal())
+        };
+        let aps = [
+            ap("corp", CORP_A, 6, SecurityType::wpa3_personal()),
+            AccessPoint {
+                channel: Channel::from_number(1, WifiBand::Band6GHz),
+                ..ap("corp", CORP_B, 1, SecurityType::wpa3_personal())
+            },
+            twin,
+        ];
+        let analysis = RogueDetector::with_allowlist(allowlist).detect(&aps);
+
+        let spoofed: Vec<_> = analysis.findings_of(RogueKind::SpoofedBssid).collect();
+        assert_eq!(spoofed.len(), 2);
+        assert!(spoofed.iter().all(|f| f.channel.band == WifiBand::Band6GHz));
+    }
+
+    #[test]
+    fn test_channel_change_is_not_spoofing() {
+        let now = chrono::Utc::now();
+        let seen = |channel, secs_ago| AccessPoint {
+            last_seen: Some(now - chrono::Duration::seconds(secs_ago)),
+            ..ap("corp", CORP_A, channel, SecurityType::wpa2_personal())
+        };
+
+        // Moved off a DFS channel; the old entry lingers in the scan cache
+        let moved = [seen(52, 25), seen(36, 1)];
+        let analysis = RogueDetector::new().detect(&moved);
+        assert_eq!(analysis.findings_of(RogueKind::SpoofedBssid).count(), 0);
+
+        // Both heard within one scan
+        let cloned = [seen(52, 3), seen(36, 1)];
+        let analysis = RogueDetector::new().detect(&cloned);
+        let spoofed: Vec<_> = analysis.findings_of(RogueKind::SpoofedBssid).collect();
+        assert_eq!(spoofed.len(), 2);
+        assert_eq!(spoofed[0].severity, IssueSeverity::Critical);
+    }
+}
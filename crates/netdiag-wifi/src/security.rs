@@ -1,6 +1,8 @@
 //! WiFi security analysis.
 
-use netdiag_types::wifi::{AccessPoint, SecurityType, WifiAuthentication, WifiConnection};
+use netdiag_types::wifi::{
+    AccessPoint, InformationElements, SecurityType, WifiAuthentication, WifiConnection,
+};
 use serde::{Deserialize, Serialize};
 
 /// Security analysis results.
@@ -132,7 +134,7 @@ impl SecurityAnalysis {
     pub fn analyze_connection(connection: &WifiConnection) -> Self {
         let protocol = connection.access_point.security.clone();
         let is_enterprise = protocol.is_enterprise();
-        let pmf_enabled = protocol.pmf_required;
+        let pmf_enabled = Self::pmf_enabled(&connection.access_point);
 
         let mut issues = Vec::new();
 
@@ -228,8 +230,11 @@ impl SecurityAnalysis {
             }
         }
 
-        // Check PMF
-        if !pmf_enabled && protocol.authentication == WifiAuthentication::Wpa2 {
+        // Check PMF (the advertised elements are checked in detail below)
+        if !pmf_enabled
+            && protocol.authentication == WifiAuthentication::Wpa2
+            && connection.access_point.elements.is_none()
+        {
             issues.push(SecurityIssue {
                 severity: IssueSeverity::Low,
                 title: "PMF Not Required".to_string(),
@@ -240,6 +245,10 @@ impl SecurityAnalysis {
             });
         }
 
+        if let Some(elements) = &connection.access_point.elements {
+            Self::check_elements(elements, &protocol, &mut issues);
+        }
+
         // Calculate rating and score
         let rating = Self::calculate_rating(&protocol);
         let score = Self::calculate_score(&issues, &rating);
@@ -262,7 +271,7 @@ impl SecurityAnalysis {
     pub fn analyze_ap(ap: &AccessPoint) -> Self {
         let protocol = ap.security.clone();
         let is_enterprise = protocol.is_enterprise();
-        let pmf_enabled = protocol.pmf_required;
+        let pmf_enabled = Self::pmf_enabled(ap);
 
         let mut issues = Vec::new();
 
@@ -284,6 +293,10 @@ impl SecurityAnalysis {
             });
         }
 
+        if let Some(elements) = &ap.elements {
+            Self::check_elements(elements, &protocol, &mut issues);
+        }
+
         let rating = Self::calculate_rating(&protocol);
         let score = Self::calculate_score(&issues, &rating);
         let recommendations = Self::generate_recommendations(&issues, &protocol);
@@ -299,6 +312,84 @@ impl SecurityAnalysis {
         }
     }
 
+    /// Whether PMF is in use: advertised in the RSN element, or required.
+    pub(crate) fn pmf_enabled(ap: &AccessPoint) -> bool {
+        ap.elements
+            .as_ref()
+            .and_then(|e| e.rsn.as_ref())
+            .map_or(ap.security.pmf_required, |rsn| {
+                rsn.mfp_capable || rsn.mfp_required
+            })
+    }
+
+    /// Check the advertised information elements for weak configurations.
+    fn check_elements(
+        elements: &InformationElements,
+        protocol: &SecurityType,
+        issues: &mut Vec<SecurityIssue>,
+    ) {
+        if let Some(rsn) = &elements.rsn {
+            if rsn.uses_tkip() {
+                issues.push(SecurityIssue {
+                    severity: IssueSeverity::High,
+                    title: "TKIP Cipher Enabled".to_string(),
+                    description: "The access point still offers TKIP, which is deprecated and limits clients to 54 Mbps.".to_string(),
+                    remediation: "Configure the access point for AES (CCMP) only.".to_string(),
+                    cve: None,
+                });
+            }
+            if elements.wpa.is_some() {
+                issues.push(SecurityIssue {
+                    severity: IssueSeverity::Medium,
+                    title: "WPA/WPA2 Mixed Mode".to_string(),
+                    description: "Legacy WPA is advertised alongside WPA2, allowing clients to use the weaker protocol.".to_string(),
+                    remediation: "Disable WPA (version 1) on the access point.".to_string(),
+                    cve: None,
+                });
+            }
+            if rsn.is_wpa3_transition() {
+                issues.push(SecurityIssue {
+                    severity: IssueSeverity::Low,
+                    title: "WPA3 Transition Mode".to_string(),
+                    description: "WPA2-PSK is offered alongside SAE, so a rogue access point advertising only WPA2 can downgrade clients and capture a handshake for an offline dictionary attack on the passphrase.".to_string(),
+                    remediation: "Switch to WPA3-only once all clients support SAE.".to_string(),
+                    cve: None,
+                });
+            }
+            if protocol.authentication == WifiAuthentication::Wpa3
+                && !rsn.is_wpa3_transition()
+                && !rsn.mfp_required
+            {
+                issues.push(SecurityIssue {
+                    severity: IssueSeverity::Medium,
+                    title: "WPA3 Without Required PMF".to_string(),
+                    description: "WPA3 mandates Protected Management Frames, but the access point only marks them optional.".to_string(),
+                    remediation: "Set PMF (802.11w) to required on the access point.".to_string(),
+                    cve: None,
+                });
+            }
+            if !rsn.mfp_capable && !rsn.mfp_required {
+                issues.push(SecurityIssue {
+                    severity: IssueSeverity::Low,
+                    title: "PMF Not Supported".to_string(),
+                    description: "The access point does not advertise Protected Management Frames, leaving clients open to deauthentication attacks.".to_string(),
+                    remediation: "Enable PMF (802.11w) on the access point.".to_string(),
+                    cve: None,
+                });
+            }
+        }
+
+        if elements.wps {
+            issues.push(SecurityIssue {
+                severity: IssueSeverity::Medium,
+                title: "WPS Enabled".to_string(),
+                description: "Wi-Fi Protected Setup PINs can be brute-forced to recover the network passphrase.".to_string(),
+                remediation: "Disable WPS on the access point.".to_string(),
+                cve: Some("CVE-2011-5053".to_string()),
+            });
+        }
+    }
+
     /// Calculate security rating.
     fn calculate_rating(protocol: &SecurityType) -> SecurityRating {
         if protocol.is_open() {
@@ -381,3 +472,59 @@ impl SecurityAnalysis {
             .collect()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::apply_elements;
+    use netdiag_types::wifi::{AccessPointCapabilities, Channel, Ssid, WifiStandard};
+
+    fn access_point(elements: &[u8]) -> AccessPoint {
+        let mut ap = AccessPoint {
+            ssid: Ssid::new("cafe"),
+            signal_quality: 80,
+            channel: Channel::from_frequency(2437),
+            security: SecurityType::open(),
+            wifi_standard: WifiStandard::Dot11n,
+            capabilities: AccessPointCapabilities {
+                privacy: true,
+                ..AccessPointCapabilities::default()
+            },
+            ..AccessPoint::test_default()
+        };
+        apply_elements(&mut ap, elements);
+        ap
+    }
+
+    fn titles(analysis: &SecurityAnalysis) -> Vec<&str> {
+        analysis.issues.iter().map(|i| i.title.as_str()).collect()
+    }
+
+    #[test]
+    fn test_analyze_ap_uses_elements() {
+        // RSN: CCMP, SAE + PSK, MFP capable; plus WPS
+        let ap = access_point(&[
+            48, 24, 1, 0, 0x00, 0x0f, 0xac, 4, 1, 0, 0x00, 0x0f, 0xac, 4, 2, 0, 0x00, 0x0f, 0xac,
+            2, 0x00, 0x0f, 0xac, 8, 0x80, 0, //
+            221, 4, 0x00, 0x50, 0xf2, 4,
+        ]);
+        let analysis = SecurityAnalysis::analyze_ap(&ap);
+        assert_eq!(analysis.protocol.authentication, WifiAuthentication::Wpa3);
+        assert!(analysis.pmf_enabled);
+        assert_eq!(titles(&analysis), ["WPA3 Transition Mode", "WPS Enabled"]);
+        // The downgrade has no CVE of its own; CVE-2019-9494 is an SAE side channel.
+        assert_eq!(analysis.issues[0].cve, None);
+
+        // RSN: TKIP + CCMP, PSK, no MFP
+        let ap = access_point(&[
+            48, 22, 1, 0, 0x00, 0x0f, 0xac, 2, 2, 0, 0x00, 0x0f, 0xac, 2, 0x00, 0x0f, 0xac, 4, 1,
+            0, 0x00, 0x0f, 0xac, 2,
+        ]);
+        let analysis = SecurityAnalysis::analyze_ap(&ap);
+        assert!(!analysis.pmf_enabled);
+        assert_eq!(
+            titles(&analysis),
+            ["TKIP Cipher Enabled", "PMF Not Supported"]
+        );
+    }
+}
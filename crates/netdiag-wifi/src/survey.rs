@@ -4,7 +4,7 @@ use crate::error::{WifiError, WifiResult};
 use crate::quality::{QualityMetrics, SignalQuality};
 use chrono::{DateTime, Utc};
 use netdiag_platform::WifiProvider;
-use netdiag_types::wifi::Channel;
+use netdiag_types::wifi::{AccessPoint, Channel, WifiBand};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
@@ -23,7 +23,17 @@ impl SiteSurvey {
 
     /// Record a single survey point.
     pub async fn record_point(&self, interface: &str, location: &str) -> WifiResult<SurveyPoint> {
-        debug!("Recording survey point at: {}", location);
+        self.record_point_at(interface, location, None).await
+    }
+
+    /// Record a single survey point at a position on the floor plan.
+    pub async fn record_point_at(
+        &self,
+        interface: &str,
+        location: &str,
+        position: Option<FloorPosition>,
+    ) -> WifiResult<SurveyPoint> {
+        debug!("Recording survey point at: {} ({:?})", location, position);
 
         let connection = self
             .provider
@@ -50,6 +60,7 @@ impl SiteSurvey {
         Ok(SurveyPoint {
             timestamp: Utc::now(),
             location: location.to_string(),
+            position,
             ssid: connection.access_point.ssid.to_string(),
             bssid: connection.access_point.bssid.to_string(),
             channel: connection.access_point.channel,
@@ -62,6 +73,7 @@ impl SiteSurvey {
                 .iter()
                 .filter(|ap| ap.channel.number == connection.access_point.channel.number)
                 .count(),
+            observations: nearby.iter().map(ApObservation::from).collect(),
         })
     }
 
@@ -229,6 +241,10 @@ pub struct SurveyPoint {
     /// Location description.
     pub location: String,
 
+    /// Position on the floor plan, if the survey is tied to one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub position: Option<FloorPosition>,
+
     /// Connected SSID.
     pub ssid: String,
 
@@ -255,6 +271,119 @@ pub struct SurveyPoint {
 
     /// Number of APs on same channel.
     pub co_channel_aps: usize,
+
+    /// Access points seen by the scan at this point.
+    #[serde(default)]
+    pub observations: Vec<ApObservation>,
+}
+
+impl SurveyPoint {
+    /// Signal-to-noise ratio of the connected AP in dB.
+    pub fn snr(&self) -> Option<f64> {
+        self.quality
+            .snr
+            .or_else(|| self.noise.map(|n| f64::from(self.rssi - n)))
+    }
+
+    /// Strongest observed access point in a band.
+    ///
+    /// Falls back to the connected AP for points recorded without scan
+    /// observations.
+    pub fn strongest_in_band(&self, band: WifiBand) -> Option<ApObservation> {
+        self.observations
+            .iter()
+            .filter(|o| o.channel.band == band)
+            .max_by_key(|o| o.rssi)
+            .cloned()
+            .or_else(|| {
+                (self.observations.is_empty() && self.channel.band == band)
+                    .then(|| self.connected())
+            })
+    }
+
+    /// Observation of a specific BSSID (case-insensitive).
+    pub fn observation(&self, bssid: &str) -> Option<ApObservation> {
+        self.observations
+            .iter()
+            .find(|o| o.bssid.eq_ignore_ascii_case(bssid))
+            .cloned()
+            .or_else(|| {
+                self.bssid
+                    .eq_ignore_ascii_case(bssid)
+                    .then(|| self.connected())
+            })
+    }
+
+    /// Number of other observed APs sharing the channel of `observation`.
+    pub fn co_channel_count(&self, observation: &ApObservation) -> usize {
+        self.observations
+            .iter()
+            .filter(|o| {
+                o.channel.band == observation.channel.band
+                    && o.channel.number == observation.channel.number
+                    && !o.bssid.eq_ignore_ascii_case(&observation.bssid)
+            })
+            .count()
+    }
+
+    /// The connected AP as an observation.
+    fn connected(&self) -> ApObservation {
+        ApObservation {
+            bssid: self.bssid.clone(),
+            ssid: self.ssid.clone(),
+            channel: self.channel,
+            rssi: self.rssi,
+        }
+    }
+}
+
+/// Position of a survey point on a floor plan, in floor plan pixels.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FloorPosition {
+    /// Horizontal offset from the left edge.
+    pub x: f64,
+
+    /// Vertical offset from the top edge.
+    pub y: f64,
+}
+
+impl FloorPosition {
+    /// Create a new floor position.
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    /// Euclidean distance to another position.
+    pub fn distance(&self, other: &FloorPosition) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// An access point seen while recording a survey point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApObservation {
+    /// BSSID (AP MAC).
+    pub bssid: String,
+
+    /// SSID.
+    pub ssid: String,
+
+    /// Channel.
+    pub channel: Channel,
+
+    /// Signal strength (RSSI).
+    pub rssi: i32,
+}
+
+impl From<&AccessPoint> for ApObservation {
+    fn from(ap: &AccessPoint) -> Self {
+        Self {
+            bssid: ap.bssid.to_string(),
+            ssid: ap.ssid.to_string(),
+            channel: ap.channel,
+            rssi: ap.rssi,
+        }
+    }
 }
 
 /// Site survey results.
@@ -315,3 +444,26 @@ impl Default for SurveyResult {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_platform::{ReplayWifiProvider, WifiRecording};
+
+    #[tokio::test]
+    async fn test_survey_recorded_session() {
+        let recording =
+            WifiRecording::parse(include_str!("../testdata/recordings/office.jsonl")).unwrap();
+        let survey = SiteSurvey::new(Arc::new(ReplayWifiProvider::new(recording)));
+
+        let desk = survey.record_point("wlan0", "desk").await.unwrap();
+        assert_eq!((desk.rssi, desk.noise), (-71, Some(-92)));
+        assert_eq!((desk.nearby_ap_count, desk.co_channel_aps), (5, 3));
+
+        // Readings are served in recorded order, then the last one repeats
+        let window = survey.record_point("wlan0", "window").await.unwrap();
+        assert_eq!((window.rssi, window.noise), (-63, Some(-93)));
+        let door = survey.record_point("wlan0", "door").await.unwrap();
+        assert_eq!(door.rssi, -63);
+    }
+}
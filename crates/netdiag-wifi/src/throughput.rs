@@ -0,0 +1,541 @@
+//! Expected throughput from WiFi link parameters.
+//!
+//! Computes the PHY rate of a link from its standard, channel width, spatial
+//! streams, MCS and guard interval, then the TCP goodput to expect once MAC
+//! overhead and other traffic on the channel are taken out. Comparing that
+//! with a speed test tells whether the WiFi link or the WAN is the bottleneck.
+
+use netdiag_platform::WifiProvider;
+use netdiag_types::wifi::{ChannelWidth, GuardInterval, WifiConnection, WifiStandard};
+use serde::{Deserialize, Serialize};
+
+/// Modulation bits per subcarrier and coding rate for MCS 0-13.
+const MCS_TABLE: [(u32, f64); 14] = [
+    (1, 1.0 / 2.0),  // BPSK 1/2
+    (2, 1.0 / 2.0),  // QPSK 1/2
+    (2, 3.0 / 4.0),  // QPSK 3/4
+    (4, 1.0 / 2.0),  // 16-QAM 1/2
+    (4, 3.0 / 4.0),  // 16-QAM 3/4
+    (6, 2.0 / 3.0),  // 64-QAM 2/3
+    (6, 3.0 / 4.0),  // 64-QAM 3/4
+    (6, 5.0 / 6.0),  // 64-QAM 5/6
+    (8, 3.0 / 4.0),  // 256-QAM 3/4
+    (8, 5.0 / 6.0),  // 256-QAM 5/6
+    (10, 3.0 / 4.0), // 1024-QAM 3/4
+    (10, 5.0 / 6.0), // 1024-QAM 5/6
+    (12, 3.0 / 4.0), // 4096-QAM 3/4
+    (12, 5.0 / 6.0), // 4096-QAM 5/6
+];
+
+/// Minimum SNR in dB to sustain each MCS on a 20 MHz channel.
+const MCS_MIN_SNR: [i32; 14] = [5, 8, 11, 14, 17, 21, 23, 25, 29, 31, 34, 37, 40, 42];
+
+/// Noise floor assumed when a link reports its RSSI but no noise level.
+const DEFAULT_NOISE_DBM: i32 = -95;
+
+/// TCP/IP header and ACK overhead on top of MAC efficiency.
+const TCP_EFFICIENCY: f64 = 0.94;
+
+/// Airtime left to us on a saturated channel; contention still gives a share.
+const MIN_AIRTIME: f64 = 0.1;
+
+/// Measured speed at or above this fraction of the expected goodput means
+/// the WiFi link is the limit.
+const WIFI_LIMITED_RATIO: f64 = 0.7;
+
+/// Measured speed at or below this fraction means the WiFi link has
+/// headroom and the limit is upstream.
+const WAN_LIMITED_RATIO: f64 = 0.5;
+
+/// Where the PHY rate of an estimate came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PhyRateSource {
+    /// Computed from the MCS reported by the driver.
+    Mcs,
+    /// The transmit rate reported by the driver.
+    ReportedRate,
+    /// Computed from the highest MCS the SNR supports.
+    Snr,
+}
+
+impl std::fmt::Display for PhyRateSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PhyRateSource::Mcs => "MCS",
+            PhyRateSource::ReportedRate => "reported rate",
+            PhyRateSource::Snr => "SNR estimate",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Parameters of a WiFi link that determine its PHY rate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LinkParameters {
+    /// WiFi standard in use.
+    pub standard: WifiStandard,
+
+    /// Channel width.
+    pub width: ChannelWidth,
+
+    /// Number of spatial streams.
+    pub spatial_streams: u8,
+
+    /// MCS index, if known.
+    pub mcs: Option<u8>,
+
+    /// Guard interval, if known.
+    pub guard_interval: Option<GuardInterval>,
+
+    /// Transmit rate reported by the driver in Mbps.
+    pub tx_rate: Option<f64>,
+
+    /// Signal-to-noise ratio in dB.
+    pub snr: Option<i32>,
+}
+
+impl LinkParameters {
+    /// Link parameters of a connection. Without a reported SNR, it is
+    /// derived from the RSSI and the noise level (or a typical noise floor).
+    pub fn from_connection(connection: &WifiConnection) -> Self {
+        let ap = &connection.access_point;
+        // An RSSI of 0 means the driver didn't report one
+        let snr = ap
+            .snr
+            .or_else(|| (ap.rssi < 0).then(|| ap.rssi - ap.noise.unwrap_or(DEFAULT_NOISE_DBM)));
+        Self {
+            standard: ap.wifi_standard,
+            width: connection.channel_width.unwrap_or(ap.channel.width),
+            spatial_streams: connection.spatial_streams.unwrap_or(1).max(1),
+            mcs: connection.mcs_index,
+            guard_interval: connection.guard_interval,
+            tx_rate: connection.tx_rate.map(f64::from),
+            snr,
+        }
+    }
+
+    /// PHY rate in Mbps for an MCS on this link, if the standard uses MCS
+    /// and supports the width.
+    pub fn phy_rate(&self, mcs: u8) -> Option<f64> {
+        let (bits, coding) = *MCS_TABLE.get(usize::from(mcs.min(max_mcs(self.standard)?)))?;
+        let subcarriers = data_subcarriers(self.standard, self.width)?;
+        let (symbol, gi) = symbol_timing(self.standard, self.guard_interval);
+        Some(
+            f64::from(self.spatial_streams) * f64::from(subcarriers) * f64::from(bits) * coding
+                / (symbol + gi),
+        )
+    }
+
+    /// Highest MCS the link's SNR supports, if the SNR is known.
+    pub fn mcs_for_snr(&self) -> Option<u8> {
+        let snr = self.snr?;
+        let max = max_mcs(self.standard)?;
+        // Each doubling of the width adds 3 dB of noise
+        let penalty = 3 * i32::try_from((self.width.mhz() / 20).ilog2()).unwrap_or(0);
+        let supported = MCS_MIN_SNR
+            .iter()
+            .take_while(|&&min| snr >= min + penalty)
+            .count();
+        Some(
+            u8::try_from(supported.saturating_sub(1))
+                .unwrap_or(0)
+                .min(max),
+        )
+    }
+}
+
+/// Expected throughput of a WiFi link.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ThroughputEstimate {
+    /// Link parameters used.
+    pub link: LinkParameters,
+
+    /// MCS the PHY rate was computed for.
+    pub mcs: Option<u8>,
+
+    /// PHY rate in Mbps.
+    pub phy_rate_mbps: f64,
+
+    /// Where the PHY rate came from.
+    pub source: PhyRateSource,
+
+    /// Fraction of the PHY rate left after MAC overhead.
+    pub mac_efficiency: f64,
+
+    /// Channel utilization by other traffic (0-100), if measured.
+    pub channel_utilization: Option<f32>,
+
+    /// Expected TCP goodput in Mbps.
+    pub expected_goodput_mbps: f64,
+}
+
+impl ThroughputEstimate {
+    /// Estimate throughput for a link, given the channel's measured
+    /// utilization. Returns `None` if neither the MCS, the rate nor the SNR
+    /// is known.
+    pub fn new(link: LinkParameters, channel_utilization: Option<f32>) -> Option<Self> {
+        let from_mcs = link.mcs.and_then(|mcs| Some((mcs, link.phy_rate(mcs)?)));
+        let (mcs, phy_rate_mbps, source) = match (from_mcs, link.tx_rate) {
+            (Some((mcs, rate)), _) => (Some(mcs), rate, PhyRateSource::Mcs),
+            (None, Some(rate)) => (link.mcs, rate, PhyRateSource::ReportedRate),
+            (None, None) => {
+                let mcs = link.mcs_for_snr()?;
+                (Some(mcs), link.phy_rate(mcs)?, PhyRateSource::Snr)
+            }
+        };
+
+        let mac_efficiency = mac_efficiency(link.standard);
+        let airtime = channel_utilization
+            .map_or(1.0, |u| 1.0 - f64::from(u.clamp(0.0, 100.0)) / 100.0)
+            .max(MIN_AIRTIME);
+
+        Some(Self {
+            link,
+            mcs,
+            phy_rate_mbps,
+            source,
+            mac_efficiency,
+            channel_utilization,
+            expected_goodput_mbps: phy_rate_mbps * mac_efficiency * TCP_EFFICIENCY * airtime,
+        })
+    }
+
+    /// Estimate throughput of a connection.
+    pub fn from_connection(
+        connection: &WifiConnection,
+        channel_utilization: Option<f32>,
+    ) -> Option<Self> {
+        Self::new(
+            LinkParameters::from_connection(connection),
+            channel_utilization,
+        )
+    }
+
+    /// Estimate throughput of a connection, taking the utilization of its
+    /// channel as measured by the provider. The estimate ignores airtime if
+    /// the provider can't measure it.
+    pub async fn measure(provider: &dyn WifiProvider, connection: &WifiConnection) -> Option<Self> {
+        let utilization = provider
+            .get_channel_utilization(connection.access_point.channel)
+            .await
+            .ok()
+            .map(|u| u.utilization_percent);
+        Self::from_connection(connection, utilization)
+    }
+
+    /// Compare a measured speed-test result with the estimate.
+    pub fn compare(&self, measured_mbps: f64) -> BottleneckAssessment {
+        let expected = self.expected_goodput_mbps;
+        let ratio = if expected > 0.0 {
+            measured_mbps / expected
+        } else {
+            0.0
+        };
+
+        let (bottleneck, summary) = if expected <= 0.0 {
+            (
+                Bottleneck::Undetermined,
+                format!(
+                    "{:.0} Mbps measured, but the WiFi link reports no usable rate to compare with",
+                    measured_mbps
+                ),
+            )
+        } else if ratio >= WIFI_LIMITED_RATIO {
+            (
+                Bottleneck::Wifi,
+                format!(
+                    "{:.0} Mbps is {:.0}% of what the WiFi link can carry (~{:.0} Mbps); \
+                     improve the WiFi link before upgrading the internet plan",
+                    measured_mbps,
+                    ratio * 100.0,
+                    expected
+                ),
+            )
+        } else if ratio <= WAN_LIMITED_RATIO {
+            (
+                Bottleneck::Wan,
+                format!(
+                    "WiFi could carry ~{:.0} Mbps but only {:.0} Mbps was measured; \
+                     the limit is upstream (router, WAN or ISP)",
+                    expected, measured_mbps
+                ),
+            )
+        } else {
+            (
+                Bottleneck::Undetermined,
+                format!(
+                    "{:.0} Mbps measured against ~{:.0} Mbps expected over WiFi; \
+                     neither side is clearly the limit",
+                    measured_mbps, expected
+                ),
+            )
+        };
+
+        BottleneckAssessment {
+            bottleneck,
+            measured_mbps,
+            expected_mbps: expected,
+            ratio,
+            summary,
+        }
+    }
+}
+
+/// Which part of the path limits throughput.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bottleneck {
+    /// The WiFi link.
+    Wifi,
+    /// The WAN or ISP.
+    Wan,
+    /// Could not be determined.
+    Undetermined,
+}
+
+impl std::fmt::Display for Bottleneck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Bottleneck::Wifi => "WiFi",
+            Bottleneck::Wan => "WAN",
+            Bottleneck::Undetermined => "undetermined",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Result of comparing a speed test with the expected WiFi throughput.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BottleneckAssessment {
+    /// Limiting part of the path.
+    pub bottleneck: Bottleneck,
+
+    /// Measured throughput in Mbps.
+    pub measured_mbps: f64,
+
+    /// Expected WiFi goodput in Mbps.
+    pub expected_mbps: f64,
+
+    /// Measured divided by expected.
+    pub ratio: f64,
+
+    /// Human-readable explanation.
+    pub summary: String,
+}
+
+/// Highest MCS of a standard, `None` for standards without MCS.
+fn max_mcs(standard: WifiStandard) -> Option<u8> {
+    match standard {
+        WifiStandard::Dot11n => Some(7),
+        WifiStandard::Dot11ac => Some(9),
+        WifiStandard::Dot11ax => Some(11),
+        WifiStandard::Dot11be => Some(13),
+        _ => None,
+    }
+}
+
+/// Data subcarriers of a standard at a width.
+fn data_subcarriers(standard: WifiStandard, width: ChannelWidth) -> Option<u32> {
+    match (standard, width) {
+        (WifiStandard::Dot11n | WifiStandard::Dot11ac, ChannelWidth::Mhz20) => Some(52),
+        (WifiStandard::Dot11n | WifiStandard::Dot11ac, ChannelWidth::Mhz40) => Some(108),
+        (WifiStandard::Dot11ac, ChannelWidth::Mhz80) => Some(234),
+        (WifiStandard::Dot11ac, ChannelWidth::Mhz160) => Some(468),
+        (WifiStandard::Dot11ax | WifiStandard::Dot11be, ChannelWidth::Mhz20) => Some(234),
+        (WifiStandard::Dot11ax | WifiStandard::Dot11be, ChannelWidth::Mhz40) => Some(468),
+        (WifiStandard::Dot11ax | WifiStandard::Dot11be, ChannelWidth::Mhz80) => Some(980),
+        (WifiStandard::Dot11ax | WifiStandard::Dot11be, ChannelWidth::Mhz160) => Some(1960),
+        (WifiStandard::Dot11be, ChannelWidth::Mhz320) => Some(3920),
+        _ => None,
+    }
+}
+
+/// OFDM symbol duration and guard interval in microseconds.
+fn symbol_timing(standard: WifiStandard, gi: Option<GuardInterval>) -> (f64, f64) {
+    let gi_us = |gi: GuardInterval| f64::from(gi.nanos()) / 1000.0;
+    match standard {
+        // HE/EHT symbols are four times longer and don't use the 0.4 µs GI
+        WifiStandard::Dot11ax | WifiStandard::Dot11be => (
+            12.8,
+            gi_us(match gi {
+                Some(GuardInterval::Ns400) | None => GuardInterval::Ns800,
+                Some(gi) => gi,
+            }),
+        ),
+        _ => (
+            3.2,
+            gi_us(match gi {
+                Some(GuardInterval::Ns400) => GuardInterval::Ns400,
+                _ => GuardInterval::Ns800,
+            }),
+        ),
+    }
+}
+
+/// Fraction of the PHY rate left after preambles, contention and block ACKs.
+fn mac_efficiency(standard: WifiStandard) -> f64 {
+    match standard {
+        // No frame aggregation
+        WifiStandard::Dot11a | WifiStandard::Dot11b | WifiStandard::Dot11g => 0.5,
+        WifiStandard::Dot11n => 0.65,
+        WifiStandard::Dot11ac => 0.7,
+        WifiStandard::Dot11ax | WifiStandard::Dot11be => 0.75,
+        WifiStandard::Unknown => 0.6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(standard: WifiStandard, width: ChannelWidth, streams: u8) -> LinkParameters {
+        LinkParameters {
+            standard,
+            width,
+            spatial_streams: streams,
+            mcs: None,
+            guard_interval: None,
+            tx_rate: None,
+            snr: None,
+        }
+    }
+
+    #[test]
+    fn test_phy_rates() {
+        let mut vht = link(WifiStandard::Dot11ac, ChannelWidth::Mhz80, 2);
+        vht.guard_interval = Some(GuardInterval::Ns400);
+        assert!((vht.phy_rate(9).unwrap() - 866.7).abs() < 0.1);
+
+        let he = link(WifiStandard::Dot11ax, ChannelWidth::Mhz80, 2);
+        assert!((he.phy_rate(11).unwrap() - 1201.0).abs() < 0.1);
+
+        let ht = link(WifiStandard::Dot11n, ChannelWidth::Mhz20, 1);
+        assert!((ht.phy_rate(7).unwrap() - 65.0).abs() < 0.1);
+        // 802.11n has no 80 MHz channels
+        assert!(link(WifiStandard::Dot11n, ChannelWidth::Mhz80, 1)
+            .phy_rate(7)
+            .is_none());
+        assert!(link(WifiStandard::Dot11g, ChannelWidth::Mhz20, 1)
+            .phy_rate(0)
+            .is_none());
+    }
+
+    #[test]
+    fn test_estimate_and_bottleneck() {
+        let mut params = link(WifiStandard::Dot11ax, ChannelWidth::Mhz80, 2);
+        params.mcs = Some(11);
+        let idle = ThroughputEstimate::new(params.clone(), None).unwrap();
+        assert_eq!(idle.source, PhyRateSource::Mcs);
+        assert!(idle.expected_goodput_mbps > 800.0 && idle.expected_goodput_mbps < 900.0);
+
+        let busy = ThroughputEstimate::new(params, Some(50.0)).unwrap();
+        assert!((busy.expected_goodput_mbps - idle.expected_goodput_mbps / 2.0).abs() < 0.1);
+
+        assert_eq!(busy.compare(400.0).bottleneck, Bottleneck::Wifi);
+        assert_eq!(busy.compare(100.0).bottleneck, Bottleneck::Wan);
+        assert_eq!(busy.compare(260.0).bottleneck, Bottleneck::Undetermined);
+
+        // A link reporting no rate says nothing about the bottleneck
+        let mut stalled = link(WifiStandard::Dot11ac, ChannelWidth::Mhz80, 1);
+        stalled.tx_rate = Some(0.0);
+        let stalled = ThroughputEstimate::new(stalled, None).unwrap();
+        assert_eq!(stalled.expected_goodput_mbps, 0.0);
+        let assessment = stalled.compare(50.0);
+        assert_eq!(assessment.bottleneck, Bottleneck::Undetermined);
+        assert_eq!(assessment.ratio, 0.0);
+
+        // Without MCS or rate, fall back to what the SNR supports
+        let mut weak = link(WifiStandard::Dot11ac, ChannelWidth::Mhz40, 1);
+        weak.snr = Some(20);
+        assert_eq!(weak.mcs_for_snr(), Some(4));
+        let estimate = ThroughputEstimate::new(weak, None).unwrap();
+        assert_eq!(estimate.source, PhyRateSource::Snr);
+
+        assert!(
+            ThroughputEstimate::new(link(WifiStandard::Dot11g, ChannelWidth::Mhz20, 1), None)
+                .is_none()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_measured_utilization_moves_bottleneck() {
+        use chrono::Utc;
+        use netdiag_platform::{
+            RecordedCall, ReplayWifiProvider, WifiCall, WifiRecording, WifiRecordingHeader,
+        };
+        use netdiag_types::wifi::{
+            AccessPoint, ChannelUtilization, InterferenceLevel, WifiAuthState, WifiConnectionState,
+        };
+
+        let connection = WifiConnection {
+            access_point: AccessPoint {
+                wifi_standard: WifiStandard::Dot11ax,
+                ..AccessPoint::test_default()
+            },
+            state: WifiConnectionState::Connected,
+            auth_state: WifiAuthState::Psk,
+            tx_rate: None,
+            rx_rate: None,
+            spatial_streams: Some(2),
+            mcs_index: Some(11),
+            guard_interval: None,
+            channel_width: Some(ChannelWidth::Mhz80),
+            connected_duration: None,
+            last_roam: None,
+            tx_packets: None,
+            tx_retries: None,
+            tx_failed: None,
+        };
+        let replay = |calls: Vec<RecordedCall>| {
+            ReplayWifiProvider::new(WifiRecording {
+                header: WifiRecordingHeader {
+                    version: "test".to_string(),
+                    platform: "linux".to_string(),
+                    started: Utc::now(),
+                    available: true,
+                    supports_enterprise: false,
+                },
+                calls,
+            })
+        };
+        let busy = |percent: f32| {
+            let channel = connection.access_point.channel;
+            replay(vec![RecordedCall {
+                at_ms: 0,
+                duration_ms: 0,
+                call: WifiCall::GetChannelUtilization {
+                    channel,
+                    result: Ok(ChannelUtilization {
+                        channel,
+                        network_count: 4,
+                        utilization_percent: percent,
+                        avg_rssi: -65,
+                        recommended: false,
+                        interference_level: InterferenceLevel::Low,
+                    }),
+                },
+            }])
+        };
+
+        // 400 Mbps over an idle 2x2 80 MHz 802.11ax link points at the WAN,
+        // but on a channel that is 60% busy the link itself is the limit
+        let idle = ThroughputEstimate::measure(&busy(0.0), &connection)
+            .await
+            .unwrap();
+        assert_eq!(idle.channel_utilization, Some(0.0));
+        assert_eq!(idle.compare(400.0).bottleneck, Bottleneck::Wan);
+
+        let loaded = ThroughputEstimate::measure(&busy(60.0), &connection)
+            .await
+            .unwrap();
+        assert_eq!(loaded.channel_utilization, Some(60.0));
+        assert_eq!(loaded.compare(400.0).bottleneck, Bottleneck::Wifi);
+
+        // A failed measurement leaves airtime out of the estimate
+        let unmeasured = ThroughputEstimate::measure(&replay(Vec::new()), &connection)
+            .await
+            .unwrap();
+        assert_eq!(unmeasured.channel_utilization, None);
+    }
+}
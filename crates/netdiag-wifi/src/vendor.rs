@@ -0,0 +1,177 @@
+//! Access point vendor identification.
+
+use netdiag_types::network::OuiDatabase;
+use netdiag_types::wifi::AccessPoint;
+use serde::{Deserialize, Serialize};
+
+/// Vendors whose access points are typically centrally managed.
+const ENTERPRISE_VENDORS: &[&str] = &[
+    "aerohive",
+    "aruba",
+    "cambium",
+    "cisco",
+    "extreme networks",
+    "fortinet",
+    "juniper",
+    "meraki",
+    "mist systems",
+    "ruckus",
+    "ubiquiti",
+];
+
+/// Vendors of home routers and mesh systems.
+const CONSUMER_VENDORS: &[&str] = &[
+    "amazon", "asustek", "belkin", "d-link", "eero", "google", "huawei", "linksys", "mikrotik",
+    "netgear", "tp-link",
+];
+
+/// Vendors of phones, whose access points are tethering hotspots.
+const MOBILE_VENDORS: &[&str] = &["apple", "motorola", "oneplus", "samsung", "xiaomi"];
+
+/// SSID fragments used by phone hotspots.
+const HOTSPOT_SSIDS: &[&str] = &["iphone", "androidap", "galaxy", "hotspot", "pixel"];
+
+/// What kind of device an access point is likely to be.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApDeviceClass {
+    /// Managed enterprise access point.
+    Enterprise,
+    /// Home router or mesh node.
+    Consumer,
+    /// Phone or tablet tethering hotspot.
+    MobileHotspot,
+    /// Could not be determined.
+    Unknown,
+}
+
+impl std::fmt::Display for ApDeviceClass {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            ApDeviceClass::Enterprise => "enterprise AP",
+            ApDeviceClass::Consumer => "home router",
+            ApDeviceClass::MobileHotspot => "mobile hotspot",
+            ApDeviceClass::Unknown => "unknown device",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+/// Vendor and device class of an access point.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApVendor {
+    /// Organization owning the BSSID's OUI.
+    pub name: Option<String>,
+
+    /// Likely device class.
+    pub class: ApDeviceClass,
+
+    /// Whether the BSSID is locally administered.
+    pub randomized: bool,
+}
+
+impl ApVendor {
+    /// Identify an access point using the global OUI database.
+    pub fn identify(ap: &AccessPoint) -> Self {
+        Self::identify_with(OuiDatabase::global(), ap)
+    }
+
+    /// Identify an access point using a specific OUI database.
+    pub fn identify_with(db: &OuiDatabase, ap: &AccessPoint) -> Self {
+        let octets = ap.bssid.octets();
+        let randomized = ap.bssid.is_locally_administered();
+
+        // Multi-SSID APs derive virtual BSSIDs from the radio MAC by setting
+        // the locally administered bit, so retry with it cleared.
+        let name = db.vendor(octets).or_else(|| {
+            randomized
+                .then(|| {
+                    let mut radio = octets;
+                    radio[0] &= !0x02;
+                    db.vendor(radio)
+                })
+                .flatten()
+        });
+
+        let class = match name {
+            Some(name) => classify(name),
+            None if randomized => ApDeviceClass::MobileHotspot,
+            None => ApDeviceClass::Unknown,
+        };
+        let class = if class == ApDeviceClass::Unknown && looks_like_hotspot(ap.ssid.as_str()) {
+            ApDeviceClass::MobileHotspot
+        } else {
+            class
+        };
+
+        Self {
+            name: name.map(str::to_string),
+            class,
+            randomized,
+        }
+    }
+}
+
+fn classify(vendor: &str) -> ApDeviceClass {
+    let vendor = vendor.to_ascii_lowercase();
+    let matches = |list: &[&str]| list.iter().any(|v| vendor.contains(v));
+    if matches(ENTERPRISE_VENDORS) {
+        ApDeviceClass::Enterprise
+    } else if matches(MOBILE_VENDORS) {
+        ApDeviceClass::MobileHotspot
+    } else if matches(CONSUMER_VENDORS) {
+        ApDeviceClass::Consumer
+    } else {
+        ApDeviceClass::Unknown
+    }
+}
+
+fn looks_like_hotspot(ssid: &str) -> bool {
+    let ssid = ssid.to_ascii_lowercase();
+    HOTSPOT_SSIDS.iter().any(|s| ssid.contains(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netdiag_types::wifi::{Bssid, Channel, Ssid, WifiBand, WifiStandard};
+
+    fn ap(ssid: &str, bssid: [u8; 6]) -> AccessPoint {
+        AccessPoint {
+            ssid: Ssid::new(ssid),
+            bssid: Bssid::new(bssid),
+            channel: Channel::from_number(6, WifiBand::Band2_4GHz),
+            wifi_standard: WifiStandard::Dot11n,
+            ..AccessPoint::test_default()
+        }
+    }
+
+    #[test]
+    fn test_identify() {
+        let db = OuiDatabase::from_csv(
+            "MA-L,000B86,\"Aruba, a Hewlett Packard Enterprise Company\",\nMA-L,001451,\"Apple, Inc.\",\n",
+        )
+        .unwrap();
+
+        let aruba = ApVendor::identify_with(&db, &ap("corp", [0x00, 0x0B, 0x86, 1, 2, 3]));
+        assert_eq!(aruba.class, ApDeviceClass::Enterprise);
+        assert!(!aruba.randomized);
+
+        // Virtual BSSID of the same radio
+        let virtual_bssid = ApVendor::identify_with(&db, &ap("guest", [0x02, 0x0B, 0x86, 1, 2, 3]));
+        assert_eq!(virtual_bssid.class, ApDeviceClass::Enterprise);
+        assert!(virtual_bssid.randomized);
+
+        let phone = ApVendor::identify_with(&db, &ap("Sam's phone", [0x00, 0x14, 0x51, 1, 2, 3]));
+        assert_eq!(phone.class, ApDeviceClass::MobileHotspot);
+
+        let random = ApVendor::identify_with(&db, &ap("cafe", [0xDA, 0xA1, 0x19, 0, 0, 1]));
+        assert_eq!(random.name, None);
+        assert_eq!(random.class, ApDeviceClass::MobileHotspot);
+
+        let unknown = ApVendor::identify_with(&db, &ap("iPhone", [0x00, 0x99, 0x99, 0, 0, 1]));
+        assert_eq!(unknown.class, ApDeviceClass::MobileHotspot);
+        let unknown = ApVendor::identify_with(&db, &ap("home", [0x00, 0x99, 0x99, 0, 0, 1]));
+        assert_eq!(unknown.class, ApDeviceClass::Unknown);
+    }
+}
@@ -2,6 +2,7 @@
 
 use anyhow::{Context, Result};
 use clap::{Parser, Subcommand};
+use netdiag_types::network::OuiDatabase;
 use xshell::{cmd, Shell};
 
 #[derive(Parser)]
@@ -35,6 +36,12 @@ enum Commands {
         #[arg(short, long, default_value = "completions")]
         output: String,
     },
+    /// Regenerate the bundled OUI registry from the IEEE MA-L, MA-M and MA-S CSVs
+    UpdateOui {
+        /// Directory with already downloaded registry CSVs (downloads them if unset)
+        #[arg(long)]
+        from: Option<String>,
+    },
 }
 
 fn main() -> Result<()> {
@@ -67,6 +74,9 @@ fn main() -> Result<()> {
         Commands::Completions { output } => {
             generate_completions(&sh, &output)?;
         }
+        Commands::UpdateOui { from } => {
+            update_oui(&sh, from.as_deref())?;
+        }
     }
 
     Ok(())
@@ -178,3 +188,46 @@ fn generate_completions(sh: &Shell, output: &str) -> Result<()> {
     println!("Completions generated in {}/", output);
     Ok(())
 }
+
+/// IEEE registries bundled with netdiag-types; the legacy IAB registry is
+/// part of MA-S.
+const OUI_REGISTRIES: [&str; 4] = ["oui.csv", "mam.csv", "oui36.csv", "iab.csv"];
+
+/// Bundled registry, relative to the workspace root.
+const OUI_OUTPUT: &str = "crates/netdiag-types/data/oui.csv";
+
+fn update_oui(sh: &Shell, from: Option<&str>) -> Result<()> {
+    let mut db = OuiDatabase::new();
+    for file in OUI_REGISTRIES {
+        let content = match from {
+            Some(dir) => std::fs::read_to_string(std::path::Path::new(dir).join(file))
+                .with_context(|| format!("Failed to read {}/{}", dir, file))?,
+            None => {
+                let url = format!("https://standards-oui.ieee.org/{}", oui_path(file));
+                println!("Downloading {}...", url);
+                cmd!(sh, "curl -fsSL {url}")
+                    .read()
+                    .with_context(|| format!("Failed to download {}", url))?
+            }
+        };
+        let before = db.len();
+        db.merge_csv(&content)
+            .with_context(|| format!("Failed to parse {}", file))?;
+        println!("  {}: {} assignments", file, db.len() - before);
+    }
+
+    std::fs::write(OUI_OUTPUT, db.to_csv())
+        .with_context(|| format!("Failed to write {}", OUI_OUTPUT))?;
+    println!("Wrote {} assignments to {}", db.len(), OUI_OUTPUT);
+    Ok(())
+}
+
+/// Path of a registry CSV on the IEEE server.
+fn oui_path(file: &str) -> String {
+    match file {
+        "oui.csv" => "oui/oui.csv".to_string(),
+        "mam.csv" => "oui28/mam.csv".to_string(),
+        "oui36.csv" => "oui36/oui36.csv".to_string(),
+        _ => format!("iab/{}", file),
+    }
+}